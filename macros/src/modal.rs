@@ -1,27 +1,45 @@
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use proc_macro2::{Delimiter, Group, TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens as _, quote};
 use syn::{
     Expr, Ident, Result, Token,
-    parse::{Parse, ParseStream},
+    parse::{Parse, ParseStream, Parser as _},
     parse_macro_input,
+    punctuated::Punctuated,
 };
 
 pub fn define_modal(input: TokenStream) -> TokenStream {
-    let modal_def = parse_macro_input!(input as ModalDefinition);
+    let definitions = parse_macro_input!(input as ModalDefinitions);
 
-    match generate_modal_code(modal_def) {
+    match generate_modals_code(definitions) {
         Ok(tokens) => tokens.into(),
         Err(err) => err.to_compile_error().into(),
     }
 }
 
 // AST structs for input parsing
+
+/// Any number of component groups and modals, where modals can include the groups defined in the
+/// same invocation
+struct ModalDefinitions {
+    groups: Vec<ComponentGroup>,
+    modals: Vec<ModalDefinition>,
+}
+
+/// Reusable list of components, e.g. `group role_id_input(description) [ input role_id { ... } ]`.
+/// Parameters are referenced as `$description` in the body, and are replaced by the arguments
+/// passed to `include role_id_input(...)`.
+struct ComponentGroup {
+    name: Ident,
+    params: Vec<Ident>,
+    body: TokenStream2,
+}
+
 struct ModalDefinition {
     name: Ident,
     custom_id: Expr,
     title: Expr,
-    components: Vec<ModalComponent>,
+    components: Vec<ComponentEntry>,
 }
 
 #[allow(clippy::large_enum_variant)] // only at compile-time
@@ -31,6 +49,18 @@ enum ModalComponent {
     Text(TextComponent),
 }
 
+/// Entry of a `components` list, before includes are resolved
+#[allow(clippy::large_enum_variant)] // only at compile-time
+enum ComponentEntry {
+    Component(ModalComponent),
+    Include(IncludeComponent),
+}
+
+struct IncludeComponent {
+    group: Ident,
+    args: Vec<Expr>,
+}
+
 struct InputComponent {
     field_name: Ident,
     style: Expr, // InputTextStyle expression
@@ -88,6 +118,46 @@ fn extract_select_kind_variant(expr: &Expr) -> SelectKindVariant {
     }
 }
 
+impl Parse for ModalDefinitions {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut groups = Vec::new();
+        let mut modals = Vec::new();
+
+        while !input.is_empty() {
+            if input.peek(Ident) && input.fork().parse::<Ident>()? == "group" {
+                groups.push(input.parse::<ComponentGroup>()?);
+            } else {
+                modals.push(input.parse::<ModalDefinition>()?);
+            }
+        }
+
+        Ok(ModalDefinitions { groups, modals })
+    }
+}
+
+impl Parse for ComponentGroup {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Ident>()?; // `group`
+        let name: Ident = input.parse()?;
+
+        let params = if input.peek(syn::token::Paren) {
+            let params_content;
+            syn::parenthesized!(params_content in input);
+            Punctuated::<Ident, Token![,]>::parse_terminated(&params_content)?
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let body_content;
+        syn::bracketed!(body_content in input);
+        let body: TokenStream2 = body_content.parse()?;
+
+        Ok(ComponentGroup { name, params, body })
+    }
+}
+
 impl Parse for ModalDefinition {
     fn parse(input: ParseStream) -> Result<Self> {
         let name: Ident = input.parse()?;
@@ -133,16 +203,7 @@ impl Parse for ModalDefinition {
                     let components_content;
                     syn::bracketed!(components_content in content);
 
-                    let mut component_list = Vec::new();
-                    while !components_content.is_empty() {
-                        let component = components_content.parse::<ModalComponent>()?;
-                        component_list.push(component);
-
-                        if components_content.peek(Token![,]) {
-                            components_content.parse::<Token![,]>()?;
-                        }
-                    }
-                    components = Some(component_list);
+                    components = Some(parse_component_list(&components_content)?);
                 }
                 _ => {
                     return Err(syn::Error::new(
@@ -174,30 +235,162 @@ impl Parse for ModalDefinition {
     }
 }
 
-impl Parse for ModalComponent {
+impl Parse for ComponentEntry {
     fn parse(input: ParseStream) -> Result<Self> {
         let ident: Ident = input.parse()?;
         match ident.to_string().as_str() {
             "input" => {
                 let input_comp = parse_input_component(input)?;
-                Ok(ModalComponent::Input(input_comp))
+                Ok(ComponentEntry::Component(ModalComponent::Input(input_comp)))
             }
             "select" => {
                 let select_comp = parse_select_component(input)?;
-                Ok(ModalComponent::Select(select_comp))
+                Ok(ComponentEntry::Component(ModalComponent::Select(
+                    select_comp,
+                )))
             }
             "text" => {
                 let text_comp = parse_text_component(input)?;
-                Ok(ModalComponent::Text(text_comp))
+                Ok(ComponentEntry::Component(ModalComponent::Text(text_comp)))
+            }
+            "include" => {
+                let include_comp = parse_include_component(input)?;
+                Ok(ComponentEntry::Include(include_comp))
             }
             _ => Err(syn::Error::new(
                 ident.span(),
-                "Unknown component. Expected 'input', 'select' or 'text'",
+                "Unknown component. Expected 'input', 'select', 'text' or 'include'",
             )),
         }
     }
 }
 
+fn parse_component_list(input: ParseStream) -> Result<Vec<ComponentEntry>> {
+    let mut component_list = Vec::new();
+    while !input.is_empty() {
+        let component = input.parse::<ComponentEntry>()?;
+        component_list.push(component);
+
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(component_list)
+}
+
+fn parse_include_component(input: ParseStream) -> Result<IncludeComponent> {
+    let group: Ident = input.parse()?;
+
+    let args = if input.peek(syn::token::Paren) {
+        let args_content;
+        syn::parenthesized!(args_content in input);
+        Punctuated::<Expr, Token![,]>::parse_terminated(&args_content)?
+            .into_iter()
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(IncludeComponent { group, args })
+}
+
+/// Replaces `include` components with the referenced group's components
+fn resolve_includes(
+    entries: Vec<ComponentEntry>,
+    groups: &[ComponentGroup],
+) -> Result<Vec<ModalComponent>> {
+    let mut resolved = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let include = match entry {
+            ComponentEntry::Component(component) => {
+                resolved.push(component);
+                continue;
+            }
+            ComponentEntry::Include(include) => include,
+        };
+
+        let group = groups
+            .iter()
+            .find(|g| g.name == include.group)
+            .ok_or_else(|| {
+                syn::Error::new(
+                    include.group.span(),
+                    "Unknown component group. Groups must be defined in the same invocation",
+                )
+            })?;
+
+        if group.params.len() != include.args.len() {
+            return Err(syn::Error::new(
+                include.group.span(),
+                format!(
+                    "Component group '{}' expects {} argument(s), but {} were given",
+                    group.name,
+                    group.params.len(),
+                    include.args.len()
+                ),
+            ));
+        }
+
+        let body = substitute_params(group.body.clone(), &group.params, &include.args)?;
+        for group_entry in parse_component_list.parse2(body)? {
+            match group_entry {
+                ComponentEntry::Component(component) => resolved.push(component),
+                ComponentEntry::Include(_) => {
+                    return Err(syn::Error::new(
+                        group.name.span(),
+                        "Component groups can't include other groups",
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+fn substitute_params(
+    tokens: TokenStream2,
+    params: &[Ident],
+    args: &[Expr],
+) -> Result<TokenStream2> {
+    let mut substituted = TokenStream2::new();
+    let mut tokens = tokens.into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                let Some(TokenTree::Ident(ident)) = tokens.next() else {
+                    return Err(syn::Error::new(
+                        punct.span(),
+                        "Expected a parameter name after '$'",
+                    ));
+                };
+                let index = params.iter().position(|p| *p == ident).ok_or_else(|| {
+                    syn::Error::new(ident.span(), "Unknown component group parameter")
+                })?;
+
+                // parenthesize the argument to preserve precedence inside larger expressions
+                substituted.extend([TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    args[index].to_token_stream(),
+                ))]);
+            }
+            TokenTree::Group(group) => {
+                let mut inner = Group::new(
+                    group.delimiter(),
+                    substitute_params(group.stream(), params, args)?,
+                );
+                inner.set_span(group.span());
+                substituted.extend([TokenTree::Group(inner)]);
+            }
+            other => substituted.extend([other]),
+        }
+    }
+
+    Ok(substituted)
+}
+
 fn parse_input_component(input: ParseStream) -> Result<InputComponent> {
     let field_name: Ident = input.parse()?;
 
@@ -345,18 +538,35 @@ fn parse_text_component(input: ParseStream) -> Result<TextComponent> {
     })
 }
 
-fn generate_modal_code(modal_def: ModalDefinition) -> Result<TokenStream2> {
-    let modal_name = &modal_def.name;
-    let modal_custom_id = &modal_def.custom_id;
-    let modal_title = &modal_def.title;
+fn generate_modals_code(definitions: ModalDefinitions) -> Result<TokenStream2> {
+    let mut tokens = TokenStream2::new();
+
+    for modal_def in definitions.modals {
+        let components = resolve_includes(modal_def.components, &definitions.groups)?;
+        tokens.extend(generate_modal_code(
+            &modal_def.name,
+            &modal_def.custom_id,
+            &modal_def.title,
+            &components,
+        )?);
+    }
 
+    Ok(tokens)
+}
+
+fn generate_modal_code(
+    modal_name: &Ident,
+    modal_custom_id: &Expr,
+    modal_title: &Expr,
+    components: &[ModalComponent],
+) -> Result<TokenStream2> {
     let validated_name = Ident::new(&format!("{}Validated", modal_name), modal_name.span());
 
-    let prefill_parameters = generate_prefill_parameters(&modal_def.components);
-    let validated_fields = generate_validated_fields(&modal_def.components);
-    let create_components = generate_create_components(&modal_def.components, false);
-    let create_components_prefill = generate_create_components(&modal_def.components, true);
-    let validation_logic = generate_validation_logic(&modal_def.components, &validated_name);
+    let prefill_parameters = generate_prefill_parameters(components);
+    let validated_fields = generate_validated_fields(components);
+    let create_components = generate_create_components(components, false);
+    let create_components_prefill = generate_create_components(components, true);
+    let validation_logic = generate_validation_logic(components, &validated_name);
 
     // TODO: reduce excessive full paths in macro
     // - don't pollute caller's scope
@@ -618,47 +828,36 @@ fn generate_validation_logic(
                 let field_name_str = field_name.to_string();
                 let kind_variant = extract_select_kind_variant(&select.kind);
 
-                // TODO: values no longer parsed by serenity, always FixedArray<String> -> individual parsing required
+                // serenity doesn't parse select values anymore, so IDs are parsed individually
+                // (see `crate::shared::interaction::select` in the bot crate)
+                let parse_ids = |ty: TokenStream2, kind: &str| {
+                    quote! {
+                        ::poise::serenity_prelude::small_fixed_array::FixedArray::from_vec_trunc(
+                            crate::shared::interaction::select::parse_select_ids::<#ty>(
+                                #field_name_str,
+                                #kind,
+                                &select_values,
+                            )?,
+                        )
+                    }
+                };
                 let extraction_logic = match kind_variant {
                     SelectKindVariant::String => quote! {
                         select_values
                     },
-                    SelectKindVariant::User => quote! {
-                        select_values
-                            .iter()
-                            .map(|id| id.parse().map(::poise::serenity_prelude::UserId::new))
-                            .collect::<Result<Vec<_>, _>>()
-                            .map_err(
-                                |_| ::anyhow::anyhow!("Expected User select menu values for field: {}", #field_name_str),
-                            )
-                    },
-                    SelectKindVariant::Role => quote! {
-                        select_values
-                            .iter()
-                            .map(|id| id.parse().map(::poise::serenity_prelude::RoleId::new))
-                            .collect::<Result<Vec<_>, _>>()
-                            .map_err(
-                                |_| ::anyhow::anyhow!("Expected Role select menu values for field: {}", #field_name_str),
-                            )
-                    },
-                    SelectKindVariant::Mentionable => quote! {
-                        select_values
-                            .iter()
-                            .map(|id| id.parse().map(::poise::serenity_prelude::GenericId::new))
-                            .collect::<Result<Vec<_>, _>>()
-                            .map_err(
-                                |_| ::anyhow::anyhow!("Expected Mentionable select menu values for field: {}", #field_name_str),
-                            )
-                    },
-                    SelectKindVariant::Channel => quote! {
-                        select_values
-                            .iter()
-                            .map(|id| id.parse().map(::poise::serenity_prelude::GenericChannelId::new))
-                            .collect::<Result<Vec<_>, _>>()
-                            .map_err(
-                                |_| ::anyhow::anyhow!("Expected Channel select menu values for field: {}", #field_name_str),
-                            )
-                    },
+                    SelectKindVariant::User => {
+                        parse_ids(quote! { ::poise::serenity_prelude::UserId }, "User")
+                    }
+                    SelectKindVariant::Role => {
+                        parse_ids(quote! { ::poise::serenity_prelude::RoleId }, "Role")
+                    }
+                    // only the IDs are submitted, so users and roles can't be told apart here
+                    SelectKindVariant::Mentionable => {
+                        parse_ids(quote! { ::poise::serenity_prelude::GenericId }, "User or Role")
+                    }
+                    SelectKindVariant::Channel => {
+                        parse_ids(quote! { ::poise::serenity_prelude::GenericChannelId }, "Channel")
+                    }
                     SelectKindVariant::Unknown => quote! {
                         select_values // fallback to raw strings
                     },
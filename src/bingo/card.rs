@@ -0,0 +1,122 @@
+use anyhow::Result;
+use plotters::{
+    element::{Rectangle, Text},
+    prelude::{IntoDrawingArea as _, SVGBackend},
+    style::{
+        RGBAColor, ShapeStyle, TextStyle,
+        full_palette::GREY_200,
+        text_anchor::{HPos, Pos, VPos},
+    },
+};
+
+use crate::hypixel_api::BingoGoalProgress;
+use crate::splashes::splashlist::render::{FONT_NAME, svg_to_png};
+
+/// Bingo cards are always square
+const COLUMNS: usize = 5;
+const CELL_SIZE: i32 = 260;
+const CELL_GAP: i32 = 16;
+const TITLE_HEIGHT: i32 = 110;
+const FONT_SIZE: f64 = 30.0;
+const LINE_HEIGHT: i32 = 36;
+/// Goal names are wrapped to fit the width of a cell
+const MAX_LINE_CHARS: usize = 16;
+
+const COMPLETED_COLOR: RGBAColor = RGBAColor(120, 177, 89, 0.8);
+const OPEN_COLOR: RGBAColor = RGBAColor(255, 255, 255, 0.1);
+
+/// Draws the goals of the card as a grid, highlighting those the player has completed
+pub fn png_bytes(progress: &BingoGoalProgress, title: &str) -> Result<Vec<u8>> {
+    let rows = progress.goals.len().div_ceil(COLUMNS).max(1) as i32;
+    let width = COLUMNS as i32 * (CELL_SIZE + CELL_GAP) + CELL_GAP;
+    let height = TITLE_HEIGHT + rows * (CELL_SIZE + CELL_GAP) + CELL_GAP;
+
+    let mut svg = String::new();
+
+    {
+        let root =
+            SVGBackend::with_string(&mut svg, (width as u32, height as u32)).into_drawing_area();
+
+        let title_style = TextStyle::from((FONT_NAME, 56.0))
+            .color(&GREY_200)
+            .pos(Pos::new(HPos::Center, VPos::Center));
+        root.draw(&Text::new(
+            title.to_string(),
+            (width / 2, TITLE_HEIGHT / 2),
+            title_style,
+        ))?;
+
+        let goal_style = TextStyle::from((FONT_NAME, FONT_SIZE))
+            .color(&GREY_200)
+            .pos(Pos::new(HPos::Center, VPos::Center));
+
+        for (i, (name, completed)) in progress.goals.iter().enumerate() {
+            let column = (i % COLUMNS) as i32;
+            let row = (i / COLUMNS) as i32;
+            let left = CELL_GAP + column * (CELL_SIZE + CELL_GAP);
+            let top = TITLE_HEIGHT + row * (CELL_SIZE + CELL_GAP);
+
+            let fill = if *completed {
+                COMPLETED_COLOR
+            } else {
+                OPEN_COLOR
+            };
+            root.draw(&Rectangle::new(
+                [(left, top), (left + CELL_SIZE, top + CELL_SIZE)],
+                ShapeStyle::from(&fill).filled(),
+            ))?;
+
+            // vertically centered as a whole
+            let lines = wrap(name, MAX_LINE_CHARS);
+            let center = (left + CELL_SIZE / 2, top + CELL_SIZE / 2);
+            let first_offset = -(lines.len() as i32 - 1) * LINE_HEIGHT / 2;
+            for (line_index, line) in lines.into_iter().enumerate() {
+                root.draw(&Text::new(
+                    line,
+                    (
+                        center.0,
+                        center.1 + first_offset + line_index as i32 * LINE_HEIGHT,
+                    ),
+                    goal_style.clone(),
+                ))?;
+            }
+        }
+
+        root.present()?;
+    }
+
+    svg_to_png(&svg, 1.0)
+}
+
+/// Greedily breaks the text into lines of at most `max_chars`, only splitting words that are too
+/// long by themselves
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let fits =
+            current.is_empty() || current.chars().count() + 1 + word.chars().count() <= max_chars;
+        if !fits {
+            lines.push(std::mem::take(&mut current));
+        }
+
+        let mut chars: Vec<char> = word.chars().collect();
+        while chars.len() > max_chars {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(chars.drain(..max_chars).collect());
+        }
+
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.extend(chars);
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
@@ -0,0 +1,58 @@
+use poise::serenity_prelude::{
+    CreateComponent, CreateContainer, CreateContainerComponent, CreateTextDisplay,
+    colours::branding::YELLOW,
+};
+
+pub struct Release {
+    pub version: &'static str,
+    pub features: &'static [&'static str],
+    pub fixes: &'static [&'static str],
+}
+
+/// Release notes, newest first. Add an entry when deploying user-facing changes, it is posted by
+/// `/debug announce-release`.
+pub const RELEASES: &[Release] = &[Release {
+    version: "0.1.0",
+    features: &[
+        "`/splashlist` can now show splasher shares, cumulative splashes or an hourly heatmap \
+        instead of the daily distribution",
+        "`/splashlist export` exports a month's splashes as CSV or JSON",
+        "`/hob export`, `/hob import` and `/hob publish` for backing up and showcasing the HoB",
+        "`/rolerequest query stats` can list the current bingo card's goals",
+        "`/mystats` shows your own bingo stats, splashes and HoB appearances",
+    ],
+    fixes: &[
+        "Splashes posted while the bot was offline are now counted after it restarts",
+        "`/lastsplashed list` no longer hangs if the bot reconnects while loading members",
+    ],
+}];
+
+/// Releases newer than the last announced version, oldest first. If the last announced version is
+/// unknown, only the newest release is returned rather than the entire history.
+pub fn unannounced(last_announced: Option<&str>) -> Vec<&'static Release> {
+    let newer = last_announced
+        .and_then(|version| RELEASES.iter().position(|r| r.version == version))
+        .unwrap_or(RELEASES.len().min(1));
+
+    RELEASES[..newer].iter().rev().collect()
+}
+
+pub fn release_container(release: &Release) -> CreateComponent<'static> {
+    let mut text = format!("## What's New in v{}\n", release.version);
+    for (heading, entries) in [("Features", release.features), ("Fixes", release.fixes)] {
+        if entries.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("### {heading}\n"));
+        for entry in entries {
+            text.push_str(&format!("- {entry}\n"));
+        }
+    }
+
+    CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(text),
+        )])
+        .accent_color(YELLOW),
+    )
+}
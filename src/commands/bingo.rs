@@ -0,0 +1,160 @@
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::Utc;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateMediaGallery, CreateMediaGalleryItem, CreateTextDisplay, CreateUnfurledMediaItem,
+        MessageFlags, Timestamp, colours::branding::YELLOW,
+    },
+};
+
+use crate::bingo::card;
+use crate::error::UserError;
+use crate::role::db::link::GetLinkedUserByDiscord;
+use crate::shared::{
+    Context,
+    time::{TimestampStyle, discord_timestamp},
+    types::{BingoKind, MinecraftIdent},
+};
+use crate::splashes::splashlist::render;
+
+#[poise::command(slash_command, subcommand_required, subcommands("card", "info"))]
+pub async fn bingo(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// Show the current bingo card with a player's completed goals highlighted
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | ATTACH_FILES"
+)]
+async fn card(
+    ctx: Context<'_>,
+    #[description = "Minecraft username or UUID (defaults to your linked account)"] player: Option<
+        String,
+    >,
+) -> Result<()> {
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    let uuid = match player {
+        Some(player) => {
+            data.api_handle
+                .uuid(db, &MinecraftIdent::from_input(&player)?)
+                .await?
+        }
+        None => {
+            db.request(GetLinkedUserByDiscord {
+                discord: ctx.author().id,
+            })
+            .await??
+            .context(UserError(anyhow!(
+                "You haven't linked a Minecraft account yet, specify a player or request your roles once to link your account"
+            )))?
+            .mc_uuid
+        }
+    };
+
+    let username = data.api_handle.username(db, &uuid).await?;
+    let progress = data.api_handle.bingo_goal_progress(&uuid).await?;
+    if progress.goals.is_empty() {
+        bail!(UserError(anyhow!(
+            "The current bingo card isn't available yet"
+        )));
+    }
+
+    let text = CreateTextDisplay::new(format!(
+        "## {username}'s Bingo Card\n**{}/{}** goals completed",
+        progress.completed(),
+        progress.goals.len()
+    ));
+
+    let title = format!("{username}'s Bingo Card");
+    let png = render::render(move || card::png_bytes(&progress, &title)).await?;
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text),
+            CreateContainerComponent::MediaGallery(CreateMediaGallery::new(vec![
+                CreateMediaGalleryItem::new(CreateUnfurledMediaItem::new(
+                    "attachment://bingo_card.png",
+                )),
+            ])),
+        ])
+        .accent_color(YELLOW),
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .attachment(CreateAttachment::bytes(png, "bingo_card.png")),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show the ID, kind and duration of the current bingo event
+#[poise::command(slash_command)]
+async fn info(ctx: Context<'_>) -> Result<()> {
+    // fetching the bingo from the API may exceed the interaction's response deadline
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let (bingo, start, end) = data
+        .api_handle
+        .update_current_bingo(data.db_for(ctx.guild_id()))
+        .await?;
+
+    let kind = match bingo.kind {
+        BingoKind::Normal => "Normal",
+        BingoKind::Extreme => "Extreme",
+        BingoKind::Secret => "Secret",
+    };
+    let timestamp = |t: i64, style| -> Result<String> {
+        Ok(discord_timestamp(Timestamp::from_unix_timestamp(t)?, style).to_string())
+    };
+
+    let now = Utc::now().timestamp();
+    let status = if now < start {
+        format!("Starts {}", timestamp(start, TimestampStyle::Relative)?)
+    } else if now < end {
+        let days = ((end - now) as u64).div_ceil(24 * 60 * 60);
+        format!(
+            "**{days}** day{} remaining",
+            if days == 1 { "" } else { "s" }
+        )
+    } else {
+        "Ended, the next bingo hasn't been announced yet".to_string()
+    };
+
+    let text = CreateTextDisplay::new(format!(
+        "## {bingo}
+**ID:** {}
+**Kind:** {kind}
+**Start:** {}
+**End:** {}
+{status}",
+        bingo
+            .unique_id
+            .map_or("Unknown".to_string(), |id| id.to_string()),
+        timestamp(start, TimestampStyle::LongDateTime)?,
+        timestamp(end, TimestampStyle::LongDateTime)?,
+    ));
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                    .accent_color(YELLOW),
+            )]),
+    )
+    .await?;
+
+    Ok(())
+}
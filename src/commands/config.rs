@@ -0,0 +1,380 @@
+use anyhow::{Context as _, Result, anyhow, bail};
+use poise::{
+    ChoiceParameter, CreateReply,
+    serenity_prelude::{
+        Colour, CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateTextDisplay, GenericChannelId, Mentionable as _, MessageFlags, Role,
+        colours::{branding::YELLOW, css::POSITIVE},
+    },
+};
+
+use crate::error::UserError;
+use crate::hob::{
+    db::{GetHobPermissions, SetHobPermission},
+    permission::HobAction,
+};
+use crate::role::{
+    cache_pruning,
+    db::cache::{DEFAULT_DISCORD_INDEX_RETENTION_DAYS, GetCacheRetention, SetCacheRetention},
+};
+use crate::shared::{
+    Context,
+    db::{GetGuildPrefix, SetGuildConfigValue, SetGuildPrefix},
+    dry_run,
+    types::{GuildConfig, GuildConfigKey},
+};
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommand_required,
+    subcommands(
+        "view",
+        "channel",
+        "role",
+        "prefix",
+        "hob",
+        "cache_retention",
+        "cache_prune"
+    )
+)]
+pub async fn config(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+const MAX_PREFIX_LEN: usize = 8;
+/// Prefixes used by many popular bots, which would trigger them as well
+const COMMON_PREFIXES: &[&str] = &[
+    "!", "?", ".", ",", "$", "%", "&", "-", "+", "=", "~", ";", ">", "t!", "m!", "p!", "pls",
+];
+
+#[derive(ChoiceParameter)]
+enum ChannelSetting {
+    #[name = "Splashes channel"]
+    Splashes,
+    #[name = "Thank you channel"]
+    ThankYou,
+}
+
+impl From<ChannelSetting> for GuildConfigKey {
+    fn from(setting: ChannelSetting) -> Self {
+        match setting {
+            ChannelSetting::Splashes => GuildConfigKey::SplashesChannel,
+            ChannelSetting::ThankYou => GuildConfigKey::TyChannel,
+        }
+    }
+}
+
+#[derive(ChoiceParameter)]
+enum RoleSetting {
+    #[name = "Splash ping role"]
+    SplashPing,
+    #[name = "Splasher role"]
+    Splasher,
+    #[name = "Splash reminder role"]
+    SplashReminder,
+}
+
+impl From<RoleSetting> for GuildConfigKey {
+    fn from(setting: RoleSetting) -> Self {
+        match setting {
+            RoleSetting::SplashPing => GuildConfigKey::SplashPingRole,
+            RoleSetting::Splasher => GuildConfigKey::SplasherRole,
+            RoleSetting::SplashReminder => GuildConfigKey::SplashReminderRole,
+        }
+    }
+}
+
+/// View the channels and roles used in this server
+#[poise::command(slash_command)]
+async fn view(ctx: Context<'_>) -> Result<()> {
+    let config = ctx.data().guild_config(ctx.guild_id()).await?;
+
+    send_config(ctx, "Server Configuration", &config, YELLOW).await
+}
+
+/// Set a channel used in this server
+#[poise::command(slash_command)]
+async fn channel(
+    ctx: Context<'_>,
+    #[description = "Which channel to configure"] setting: ChannelSetting,
+    #[description = "Channel to use (omit to reset)"] channel: Option<GenericChannelId>,
+) -> Result<()> {
+    set_value(ctx, setting.into(), channel.map(|c| c.get())).await
+}
+
+/// Set a role used in this server
+#[poise::command(slash_command)]
+async fn role(
+    ctx: Context<'_>,
+    #[description = "Which role to configure"] setting: RoleSetting,
+    #[description = "Role to use (omit to reset)"] role: Option<Role>,
+) -> Result<()> {
+    set_value(ctx, setting.into(), role.map(|r| r.id.get())).await
+}
+
+/// Set an additional text prefix for prefix commands like `register` (the bot mention always works)
+#[poise::command(slash_command)]
+async fn prefix(
+    ctx: Context<'_>,
+    #[description = "Text prefix, e.g. 'bb!' (removes the prefix if omitted)"] prefix: Option<
+        String,
+    >,
+) -> Result<()> {
+    let guild_id = ctx
+        .guild_id()
+        .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+
+    let prefix = prefix.map(|p| p.trim().to_string());
+    if let Some(prefix) = &prefix {
+        validate_prefix(prefix)?;
+    }
+
+    let db = ctx.data().db_for(Some(guild_id));
+    db.request(SetGuildPrefix {
+        guild_id,
+        prefix: prefix.clone(),
+    })
+    .await??;
+
+    let text = match db.request(GetGuildPrefix { guild_id }).await?? {
+        Some(prefix) => format!(
+            "## Updated Prefix
+Prefix commands can now be used with `{prefix}` or by mentioning the bot."
+        ),
+        None => "## Removed Prefix
+Prefix commands can only be used by mentioning the bot."
+            .to_string(),
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Restrict an action of the HoB menu to a staff role (administrators are never restricted)
+#[poise::command(slash_command)]
+async fn hob(
+    ctx: Context<'_>,
+    #[description = "Which action to restrict"] action: HobAction,
+    #[description = "Role required for the action (omit to lift the restriction)"] role: Option<
+        Role,
+    >,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+    db.request(SetHobPermission {
+        action,
+        role: role.map(|r| r.id),
+    })
+    .await??;
+
+    let permissions = db.request(GetHobPermissions).await??;
+    let values: String = HobAction::ALL
+        .iter()
+        .map(|action| {
+            let value = permissions
+                .get(action)
+                .map_or_else(|| "*Unrestricted*".to_string(), |r| r.mention().to_string());
+            format!("- {}: {value}\n", action.description())
+        })
+        .collect();
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## Updated HoB Permissions
+{values}-# Unrestricted actions are available to everyone who can use `/hob manage`."
+                    )),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Set how long Discord usernames from fetched players are kept for link suggestions
+#[poise::command(slash_command, rename = "cache-retention")]
+async fn cache_retention(
+    ctx: Context<'_>,
+    #[description = "Retention in days (resets to the default if omitted)"]
+    #[min = 1]
+    #[max = 3650]
+    days: Option<u32>,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+    db.request(SetCacheRetention {
+        discord_index_days: days,
+    })
+    .await??;
+    let days = db.request(GetCacheRetention).await??;
+
+    let default_note = if days == DEFAULT_DISCORD_INDEX_RETENTION_DAYS {
+        " (default)"
+    } else {
+        ""
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## Updated Cache Retention
+Discord usernames of fetched players are kept for **{days} days**{default_note}.
+-# Expired Hypixel responses and completions from past bingos are pruned regardless."
+                    )),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Delete expired cache entries now, instead of waiting for the hourly pruning
+#[poise::command(slash_command, rename = "cache-prune")]
+async fn cache_prune(
+    ctx: Context<'_>,
+    #[description = "Only count the entries that would be deleted"] dry_run: Option<bool>,
+) -> Result<()> {
+    let dry_run = dry_run.unwrap_or(false);
+    ctx.defer_ephemeral().await?;
+
+    let data = ctx.data();
+    let db = dry_run::db(data, ctx.guild_id(), dry_run);
+    let pruned = cache_pruning::prune(&data.api_handle, &db).await?;
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## {}
+- Hypixel player responses: **{}**
+- Hypixel guild member lists: **{}**
+- Completions from past bingos: **{}**
+- Discord usernames past retention: **{}**
+- Mojang profiles: **{}**{}",
+                        dry_run::title("Pruned Caches", dry_run),
+                        pruned.player_endpoint,
+                        pruned.hypixel_guild,
+                        pruned.completions,
+                        pruned.discord_index,
+                        pruned.mojang_profiles,
+                        dry_run::notice(dry_run),
+                    )),
+                )])
+                .accent_color(if dry_run { YELLOW } else { POSITIVE }),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn validate_prefix(prefix: &str) -> Result<()> {
+    if prefix.is_empty() || prefix.chars().count() > MAX_PREFIX_LEN {
+        bail!(UserError(anyhow!(
+            "The prefix must be between 1 and {MAX_PREFIX_LEN} characters long"
+        )));
+    }
+    if prefix.chars().any(char::is_whitespace) || prefix.contains(['`', '@', '<', '/']) {
+        bail!(UserError(anyhow!(
+            "The prefix can't contain whitespace, mentions, slashes or backticks"
+        )));
+    }
+    if COMMON_PREFIXES
+        .iter()
+        .any(|common| prefix.eq_ignore_ascii_case(common))
+    {
+        bail!(UserError(anyhow!(
+            "`{prefix}` is commonly used by other bots, please choose a more specific prefix (e.g. `bb!`)"
+        )));
+    }
+    Ok(())
+}
+
+async fn set_value(ctx: Context<'_>, key: GuildConfigKey, value: Option<u64>) -> Result<()> {
+    let guild_id = ctx
+        .guild_id()
+        .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+
+    let data = ctx.data();
+    data.db_for(Some(guild_id))
+        .request(SetGuildConfigValue {
+            guild_id,
+            key,
+            value,
+        })
+        .await??;
+    data.invalidate_guild_config(guild_id).await;
+
+    let config = data.guild_config(Some(guild_id)).await?;
+
+    send_config(ctx, &format!("Updated {}", key.name()), &config, POSITIVE).await
+}
+
+async fn send_config(
+    ctx: Context<'_>,
+    title: &str,
+    config: &GuildConfig,
+    accent: Colour,
+) -> Result<()> {
+    let values: String = GuildConfigKey::ALL
+        .iter()
+        .map(|key| {
+            let value = match key {
+                GuildConfigKey::SplashesChannel => config.splashes_channel.mention().to_string(),
+                GuildConfigKey::SplashPingRole => config.splash_ping_role.mention().to_string(),
+                GuildConfigKey::SplasherRole => config.splasher_role.mention().to_string(),
+                GuildConfigKey::TyChannel => config.ty_channel.mention().to_string(),
+                GuildConfigKey::SplashReminderRole => {
+                    config.splash_reminder_role.mention().to_string()
+                }
+            };
+            format!("- {}: {value}\n", key.name())
+        })
+        .collect();
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## {title}
+{values}-# Unconfigured values fall back to the bot's defaults."
+                    )),
+                )])
+                .accent_color(accent),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
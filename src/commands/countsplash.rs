@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow, bail};
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateComponent, CreateContainer, CreateContainerComponent, CreateTextDisplay,
+        Mentionable as _, Message, MessageFlags, colours::css::POSITIVE,
+    },
+};
+
+use crate::error::UserError;
+use crate::shared::{
+    Context,
+    db::{GetSplashIngestion, SetSplashOverride, SetSplashReminderSkipped},
+};
+use crate::splash_reminder::event;
+use crate::splashes::ingestion::SplasherResolver;
+
+/// Count a splash message that wasn't detected automatically, e.g. due to unusual formatting
+#[poise::command(context_menu_command = "Count as splash")]
+pub async fn count_splash(
+    ctx: Context<'_>,
+    #[description = "Message to count as a splash"] message: Message,
+) -> Result<()> {
+    set_counted(ctx, &message, true).await?;
+
+    // the message might be the most recent splash, which the reminder didn't pick up on
+    event::register_splash(ctx.serenity_context(), ctx.guild_id(), message.id).await?;
+
+    reply(
+        ctx,
+        format!(
+            "## Counted Splash
+{}{} will now be counted as a splash.",
+            message.link(),
+            splasher_suffix(ctx, &message).await?
+        ),
+    )
+    .await
+}
+
+/// Stop counting a message as a splash, e.g. if it was detected by mistake
+#[poise::command(context_menu_command = "Uncount splash")]
+pub async fn uncount_splash(
+    ctx: Context<'_>,
+    #[description = "Message to no longer count as a splash"] message: Message,
+) -> Result<()> {
+    set_counted(ctx, &message, false).await?;
+
+    event::unregister_splash(ctx.serenity_context(), ctx.guild_id(), message.id).await?;
+
+    reply(
+        ctx,
+        format!(
+            "## Uncounted Splash
+{}{} will no longer be counted as a splash.",
+            message.link(),
+            splasher_suffix(ctx, &message).await?
+        ),
+    )
+    .await
+}
+
+/// Mark a splash as not needing a reminder, e.g. if it was posted right before a planned break
+#[poise::command(context_menu_command = "Skip splash reminder")]
+pub async fn skip_reminder(
+    ctx: Context<'_>,
+    #[description = "Splash message that shouldn't trigger a reminder"] message: Message,
+) -> Result<()> {
+    ensure_splashes_channel(ctx, &message, "can have their reminder skipped").await?;
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashReminderSkipped {
+            message_id: message.id,
+            skipped: true,
+        })
+        .await??;
+
+    // cancels the pending reminder if this is the latest splash
+    event::unregister_splash(ctx.serenity_context(), ctx.guild_id(), message.id).await?;
+
+    reply(
+        ctx,
+        format!(
+            "## Skipped Splash Reminder
+{} will no longer trigger a splash reminder.",
+            message.link()
+        ),
+    )
+    .await
+}
+
+/// `action` completes the error message "Only messages in #splashes ..."
+async fn ensure_splashes_channel(ctx: Context<'_>, message: &Message, action: &str) -> Result<()> {
+    // splashes are only ever fetched from the splashes channel
+    let splashes_channel = ctx
+        .data()
+        .guild_config(ctx.guild_id())
+        .await?
+        .splashes_channel;
+    if message.channel_id != splashes_channel {
+        bail!(UserError(anyhow!(
+            "Only messages in {} {action}",
+            splashes_channel.mention()
+        )));
+    }
+
+    Ok(())
+}
+
+async fn set_counted(ctx: Context<'_>, message: &Message, counted: bool) -> Result<()> {
+    ensure_splashes_channel(ctx, message, "can be counted as splashes").await?;
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashOverride {
+            message_id: message.id,
+            counted,
+        })
+        .await??;
+
+    Ok(())
+}
+
+/// `" by <splasher>"`, or nothing if the splasher of a relayed splash can't be determined
+async fn splasher_suffix(ctx: Context<'_>, message: &Message) -> Result<String> {
+    let data = ctx.data();
+    let ingestion = data
+        .db_for(ctx.guild_id())
+        .request(GetSplashIngestion)
+        .await??;
+
+    Ok(SplasherResolver::new(ingestion)
+        .resolve(ctx.http(), message)
+        .await?
+        .map(|splasher| format!(" by {}", splasher.mention()))
+        .unwrap_or_default())
+}
+
+async fn reply(ctx: Context<'_>, text: String) -> Result<()> {
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -2,24 +2,43 @@ use std::fs;
 use std::path::Path;
 
 use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::Utc;
 use poise::{
     ChoiceParameter, CreateReply,
     serenity_prelude::{
-        AutocompleteChoice, CreateAutocompleteResponse, CreateComponent, CreateContainer,
-        CreateContainerComponent, CreateTextDisplay, MessageFlags,
-        colours::css::{DANGER, POSITIVE},
+        AutocompleteChoice, CreateAllowedMentions, CreateAttachment, CreateAutocompleteResponse,
+        CreateComponent, CreateContainer, CreateContainerComponent, CreateFile, CreateMessage,
+        CreateTextDisplay, CreateUnfurledMediaItem, GenericChannelId, Mentionable as _,
+        MessageFlags,
+        colours::css::{DANGER, POSITIVE, WARNING},
     },
 };
 use tracing::warn;
 
+use crate::changelog;
 use crate::config::DB_SCRIPTS_DIR;
+use crate::db::{backup as db_backup, slow_query};
 use crate::error::UserError;
+use crate::metrics as bot_metrics;
+use crate::role::db::cache::InspectPlayerCache;
 use crate::shared::{
     Context,
-    db::{RawBatch, RawQueryReadonly},
+    db::{
+        GetDbStats, GetReleaseAnnouncement, RawBatch, RawQueryReadonly, SetReleaseAnnouncement,
+        Vacuum,
+    },
+    task,
+    types::BitSet,
 };
 
-#[poise::command(slash_command, subcommand_required, subcommands("error", "sql"))]
+/// Discord's upload limit for bots in servers without boosts
+const MAX_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("error", "sql", "tasks", "db", "backup", "metrics", "announce_release")
+)]
 pub async fn debug(_ctx: Context<'_>) -> Result<()> {
     unreachable!("This shouldn't be possible to invoke");
 }
@@ -49,6 +68,407 @@ async fn error(
     }
 }
 
+/// View counters of background tasks (menu timeouts, splash reminder timers) since startup
+#[poise::command(slash_command)]
+async fn tasks(ctx: Context<'_>) -> Result<()> {
+    let stats = task::stats();
+
+    let text = format!(
+        "## Background Tasks
+Spawned: **{}**
+Running: **{}**
+Panicked: **{}**",
+        stats.spawned, stats.running, stats.panicked
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(if stats.panicked > 0 { DANGER } else { POSITIVE }),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("db_queue", "db_stats", "db_query_cache", "db_vacuum")
+)]
+async fn db(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// View the database queue and the slowest requests since startup
+#[poise::command(slash_command, rename = "queue")]
+async fn db_queue(ctx: Context<'_>) -> Result<()> {
+    let threshold = slow_query::threshold();
+    let slowest = slow_query::slowest();
+
+    let list = if slowest.is_empty() {
+        "*No requests yet*".to_string()
+    } else {
+        slowest
+            .iter()
+            .enumerate()
+            .map(|(i, query)| {
+                format!(
+                    "{}. `{}`: **{:.1?}** (<t:{}:R>)",
+                    i + 1,
+                    query.request,
+                    query.duration,
+                    query.at.timestamp()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let text = format!(
+        "## Database
+Queued requests: **{}**
+Slow request threshold: **{threshold:?}**
+### Slowest Requests
+{list}",
+        ctx.data().db_for(ctx.guild_id()).queue_depth()
+    );
+
+    let slow = slowest
+        .first()
+        .is_some_and(|query| query.duration >= threshold);
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(if slow { WARNING } else { POSITIVE }),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// View the row count of every table and the size of the database file
+#[poise::command(slash_command, rename = "stats")]
+async fn db_stats(ctx: Context<'_>) -> Result<()> {
+    let stats = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetDbStats)
+        .await??;
+
+    let rows = stats
+        .tables
+        .iter()
+        .map(|(table, count)| format!("`{table}`: **{count}**"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let text = format!(
+        "## Database Stats
+File size: **{} KiB** ({} KiB unused)
+### Rows per Table
+{rows}",
+        stats.file_size() / 1024,
+        stats.free_size() / 1024
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// View the raw cached bingo data of a player, without invalidating outdated entries
+#[poise::command(slash_command, rename = "query-cache")]
+async fn db_query_cache(
+    ctx: Context<'_>,
+    #[description = "Minecraft UUID of the player"] uuid: String,
+) -> Result<()> {
+    let uuid = uuid.trim().replace('-', "").to_lowercase();
+    if uuid.len() != 32 || !uuid.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(UserError(anyhow!("Invalid UUID")));
+    }
+
+    let entries = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(InspectPlayerCache { uuid: uuid.clone() })
+        .await??;
+
+    fn entry<T>(cached: &Option<(u8, T)>, format: impl Fn(&T) -> String) -> String {
+        match cached {
+            Some((updated_after, value)) => {
+                format!("{} (updated after #{updated_after})", format(value))
+            }
+            None => "*none*".to_string(),
+        }
+    }
+    let ids = |set: &BitSet| {
+        let ids = set.get_all_set();
+        if ids.is_empty() {
+            "**0** IDs".to_string()
+        } else {
+            let list = ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("**{}** IDs: `{list}`", ids.len())
+        }
+    };
+
+    let text = format!(
+        "## Cached Data of `{uuid}`
+Completions: {}
+Network Bingos: {}
+Bingo Rank: {}
+Immortal: {}
+Player endpoint: {}",
+        entry(&entries.completions, ids),
+        entry(&entries.network_bingos, ids),
+        entry(&entries.bingo_rank, |rank| format!("**{rank}**")),
+        entry(&entries.immortal, |immortal| format!("**{immortal}**")),
+        entries
+            .player_endpoint
+            .map_or("*none*".to_string(), |timestamp| format!(
+                "cached <t:{timestamp}:R>"
+            )),
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Rebuild the database file to reclaim unused space. Blocks all other requests while running!
+#[poise::command(slash_command, rename = "vacuum")]
+async fn db_vacuum(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let before = db.request(GetDbStats).await??.file_size();
+    db.request(Vacuum).await??;
+    let after = db.request(GetDbStats).await??.file_size();
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## Database Vacuumed
+File size: **{} KiB** → **{} KiB**",
+                        before / 1024,
+                        after / 1024
+                    )),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Create a consistent snapshot of the database, written to `BACKUP_DIR` or uploaded
+#[poise::command(slash_command)]
+async fn backup(
+    ctx: Context<'_>,
+    #[description = "Upload the snapshot, even if a backup directory is configured"] upload: Option<
+        bool,
+    >,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    let reply = match db_backup::config().filter(|_| !upload.unwrap_or(false)) {
+        Some(config) => {
+            let path = db_backup::backup_to(db, &config.dir, "manual").await?;
+            CreateReply::new().components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## Database Backed Up
+Written to `{}`.",
+                        path.display()
+                    )),
+                )])
+                .accent_color(POSITIVE),
+            )])
+        }
+        None => {
+            let bytes = db_backup::backup_bytes(db, MAX_UPLOAD_BYTES).await?;
+
+            let file_name = format!("backup-{}.sqlite3", Utc::now().format("%Y-%m-%d"));
+            let text = format!(
+                "## Database Backed Up
+Snapshot of **{} KiB**.",
+                bytes.len() / 1024
+            );
+            CreateReply::new()
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![
+                        CreateContainerComponent::TextDisplay(CreateTextDisplay::new(text)),
+                        CreateContainerComponent::File(CreateFile::new(
+                            CreateUnfurledMediaItem::new(format!("attachment://{file_name}")),
+                        )),
+                    ])
+                    .accent_color(POSITIVE),
+                )])
+                .attachment(CreateAttachment::bytes(bytes, file_name))
+        }
+    };
+
+    ctx.send(reply.flags(MessageFlags::IS_COMPONENTS_V2).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Download the metrics otherwise served by the health server, in Prometheus' text format
+#[poise::command(slash_command)]
+async fn metrics(ctx: Context<'_>) -> Result<()> {
+    let exposition = bot_metrics::render(ctx.data()).await;
+
+    let text = format!(
+        "## Metrics
+Counters since startup, as served on `/metrics` by the health server (see `HEALTH_ADDR`).
+-# {} series",
+        exposition
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .count()
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![
+                    CreateContainerComponent::TextDisplay(CreateTextDisplay::new(text)),
+                    CreateContainerComponent::File(CreateFile::new(CreateUnfurledMediaItem::new(
+                        "attachment://metrics.txt",
+                    ))),
+                ])
+                .accent_color(POSITIVE),
+            )])
+            .attachment(CreateAttachment::bytes(
+                exposition.into_bytes(),
+                "metrics.txt",
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Post release notes of versions that haven't been announced yet
+#[poise::command(
+    slash_command,
+    rename = "announce-release",
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES"
+)]
+async fn announce_release(
+    ctx: Context<'_>,
+    #[description = "Channel to post release notes to (defaults to the previously used channel)"]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let previous = db.request(GetReleaseAnnouncement).await??;
+
+    let channel = channel
+        .or_else(|| previous.as_ref().map(|(channel, _)| *channel))
+        .context(UserError(anyhow!(
+            "No release notes have been posted yet, please specify a channel"
+        )))?;
+
+    let releases = changelog::unannounced(previous.as_ref().map(|(_, version)| version.as_str()));
+    let Some(newest) = releases.last() else {
+        bail!(UserError(anyhow!(
+            "All releases have already been announced"
+        )));
+    };
+
+    for release in &releases {
+        channel
+            .send_message(
+                ctx.http(),
+                CreateMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![changelog::release_container(release)]),
+            )
+            .await?;
+    }
+
+    db.request(SetReleaseAnnouncement {
+        channel,
+        version: newest.version.to_string(),
+    })
+    .await??;
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Announced Release
+Posted the release notes of {} version(s) up to v{} to {}.",
+                releases.len(),
+                newest.version,
+                channel.mention()
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
 /// Execute read-only SQL on the bot's database. ('SELECT' statements)
 #[poise::command(slash_command)]
 async fn sql(
@@ -57,7 +477,7 @@ async fn sql(
 ) -> Result<()> {
     let sql_data = ctx
         .data()
-        .db_handle
+        .db_for(ctx.guild_id())
         .request(RawQueryReadonly { sql })
         .await??;
 
@@ -118,7 +538,10 @@ Script path cannot contain `..`",
     let sql = fs::read_to_string(format!("{DB_SCRIPTS_DIR}/{script}"))
         .context(UserError(anyhow!("Failed to load SQL script")))?;
 
-    ctx.data().db_handle.request(RawBatch { sql }).await??;
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(RawBatch { sql })
+        .await??;
 
     let container = CreateComponent::Container(
         CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
@@ -0,0 +1,147 @@
+use anyhow::{Result, anyhow, bail};
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        Colour, CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateTextDisplay, Mentionable as _, MessageFlags, Role, User,
+        colours::{branding::YELLOW, css::POSITIVE},
+    },
+};
+
+use crate::error::UserError;
+use crate::shared::{
+    Context,
+    db::{GetFeatureFlag, SetFeatureAccess, SetFeatureEnabled},
+    feature::{Feature, FeatureAccess, FeatureFlag},
+};
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommand_required,
+    subcommands("view", "set", "allow", "revoke")
+)]
+pub async fn feature(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// View which features are available to whom
+#[poise::command(slash_command)]
+async fn view(ctx: Context<'_>) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    let mut flags = Vec::new();
+    for &feature in Feature::ALL {
+        flags.push(db.request(GetFeatureFlag { feature }).await??);
+    }
+
+    send_flags(ctx, "Feature Flags", &flags, YELLOW).await
+}
+
+/// Enable a feature for everyone, or restrict it to its allowed users and roles
+#[poise::command(slash_command)]
+async fn set(
+    ctx: Context<'_>,
+    #[description = "Feature to configure"] feature: Feature,
+    #[description = "Whether the feature is available to everyone"] enabled: bool,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+    db.request(SetFeatureEnabled { feature, enabled }).await??;
+
+    let flag = db.request(GetFeatureFlag { feature }).await??;
+    send_flags(ctx, "Updated Feature Flag", &[flag], POSITIVE).await
+}
+
+/// Make a feature available to a user or role before it is enabled for everyone
+#[poise::command(slash_command)]
+async fn allow(
+    ctx: Context<'_>,
+    #[description = "Feature to configure"] feature: Feature,
+    #[description = "User to allow"] user: Option<User>,
+    #[description = "Role to allow"] role: Option<Role>,
+) -> Result<()> {
+    set_access(ctx, feature, user, role, true).await
+}
+
+/// Remove a user or role from a feature's allowed users and roles
+#[poise::command(slash_command)]
+async fn revoke(
+    ctx: Context<'_>,
+    #[description = "Feature to configure"] feature: Feature,
+    #[description = "User to remove"] user: Option<User>,
+    #[description = "Role to remove"] role: Option<Role>,
+) -> Result<()> {
+    set_access(ctx, feature, user, role, false).await
+}
+
+async fn set_access(
+    ctx: Context<'_>,
+    feature: Feature,
+    user: Option<User>,
+    role: Option<Role>,
+    allowed: bool,
+) -> Result<()> {
+    let access = match (user, role) {
+        (Some(user), None) => FeatureAccess::User(user.id),
+        (None, Some(role)) => FeatureAccess::Role(role.id),
+        _ => bail!(UserError(anyhow!(
+            "Exactly one of `user` or `role` must be specified"
+        ))),
+    };
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    db.request(SetFeatureAccess {
+        feature,
+        access,
+        allowed,
+    })
+    .await??;
+
+    let flag = db.request(GetFeatureFlag { feature }).await??;
+    send_flags(ctx, "Updated Feature Flag", &[flag], POSITIVE).await
+}
+
+async fn send_flags(
+    ctx: Context<'_>,
+    title: &str,
+    flags: &[FeatureFlag],
+    accent: Colour,
+) -> Result<()> {
+    let list: String = flags
+        .iter()
+        .map(|flag| {
+            let status = if flag.enabled {
+                "enabled for everyone".to_string()
+            } else {
+                let allowed: Vec<String> = flag
+                    .users
+                    .iter()
+                    .map(|u| u.mention().to_string())
+                    .chain(flag.roles.iter().map(|r| r.mention().to_string()))
+                    .collect();
+                if allowed.is_empty() {
+                    "disabled".to_string()
+                } else {
+                    format!("only for {}", allowed.join(", "))
+                }
+            };
+            format!("- {}: {status}\n", flag.feature.name())
+        })
+        .collect();
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!("## {title}\n{list}")),
+                )])
+                .accent_color(accent),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
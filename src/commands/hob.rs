@@ -1,42 +1,311 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use poise::{
-    CreateReply,
+    ChoiceParameter, CreateReply,
     serenity_prelude::{
-        CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent,
-        CreateMessage, CreateTextDisplay, GenericChannelId, Mentionable as _, MessageFlags,
-        colours::css::POSITIVE,
+        Attachment, AutocompleteChoice, ButtonStyle, CreateActionRow, CreateAllowedMentions,
+        CreateAttachment, CreateAutocompleteResponse, CreateButton, CreateComponent,
+        CreateContainer, CreateContainerComponent, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, CreateSeparator, CreateTextDisplay, Event,
+        GenericChannelId, Interaction, Mentionable as _, MessageFlags, collector,
+        colours::{
+            branding::YELLOW,
+            css::{POSITIVE, WARNING},
+        },
+        futures::StreamExt as _,
     },
 };
 use tokio::sync::{Mutex, Notify};
+use tracing::warn;
 
 use crate::config::{HOB_LOG_CHANNEL, MENU_TIMEOUT_SECS};
+use crate::error::UserError;
 use crate::hob::{
-    db::GetAllHobEntries,
-    menu::{HobEditSession, HobEditState, SelectEntryState, format},
-    types::HobEntry,
+    db::{
+        GetAllHobAttachments, GetAllHobEntries, GetAllHobTags, GetHobEntry, ImportHobEntries,
+        SearchEntriesContent, SetHobRecordChannel,
+    },
+    menu::{HobEditSession, HobEditState, SelectEntryState, ViewEntryState, format},
+    types::{ArchivedHobEntry, HobEntry},
 };
 use crate::shared::{
     Context,
-    menu::{navigation::GenerateMenu as _, timeout},
+    feature::{self, Feature},
+    menu::{
+        confirm, generate_id,
+        navigation::{BacktrackState as _, GenerateMenu as _, PaginatedChunk, page_navigation},
+        timeout,
+    },
+    types::BingoKind,
 };
 
-#[poise::command(slash_command, subcommand_required, subcommands("manage", "send"))]
+/// Entries per page of `/hob browse`, kept low as every entry may include a gallery
+const BROWSE_PAGE_SIZE: usize = 3;
+/// Generous for a text export, while rejecting unrelated uploads before downloading them
+const MAX_IMPORT_BYTES: u32 = 2 * 1024 * 1024;
+/// Titles listed in the `/hob import` preview before truncating
+const MAX_PREVIEW_TITLES: usize = 15;
+/// Discord's limits for autocomplete responses
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+const MAX_CHOICE_NAME_CHARS: usize = 100;
+/// Marks autocomplete values of `/hob view` as entry IDs rather than search input
+const ENTRY_CHOICE_PREFIX: &str = "id:";
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands(
+        "browse", "manage", "view", "send", "export", "import", "publish", "records"
+    )
+)]
 pub async fn hob(_ctx: Context<'_>) -> Result<()> {
     unreachable!("This shouldn't be possible to invoke");
 }
 
+#[derive(ChoiceParameter, Clone, Copy)]
+enum KindOption {
+    Normal,
+    Extreme,
+    Secret,
+}
+
+impl From<KindOption> for BingoKind {
+    fn from(kind: KindOption) -> Self {
+        match kind {
+            KindOption::Normal => BingoKind::Normal,
+            KindOption::Extreme => BingoKind::Extreme,
+            KindOption::Secret => BingoKind::Secret,
+        }
+    }
+}
+
+/// Browse the Hall of Bingo
+#[poise::command(slash_command)]
+async fn browse(
+    ctx: Context<'_>,
+    #[description = "Only show entries achieved during this kind of bingo"] kind: Option<
+        KindOption,
+    >,
+    #[description = "Only show entries involving this player"] player: Option<String>,
+) -> Result<()> {
+    feature::require(ctx, Feature::HobBrowse).await?;
+    ctx.defer_ephemeral().await?;
+
+    let player = player
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let attachments = db.request(GetAllHobAttachments).await??;
+    let entries: Vec<HobEntry> = db
+        .request(GetAllHobEntries)
+        .await??
+        .into_iter()
+        .filter(|entry| kind.is_none_or(|kind| entry.has_bingo_kind(kind.into())))
+        .filter(|entry| {
+            player
+                .as_ref()
+                .is_none_or(|player| entry.has_player(player))
+        })
+        .collect();
+
+    // not routed by the event handler, unlike the `hob` prefix of the edit menu
+    let id_prefix = format!("hobbrowse:{}", generate_id());
+
+    let mut page = 0;
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(browse_page(&entries, &attachments, page, &id_prefix))
+                .ephemeral(true),
+        )
+        .await?;
+
+    // only the invoking user can see the ephemeral response, so no need to check who clicked
+    let prefix = id_prefix.clone();
+    let mut stream = collector::collect(ctx.serenity_context(), move |event| match event {
+        Event::InteractionCreate(event) => match &event.interaction {
+            Interaction::Component(interaction)
+                if interaction.data.custom_id.starts_with(&prefix) =>
+            {
+                Some(interaction.clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    });
+
+    let timeout = Duration::from_secs(MENU_TIMEOUT_SECS);
+    while let Ok(Some(interaction)) = tokio::time::timeout(timeout, stream.next()).await {
+        match interaction.data.custom_id.rsplit(':').next() {
+            Some("prev") => page = page.saturating_sub(1),
+            Some("next") => page += 1,
+            _ => continue,
+        }
+
+        // clamp page to the actually displayed one
+        page = PaginatedChunk::new(entries.len(), page, BROWSE_PAGE_SIZE).page;
+
+        interaction
+            .create_response(
+                ctx.http(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .flags(MessageFlags::IS_COMPONENTS_V2)
+                        .components(browse_page(&entries, &attachments, page, &id_prefix)),
+                ),
+            )
+            .await?;
+    }
+
+    // remove navigation once the menu has expired
+    handle
+        .edit(
+            ctx,
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(browse_page(&entries, &attachments, page, "")),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Renders a page of read-only entries, without navigation if `id_prefix` is empty
+fn browse_page(
+    entries: &[HobEntry],
+    attachments: &HashMap<u64, Vec<String>>,
+    page: usize,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let chunk = PaginatedChunk::new(entries.len(), page, BROWSE_PAGE_SIZE);
+
+    let mut components = vec![CreateContainerComponent::TextDisplay(
+        CreateTextDisplay::new(format!(
+            "## Hall of Bingo
+-# {} matching entries",
+            entries.len()
+        )),
+    )];
+
+    if entries.is_empty() {
+        components.push(CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new("*No entries found.*"),
+        ));
+    }
+
+    for entry in &entries[chunk.range.clone()] {
+        components.push(CreateContainerComponent::Separator(CreateSeparator::new(
+            true,
+        )));
+        components.push(entry.to_text_display().0);
+
+        let entry_attachments = attachments.get(&entry.id()).map_or(&[][..], Vec::as_slice);
+        components.extend(entry.gallery(entry_attachments));
+    }
+
+    if !id_prefix.is_empty() && chunk.total_pages > 1 {
+        components.push(page_navigation(id_prefix, &chunk));
+    }
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_color(YELLOW),
+    )]
+}
+
 /// Manage the HoB database
 #[poise::command(slash_command, required_bot_permissions = "VIEW_CHANNEL")]
 async fn manage(ctx: Context<'_>) -> Result<()> {
-    let menu_id = crate::shared::menu::generate_id();
-    let mut initial_state = SelectEntryState {
+    let initial_state = SelectEntryState {
         page: 0,
         search_query: None,
+        tag: None,
+    };
+    open_edit_menu(ctx, HobEditState::SelectEntry(initial_state)).await
+}
+
+/// Open a single HoB entry for editing
+#[poise::command(slash_command, required_bot_permissions = "VIEW_CHANNEL")]
+async fn view(
+    ctx: Context<'_>,
+    #[description = "Part of the entry's title, or any other text it contains"]
+    #[autocomplete = "autocomplete_entry"]
+    entry: String,
+) -> Result<()> {
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    // choices carry the prefixed entry ID, anything else is treated as a search, so that numbers
+    // such as bingos aren't mistaken for IDs
+    let chosen = match entry
+        .strip_prefix(ENTRY_CHOICE_PREFIX)
+        .and_then(|id| id.parse().ok())
+    {
+        Some(id) => db.request(GetHobEntry { id }).await??,
+        None => None,
     };
+    let entry_id = match chosen {
+        Some(chosen) => chosen.id(),
+        None => db
+            .request(SearchEntriesContent {
+                query: entry.clone(),
+            })
+            .await??
+            .first()
+            .map(HobEntry::id)
+            .ok_or_else(|| UserError(anyhow!("No HoB entry matches `{entry}`")))?,
+    };
+
+    // entries opened directly return to the entry list
+    let mut initial_state = ViewEntryState::new(entry_id, 0);
+    initial_state.set_referrer(HobEditState::SelectEntry(SelectEntryState::new(0, None)));
+    open_edit_menu(ctx, HobEditState::ViewEntry(initial_state)).await
+}
+
+async fn autocomplete_entry<'a>(ctx: Context<'_>, partial: &str) -> CreateAutocompleteResponse<'a> {
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let entries: Result<Vec<HobEntry>> = async {
+        Ok(match partial.trim() {
+            "" => db.request(GetAllHobEntries).await??,
+            query => {
+                db.request(SearchEntriesContent {
+                    query: query.to_string(),
+                })
+                .await??
+            }
+        })
+    }
+    .await;
+    let entries = entries.unwrap_or_else(|err| {
+        warn!("HoB entry autocompletion failed for input '{partial}': {err:#}");
+        Vec::new()
+    });
+
+    let choices: Vec<_> = entries
+        .iter()
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .map(|entry| {
+            let name: String = entry.title().chars().take(MAX_CHOICE_NAME_CHARS).collect();
+            AutocompleteChoice::new(name, format!("{ENTRY_CHOICE_PREFIX}{}", entry.id()))
+        })
+        .collect();
+
+    CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Sends the HoB edit menu in the given state and registers its session
+async fn open_edit_menu(ctx: Context<'_>, mut initial_state: HobEditState) -> Result<()> {
+    let menu_id = crate::shared::menu::generate_id();
     let menu = initial_state
-        .generate(&ctx.data().db_handle, menu_id)
+        .generate(ctx.data().db_for(ctx.guild_id()), menu_id)
         .await?;
 
     let message_handle = ctx.send(menu.into_reply()).await?.into_message().await?;
@@ -45,8 +314,9 @@ async fn manage(ctx: Context<'_>) -> Result<()> {
 
     let session = HobEditSession {
         menu_id,
-        state: HobEditState::SelectEntry(initial_state),
+        state: initial_state,
         owner,
+        guild_id: ctx.guild_id(),
         channel_id: message_handle.channel_id,
         message_id: message_handle.id,
         timeout_reset: Arc::new(Notify::new()),
@@ -86,7 +356,11 @@ async fn send(
     let channel = channel.unwrap_or(ctx.channel_id());
     let suppress_backup_script = suppress_backup_script.unwrap_or(false);
 
-    let hob_entries = ctx.data().db_handle.request(GetAllHobEntries).await??;
+    let hob_entries = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetAllHobEntries)
+        .await??;
 
     let containers = format::build_hob_messages(&hob_entries, 5)?;
     let message_count = containers.len();
@@ -131,6 +405,306 @@ The full HoB was sent to {} in the form of {message_count} messages.",
     Ok(())
 }
 
+#[derive(ChoiceParameter, Clone, Copy, Default)]
+enum ExportFormat {
+    #[default]
+    Markdown,
+    #[name = "JSON"]
+    Json,
+}
+
+/// Publish the HoB to a showcase channel, updating previously published messages in place
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | MANAGE_MESSAGES"
+)]
+async fn publish(
+    ctx: Context<'_>,
+    #[description = "Showcase channel (defaults to the previously used one)"]
+    #[channel_types("Text")]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let outcome =
+        crate::hob::publish::publish(ctx.http(), ctx.data().db_for(ctx.guild_id()), channel)
+            .await?;
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Published Successfully
+The HoB showcase in {} is up to date.
+-# {} messages edited, {} sent, {} removed.",
+                outcome.channel.mention(),
+                outcome.edited,
+                outcome.sent,
+                outcome.deleted,
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Set the channel announcing new records of ongoing entries, enabled per entry in `/hob manage`
+#[poise::command(slash_command)]
+async fn records(
+    ctx: Context<'_>,
+    #[description = "Announcement channel (disables announcements if omitted)"]
+    #[channel_types("Text")]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetHobRecordChannel { channel })
+        .await??;
+
+    let text = match channel {
+        Some(channel) => format!(
+            "## Enabled Record Announcements
+New records of ongoing entries are announced in {}, if enabled for the entry in `/hob manage`.",
+            channel.mention()
+        ),
+        None => "## Disabled Record Announcements
+New records of ongoing entries are no longer announced."
+            .to_string(),
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Export the full HoB as a file, e.g. to archive or publish it outside of Discord
+#[poise::command(slash_command)]
+async fn export(
+    ctx: Context<'_>,
+    #[description = "File format of the export (defaults to Markdown)"] format: Option<
+        ExportFormat,
+    >,
+) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let hob_entries = db.request(GetAllHobEntries).await??;
+
+    let (contents, extension) = match format.unwrap_or_default() {
+        ExportFormat::Markdown => (format::build_hob_markdown(&hob_entries), "md"),
+        ExportFormat::Json => {
+            let attachments = db.request(GetAllHobAttachments).await??;
+            let tags = db.request(GetAllHobTags).await??;
+            (
+                format::build_hob_json(&hob_entries, &attachments, &tags),
+                "json",
+            )
+        }
+    };
+    let file = CreateAttachment::bytes(
+        contents.into_bytes(),
+        format!(
+            "hall_of_bingo_{}.{extension}",
+            chrono::Utc::now().format("%Y-%m-%d")
+        ),
+    );
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Exported Hall of Bingo
+The attached file contains all {} HoB entries.",
+                hob_entries.len()
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .attachment(file)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Import HoB entries from a JSON file created by `/hob export`
+#[poise::command(slash_command)]
+async fn import(
+    ctx: Context<'_>,
+    #[description = "JSON export of HoB entries"] file: Attachment,
+) -> Result<()> {
+    if file.size > MAX_IMPORT_BYTES {
+        bail!(UserError(anyhow!(
+            "The file is too large to be a HoB export (max {} KB)",
+            MAX_IMPORT_BYTES / 1024
+        )));
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let contents = String::from_utf8(file.download().await?)
+        .map_err(|_| UserError(anyhow!("The file isn't valid UTF-8 text")))?;
+    let entries = format::parse_hob_json(&contents)?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let existing_ids: HashSet<u64> = db
+        .request(GetAllHobEntries)
+        .await??
+        .iter()
+        .map(HobEntry::id)
+        .collect();
+
+    let total = entries.len();
+    let new_entries: Vec<ArchivedHobEntry> = entries
+        .into_iter()
+        .filter(|archived| !existing_ids.contains(&archived.entry.id()))
+        .collect();
+
+    // dry run: nothing is written until the preview is confirmed
+    let id_prefix = format!("hobimport:{}", generate_id());
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(import_preview(&new_entries, total, &id_prefix))
+                .ephemeral(true),
+        )
+        .await?;
+
+    if new_entries.is_empty() {
+        return Ok(());
+    }
+
+    confirm::await_confirmation(
+        ctx,
+        handle,
+        &id_prefix,
+        "Import",
+        "No entries were imported.",
+        || async move {
+            let imported = db
+                .request(ImportHobEntries {
+                    entries: new_entries,
+                })
+                .await??;
+            Ok(confirm::outcome(
+                &format!(
+                    "## Imported Successfully
+{imported} entries were added to the Hall of Bingo.
+-# Use `/hob send` to update the published list."
+                ),
+                POSITIVE,
+            ))
+        },
+    )
+    .await
+}
+
+fn import_preview(
+    new_entries: &[ArchivedHobEntry],
+    total: usize,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let skipped = total - new_entries.len();
+    if new_entries.is_empty() {
+        return confirm::outcome(
+            &format!(
+                "## Nothing to Import
+All {total} entries of the file already exist in the Hall of Bingo."
+            ),
+            WARNING,
+        );
+    }
+
+    let entries = new_entries.iter().map(|archived| &archived.entry);
+    let oneoff_count = entries
+        .clone()
+        .filter(|entry| matches!(entry, HobEntry::OneOff { .. }))
+        .count();
+    let subentry_count: usize = entries
+        .clone()
+        .map(|entry| match entry {
+            HobEntry::OneOff { .. } => 0,
+            HobEntry::Ongoing { subentries, .. } => subentries.len(),
+        })
+        .sum();
+
+    let mut titles: String = entries
+        .take(MAX_PREVIEW_TITLES)
+        .map(|entry| match entry {
+            HobEntry::OneOff { title, .. } | HobEntry::Ongoing { title, .. } => {
+                format!("- {title}\n")
+            }
+        })
+        .collect();
+    if new_entries.len() > MAX_PREVIEW_TITLES {
+        titles.push_str(&format!(
+            "- *...and {} more*\n",
+            new_entries.len() - MAX_PREVIEW_TITLES
+        ));
+    }
+
+    let text = CreateTextDisplay::new(format!(
+        "## Import Preview
+The following would be added to the Hall of Bingo:
+- **{oneoff_count}** one-off entries
+- **{}** iterative entries with **{subentry_count}** subentries
+{}### New Entries
+{titles}-# Nothing has been imported yet, confirm to import all entries at once.",
+        new_entries.len() - oneoff_count,
+        if skipped > 0 {
+            format!("-# {skipped} entries are skipped, as their IDs already exist.\n")
+        } else {
+            String::new()
+        },
+    ));
+
+    let buttons = CreateActionRow::Buttons(
+        vec![
+            CreateButton::new(format!("{id_prefix}:confirm"))
+                .label("Import")
+                .style(ButtonStyle::Success),
+            CreateButton::new(format!("{id_prefix}:cancel"))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ]
+        .into(),
+    );
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text),
+            CreateContainerComponent::ActionRow(buttons),
+        ])
+        .accent_color(YELLOW),
+    )]
+}
+
 fn log_message(hob_entries: &[HobEntry]) -> Result<CreateMessage<'static>> {
     let log_text = "## HoB Backup Script
 This script resets the tables responsible for storing HoB data to their current state \
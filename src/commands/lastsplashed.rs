@@ -1,24 +1,25 @@
-use std::cmp::Ordering;
-
 use anyhow::{Context as _, Result, anyhow};
-use chrono::{Datelike as _, TimeZone as _};
 use poise::{
     CreateReply,
     serenity_prelude::{
-        ChunkGuildFilter, CreateAllowedMentions, CreateComponent, CreateContainer,
-        CreateContainerComponent, CreateTextDisplay, Event, Mentionable as _, MessageFlags,
-        Timestamp, UserId, collector,
+        CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateTextDisplay, Mentionable as _, MessageFlags, Timestamp, UserId,
         colours::{
             branding::YELLOW,
             css::{DANGER, POSITIVE, WARNING},
         },
-        futures::StreamExt,
     },
 };
 
-use crate::config::SPLASHER_ROLE;
 use crate::error::UserError;
-use crate::shared::{Context, menu::generate_id};
+use crate::shared::{
+    Context,
+    calendar::{self, SystemClock},
+    db::GetSplasherAways,
+    members,
+    time::{TimestampStyle::LongDate, discord_timestamp},
+    types::SplasherAway,
+};
 use crate::splashes::lastsplashed;
 
 #[poise::command(
@@ -40,67 +41,42 @@ async fn lastsplashed_list(ctx: Context<'_>) -> Result<()> {
         .guild_id()
         .context(UserError(anyhow!("Command invoked outside of a guild")))?;
 
-    let nonce = generate_id().to_string();
-    // request the guild's members to be chunked and sent over the shard connection
-    ctx.serenity_context().chunk_guild(
-        guild,
-        None,
-        false,
-        ChunkGuildFilter::None,
-        Some(nonce.clone()),
-    );
+    let splasher_role = ctx.data().guild_config(Some(guild)).await?.splasher_role;
 
-    // collect GuildMembersChunk events
-    let mut stream = collector::collect(ctx.serenity_context(), move |event| match event {
-        Event::GuildMembersChunk(event) => {
-            if let Some(chunk_nonce) = &event.nonce
-                && chunk_nonce.as_str() == nonce
-            {
-                let is_final = event.chunk_index == event.chunk_count - 1;
+    let splashers = members::role_members(ctx.serenity_context(), guild, splasher_role).await?;
 
-                let splashers: Vec<_> = event
-                    .members
-                    .iter()
-                    // filter inside collector to avoid cloning values
-                    .filter_map(|m| m.roles.contains(&SPLASHER_ROLE).then_some(m.user.id))
-                    .collect();
-
-                Some((is_final, splashers))
-            } else {
-                None
-            }
-        }
-        _ => None,
-    });
-
-    let mut splashers = Vec::new();
-    while let Some((is_final, members)) = stream.next().await {
-        splashers.extend(members.into_iter());
-        if is_final {
-            break;
-        }
-    }
-
-    let last_splashes = lastsplashed::latest_splash_batch(ctx.http(), &splashers).await?;
-
-    let container_this =
-        CreateComponent::Container(
-            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
-                CreateTextDisplay::new(format!(
-                    "## Most recent splashes
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let last_splashes =
+        lastsplashed::latest_splash_batch(ctx.http(), db, ctx.guild_id(), &splashers).await?;
+    let aways = db
+        .request(GetSplasherAways {
+            now: chrono::Utc::now().timestamp(),
+        })
+        .await??;
+
+    let container_this = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Most recent splashes
 Detected {} splashers.
 ### This month\n{}",
-                    splashers.len(),
-                    last_splashes
-                        .iter()
-                        .filter_map(|(id, &t)| (t > est_start_of_month_relative(0)).then_some(
-                            format!("- {}: <t:{}:D>\n", id.mention(), t.unix_timestamp())
+                splashers.len(),
+                last_splashes
+                    .iter()
+                    .filter_map(
+                        |(id, &t)| (t > est_start_of_month_relative(0)).then_some(format!(
+                            "- {}: {}{}\n",
+                            id.mention(),
+                            discord_timestamp(t, LongDate),
+                            away_note(&aways, *id)
                         ))
-                        .collect::<String>(),
-                )),
-            )])
-            .accent_color(POSITIVE),
-        );
+                    )
+                    .collect::<String>(),
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
     let container_last = CreateComponent::Container(
         CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
             CreateTextDisplay::new(format!(
@@ -110,9 +86,10 @@ Detected {} splashers.
                     .filter_map(|(id, &t)| (t > est_start_of_month_relative(-1)
                         && t < est_start_of_month_relative(0))
                     .then_some(format!(
-                        "- {}: <t:{}:D>\n",
+                        "- {}: {}{}\n",
                         id.mention(),
-                        t.unix_timestamp()
+                        discord_timestamp(t, LongDate),
+                        away_note(&aways, *id)
                     )))
                     .collect::<String>(),
             )),
@@ -126,7 +103,12 @@ Detected {} splashers.
                 last_splashes
                     .iter()
                     .filter_map(|(id, &t)| (t < est_start_of_month_relative(-1)).then_some(
-                        format!("- {}: <t:{}:D>\n", id.mention(), t.unix_timestamp())
+                        format!(
+                            "- {}: {}{}\n",
+                            id.mention(),
+                            discord_timestamp(t, LongDate),
+                            away_note(&aways, *id)
+                        )
                     ))
                     .collect::<String>(),
             )),
@@ -139,8 +121,11 @@ Detected {} splashers.
                 "### >6 months ago or never\n{}",
                 splashers
                     .iter()
-                    .filter_map(|id| (!last_splashes.contains_key(id))
-                        .then_some(format!("- {}\n", id.mention())))
+                    .filter_map(|id| (!last_splashes.contains_key(id)).then_some(format!(
+                        "- {}{}\n",
+                        id.mention(),
+                        away_note(&aways, *id)
+                    )))
                     .collect::<String>()
             )),
         )])
@@ -161,18 +146,20 @@ Detected {} splashers.
     Ok(())
 }
 
+/// Marks splashers who are currently away, so their inactivity isn't mistaken for neglect
+fn away_note(aways: &[SplasherAway], user: UserId) -> String {
+    let now = chrono::Utc::now().timestamp();
+    aways
+        .iter()
+        .find(|away| away.user == user && away.is_active(now))
+        .and_then(|away| Timestamp::from_unix_timestamp(away.end).ok())
+        .map(|end| format!(" *(away until {})*", discord_timestamp(end, LongDate)))
+        .unwrap_or_default()
+}
+
 fn est_start_of_month_relative(offset_months: i32) -> Timestamp {
-    let est = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
-    let now = chrono::Utc::now().with_timezone(&est);
-    let start_of_month = est
-        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
-        .unwrap();
-    let offset_month = match offset_months.cmp(&0) {
-        Ordering::Less => start_of_month - chrono::Months::new(offset_months.unsigned_abs()),
-        Ordering::Greater => start_of_month + chrono::Months::new(offset_months.unsigned_abs()),
-        Ordering::Equal => start_of_month,
-    };
-    Timestamp::from_unix_timestamp(offset_month.timestamp()).unwrap()
+    Timestamp::from_unix_timestamp(calendar::start_of_month_est(&SystemClock, offset_months))
+        .unwrap()
 }
 
 /// View a specific splasher's most recent splash. Can take a while due to rate limits.
@@ -184,18 +171,26 @@ fn est_start_of_month_relative(offset_months: i32) -> Timestamp {
 async fn lastsplashed_get(ctx: Context<'_>, splasher: UserId) -> Result<()> {
     ctx.defer().await?;
 
-    let last_splash = lastsplashed::latest_splash(ctx.http(), splasher).await?;
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let last_splash = lastsplashed::latest_splash(ctx.http(), db, ctx.guild_id(), splasher).await?;
+    let aways = db
+        .request(GetSplasherAways {
+            now: chrono::Utc::now().timestamp(),
+        })
+        .await??;
+    let away = away_note(&aways, splasher);
 
     let text = match last_splash {
         Some(timestamp) => CreateTextDisplay::new(format!(
             "## Most recent splash
-{} last splashed on <t:{}:D>.",
+{}{away} last splashed on {}.",
             splasher.mention(),
-            timestamp.unix_timestamp()
+            discord_timestamp(timestamp, LongDate)
         )),
         None => CreateTextDisplay::new(format!(
             "## Most recent splash
-{} last splashed more than six months ago or has never splashed.",
+{}{away} last splashed more than six months ago or has never splashed.",
             splasher.mention()
         )),
     };
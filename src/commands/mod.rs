@@ -1,8 +1,16 @@
 pub mod baninfo;
+pub mod bingo;
+pub mod config;
+pub mod countsplash;
 pub mod debug;
+pub mod feature;
 pub mod hob;
 pub mod lastsplashed;
+pub mod mystats;
+pub mod policy;
 pub mod register;
 pub mod role;
+pub mod splashes;
 pub mod splashlist;
 pub mod splashreminder;
+pub mod stats;
@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateComponent, CreateContainer, CreateContainerComponent, CreateTextDisplay,
+        MessageFlags, Timestamp,
+        colours::{branding::YELLOW, css::WARNING},
+    },
+};
+
+use crate::hob::db::GetPlayerEntryTitles;
+use crate::role::{db::link::GetLinkedUserByDiscord, request};
+use crate::shared::Context;
+use crate::splashes::{fetch::FetchSplashes, splashlist};
+
+/// View your own linked account, bingo stats, splashes and HoB appearances
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | READ_MESSAGE_HISTORY"
+)]
+pub async fn mystats(ctx: Context<'_>) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    ctx.defer_ephemeral().await?;
+
+    let Some(linked_user) = db
+        .request(GetLinkedUserByDiscord {
+            discord: ctx.author().id,
+        })
+        .await??
+    else {
+        let container = CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(
+                    "## Unlinked
+You haven't linked a Minecraft account yet. Request your roles once to link your account.",
+                ),
+            )])
+            .accent_color(WARNING),
+        );
+        ctx.send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![container])
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let roles =
+        request::player_roles(ctx.serenity_context(), ctx.guild_id(), &linked_user.mc_uuid).await?;
+
+    let hob_titles = db
+        .request(GetPlayerEntryTitles {
+            player: roles.username.clone(),
+        })
+        .await??;
+
+    let mut fetcher = FetchSplashes::from_config(db, ctx.guild_id()).await?;
+    let splash_count = fetcher
+        .splashes_during(
+            ctx.http(),
+            db,
+            splashlist::timestamp_start_of_day_est(1),
+            Timestamp::now(),
+        )
+        .await?
+        .iter()
+        .filter(|s| s.splasher == ctx.author().id)
+        .count();
+
+    let hob_list = if hob_titles.is_empty() {
+        Cow::Borrowed("*None*")
+    } else {
+        Cow::Owned(
+            hob_titles
+                .iter()
+                .map(|t| format!("- {t}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    };
+
+    let extra_text = CreateTextDisplay::new(format!(
+        "### Immortal
+{}
+### Splashes This Month
+**{splash_count}**
+### Hall of Bingo Appearances
+{hob_list}",
+        if roles.immortal { "Yes" } else { "No" },
+    ));
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(roles.to_text_display()),
+            CreateContainerComponent::TextDisplay(extra_text),
+        ])
+        .accent_color(YELLOW),
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use anyhow::Error;
+use poise::{Command, CooldownConfig, serenity_prelude::Permissions};
+
+use crate::shared::BotData;
+
+/// Who a command is available to by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Requires `MANAGE_GUILD`, as bots cannot access the endpoint for role-based permission
+    /// overrides (manual configuration intended)
+    Staff,
+    /// Available to every member
+    Member,
+}
+
+/// Declarative policy for a top-level command, applied to the command and all of its subcommands
+#[derive(Debug, Clone, Copy)]
+pub struct CommandPolicy {
+    pub access: Access,
+    /// Per-user cooldown between invocations
+    pub user_cooldown: Option<Duration>,
+    /// Disabled commands aren't registered at all
+    pub enabled: bool,
+    /// Subcommands available to every member, even though the command itself is staff-only
+    pub public_subcommands: &'static [&'static str],
+    /// Subcommands (at any depth) restricted to staff and exempt from the cooldown, even though the
+    /// command itself is available to every member
+    pub staff_subcommands: &'static [&'static str],
+    /// Subcommands (at any depth) restricted to the bot owners configured via `OWNER_IDS`
+    pub owner_subcommands: &'static [&'static str],
+}
+
+impl CommandPolicy {
+    /// Used for every command without an entry in [`POLICIES`]
+    pub const DEFAULT: Self = Self {
+        access: Access::Staff,
+        user_cooldown: None,
+        enabled: true,
+        public_subcommands: &[],
+        staff_subcommands: &[],
+        owner_subcommands: &[],
+    };
+
+    const fn member() -> Self {
+        Self {
+            access: Access::Member,
+            ..Self::DEFAULT
+        }
+    }
+
+    const fn user_cooldown(self, secs: u64) -> Self {
+        Self {
+            user_cooldown: Some(Duration::from_secs(secs)),
+            ..self
+        }
+    }
+
+    const fn public_subcommands(self, subcommands: &'static [&'static str]) -> Self {
+        Self {
+            public_subcommands: subcommands,
+            ..self
+        }
+    }
+
+    const fn staff_subcommands(self, subcommands: &'static [&'static str]) -> Self {
+        Self {
+            staff_subcommands: subcommands,
+            ..self
+        }
+    }
+
+    const fn owner_subcommands(self, subcommands: &'static [&'static str]) -> Self {
+        Self {
+            owner_subcommands: subcommands,
+            ..self
+        }
+    }
+}
+
+/// Policies keyed by the command's (non-localized) name. Subcommands with an entry of their own,
+/// keyed by their qualified name like `bingo info`, use it instead of their parent's policy.
+// NOTE: keep this as the single source of truth for permissions and cooldowns, rather than setting
+// them via attributes on the commands themselves
+const POLICIES: &[(&str, CommandPolicy)] = &[
+    // scans the splashes channel and queries the Hypixel API on every invocation
+    ("mystats", CommandPolicy::member().user_cooldown(30)),
+    // aggregates the caches of every linked member and renders a chart
+    ("stats", CommandPolicy::member().user_cooldown(60)),
+    // queries the Hypixel API and renders the card on every invocation
+    ("bingo", CommandPolicy::member().user_cooldown(30)),
+    // only queries the Hypixel API once the stored bingo has ended
+    ("bingo info", CommandPolicy::member().user_cooldown(5)),
+    // lets splashers verify their own counts, scanning up to 6 months of splashes, while the
+    // availability overview and splasher management stay staff-only
+    (
+        "splashes",
+        CommandPolicy::member()
+            .user_cooldown(60)
+            .staff_subcommands(&["inactivity", "list", "onboarding"]),
+    ),
+    // chunks the entire member list and scans months of splashes
+    ("lastsplashed", CommandPolicy::DEFAULT.user_cooldown(60)),
+    // read-only view of the HoB, while managing it stays staff-only
+    (
+        "hob",
+        CommandPolicy::DEFAULT.public_subcommands(&["browse"]),
+    ),
+    // direct access to the database and metrics of all guilds, beyond what staff of a single guild
+    // should be able to do
+    (
+        "debug",
+        CommandPolicy::DEFAULT.owner_subcommands(&[
+            "stats",
+            "query-cache",
+            "vacuum",
+            "backup",
+            "metrics",
+        ]),
+    ),
+];
+
+pub fn policy_for(name: &str) -> CommandPolicy {
+    own_policy(name).unwrap_or(CommandPolicy::DEFAULT)
+}
+
+fn own_policy(qualified_name: &str) -> Option<CommandPolicy> {
+    POLICIES
+        .iter()
+        .find(|(command, _)| *command == qualified_name)
+        .map(|(_, policy)| *policy)
+}
+
+/// Applies each command's policy, dropping disabled commands
+pub fn apply(commands: Vec<Command<BotData, Error>>) -> Vec<Command<BotData, Error>> {
+    commands
+        .into_iter()
+        .filter_map(|mut command| {
+            let policy = policy_for(&command.name);
+            if !policy.enabled {
+                return None;
+            }
+            let name = command.name.to_string();
+            apply_to(&mut command, policy, &name);
+            Some(command)
+        })
+        .collect()
+}
+
+fn apply_to(command: &mut Command<BotData, Error>, policy: CommandPolicy, qualified_name: &str) {
+    command.default_member_permissions = match policy.access {
+        Access::Staff => Permissions::MANAGE_GUILD,
+        Access::Member => Permissions::empty(),
+    };
+
+    // Discord only supports default permissions for top-level commands, so the command has to be
+    // visible to everyone, with its other subcommands checked on invocation instead
+    let has_public_subcommands = !policy.public_subcommands.is_empty();
+    if has_public_subcommands {
+        command.default_member_permissions = Permissions::empty();
+    }
+
+    if let Some(cooldown) = policy.user_cooldown {
+        *command
+            .cooldown_config
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = CooldownConfig {
+            user: Some(cooldown),
+            ..Default::default()
+        };
+    }
+
+    // cooldowns are checked on the invoked subcommand, not the parent
+    for subcommand in &mut command.subcommands {
+        let qualified_name = format!("{qualified_name} {}", subcommand.name);
+        if let Some(own) = own_policy(&qualified_name) {
+            // default permissions only apply to top-level commands
+            if own.access == Access::Staff {
+                subcommand.required_permissions |= Permissions::MANAGE_GUILD;
+            }
+            apply_to(subcommand, own, &qualified_name);
+            continue;
+        }
+
+        if has_public_subcommands
+            && !policy
+                .public_subcommands
+                .iter()
+                .any(|name| *name == subcommand.name)
+        {
+            subcommand.required_permissions |= Permissions::MANAGE_GUILD;
+        }
+        if policy
+            .owner_subcommands
+            .iter()
+            .any(|name| *name == subcommand.name)
+        {
+            subcommand.owners_only = true;
+        }
+        if policy
+            .staff_subcommands
+            .iter()
+            .any(|name| *name == subcommand.name)
+        {
+            subcommand.required_permissions |= Permissions::MANAGE_GUILD;
+            apply_to(
+                subcommand,
+                CommandPolicy {
+                    access: Access::Staff,
+                    user_cooldown: None,
+                    public_subcommands: &[],
+                    staff_subcommands: &[],
+                    ..policy
+                },
+                &qualified_name,
+            );
+            continue;
+        }
+        apply_to(
+            subcommand,
+            CommandPolicy {
+                public_subcommands: &[],
+                ..policy
+            },
+            &qualified_name,
+        );
+    }
+}
@@ -1,43 +1,100 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use anyhow::{Context as _, Result, anyhow, bail};
 use poise::{
-    CreateReply,
+    ChoiceParameter, CreateReply,
     serenity_prelude::{
-        ButtonStyle, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
-        CreateContainerComponent, CreateMessage, CreateSection, CreateSectionAccessory,
-        CreateSectionComponent, CreateTextDisplay, EditMessage, GenericChannelId, Member,
-        Mentionable as _, Message, MessageFlags, ReactionType, UserId,
+        Attachment, ButtonStyle, Colour, CreateActionRow, CreateAllowedMentions, CreateAttachment,
+        CreateButton, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateInteractionResponse, CreateMessage, CreateSection, CreateSectionAccessory,
+        CreateSectionComponent, CreateSeparator, CreateTextDisplay, EditMessage, Event,
+        GenericChannelId, Interaction, Member, Mentionable as _, Message, MessageFlags,
+        ReactionType, Role, RoleId, Timestamp, User, UserId, collector,
         colours::{
+            branding::YELLOW,
             css::{POSITIVE, WARNING},
             roles::BLUE,
         },
+        futures::StreamExt as _,
         small_fixed_array::FixedString,
     },
 };
-use tokio::sync::{Mutex, Notify};
+use tokio::{
+    sync::{Mutex, Notify},
+    task::JoinHandle,
+};
+use tracing::{Instrument as _, warn};
 
 use crate::config::{MANUAL_ROLE_CHANNEL, MENU_TIMEOUT_SECS};
-use crate::error::UserError;
+use crate::db::DbHandle;
+use crate::error::{self, UserError};
+use crate::hypixel_api::BingoGoalProgress;
 use crate::role::{
-    db::link::{
-        GetLinkedUserByDiscord, GetLinkedUserByMinecraft, RemoveLinkedUserByDiscord,
-        UpdateLinkedUser,
+    config_export,
+    db::{
+        application::GetManualApplications,
+        audit::{GetRoleAuditEntries, GetRoleUpdateCounts},
+        cache::CachedCompletions,
+        link::{
+            CountLinkedUsers, GetAllLinkedUsers, GetBulkUpdateProgress, GetLinkLastVerified,
+            GetLinkedUserByDiscord, GetLinkedUserByMinecraft, GetLinkedUsersByDiscord,
+            GetLinkedUsersChunk, ImportLinkedUsers, RemoveLinkedUserByDiscord, RemoveLinkedUsers,
+            SetBulkUpdateProgress, UpdateLinkedUser,
+        },
+        role_config::{
+            ImportRoleConfig, SetHypixelGuildMapping, SetLinkVerificationConfig,
+            SetRejoinRestoreConfig, SetRoleAutoCreateConfig,
+        },
+    },
+    links,
+    menu::{self, RoleConfigSession, RoleConfigState},
+    network_bingo::{self, DetectionRule},
+    request::{self, RoleRequestStatus},
+    types::{
+        ApplicationStatus, BulkUpdateProgress, HypixelGuildMapping, LinkVerificationConfig,
+        LinkedUser, RejoinRestoreConfig, RoleAutoCreateConfig, RoleMapping, RoleMappingKindRaw,
+        RoleUpdateOutcome, RoleUpdateTrigger, RoleUpdateTriggerKind, role_update_sources,
     },
-    menu::{RoleConfigSession, RoleConfigState},
-    request,
-    types::{LinkedUser, RoleMappingKindRaw},
 };
 use crate::shared::{
     Context,
-    db::SetIsNetworkBingo,
-    menu::{navigation::GenerateMenu as _, timeout},
+    db::{GetBingoData, SetIsNetworkBingo},
+    dry_run,
+    interaction::custom_id::{self, Namespace},
+    members,
+    menu::{MenuMessage, confirm, generate_id, navigation::GenerateMenu as _, timeout},
+    time::{TimestampStyle, discord_timestamp},
+    types::{BitSet, MinecraftIdent},
 };
 
 #[poise::command(
     slash_command,
     subcommand_required,
-    subcommands("send", "force", "config", "query", "network_bingo")
+    subcommands(
+        "send",
+        "force",
+        "config",
+        "config_import",
+        "query",
+        "stats",
+        "network_bingo",
+        "network_bingo_register",
+        "hypixel_guild",
+        "rejoin",
+        "verification",
+        "auto_create",
+        "audit",
+        "links",
+        "applications"
+    )
 )]
 pub async fn rolerequest(_ctx: Context<'_>) -> Result<()> {
     unreachable!("This shouldn't be possible to invoke");
@@ -55,14 +112,21 @@ async fn send(
     channel: Option<GenericChannelId>,
     #[description = "ID of existing message to edit (must belong to bot)"] edit: Option<Message>,
 ) -> Result<()> {
-    let begin_button = CreateButton::new("role:request:begin")
-        .label("Request Roles")
-        .style(ButtonStyle::Primary);
-    let faq_button = CreateButton::new("role:request:faq")
+    let begin_button =
+        CreateButton::new(custom_id::encode(Namespace::Role, &[&"request", &"begin"]))
+            .label("Request Roles")
+            .style(ButtonStyle::Primary);
+    let faq_button = CreateButton::new(custom_id::encode(Namespace::Role, &[&"request", &"faq"]))
         .emoji(ReactionType::Unicode(FixedString::from_static_trunc("ℹ️")))
         // .emoji('❓')
         .label("Common Questions")
         .style(ButtonStyle::Secondary);
+    let preview_button = CreateButton::new(custom_id::encode(
+        Namespace::Role,
+        &[&"request", &"preview"],
+    ))
+    .label("Preview Changes")
+    .style(ButtonStyle::Secondary);
 
     // TODO: add additional unlink button here?
     let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
@@ -92,8 +156,9 @@ Awarded for completing a bingo card without dying a single time
         CreateSectionAccessory::Button(begin_button),
     ));
 
-    let faq_row =
-        CreateContainerComponent::ActionRow(CreateActionRow::Buttons(vec![faq_button].into()));
+    let faq_row = CreateContainerComponent::ActionRow(CreateActionRow::Buttons(
+        vec![preview_button, faq_button].into(),
+    ));
 
     let credit = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
         "-# made by <:bossflea:1213572347521536080>",
@@ -113,14 +178,24 @@ Awarded for completing a bingo card without dying a single time
                 bail!(UserError(anyhow!("Provided message doesn't belong to bot")))
             }
 
-            message
+            let edited = message
                 .edit(
                     ctx,
                     EditMessage::new()
                         .flags(MessageFlags::IS_COMPONENTS_V2)
                         .components(vec![container]),
                 )
-                .await?
+                .await
+                .map_err(anyhow::Error::from);
+            // the message may have been deleted after it was resolved
+            if let Err(err) = edited {
+                if error::is_unknown_message(&err) {
+                    bail!(UserError(anyhow!(
+                        "The provided message was deleted, omit `edit` to send a new kiosk instead"
+                    )));
+                }
+                return Err(err);
+            }
         }
         None => {
             channel
@@ -166,7 +241,7 @@ async fn config(ctx: Context<'_>) -> Result<()> {
     let mut initial_state = RoleConfigState::new(RoleMappingKindRaw::BingoRank, 0);
 
     let menu = initial_state
-        .generate(&ctx.data().db_handle, menu_id)
+        .generate(ctx.data().db_for(ctx.guild_id()), menu_id)
         .await?;
 
     let message_handle = ctx.send(menu.into_reply()).await?.into_message().await?;
@@ -176,6 +251,7 @@ async fn config(ctx: Context<'_>) -> Result<()> {
     let session = RoleConfigSession {
         menu_id,
         owner,
+        guild_id: ctx.guild_id(),
         state: initial_state,
         channel_id: message_handle.channel_id,
         message_id: message_handle.id,
@@ -199,6 +275,147 @@ async fn config(ctx: Context<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Exports of a full role config stay far below this
+const MAX_CONFIG_IMPORT_BYTES: u32 = 1024 * 1024;
+/// Bindings listed in the import preview, so that it stays within the message length limit
+const MAX_PREVIEW_MAPPINGS: usize = 20;
+
+/// Import role bindings and patterns from a file exported with the config menu
+#[poise::command(slash_command, rename = "config-import", guild_only)]
+async fn config_import(
+    ctx: Context<'_>,
+    #[description = "JSON file exported from another server's config menu"] file: Attachment,
+) -> Result<()> {
+    if file.size > MAX_CONFIG_IMPORT_BYTES {
+        bail!(UserError(anyhow!(
+            "The file is too large to be a config export (max {} KB)",
+            MAX_CONFIG_IMPORT_BYTES / 1024
+        )));
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let contents = String::from_utf8(file.download().await?)
+        .map_err(|_| UserError(anyhow!("The file isn't valid UTF-8 text")))?;
+    let import = config_export::parse(&contents)?;
+
+    let guild = ctx.guild_id().context("Expected guild-only command")?;
+    let guild_roles: Vec<Role> = guild.roles(ctx.http()).await?.into_iter().collect();
+
+    let total = import.mappings.len();
+    let mappings: Vec<RoleMapping> = import
+        .mappings
+        .iter()
+        .filter_map(|mapping| mapping.resolve(&guild_roles))
+        .collect();
+
+    if mappings.is_empty() && import.patterns.is_none() {
+        bail!(UserError(anyhow!(
+            "None of the {total} bindings of the file match a role of this server"
+        )));
+    }
+
+    // dry run: nothing is written until the preview is confirmed
+    let id_prefix = format!("configimport:{}", generate_id());
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(config_import_preview(
+                    &mappings,
+                    total,
+                    import.patterns.is_some(),
+                    &id_prefix,
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+
+    confirm::await_confirmation(
+        ctx,
+        handle,
+        &id_prefix,
+        "Import",
+        "Nothing was imported.",
+        || async move {
+            let imported = mappings.len();
+            ctx.data()
+                .db_for(ctx.guild_id())
+                .request(ImportRoleConfig {
+                    mappings,
+                    patterns: import.patterns,
+                })
+                .await??;
+            // open config menus would otherwise keep showing the previous bindings
+            // NOTE: generated menu IDs are never 0, so no menu is skipped
+            menu::refresh_other_sessions(ctx.serenity_context(), 0, ctx.guild_id());
+            Ok(confirm::outcome(
+                &format!("## Imported Successfully\n**{imported}** role bindings were imported."),
+                POSITIVE,
+            ))
+        },
+    )
+    .await
+}
+
+fn config_import_preview(
+    mappings: &[RoleMapping],
+    total: usize,
+    replaces_patterns: bool,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let skipped = total - mappings.len();
+    let preview: String = mappings
+        .iter()
+        .take(MAX_PREVIEW_MAPPINGS)
+        .map(|mapping| format!("{}\n", mapping.to_list_entry()))
+        .collect();
+    let text = CreateTextDisplay::new(format!(
+        "## Import Preview
+**{}** role bindings would be imported:
+{preview}{}{}{}-# Nothing has been imported yet, confirm to import everything at once.",
+        mappings.len(),
+        if mappings.len() > MAX_PREVIEW_MAPPINGS {
+            format!("-# …and {} more\n", mappings.len() - MAX_PREVIEW_MAPPINGS)
+        } else {
+            String::new()
+        },
+        if skipped > 0 {
+            format!(
+                "-# {skipped} bindings are skipped, as no role of this server matches their ID or name.\n"
+            )
+        } else {
+            String::new()
+        },
+        if replaces_patterns {
+            "-# The auto-detection patterns are replaced by those of the file.\n"
+        } else {
+            ""
+        },
+    ));
+
+    let buttons = CreateActionRow::Buttons(
+        vec![
+            CreateButton::new(format!("{id_prefix}:confirm"))
+                .label("Import")
+                .style(ButtonStyle::Success),
+            CreateButton::new(format!("{id_prefix}:cancel"))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ]
+        .into(),
+    );
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text),
+            CreateContainerComponent::ActionRow(buttons),
+        ])
+        .accent_color(YELLOW),
+    )]
+}
+
 /// Set whether the bot considers the latest Network Bingo as currently ongoing. This affects caching.
 #[poise::command(slash_command, rename = "networkbingo")]
 async fn network_bingo(
@@ -206,7 +423,7 @@ async fn network_bingo(
     #[description = "Whether a Network Bingo Event is currently ongoing"] active: bool,
 ) -> Result<()> {
     ctx.data()
-        .db_handle
+        .db_for(ctx.guild_id())
         .request(SetIsNetworkBingo { is_active: active })
         .await??;
 
@@ -231,148 +448,1349 @@ async fn network_bingo(
     Ok(())
 }
 
+/// Register a new Network Bingo event, or rename one that was detected automatically
+#[poise::command(slash_command, rename = "networkbingo-register")]
+async fn network_bingo_register(
+    ctx: Context<'_>,
+    #[description = "Season key on the API, e.g. `halloween`"] season: String,
+    #[description = "Year key on the API, e.g. `2025`"] year: String,
+    #[description = "Displayed name (defaults to e.g. 'Halloween Bingo 2025')"] name: Option<
+        String,
+    >,
+    #[description = "How completions of new events are detected (defaults to blackouts on every difficulty)"]
+    rule: Option<DetectionRule>,
+) -> Result<()> {
+    // the registry is shared by all guilds, as it mirrors Hypixel's events
+    let event = network_bingo::register(
+        &ctx.data().db_handle,
+        &season,
+        &year,
+        name,
+        rule.unwrap_or(DetectionRule::AllDifficulties),
+    )
+    .await?;
+
+    let response = CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!(
+                    "## Successfully Registered Network Bingo
+'{}' (ID {}) is detected from `seasonal.{}.{}` of the player endpoint and can now be bound to a role via `/rolerequest config`.",
+                    event.name,
+                    event.bingo.id(),
+                    event.season,
+                    event.year
+                )),
+            )])
+            .accent_colour(POSITIVE),
+        )])
+        .ephemeral(true);
+
+    ctx.send(response).await?;
+    Ok(())
+}
+
+/// Grant a role to all members of a Hypixel guild during role requests. Omit both options to remove.
+#[poise::command(slash_command, rename = "hypixelguild")]
+async fn hypixel_guild(
+    ctx: Context<'_>,
+    #[description = "Name of the Hypixel guild whose members should receive the role"]
+    guild: Option<String>,
+    #[description = "Role to grant to members of the guild"] role: Option<Role>,
+) -> Result<()> {
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    let message = match (guild, role) {
+        (Some(guild), Some(role)) => {
+            let Some((guild_id, guild_name)) = data.api_handle.guild_by_name(&guild).await? else {
+                bail!(UserError(anyhow!(
+                    "No Hypixel guild named '{guild}' exists"
+                )));
+            };
+
+            db.request(SetHypixelGuildMapping {
+                mapping: Some(HypixelGuildMapping {
+                    guild_id,
+                    guild_name: guild_name.clone(),
+                    role: role.id,
+                }),
+            })
+            .await??;
+
+            format!(
+                "Members of **{guild_name}** will now receive {} when requesting roles.",
+                role.mention()
+            )
+        }
+        (None, None) => {
+            db.request(SetHypixelGuildMapping { mapping: None })
+                .await??;
+            "Hypixel guild membership no longer grants a role.".to_string()
+        }
+        _ => bail!(UserError(anyhow!(
+            "Both a guild and a role are required, or neither to remove the mapping"
+        ))),
+    };
+
+    let response = CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!("## Successfully Updated Guild Role\n{message}")),
+            )])
+            .accent_colour(POSITIVE),
+        )])
+        .ephemeral(true);
+
+    ctx.send(response).await?;
+    Ok(())
+}
+
+/// Restore the roles of linked users when they rejoin the server
+#[poise::command(slash_command)]
+async fn rejoin(
+    ctx: Context<'_>,
+    #[description = "Whether roles should be restored when linked users rejoin"] enabled: bool,
+    #[description = "Minutes to wait after the user joins (defaults to 1)"] delay: Option<u32>,
+    #[description = "Where to log restored roles for staff"] log_channel: Option<GenericChannelId>,
+) -> Result<()> {
+    let config = enabled.then(|| RejoinRestoreConfig {
+        delay_secs: u64::from(delay.unwrap_or(1)) * 60,
+        log_channel,
+    });
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetRejoinRestoreConfig { config })
+        .await??;
+
+    let message = match config {
+        Some(config) => format!(
+            "Linked users will have their roles restored {} minute(s) after rejoining.{}",
+            config.delay_secs / 60,
+            config
+                .log_channel
+                .map(|channel| format!(" Restored roles are logged in {}.", channel.mention()))
+                .unwrap_or_default()
+        ),
+        None => "Roles will no longer be restored when linked users rejoin.".to_string(),
+    };
+
+    let response = CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!("## Successfully Updated Rejoin Roles\n{message}")),
+            )])
+            .accent_colour(POSITIVE),
+        )])
+        .ephemeral(true);
+
+    ctx.send(response).await?;
+    Ok(())
+}
+
+/// Periodically check that links still match the Discord account set on Hypixel
 #[poise::command(
     slash_command,
-    subcommand_required,
-    subcommands("force_update", "force_link", "force_unlink")
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES"
 )]
-async fn force(_ctx: Context<'_>) -> Result<()> {
-    unreachable!("This shouldn't be possible to invoke");
+async fn verification(
+    ctx: Context<'_>,
+    #[description = "Whether links should be re-verified"] enabled: bool,
+    #[description = "Where to report mismatched links (required when enabling)"]
+    #[channel_types("Text")]
+    channel: Option<GenericChannelId>,
+    #[description = "Whether mismatched links should be removed instead of only reported"]
+    auto_unlink: Option<bool>,
+    #[description = "Days between checks of the same link (defaults to 30)"]
+    #[min = 1]
+    interval: Option<u32>,
+) -> Result<()> {
+    let config = match (enabled, channel) {
+        (true, Some(channel)) => Some(LinkVerificationConfig {
+            channel,
+            auto_unlink: auto_unlink.unwrap_or(false),
+            interval_days: interval.unwrap_or(30),
+        }),
+        (true, None) => bail!(UserError(anyhow!(
+            "A channel for reporting mismatched links is required"
+        ))),
+        (false, _) => None,
+    };
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetLinkVerificationConfig { config })
+        .await??;
+
+    let message = match config {
+        Some(config) => format!(
+            "Links will be re-verified every {} day(s). Mismatched links are {} in {}.",
+            config.interval_days,
+            if config.auto_unlink {
+                "removed and reported"
+            } else {
+                "reported"
+            },
+            config.channel.mention()
+        ),
+        None => "Links will no longer be re-verified.".to_string(),
+    };
+
+    let response = CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!(
+                    "## Successfully Updated Link Verification\n{message}"
+                )),
+            )])
+            .accent_colour(POSITIVE),
+        )])
+        .ephemeral(true);
+
+    ctx.send(response).await?;
+    Ok(())
 }
 
-/// Update another user's roles (use '/rolerequest inspect stats' to check stats without updating roles)
+/// Create missing roles whose names follow the configured patterns instead of skipping them
 #[poise::command(
     slash_command,
-    rename = "update",
+    rename = "autocreate",
     required_bot_permissions = "MANAGE_ROLES"
 )]
-async fn force_update(
+async fn auto_create(
     ctx: Context<'_>,
-    #[description = "Whose roles to update"] user: Member,
+    #[description = "Whether missing roles should be created during role requests"] enabled: bool,
+    #[description = "Hex colour of created roles, e.g. #F1C40F (defaults to no colour)"]
+    colour: Option<String>,
 ) -> Result<()> {
-    let linked_user = ctx
-        .data()
-        .db_handle
-        .request(GetLinkedUserByDiscord {
-            discord: user.user.id,
+    let colour = colour
+        .map(|input| {
+            u32::from_str_radix(input.trim().trim_start_matches('#'), 16)
+                .ok()
+                .filter(|rgb| *rgb <= 0xFFFFFF)
+                .map(Colour::new)
+                .context(UserError(anyhow!(
+                    "Invalid colour '{input}', expected a hex colour like `#F1C40F`"
+                )))
         })
-        .await??
-        .context(UserError(anyhow!("User hasn't linked their accounts")))?;
+        .transpose()?;
+    let config = enabled.then_some(RoleAutoCreateConfig { colour });
 
-    ctx.defer().await?;
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetRoleAutoCreateConfig { config })
+        .await??;
 
-    let role_status =
-        request::update_roles(ctx.serenity_context(), &linked_user.mc_uuid, &user).await?;
+    let message = match config {
+        Some(config) => format!(
+            "Roles matching the configured patterns will be created when a member qualifies for a role that doesn't exist yet{}. Created roles never have any permissions.",
+            config
+                .colour
+                .map(|colour| format!(", using the colour `#{}`", colour.hex()))
+                .unwrap_or_default()
+        ),
+        None => "Missing roles will no longer be created automatically.".to_string(),
+    };
 
-    let container = role_status.to_diff_message(Some(&user.user.id));
+    let response = CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!(
+                    "## Successfully Updated Automatic Role Creation\n{message}"
+                )),
+            )])
+            .accent_colour(POSITIVE),
+        )])
+        .ephemeral(true);
+
+    ctx.send(response).await?;
+    Ok(())
+}
+
+const MAX_AUDIT_ENTRIES: u32 = 25;
+/// Applications listed by `/rolerequest applications`, so that the list stays within Discord's
+/// message length limit
+const MAX_LISTED_APPLICATIONS: u32 = 25;
+
+/// View a user's recent role changes made by role updates
+#[poise::command(slash_command)]
+async fn audit(
+    ctx: Context<'_>,
+    #[description = "Whose role changes to view"] user: User,
+    #[description = "Number of changes to show (defaults to 10)"]
+    #[min = 1]
+    #[max = 25]
+    limit: Option<u32>,
+) -> Result<()> {
+    let entries = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetRoleAuditEntries {
+            user: user.id,
+            limit: limit.unwrap_or(10).min(MAX_AUDIT_ENTRIES),
+        })
+        .await??;
+
+    let role_list = |roles: &[RoleId]| {
+        roles
+            .iter()
+            .map(|role| role.mention().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let text = if entries.is_empty() {
+        format!(
+            "## Role Audit Log\nNo role changes have been recorded for {}.",
+            user.mention()
+        )
+    } else {
+        let mut list = String::new();
+        for entry in &entries {
+            let timestamp = Timestamp::from_unix_timestamp(entry.timestamp)?;
+            list += &format!(
+                "- {} – {}\n",
+                discord_timestamp(timestamp, TimestampStyle::ShortDateTime),
+                entry.trigger,
+            );
+            if !entry.added.is_empty() {
+                list += &format!("  - Added: {}\n", role_list(&entry.added));
+            }
+            if !entry.removed.is_empty() {
+                list += &format!("  - Removed: {}\n", role_list(&entry.removed));
+            }
+        }
+        format!(
+            "## Role Audit Log\nRecent role changes of {}:\n{list}",
+            user.mention()
+        )
+    };
 
     ctx.send(
-        CreateReply::default()
+        CreateReply::new()
             .flags(MessageFlags::IS_COMPONENTS_V2)
-            .components(vec![container]),
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_colour(YELLOW),
+            )])
+            .ephemeral(true),
     )
     .await?;
 
     Ok(())
 }
 
-/// Override another user's linked minecraft account
-#[poise::command(slash_command, rename = "link")]
-async fn force_link(
+/// View the queue of applications posted in the manual role channel
+#[poise::command(slash_command)]
+async fn applications(
     ctx: Context<'_>,
-    #[description = "Whose linked account to update"] discord: UserId,
-    #[description = "Minecraft account to link"] minecraft: String,
+    #[description = "Only list applications with this status (defaults to open and claimed)"]
+    status: Option<ApplicationStatus>,
 ) -> Result<()> {
-    ctx.defer().await?;
-
-    let uuid = ctx.data().api_handle.uuid(&minecraft).await?;
-
-    ctx.data()
-        .db_handle
-        .request(UpdateLinkedUser {
-            user: LinkedUser::new(discord, uuid),
+    let applications = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetManualApplications {
+            status,
+            limit: MAX_LISTED_APPLICATIONS,
         })
         .await??;
 
-    let container = CreateComponent::Container(
-        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
-            CreateTextDisplay::new(format!(
-                "## Linked Successfully
-Linked `{minecraft}` to {}, discarding any existing links for either account.",
-                discord.mention()
-            )),
-        )])
-        .accent_color(POSITIVE),
-    );
+    let handled_by = |(user, at): (UserId, i64)| -> Result<String> {
+        Ok(format!(
+            "{} {}",
+            user.mention(),
+            discord_timestamp(
+                Timestamp::from_unix_timestamp(at)?,
+                TimestampStyle::Relative
+            )
+        ))
+    };
+
+    let text = if applications.is_empty() {
+        format!(
+            "## Manual Role Applications\nThere are no matching applications in {}.",
+            MANUAL_ROLE_CHANNEL.mention()
+        )
+    } else {
+        let mut list = String::new();
+        for application in &applications {
+            let link = application
+                .message_id
+                .link(MANUAL_ROLE_CHANNEL, ctx.guild_id());
+            let posted = discord_timestamp(
+                Timestamp::from_unix_timestamp(application.posted_at)?,
+                TimestampStyle::Relative,
+            );
+            let status = match (application.claimed, application.resolved) {
+                (_, Some(resolved)) => format!("resolved by {}", handled_by(resolved)?),
+                (Some(claimed), None) => format!("claimed by {}", handled_by(claimed)?),
+                (None, None) => "**open**".to_string(),
+            };
+            list += &format!(
+                "- {link} by {} ({posted}): {status}\n",
+                application.author.mention()
+            );
+        }
+        let order = if status == Some(ApplicationStatus::Resolved) {
+            "most recently resolved first"
+        } else {
+            "oldest first"
+        };
+        format!(
+            "## Manual Role Applications\nApplications in {}, {order}:\n{list}",
+            MANUAL_ROLE_CHANNEL.mention()
+        )
+    };
 
     ctx.send(
-        CreateReply::default()
+        CreateReply::new()
             .flags(MessageFlags::IS_COMPONENTS_V2)
-            .components(vec![container]),
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_colour(YELLOW),
+            )])
+            .ephemeral(true),
     )
     .await?;
 
     Ok(())
 }
 
-/// Unlink another user's minecraft account
-#[poise::command(slash_command, rename = "unlink")]
-async fn force_unlink(
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("links_export", "links_import")
+)]
+async fn links(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+#[derive(Default, ChoiceParameter)]
+enum LinksFormat {
+    #[default]
+    #[name = "JSON"]
+    Json,
+    #[name = "CSV"]
+    Csv,
+}
+
+/// Export all linked accounts as a file, e.g. as a backup
+#[poise::command(slash_command, rename = "export")]
+async fn links_export(
     ctx: Context<'_>,
-    #[description = "Whose account to unlink"] user: UserId,
+    #[description = "File format of the export (defaults to JSON)"] format: Option<LinksFormat>,
 ) -> Result<()> {
-    ctx.defer().await?;
+    ctx.defer_ephemeral().await?;
 
-    let removed_user = ctx
+    let linked_users = ctx
         .data()
-        .db_handle
-        .request(RemoveLinkedUserByDiscord { discord: user })
-        .await??
-        .context(UserError(anyhow!("User hasn't linked their accounts",)))?;
+        .db_for(ctx.guild_id())
+        .request(GetAllLinkedUsers)
+        .await??;
 
-    let username = ctx
-        .data()
-        .api_handle
-        .username(&removed_user.mc_uuid)
-        .await?;
+    let (contents, extension) = match format.unwrap_or_default() {
+        LinksFormat::Json => (links::to_json(&linked_users), "json"),
+        LinksFormat::Csv => (links::to_csv(&linked_users), "csv"),
+    };
+    let file = CreateAttachment::bytes(
+        contents.into_bytes(),
+        format!(
+            "linked_accounts_{}.{extension}",
+            chrono::Utc::now().format("%Y-%m-%d")
+        ),
+    );
 
     let container = CreateComponent::Container(
         CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
             CreateTextDisplay::new(format!(
-                "## Unlinked Successfully
-Unlinked `{username}` from {}.",
-                user.mention()
+                "## Exported Linked Accounts
+The attached file contains all {} linked accounts.",
+                linked_users.len()
             )),
         )])
         .accent_color(POSITIVE),
     );
 
     ctx.send(
-        CreateReply::default()
+        CreateReply::new()
             .flags(MessageFlags::IS_COMPONENTS_V2)
-            .components(vec![container]),
+            .components(vec![container])
+            .attachment(file)
+            .ephemeral(true),
     )
     .await?;
 
     Ok(())
 }
 
-/// Check the link status and stats of any Discord/Minecraft account
-#[poise::command(slash_command)]
-async fn query(
+/// Exports of every linked account stay far below this
+const MAX_LINKS_IMPORT_BYTES: u32 = 4 * 1024 * 1024;
+
+/// Import linked accounts from a JSON or CSV file, e.g. created by `/rolerequest links export`
+#[poise::command(slash_command, rename = "import")]
+async fn links_import(
     ctx: Context<'_>,
-    #[description = "By Discord account"] discord: Option<UserId>,
-    #[description = "By Minecraft username/UUID"] minecraft: Option<String>,
+    #[description = "JSON or CSV file with Discord IDs and Minecraft UUIDs"] file: Attachment,
 ) -> Result<()> {
-    let db = &ctx.data().db_handle;
-    let api = &ctx.data().api_handle;
+    if file.size > MAX_LINKS_IMPORT_BYTES {
+        bail!(UserError(anyhow!(
+            "The file is too large to be a link export (max {} KB)",
+            MAX_LINKS_IMPORT_BYTES / 1024
+        )));
+    }
 
-    ctx.defer().await?;
+    ctx.defer_ephemeral().await?;
 
-    let (discord, uuid, username) = match (discord, minecraft) {
-        (None, None) => {
-            let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
-                "## Insufficient arguments
+    let contents = String::from_utf8(file.download().await?)
+        .map_err(|_| UserError(anyhow!("The file isn't valid UTF-8 text")))?;
+    let imported_users = links::parse(&contents)?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let existing = db.request(GetAllLinkedUsers).await??;
+    let existing_discord: HashSet<_> = existing.iter().map(|user| user.discord).collect();
+    let existing_uuids: HashSet<_> = existing.iter().map(|user| user.mc_uuid.as_str()).collect();
+
+    let total = imported_users.len();
+    // existing links are never overwritten, as they were most likely verified more recently
+    let new_users: Vec<LinkedUser> = imported_users
+        .into_iter()
+        .filter(|user| {
+            !existing_discord.contains(&user.discord)
+                && !existing_uuids.contains(user.mc_uuid.as_str())
+        })
+        .collect();
+
+    if new_users.is_empty() {
+        bail!(UserError(anyhow!(
+            "All {total} links of the file conflict with already linked accounts"
+        )));
+    }
+
+    // dry run: nothing is written until the preview is confirmed
+    let id_prefix = format!("linksimport:{}", generate_id());
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(links_import_preview(new_users.len(), total, &id_prefix))
+                .ephemeral(true),
+        )
+        .await?;
+
+    confirm::await_confirmation(
+        ctx,
+        handle,
+        &id_prefix,
+        "Import",
+        "No links were imported.",
+        || async move {
+            let imported = db.request(ImportLinkedUsers { users: new_users }).await??;
+            Ok(confirm::outcome(
+                &format!("## Imported Successfully\n**{imported}** links were imported."),
+                POSITIVE,
+            ))
+        },
+    )
+    .await
+}
+
+fn links_import_preview(
+    new_links: usize,
+    total: usize,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let skipped = total - new_links;
+    let text = CreateTextDisplay::new(format!(
+        "## Import Preview
+**{new_links}** links would be imported.
+{}-# Nothing has been imported yet, confirm to import all links at once.",
+        if skipped > 0 {
+            format!(
+                "-# {skipped} links are skipped, as their Discord or Minecraft account is already linked.\n"
+            )
+        } else {
+            String::new()
+        },
+    ));
+
+    let buttons = CreateActionRow::Buttons(
+        vec![
+            CreateButton::new(format!("{id_prefix}:confirm"))
+                .label("Import")
+                .style(ButtonStyle::Success),
+            CreateButton::new(format!("{id_prefix}:cancel"))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ]
+        .into(),
+    );
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text),
+            CreateContainerComponent::ActionRow(buttons),
+        ])
+        .accent_color(YELLOW),
+    )]
+}
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands(
+        "force_update",
+        "force_update_all",
+        "force_link",
+        "force_unlink",
+        "force_unlink_bulk",
+        "force_suggest",
+        "force_preview"
+    )
+)]
+async fn force(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// Update another user's roles (use '/rolerequest query stats' to check stats without updating roles)
+#[poise::command(
+    slash_command,
+    rename = "update",
+    required_bot_permissions = "MANAGE_ROLES"
+)]
+async fn force_update(
+    ctx: Context<'_>,
+    #[description = "Whose roles to update"] user: Member,
+) -> Result<()> {
+    let Some(linked_user) = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetLinkedUserByDiscord {
+            discord: user.user.id,
+        })
+        .await??
+    else {
+        ctx.defer().await?;
+        return send_link_suggestion(ctx, &user.user).await;
+    };
+
+    ctx.defer().await?;
+
+    let role_status = request::update_roles(
+        ctx.serenity_context(),
+        &linked_user.mc_uuid,
+        &user,
+        RoleUpdateTrigger::ForceUpdate {
+            by: ctx.author().id,
+        },
+    )
+    .await?;
+
+    let container = role_status.to_diff_message(Some(&user.user.id));
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show which roles an update would add and remove for another user, without changing any roles
+#[poise::command(slash_command, rename = "preview")]
+async fn force_preview(
+    ctx: Context<'_>,
+    #[description = "Whose roles to preview"] user: Member,
+) -> Result<()> {
+    let Some(linked_user) = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetLinkedUserByDiscord {
+            discord: user.user.id,
+        })
+        .await??
+    else {
+        ctx.defer().await?;
+        return send_link_suggestion(ctx, &user.user).await;
+    };
+
+    ctx.defer_ephemeral().await?;
+
+    let preview =
+        request::preview_roles(ctx.serenity_context(), &linked_user.mc_uuid, &user).await?;
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![preview.to_message(Some(&user.user.id))])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Linked users are loaded and their progress saved in chunks of this size
+const BULK_UPDATE_CHUNK_SIZE: u32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BulkUpdateState {
+    Running,
+    Cancelled,
+    Completed,
+}
+
+/// Update the roles of every linked user. Cancelled or interrupted runs are resumed.
+#[poise::command(
+    slash_command,
+    rename = "update-all",
+    guild_only,
+    required_bot_permissions = "MANAGE_ROLES"
+)]
+async fn force_update_all(
+    ctx: Context<'_>,
+    #[description = "Start over instead of resuming a previous run"] restart: Option<bool>,
+    #[description = "Only count the users whose roles would change, without updating any"]
+    dry_run: Option<bool>,
+) -> Result<()> {
+    let guild_id = ctx
+        .guild_id()
+        .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    ctx.defer().await?;
+
+    let db = dry_run::db(ctx.data(), Some(guild_id), dry_run);
+
+    if restart.unwrap_or(false) {
+        db.request(SetBulkUpdateProgress { progress: None })
+            .await??;
+    }
+    // the progress of dry runs is rolled back, so they can't resume and always cover every user
+    let mut progress = if dry_run {
+        BulkUpdateProgress::default()
+    } else {
+        db.request(GetBulkUpdateProgress)
+            .await??
+            .unwrap_or_default()
+    };
+    let total = db.request(CountLinkedUsers).await??;
+
+    let id_prefix = format!("bulkupdate:{}", generate_id());
+    let handle = ctx
+        .send(
+            bulk_update_reply(
+                &progress,
+                total,
+                BulkUpdateState::Running,
+                &id_prefix,
+                dry_run,
+            )
+            .into_reply(),
+        )
+        .await?;
+    // edited directly, as the interaction token expires after 15 minutes, long before large bulk
+    // updates are done
+    let message = handle.message().await?;
+    let (channel_id, message_id) = (message.channel_id, message.id);
+
+    // listen for the cancel button in the background, only the invoking user may cancel
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let listener = AbortOnDrop({
+        let cancelled = Arc::clone(&cancelled);
+        let serenity_ctx = ctx.serenity_context().clone();
+        let prefix = id_prefix.clone();
+        let author = ctx.author().id;
+
+        tokio::spawn(
+            async move {
+                let mut stream = collector::collect(&serenity_ctx, move |event| match event {
+                    Event::InteractionCreate(event) => match &event.interaction {
+                        Interaction::Component(interaction)
+                            if interaction.data.custom_id.starts_with(&prefix)
+                                && interaction.user.id == author =>
+                        {
+                            Some(interaction.clone())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                });
+
+                if let Some(interaction) = stream.next().await {
+                    cancelled.store(true, Ordering::Relaxed);
+                    let _ = interaction
+                        .create_response(&serenity_ctx.http, CreateInteractionResponse::Acknowledge)
+                        .await;
+                }
+            }
+            .in_current_span(),
+        )
+    });
+
+    'chunks: loop {
+        let chunk = db
+            .request(GetLinkedUsersChunk {
+                after: progress.last_user,
+                limit: BULK_UPDATE_CHUNK_SIZE,
+            })
+            .await??;
+        if chunk.is_empty() {
+            break;
+        }
+
+        for linked_user in chunk {
+            if cancelled.load(Ordering::Relaxed) {
+                break 'chunks;
+            }
+
+            match guild_id.member(ctx.http(), linked_user.discord).await {
+                Ok(member) => {
+                    // cached stats are used where possible, and API requests are rate limited
+                    let changed = if dry_run {
+                        request::preview_roles(
+                            ctx.serenity_context(),
+                            &linked_user.mc_uuid,
+                            &member,
+                        )
+                        .await
+                        .map(|preview| preview.has_changes())
+                    } else {
+                        request::update_roles(
+                            ctx.serenity_context(),
+                            &linked_user.mc_uuid,
+                            &member,
+                            RoleUpdateTrigger::BulkUpdate {
+                                by: ctx.author().id,
+                            },
+                        )
+                        .await
+                        .map(|status| matches!(status, RoleRequestStatus::Updated { .. }))
+                    };
+                    match changed {
+                        Ok(true) => progress.updated += 1,
+                        Ok(false) => progress.unchanged += 1,
+                        Err(err) => {
+                            warn!(
+                                "Failed to update roles of {} during bulk update: {err:#}",
+                                linked_user.discord
+                            );
+                            progress.failed += 1;
+                        }
+                    }
+                }
+                Err(err) if error::is_unknown_member(&err) => progress.skipped += 1,
+                Err(err) => {
+                    warn!(
+                        "Failed to fetch member {} during bulk update: {err:#}",
+                        linked_user.discord
+                    );
+                    progress.failed += 1;
+                }
+            }
+            progress.last_user = Some(linked_user.discord);
+        }
+
+        db.request(SetBulkUpdateProgress {
+            progress: Some(progress),
+        })
+        .await??;
+        // the update keeps going even if the progress message can't be edited
+        let reply = bulk_update_reply(
+            &progress,
+            total,
+            BulkUpdateState::Running,
+            &id_prefix,
+            dry_run,
+        );
+        if let Err(err) = channel_id
+            .edit_message(ctx.http(), message_id, reply.into_edit())
+            .await
+        {
+            warn!("Failed to update bulk update progress: {err:#}");
+        }
+    }
+    drop(listener);
+
+    let state = if cancelled.load(Ordering::Relaxed) {
+        db.request(SetBulkUpdateProgress {
+            progress: Some(progress),
+        })
+        .await??;
+        BulkUpdateState::Cancelled
+    } else {
+        db.request(SetBulkUpdateProgress { progress: None })
+            .await??;
+        BulkUpdateState::Completed
+    };
+
+    let reply = bulk_update_reply(&progress, total, state, &id_prefix, dry_run);
+    if let Err(err) = channel_id
+        .edit_message(ctx.http(), message_id, reply.into_edit())
+        .await
+    {
+        warn!("Failed to update bulk update progress: {err:#}");
+    }
+
+    Ok(())
+}
+
+fn bulk_update_reply(
+    progress: &BulkUpdateProgress,
+    total: u32,
+    state: BulkUpdateState,
+    id_prefix: &str,
+    dry_run: bool,
+) -> MenuMessage<'static> {
+    let (title, note, color) = match state {
+        BulkUpdateState::Running => ("Updating All Roles", "", YELLOW),
+        BulkUpdateState::Cancelled if dry_run => ("Cancelled Role Update", "", WARNING),
+        BulkUpdateState::Cancelled => (
+            "Cancelled Role Update",
+            "\n-# Run the command again to resume where it left off.",
+            WARNING,
+        ),
+        BulkUpdateState::Completed => ("Updated All Roles", "", POSITIVE),
+    };
+
+    let mut components = vec![CreateContainerComponent::TextDisplay(
+        CreateTextDisplay::new(format!(
+            "## {}
+Processed **{}** of **{total}** linked users.
+- {}: **{}**
+- Unchanged: **{}**
+- Not in server: **{}**
+- Failed: **{}**{note}{}",
+            dry_run::title(title, dry_run),
+            progress.processed(),
+            if dry_run { "Would update" } else { "Updated" },
+            progress.updated,
+            progress.unchanged,
+            progress.skipped,
+            progress.failed,
+            dry_run::notice(dry_run),
+        )),
+    )];
+
+    if state == BulkUpdateState::Running {
+        let cancel_button = CreateButton::new(format!("{id_prefix}:cancel"))
+            .label("Cancel")
+            .style(ButtonStyle::Danger);
+        components.push(CreateContainerComponent::ActionRow(
+            CreateActionRow::Buttons(vec![cancel_button].into()),
+        ));
+    }
+
+    MenuMessage::new(vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_colour(color),
+    )])
+}
+
+/// Stops the cancel button listener of a bulk update however it ends, including on errors
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Override another user's linked minecraft account
+#[poise::command(slash_command, rename = "link")]
+async fn force_link(
+    ctx: Context<'_>,
+    #[description = "Whose linked account to update"] discord: UserId,
+    #[description = "Minecraft account to link"] minecraft: String,
+) -> Result<()> {
+    let minecraft = MinecraftIdent::from_input(&minecraft)?;
+
+    ctx.defer().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let uuid = ctx.data().api_handle.uuid(db, &minecraft).await?;
+
+    db.request(UpdateLinkedUser {
+        user: LinkedUser::new(discord, uuid),
+    })
+    .await??;
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Linked Successfully
+Linked `{minecraft}` to {}, discarding any existing links for either account.",
+                discord.mention()
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Unlink another user's minecraft account
+#[poise::command(slash_command, rename = "unlink")]
+async fn force_unlink(
+    ctx: Context<'_>,
+    #[description = "Whose account to unlink"] user: UserId,
+) -> Result<()> {
+    ctx.defer().await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let removed_user = db
+        .request(RemoveLinkedUserByDiscord { discord: user })
+        .await??
+        .context(UserError(anyhow!("User hasn't linked their accounts",)))?;
+
+    let username = ctx
+        .data()
+        .api_handle
+        .username(db, &removed_user.mc_uuid)
+        .await?;
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Unlinked Successfully
+Unlinked `{username}` from {}.",
+                user.mention()
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Mentions shown in the bulk unlink preview, the full list is in the attached CSV
+const MAX_PREVIEW_USERS: usize = 20;
+
+/// Unlink many users' minecraft accounts at once, e.g. for data removal requests
+#[poise::command(slash_command, rename = "unlink-bulk", guild_only)]
+async fn force_unlink_bulk(
+    ctx: Context<'_>,
+    #[description = "Discord IDs or mentions of the users to unlink, separated by spaces or commas"]
+    users: Option<String>,
+    #[description = "Unlink all members with this role"] role: Option<Role>,
+    #[description = "Only list the accounts that would be unlinked, without asking to confirm"]
+    dry_run: Option<bool>,
+) -> Result<()> {
+    let dry_run = dry_run.unwrap_or(false);
+    if users.is_none() && role.is_none() {
+        bail!(UserError(anyhow!(
+            "Please specify the users to unlink, a role, or both"
+        )));
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let mut targets = match &users {
+        Some(users) => parse_user_list(users)?,
+        None => Vec::new(),
+    };
+    if let Some(role) = &role {
+        let guild = ctx
+            .guild_id()
+            .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+        targets.extend(members::role_members(ctx.serenity_context(), guild, role.id).await?);
+    }
+    targets.sort_unstable();
+    targets.dedup();
+
+    let db = dry_run::db(ctx.data(), ctx.guild_id(), dry_run);
+    let linked_users = db
+        .request(GetLinkedUsersByDiscord {
+            discord: targets.clone(),
+        })
+        .await??;
+
+    if linked_users.is_empty() {
+        bail!(UserError(anyhow!(
+            "None of the {} specified users have linked their accounts",
+            targets.len()
+        )));
+    }
+
+    // exported before anything is deleted, so that the removed data is never lost by accident
+    let export = CreateAttachment::bytes(
+        links::to_csv(&linked_users).into_bytes(),
+        format!(
+            "unlinked_users_{}.csv",
+            chrono::Utc::now().format("%Y-%m-%d")
+        ),
+    );
+
+    if dry_run {
+        // the same removal as confirming the preview, rolled back by the dry run handle
+        let removed = db
+            .request(RemoveLinkedUsers {
+                discord: linked_users.iter().map(|user| user.discord).collect(),
+            })
+            .await??;
+        ctx.send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(confirm::outcome(
+                    &format!(
+                        "## {}
+**{}** accounts would be unlinked:
+{}-# The attached CSV contains all links that would be removed.{}",
+                        dry_run::title("Unlink", dry_run),
+                        removed.len(),
+                        preview_user_list(&linked_users),
+                        dry_run::notice(dry_run),
+                    ),
+                    YELLOW,
+                ))
+                .attachment(export)
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let id_prefix = format!("unlinkbulk:{}", generate_id());
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(unlink_bulk_preview(
+                    &linked_users,
+                    targets.len(),
+                    &id_prefix,
+                ))
+                .attachment(export)
+                .ephemeral(true),
+        )
+        .await?;
+
+    confirm::await_confirmation(
+        ctx,
+        handle,
+        &id_prefix,
+        "Unlink",
+        "No accounts were unlinked.",
+        || async move {
+            // only the previewed links are removed, even if more were created in the meantime
+            let removed = db
+                .request(RemoveLinkedUsers {
+                    discord: linked_users.into_iter().map(|user| user.discord).collect(),
+                })
+                .await??;
+            Ok(confirm::outcome(
+                &format!(
+                    "## Unlinked Successfully
+Unlinked **{}** accounts.
+-# The attached CSV contains the removed links.",
+                    removed.len()
+                ),
+                POSITIVE,
+            ))
+        },
+    )
+    .await
+}
+
+fn parse_user_list(input: &str) -> Result<Vec<UserId>> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>')
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+                .map(UserId::new)
+                .ok_or_else(|| {
+                    UserError(anyhow!(
+                        "`{token}` is neither a Discord ID nor a user mention"
+                    ))
+                    .into()
+                })
+        })
+        .collect()
+}
+
+fn preview_user_list(linked_users: &[LinkedUser]) -> String {
+    let mut users: String = linked_users
+        .iter()
+        .take(MAX_PREVIEW_USERS)
+        .map(|user| format!("- {} (`{}`)\n", user.discord.mention(), user.mc_uuid))
+        .collect();
+    if linked_users.len() > MAX_PREVIEW_USERS {
+        users.push_str(&format!(
+            "- *...and {} more*\n",
+            linked_users.len() - MAX_PREVIEW_USERS
+        ));
+    }
+    users
+}
+
+fn unlink_bulk_preview(
+    linked_users: &[LinkedUser],
+    targets: usize,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let users = preview_user_list(linked_users);
+    let skipped = targets - linked_users.len();
+    let text = CreateTextDisplay::new(format!(
+        "## Unlink Preview
+**{}** accounts would be unlinked:
+{users}{}-# Nothing has been unlinked yet. The attached CSV contains all links that would be removed, \
+confirm to remove them at once.",
+        linked_users.len(),
+        if skipped > 0 {
+            format!("-# {skipped} users are skipped, as they haven't linked their accounts.\n")
+        } else {
+            String::new()
+        },
+    ));
+
+    let buttons = CreateActionRow::Buttons(
+        vec![
+            CreateButton::new(format!("{id_prefix}:confirm"))
+                .label("Unlink")
+                .style(ButtonStyle::Danger),
+            CreateButton::new(format!("{id_prefix}:cancel"))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        ]
+        .into(),
+    );
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text),
+            CreateContainerComponent::ActionRow(buttons),
+        ])
+        .accent_color(WARNING),
+    )]
+}
+
+/// Suggest a minecraft account for an unlinked user, based on previously fetched Hypixel profiles
+#[poise::command(slash_command, rename = "suggest")]
+async fn force_suggest(
+    ctx: Context<'_>,
+    #[description = "Who to find a matching account for"] user: User,
+) -> Result<()> {
+    if ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetLinkedUserByDiscord { discord: user.id })
+        .await??
+        .is_some()
+    {
+        bail!(UserError(anyhow!("User has already linked their accounts")));
+    }
+
+    ctx.defer().await?;
+
+    send_link_suggestion(ctx, &user).await
+}
+
+/// Responds with a one-click link for a cached Hypixel profile that lists the user's Discord account
+async fn send_link_suggestion(ctx: Context<'_>, user: &User) -> Result<()> {
+    let Some((uuid, username)) =
+        request::suggest_link(ctx.serenity_context(), ctx.guild_id(), user).await?
+    else {
+        bail!(UserError(anyhow!(
+            "User hasn't linked their accounts, and no recently fetched Hypixel profile lists their Discord account"
+        )));
+    };
+
+    let link_button = CreateButton::new(custom_id::encode(
+        Namespace::Role,
+        &[&"request", &"suggested", &ctx.author().id, &user.id, &uuid],
+    ))
+    .emoji('🔗')
+    .label("Link Account")
+    .style(ButtonStyle::Success);
+
+    let section = CreateContainerComponent::Section(CreateSection::new(
+        vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+            format!(
+                "## Link Suggestion
+{} hasn't linked their accounts, but the Hypixel profile of `{username}` lists `{}` as its Discord account.
+\nUse the button to link these accounts.",
+                user.mention(),
+                request::full_username(user)
+            ),
+        ))],
+        CreateSectionAccessory::Button(link_button),
+    ));
+
+    ctx.send(
+        CreateReply::default()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![section]).accent_color(BLUE),
+            )]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("query_stats", "query_diff")
+)]
+async fn query(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// Check the link status and stats of any Discord/Minecraft account
+#[poise::command(slash_command, rename = "stats")]
+async fn query_stats(
+    ctx: Context<'_>,
+    #[description = "By Discord account"] discord: Option<UserId>,
+    #[description = "By Minecraft username/UUID"] minecraft: Option<String>,
+    #[description = "Whether to list the goals of the current bingo card"] goals: Option<bool>,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+    let api = &ctx.data().api_handle;
+
+    ctx.defer().await?;
+
+    let (discord, uuid, username) = match (discord, minecraft) {
+        (None, None) => {
+            let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+                "## Insufficient arguments
 You need to provide either the `discord` or `minecraft` command argument.",
             ));
             let container =
@@ -412,19 +1830,22 @@ You need to provide either the `discord` or `minecraft` command argument.",
                     return Ok(());
                 }
             };
-            let username = api.username(&uuid).await?;
+            let username = api.username(db, &uuid).await?;
             (Some(user_id), uuid, username)
         }
         (_, Some(minecraft)) => {
-            let (uuid, username) = if minecraft.len() >= 32 {
-                let username = api
-                    .username(&minecraft)
-                    .await
-                    .map_err(|err| anyhow!(UserError(err)))?;
-                (minecraft, username)
-            } else {
-                let uuid = api.uuid(&minecraft).await?;
-                (uuid, minecraft)
+            let (uuid, username) = match MinecraftIdent::from_input(&minecraft)? {
+                MinecraftIdent::Uuid(uuid) => {
+                    let username = api
+                        .username(db, &uuid)
+                        .await
+                        .map_err(|err| anyhow!(UserError(err)))?;
+                    (uuid, username)
+                }
+                ident @ MinecraftIdent::Username(_) => {
+                    let uuid = api.uuid(db, &ident).await?;
+                    (uuid, ident.to_string())
+                }
             };
             let linked_user = db
                 .request(GetLinkedUserByMinecraft {
@@ -437,10 +1858,17 @@ You need to provide either the `discord` or `minecraft` command argument.",
 
     let link_message = match discord {
         Some(user_id) => {
+            let verified = match db
+                .request(GetLinkLastVerified { discord: user_id })
+                .await??
+            {
+                Some(at) => format!("Last verified against Hypixel <t:{at}:R>."),
+                None => "Not verified against Hypixel since linking.".to_string(),
+            };
             format!(
                 "## Linked
 {} is linked to `{username}`.
--# Stored in database as UUID.",
+-# Stored in database as UUID. {verified}",
                 user_id.mention()
             )
         }
@@ -454,14 +1882,24 @@ You need to provide either the `discord` or `minecraft` command argument.",
     let link_text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(link_message));
 
     let roles_text = CreateContainerComponent::TextDisplay(
-        request::player_roles(ctx.serenity_context(), &uuid)
+        request::player_roles(ctx.serenity_context(), ctx.guild_id(), &uuid)
             .await?
             .to_text_display(),
     );
 
-    let container = CreateComponent::Container(
-        CreateContainer::new(vec![link_text, roles_text]).accent_color(POSITIVE),
-    );
+    let mut components = vec![link_text, roles_text];
+    if goals.unwrap_or(false) {
+        let progress = api.bingo_goal_progress(&uuid).await?;
+        components.push(CreateContainerComponent::Separator(CreateSeparator::new(
+            true,
+        )));
+        components.push(CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(goal_checklist(&progress)),
+        ));
+    }
+
+    let container =
+        CreateComponent::Container(CreateContainer::new(components).accent_color(POSITIVE));
 
     ctx.send(
         CreateReply::new()
@@ -471,3 +1909,171 @@ You need to provide either the `discord` or `minecraft` command argument.",
     .await?;
     Ok(())
 }
+
+fn goal_checklist(progress: &BingoGoalProgress) -> String {
+    let goals: String = progress
+        .goals
+        .iter()
+        .map(|(name, completed)| format!("- {} {name}\n", if *completed { "✅" } else { "⬜" }))
+        .collect();
+
+    format!(
+        "### Current Bingo Card
+**{}/{}** goals completed.
+{goals}",
+        progress.completed(),
+        progress.goals.len()
+    )
+}
+
+/// Compare a user's cached Blackouts against freshly fetched ones to debug stale cache data
+#[poise::command(slash_command, rename = "diff")]
+async fn query_diff(
+    ctx: Context<'_>,
+    #[description = "Whose completions to compare"] user: UserId,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+    let api = &ctx.data().api_handle;
+
+    let linked_user = db
+        .request(GetLinkedUserByDiscord { discord: user })
+        .await??
+        .context(UserError(anyhow!("User hasn't linked their accounts")))?;
+
+    ctx.defer().await?;
+
+    // cached data must not be read while the current bingo is outdated
+    api.update_current_bingo(db).await?;
+
+    let cached = db
+        .request(CachedCompletions {
+            uuid: linked_user.mc_uuid.clone(),
+        })
+        .await??;
+    let live = BitSet::from_indexes(&api.bingo_completions(&linked_user.mc_uuid).await?);
+    let username = api.username(db, &linked_user.mc_uuid).await?;
+
+    let cache_text = match cached {
+        None => Cow::Borrowed("*No valid cache entry*"),
+        Some(cached) => {
+            let cached_ids = cached.get_all_set();
+            let live_ids = live.get_all_set();
+
+            let new_ids: Vec<u8> = live_ids
+                .iter()
+                .filter(|id| !cached_ids.contains(id))
+                .map(|&id| id as u8)
+                .collect();
+            let missing_ids: Vec<u8> = cached_ids
+                .iter()
+                .filter(|id| !live_ids.contains(id))
+                .map(|&id| id as u8)
+                .collect();
+
+            let new_list = bingo_list(db, new_ids).await?;
+            let missing_list = bingo_list(db, missing_ids).await?;
+
+            Cow::Owned(format!(
+                "Cached: {} Blackouts
+### Newly completed since last cache update
+{new_list}
+### Cached but missing from the API
+{missing_list}",
+                cached_ids.len()
+            ))
+        }
+    };
+
+    let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+        "## Completions Diff for `{username}`
+Linked to {}
+Live: {} Blackouts
+{cache_text}
+-# The cache entry wasn't modified.",
+        user.mention(),
+        live.get_all_set().len(),
+    )));
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![text]).accent_color(POSITIVE),
+            )]),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Days covered by the recent column of `/rolerequest stats`
+const RECENT_UPDATE_DAYS: i64 = 30;
+
+/// View how many role updates each entry point handled
+#[poise::command(slash_command)]
+async fn stats(ctx: Context<'_>) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    ctx.defer().await?;
+
+    let since = chrono::Utc::now().timestamp() - RECENT_UPDATE_DAYS * 24 * 60 * 60;
+    let recent = db
+        .request(GetRoleUpdateCounts { since: Some(since) })
+        .await??;
+    let all_time = db.request(GetRoleUpdateCounts { since: None }).await??;
+
+    // all outcomes if `outcome` is `None`
+    let count = |counts: &[(RoleUpdateTriggerKind, RoleUpdateOutcome, u64)],
+                 source: RoleUpdateTriggerKind,
+                 outcome: Option<RoleUpdateOutcome>|
+     -> u64 {
+        counts
+            .iter()
+            .filter(|(kind, other, _)| {
+                *kind == source && outcome.is_none_or(|outcome| outcome == *other)
+            })
+            .map(|(_, _, count)| count)
+            .sum()
+    };
+
+    let mut list = String::new();
+    for (source, name) in role_update_sources() {
+        list += &format!(
+            "- **{name}**: {} in the last {RECENT_UPDATE_DAYS} days ({} updated, {} unchanged, {} failed), {} in total\n",
+            count(&recent, source, None),
+            count(&recent, source, Some(RoleUpdateOutcome::Updated)),
+            count(&recent, source, Some(RoleUpdateOutcome::Unchanged)),
+            count(&recent, source, Some(RoleUpdateOutcome::Failed)),
+            count(&all_time, source, None),
+        );
+    }
+
+    let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+        "## Role Updates by Entry Point
+{list}"
+    )));
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![text]).accent_color(YELLOW),
+            )]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn bingo_list(db: &DbHandle, bingo_ids: Vec<u8>) -> Result<Cow<'static, str>> {
+    if bingo_ids.is_empty() {
+        return Ok(Cow::Borrowed("*None*"));
+    }
+
+    let bingos = db.request(GetBingoData { bingo_ids }).await??;
+    Ok(Cow::Owned(
+        bingos
+            .iter()
+            .map(|b| format!("- {b}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
@@ -0,0 +1,530 @@
+use std::time::Duration;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::{NaiveDate, Utc};
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateTextDisplay, Event,
+        GenericChannelId, Interaction, Mentionable as _, MessageFlags, Timestamp, User, collector,
+        colours::{branding::YELLOW, css::POSITIVE},
+        futures::StreamExt as _,
+    },
+};
+
+use crate::config::MENU_TIMEOUT_SECS;
+use crate::error::UserError;
+use crate::shared::{
+    Context,
+    db::{
+        ClearSplasherAway, GetSplashInactivityConfig, GetSplasherAways, GetSplasherOnboardings,
+        SetSplashInactivityConfig, SetSplashInactivityOptOut, SetSplasherAway,
+    },
+    menu::{
+        generate_id,
+        navigation::{PaginatedChunk, page_navigation},
+    },
+    time::{TimestampStyle, discord_timestamp},
+    types::{SplashInactivityConfig, SplasherAway},
+};
+use crate::splashes::{
+    history::{SplashHistoryEntry, splash_history},
+    inactivity,
+    onboarding::OnboardingItem,
+};
+
+/// How far back splash history can be requested, matching the search limit of `/lastsplashed`
+const MAX_HISTORY_MONTHS: u32 = 6;
+const HISTORY_PAGE_SIZE: usize = 10;
+/// Longest period a splasher can mark themselves as away for at once
+const MAX_AWAY_DAYS: i64 = 90;
+/// Pending onboardings listed at once, so that the list stays within Discord's message length limit
+const MAX_LISTED_ONBOARDINGS: usize = 30;
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("history", "away", "inactivity", "onboarding"),
+    required_bot_permissions = "VIEW_CHANNEL | READ_MESSAGE_HISTORY"
+)]
+pub async fn splashes(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("away_set", "away_clear", "away_list")
+)]
+async fn away(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands("inactivity_config", "inactivity_disable", "inactivity_opt_out")
+)]
+async fn inactivity(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// Post a weekly digest of splashers who haven't splashed in a while to a staff channel
+#[poise::command(slash_command, rename = "config")]
+async fn inactivity_config(
+    ctx: Context<'_>,
+    #[description = "Channel the digest is posted to"] channel: GenericChannelId,
+    #[description = "Days without a splash after which a splasher is listed"]
+    #[min = 1]
+    #[max = 180]
+    days: u32,
+) -> Result<()> {
+    let guild_id = ctx
+        .guild_id()
+        .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+
+    let db = ctx.data().db_for(Some(guild_id));
+    db.request(SetSplashInactivityConfig {
+        config: Some(SplashInactivityConfig {
+            guild_id,
+            channel,
+            threshold_days: days,
+            last_alert: None,
+        }),
+    })
+    .await??;
+    let config = db
+        .request(GetSplashInactivityConfig)
+        .await??
+        .context("Inactivity config should exist after setting it")?;
+
+    let next_digest = match config.last_alert.and_then(|last| {
+        Timestamp::from_unix_timestamp(last + inactivity::ALERT_INTERVAL_SECS).ok()
+    }) {
+        Some(next) => format!(
+            "The next digest is posted {}.",
+            discord_timestamp(next, TimestampStyle::Relative)
+        ),
+        None => "The first digest is posted within the next hour.".to_string(),
+    };
+
+    send_away_reply(
+        ctx,
+        &format!(
+            "## Inactivity Alerts Enabled
+Splashers who haven't splashed in the last **{days} days** are reported in {} once a week. \
+             {next_digest}",
+            channel.mention()
+        ),
+    )
+    .await
+}
+
+/// Stop posting splasher inactivity digests
+#[poise::command(slash_command, rename = "disable")]
+async fn inactivity_disable(ctx: Context<'_>) -> Result<()> {
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashInactivityConfig { config: None })
+        .await??;
+
+    send_away_reply(
+        ctx,
+        "## Inactivity Alerts Disabled
+Inactive splashers are no longer reported.",
+    )
+    .await
+}
+
+/// Exclude a splasher from inactivity digests, e.g. if they only splash occasionally
+#[poise::command(slash_command, rename = "opt-out")]
+async fn inactivity_opt_out(
+    ctx: Context<'_>,
+    #[description = "Splasher to exclude or include again"] splasher: User,
+    #[description = "Whether to exclude the splasher (defaults to true)"] opted_out: Option<bool>,
+) -> Result<()> {
+    let opted_out = opted_out.unwrap_or(true);
+
+    let changed = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashInactivityOptOut {
+            user: splasher.id,
+            opted_out,
+        })
+        .await??;
+
+    let text = match (opted_out, changed) {
+        (true, true) => format!(
+            "## Opted Out
+{} is no longer listed in inactivity digests.",
+            splasher.mention()
+        ),
+        (false, true) => format!(
+            "## Opted In
+{} is listed in inactivity digests again.",
+            splasher.mention()
+        ),
+        (true, false) => bail!(UserError(anyhow!(
+            "{} is already opted out of inactivity digests",
+            splasher.name
+        ))),
+        (false, false) => bail!(UserError(anyhow!(
+            "{} isn't opted out of inactivity digests",
+            splasher.name
+        ))),
+    };
+
+    send_away_reply(ctx, &text).await
+}
+
+/// Mark yourself as unavailable for splashing, e.g. during a vacation
+#[poise::command(slash_command, rename = "set")]
+async fn away_set(
+    ctx: Context<'_>,
+    #[description = "First day of your absence, formatted as YYYY-MM-DD (e.g. 2025-07-01)"]
+    from: String,
+    #[description = "Last day of your absence, formatted as YYYY-MM-DD"] until: String,
+    #[description = "Optional note for staff"]
+    #[max_length = 100]
+    reason: Option<String>,
+) -> Result<()> {
+    let from = parse_date(&from)?;
+    let until = parse_date(&until)?;
+
+    if until < from {
+        bail!(UserError(anyhow!(
+            "The last day can't be before the first day"
+        )));
+    }
+    if until < Utc::now().date_naive() {
+        bail!(UserError(anyhow!("The absence is already over")));
+    }
+    if (until - from).num_days() >= MAX_AWAY_DAYS {
+        bail!(UserError(anyhow!(
+            "Absences can be at most {MAX_AWAY_DAYS} days long, set a new one once it is over"
+        )));
+    }
+
+    let away = SplasherAway {
+        user: ctx.author().id,
+        start: from.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp(),
+        end: until.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp(),
+        reason: reason
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty()),
+    };
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplasherAway { away: away.clone() })
+        .await??;
+
+    send_away_reply(
+        ctx,
+        &format!(
+            "## Marked as Away
+You are marked as unavailable {}. Use `/splashes away clear` if your plans change.",
+            away_range(&away)
+        ),
+    )
+    .await
+}
+
+/// Mark yourself as available for splashing again
+#[poise::command(slash_command, rename = "clear")]
+async fn away_clear(ctx: Context<'_>) -> Result<()> {
+    let cleared = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(ClearSplasherAway {
+            user: ctx.author().id,
+        })
+        .await??;
+
+    if !cleared {
+        bail!(UserError(anyhow!("You aren't marked as away")));
+    }
+
+    send_away_reply(
+        ctx,
+        "## Welcome Back
+You are no longer marked as unavailable.",
+    )
+    .await
+}
+
+/// List splashers who are currently or will soon be unavailable
+#[poise::command(slash_command, rename = "list")]
+async fn away_list(ctx: Context<'_>) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let aways = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetSplasherAways { now })
+        .await??;
+
+    let format_list = |active: bool| -> String {
+        let lines: Vec<String> = aways
+            .iter()
+            .filter(|away| away.is_active(now) == active)
+            .map(|away| {
+                format!(
+                    "- {} {}{}",
+                    away.user.mention(),
+                    away_range(away),
+                    away.reason
+                        .as_deref()
+                        .map(|r| format!(" — *{r}*"))
+                        .unwrap_or_default()
+                )
+            })
+            .collect();
+        if lines.is_empty() {
+            "*None*".to_string()
+        } else {
+            lines.join("\n")
+        }
+    };
+
+    send_away_reply(
+        ctx,
+        &format!(
+            "## Splasher Availability
+### Currently away
+{}
+### Upcoming
+{}",
+            format_list(true),
+            format_list(false)
+        ),
+    )
+    .await
+}
+
+/// List splashers who haven't confirmed every item of the onboarding checklist yet
+#[poise::command(slash_command)]
+async fn onboarding(ctx: Context<'_>) -> Result<()> {
+    let pending: Vec<_> = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetSplasherOnboardings)
+        .await??
+        .into_iter()
+        .filter(|onboarding| !onboarding.is_complete())
+        .collect();
+
+    let mut listed: Vec<String> = pending
+        .iter()
+        .take(MAX_LISTED_ONBOARDINGS)
+        .map(|onboarding| {
+            let started = Timestamp::from_unix_timestamp(onboarding.started_at)
+                .map(|t| discord_timestamp(t, TimestampStyle::Relative).to_string())
+                .unwrap_or_default();
+            format!(
+                "- {} — {}/{} confirmed, started {started}",
+                onboarding.user.mention(),
+                onboarding.acknowledged_count(),
+                OnboardingItem::ALL.len()
+            )
+        })
+        .collect();
+
+    if pending.len() > MAX_LISTED_ONBOARDINGS {
+        listed.push(format!(
+            "- *...and {} more*",
+            pending.len() - MAX_LISTED_ONBOARDINGS
+        ));
+    }
+
+    let (list, accent) = if pending.is_empty() {
+        (
+            "*Every splasher has completed onboarding*".to_string(),
+            POSITIVE,
+        )
+    } else {
+        (listed.join("\n"), YELLOW)
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!("## Pending Onboarding\n{list}")),
+                )])
+                .accent_color(accent),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn parse_date(input: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d").context(UserError(anyhow!(
+        "Failed to parse date '{input}', expected a format like `2025-07-01`"
+    )))
+}
+
+fn away_range(away: &SplasherAway) -> String {
+    let date = |t| {
+        Timestamp::from_unix_timestamp(t)
+            .map(|t| discord_timestamp(t, TimestampStyle::LongDate).to_string())
+            .unwrap_or_default()
+    };
+    format!("from {} until {}", date(away.start), date(away.end))
+}
+
+async fn send_away_reply(ctx: Context<'_>, text: &str) -> Result<()> {
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text.to_string()),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// List a splasher's individual splashes, e.g. to verify their count on the splash list
+#[poise::command(slash_command)]
+async fn history(
+    ctx: Context<'_>,
+    #[description = "Splasher whose splashes should be listed"] user: User,
+    #[description = "How many months to look back (defaults to 1, at most 6)"] months: Option<u32>,
+) -> Result<()> {
+    let months = months.unwrap_or(1);
+    if months == 0 || months > MAX_HISTORY_MONTHS {
+        bail!(UserError(anyhow!(
+            "Months must be between 1 and {MAX_HISTORY_MONTHS}"
+        )));
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let start = Timestamp::from_unix_timestamp(
+        (chrono::Utc::now() - chrono::Months::new(months)).timestamp(),
+    )?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+    let entries = splash_history(ctx.http(), db, ctx.guild_id(), user.id, start).await?;
+
+    let menu_id = generate_id();
+    let id_prefix = format!("splashes:history:{menu_id}");
+
+    let mut page = 0;
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(history_page(&user, start, &entries, page, &id_prefix))
+                .ephemeral(true),
+        )
+        .await?;
+
+    // only the invoking user can see the ephemeral response, so no need to check who clicked
+    let prefix = id_prefix.clone();
+    let mut stream = collector::collect(ctx.serenity_context(), move |event| match event {
+        Event::InteractionCreate(event) => match &event.interaction {
+            Interaction::Component(interaction)
+                if interaction.data.custom_id.starts_with(&prefix) =>
+            {
+                Some(interaction.clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    });
+
+    let timeout = Duration::from_secs(MENU_TIMEOUT_SECS);
+    while let Ok(Some(interaction)) = tokio::time::timeout(timeout, stream.next()).await {
+        match interaction.data.custom_id.rsplit(':').next() {
+            Some("prev") => page = page.saturating_sub(1),
+            Some("next") => page += 1,
+            _ => continue,
+        }
+
+        // clamp page to the actually displayed one
+        page = PaginatedChunk::new(entries.len(), page, HISTORY_PAGE_SIZE).page;
+
+        interaction
+            .create_response(
+                ctx.http(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .flags(MessageFlags::IS_COMPONENTS_V2)
+                        .components(history_page(&user, start, &entries, page, &id_prefix)),
+                ),
+            )
+            .await?;
+    }
+
+    // remove navigation once the menu has expired
+    handle
+        .edit(
+            ctx,
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(history_page(&user, start, &entries, page, "")),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Renders a page of the history, without navigation if `id_prefix` is empty
+fn history_page(
+    user: &User,
+    start: Timestamp,
+    entries: &[SplashHistoryEntry],
+    page: usize,
+    id_prefix: &str,
+) -> Vec<CreateComponent<'static>> {
+    let chunk = PaginatedChunk::new(entries.len(), page, HISTORY_PAGE_SIZE);
+
+    let list = if entries.is_empty() {
+        "*No splashes found.*".to_string()
+    } else {
+        entries[chunk.range.clone()]
+            .iter()
+            .map(|entry| {
+                format!(
+                    "- {} — {} — {}",
+                    discord_timestamp(entry.timestamp, TimestampStyle::ShortDateTime),
+                    entry.hub.as_deref().unwrap_or("Unknown hub"),
+                    entry.link
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut components = vec![CreateContainerComponent::TextDisplay(
+        CreateTextDisplay::new(format!(
+            "## Splash History
+{} has **{}** counted splashes since {}.
+{list}",
+            user.mention(),
+            entries.len(),
+            discord_timestamp(start, TimestampStyle::LongDate)
+        )),
+    )];
+    if !id_prefix.is_empty() && chunk.total_pages > 1 {
+        components.push(page_navigation(id_prefix, &chunk));
+    }
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_color(YELLOW),
+    )]
+}
@@ -1,28 +1,836 @@
-use anyhow::Result;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-use crate::shared::Context;
-use crate::splashes::splashlist;
+use anyhow::{Result, anyhow, bail};
+use poise::{
+    ChoiceParameter, CreateReply,
+    serenity_prelude::{
+        CreateAllowedMentions, CreateAttachment, CreateComponent, CreateContainer,
+        CreateContainerComponent, CreateTextDisplay, GenericChannelId, Mentionable as _,
+        MessageFlags,
+        colours::{branding::YELLOW, css::POSITIVE},
+    },
+};
+use tokio::sync::watch;
+
+use crate::error::UserError;
+use crate::scheduler::db::{
+    LiveSplashList, SetLiveSplashList, SetSplashListSchedule, SetWeeklyChartConfig,
+    WeeklyChartConfig,
+};
+use crate::shared::{
+    Context,
+    db::{
+        GetSplashIngestion, GetSplashThanksCounts, GetStoredSplashes, GetThankedSplashes,
+        SetSplashGoal, SetSplashImageRequired, SetSplashIngestion,
+    },
+    feature::{self, Feature},
+    time::{TimestampStyle, discord_timestamp},
+    types::{Bingo, SplashIngestion},
+};
+use crate::splashes::{
+    fetch::FetchProgress,
+    ingestion::SplasherResolver,
+    palette::TopSplashers,
+    splashlist::{
+        self, BingoSelector, ChartKind, ChartOptions, ChartSize, SplashMonth, SplashPeriod,
+    },
+    ty_tracking::THANKS_WINDOW_SECS,
+};
+
+#[poise::command(
+    slash_command,
+    subcommand_required,
+    subcommands(
+        "send",
+        "history",
+        "goal",
+        "require_image",
+        "ingestion",
+        "schedule",
+        "live",
+        "weekly_chart",
+        "export",
+        "thanks"
+    )
+)]
+pub async fn splashlist(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+#[derive(ChoiceParameter)]
+enum ChartKindOption {
+    #[name = "Daily distribution"]
+    Distribution,
+    #[name = "Splasher shares (pie chart)"]
+    Splashers,
+    #[name = "Cumulative splashes"]
+    Cumulative,
+    #[name = "Hour-of-day heatmap"]
+    Heatmap,
+}
+
+impl From<ChartKindOption> for ChartKind {
+    fn from(option: ChartKindOption) -> Self {
+        match option {
+            ChartKindOption::Distribution => ChartKind::Distribution,
+            ChartKindOption::Splashers => ChartKind::Splashers,
+            ChartKindOption::Cumulative => ChartKind::Cumulative,
+            ChartKindOption::Heatmap => ChartKind::Heatmap,
+        }
+    }
+}
+
+#[derive(ChoiceParameter)]
+enum ChartSizeOption {
+    #[name = "Compact (mobile)"]
+    Compact,
+    Standard,
+    #[name = "High resolution (desktop)"]
+    HighDpi,
+}
+
+impl From<ChartSizeOption> for ChartSize {
+    fn from(option: ChartSizeOption) -> Self {
+        match option {
+            ChartSizeOption::Compact => ChartSize::COMPACT,
+            ChartSizeOption::Standard => ChartSize::STANDARD,
+            ChartSizeOption::HighDpi => ChartSize::HIGH_DPI,
+        }
+    }
+}
+
+#[derive(ChoiceParameter)]
+enum TopSplashersOption {
+    #[name = "Top 3"]
+    Three,
+    #[name = "Top 5"]
+    Five,
+}
+
+impl From<TopSplashersOption> for TopSplashers {
+    fn from(option: TopSplashersOption) -> Self {
+        match option {
+            TopSplashersOption::Three => TopSplashers::Three,
+            TopSplashersOption::Five => TopSplashers::Five,
+        }
+    }
+}
 
 /// Create and send the splashlist
 #[poise::command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES"
 )]
-pub async fn splashlist(
+async fn send(
     ctx: Context<'_>,
     #[description = "Send the splash list as an ephemeral message (for prior inspection)"]
     ephemeral: Option<bool>,
+    #[description = "Which chart to include (defaults to the daily distribution)"] chart: Option<
+        ChartKindOption,
+    >,
+    #[description = "Size of the chart (defaults to standard, compact is best on mobile)"]
+    size: Option<ChartSizeOption>,
+    #[description = "Attach the chart as an SVG file instead of an embedded image"] svg: Option<
+        bool,
+    >,
+    #[description = "How many top splashers to highlight (defaults to 3)"] top: Option<
+        TopSplashersOption,
+    >,
+    #[description = "Bingo to create the splash list for: current, previous or an ID like #42 or extreme 3"]
+    bingo: Option<String>,
 ) -> Result<()> {
-    let ephemeral = ephemeral.unwrap_or(false);
+    let selector = match bingo {
+        Some(input) => BingoSelector::parse(&input)?,
+        None => BingoSelector::Current,
+    };
+
+    send_splash_list(
+        ctx,
+        selector,
+        ephemeral.unwrap_or(false),
+        chart_options(chart, size, svg, top),
+    )
+    .await
+}
+
+/// Splash lists further back require scanning large parts of the splashes channel
+const MAX_HISTORY_MONTHS: i32 = 12;
+
+/// Resolves the selected bingo, making sure past bingos may be accessed
+async fn resolve_period(ctx: Context<'_>, selector: BingoSelector) -> Result<SplashPeriod> {
+    if selector != BingoSelector::Current {
+        feature::require(ctx, Feature::SplashlistHistory).await?;
+    }
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let period = SplashPeriod::resolve(db, &data.api_handle, selector).await?;
 
+    if SplashMonth::of(period.start).months_ago() > MAX_HISTORY_MONTHS {
+        bail!(UserError(anyhow!(
+            "Splash lists can only be created for the past {MAX_HISTORY_MONTHS} months"
+        )));
+    }
+
+    Ok(period)
+}
+
+/// Create and send the splashlist of a past month
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES"
+)]
+async fn history(
+    ctx: Context<'_>,
+    #[description = "Month to create the splash list for, formatted as YYYY-MM (e.g. 2025-03)"]
+    month: String,
+    #[description = "Send the splash list as an ephemeral message (for prior inspection)"]
+    ephemeral: Option<bool>,
+    #[description = "Which chart to include (defaults to the daily distribution)"] chart: Option<
+        ChartKindOption,
+    >,
+    #[description = "Size of the chart (defaults to standard, compact is best on mobile)"]
+    size: Option<ChartSizeOption>,
+    #[description = "Attach the chart as an SVG file instead of an embedded image"] svg: Option<
+        bool,
+    >,
+    #[description = "How many top splashers to highlight (defaults to 3)"] top: Option<
+        TopSplashersOption,
+    >,
+) -> Result<()> {
+    let Some(month) = SplashMonth::parse(&month) else {
+        bail!(UserError(anyhow!(
+            "Failed to parse month '{month}', expected a format like `2025-03`"
+        )));
+    };
+
+    if month.months_ago() <= 0 {
+        bail!(UserError(anyhow!(
+            "{} isn't over yet, use `/splashlist send` instead",
+            month.name()
+        )));
+    }
+
+    send_splash_list(
+        ctx,
+        BingoSelector::Month(month),
+        ephemeral.unwrap_or(false),
+        chart_options(chart, size, svg, top),
+    )
+    .await
+}
+
+#[derive(Default, ChoiceParameter)]
+enum ExportFormat {
+    #[default]
+    #[name = "CSV"]
+    Csv,
+    #[name = "JSON"]
+    Json,
+}
+
+/// Export the raw splash records of a bingo as a file
+#[poise::command(slash_command)]
+async fn export(
+    ctx: Context<'_>,
+    #[description = "Month to export, formatted as YYYY-MM (defaults to the current bingo)"]
+    month: Option<String>,
+    #[description = "Bingo to export: current, previous or an ID like #42 or extreme 3"]
+    bingo: Option<String>,
+    #[description = "File format of the export (defaults to CSV)"] format: Option<ExportFormat>,
+) -> Result<()> {
+    let selector = match (month, bingo) {
+        (Some(_), Some(_)) => bail!(UserError(anyhow!(
+            "Please specify either a month or a bingo, not both"
+        ))),
+        (Some(input), None) => match SplashMonth::parse(&input) {
+            Some(month) if month.months_ago() == 0 => BingoSelector::Current,
+            Some(month) => BingoSelector::Month(month),
+            None => bail!(UserError(anyhow!(
+                "Failed to parse month '{input}', expected a format like `2025-03`"
+            ))),
+        },
+        (None, Some(input)) => BingoSelector::parse(&input)?,
+        (None, None) => BingoSelector::Current,
+    };
+
+    // resolving the period may have to query the current bingo from the API
+    ctx.defer_ephemeral().await?;
+
+    let period = resolve_period(ctx, selector).await?;
+
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    let bingo_splashes =
+        splashlist::load_bingo(ctx.http(), db, ctx.guild_id(), period, |_| {}).await?;
+
+    let mut usernames = HashMap::new();
+    for (_, user_id) in bingo_splashes.splashes.items() {
+        if usernames.contains_key(user_id) {
+            continue;
+        }
+        // the ID is still exported if the user can't be fetched (e.g. deleted accounts)
+        let username = match user_id.to_user(ctx.http()).await {
+            Ok(user) => user.name.to_string(),
+            Err(_) => String::new(),
+        };
+        usernames.insert(*user_id, username);
+    }
+
+    let (contents, extension) = match format.unwrap_or_default() {
+        ExportFormat::Csv => (
+            splashlist::export::to_csv(&bingo_splashes.splashes, bingo_splashes.start, &usernames),
+            "csv",
+        ),
+        ExportFormat::Json => (
+            splashlist::export::to_json(&bingo_splashes.splashes, bingo_splashes.start, &usernames),
+            "json",
+        ),
+    };
+    let file = CreateAttachment::bytes(
+        contents.into_bytes(),
+        format!("splashes_{}.{extension}", period.key()),
+    );
+
+    let container = CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(format!(
+                "## Exported Splashes
+The attached file contains all {} splashes of {}.",
+                bingo_splashes.splashes.items().len(),
+                bingo_splashes.bingo
+            )),
+        )])
+        .accent_color(POSITIVE),
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![container])
+            .attachment(file)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn chart_options(
+    kind: Option<ChartKindOption>,
+    size: Option<ChartSizeOption>,
+    svg: Option<bool>,
+    top: Option<TopSplashersOption>,
+) -> ChartOptions {
+    ChartOptions {
+        kind: kind.map(ChartKind::from).unwrap_or_default(),
+        size: size.map(ChartSize::from).unwrap_or_default(),
+        svg: svg.unwrap_or(false),
+        top: top.map(TopSplashers::from).unwrap_or_default(),
+    }
+}
+
+async fn send_splash_list(
+    ctx: Context<'_>,
+    selector: BingoSelector,
+    ephemeral: bool,
+    chart: ChartOptions,
+) -> Result<()> {
+    // resolving the period may have to query the current bingo from the API
     if ephemeral {
         ctx.defer_ephemeral().await?;
     } else {
         ctx.defer().await?;
     }
 
-    let message = splashlist::generate_message(&ctx).await?;
-    ctx.send(message.ephemeral(ephemeral)).await?;
+    let period = resolve_period(ctx, selector).await?;
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    let (progress_tx, mut progress_rx) = watch::channel(None);
+    let generate = splashlist::generate_message(
+        ctx.http(),
+        db,
+        ctx.guild_id(),
+        period,
+        chart,
+        move |progress| {
+            // receiver being dropped only means the list is already done
+            let _ = progress_tx.send(Some(progress));
+        },
+    );
+    tokio::pin!(generate);
+
+    // show scan progress in the deferred response if fetching the splashes takes a while
+    let mut progress_handle = None;
+    let mut last_update = Instant::now();
+    let message = loop {
+        tokio::select! {
+            message = &mut generate => break message?.into_reply(),
+            Ok(()) = progress_rx.changed() => {
+                let Some(progress) = *progress_rx.borrow_and_update() else {
+                    continue;
+                };
+                if last_update.elapsed() < PROGRESS_UPDATE_INTERVAL {
+                    continue;
+                }
+                last_update = Instant::now();
+
+                let reply = progress_reply(progress).ephemeral(ephemeral);
+                match &progress_handle {
+                    None => progress_handle = Some(ctx.send(reply).await?),
+                    Some(handle) => handle.edit(ctx, reply).await?,
+                }
+            }
+        }
+    };
+
+    match progress_handle {
+        Some(handle) => handle.edit(ctx, message).await?,
+        None => {
+            ctx.send(message.ephemeral(ephemeral)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_secs(3);
+
+fn progress_reply(progress: FetchProgress) -> CreateReply<'static> {
+    let reached = progress
+        .reached
+        .map(|timestamp| {
+            format!(
+                "\nReached messages from {}.",
+                discord_timestamp(timestamp, TimestampStyle::Relative)
+            )
+        })
+        .unwrap_or_default();
+
+    CreateReply::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(format!(
+                    "## Generating Splash List
+Scanned **{}** messages and found **{}** splashes so far...{reached}",
+                    progress.scanned_messages, progress.splashes
+                )),
+            )])
+            .accent_color(YELLOW),
+        )])
+}
+
+/// Splashers and unthanked splashes listed by `/splashlist thanks`
+const MAX_LISTED_THANKS: usize = 10;
+
+/// Show who gets thanked most in the thank-you channel and which splashes went unthanked
+#[poise::command(slash_command)]
+async fn thanks(
+    ctx: Context<'_>,
+    #[description = "Bingo to show thanks for: current, previous or an ID like #42 or extreme 3"]
+    bingo: Option<String>,
+) -> Result<()> {
+    let selector = match bingo {
+        Some(input) => BingoSelector::parse(&input)?,
+        None => BingoSelector::Current,
+    };
+    // resolving the period may have to query the current bingo from the API
+    ctx.defer_ephemeral().await?;
+
+    let period = resolve_period(ctx, selector).await?;
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+    let splashes_channel = data.guild_config(ctx.guild_id()).await?.splashes_channel;
+    let (start, end) = (period.start.unix_timestamp(), period.end.unix_timestamp());
+
+    let counts = db.request(GetSplashThanksCounts { start, end }).await??;
+    // splashes at the end of the bingo may be thanked after it ended
+    let thanked = db
+        .request(GetThankedSplashes {
+            start,
+            end: end + THANKS_WINDOW_SECS,
+        })
+        .await??;
+    // the most recent splashes may still be thanked
+    let cutoff = chrono::Utc::now().timestamp() - THANKS_WINDOW_SECS;
+    let unthanked: Vec<_> = db
+        .request(GetStoredSplashes {
+            channel: splashes_channel,
+            start,
+            end: end.min(cutoff),
+        })
+        .await??
+        .into_iter()
+        .filter(|splash| !thanked.contains(&splash.message_id))
+        .collect();
+
+    let most_thanked = if counts.is_empty() {
+        "*Nobody has been thanked yet*".to_string()
+    } else {
+        counts
+            .iter()
+            .take(MAX_LISTED_THANKS)
+            .enumerate()
+            .map(|(i, (splasher, count))| format!("{}. {}: **{count}**", i + 1, splasher.mention()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut resolver = SplasherResolver::new(db.request(GetSplashIngestion).await??);
+    let mut unthanked_list = Vec::new();
+    for splash in unthanked.iter().take(MAX_LISTED_THANKS) {
+        let link = splash.message_id.link(splash.channel, splash.guild_id);
+        let splasher = resolver
+            .resolve_stored(ctx.http(), splash)
+            .await?
+            .map(|splasher| format!(" by {}", splasher.mention()))
+            .unwrap_or_default();
+        unthanked_list.push(format!("- {link}{splasher}"));
+    }
+    let unthanked_text = match unthanked.len() {
+        0 => "*Every splash has been thanked*".to_string(),
+        total if total > MAX_LISTED_THANKS => format!(
+            "{}\n-# ...and {} more",
+            unthanked_list.join("\n"),
+            total - MAX_LISTED_THANKS
+        ),
+        _ => unthanked_list.join("\n"),
+    };
+
+    let text = format!(
+        "## Splash Thanks for {}
+### Most thanked splashers
+{most_thanked}
+### Unthanked splashes ({})
+{unthanked_text}
+-# Messages in the thank-you channel are attributed to the latest splash, reactions to them count as well.",
+        period.bingo,
+        unthanked.len()
+    );
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(YELLOW),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Set the target splash count for a bingo, shown on the splashlist
+#[poise::command(slash_command)]
+async fn goal(
+    ctx: Context<'_>,
+    #[description = "Target splash count for the whole bingo (0 removes the goal)"] target: u32,
+    #[description = "Bingo to set the goal for (defaults to the current bingo)"] bingo: Option<
+        String,
+    >,
+) -> Result<()> {
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    let bingo = match bingo {
+        Some(input) => Bingo::from_input(&input)?,
+        None => {
+            // the current bingo may have to be queried from the API
+            ctx.defer_ephemeral().await?;
+            data.api_handle.update_current_bingo(db).await?.0
+        }
+    };
+    let target = (target > 0).then_some(target);
+
+    db.request(SetSplashGoal { bingo, target }).await??;
+
+    let text = match target {
+        Some(target) => format!(
+            "## Updated Splash Goal
+The splash goal for {bingo} is now **{target}** splashes."
+        ),
+        None => format!(
+            "## Removed Splash Goal
+{bingo} no longer has a splash goal."
+        ),
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Require splash messages to include a screenshot in order to be counted
+#[poise::command(slash_command)]
+async fn require_image(
+    ctx: Context<'_>,
+    #[description = "Whether splashes without an attached or embedded image should be ignored"]
+    enabled: bool,
+) -> Result<()> {
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashImageRequired { required: enabled })
+        .await??;
+
+    let text = if enabled {
+        "## Enabled Image Requirement
+Splashes without a screenshot will no longer be counted, and their splashers will be asked to include one."
+    } else {
+        "## Disabled Image Requirement
+All splashes will be counted, regardless of whether they include a screenshot."
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[derive(ChoiceParameter)]
+enum IngestionOption {
+    #[name = "Message author"]
+    Author,
+    #[name = "Message content (webhooks and bridge bots)"]
+    Content,
+}
+
+/// Configure how splashers are determined from splash messages
+#[poise::command(slash_command, guild_only)]
+async fn ingestion(
+    ctx: Context<'_>,
+    #[description = "Where to take the splasher from; content mode looks for a mention or \"by <name>\""]
+    mode: IngestionOption,
+) -> Result<()> {
+    let Some(guild_id) = ctx.guild_id() else {
+        bail!(UserError(anyhow!(
+            "This command can only be used in a server"
+        )));
+    };
+
+    let ingestion = match mode {
+        IngestionOption::Author => SplashIngestion::Author,
+        // names in relayed messages are looked up among this server's members
+        IngestionOption::Content => SplashIngestion::Content { guild_id },
+    };
+
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashIngestion { ingestion })
+        .await??;
+
+    let text = match ingestion {
+        SplashIngestion::Author => {
+            "## Splashers From Author
+Splashes will be attributed to the author of each splash message."
+        }
+        SplashIngestion::Content { .. } => {
+            "## Splashers From Content
+Splashes will be attributed to the user mentioned in each splash message, or the member named in a \"by <name>\" pattern. Splashes whose splasher can't be determined will be ignored."
+        }
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Automatically post the splash list to a channel at the end of each bingo
+#[poise::command(slash_command)]
+async fn schedule(
+    ctx: Context<'_>,
+    #[description = "Channel to post the splash list to (disables scheduled posting if omitted)"]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetSplashListSchedule { channel })
+        .await??;
+
+    let text = match channel {
+        Some(channel) => format!(
+            "## Scheduled Splash List
+The splash list will be posted to {} at the end of each bingo.",
+            channel.mention()
+        ),
+        None => "## Disabled Scheduled Splash List
+The splash list will no longer be posted automatically."
+            .to_string(),
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Post a splash list of the current bingo that keeps updating as splashes come in
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | ATTACH_FILES"
+)]
+async fn live(
+    ctx: Context<'_>,
+    #[description = "Channel to post the live splash list to (disables updates if omitted)"]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    let db = ctx.data().db_for(ctx.guild_id());
+
+    let text = match channel {
+        Some(channel) => {
+            // lists set up while staged keep being updated, as they are already public
+            feature::require(ctx, Feature::LiveSplashlist).await?;
+            // resolving the period may have to query the current bingo from the API
+            ctx.defer_ephemeral().await?;
+
+            let period = resolve_period(ctx, BingoSelector::Current).await?;
+            let message = splashlist::generate_message(
+                ctx.http(),
+                db,
+                ctx.guild_id(),
+                period,
+                ChartOptions::default(),
+                |_| {},
+            )
+            .await?;
+            let message = channel
+                .send_message(ctx.http(), message.into_message())
+                .await?;
+
+            // a previous live splash list is left as is, it simply stops being updated
+            db.request(SetLiveSplashList {
+                live: Some(LiveSplashList {
+                    channel,
+                    message_id: message.id,
+                }),
+            })
+            .await??;
+
+            format!(
+                "## Live Splash List
+The splash list in {} will be updated every few minutes, along with the progress towards the \
+splash goal if one is set.",
+                channel.mention()
+            )
+        }
+        None => {
+            db.request(SetLiveSplashList { live: None }).await??;
+            "## Disabled Live Splash List
+The live splash list will no longer be updated, its last version is kept."
+                .to_string()
+        }
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Configure the chart of the past week's splashes, posted every Monday during a bingo
+#[poise::command(slash_command, rename = "weekly-chart")]
+async fn weekly_chart(
+    ctx: Context<'_>,
+    #[description = "Whether to post the weekly chart"] enabled: bool,
+    #[description = "Channel to post the chart to (defaults to the splashes channel)"]
+    channel: Option<GenericChannelId>,
+) -> Result<()> {
+    ctx.data()
+        .db_for(ctx.guild_id())
+        .request(SetWeeklyChartConfig {
+            config: WeeklyChartConfig { enabled, channel },
+        })
+        .await??;
+
+    let text = match (enabled, channel) {
+        (true, Some(channel)) => format!(
+            "## Enabled Weekly Chart
+A chart of the past week's splashes will be posted to {} every Monday during a bingo.",
+            channel.mention()
+        ),
+        (true, None) => "## Enabled Weekly Chart
+A chart of the past week's splashes will be posted to the splashes channel every Monday during a bingo."
+            .to_string(),
+        (false, _) => "## Disabled Weekly Chart
+The weekly splash chart will no longer be posted."
+            .to_string(),
+    };
+
+    ctx.send(
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(vec![CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(POSITIVE),
+            )])
+            .ephemeral(true),
+    )
+    .await?;
 
     Ok(())
 }
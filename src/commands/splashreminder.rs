@@ -1,27 +1,51 @@
-use std::sync::LazyLock;
+use std::{borrow::Cow, sync::LazyLock, time::Duration};
 
-use anyhow::{Context as _, Result, anyhow};
+use anyhow::{Context as _, Result, anyhow, bail};
 use poise::{
     CreateReply,
     serenity_prelude::{
-        CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
-        CreateTextDisplay, EmojiId, Mentionable as _, MessageFlags, colours::css::POSITIVE,
+        ButtonStyle, CreateActionRow, CreateAllowedMentions, CreateButton, CreateComponent,
+        CreateContainer, CreateContainerComponent, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateTextDisplay, EmojiId, Event, GenericChannelId,
+        GuildId, Http, Interaction, Mentionable as _, MessageFlags, collector,
+        colours::{
+            branding::YELLOW,
+            css::{DANGER, POSITIVE},
+        },
+        futures::StreamExt as _,
     },
 };
 use regex::Regex;
 
-use crate::config::{SPLASH_REMINDER_CHANNEL, SPLASH_REMINDER_ROLE};
+use crate::config::MENU_TIMEOUT_SECS;
 use crate::error::UserError;
-use crate::shared::{Context, db::SetSplashReminder};
+use crate::shared::{
+    Context,
+    db::{GetSplashReminderConfig, SetSplashReminder, SetSplashReminderConfig},
+    menu::generate_id,
+    types::{REMINDER_PLACEHOLDERS, SplashReminderConfig},
+};
+use crate::splash_reminder::modal::{ReminderConfig, ReminderConfigValidated};
 
 static EMOJI_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<a?:.+:(\d+)>$").unwrap());
 
-/// Manage splash reminders sent after 1 hour or a number of reactions on the latest splash
+/// Bounds of the configurable reminder delay, in minutes
+const MIN_DELAY_MINUTES: u64 = 5;
+const MAX_DELAY_MINUTES: u64 = 360;
+
 #[poise::command(
     slash_command,
+    subcommand_required,
+    subcommands("toggle", "config"),
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | READ_MESSAGE_HISTORY"
 )]
-pub async fn splashreminder(
+pub async fn splashreminder(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// Enable or disable splash reminders sent after a delay or a number of reactions on the latest splash
+#[poise::command(slash_command)]
+async fn toggle(
     ctx: Context<'_>,
     #[description = "Whether to enable splash reminders"] enable: bool,
     #[description = "Emoji to use for reaction count; must be a server-specific emoji (feature disabled if omitted)"]
@@ -46,21 +70,27 @@ pub async fn splashreminder(
         None
     };
 
-    ctx.data()
-        .db_handle
-        .request(SetSplashReminder {
-            enabled: enable,
-            emoji: emoji_id,
-            emoji_count: reaction_count,
-        })
-        .await??;
+    let db = ctx.data().db_for(ctx.guild_id());
+    db.request(SetSplashReminder {
+        enabled: enable,
+        emoji: emoji_id,
+        emoji_count: reaction_count,
+    })
+    .await??;
+    let config = db.request(GetSplashReminderConfig).await??;
+    let role = ctx
+        .data()
+        .guild_config(ctx.guild_id())
+        .await?
+        .splash_reminder_role;
 
     let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(if enable {
         Cow::Owned(format!(
             "## Enabled Splash Reminders
-{} will be pinged in {} when there hasn't been a splash for 1h during a bingo{}.",
-            SPLASH_REMINDER_ROLE.mention(),
-            SPLASH_REMINDER_CHANNEL.mention(),
+{} will be pinged in {} when there hasn't been a splash for {} during a bingo{}.",
+            role.mention(),
+            config.channel.mention(),
+            config.delay_text(),
             if let Some(emoji_mention) = emoji {
                 format!(
                     ", or when the latest splash message has {}+ {emoji_mention} reactions",
@@ -89,3 +119,200 @@ Splash reminders will no longer be sent.",
 
     Ok(())
 }
+
+/// Configure the splash reminder delay, channel and message
+#[poise::command(slash_command, guild_only)]
+async fn config(ctx: Context<'_>) -> Result<()> {
+    let guild_id = ctx
+        .guild_id()
+        .context(UserError(anyhow!("Command invoked outside of a guild")))?;
+    let db = ctx.data().db_for(Some(guild_id));
+
+    let mut config = db.request(GetSplashReminderConfig).await??;
+
+    let id_prefix = format!("splashreminder:config:{}", generate_id());
+
+    let handle = ctx
+        .send(
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(config_menu(&config, &id_prefix))
+                .ephemeral(true),
+        )
+        .await?;
+
+    // only the invoking user can see the ephemeral response, so no need to check who clicked
+    let prefix = id_prefix.clone();
+    let mut stream = collector::collect(ctx.serenity_context(), move |event| match event {
+        Event::InteractionCreate(event) => match &event.interaction {
+            Interaction::Component(interaction)
+                if interaction.data.custom_id.starts_with(&prefix) =>
+            {
+                Some(event.interaction.clone())
+            }
+            Interaction::Modal(interaction) if interaction.data.custom_id.starts_with(&prefix) => {
+                Some(event.interaction.clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    });
+
+    let timeout = Duration::from_secs(MENU_TIMEOUT_SECS);
+    while let Ok(Some(interaction)) = tokio::time::timeout(timeout, stream.next()).await {
+        match interaction {
+            Interaction::Component(interaction) => {
+                let modal = ReminderConfig::create_prefilled(
+                    &id_prefix,
+                    (config.delay_secs / 60).to_string().into(),
+                    config.channel.get().to_string().into(),
+                    config.template.as_str().into(),
+                );
+                interaction
+                    .create_response(ctx.http(), CreateInteractionResponse::Modal(modal))
+                    .await?;
+            }
+            Interaction::Modal(interaction) => {
+                let result = match ReminderConfig::validate(&interaction.data.components) {
+                    Ok(values) => parse_config(ctx.http(), guild_id, &values).await,
+                    Err(err) => Err(err),
+                };
+
+                let response = match result {
+                    Ok(new_config) => {
+                        db.request(SetSplashReminderConfig {
+                            config: new_config.clone(),
+                        })
+                        .await??;
+                        config = new_config;
+
+                        CreateInteractionResponse::UpdateMessage(
+                            CreateInteractionResponseMessage::new()
+                                .flags(MessageFlags::IS_COMPONENTS_V2)
+                                .allowed_mentions(CreateAllowedMentions::new())
+                                .components(config_menu(&config, &id_prefix)),
+                        )
+                    }
+                    // keep the menu open, so that the input can be corrected
+                    Err(err) => CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .flags(MessageFlags::IS_COMPONENTS_V2)
+                            .components(vec![CreateComponent::Container(
+                                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                                    CreateTextDisplay::new(format!(
+                                        "## Invalid Configuration
+{err:#}"
+                                    )),
+                                )])
+                                .accent_color(DANGER),
+                            )])
+                            .ephemeral(true),
+                    ),
+                };
+
+                interaction.create_response(ctx.http(), response).await?;
+            }
+            _ => (),
+        }
+    }
+
+    // remove the edit button once the menu has expired
+    handle
+        .edit(
+            ctx,
+            CreateReply::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(config_menu(&config, "")),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn parse_config(
+    http: &Http,
+    guild_id: GuildId,
+    values: &ReminderConfigValidated,
+) -> Result<SplashReminderConfig> {
+    let delay_minutes: u64 = values
+        .delay
+        .trim()
+        .parse()
+        .context(UserError(anyhow!("Failed to parse delay: Invalid number")))?;
+    if !(MIN_DELAY_MINUTES..=MAX_DELAY_MINUTES).contains(&delay_minutes) {
+        bail!(UserError(anyhow!(
+            "The delay must be between {MIN_DELAY_MINUTES} and {MAX_DELAY_MINUTES} minutes"
+        )));
+    }
+
+    let channel = GenericChannelId::new(values.channel_id.trim().parse().context(UserError(
+        anyhow!("Failed to parse channel ID: Invalid format"),
+    ))?);
+    let in_guild = http
+        .get_channel(channel)
+        .await
+        .ok()
+        .and_then(|c| c.guild())
+        .is_some_and(|c| c.base.guild_id == guild_id);
+    if !in_guild {
+        bail!(UserError(anyhow!(
+            "Failed to validate channel ID: Invalid channel in current guild"
+        )));
+    }
+
+    let template = values.template.trim().to_string();
+    if !template.contains("{reason}") {
+        bail!(UserError(anyhow!(
+            "The message must contain the `{{reason}}` placeholder"
+        )));
+    }
+
+    Ok(SplashReminderConfig {
+        delay_secs: delay_minutes * 60,
+        channel,
+        template,
+    })
+}
+
+/// Renders the current configuration, without the edit button if `id_prefix` is empty
+fn config_menu(config: &SplashReminderConfig, id_prefix: &str) -> Vec<CreateComponent<'static>> {
+    let mut components = vec![CreateContainerComponent::TextDisplay(
+        CreateTextDisplay::new(format!(
+            "## Splash Reminder Configuration
+- Delay: **{}**
+- Channel: {}
+### Message
+{}
+-# Placeholders: {}",
+            config.delay_text(),
+            config.channel.mention(),
+            config.render(
+                "*It has been ... since the last splash!*",
+                "`@Splash Needed`"
+            ),
+            REMINDER_PLACEHOLDERS
+                .iter()
+                .map(|p| format!("`{p}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+    )];
+
+    if !id_prefix.is_empty() {
+        components.push(CreateContainerComponent::ActionRow(
+            CreateActionRow::Buttons(
+                vec![
+                    CreateButton::new(format!("{id_prefix}:edit"))
+                        .label("Edit")
+                        .style(ButtonStyle::Primary),
+                ]
+                .into(),
+            ),
+        ));
+    }
+
+    vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_color(YELLOW),
+    )]
+}
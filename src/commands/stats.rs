@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateMediaGallery, CreateMediaGalleryItem, CreateSeparator, CreateTextDisplay,
+        CreateUnfurledMediaItem, MessageFlags, colours::branding::YELLOW,
+    },
+};
+
+use crate::role::{
+    db::{
+        cache::{
+            CountImmortalHolders, GetBingoRankDistribution, GetBlackoutDistribution,
+            GetNetworkBingoCompletionCounts,
+        },
+        link::CountLinkedUsers,
+    },
+    types::NetworkBingo,
+};
+use crate::shared::Context;
+use crate::splashes::splashlist;
+
+/// Blackout counts are grouped into ranges beyond this many bars, keeping labels legible
+const MAX_BARS: u32 = 20;
+
+#[poise::command(slash_command, subcommand_required, subcommands("server"))]
+pub async fn stats(_ctx: Context<'_>) -> Result<()> {
+    unreachable!("This shouldn't be possible to invoke");
+}
+
+/// View the combined bingo stats of all linked members
+#[poise::command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | ATTACH_FILES"
+)]
+async fn server(ctx: Context<'_>) -> Result<()> {
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let db = data.db_for(ctx.guild_id());
+
+    let linked = db.request(CountLinkedUsers).await??;
+    let blackouts = db.request(GetBlackoutDistribution).await??;
+    let ranks = db.request(GetBingoRankDistribution).await??;
+    let immortal = db.request(CountImmortalHolders).await??;
+    let network_bingos = db.request(GetNetworkBingoCompletionCounts).await??;
+
+    // only players who requested roles since the caches were last invalidated are included
+    let cached: u32 = blackouts.values().sum();
+    let total_blackouts: u32 = blackouts
+        .iter()
+        .map(|(blackouts, players)| blackouts * players)
+        .sum();
+
+    let overview = CreateTextDisplay::new(format!(
+        "## Server Stats
+**{linked}** linked members, **{cached}** of which have cached stats.
+### Blackouts
+**{total_blackouts}** blackouts in total, **{:.1}** per player on average.
+### Immortal
+**{immortal}** players hold the immortal role.",
+        if cached == 0 {
+            0.0
+        } else {
+            total_blackouts as f64 / cached as f64
+        },
+    ));
+
+    // rank 0 is a common bingo pet as well as none at all, so only players without a cached rank
+    // are counted as unranked
+    let unranked = linked.saturating_sub(ranks.values().sum());
+    let mut rank_list = ranks
+        .iter()
+        .map(|(rank, players)| format!("- Bingo Rank {rank}: **{players}**"))
+        .chain((unranked > 0).then(|| format!("- No cached rank: **{unranked}**")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if rank_list.is_empty() {
+        rank_list = "*None*".to_string();
+    }
+    let network_list = NetworkBingo::all()
+        .into_iter()
+        .map(|bingo| {
+            format!(
+                "- {bingo}: **{}**",
+                network_bingos.get(&bingo.id()).copied().unwrap_or(0)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let details = CreateTextDisplay::new(format!(
+        "### Bingo Ranks
+{rank_list}
+### Network Bingo Completions
+{network_list}
+-# Based on the stats cached during role requests, so recent progress may be missing."
+    ));
+
+    let mut components = vec![CreateContainerComponent::TextDisplay(overview)];
+    let mut reply = CreateReply::new().flags(MessageFlags::IS_COMPONENTS_V2);
+
+    if !blackouts.is_empty() {
+        let chart =
+            splashlist::bar_chart(blackout_bars(&blackouts), "Blackouts", "Players").await?;
+        components.push(CreateContainerComponent::MediaGallery(
+            CreateMediaGallery::new(vec![CreateMediaGalleryItem::new(
+                CreateUnfurledMediaItem::new("attachment://blackouts.png"),
+            )]),
+        ));
+        reply = reply.attachment(CreateAttachment::bytes(chart, "blackouts.png"));
+    }
+
+    components.push(CreateContainerComponent::Separator(CreateSeparator::new(
+        true,
+    )));
+    components.push(CreateContainerComponent::TextDisplay(details));
+
+    ctx.send(reply.components(vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_color(YELLOW),
+    )]))
+    .await?;
+
+    Ok(())
+}
+
+/// Players per blackout count, grouped into equally sized ranges if there are too many counts.
+/// Counts without any players are included, so that gaps stay visible.
+fn blackout_bars(blackouts: &BTreeMap<u32, u32>) -> Vec<(String, u32)> {
+    let max = blackouts.keys().last().copied().unwrap_or(0);
+    let width = max / MAX_BARS + 1;
+
+    (0..=max / width)
+        .map(|bucket| {
+            let (start, end) = (bucket * width, bucket * width + width - 1);
+            let players = blackouts
+                .range(start..=end)
+                .map(|(_, players)| players)
+                .sum();
+            let label = if width == 1 {
+                start.to_string()
+            } else {
+                format!("{start}–{end}")
+            };
+            (label, players)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_blackouts_into_ranges() {
+        let few = BTreeMap::from([(0, 3), (2, 5)]);
+        assert_eq!(
+            blackout_bars(&few),
+            [
+                ("0".to_string(), 3),
+                ("1".to_string(), 0),
+                ("2".to_string(), 5)
+            ]
+        );
+
+        let many = BTreeMap::from([(0, 1), (1, 2), (45, 4)]);
+        let bars = blackout_bars(&many);
+        assert_eq!(bars.len(), 16);
+        assert_eq!(bars[0], ("0–2".to_string(), 3));
+        assert_eq!(bars[15], ("45–47".to_string(), 4));
+    }
+}
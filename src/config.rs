@@ -2,9 +2,14 @@ use poise::serenity_prelude::{GenericChannelId, RoleId, UserId};
 
 // path to store the database file
 pub const DB_PATH: &str = "./data/db.sqlite3";
+// separate database used for the sandbox guild (see `SANDBOX_GUILD_ID` environment variable)
+pub const SANDBOX_DB_PATH: &str = "./data/sandbox.sqlite3";
 // path to the directory containing SQL scripts for `/debug sql script`
 pub const DB_SCRIPTS_DIR: &str = "./data/scripts";
 
+// database requests taking longer are logged, unless overridden by `DB_SLOW_QUERY_MS`
+pub const DB_SLOW_QUERY_MS: u64 = 100;
+
 // how long HoB's and role request's interactive configuration menus should stay valid for
 pub const MENU_TIMEOUT_SECS: u64 = 180;
 
@@ -22,7 +27,9 @@ pub const SPLASHER_ROLE: RoleId = RoleId::new(916073464681603103);
 pub const SPLASH_REMINDER_ROLE: RoleId = RoleId::new(1038620889278849044);
 // where to send `@Splash Needed` pings
 pub const SPLASH_REMINDER_CHANNEL: GenericChannelId = GenericChannelId::new(989395745134235669);
-// mentioned in splash list message
+// how long after the latest splash the reminder is sent, unless configured otherwise
+pub const SPLASH_REMINDER_DELAY_SECS: u64 = 3600;
+// mentioned in splash list message, and where monthly splash recaps are posted
 pub const TY_CHANNEL: GenericChannelId = GenericChannelId::new(1006007462043852910);
 // part of error messages
 pub const BOT_MAINTAINER: UserId = UserId::new(821735954128830504);
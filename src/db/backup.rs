@@ -0,0 +1,185 @@
+//! Consistent snapshots of the database, created on the database thread using SQLite's online
+//! backup API. Snapshots are uploaded by `/debug backup` or written to `BACKUP_DIR`, which also
+//! enables a nightly backup keeping the latest `BACKUP_KEEP` snapshots.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::{NaiveTime, Utc};
+use poise::serenity_prelude::Context as SerenityContext;
+use rusqlite::{Connection, DatabaseName};
+use tokio::fs;
+use tracing::{error, info};
+
+use crate::db::{DbHandle, DbRequest};
+use crate::error::UserError;
+use crate::shared::{BotData, task::spawn_background};
+
+/// Nightly backups run at this time, when the fewest splashes and role requests happen
+const NIGHTLY_TIME_UTC: NaiveTime = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+/// Nightly backups kept unless overridden by `BACKUP_KEEP`
+const DEFAULT_KEEP: usize = 7;
+
+static CONFIG: OnceLock<BackupConfig> = OnceLock::new();
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    pub dir: PathBuf,
+    /// number of nightly backups to keep, older ones are deleted after each backup
+    pub keep: usize,
+}
+
+impl BackupConfig {
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(dir) = env::var("BACKUP_DIR") else {
+            return Ok(None);
+        };
+        let keep = match env::var("BACKUP_KEEP") {
+            Ok(keep) => keep.trim().parse().context("Invalid backup count")?,
+            Err(_) => DEFAULT_KEEP,
+        };
+        Ok(Some(Self {
+            dir: dir.into(),
+            keep,
+        }))
+    }
+}
+
+pub fn init(config: BackupConfig) {
+    CONFIG
+        .set(config)
+        .expect("backup config should only be initialised once");
+}
+
+pub fn config() -> Option<&'static BackupConfig> {
+    CONFIG.get()
+}
+
+/// Copies the entire database to `path` in a single step, so other requests wait until it's done
+struct Backup {
+    path: PathBuf,
+}
+impl DbRequest for Backup {
+    type ReturnValue = rusqlite::Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.backup(DatabaseName::Main, &self.path, None)
+    }
+}
+
+/// Writes a snapshot to `dir`, named after the kind of backup and the current time so that file
+/// names sort chronologically
+pub async fn backup_to(db: &DbHandle, dir: &Path, kind: &str) -> Result<PathBuf> {
+    fs::create_dir_all(dir)
+        .await
+        .context("Failed to create backup directory")?;
+
+    let path = dir.join(format!(
+        "{kind}-{}.sqlite3",
+        Utc::now().format("%Y-%m-%dT%H%M%S")
+    ));
+    db.request(Backup { path: path.clone() })
+        .await?
+        .context("Failed to back up database")?;
+
+    Ok(path)
+}
+
+/// Snapshot of the database as bytes, using a temporary file. Snapshots larger than `max_len` aren't
+/// read into memory, but fail with a [`UserError`].
+pub async fn backup_bytes(db: &DbHandle, max_len: u64) -> Result<Vec<u8>> {
+    let path = backup_to(db, &env::temp_dir(), "bb-bot-upload").await?;
+    let bytes = read_limited(&path, max_len).await;
+    _ = fs::remove_file(&path).await;
+    bytes
+}
+
+async fn read_limited(path: &Path, max_len: u64) -> Result<Vec<u8>> {
+    let len = fs::metadata(path)
+        .await
+        .context("Failed to read backup size")?
+        .len();
+    if len > max_len {
+        bail!(UserError(anyhow!(
+            "The backup is too large to upload ({} KiB), please configure `BACKUP_DIR` instead",
+            len / 1024
+        )));
+    }
+    fs::read(path).await.context("Failed to read backup")
+}
+
+/// Starts the nightly backup of the production database if `BACKUP_DIR` is configured
+pub fn start_nightly_backups(ctx: &SerenityContext) {
+    let Some(config) = config() else {
+        return;
+    };
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let data = ctx.data::<BotData>();
+
+    spawn_background("nightly backup", Arc::clone(&ctx.http), async move {
+        loop {
+            tokio::time::sleep(until_next_run()).await;
+
+            match nightly_backup(&data.db_handle, config).await {
+                Ok(path) => info!("Backed up database to '{}'", path.display()),
+                Err(err) => error!("Failed to run nightly backup: {err:#}"),
+            }
+        }
+    });
+}
+
+fn until_next_run() -> Duration {
+    let now = Utc::now();
+    let today = now.date_naive().and_time(NIGHTLY_TIME_UTC).and_utc();
+    let next = if today > now {
+        today
+    } else {
+        today + chrono::Duration::days(1)
+    };
+    (next - now).to_std().unwrap_or_default()
+}
+
+async fn nightly_backup(db: &DbHandle, config: &BackupConfig) -> Result<PathBuf> {
+    let path = backup_to(db, &config.dir, "nightly").await?;
+    rotate(&config.dir, config.keep).await?;
+    Ok(path)
+}
+
+/// Deletes all but the latest `keep` nightly backups, leaving manual ones untouched. The latest
+/// backup is always kept, even if `keep` is 0.
+async fn rotate(dir: &Path, keep: usize) -> Result<()> {
+    let mut backups = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("nightly-") && name.ends_with(".sqlite3"))
+        {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+
+    let outdated = backups.len().saturating_sub(keep.max(1));
+    for path in &backups[..outdated] {
+        fs::remove_file(path)
+            .await
+            .with_context(|| format!("Failed to delete old backup '{}'", path.display()))?;
+    }
+
+    Ok(())
+}
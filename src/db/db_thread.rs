@@ -1,19 +1,21 @@
-use std::thread;
+use std::{thread, time::Duration};
 
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 use tokio::sync::{mpsc, oneshot};
 
-use crate::config::DB_PATH;
-use crate::db::ErasedDbRequest;
+use crate::db::{ErasedDbRequest, migrations};
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub fn start_db_thread(
     mut rx: mpsc::Receiver<Box<dyn ErasedDbRequest>>,
+    path: &'static str,
 ) -> oneshot::Receiver<Result<()>> {
     let (ready_tx, ready_rx) = oneshot::channel();
 
     thread::spawn(move || {
-        let mut conn = match initialise_database() {
+        let mut conn = match initialise_database(path) {
             Ok(conn) => conn,
             Err(err) => {
                 let _ = ready_tx.send(Err(err.context("Failed to initialise database")));
@@ -31,14 +33,16 @@ pub fn start_db_thread(
     ready_rx
 }
 
-fn initialise_database() -> Result<Connection> {
-    let mut conn = Connection::open(DB_PATH).context("Unable to load database file")?;
+fn initialise_database(path: &str) -> Result<Connection> {
+    let mut conn = Connection::open(path).context("Unable to load database file")?;
 
     conn.pragma_update(None, "foreign_keys", true)
         .context("Failed to configure database")?;
+    // processes running separate shards share the database file, and wait for each other's writes
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("Failed to configure database")?;
 
-    crate::hob::db::initialise_tables(&mut conn)?;
-    crate::role::db::initialise_tables(&mut conn)?;
-    crate::shared::db::initialise_tables(&mut conn)?;
+    migrations::run(&mut conn).context("Failed to migrate database schema")?;
+    crate::role::db::clear_startup_caches(&conn)?;
     Ok(conn)
 }
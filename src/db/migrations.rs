@@ -0,0 +1,516 @@
+//! Ordered schema changes, applied on startup.
+//!
+//! Each database tracks the last applied migration in the `schema_version` table. Databases
+//! created before migrations were introduced start at version 0. The baseline only creates missing
+//! tables, so it doesn't change existing ones whose definition differs, which needs a separate
+//! migration instead.
+//!
+//! To change the schema, append a migration with the next version. Migrations that were already
+//! released must never be edited, as existing deployments won't apply them again.
+
+use anyhow::{Context as _, Result, bail};
+use rusqlite::{Connection, OptionalExtension as _, TransactionBehavior, params};
+use tracing::info;
+
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        apply: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "Mojang profile cache",
+        apply: mojang_profile_cache,
+    },
+    Migration {
+        version: 3,
+        description: "weekly splash chart",
+        apply: weekly_chart,
+    },
+    Migration {
+        version: 4,
+        description: "skipped splash reminders",
+        apply: splash_reminder_skips,
+    },
+    Migration {
+        version: 5,
+        description: "manual role applications",
+        apply: manual_role_applications,
+    },
+    Migration {
+        version: 6,
+        description: "concurrent splash reminders",
+        apply: splash_reminder_timers,
+    },
+    Migration {
+        version: 7,
+        description: "role update counts",
+        apply: role_update_counts,
+    },
+    Migration {
+        version: 8,
+        description: "splash thanks",
+        apply: splash_thanks,
+    },
+    Migration {
+        version: 9,
+        description: "splasher onboarding",
+        apply: splasher_onboarding,
+    },
+    Migration {
+        version: 10,
+        description: "automatic role creation",
+        apply: role_auto_create,
+    },
+    Migration {
+        version: 11,
+        description: "network bingo registry",
+        apply: network_bingo_registry,
+    },
+    Migration {
+        version: 12,
+        description: "hob entry attachments",
+        apply: hob_attachments,
+    },
+    Migration {
+        version: 13,
+        description: "hob entry tags",
+        apply: hob_tags,
+    },
+    Migration {
+        version: 14,
+        description: "link verification",
+        apply: link_verification,
+    },
+    Migration {
+        version: 15,
+        description: "never reused hob subentry IDs",
+        apply: hob_subentry_autoincrement,
+    },
+    Migration {
+        version: 16,
+        description: "splash history cache keyed by bingo",
+        apply: splash_history_cache_bingo_keys,
+    },
+    Migration {
+        version: 17,
+        description: "live splash list",
+        apply: live_splashlist,
+    },
+    Migration {
+        version: 18,
+        description: "configurable splash reminder role",
+        apply: guild_config_reminder_role,
+    },
+    Migration {
+        version: 19,
+        description: "link verification retries",
+        apply: link_verification_retries,
+    },
+];
+
+fn baseline(conn: &Connection) -> rusqlite::Result<()> {
+    crate::hob::db::baseline_schema(conn)?;
+    crate::role::db::baseline_schema(conn)?;
+    crate::scheduler::db::baseline_schema(conn)?;
+    crate::shared::db::baseline_schema(conn)
+}
+
+fn mojang_profile_cache(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Username of each looked up UUID, shared by lookups in both directions
+        CREATE TABLE role_mojang_profile_cache (
+            uuid TEXT PRIMARY KEY,
+            username TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX role_mojang_profile_cache_username
+        ON role_mojang_profile_cache (username COLLATE NOCASE);
+        ",
+    )
+}
+
+fn weekly_chart(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Overrides of the weekly splash chart, which is posted to the splashes channel by default
+        CREATE TABLE schedule_weekly_chart_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled INTEGER NOT NULL,
+            channel INTEGER
+        );
+
+        -- Weeks whose chart has already been posted, keyed by the start of their Monday in EST
+        CREATE TABLE schedule_weekly_chart_posts (
+            week INTEGER PRIMARY KEY,
+            message_id INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+fn splash_reminder_skips(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Splash messages marked by staff as not needing a reminder, e.g. because they were posted
+        -- right before the bingo's end or a planned break
+        CREATE TABLE splash_reminder_skips (
+            message_id INTEGER PRIMARY KEY
+        );
+        ",
+    )
+}
+
+fn manual_role_applications(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Applications posted in the manual role channel, along with the bot's reply carrying the
+        -- claim buttons and who handled the application
+        CREATE TABLE role_manual_applications (
+            message_id INTEGER PRIMARY KEY,
+            author INTEGER NOT NULL,
+            posted_at INTEGER NOT NULL,
+            panel_message_id INTEGER NOT NULL,
+            claimed_by INTEGER,
+            claimed_at INTEGER,
+            resolved_by INTEGER,
+            resolved_at INTEGER
+        );
+        CREATE INDEX role_manual_applications_author ON role_manual_applications (author);
+        ",
+    )
+}
+
+fn splash_reminder_timers(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Pending reminder of every splash, replacing the single latest splash, so that the timers
+        -- survive restarts
+        CREATE TABLE splash_reminder_timers (
+            message_id INTEGER PRIMARY KEY,
+            deadline INTEGER NOT NULL
+        );
+        INSERT INTO splash_reminder_timers (message_id, deadline)
+        SELECT latest_message_id, deadline FROM splash_reminder_state;
+        DROP TABLE splash_reminder_state;
+        ",
+    )
+}
+
+fn role_update_counts(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Daily role updates per entry point, including those without any changes, which aren't
+        -- recorded in the audit log
+        CREATE TABLE role_update_counts (
+            -- days since the unix epoch
+            day INTEGER NOT NULL,
+            -- same values as `role_audit_log.trigger`
+            trigger INTEGER NOT NULL,
+            -- 0 = updated, 1 = unchanged, 2 = failed
+            outcome INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY(day, trigger, outcome)
+        );
+        ",
+    )
+}
+
+fn splash_thanks(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Thank-you messages (and reactions to them) in the thank-you channel, each associated with
+        -- the most recent splash at the time
+        CREATE TABLE splash_thanks (
+            -- message in the thank-you channel, either sent or reacted to by `thanked_by`
+            source_message_id INTEGER NOT NULL,
+            thanked_by INTEGER NOT NULL,
+            splash_message_id INTEGER NOT NULL,
+            -- resolved when thanked, NULL if the splasher couldn't be determined
+            splasher INTEGER,
+            timestamp INTEGER NOT NULL,
+            PRIMARY KEY(source_message_id, thanked_by)
+        );
+        CREATE INDEX splash_thanks_timestamp ON splash_thanks (timestamp);
+        ",
+    )
+}
+
+fn splasher_onboarding(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Members who were sent the onboarding checklist after gaining the splasher role
+        CREATE TABLE splasher_onboarding (
+            user INTEGER PRIMARY KEY,
+            started_at INTEGER NOT NULL
+        );
+
+        -- Checklist items acknowledged by each member, keyed by the item's identifier
+        CREATE TABLE splasher_onboarding_acks (
+            user INTEGER NOT NULL REFERENCES splasher_onboarding(user) ON DELETE CASCADE,
+            item TEXT NOT NULL,
+            acknowledged_at INTEGER NOT NULL,
+            PRIMARY KEY(user, item)
+        );
+        ",
+    )
+}
+
+fn role_auto_create(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Creating roles for patterns without a matching role (optional, disabled if absent)
+        CREATE TABLE role_auto_create_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            -- colour of created roles, Discord's default if NULL
+            colour INTEGER
+        );
+        ",
+    )
+}
+
+fn network_bingo_registry(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Network bingos added after the ones built into the bot, IDs continue after the built-in
+        -- ones and index the bits of `role_network_bingo_cache.bingo_set`
+        CREATE TABLE network_bingo_registry (
+            id INTEGER PRIMARY KEY,
+            -- keys in the `seasonal` section of the player endpoint
+            season TEXT NOT NULL,
+            year TEXT NOT NULL,
+            name TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            registered_at INTEGER NOT NULL,
+            UNIQUE(season, year)
+        );
+        ",
+    )
+}
+
+fn hob_attachments(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Screenshots shown below HoB entries, in addition to images linked in their comment
+        -- Note: entries of both kinds share an ID space, so there is no foreign key
+        CREATE TABLE hob_entry_attachments (
+            entry_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            PRIMARY KEY(entry_id, position)
+        );
+        ",
+    )
+}
+
+fn hob_tags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Categories such as 'speedrun' or 'community', stored normalized to lowercase
+        -- Note: entries of both kinds share an ID space, so there is no foreign key
+        CREATE TABLE hob_entry_tags (
+            entry_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY(entry_id, tag)
+        );
+        CREATE INDEX idx_hob_entry_tags_tag ON hob_entry_tags(tag);
+        ",
+    )
+}
+
+fn link_verification(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- When the link was last compared against Hypixel's Discord setting, and when it last
+        -- matched. Links checked after their last match are flagged as mismatched.
+        ALTER TABLE role_users_linked ADD COLUMN last_checked INTEGER;
+        ALTER TABLE role_users_linked ADD COLUMN last_verified INTEGER;
+
+        -- Periodic re-verification of links (optional, disabled if absent)
+        CREATE TABLE role_link_verification_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            -- where the digest of mismatched links is posted for staff
+            channel INTEGER NOT NULL,
+            -- whether mismatched links are removed, instead of only being reported
+            auto_unlink INTEGER NOT NULL,
+            interval_days INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Databases created before subentry IDs were assigned by the database lack `AUTOINCREMENT`, so
+/// the IDs of deleted subentries could be reused. SQLite can't add it to an existing table, so the
+/// table is rebuilt.
+fn hob_subentry_autoincrement(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE hob_ongoing_subentries_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL,
+            player TEXT NOT NULL,
+            value TEXT NOT NULL,
+            bingo INTEGER NOT NULL,
+            bingo_kind INTEGER NOT NULL,
+            FOREIGN KEY(entry_id) REFERENCES hob_entries_ongoing(id) ON DELETE CASCADE
+        );
+        INSERT INTO hob_ongoing_subentries_new (id, entry_id, player, value, bingo, bingo_kind)
+        SELECT id, entry_id, player, value, bingo, bingo_kind FROM hob_ongoing_subentries;
+
+        -- foreign keys can't be disabled within the migration's transaction, so dropping the old
+        -- table cascades to the numeric values, which are restored afterwards
+        CREATE TEMP TABLE hob_subentry_numeric_values_backup AS
+        SELECT subentry_id, numeric_value FROM hob_subentry_numeric_values;
+
+        DROP TABLE hob_ongoing_subentries;
+        ALTER TABLE hob_ongoing_subentries_new RENAME TO hob_ongoing_subentries;
+
+        INSERT INTO hob_subentry_numeric_values (subentry_id, numeric_value)
+        SELECT subentry_id, numeric_value FROM hob_subentry_numeric_values_backup;
+        DROP TABLE hob_subentry_numeric_values_backup;
+        ",
+    )
+}
+
+fn splash_history_cache_bingo_keys(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- splash lists used to be cached per month as `YYYY-MM`, which is no longer looked up since
+        -- they are keyed by bingo like `extreme-3`. Their splashes are removed through the cascade.
+        DELETE FROM splash_history_cache WHERE month GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]';
+        ",
+    )
+}
+
+fn live_splashlist(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Message showing the current bingo's splash list, edited as splashes come in. Disabled if
+        -- absent
+        CREATE TABLE schedule_live_splashlist (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            channel INTEGER NOT NULL,
+            message_id INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+fn guild_config_reminder_role(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Role pinged by splash reminders, falls back to the compile-time default if NULL
+        ALTER TABLE guild_config ADD COLUMN splash_reminder_role INTEGER;
+        ",
+    )
+}
+
+fn link_verification_retries(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        -- Links whose check failed (e.g. during an outage) aren't marked as checked, but retried
+        -- with a backoff growing with the consecutive failures
+        ALTER TABLE role_users_linked ADD COLUMN check_failures INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE role_users_linked ADD COLUMN retry_after INTEGER;
+        ",
+    )
+}
+
+fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies all pending migrations in a single transaction, so that a failed migration leaves the
+/// database untouched
+pub fn run(conn: &mut Connection) -> Result<()> {
+    // acquires the write lock upfront, so that processes starting at the same time can't apply the
+    // same migration twice
+    let transaction = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    transaction.execute_batch(
+        "
+        -- Last applied migration, see `db::migrations`
+        CREATE TABLE IF NOT EXISTS schema_version (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            version INTEGER NOT NULL
+        );
+        ",
+    )?;
+
+    let current: u32 = transaction
+        .query_one("SELECT version FROM schema_version WHERE id=1", [], |row| {
+            row.get("version")
+        })
+        .optional()?
+        .unwrap_or(0);
+
+    let latest = latest_version();
+    if current > latest {
+        bail!(
+            "Database schema version {current} is newer than this build supports ({latest}), \
+            it was likely migrated by a newer version of the bot"
+        );
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!(
+            "Applying database migration {}: {}",
+            migration.version, migration.description
+        );
+
+        (migration.apply)(&transaction)
+            .with_context(|| format!("Failed to apply migration {}", migration.version))?;
+
+        transaction.execute(
+            "
+            INSERT INTO schema_version (id, version) VALUES (1, ?1)
+            ON CONFLICT(id) DO UPDATE SET version = excluded.version
+            ",
+            params![migration.version],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                migration.version as usize,
+                i + 1,
+                "{}",
+                migration.description
+            );
+        }
+    }
+
+    #[test]
+    fn migrates_fresh_database_once() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+
+        run(&mut conn).unwrap();
+        // already up to date, nothing is applied again
+        run(&mut conn).unwrap();
+
+        let version: u32 = conn
+            .query_one("SELECT version FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, latest_version());
+    }
+}
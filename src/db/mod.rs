@@ -1,8 +1,14 @@
-use anyhow::Result;
+use std::{any::type_name, time::Instant};
+
+use anyhow::{Context as _, Result};
 use rusqlite::Connection;
 use tokio::sync::{mpsc, oneshot};
+use tracing::error;
 
+pub mod backup;
 pub mod db_thread;
+mod migrations;
+pub mod slow_query;
 
 pub trait DbRequest: Send + Sync + 'static {
     type ReturnValue: Send + 'static;
@@ -10,13 +16,26 @@ pub trait DbRequest: Send + Sync + 'static {
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue;
 }
 
+#[derive(Clone)]
 pub struct DbHandle {
     tx: mpsc::Sender<Box<dyn ErasedDbRequest>>,
+    /// if set, every request is rolled back after executing, see [`DbHandle::with_dry_run`]
+    dry_run: bool,
 }
 
 impl DbHandle {
     pub fn new(tx: mpsc::Sender<Box<dyn ErasedDbRequest>>) -> Self {
-        Self { tx }
+        Self { tx, dry_run: false }
+    }
+
+    /// A handle to the same database whose requests are rolled back after executing if `dry_run` is
+    /// set. Requests still return what they would have changed, so commands can report the effects
+    /// of a dry run using their regular code paths.
+    pub fn with_dry_run(&self, dry_run: bool) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            dry_run,
+        }
     }
 
     pub async fn request<R>(&self, req: R) -> Result<R::ReturnValue>
@@ -26,25 +45,139 @@ impl DbHandle {
         let (resp_tx, resp_rx) = oneshot::channel();
         let wrapped = RequestWrapper {
             inner: req,
+            dry_run: self.dry_run,
             resp_tx,
         };
         self.tx.send(Box::new(wrapped)).await?;
-        Ok(resp_rx.await?)
+        resp_rx.await?
+    }
+
+    /// Requests that were sent but haven't been picked up by the database thread yet
+    pub fn queue_depth(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Resolves once all previously sent requests have been executed, as they are handled in order
+    pub async fn flush(&self) -> Result<()> {
+        self.request(Flush).await
     }
 }
 
+/// In-memory database with all migrations applied, for testing requests
+#[cfg(test)]
+pub fn test_connection() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    conn.pragma_update(None, "foreign_keys", true).unwrap();
+    migrations::run(&mut conn).unwrap();
+    conn
+}
+
+struct Flush;
+impl DbRequest for Flush {
+    type ReturnValue = ();
+
+    fn execute(self, _conn: &mut Connection) -> Self::ReturnValue {}
+}
+
 pub trait ErasedDbRequest: Send + Sync {
     fn execute_boxed(self: Box<Self>, conn: &mut Connection);
 }
 
 struct RequestWrapper<R: DbRequest> {
     inner: R,
-    resp_tx: oneshot::Sender<R::ReturnValue>,
+    dry_run: bool,
+    resp_tx: oneshot::Sender<Result<R::ReturnValue>>,
 }
 
 impl<R: DbRequest> ErasedDbRequest for RequestWrapper<R> {
     fn execute_boxed(self: Box<Self>, conn: &mut Connection) {
-        let result = self.inner.execute(conn);
+        let start = Instant::now();
+        let result = if self.dry_run {
+            execute_dry_run(self.inner, conn)
+        } else {
+            Ok(self.inner.execute(conn))
+        };
+        slow_query::record(type_name::<R>(), start.elapsed());
+
         let _ = self.resp_tx.send(result);
     }
 }
+
+/// Executes the request within a savepoint that is rolled back afterwards.
+///
+/// NOTE: Requests use savepoints instead of transactions for atomicity, as SQLite doesn't allow
+/// nesting transactions inside of the dry run's savepoint.
+fn execute_dry_run<R: DbRequest>(request: R, conn: &mut Connection) -> Result<R::ReturnValue> {
+    conn.execute_batch("SAVEPOINT dry_run")
+        .context("Failed to begin dry run")?;
+
+    let result = request.execute(conn);
+
+    // failing to roll back would persist the changes, so the result isn't returned in that case
+    if let Err(err) = conn.execute_batch("ROLLBACK TO dry_run; RELEASE dry_run") {
+        // an open savepoint would make every later request part of the dry run, so the entire
+        // transaction it started is aborted instead
+        if !conn.is_autocommit()
+            && let Err(abort_err) = conn.execute_batch("ROLLBACK")
+        {
+            error!("Failed to abort dry run: {abort_err:#}");
+        }
+        return Err(err).context("Failed to roll back dry run");
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::role::{network_bingo::DetectionRule, types::NetworkBingo};
+    use crate::shared::{
+        db::{GetFeatureFlag, GetRegisteredNetworkBingos, RegisterNetworkBingo, SetFeatureEnabled},
+        feature::Feature,
+    };
+
+    #[test]
+    fn dry_run_leaves_database_unchanged() {
+        let mut conn = test_connection();
+        let feature = Feature::Nominations;
+        assert!(!feature.default_enabled());
+
+        execute_dry_run(
+            SetFeatureEnabled {
+                feature,
+                enabled: true,
+            },
+            &mut conn,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(conn.is_autocommit(), "dry run left its savepoint open");
+        let flag = GetFeatureFlag { feature }.execute(&mut conn).unwrap();
+        assert!(!flag.enabled);
+    }
+
+    #[test]
+    fn dry_run_returns_what_would_have_changed() {
+        let mut conn = test_connection();
+
+        let bingo = execute_dry_run(
+            RegisterNetworkBingo {
+                season: "summer".to_string(),
+                year: "2030".to_string(),
+                name: None,
+                default_name: "Summer Bingo 2030".to_string(),
+                rule: DetectionRule::AllDifficulties,
+                registered_at: 0,
+            },
+            &mut conn,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(bingo.id(), NetworkBingo::BUILTIN.last().unwrap().id() + 1);
+        let registered = GetRegisteredNetworkBingos.execute(&mut conn).unwrap();
+        assert!(registered.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tracing::warn;
+
+use crate::config::DB_SLOW_QUERY_MS;
+
+/// Number of requests kept in the slowest list
+const SLOWEST_LEN: usize = 10;
+
+static THRESHOLD_MS: AtomicU64 = AtomicU64::new(DB_SLOW_QUERY_MS);
+static SLOWEST: Mutex<Vec<SlowQuery>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+pub struct SlowQuery {
+    pub request: &'static str,
+    pub duration: Duration,
+    pub at: DateTime<Utc>,
+}
+
+pub fn set_threshold(threshold: Duration) {
+    THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn threshold() -> Duration {
+    Duration::from_millis(THRESHOLD_MS.load(Ordering::Relaxed))
+}
+
+/// The slowest requests since startup, slowest first, shown by `/debug db`
+pub fn slowest() -> Vec<SlowQuery> {
+    SLOWEST
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// Called by the database thread after every request
+pub(super) fn record(type_name: &'static str, duration: Duration) {
+    let request = short_type_name(type_name);
+
+    if duration >= threshold() {
+        warn!("Slow database request {request} took {duration:?}");
+    }
+
+    let mut slowest = SLOWEST.lock().unwrap_or_else(|err| err.into_inner());
+    if slowest.len() >= SLOWEST_LEN && slowest.last().is_some_and(|q| q.duration >= duration) {
+        return;
+    }
+
+    let index = slowest.partition_point(|q| q.duration >= duration);
+    slowest.insert(
+        index,
+        SlowQuery {
+            request,
+            duration,
+            at: Utc::now(),
+        },
+    );
+    slowest.truncate(SLOWEST_LEN);
+}
+
+/// Strips the module path, e.g. `bb_bot::hob::db::read::GetAllHobEntries` -> `GetAllHobEntries`
+fn short_type_name(type_name: &'static str) -> &'static str {
+    type_name.rsplit("::").next().unwrap_or(type_name)
+}
@@ -6,8 +6,8 @@ use poise::{
     serenity_prelude::{
         CacheHttp as _, Context as SerenityContext, CreateComponent, CreateContainer,
         CreateContainerComponent, CreateInteractionResponse, CreateInteractionResponseFollowup,
-        CreateInteractionResponseMessage, CreateTextDisplay, FullEvent, Interaction,
-        Mentionable as _, MessageFlags,
+        CreateInteractionResponseMessage, CreateTextDisplay, Error as SerenityError, FullEvent,
+        HttpError, Interaction, JsonErrorCode, Mentionable as _, MessageFlags,
         colours::css::{DANGER, WARNING},
     },
 };
@@ -35,6 +35,25 @@ pub fn deduplicate_error_chain(error: &mut Error) {
     *error = new_error;
 }
 
+/// Whether the error was caused by the targeted message no longer existing, e.g. because staff
+/// deleted it
+pub fn is_unknown_message(error: &Error) -> bool {
+    matches!(
+        error.downcast_ref::<SerenityError>(),
+        Some(SerenityError::Http(HttpError::UnsuccessfulRequest(response)))
+            if response.error.code == JsonErrorCode::UnknownMessage
+    )
+}
+
+/// Whether the error was caused by the targeted user not being a member of the guild (anymore)
+pub fn is_unknown_member(error: &SerenityError) -> bool {
+    matches!(
+        error,
+        SerenityError::Http(HttpError::UnsuccessfulRequest(response))
+            if response.error.code == JsonErrorCode::UnknownMember
+    )
+}
+
 fn internal_error_container(error: &Error) -> CreateComponent<'static> {
     CreateComponent::Container(
         CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
@@ -0,0 +1,113 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::{Context as _, Result};
+use poise::serenity_prelude::Http;
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{error, info, warn};
+
+use crate::metrics;
+use crate::shared::{BotData, task};
+use crate::splashes::store;
+
+/// Requests larger than this are rejected, only request lines and headers are expected
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+/// Connections that aren't done in time are closed, so that clients which are slow to send their
+/// request or to read the response can't pile up
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves `/healthz` and `/metrics` for external monitoring on the specified address
+pub fn start_health_server(addr: SocketAddr, http: Arc<Http>, data: Arc<BotData>) {
+    task::spawn_background("health server", http, async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Failed to bind health server to {addr}: {err:#}");
+                return;
+            }
+        };
+        info!("Health server listening on {addr}");
+
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    warn!("Failed to accept health server connection: {err:#}");
+                    continue;
+                }
+            };
+
+            let data = Arc::clone(&data);
+            tokio::spawn(async move {
+                let result =
+                    tokio::time::timeout(CONNECTION_TIMEOUT, handle_connection(stream, &data))
+                        .await
+                        .context("Timed out while handling the request");
+                if let Err(err) = result.and_then(|result| result) {
+                    warn!("Failed to handle health server request: {err:#}");
+                }
+            });
+        }
+    });
+}
+
+async fn handle_connection(mut stream: TcpStream, data: &BotData) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let (method, path) = (request_line.next(), request_line.next());
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/healthz")) => {
+            if store::live_since().is_some() {
+                ("200 OK", "text/plain", "ok\n".to_string())
+            } else {
+                (
+                    "503 Service Unavailable",
+                    "text/plain",
+                    "gateway not connected\n".to_string(),
+                )
+            }
+        }
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            metrics::render(data).await,
+        ),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_LEN {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    Ok(request)
+}
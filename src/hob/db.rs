@@ -6,9 +6,11 @@ mod write;
 pub use read::*;
 pub use write::*;
 
-pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
+/// Applied by the baseline migration
+pub fn baseline_schema(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "
+        -- Entry IDs are generated by the bot, as one-off and iterative entries share an ID space
         CREATE TABLE IF NOT EXISTS hob_entries_oneoff (
             id INTEGER PRIMARY KEY,
             title TEXT NOT NULL,
@@ -28,8 +30,9 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             title TEXT NOT NULL,
             comment TEXT
         );
+        -- Subentry IDs are assigned by the database
         CREATE TABLE IF NOT EXISTS hob_ongoing_subentries (
-            id INTEGER PRIMARY KEY,
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
             entry_id INTEGER NOT NULL,
             player TEXT NOT NULL,
             value TEXT NOT NULL,
@@ -37,6 +40,40 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             bingo_kind INTEGER NOT NULL,
             FOREIGN KEY(entry_id) REFERENCES hob_entries_ongoing(id) ON DELETE CASCADE
         );
+        -- Staff role required for restricted menu actions (see `HobAction`), unrestricted if unset
+        CREATE TABLE IF NOT EXISTS hob_permissions (
+            action INTEGER PRIMARY KEY,
+            role INTEGER NOT NULL
+        );
+        -- Messages of the published HoB showcase, edited in place on subsequent publishes
+        CREATE TABLE IF NOT EXISTS hob_published_messages (
+            position INTEGER PRIMARY KEY,
+            channel INTEGER NOT NULL,
+            message_id INTEGER NOT NULL
+        );
+        -- Value type of ongoing entries' subentries (see `ValueType`), text if unset
+        CREATE TABLE IF NOT EXISTS hob_ongoing_value_types (
+            entry_id INTEGER PRIMARY KEY,
+            value_type INTEGER NOT NULL,
+            FOREIGN KEY(entry_id) REFERENCES hob_entries_ongoing(id) ON DELETE CASCADE
+        );
+        -- Subentry values normalized according to the entry's value type, used for sorting
+        CREATE TABLE IF NOT EXISTS hob_subentry_numeric_values (
+            subentry_id INTEGER PRIMARY KEY,
+            numeric_value REAL NOT NULL,
+            FOREIGN KEY(subentry_id) REFERENCES hob_ongoing_subentries(id) ON DELETE CASCADE
+        );
+        -- Channel for announcing new records of ongoing entries, disabled if unset
+        CREATE TABLE IF NOT EXISTS hob_record_channel (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            channel INTEGER NOT NULL
+        );
+        -- Ongoing entries with record announcements enabled (see `RecordDirection`)
+        CREATE TABLE IF NOT EXISTS hob_record_announcements (
+            entry_id INTEGER PRIMARY KEY,
+            direction INTEGER NOT NULL,
+            FOREIGN KEY(entry_id) REFERENCES hob_entries_ongoing(id) ON DELETE CASCADE
+        );
         ",
     )
 }
@@ -1,7 +1,15 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{GenericChannelId, MessageId, RoleId};
 use rusqlite::{Connection, OptionalExtension as _, Result, Statement, ToSql, params};
 
 use crate::db::DbRequest;
-use crate::hob::types::{HobEntry, OneOffPlayers, OngoingSubentry};
+use crate::hob::{
+    permission::HobAction,
+    records::RecordDirection,
+    types::{HobEntry, OneOffPlayers, OngoingSubentry},
+    value::ValueType,
+};
 use crate::shared::types::{Bingo, BingoKind};
 
 struct PartialHobEntryOneOff {
@@ -81,6 +89,73 @@ impl DbRequest for GetHobEntry {
     }
 }
 
+/// Image URLs attached to the entry, in the order they were entered
+pub struct GetHobAttachments {
+    pub entry_id: u64,
+}
+impl DbRequest for GetHobAttachments {
+    type ReturnValue = Result<Vec<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn
+            .prepare("SELECT url FROM hob_entry_attachments WHERE entry_id=?1 ORDER BY position")?;
+        statement
+            .query_map([self.entry_id], |row| row.get("url"))?
+            .collect()
+    }
+}
+
+/// Image URLs of all entries with attachments, keyed by entry ID
+pub struct GetAllHobAttachments;
+impl DbRequest for GetAllHobAttachments {
+    type ReturnValue = Result<HashMap<u64, Vec<String>>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "SELECT entry_id, url FROM hob_entry_attachments ORDER BY entry_id, position",
+        )?;
+        let mut attachments: HashMap<u64, Vec<String>> = HashMap::new();
+        for row in statement.query_map([], |row| Ok((row.get("entry_id")?, row.get("url")?)))? {
+            let (entry_id, url) = row?;
+            attachments.entry(entry_id).or_default().push(url);
+        }
+        Ok(attachments)
+    }
+}
+
+/// Tags of the entry in alphabetical order
+pub struct GetHobTags {
+    pub entry_id: u64,
+}
+impl DbRequest for GetHobTags {
+    type ReturnValue = Result<Vec<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement =
+            conn.prepare("SELECT tag FROM hob_entry_tags WHERE entry_id=?1 ORDER BY tag")?;
+        statement
+            .query_map([self.entry_id], |row| row.get("tag"))?
+            .collect()
+    }
+}
+
+/// Tags of all tagged entries, keyed by entry ID
+pub struct GetAllHobTags;
+impl DbRequest for GetAllHobTags {
+    type ReturnValue = Result<HashMap<u64, Vec<String>>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement =
+            conn.prepare("SELECT entry_id, tag FROM hob_entry_tags ORDER BY entry_id, tag")?;
+        let mut tags: HashMap<u64, Vec<String>> = HashMap::new();
+        for row in statement.query_map([], |row| Ok((row.get("entry_id")?, row.get("tag")?)))? {
+            let (entry_id, tag) = row?;
+            tags.entry(entry_id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+}
+
 pub struct GetHobSubentry {
     pub id: u64,
     pub entry_id: u64,
@@ -91,12 +166,13 @@ impl DbRequest for GetHobSubentry {
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
         let mut statement = conn.prepare(
             "
-            SELECT s.player, s.value, s.bingo, s.bingo_kind, m.bingo AS sort_value
+            SELECT s.player, s.value, s.bingo, s.bingo_kind, m.bingo AS sort_value, n.numeric_value
             FROM hob_ongoing_subentries s
             LEFT JOIN bingo_kind_id_map m
                 ON s.bingo_kind = m.bingo_kind
                 AND s.bingo = m.kind_specific_id
-            WHERE id=?1 AND entry_id=?2
+            LEFT JOIN hob_subentry_numeric_values n ON n.subentry_id = s.id
+            WHERE s.id=?1 AND s.entry_id=?2
             ",
         )?;
         statement
@@ -106,6 +182,7 @@ impl DbRequest for GetHobSubentry {
                     entry_id: self.entry_id,
                     player: row.get("player")?,
                     value: row.get("value")?,
+                    numeric_value: row.get("numeric_value")?,
                     bingo: Bingo {
                         kind_specific_id: row.get("bingo")?,
                         kind: BingoKind::from_u8(row.get("bingo_kind")?),
@@ -144,6 +221,12 @@ impl DbRequest for SearchEntriesContent {
                     END
                     || CAST(e.bingo AS TEXT)
                 ) LIKE '%' || ?1 || '%' ESCAPE '\\'
+                OR EXISTS (
+                    SELECT 1
+                    FROM hob_entry_tags t
+                    WHERE t.entry_id = e.id
+                    AND t.tag LIKE '%' || ?1 || '%' ESCAPE '\\'
+                )
             ORDER BY COALESCE(m.bingo, e.bingo) DESC;
             ",
         )?;
@@ -178,6 +261,12 @@ impl DbRequest for SearchEntriesContent {
                         ) LIKE '%' || ?1 || '%' ESCAPE '\\'
                     )
                 )
+                OR EXISTS (
+                    SELECT 1
+                    FROM hob_entry_tags t
+                    WHERE t.entry_id = e.id
+                    AND t.tag LIKE '%' || ?1 || '%' ESCAPE '\\'
+                )
             ORDER BY s_max.sort_value DESC;
             ",
         )?;
@@ -191,6 +280,32 @@ impl DbRequest for SearchEntriesContent {
     }
 }
 
+pub struct GetPlayerEntryTitles {
+    pub player: String,
+}
+impl DbRequest for GetPlayerEntryTitles {
+    type ReturnValue = Result<Vec<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT e.title
+            FROM hob_entries_oneoff e
+            JOIN hob_oneoff_players p ON e.id = p.entry_id
+            WHERE p.player = ?1 COLLATE NOCASE
+            UNION
+            SELECT e.title
+            FROM hob_entries_ongoing e
+            JOIN hob_ongoing_subentries s ON e.id = s.entry_id
+            WHERE s.player = ?1 COLLATE NOCASE
+            ",
+        )?;
+        statement
+            .query_map([self.player], |row| row.get("title"))?
+            .collect()
+    }
+}
+
 fn query_entries(
     conn: &Connection,
     mut oneoff_statement: Statement,
@@ -246,10 +361,15 @@ fn query_entries(
             .into_iter()
             .map(|partial_data| {
                 let subentries = get_ongoing_subentries(conn, partial_data.id)?;
+                let value_type = GetHobValueType {
+                    entry_id: partial_data.id,
+                }
+                .execute(conn)?;
                 Ok(HobEntry::Ongoing {
                     id: partial_data.id,
                     title: partial_data.title,
                     comment: partial_data.comment,
+                    value_type,
                     subentries,
                 })
             })
@@ -270,22 +390,31 @@ fn get_oneoff_players(conn: &Connection, entry_id: u64) -> Result<Vec<String>> {
 fn get_ongoing_subentries(conn: &Connection, entry_id: u64) -> Result<Vec<OngoingSubentry>> {
     let mut statement = conn.prepare(
         "
-        SELECT s.id, s.entry_id, s.player, s.value, s.bingo, s.bingo_kind, m.bingo AS sort_value
+        SELECT s.id, s.entry_id, s.player, s.value, s.bingo, s.bingo_kind, m.bingo AS sort_value,
+            n.numeric_value
         FROM hob_ongoing_subentries s
         LEFT JOIN bingo_kind_id_map m
             ON s.bingo_kind = m.bingo_kind
             AND s.bingo = m.kind_specific_id
+        LEFT JOIN hob_subentry_numeric_values n ON n.subentry_id = s.id
+        LEFT JOIN hob_record_announcements r ON r.entry_id = s.entry_id
         WHERE s.entry_id=?1
-        ORDER BY COALESCE(m.bingo, s.bingo) DESC
+        -- typed values are ranked best first, according to the record direction (highest by
+        -- default), while text and unparsable values follow by recency
+        ORDER BY
+            n.numeric_value IS NULL,
+            CASE WHEN r.direction = ?2 THEN n.numeric_value ELSE -n.numeric_value END,
+            COALESCE(m.bingo, s.bingo) DESC
         ",
     )?;
     statement
-        .query_map([entry_id], |row| {
+        .query_map(params![entry_id, RecordDirection::Lowest as u8], |row| {
             Ok(OngoingSubentry {
                 id: row.get("id")?,
                 entry_id: row.get("entry_id")?,
                 player: row.get("player")?,
                 value: row.get("value")?,
+                numeric_value: row.get("numeric_value")?,
                 bingo: Bingo {
                     kind_specific_id: row.get("bingo")?,
                     kind: BingoKind::from_u8(row.get("bingo_kind")?),
@@ -315,3 +444,94 @@ fn merge_sorted_entry_vecs(vec_a: Vec<HobEntry>, vec_b: Vec<HobEntry>) -> Vec<Ho
     merged.extend(iter_b);
     merged
 }
+
+/// Messages of the published showcase, in display order
+pub struct GetHobPublishedMessages;
+impl DbRequest for GetHobPublishedMessages {
+    type ReturnValue = Result<Vec<(GenericChannelId, MessageId)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn
+            .prepare("SELECT channel, message_id FROM hob_published_messages ORDER BY position")?;
+        statement
+            .query_map([], |row| {
+                Ok((
+                    GenericChannelId::new(row.get("channel")?),
+                    MessageId::new(row.get("message_id")?),
+                ))
+            })?
+            .collect()
+    }
+}
+
+/// Roles required for restricted menu actions
+pub struct GetHobPermissions;
+impl DbRequest for GetHobPermissions {
+    type ReturnValue = Result<HashMap<HobAction, RoleId>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare("SELECT action, role FROM hob_permissions")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, u8>("action")?, RoleId::new(row.get("role")?)))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        // actions removed in newer versions are ignored
+        Ok(rows
+            .into_iter()
+            .filter_map(|(action, role)| Some((HobAction::from_u8(action)?, role)))
+            .collect())
+    }
+}
+
+pub struct GetHobRecordChannel;
+impl DbRequest for GetHobRecordChannel {
+    type ReturnValue = Result<Option<GenericChannelId>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT channel FROM hob_record_channel WHERE id=1",
+            [],
+            |row| Ok(GenericChannelId::new(row.get("channel")?)),
+        )
+        .optional()
+    }
+}
+
+/// `None` if new records of the entry aren't announced
+pub struct GetHobRecordDirection {
+    pub entry_id: u64,
+}
+impl DbRequest for GetHobRecordDirection {
+    type ReturnValue = Result<Option<RecordDirection>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let direction: Option<u8> = conn
+            .query_one(
+                "SELECT direction FROM hob_record_announcements WHERE entry_id=?1",
+                params![self.entry_id],
+                |row| row.get("direction"),
+            )
+            .optional()?;
+        Ok(direction.and_then(RecordDirection::from_u8))
+    }
+}
+
+pub struct GetHobValueType {
+    pub entry_id: u64,
+}
+impl DbRequest for GetHobValueType {
+    type ReturnValue = Result<ValueType>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let value_type: Option<u8> = conn
+            .query_one(
+                "SELECT value_type FROM hob_ongoing_value_types WHERE entry_id=?1",
+                params![self.entry_id],
+                |row| row.get("value_type"),
+            )
+            .optional()?;
+        Ok(value_type.and_then(ValueType::from_u8).unwrap_or_default())
+    }
+}
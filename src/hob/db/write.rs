@@ -1,7 +1,13 @@
-use rusqlite::{Connection, Result, Transaction, params};
+use poise::serenity_prelude::{GenericChannelId, MessageId, RoleId};
+use rusqlite::{Connection, Result, Savepoint, params};
 
 use crate::db::DbRequest;
-use crate::hob::types::{HobEntry, OngoingSubentry};
+use crate::hob::{
+    permission::HobAction,
+    records::RecordDirection,
+    types::{ArchivedHobEntry, HobEntry, OngoingSubentry},
+    value::ValueType,
+};
 
 pub struct InsertHobEntry {
     pub entry: HobEntry,
@@ -10,62 +16,110 @@ impl DbRequest for InsertHobEntry {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
-        match self.entry {
-            HobEntry::OneOff {
-                id,
-                title,
-                comment,
-                bingo,
-                players,
-            } => {
-                transaction.execute(
-                    "
-                    INSERT INTO hob_entries_oneoff (id, title, comment, bingo, bingo_kind)
-                    VALUES (?1, ?2, ?3, ?4, ?5)
-                    ",
-                    params![id, title, comment, bingo.kind_specific_id, bingo.kind as u8],
-                )?;
+        let transaction = conn.savepoint()?;
+        insert_entry(&transaction, self.entry)?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
 
-                insert_oneoff_players(&transaction, id, &players.players)?;
-            }
-            HobEntry::Ongoing {
-                id,
-                title,
-                comment,
-                subentries,
-            } => {
-                transaction.execute(
-                    "
-                    INSERT INTO hob_entries_ongoing (id, title, comment)
-                    VALUES (?1, ?2, ?3)
-                    ",
-                    params![id, title, comment],
-                )?;
-
-                insert_ongoing_subentries(&transaction, id, &subentries)?;
+/// Inserts all entries along with their attachments and tags in a single transaction, skipping
+/// entries whose ID already exists. Returns the number of inserted entries.
+pub struct ImportHobEntries {
+    pub entries: Vec<ArchivedHobEntry>,
+}
+impl DbRequest for ImportHobEntries {
+    type ReturnValue = Result<usize>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        let mut inserted = 0;
+        {
+            let mut exists_statement = transaction.prepare(
+                "
+                SELECT 1 FROM hob_entries_oneoff WHERE id=?1
+                UNION ALL
+                SELECT 1 FROM hob_entries_ongoing WHERE id=?1
+                ",
+            )?;
+
+            for ArchivedHobEntry {
+                entry,
+                attachments,
+                tags,
+            } in self.entries
+            {
+                let id = entry.id();
+                if exists_statement.exists(params![id])? {
+                    continue;
+                }
+                insert_entry(&transaction, entry)?;
+                insert_attachments(&transaction, id, &attachments)?;
+                insert_tags(&transaction, id, &tags)?;
+                inserted += 1;
             }
         }
         transaction.commit()?;
-        Ok(())
+        Ok(inserted)
     }
 }
 
+fn insert_entry(transaction: &Savepoint, entry: HobEntry) -> Result<()> {
+    match entry {
+        HobEntry::OneOff {
+            id,
+            title,
+            comment,
+            bingo,
+            players,
+        } => {
+            transaction.execute(
+                "
+                INSERT INTO hob_entries_oneoff (id, title, comment, bingo, bingo_kind)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ",
+                params![id, title, comment, bingo.kind_specific_id, bingo.kind as u8],
+            )?;
+
+            insert_oneoff_players(transaction, id, &players.players)?;
+        }
+        HobEntry::Ongoing {
+            id,
+            title,
+            comment,
+            value_type,
+            subentries,
+        } => {
+            transaction.execute(
+                "
+                INSERT INTO hob_entries_ongoing (id, title, comment)
+                VALUES (?1, ?2, ?3)
+                ",
+                params![id, title, comment],
+            )?;
+            set_value_type(transaction, id, value_type)?;
+
+            insert_ongoing_subentries(transaction, id, &subentries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Inserts a subentry, ignoring its `id` field. Returns the ID assigned by the database.
 pub struct InsertHobSubentry {
     pub subentry: OngoingSubentry,
     pub ongoing_entry_id: u64,
 }
 impl DbRequest for InsertHobSubentry {
-    type ReturnValue = Result<()>;
+    type ReturnValue = Result<u64>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
         conn.execute(
             "
-            INSERT INTO hob_ongoing_subentries (id, entry_id, player, value, bingo, bingo_kind)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT INTO hob_ongoing_subentries (entry_id, player, value, bingo, bingo_kind)
+            VALUES (?1, ?2, ?3, ?4, ?5)
             ",
             params![
-                self.subentry.id,
                 self.ongoing_entry_id,
                 self.subentry.player,
                 self.subentry.value,
@@ -74,7 +128,9 @@ impl DbRequest for InsertHobSubentry {
             ],
         )?;
 
-        Ok(())
+        let id = conn.last_insert_rowid() as u64;
+        set_numeric_value(conn, id, self.subentry.numeric_value)?;
+        Ok(id)
     }
 }
 
@@ -85,7 +141,7 @@ impl DbRequest for UpdateHobEntry {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
         match self.entry {
             HobEntry::OneOff {
                 id,
@@ -167,16 +223,13 @@ impl DbRequest for UpdateHobSubentry {
             self.subentry.bingo.kind_specific_id,
             self.subentry.bingo.kind as u8
         ])?;
+        set_numeric_value(conn, self.subentry.id, self.subentry.numeric_value)?;
 
         Ok(())
     }
 }
 
-fn insert_oneoff_players(
-    transaction: &Transaction,
-    entry_id: u64,
-    players: &[String],
-) -> Result<()> {
+fn insert_oneoff_players(transaction: &Savepoint, entry_id: u64, players: &[String]) -> Result<()> {
     let mut player_statement = transaction.prepare(
         "INSERT INTO hob_oneoff_players (entry_id, player, position) VALUES (?1, ?2, ?3)",
     )?;
@@ -189,28 +242,125 @@ fn insert_oneoff_players(
 }
 
 fn insert_ongoing_subentries(
-    transaction: &Transaction,
+    transaction: &Savepoint,
     entry_id: u64,
     subentries: &[OngoingSubentry],
 ) -> Result<()> {
     let mut subentry_statement = transaction.prepare(
-        "INSERT INTO hob_ongoing_subentries (id, entry_id, player, value, bingo, bingo_kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO hob_ongoing_subentries (entry_id, player, value, bingo, bingo_kind) VALUES (?1, ?2, ?3, ?4, ?5)",
     )?;
 
+    // subentry IDs are assigned by the database
     for subentry in subentries {
         subentry_statement.execute(params![
-            subentry.id,
             entry_id,
             subentry.player,
             subentry.value,
             subentry.bingo.kind_specific_id,
             subentry.bingo.kind as u8
         ])?;
+        set_numeric_value(
+            transaction,
+            transaction.last_insert_rowid() as u64,
+            subentry.numeric_value,
+        )?;
     }
 
     Ok(())
 }
 
+fn set_value_type(conn: &Connection, entry_id: u64, value_type: ValueType) -> Result<()> {
+    match value_type {
+        ValueType::Text => conn.execute(
+            "DELETE FROM hob_ongoing_value_types WHERE entry_id=?1",
+            params![entry_id],
+        ),
+        value_type => conn.execute(
+            "INSERT OR REPLACE INTO hob_ongoing_value_types (entry_id, value_type) VALUES (?1, ?2)",
+            params![entry_id, value_type as u8],
+        ),
+    }?;
+    Ok(())
+}
+
+fn set_numeric_value(
+    conn: &Connection,
+    subentry_id: u64,
+    numeric_value: Option<f64>,
+) -> Result<()> {
+    match numeric_value {
+        Some(value) => conn.execute(
+            "
+            INSERT OR REPLACE INTO hob_subentry_numeric_values (subentry_id, numeric_value)
+            VALUES (?1, ?2)
+            ",
+            params![subentry_id, value],
+        ),
+        None => conn.execute(
+            "DELETE FROM hob_subentry_numeric_values WHERE subentry_id=?1",
+            params![subentry_id],
+        ),
+    }?;
+    Ok(())
+}
+
+/// Replaces all image URLs attached to the entry
+pub struct SetHobAttachments {
+    pub entry_id: u64,
+    pub urls: Vec<String>,
+}
+impl DbRequest for SetHobAttachments {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        transaction.execute(
+            "DELETE FROM hob_entry_attachments WHERE entry_id=?1",
+            [self.entry_id],
+        )?;
+        insert_attachments(&transaction, self.entry_id, &self.urls)?;
+        transaction.commit()
+    }
+}
+
+fn insert_attachments(transaction: &Savepoint, entry_id: u64, urls: &[String]) -> Result<()> {
+    let mut statement = transaction.prepare(
+        "INSERT INTO hob_entry_attachments (entry_id, position, url) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, url) in urls.iter().enumerate() {
+        statement.execute(params![entry_id, position as i64, url])?;
+    }
+    Ok(())
+}
+
+/// Replaces the entry's tags, which must already be normalized
+pub struct SetHobTags {
+    pub entry_id: u64,
+    pub tags: Vec<String>,
+}
+impl DbRequest for SetHobTags {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        transaction.execute(
+            "DELETE FROM hob_entry_tags WHERE entry_id=?1",
+            [self.entry_id],
+        )?;
+        insert_tags(&transaction, self.entry_id, &self.tags)?;
+        transaction.commit()
+    }
+}
+
+fn insert_tags(transaction: &Savepoint, entry_id: u64, tags: &[String]) -> Result<()> {
+    let mut statement = transaction
+        .prepare("INSERT OR IGNORE INTO hob_entry_tags (entry_id, tag) VALUES (?1, ?2)")?;
+    for tag in tags {
+        statement.execute(params![entry_id, tag])?;
+    }
+    Ok(())
+}
+
 pub struct DeleteHobEntry {
     pub id: u64,
 }
@@ -218,7 +368,13 @@ impl DbRequest for DeleteHobEntry {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
+        transaction.execute(
+            "DELETE FROM hob_entry_attachments WHERE entry_id=?1",
+            [self.id],
+        )?;
+        transaction.execute("DELETE FROM hob_entry_tags WHERE entry_id=?1", [self.id])?;
+
         let oneoff_entries_deleted = {
             let mut statement =
                 transaction.prepare("DELETE FROM hob_entries_oneoff WHERE id=?1")?;
@@ -256,3 +412,130 @@ impl DbRequest for DeleteHobSubentry {
         Ok(())
     }
 }
+
+/// Replaces the tracked messages of the published showcase
+pub struct SetHobPublishedMessages {
+    pub messages: Vec<(GenericChannelId, MessageId)>,
+}
+impl DbRequest for SetHobPublishedMessages {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        transaction.execute("DELETE FROM hob_published_messages", [])?;
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT INTO hob_published_messages (position, channel, message_id)
+                VALUES (?1, ?2, ?3)
+                ",
+            )?;
+            for (position, (channel, message_id)) in self.messages.iter().enumerate() {
+                statement.execute(params![position, channel.get(), message_id.get()])?;
+            }
+        }
+        transaction.commit()
+    }
+}
+
+/// Restricts a menu action to a role, or lifts the restriction if `role` is `None`
+pub struct SetHobPermission {
+    pub action: HobAction,
+    pub role: Option<RoleId>,
+}
+impl DbRequest for SetHobPermission {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.role {
+            Some(role) => conn.execute(
+                "INSERT OR REPLACE INTO hob_permissions (action, role) VALUES (?1, ?2)",
+                params![self.action as u8, role.get()],
+            ),
+            None => conn.execute(
+                "DELETE FROM hob_permissions WHERE action=?1",
+                params![self.action as u8],
+            ),
+        }?;
+        Ok(())
+    }
+}
+
+/// `None` disables record announcements
+pub struct SetHobRecordChannel {
+    pub channel: Option<GenericChannelId>,
+}
+impl DbRequest for SetHobRecordChannel {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.channel {
+            Some(channel) => conn.execute(
+                "INSERT OR REPLACE INTO hob_record_channel (id, channel) VALUES (1, ?1)",
+                params![channel.get()],
+            ),
+            None => conn.execute("DELETE FROM hob_record_channel", []),
+        }?;
+        Ok(())
+    }
+}
+
+/// `None` disables record announcements for the entry
+pub struct SetHobRecordDirection {
+    pub entry_id: u64,
+    pub direction: Option<RecordDirection>,
+}
+impl DbRequest for SetHobRecordDirection {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.direction {
+            Some(direction) => conn.execute(
+                "
+                INSERT OR REPLACE INTO hob_record_announcements (entry_id, direction)
+                VALUES (?1, ?2)
+                ",
+                params![self.entry_id, direction as u8],
+            ),
+            None => conn.execute(
+                "DELETE FROM hob_record_announcements WHERE entry_id=?1",
+                params![self.entry_id],
+            ),
+        }?;
+        Ok(())
+    }
+}
+
+/// Changes the value type of an ongoing entry and renormalizes its subentries, returning the values
+/// which aren't valid for the new type. These are kept, but excluded from sorting and records.
+pub struct SetHobValueType {
+    pub entry_id: u64,
+    pub value_type: ValueType,
+}
+impl DbRequest for SetHobValueType {
+    type ReturnValue = Result<Vec<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        set_value_type(&transaction, self.entry_id, self.value_type)?;
+
+        let subentries: Vec<(u64, String)> = transaction
+            .prepare("SELECT id, value FROM hob_ongoing_subentries WHERE entry_id=?1")?
+            .query_map(params![self.entry_id], |row| {
+                Ok((row.get("id")?, row.get("value")?))
+            })?
+            .collect::<Result<_>>()?;
+
+        let mut invalid = Vec::new();
+        for (id, value) in subentries {
+            let numeric_value = self.value_type.parse(&value).unwrap_or_else(|_| {
+                invalid.push(value);
+                None
+            });
+            set_numeric_value(&transaction, id, numeric_value)?;
+        }
+
+        transaction.commit()?;
+        Ok(invalid)
+    }
+}
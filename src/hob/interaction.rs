@@ -3,17 +3,31 @@ use std::sync::Arc;
 use anyhow::{Context as _, Result, anyhow};
 use either::Either;
 use poise::serenity_prelude::{
-    CacheHttp as _, ComponentInteraction, Context as SerenityContext, CreateComponent,
-    CreateContainer, CreateContainerComponent, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateTextDisplay, Mentionable as _, MessageFlags,
+    CacheHttp as _, ComponentInteraction, Context as SerenityContext, CreateAllowedMentions,
+    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateTextDisplay, Member, Mentionable as _, MessageFlags,
     ModalInteraction, colours::css::DANGER,
 };
 use tokio::sync::MutexGuard;
 use tracing::{info, warn};
 
 use crate::error::UserError;
-use crate::hob::menu::{HobEditSession, HobEditState};
-use crate::shared::{BotData, interaction::MessageEdit, menu::navigation::Backtrack as _};
+use crate::hob::{
+    db::GetHobPermissions,
+    menu::{HobEditSession, HobEditState},
+    permission::{self, HobAction},
+};
+use crate::shared::{
+    BotData,
+    interaction::{
+        MessageEdit,
+        custom_id::{self, Namespace, Parts},
+    },
+    menu::{
+        navigation::{Backtrack as _, GenerateMenu as _},
+        timeout, transfer,
+    },
+};
 
 mod modal;
 mod select_entry;
@@ -23,7 +37,7 @@ mod view_subentry;
 pub async fn handle_interaction(
     ctx: &SerenityContext,
     interaction: Either<&ComponentInteraction, &ModalInteraction>,
-    mut action: impl Iterator<Item = &str>,
+    mut action: Parts<'_>,
 ) -> Result<()> {
     match &interaction {
         Either::Left(component_interaction) => {
@@ -40,11 +54,7 @@ pub async fn handle_interaction(
         }
     }
 
-    let menu_id = action
-        .next()
-        .unwrap_or_default()
-        .parse::<u64>()
-        .context("Invalid interaction: Expected menu ID")?;
+    let menu_id: u64 = action.next_parsed("menu ID")?;
 
     // NOTE: lock dropped at the end of the expression
     let session_mutex = Arc::clone(
@@ -91,13 +101,84 @@ Only {} is allowed to interact with this menu.",
         return Ok(());
     }
 
+    // checked before dispatching, so that restricted actions don't need individual checks
+    let action: Vec<&str> = action.collect();
+    if let Some(restricted) =
+        HobAction::of_interaction(&session.state, action.first().copied().unwrap_or_default())
+    {
+        let permissions = ctx
+            .data::<BotData>()
+            .db_for(session.guild_id)
+            .request(GetHobPermissions)
+            .await??;
+        let (user, member) = match &interaction {
+            Either::Left(i) => (&i.user, i.member.as_ref()),
+            Either::Right(i) => (&i.user, i.member.as_ref()),
+        };
+        let member = member.map(|m| -> &Member { m });
+
+        if let Some(role) = permission::missing_role(&permissions, restricted, member) {
+            warn!(
+                "{} tried to perform restricted HoB action {restricted:?}",
+                user.name
+            );
+
+            let container = CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(permission::denial_text(restricted, role)),
+                )])
+                .accent_color(DANGER),
+            );
+            let message = CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::default()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![container])
+                    .ephemeral(true),
+            );
+
+            match interaction {
+                Either::Left(i) => i.create_response(ctx.http(), message).await?,
+                Either::Right(i) => i.create_response(ctx.http(), message).await?,
+            }
+            return Ok(());
+        }
+    }
+
     session.timeout_reset.notify_one();
 
-    let new_content = match interaction {
+    // available in every view, as the transfer select may be submitted after navigating away
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&session.menu_id]);
+    let transferred = match interaction {
         Either::Left(component_interaction) => {
+            transfer::handle_component(
+                ctx,
+                component_interaction,
+                action.first().copied().unwrap_or_default(),
+                &id_prefix,
+                &mut session.owner,
+            )
+            .await?
+        }
+        Either::Right(_) => None,
+    };
+
+    let extend = action.first() == Some(&timeout::EXTEND_ACTION);
+
+    let action = action.into_iter();
+    let new_content = match (interaction, transferred) {
+        (_, Some(edit)) => edit,
+        // the timeout was already reset, re-rendering removes the expiry warning
+        (Either::Left(_), None) if extend => {
+            let data = ctx.data::<BotData>();
+            let menu_id = session.menu_id;
+            let db = data.db_for(session.guild_id);
+            MessageEdit::Interaction(session.state.generate(db, menu_id).await?)
+        }
+        (Either::Left(component_interaction), None) => {
             component(ctx, component_interaction, action, &mut session).await?
         }
-        Either::Right(modal_interaction) => {
+        (Either::Right(modal_interaction), None) => {
             modal(ctx, modal_interaction, action, &mut session).await?
         }
     };
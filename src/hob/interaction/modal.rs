@@ -5,6 +5,28 @@ use bb_bot_macros::define_modal;
 use crate::shared::interaction::modal::BINGO_SYNTAX;
 
 define_modal! {
+    // parsed via `Bingo::from_input`, explained by `BINGO_SYNTAX`
+    group bingo_input [
+        input bingo {
+            style: InputTextStyle::Short,
+            label: "Bingo",
+            placeholder: "Enter the bingo identifier (scroll for syntax)",
+            max_length: 20,
+            required: true,
+        }
+    ]
+
+    group comment_input [
+        input comment {
+            style: InputTextStyle::Short,
+            label: "Comment (optional)",
+            description: "Will be shown below the entry",
+            placeholder: "Enter an additional comment",
+            max_length: 500,
+            required: false,
+        }
+    ]
+
     HobEntryOneoff {
         custom_id: "oneoff_submit",
         title: "One-off Entry",
@@ -24,29 +46,14 @@ define_modal! {
                 max_length: 200,
                 required: true,
             },
-            input bingo {
-                style: InputTextStyle::Short,
-                label: "Bingo",
-                placeholder: "Enter the bingo identifier (scroll for syntax)",
-                max_length: 20,
-                required: true,
-            },
-            input comment {
-                style: InputTextStyle::Short,
-                label: "Comment (optional)",
-                description: "Will be shown below the entry",
-                placeholder: "Enter an additional comment",
-                max_length: 500,
-                required: false,
-            },
+            include bingo_input,
+            include comment_input,
             text {
                 content: BINGO_SYNTAX,
             },
         ]
     }
-}
 
-define_modal! {
     HobEntryOngoing {
         custom_id: "ongoing_submit",
         title: "Iterative Entry",
@@ -58,19 +65,10 @@ define_modal! {
                 max_length: 200,
                 required: true,
             },
-            input comment {
-                style: InputTextStyle::Short,
-                label: "Comment (optional)",
-                description: "Will be shown below the entry",
-                placeholder: "Enter an additional comment",
-                max_length: 500,
-                required: false,
-            },
+            include comment_input,
         ]
     }
-}
 
-define_modal! {
     HobOngoingSubentry {
         custom_id: "subentry_submit",
         title: "Subentry",
@@ -85,21 +83,45 @@ define_modal! {
             input value {
                 style: InputTextStyle::Short,
                 label: "Value",
-                description: "Note: This can be any string (subentry sorting is based on bingo ID)",
+                description: "Must match the entry's value type, e.g. a duration (subentries are sorted by bingo)",
                 placeholder: "Enter the achieved value/score",
                 max_length: 50,
                 required: true,
             },
-            input bingo {
-                style: InputTextStyle::Short,
-                label: "Bingo",
-                placeholder: "Enter the bingo identifier (scroll for syntax)",
-                max_length: 20,
-                required: true,
-            },
+            include bingo_input,
             text {
                 content: BINGO_SYNTAX,
             },
         ]
     }
+
+    HobTags {
+        custom_id: "tags_submit",
+        title: "Tags",
+        components: [
+            input tags {
+                style: InputTextStyle::Short,
+                label: "Tags",
+                description: "Separated by commas, up to 10",
+                placeholder: "e.g. speedrun, collection, community",
+                max_length: 400,
+                required: false,
+            },
+        ]
+    }
+
+    HobAttachments {
+        custom_id: "attachments_submit",
+        title: "Attachments",
+        components: [
+            input urls {
+                style: InputTextStyle::Paragraph,
+                label: "Image URLs",
+                description: "One per line, up to 10, shown in a gallery below the entry",
+                placeholder: "Paste links to screenshots, e.g. from Discord or Imgur",
+                max_length: 4000,
+                required: false,
+            },
+        ]
+    }
 }
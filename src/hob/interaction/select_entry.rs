@@ -1,21 +1,26 @@
-use anyhow::{Context as _, Result, anyhow};
+use anyhow::{Context as _, Result, anyhow, bail};
 use poise::serenity_prelude::{
-    ButtonStyle, CacheHttp as _, ComponentInteraction, Context as SerenityContext, CreateButton,
-    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateSection, CreateSectionAccessory,
-    CreateSectionComponent, CreateTextDisplay, MessageFlags, ModalInteraction, ReactionType,
-    small_fixed_array::FixedString,
+    ButtonStyle, CacheHttp as _, ComponentInteraction, ComponentInteractionDataKind,
+    Context as SerenityContext, CreateButton, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSection, CreateSectionAccessory, CreateSectionComponent, CreateTextDisplay, MessageFlags,
+    ModalInteraction, ReactionType, small_fixed_array::FixedString,
 };
 
 use crate::hob::{
     db::InsertHobEntry,
     interaction::{MessageEdit, modal},
-    menu::{HobEditState, SelectEntryState, ViewEntryState},
+    menu::{HobEditState, SelectEntryState, ViewEntryState, select_entry::ALL_TAGS},
     types::{HobEntry, OneOffPlayers},
+    value::ValueType,
 };
 use crate::shared::{
     BotData,
-    interaction::{MenuChange, modal as shared_modal},
+    interaction::{
+        MenuChange,
+        custom_id::{self, Namespace},
+        modal as shared_modal,
+    },
     menu::navigation::GenerateMenu as _,
     types::Bingo,
 };
@@ -27,8 +32,9 @@ pub async fn handle_component(
     menu_id: u64,
     session_state: &mut SelectEntryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
-    let id_prefix = format!("hob:{menu_id}");
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
 
     match action.next().unwrap_or_default() {
         "goto_page" => {
@@ -148,6 +154,21 @@ Meant for achievements that can be improved upon (e.g. Highest XY).",
 
             Ok(MenuChange::none())
         }
+        "filter_tag" => {
+            let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+            else {
+                bail!("Invalid interaction: Expected String SelectMenu")
+            };
+            session_state.page = 0;
+            session_state.tag = match values.first().map(String::as_str) {
+                Some(ALL_TAGS) => None,
+                Some(tag) => Some(tag.to_string()),
+                None => bail!("Invalid interaction: Expected selected option"),
+            };
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Interaction(menu)))
+        }
         "reset_search" => {
             session_state.page = 0;
             session_state.search_query = None;
@@ -165,7 +186,8 @@ pub async fn handle_modal(
     menu_id: u64,
     session_state: &mut SelectEntryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
 
     match action.next().unwrap_or_default() {
         "search_submit" => {
@@ -250,6 +272,7 @@ pub async fn handle_modal(
                     id: entry_id,
                     title: values.title.into_string(),
                     comment,
+                    value_type: ValueType::Text,
                     subentries: Vec::new(),
                 },
             })
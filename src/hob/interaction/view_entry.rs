@@ -1,22 +1,34 @@
-use anyhow::{Context as _, Result, anyhow};
+use anyhow::{Context as _, Result, anyhow, bail};
 use poise::serenity_prelude::{
-    ButtonStyle, CacheHttp as _, ComponentInteraction, Context as SerenityContext, CreateButton,
-    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateSection, CreateSectionAccessory,
-    CreateSectionComponent, CreateSeparator, CreateTextDisplay, MessageFlags, ModalInteraction,
-    colours::css::{DANGER, POSITIVE},
+    ButtonStyle, CacheHttp as _, ComponentInteraction, ComponentInteractionDataKind,
+    Context as SerenityContext, CreateButton, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSection, CreateSectionAccessory, CreateSectionComponent, CreateSeparator,
+    CreateTextDisplay, MessageFlags, ModalInteraction,
+    colours::css::{DANGER, POSITIVE, WARNING},
     small_fixed_array::FixedString,
 };
+use tracing::warn;
 
+use crate::error::UserError;
 use crate::hob::{
-    db::{DeleteHobEntry, GetHobEntry, InsertHobSubentry, UpdateHobEntry},
+    db::{
+        DeleteHobEntry, GetHobAttachments, GetHobEntry, GetHobTags, GetHobValueType,
+        InsertHobSubentry, SetHobAttachments, SetHobRecordDirection, SetHobTags, SetHobValueType,
+        UpdateHobEntry,
+    },
     interaction::{MessageEdit, modal},
     menu::{HobEditState, SelectEntryState, ViewEntryState, ViewSubentryState},
-    types::{HobEntry, OneOffPlayers, OngoingSubentry},
+    records::{self, RecordDirection},
+    types::{self, HobEntry, OneOffPlayers, OngoingSubentry},
+    value::ValueType,
 };
 use crate::shared::{
     BotData,
-    interaction::MenuChange,
+    interaction::{
+        MenuChange,
+        custom_id::{self, Namespace},
+    },
     menu::{
         ACCENT_COLOR,
         navigation::{BacktrackState as _, GenerateMenu as _},
@@ -24,6 +36,10 @@ use crate::shared::{
     types::Bingo,
 };
 
+/// Unparsable values listed after changing the value type, keeping the message within Discord's
+/// length limit
+const MAX_LISTED_INVALID: usize = 20;
+
 pub async fn handle_component(
     ctx: &SerenityContext,
     interaction: &ComponentInteraction,
@@ -31,10 +47,17 @@ pub async fn handle_component(
     menu_id: u64,
     session_state: &mut ViewEntryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
-    let id_prefix = format!("hob:{menu_id}");
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
 
     match action.next().unwrap_or_default() {
+        "back" if session_state.settings => {
+            session_state.settings = false;
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Interaction(menu)))
+        }
         "back" => {
             let mut new_state = session_state
                 .take_referrer_or(|| HobEditState::SelectEntry(SelectEntryState::new(0, None)));
@@ -42,6 +65,94 @@ pub async fn handle_component(
             let menu = new_state.generate(db, menu_id).await?;
             Ok(MenuChange::new(new_state, MessageEdit::Interaction(menu)))
         }
+        "settings" => {
+            session_state.settings = true;
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Interaction(menu)))
+        }
+        "record_announcements" => {
+            let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+            else {
+                bail!("Invalid interaction: Expected String SelectMenu")
+            };
+            let direction = match values.first().map(String::as_str) {
+                Some("off") => None,
+                Some(value) => Some(
+                    value
+                        .parse()
+                        .ok()
+                        .and_then(RecordDirection::from_u8)
+                        .context("Invalid interaction: Unexpected record direction")?,
+                ),
+                None => bail!("Invalid interaction: Expected selected option"),
+            };
+
+            db.request(SetHobRecordDirection {
+                entry_id: session_state.id,
+                direction,
+            })
+            .await??;
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Interaction(menu)))
+        }
+        "value_type" => {
+            let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+            else {
+                bail!("Invalid interaction: Expected String SelectMenu")
+            };
+            let value_type = values
+                .first()
+                .and_then(|value| ValueType::from_key(value))
+                .context("Invalid interaction: Unexpected value type")?;
+
+            let invalid = db
+                .request(SetHobValueType {
+                    entry_id: session_state.id,
+                    value_type,
+                })
+                .await??;
+
+            let menu = session_state.generate(db, menu_id).await?;
+
+            if invalid.is_empty() {
+                return Ok(MenuChange::message(MessageEdit::Interaction(menu)));
+            }
+
+            let mut invalid_list: String = invalid
+                .iter()
+                .take(MAX_LISTED_INVALID)
+                .map(|value| format!("- `{value}`\n"))
+                .collect();
+            if invalid.len() > MAX_LISTED_INVALID {
+                invalid_list.push_str(&format!(
+                    "- *...and {} more*\n",
+                    invalid.len() - MAX_LISTED_INVALID
+                ));
+            }
+            let warning_text =
+                CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+                    "## Unparsable Values
+The following values aren't valid {} values. They are kept as entered, but are left out of \
+                     sorting and record comparisons until they are edited:
+{invalid_list}",
+                    value_type.name().to_lowercase()
+                )));
+
+            let message = CreateInteractionResponseMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![warning_text]).accent_color(WARNING),
+                )])
+                .ephemeral(true);
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Message(message))
+                .await?;
+
+            Ok(MenuChange::message(MessageEdit::Direct(menu)))
+        }
         "goto_page" => {
             session_state.page = match action.next().unwrap_or_default() {
                 "next" => session_state.page + 1,
@@ -58,15 +169,24 @@ pub async fn handle_component(
                 })
                 .await??
                 .context("Invalid entry ID")?;
+            let attachments = db
+                .request(GetHobAttachments {
+                    entry_id: session_state.id,
+                })
+                .await??;
             let entry_text = entry.to_text_display().0;
             let title = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
                 "### *Single Entry Preview*",
             ));
 
             let divider = CreateContainerComponent::Separator(CreateSeparator::new(true));
+            let components: Vec<_> = [title, divider.clone(), entry_text]
+                .into_iter()
+                .chain(entry.gallery(&attachments))
+                .chain([divider])
+                .collect();
             let container = CreateComponent::Container(
-                CreateContainer::new(vec![title, divider.clone(), entry_text, divider])
-                    .accent_color(ACCENT_COLOR),
+                CreateContainer::new(components).accent_color(ACCENT_COLOR),
             );
 
             let message = CreateInteractionResponseMessage::new()
@@ -117,6 +237,33 @@ pub async fn handle_component(
                 .await?;
             Ok(MenuChange::none())
         }
+        "attachments" => {
+            let attachments = db
+                .request(GetHobAttachments {
+                    entry_id: session_state.id,
+                })
+                .await??;
+            let modal =
+                modal::HobAttachments::create_prefilled(&id_prefix, attachments.join("\n").into());
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Modal(modal))
+                .await?;
+            Ok(MenuChange::none())
+        }
+        "tags" => {
+            let tags = db
+                .request(GetHobTags {
+                    entry_id: session_state.id,
+                })
+                .await??;
+            let modal = modal::HobTags::create_prefilled(&id_prefix, tags.join(", ").into());
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Modal(modal))
+                .await?;
+            Ok(MenuChange::none())
+        }
         "delete" => {
             let confirm_button = CreateButton::new(format!("{id_prefix}:delete_confirm"))
                 .label("Delete")
@@ -211,7 +358,8 @@ pub async fn handle_modal(
     menu_id: u64,
     session_state: &mut ViewEntryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
 
     match action.next().unwrap_or_default() {
         "oneoff_submit" => {
@@ -261,7 +409,8 @@ pub async fn handle_modal(
                     id: session_state.id,
                     title: values.title.into_string(),
                     comment,
-                    subentries: Vec::new(), // edited separately
+                    value_type: ValueType::default(), // edited separately
+                    subentries: Vec::new(),           // edited separately
                 },
             })
             .await??;
@@ -277,17 +426,83 @@ pub async fn handle_modal(
             let values = modal::HobOngoingSubentry::validate(&interaction.data.components)?;
 
             let bingo = Bingo::from_input(&values.bingo)?;
-            let subentry_id = crate::shared::menu::generate_id();
 
-            db.request(InsertHobSubentry {
-                subentry: OngoingSubentry {
-                    id: subentry_id,
+            let value_type = db
+                .request(GetHobValueType {
                     entry_id: session_state.id,
-                    player: values.player.into_string(),
-                    value: values.value.into_string(),
-                    bingo,
-                },
-                ongoing_entry_id: session_state.id,
+                })
+                .await??;
+            let value = values.value.into_string();
+
+            let mut subentry = OngoingSubentry {
+                id: 0, // assigned by the database
+                entry_id: session_state.id,
+                player: values.player.into_string(),
+                numeric_value: value_type.parse(&value)?,
+                value,
+                bingo,
+            };
+            subentry.id = db
+                .request(InsertHobSubentry {
+                    subentry: subentry.clone(),
+                    ongoing_entry_id: session_state.id,
+                })
+                .await??;
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Acknowledge)
+                .await?;
+
+            if let Err(err) = records::announce_if_record(ctx.http(), db, &subentry, None).await {
+                warn!("Failed to announce HoB record: {err:#}");
+            }
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Direct(menu)))
+        }
+        "tags_submit" => {
+            let values = modal::HobTags::validate(&interaction.data.components)?;
+            let tags = types::parse_tags(&values.tags)?;
+
+            db.request(SetHobTags {
+                entry_id: session_state.id,
+                tags,
+            })
+            .await??;
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Acknowledge)
+                .await?;
+
+            let menu = session_state.generate(db, menu_id).await?;
+            Ok(MenuChange::message(MessageEdit::Direct(menu)))
+        }
+        "attachments_submit" => {
+            let values = modal::HobAttachments::validate(&interaction.data.components)?;
+
+            let urls: Vec<String> = values
+                .urls
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect();
+            if let Some(invalid) = urls.iter().find(|url| !types::is_image_url(url)) {
+                bail!(UserError(anyhow!(
+                    "`{invalid}` isn't a link to an image. Attachments must be `https://` links \
+                     ending in `.png`, `.jpg`, `.jpeg`, `.gif` or `.webp`."
+                )));
+            }
+            if urls.len() > types::MAX_GALLERY_ITEMS {
+                bail!(UserError(anyhow!(
+                    "Entries can have at most {} attachments",
+                    types::MAX_GALLERY_ITEMS
+                )));
+            }
+
+            db.request(SetHobAttachments {
+                entry_id: session_state.id,
+                urls,
             })
             .await??;
 
@@ -6,16 +6,21 @@ use poise::serenity_prelude::{
     CreateSectionComponent, CreateSeparator, CreateTextDisplay, MessageFlags, ModalInteraction,
     colours::css::{DANGER, POSITIVE},
 };
+use tracing::warn;
 
 use crate::hob::{
-    db::{DeleteHobSubentry, GetHobSubentry, UpdateHobSubentry},
+    db::{DeleteHobSubentry, GetHobSubentry, GetHobValueType, UpdateHobSubentry},
     interaction::{MessageEdit, modal},
     menu::{HobEditState, ViewEntryState, ViewSubentryState},
+    records,
     types::OngoingSubentry,
 };
 use crate::shared::{
     BotData,
-    interaction::MenuChange,
+    interaction::{
+        MenuChange,
+        custom_id::{self, Namespace},
+    },
     menu::{
         ACCENT_COLOR,
         navigation::{BacktrackState as _, GenerateMenu as _},
@@ -30,8 +35,9 @@ pub async fn handle_component(
     menu_id: u64,
     session_state: &mut ViewSubentryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
-    let id_prefix = format!("hob:{menu_id}");
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
 
     match action.next().unwrap_or_default() {
         "back" => {
@@ -165,7 +171,8 @@ pub async fn handle_modal(
     menu_id: u64,
     session_state: &mut ViewSubentryState,
 ) -> Result<MenuChange<'static, HobEditState>> {
-    let db = &ctx.data::<BotData>().db_handle;
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
 
     match action.next().unwrap_or_default() {
         "subentry_submit" => {
@@ -173,14 +180,29 @@ pub async fn handle_modal(
 
             let bingo = Bingo::from_input(&values.bingo)?;
 
-            db.request(UpdateHobSubentry {
-                subentry: OngoingSubentry {
+            let value_type = db
+                .request(GetHobValueType {
+                    entry_id: session_state.entry_id,
+                })
+                .await??;
+            let value = values.value.into_string();
+
+            let subentry = OngoingSubentry {
+                id: session_state.id,
+                entry_id: session_state.entry_id,
+                player: values.player.into_string(),
+                numeric_value: value_type.parse(&value)?,
+                value,
+                bingo,
+            };
+            let previous = db
+                .request(GetHobSubentry {
                     id: session_state.id,
                     entry_id: session_state.entry_id,
-                    player: values.player.into_string(),
-                    value: values.value.into_string(),
-                    bingo,
-                },
+                })
+                .await??;
+            db.request(UpdateHobSubentry {
+                subentry: subentry.clone(),
             })
             .await??;
 
@@ -188,6 +210,13 @@ pub async fn handle_modal(
                 .create_response(ctx.http(), CreateInteractionResponse::Acknowledge)
                 .await?;
 
+            // an edited value can set a new record as well
+            if let Err(err) =
+                records::announce_if_record(ctx.http(), db, &subentry, previous.as_ref()).await
+            {
+                warn!("Failed to announce HoB record: {err:#}");
+            }
+
             let menu = session_state.generate(db, menu_id).await?;
             Ok(MenuChange::message(MessageEdit::Direct(menu)))
         }
@@ -3,12 +3,17 @@ use std::sync::Arc;
 use anyhow::{Context as _, Result};
 use poise::serenity_prelude::{
     Component, CreateComponent, CreateContainer, CreateContainerComponent, CreateTextDisplay,
-    GenericChannelId, Http, MessageId, UserId, async_trait,
+    GenericChannelId, GuildId, Http, MessageId, UserId, async_trait,
 };
+use serde_json::{Value, json};
 use tokio::sync::Notify;
 
 use crate::db::DbHandle;
-use crate::hob::db::{GetAllHobEntries, GetHobEntry, GetHobSubentry, SearchEntriesContent};
+use crate::hob::db::{
+    GetAllHobEntries, GetAllHobTags, GetHobAttachments, GetHobEntry, GetHobRecordDirection,
+    GetHobSubentry, GetHobTags, SearchEntriesContent,
+};
+use crate::shared::interaction::custom_id::{self, Namespace};
 use crate::shared::menu::{
     MenuMessage,
     navigation::{Backtrack, BacktrackState, GenerateMenu},
@@ -17,7 +22,7 @@ use crate::shared::menu::{
 
 pub mod format;
 
-mod select_entry;
+pub mod select_entry;
 mod view_entry;
 
 #[derive(Debug)]
@@ -25,6 +30,8 @@ pub struct HobEditSession {
     pub menu_id: u64,
     pub state: HobEditState,
     pub owner: (UserId, String),
+    /// determines which database the menu operates on
+    pub guild_id: Option<GuildId>,
     pub channel_id: GenericChannelId,
     pub message_id: MessageId,
     pub timeout_reset: Arc<Notify>,
@@ -32,7 +39,7 @@ pub struct HobEditSession {
 
 #[async_trait]
 impl Expirable for HobEditSession {
-    async fn invalidate<'a>(&'a self, http: Arc<Http>) -> Result<&'a str> {
+    async fn invalidate<'a>(&'a self, http: Arc<Http>, notice: &str) -> Result<&'a str> {
         let (&channel_id, &message_id) = self.message_ids();
 
         let mut components = http.get_message(channel_id, message_id).await?.components;
@@ -50,7 +57,7 @@ impl Expirable for HobEditSession {
         }) {
             *container = std::mem::replace(container, CreateContainer::new(Vec::new()))
                 .add_component(CreateContainerComponent::TextDisplay(
-                    CreateTextDisplay::new("-# This menu has expired."),
+                    CreateTextDisplay::new(notice.to_string()),
                 ));
         }
 
@@ -65,16 +72,26 @@ impl Expirable for HobEditSession {
     fn message_ids(&self) -> (&GenericChannelId, &MessageId) {
         (&self.channel_id, &self.message_id)
     }
+
+    fn id_prefix(&self) -> String {
+        custom_id::encode(Namespace::Hob, &[&self.menu_id])
+    }
 }
 
 #[derive(Debug)]
 pub struct SelectEntryState {
     pub page: usize,
     pub search_query: Option<String>,
+    /// only lists entries with this tag
+    pub tag: Option<String>,
 }
 impl SelectEntryState {
     pub fn new(page: usize, search_query: Option<String>) -> Self {
-        Self { page, search_query }
+        Self {
+            page,
+            search_query,
+            tag: None,
+        }
     }
 }
 
@@ -82,6 +99,8 @@ impl SelectEntryState {
 pub struct ViewEntryState {
     pub id: u64,
     pub page: usize,
+    /// whether the entry's tags and announcement settings are shown instead of its subentries
+    pub settings: bool,
     referrer_state: Option<Box<HobEditState>>,
 }
 impl ViewEntryState {
@@ -89,6 +108,7 @@ impl ViewEntryState {
         Self {
             id,
             page,
+            settings: false,
             referrer_state: None,
         }
     }
@@ -153,6 +173,44 @@ impl Backtrack for HobEditState {
     }
 }
 
+impl HobEditState {
+    /// Serialises the current view to be restored after a restart, without its referrers
+    pub fn to_persisted(&self) -> Value {
+        match self {
+            HobEditState::SelectEntry(state) => {
+                json!({
+                    "view": "select",
+                    "page": state.page,
+                    "query": state.search_query,
+                    "tag": state.tag,
+                })
+            }
+            HobEditState::ViewEntry(state) => {
+                json!({ "view": "entry", "id": state.id, "page": state.page })
+            }
+            HobEditState::ViewSubentry(state) => {
+                json!({ "view": "subentry", "id": state.id, "entry_id": state.entry_id })
+            }
+        }
+    }
+
+    pub fn from_persisted(value: &Value) -> Option<Self> {
+        let page = value["page"].as_u64().unwrap_or(0) as usize;
+        Some(match value["view"].as_str()? {
+            "select" => HobEditState::SelectEntry(SelectEntryState {
+                tag: value["tag"].as_str().map(str::to_string),
+                ..SelectEntryState::new(page, value["query"].as_str().map(str::to_string))
+            }),
+            "entry" => HobEditState::ViewEntry(ViewEntryState::new(value["id"].as_u64()?, page)),
+            "subentry" => HobEditState::ViewSubentry(ViewSubentryState::new(
+                value["id"].as_u64()?,
+                value["entry_id"].as_u64()?,
+            )),
+            _ => return None,
+        })
+    }
+}
+
 #[async_trait]
 impl GenerateMenu for HobEditState {
     async fn generate(&mut self, db: &DbHandle, menu_id: u64) -> Result<MenuMessage<'static>> {
@@ -176,9 +234,28 @@ impl GenerateMenu for SelectEntryState {
             }
             None => db.request(GetAllHobEntries).await??,
         };
+
+        let entry_tags = db.request(GetAllHobTags).await??;
+        let mut all_tags: Vec<String> = entry_tags.values().flatten().cloned().collect();
+        all_tags.sort();
+        all_tags.dedup();
+
+        let hob_entries: Vec<_> = match &self.tag {
+            Some(tag) => hob_entries
+                .into_iter()
+                .filter(|entry| {
+                    entry_tags
+                        .get(&entry.id())
+                        .is_some_and(|tags| tags.contains(tag))
+                })
+                .collect(),
+            None => hob_entries,
+        };
+
         Ok(select_entry::generate_entry_list(
             menu_id,
             &hob_entries,
+            &all_tags,
             self,
         ))
     }
@@ -191,11 +268,21 @@ impl GenerateMenu for ViewEntryState {
             .request(GetHobEntry { id: self.id })
             .await??
             .context("Unable to find entry by ID")?;
+        let record_direction = db
+            .request(GetHobRecordDirection { entry_id: self.id })
+            .await??;
+        let attachments = db
+            .request(GetHobAttachments { entry_id: self.id })
+            .await??;
+        let tags = db.request(GetHobTags { entry_id: self.id }).await??;
 
         Ok(view_entry::generate_entry(
             menu_id,
             hob_entry,
-            &mut self.page,
+            record_direction,
+            attachments.len(),
+            &tags,
+            self,
         ))
     }
 }
@@ -1,13 +1,23 @@
-use std::fmt::Write as _;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Context as _, Result, anyhow, bail};
 use poise::serenity_prelude::{
     CreateComponent, CreateContainer, CreateContainerComponent, CreateSeparator, CreateTextDisplay,
     Timestamp,
 };
+use serde_json::{Map, Value, json};
 
-use crate::hob::types::HobEntry;
-use crate::shared::menu::ACCENT_COLOR;
+use crate::error::UserError;
+use crate::hob::types::{self, ArchivedHobEntry, HobEntry, OneOffPlayers, OngoingSubentry};
+use crate::hob::value::ValueType;
+use crate::shared::{
+    menu::ACCENT_COLOR,
+    time::{TimestampStyle, discord_timestamp},
+    types::{Bingo, BingoKind},
+};
 
 const MAX_CHARS: usize = 4000;
 const MAX_COMPONENTS: usize = 40;
@@ -19,8 +29,8 @@ pub fn build_hob_messages(
     const TITLE_TEXT: &str = "# Hall of Bingo";
 
     let footer = format!(
-        "-# Last updated: <t:{}:f>",
-        Timestamp::now().unix_timestamp()
+        "-# Last updated: {}",
+        discord_timestamp(Timestamp::now(), TimestampStyle::ShortDateTime)
     );
     let footer_length = footer.len();
 
@@ -82,6 +92,8 @@ pub fn build_hob_backup_script(hob_entries: &[HobEntry]) -> String {
     let mut oneoff_players_values: Vec<String> = Vec::new();
     let mut ongoing_entries_values: Vec<String> = Vec::new();
     let mut ongoing_subentries_values: Vec<String> = Vec::new();
+    let mut value_types_values: Vec<String> = Vec::new();
+    let mut numeric_values_values: Vec<String> = Vec::new();
 
     for entry in hob_entries {
         match entry {
@@ -110,8 +122,12 @@ pub fn build_hob_backup_script(hob_entries: &[HobEntry]) -> String {
                 id,
                 title,
                 comment,
+                value_type,
                 subentries,
             } => {
+                if *value_type != ValueType::Text {
+                    value_types_values.push(format!("({id}, {})", *value_type as u8));
+                }
                 ongoing_entries_values.push(format!(
                     "({id}, {}, {})",
                     wrap_sql_string(title),
@@ -129,6 +145,9 @@ pub fn build_hob_backup_script(hob_entries: &[HobEntry]) -> String {
                         subentry.bingo.kind_specific_id,
                         subentry.bingo.kind as u8,
                     ));
+                    if let Some(numeric_value) = subentry.numeric_value {
+                        numeric_values_values.push(format!("({}, {numeric_value})", subentry.id));
+                    }
                 }
             }
         }
@@ -166,10 +185,373 @@ pub fn build_hob_backup_script(hob_entries: &[HobEntry]) -> String {
         "id, entry_id, player, value, bingo, bingo_kind",
         &ongoing_subentries_values,
     );
+    output.push_str("\n\n");
+    append_section(
+        &mut output,
+        "Iterative value types",
+        "hob_ongoing_value_types",
+        "entry_id, value_type",
+        &value_types_values,
+    );
+    output.push_str("\n\n");
+    append_section(
+        &mut output,
+        "Iterative numeric values",
+        "hob_subentry_numeric_values",
+        "subentry_id, numeric_value",
+        &numeric_values_values,
+    );
+
+    output
+}
+
+/// Human-readable archive of the full HoB, e.g. for publishing it outside of Discord
+pub fn build_hob_markdown(hob_entries: &[HobEntry]) -> String {
+    let mut output = format!(
+        "# Hall of Bingo\n\n*Exported on {}*\n",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+
+    output.push_str("\n## One-off Entries\n");
+    for entry in hob_entries {
+        if let HobEntry::OneOff {
+            title,
+            comment,
+            bingo,
+            players,
+            ..
+        } = entry
+        {
+            write!(
+                output,
+                "\n### {}\n- **Bingo:** {bingo}\n- **Players:** {}\n",
+                escape_markdown(title),
+                players
+                    .players
+                    .iter()
+                    .map(escape_markdown)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .unwrap();
+            if let Some(comment) = comment {
+                writeln!(output, "\n> {}", escape_markdown(comment)).unwrap();
+            }
+        }
+    }
+
+    output.push_str("\n## Iterative Entries\n");
+    for entry in hob_entries {
+        if let HobEntry::Ongoing {
+            title,
+            comment,
+            subentries,
+            ..
+        } = entry
+        {
+            writeln!(output, "\n### {}", escape_markdown(title)).unwrap();
+            if let Some(comment) = comment {
+                writeln!(output, "\n> {}", escape_markdown(comment)).unwrap();
+            }
+            if subentries.is_empty() {
+                output.push_str("\n*No entries yet.*\n");
+                continue;
+            }
+            output.push_str("\n| Player | Value | Bingo |\n| --- | --- | --- |\n");
+            for subentry in subentries {
+                writeln!(
+                    output,
+                    "| {} | {} | {} |",
+                    escape_markdown(&subentry.player).replace('|', "\\|"),
+                    escape_markdown(&subentry.value).replace('|', "\\|"),
+                    subentry.bingo
+                )
+                .unwrap();
+            }
+        }
+    }
 
     output
 }
 
+/// Machine-readable archive of the full HoB, including all IDs. Attachments and tags are keyed by
+/// entry ID.
+pub fn build_hob_json(
+    hob_entries: &[HobEntry],
+    attachments: &HashMap<u64, Vec<String>>,
+    tags: &HashMap<u64, Vec<String>>,
+) -> String {
+    let entries: Vec<Value> = hob_entries
+        .iter()
+        .map(|entry| {
+            let of_entry = |lists: &HashMap<u64, Vec<String>>| {
+                json!(lists.get(&entry.id()).cloned().unwrap_or_default())
+            };
+            let mut value = entry_json(entry);
+            value["attachments"] = of_entry(attachments);
+            value["tags"] = of_entry(tags);
+            value
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "entries": entries,
+    }))
+    .unwrap_or_default()
+}
+
+fn entry_json(entry: &HobEntry) -> Value {
+    match entry {
+        HobEntry::OneOff {
+            id,
+            title,
+            comment,
+            bingo,
+            players,
+        } => json!({
+            "id": id,
+            "type": "one_off",
+            "title": title,
+            "comment": comment,
+            "bingo": bingo_json(bingo),
+            "players": players.players,
+        }),
+        HobEntry::Ongoing {
+            id,
+            title,
+            comment,
+            value_type,
+            subentries,
+        } => json!({
+            "id": id,
+            "type": "ongoing",
+            "title": title,
+            "comment": comment,
+            "value_type": value_type.key(),
+            "subentries": subentries
+                .iter()
+                .map(|subentry| json!({
+                    "id": subentry.id,
+                    "player": subentry.player,
+                    "value": subentry.value,
+                    "bingo": bingo_json(&subentry.bingo),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn bingo_json(bingo: &Bingo) -> Value {
+    json!({
+        "kind": format!("{:?}", bingo.kind),
+        "kind_specific_id": bingo.kind_specific_id,
+        "name": bingo.to_string(),
+    })
+}
+
+/// Parses and validates a HoB export created by [`build_hob_json`]
+pub fn parse_hob_json(input: &str) -> Result<Vec<ArchivedHobEntry>> {
+    let root: Value = serde_json::from_str(input)
+        .map_err(|err| UserError(anyhow!("The file isn't valid JSON: {err}")))?;
+    let entries = root
+        .get("entries")
+        .and_then(Value::as_array)
+        .context(UserError(anyhow!(
+            "The file doesn't contain an `entries` list, is it a HoB export?"
+        )))?;
+
+    let mut ids = HashSet::new();
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let entry = parse_hob_json_entry(entry)
+                .with_context(|| UserError(anyhow!("Invalid entry #{}", i + 1)))?;
+            if !ids.insert(entry.entry.id()) {
+                bail!(UserError(anyhow!(
+                    "Entry #{} reuses the ID {} of a previous entry",
+                    i + 1,
+                    entry.entry.id()
+                )));
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+fn parse_hob_json_entry(entry: &Value) -> Result<ArchivedHobEntry> {
+    let entry = entry
+        .as_object()
+        .context(UserError(anyhow!("Expected an object")))?;
+
+    // exports from before attachments and tags were introduced contain neither
+    let attachments = json_strings(entry, "attachments")?;
+    if let Some(invalid) = attachments.iter().find(|url| !types::is_image_url(url)) {
+        bail!(UserError(anyhow!("`{invalid}` isn't a link to an image")));
+    }
+    if attachments.len() > types::MAX_GALLERY_ITEMS {
+        bail!(UserError(anyhow!(
+            "Entries can have at most {} attachments",
+            types::MAX_GALLERY_ITEMS
+        )));
+    }
+    let tags = types::parse_tags(&json_strings(entry, "tags")?.join(","))?;
+
+    Ok(ArchivedHobEntry {
+        entry: parse_entry_fields(entry)?,
+        attachments,
+        tags,
+    })
+}
+
+fn parse_entry_fields(entry: &Map<String, Value>) -> Result<HobEntry> {
+    let id = json_field(entry, "id", Value::as_u64)?;
+    let title = json_field(entry, "title", Value::as_str)?
+        .trim()
+        .to_string();
+    if title.is_empty() {
+        bail!(UserError(anyhow!("The title can't be empty")));
+    }
+    let comment = match entry.get("comment") {
+        None | Some(Value::Null) => None,
+        Some(comment) => Some(
+            comment
+                .as_str()
+                .context(UserError(anyhow!("Expected `comment` to be a string")))?
+                .to_string(),
+        ),
+    }
+    .filter(|comment| !comment.trim().is_empty());
+
+    match json_field(entry, "type", Value::as_str)? {
+        "one_off" => {
+            let players = json_field(entry, "players", Value::as_array)?
+                .iter()
+                .map(|player| {
+                    player
+                        .as_str()
+                        .map(|p| p.trim().to_string())
+                        .filter(|p| !p.is_empty())
+                        .context(UserError(anyhow!(
+                            "Expected players to be non-empty strings"
+                        )))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if players.is_empty() {
+                bail!(UserError(anyhow!(
+                    "One-off entries need at least one player"
+                )));
+            }
+
+            Ok(HobEntry::OneOff {
+                id,
+                title,
+                comment,
+                bingo: parse_bingo_json(json_field(entry, "bingo", Some)?)?,
+                players: OneOffPlayers { players },
+            })
+        }
+        "ongoing" => {
+            // exports from before value types were introduced only contain text values
+            let value_type =
+                match entry.get("value_type") {
+                    None | Some(Value::Null) => ValueType::Text,
+                    Some(value_type) => value_type.as_str().and_then(ValueType::from_key).context(
+                        UserError(anyhow!(
+                            "Expected `value_type` to be one of `text`, `number`, `duration` or \
+                         `percentage`"
+                        )),
+                    )?,
+                };
+
+            let subentries = json_field(entry, "subentries", Value::as_array)?
+                .iter()
+                .map(|subentry| {
+                    let subentry = subentry
+                        .as_object()
+                        .context(UserError(anyhow!("Expected subentries to be objects")))?;
+                    let value = json_field(subentry, "value", Value::as_str)?.to_string();
+                    Ok(OngoingSubentry {
+                        // assigned by the database on import
+                        id: 0,
+                        entry_id: id,
+                        player: json_field(subentry, "player", Value::as_str)?.to_string(),
+                        // like after changing the value type, unparsable values are kept as
+                        // entered and left out of sorting
+                        numeric_value: value_type.parse(&value).ok().flatten(),
+                        value,
+                        bingo: parse_bingo_json(json_field(subentry, "bingo", Some)?)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(HobEntry::Ongoing {
+                id,
+                title,
+                comment,
+                value_type,
+                subentries,
+            })
+        }
+        other => bail!(UserError(anyhow!(
+            "Unknown entry type `{other}` (expected `one_off` or `ongoing`)"
+        ))),
+    }
+}
+
+fn parse_bingo_json(bingo: &Value) -> Result<Bingo> {
+    let bingo = bingo
+        .as_object()
+        .context(UserError(anyhow!("Expected `bingo` to be an object")))?;
+
+    let kind = match json_field(bingo, "kind", Value::as_str)? {
+        "Normal" => BingoKind::Normal,
+        "Extreme" => BingoKind::Extreme,
+        "Secret" => BingoKind::Secret,
+        other => bail!(UserError(anyhow!("Unknown bingo kind `{other}`"))),
+    };
+    let kind_specific_id = u8::try_from(json_field(bingo, "kind_specific_id", Value::as_u64)?)
+        .context(UserError(anyhow!("Bingo ID out of range")))?;
+
+    Ok(Bingo::new(kind_specific_id, kind, None))
+}
+
+/// Strings of an optional list, which is treated as empty if missing
+fn json_strings(object: &Map<String, Value>, key: &str) -> Result<Vec<String>> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(list) => list
+            .as_array()
+            .and_then(|list| {
+                list.iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .with_context(|| UserError(anyhow!("Expected `{key}` to be a list of strings"))),
+    }
+}
+
+fn json_field<'a, T>(
+    object: &'a Map<String, Value>,
+    key: &str,
+    extract: impl FnOnce(&'a Value) -> Option<T>,
+) -> Result<T> {
+    object
+        .get(key)
+        .and_then(extract)
+        .with_context(|| UserError(anyhow!("Missing or invalid field `{key}`")))
+}
+
+fn escape_markdown(text: impl AsRef<str>) -> String {
+    text.as_ref()
+        .replace('\\', "\\\\")
+        .replace(['\n', '\r'], " ")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+        .replace('`', "\\`")
+}
+
 fn wrap_sql_string<T: AsRef<str>>(value: T) -> String {
     let s = value.as_ref();
     let escaped = s.replace("'", "''");
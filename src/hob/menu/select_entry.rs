@@ -1,23 +1,35 @@
 use poise::serenity_prelude::{
-    ButtonStyle, CreateButton, CreateComponent, CreateContainer, CreateContainerComponent,
-    CreateSection, CreateSectionAccessory, CreateSectionComponent, CreateSeparator,
+    ButtonStyle, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateSection, CreateSectionAccessory, CreateSectionComponent,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, CreateSeparator,
     CreateTextDisplay,
 };
 
 use crate::hob::{menu::SelectEntryState, types::HobEntry};
-use crate::shared::menu::{
-    MenuMessage,
-    navigation::{self, PaginatedChunk},
+use crate::shared::{
+    interaction::custom_id::{self, Namespace},
+    menu::{
+        MenuMessage,
+        navigation::{self, PaginatedChunk},
+        transfer,
+    },
 };
 
-const PAGE_SIZE: usize = 6;
+// NOTE: Discord limits messages to 40 components (nested ones included), a full page uses 35 to
+// leave room for the expiry warning (3 components)
+const PAGE_SIZE: usize = 4;
+/// Discord's limit of select menu options, including the one resetting the filter
+const MAX_TAG_OPTIONS: usize = 25;
+/// Option value resetting the tag filter, which can't clash with tags as they are alphanumeric
+pub const ALL_TAGS: &str = "*";
 
 pub fn generate_entry_list(
     menu_id: u64,
     hob_entries: &[HobEntry],
+    all_tags: &[String],
     session_state: &mut SelectEntryState,
 ) -> MenuMessage<'static> {
-    let id_prefix = format!("hob:{menu_id}");
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
 
     let chunk = PaginatedChunk::new(hob_entries.len(), session_state.page, PAGE_SIZE);
     session_state.page = chunk.page;
@@ -79,13 +91,62 @@ pub fn generate_entry_list(
         CreateSectionAccessory::Button(create_button),
     ));
 
-    let components: Vec<_> = [title_section, showing_section, divider]
+    let tag_row = (!all_tags.is_empty())
+        .then(|| tag_select(&id_prefix, all_tags, session_state.tag.as_deref()));
+
+    let components: Vec<_> = [title_section, showing_section]
         .into_iter()
+        .chain(tag_row)
+        .chain([divider])
         .chain(entry_components)
-        .chain([navigation])
+        .chain([navigation, transfer::section(&id_prefix)])
         .collect();
 
     MenuMessage {
         components: vec![CreateComponent::Container(CreateContainer::new(components))],
     }
 }
+
+/// Filters the listed entries by tag
+fn tag_select(
+    id_prefix: &str,
+    all_tags: &[String],
+    selected: Option<&str>,
+) -> CreateContainerComponent<'static> {
+    let mut listed: Vec<_> = all_tags.iter().take(MAX_TAG_OPTIONS - 1).collect();
+    // the active filter has to stay listed, so that it's shown as selected
+    if let Some(selected) = selected
+        && !listed.iter().any(|tag| *tag == selected)
+        && let Some(tag) = all_tags.iter().find(|tag| *tag == selected)
+    {
+        listed.pop();
+        listed.push(tag);
+    }
+
+    let mut all_option =
+        CreateSelectMenuOption::new("All tags", ALL_TAGS).default_selection(selected.is_none());
+    let unlisted = all_tags.len() - listed.len();
+    if unlisted > 0 {
+        all_option = all_option.description(format!(
+            "{unlisted} more tags don't fit here, search for them instead"
+        ));
+    }
+
+    let options: Vec<_> = std::iter::once(all_option)
+        .chain(listed.into_iter().map(|tag| {
+            CreateSelectMenuOption::new(format!("Tag: {tag}"), tag.clone())
+                .default_selection(selected == Some(tag.as_str()))
+        }))
+        .collect();
+
+    CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{id_prefix}:filter_tag"),
+            CreateSelectMenuKind::String {
+                options: options.into(),
+            },
+        )
+        .min_values(1)
+        .max_values(1),
+    ))
+}
@@ -1,19 +1,43 @@
 use poise::serenity_prelude::{
     ButtonStyle, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
     CreateContainerComponent, CreateSection, CreateSectionAccessory, CreateSectionComponent,
-    CreateSeparator, CreateTextDisplay,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, CreateSeparator,
+    CreateTextDisplay,
 };
 
-use crate::hob::types::{HobEntry, OngoingSubentry};
-use crate::shared::menu::{
-    MenuMessage,
-    navigation::{self, PaginatedChunk},
+use crate::hob::{
+    menu::ViewEntryState,
+    records::RecordDirection,
+    types::{HobEntry, OngoingSubentry},
+    value::ValueType,
 };
+use crate::shared::{
+    interaction::custom_id::{self, Namespace},
+    menu::{
+        MenuMessage,
+        navigation::{self, PaginatedChunk},
+    },
+};
+
+// NOTE: Discord limits messages to 40 components (nested ones included), a full page of subentries
+// uses 35 to leave room for the expiry warning (3 components). The settings have their own view
+// for the same reason.
+const SUBENTRIES_PAGE_SIZE: usize = 4;
 
-const SUBENTRIES_PAGE_SIZE: usize = 5;
+pub fn generate_entry(
+    menu_id: u64,
+    hob_entry: HobEntry,
+    record_direction: Option<RecordDirection>,
+    attachment_count: usize,
+    tags: &[String],
+    session_state: &mut ViewEntryState,
+) -> MenuMessage<'static> {
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
+
+    if session_state.settings {
+        return generate_settings(&id_prefix, &hob_entry, record_direction, tags);
+    }
 
-pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> MenuMessage<'static> {
-    let id_prefix = format!("hob:{menu_id}");
     let title = CreateSectionComponent::TextDisplay(CreateTextDisplay::new("# View HoB Entry"));
 
     let delete_button = CreateSectionAccessory::Button(
@@ -27,14 +51,30 @@ pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> Me
     let preview_button = CreateButton::new(format!("{id_prefix}:preview"))
         .label("Preview")
         .style(ButtonStyle::Secondary);
+    let attachments_button = CreateButton::new(format!("{id_prefix}:attachments"))
+        .emoji('🖼')
+        .label(format!("Attachments ({attachment_count})"))
+        .style(ButtonStyle::Secondary);
+    let settings_button = CreateButton::new(format!("{id_prefix}:settings"))
+        .emoji('⚙')
+        .label("Settings")
+        .style(ButtonStyle::Secondary);
 
     let title_section =
         CreateContainerComponent::Section(CreateSection::new(vec![title], delete_button));
 
     let edit_row = CreateContainerComponent::ActionRow(CreateActionRow::Buttons(
-        vec![edit_button, preview_button].into(),
+        vec![
+            edit_button,
+            preview_button,
+            attachments_button,
+            settings_button,
+        ]
+        .into(),
     ));
 
+    let tag_list = tag_list(tags);
+
     let divider = CreateContainerComponent::Separator(CreateSeparator::new(true));
 
     let components = match hob_entry {
@@ -53,13 +93,15 @@ pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> Me
 ### Players\n{}
 ### Bingo\n{}
 ### Comment\n{}
+### Tags\n{}
 ",
                     title,
                     players.to_list(),
                     bingo,
                     comment
                         .filter(|s| !s.is_empty())
-                        .unwrap_or_else(|| "*None*".to_string())
+                        .unwrap_or_else(|| "*None*".to_string()),
+                    tag_list,
                 )));
 
             let navigation_row = navigation::nagivation_back(&id_prefix);
@@ -75,11 +117,13 @@ pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> Me
         HobEntry::Ongoing {
             title,
             comment,
+            value_type,
             subentries,
             ..
         } => {
-            let page_chunk = PaginatedChunk::new(subentries.len(), *page, SUBENTRIES_PAGE_SIZE);
-            *page = page_chunk.page;
+            let page_chunk =
+                PaginatedChunk::new(subentries.len(), session_state.page, SUBENTRIES_PAGE_SIZE);
+            session_state.page = page_chunk.page;
             let subentries_paginated = &subentries[page_chunk.range.clone()];
 
             let description =
@@ -88,11 +132,16 @@ pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> Me
 ### Type\nIterative achievement
 ### Title\n{}
 ### Comment\n{}
+### Value Type\n{} (e.g. {})
+### Tags\n{}
 ",
                     title,
                     comment
                         .filter(|s| !s.is_empty())
                         .unwrap_or_else(|| "*None*".to_string()),
+                    value_type.name(),
+                    value_type.example(),
+                    tag_list,
                 )));
 
             let subentry_text =
@@ -141,8 +190,115 @@ pub fn generate_entry(menu_id: u64, hob_entry: HobEntry, page: &mut usize) -> Me
     }
 }
 
+/// Tags and, for ongoing entries, the value type and record announcements
+fn generate_settings(
+    id_prefix: &str,
+    hob_entry: &HobEntry,
+    record_direction: Option<RecordDirection>,
+    tags: &[String],
+) -> MenuMessage<'static> {
+    let title = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+        "# HoB Entry Settings\n{}",
+        hob_entry.title()
+    )));
+
+    let tags_section = CreateContainerComponent::Section(CreateSection::new(
+        vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+            format!("### Tags\n{}", tag_list(tags)),
+        ))],
+        CreateSectionAccessory::Button(
+            CreateButton::new(format!("{id_prefix}:tags"))
+                .label("Edit Tags")
+                .style(ButtonStyle::Secondary),
+        ),
+    ));
+
+    let divider = CreateContainerComponent::Separator(CreateSeparator::new(true));
+
+    let ongoing_settings = match hob_entry {
+        HobEntry::OneOff { .. } => Vec::new(),
+        HobEntry::Ongoing { value_type, .. } => vec![
+            value_type_select(id_prefix, *value_type),
+            record_select(id_prefix, record_direction),
+        ],
+    };
+
+    let components: Vec<_> = [title, divider.clone(), tags_section]
+        .into_iter()
+        .chain(ongoing_settings)
+        .chain([divider, navigation::nagivation_back(id_prefix)])
+        .collect();
+
+    MenuMessage {
+        components: vec![CreateComponent::Container(CreateContainer::new(components))],
+    }
+}
+
+fn tag_list(tags: &[String]) -> String {
+    if tags.is_empty() {
+        "*None*".to_string()
+    } else {
+        tags.iter()
+            .map(|tag| format!("`{tag}`"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// How subentry values are validated and normalized
+fn value_type_select(id_prefix: &str, value_type: ValueType) -> CreateContainerComponent<'static> {
+    let options: Vec<_> = ValueType::ALL
+        .iter()
+        .map(|&kind| {
+            CreateSelectMenuOption::new(format!("Value type: {}", kind.name()), kind.key())
+                .default_selection(kind == value_type)
+        })
+        .collect();
+
+    CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{id_prefix}:value_type"),
+            CreateSelectMenuKind::String {
+                options: options.into(),
+            },
+        )
+        .min_values(1)
+        .max_values(1),
+    ))
+}
+
+/// Whether and how new records of an ongoing entry are announced
+fn record_select(
+    id_prefix: &str,
+    record_direction: Option<RecordDirection>,
+) -> CreateContainerComponent<'static> {
+    let options: Vec<_> = std::iter::once(
+        CreateSelectMenuOption::new("Don't announce records", "off")
+            .default_selection(record_direction.is_none()),
+    )
+    .chain(RecordDirection::ALL.iter().map(|&direction| {
+        CreateSelectMenuOption::new(
+            format!("Announce records: {}", direction.name()),
+            (direction as u8).to_string(),
+        )
+        .default_selection(record_direction == Some(direction))
+    }))
+    .collect();
+
+    CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{id_prefix}:record_announcements"),
+            CreateSelectMenuKind::String {
+                options: options.into(),
+            },
+        )
+        .min_values(1)
+        .max_values(1),
+    ))
+}
+
 pub fn generate_subentry(menu_id: u64, subentry: OngoingSubentry) -> MenuMessage<'static> {
-    let id_prefix = format!("hob:{menu_id}");
+    let id_prefix = custom_id::encode(Namespace::Hob, &[&menu_id]);
     let title = CreateSectionComponent::TextDisplay(CreateTextDisplay::new("# View Subentry"));
 
     let delete_button = CreateSectionAccessory::Button(
@@ -1,4 +1,8 @@
 pub mod db;
 pub mod interaction;
 pub mod menu;
+pub mod permission;
+pub mod publish;
+pub mod records;
 pub mod types;
+pub mod value;
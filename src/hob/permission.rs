@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{Member, Mentionable as _, Permissions, RoleId};
+
+use crate::hob::menu::HobEditState;
+
+/// Menu actions which can be restricted to a staff role, on top of the `/hob manage` permission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, poise::ChoiceParameter)]
+pub enum HobAction {
+    #[name = "Create entries"]
+    Create,
+    #[name = "Edit entries"]
+    Edit,
+    #[name = "Delete entries"]
+    Delete,
+}
+
+impl HobAction {
+    pub const ALL: &[HobAction; 3] = &[HobAction::Create, HobAction::Edit, HobAction::Delete];
+
+    pub fn from_u8(int: u8) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| *action as u8 == int)
+    }
+
+    /// The restricted action an interaction performs in the menu's current state, if any
+    pub fn of_interaction(state: &HobEditState, action: &str) -> Option<Self> {
+        match (state, action) {
+            (
+                HobEditState::SelectEntry(_),
+                "create_entry"
+                | "create_oneoff_confirm"
+                | "create_ongoing_confirm"
+                | "oneoff_submit"
+                | "ongoing_submit",
+            )
+            | (HobEditState::ViewEntry(_), "create_subentry" | "subentry_submit") => {
+                Some(HobAction::Create)
+            }
+            (
+                HobEditState::ViewEntry(_),
+                "edit"
+                | "oneoff_submit"
+                | "ongoing_submit"
+                | "record_announcements"
+                | "value_type"
+                | "attachments"
+                | "attachments_submit"
+                | "tags"
+                | "tags_submit",
+            )
+            | (HobEditState::ViewSubentry(_), "edit" | "subentry_submit") => Some(HobAction::Edit),
+            (
+                HobEditState::ViewEntry(_) | HobEditState::ViewSubentry(_),
+                "delete" | "delete_confirm",
+            ) => Some(HobAction::Delete),
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            HobAction::Create => "Creating HoB entries",
+            HobAction::Edit => "Editing HoB entries",
+            HobAction::Delete => "Deleting HoB entries",
+        }
+    }
+}
+
+/// Returns the role the member is missing for the action, if it is restricted. Administrators are
+/// never restricted, so that a deleted role can't lock everyone out.
+pub fn missing_role(
+    permissions: &HashMap<HobAction, RoleId>,
+    action: HobAction,
+    member: Option<&Member>,
+) -> Option<RoleId> {
+    let role = *permissions.get(&action)?;
+
+    let allowed = member.is_some_and(|member| {
+        member.roles.contains(&role)
+            || member
+                .permissions
+                .is_some_and(|p| p.contains(Permissions::ADMINISTRATOR))
+    });
+
+    (!allowed).then_some(role)
+}
+
+pub fn denial_text(action: HobAction, role: RoleId) -> String {
+    format!(
+        "## Missing Permission
+{} requires the {} role. Ask an administrator if you need access.",
+        action.description(),
+        role.mention()
+    )
+}
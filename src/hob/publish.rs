@@ -0,0 +1,115 @@
+use anyhow::{Result, anyhow};
+use poise::serenity_prelude::{
+    CreateAllowedMentions, CreateMessage, GenericChannelId, Http, MessageFlags, MessageId,
+};
+use tracing::{info, warn};
+
+use crate::db::DbHandle;
+use crate::error::UserError;
+use crate::hob::{
+    db::{GetAllHobEntries, GetHobPublishedMessages, SetHobPublishedMessages},
+    menu::format,
+};
+use crate::shared::menu::MenuMessage;
+
+/// Upper bound of messages the showcase may span
+const MAX_MESSAGES: usize = 10;
+
+#[derive(Debug)]
+pub struct PublishOutcome {
+    pub channel: GenericChannelId,
+    pub edited: usize,
+    pub sent: usize,
+    pub deleted: usize,
+}
+
+/// Renders the full HoB into the showcase channel. Previously published messages in the same
+/// channel are edited in place, so that links to them and the channel's history stay intact.
+///
+/// If `channel` is `None`, the channel of the previous publish is used.
+pub async fn publish(
+    http: &Http,
+    db: &DbHandle,
+    channel: Option<GenericChannelId>,
+) -> Result<PublishOutcome> {
+    let previous = db.request(GetHobPublishedMessages).await??;
+    let channel = channel
+        .or_else(|| previous.first().map(|(channel, _)| *channel))
+        .ok_or_else(|| {
+            UserError(anyhow!(
+                "The HoB hasn't been published yet, please specify a showcase channel"
+            ))
+        })?;
+
+    let hob_entries = db.request(GetAllHobEntries).await??;
+    let containers = format::build_hob_messages(&hob_entries, MAX_MESSAGES)?;
+
+    let mut outcome = PublishOutcome {
+        channel,
+        edited: 0,
+        sent: 0,
+        deleted: 0,
+    };
+
+    // messages in other channels are outdated after moving the showcase
+    let (reusable, mut stale): (Vec<_>, Vec<_>) = previous
+        .into_iter()
+        .partition(|(previous_channel, _)| *previous_channel == channel);
+    let mut reusable = reusable.into_iter();
+
+    let mut published: Vec<(GenericChannelId, MessageId)> = Vec::new();
+    // once a message fails to be edited, all following ones are reposted to keep the order intact
+    let mut reposting = false;
+
+    for container in containers {
+        if !reposting && let Some((_, message_id)) = reusable.next() {
+            let edit = MenuMessage::new(vec![container.clone()]).into_edit();
+            match http.edit_message(channel, message_id, &edit, vec![]).await {
+                Ok(_) => {
+                    published.push((channel, message_id));
+                    outcome.edited += 1;
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Failed to edit published HoB message {message_id}, reposting: {err:#}");
+                    stale.push((channel, message_id));
+                    reposting = true;
+                }
+            }
+        }
+
+        let message = channel
+            .send_message(
+                http,
+                CreateMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![container]),
+            )
+            .await?;
+        published.push((channel, message.id));
+        outcome.sent += 1;
+    }
+    stale.extend(reusable);
+
+    // track the new messages before cleaning up, so that a failed cleanup can't lose them
+    db.request(SetHobPublishedMessages {
+        messages: published,
+    })
+    .await??;
+
+    for (stale_channel, message_id) in stale {
+        match http.delete_message(stale_channel, message_id, None).await {
+            Ok(()) => outcome.deleted += 1,
+            // most likely deleted manually already
+            Err(err) => warn!("Failed to delete outdated HoB message {message_id}: {err:#}"),
+        }
+    }
+
+    info!(
+        "Published HoB to channel {channel}: {} edited, {} sent, {} deleted",
+        outcome.edited, outcome.sent, outcome.deleted
+    );
+
+    Ok(outcome)
+}
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use poise::serenity_prelude::{
+    CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
+    CreateMessage, CreateTextDisplay, Http, MessageFlags,
+};
+use tracing::info;
+
+use crate::db::DbHandle;
+use crate::hob::{
+    db::{GetHobEntry, GetHobRecordChannel, GetHobRecordDirection, GetHobValueType},
+    types::{HobEntry, OngoingSubentry},
+    value::{self, ValueType},
+};
+use crate::shared::menu::ACCENT_COLOR;
+
+/// Which subentry value of an ongoing entry counts as the record
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordDirection {
+    Highest = 0,
+    Lowest = 1,
+}
+
+impl RecordDirection {
+    pub const ALL: &[RecordDirection; 2] = &[RecordDirection::Highest, RecordDirection::Lowest];
+
+    pub fn from_u8(int: u8) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|direction| *direction as u8 == int)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordDirection::Highest => "Highest value",
+            RecordDirection::Lowest => "Lowest value",
+        }
+    }
+
+    fn beats(self, value: f64, best: f64) -> bool {
+        match self {
+            RecordDirection::Highest => value > best,
+            RecordDirection::Lowest => value < best,
+        }
+    }
+}
+
+/// Announces the subentry in the configured channel if its entry has record announcements enabled
+/// and the value beats all other subentries of the entry. `previous` is the subentry before it was
+/// edited, which isn't announced again if it already held the record.
+pub async fn announce_if_record(
+    http: &Http,
+    db: &DbHandle,
+    subentry: &OngoingSubentry,
+    previous: Option<&OngoingSubentry>,
+) -> Result<()> {
+    let Some(channel) = db.request(GetHobRecordChannel).await?? else {
+        return Ok(());
+    };
+    let Some(direction) = db
+        .request(GetHobRecordDirection {
+            entry_id: subentry.entry_id,
+        })
+        .await??
+    else {
+        return Ok(());
+    };
+    let Some(HobEntry::Ongoing {
+        title, subentries, ..
+    }) = db
+        .request(GetHobEntry {
+            id: subentry.entry_id,
+        })
+        .await??
+    else {
+        return Ok(());
+    };
+    let value_type = db
+        .request(GetHobValueType {
+            entry_id: subentry.entry_id,
+        })
+        .await??;
+    let Some(value) = comparable_value(value_type, subentry) else {
+        return Ok(());
+    };
+
+    let previous_best = subentries
+        .iter()
+        .filter(|other| other.id != subentry.id)
+        .filter_map(|other| comparable_value(value_type, other))
+        .reduce(|best, other| {
+            if direction.beats(other, best) {
+                other
+            } else {
+                best
+            }
+        });
+
+    let previous_value = previous.and_then(|previous| comparable_value(value_type, previous));
+    if !is_new_record(direction, value, previous_value, previous_best) {
+        return Ok(());
+    }
+
+    channel
+        .send_message(
+            http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                        CreateTextDisplay::new(format!(
+                            "## New Record for {title}\n`{}` with **{}** during {}",
+                            subentry.player, subentry.value, subentry.bingo
+                        )),
+                    )])
+                    .accent_color(ACCENT_COLOR),
+                )]),
+        )
+        .await?;
+
+    info!(
+        "Announced new HoB record for '{title}': {} with {}",
+        subentry.player, subentry.value
+    );
+
+    Ok(())
+}
+
+/// Whether `value` beats the best value of the other subentries, which the subentry didn't already
+/// hold with its value from before an edit
+fn is_new_record(
+    direction: RecordDirection,
+    value: f64,
+    previous_value: Option<f64>,
+    others_best: Option<f64>,
+) -> bool {
+    // the first subentry of an entry has nothing to beat
+    let Some(best) = others_best else {
+        return false;
+    };
+    let held_record = previous_value.is_some_and(|previous| direction.beats(previous, best));
+    direction.beats(value, best) && !held_record
+}
+
+/// Typed values are compared by their normalized number. Text values have none, so their leading
+/// number is compared instead, as records were announced before entries had value types.
+fn comparable_value(value_type: ValueType, subentry: &OngoingSubentry) -> Option<f64> {
+    match value_type {
+        ValueType::Text => value::parse_number(&subentry.value),
+        _ => subentry.numeric_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::types::{Bingo, BingoKind};
+
+    #[test]
+    fn compare_directions() {
+        assert!(RecordDirection::Highest.beats(2.0, 1.0));
+        assert!(!RecordDirection::Highest.beats(1.0, 1.0));
+        assert!(RecordDirection::Lowest.beats(1.0, 2.0));
+    }
+
+    #[test]
+    fn announce_only_new_records() {
+        let highest = RecordDirection::Highest;
+        assert!(is_new_record(highest, 3.0, None, Some(2.0)));
+        assert!(!is_new_record(highest, 3.0, None, None));
+        assert!(!is_new_record(highest, 1.0, None, Some(2.0)));
+        // e.g. the player name of the record holder was edited
+        assert!(!is_new_record(highest, 3.0, Some(3.0), Some(2.0)));
+        assert!(!is_new_record(highest, 4.0, Some(3.0), Some(2.0)));
+        assert!(is_new_record(highest, 3.0, Some(1.0), Some(2.0)));
+        assert!(is_new_record(highest, 3.0, Some(2.0), Some(2.0)));
+    }
+
+    #[test]
+    fn compare_values_by_type() {
+        let subentry = |value: &str, numeric_value| OngoingSubentry {
+            id: 1,
+            entry_id: 1,
+            player: "player".to_string(),
+            value: value.to_string(),
+            numeric_value,
+            bingo: Bingo::new(1, BingoKind::Normal, None),
+        };
+
+        assert_eq!(
+            comparable_value(ValueType::Text, &subentry("2.5M coins", None)),
+            Some(2_500_000.0)
+        );
+        assert_eq!(
+            comparable_value(ValueType::Duration, &subentry("1m 30s", Some(90.0))),
+            Some(90.0)
+        );
+        assert_eq!(
+            comparable_value(ValueType::Duration, &subentry("soon", None)),
+            None
+        );
+    }
+}
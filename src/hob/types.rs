@@ -1,11 +1,70 @@
 use std::borrow::Cow;
 
+use anyhow::{Result, anyhow, bail};
 use poise::serenity_prelude::{
-    ButtonStyle, CreateButton, CreateContainerComponent, CreateSection, CreateSectionAccessory,
-    CreateSectionComponent, CreateTextDisplay,
+    ButtonStyle, CreateButton, CreateContainerComponent, CreateMediaGallery,
+    CreateMediaGalleryItem, CreateSection, CreateSectionAccessory, CreateSectionComponent,
+    CreateTextDisplay, CreateUnfurledMediaItem,
 };
 
-use crate::shared::types::Bingo;
+use crate::error::UserError;
+use crate::hob::value::ValueType;
+use crate::shared::types::{Bingo, BingoKind};
+
+const IMAGE_EXTENSIONS: &[&str] = &[".png", ".jpg", ".jpeg", ".gif", ".webp"];
+/// Discord's limit of items in a media gallery
+pub const MAX_GALLERY_ITEMS: usize = 10;
+
+pub const MAX_TAGS: usize = 10;
+/// Short enough to fit into select menu options and the entry list
+const MAX_TAG_LENGTH: usize = 32;
+
+/// Parses comma-separated tags, normalized to lowercase with whitespace replaced by dashes, in
+/// alphabetical order
+pub fn parse_tags(input: &str) -> Result<Vec<String>> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',') {
+        let tag = tag
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        if tag.is_empty() || tags.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH
+            || !tag.chars().all(|c| c.is_alphanumeric() || c == '-')
+        {
+            bail!(UserError(anyhow!(
+                "Invalid tag `{tag}`: Tags may only contain letters, numbers and dashes, and be at \
+                 most {MAX_TAG_LENGTH} characters long"
+            )));
+        }
+        tags.push(tag);
+    }
+    if tags.len() > MAX_TAGS {
+        bail!(UserError(anyhow!(
+            "Entries can have at most {MAX_TAGS} tags"
+        )));
+    }
+
+    tags.sort();
+    Ok(tags)
+}
+
+/// Whether the URL points to an image, ignoring query parameters as used by Discord's CDN
+pub fn is_image_url(url: &str) -> bool {
+    let path = url.split('?').next().unwrap_or_default().to_lowercase();
+    url.starts_with("https://") && IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+/// Entry of a JSON export, along with its attachments and tags, which are stored separately
+#[derive(Debug, Clone)]
+pub struct ArchivedHobEntry {
+    pub entry: HobEntry,
+    pub attachments: Vec<String>,
+    pub tags: Vec<String>,
+}
 
 #[derive(Debug, Clone)]
 pub enum HobEntry {
@@ -20,22 +79,97 @@ pub enum HobEntry {
         id: u64,
         title: String,
         comment: Option<String>,
+        value_type: ValueType,
         subentries: Vec<OngoingSubentry>,
     },
 }
 
 impl HobEntry {
+    pub fn id(&self) -> u64 {
+        match self {
+            HobEntry::OneOff { id, .. } | HobEntry::Ongoing { id, .. } => *id,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            HobEntry::OneOff { title, .. } | HobEntry::Ongoing { title, .. } => title,
+        }
+    }
+
     pub fn get_bingo_num(&self) -> u8 {
         match self {
             HobEntry::OneOff { bingo, .. } => bingo.get_id(),
-            HobEntry::Ongoing { subentries, .. } => {
-                if let Some(subentry) = subentries.first() {
-                    subentry.bingo.get_id()
-                } else {
-                    0
-                }
+            // subentries are ranked by value, so the most recent one can be anywhere
+            HobEntry::Ongoing { subentries, .. } => subentries
+                .iter()
+                .map(|subentry| subentry.bingo.get_id())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Whether the entry was achieved during a bingo of the kind, by any player for ongoing entries
+    pub fn has_bingo_kind(&self, kind: BingoKind) -> bool {
+        match self {
+            HobEntry::OneOff { bingo, .. } => bingo.kind == kind,
+            HobEntry::Ongoing { subentries, .. } => subentries.iter().any(|s| s.bingo.kind == kind),
+        }
+    }
+
+    /// Case-insensitive substring match against the entry's players
+    pub fn has_player(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        match self {
+            HobEntry::OneOff { players, .. } => players
+                .players
+                .iter()
+                .any(|p| p.to_lowercase().contains(&query)),
+            HobEntry::Ongoing { subentries, .. } => subentries
+                .iter()
+                .any(|s| s.player.to_lowercase().contains(&query)),
+        }
+    }
+
+    /// Screenshots linked in the entry's comment
+    pub fn image_urls(&self) -> Vec<String> {
+        let (HobEntry::OneOff { comment, .. } | HobEntry::Ongoing { comment, .. }) = self;
+
+        comment
+            .iter()
+            .flat_map(|c| c.split_whitespace())
+            .filter(|word| is_image_url(word))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Gallery of the entry's attachments followed by the screenshots linked in its comment, if
+    /// there are any
+    pub fn gallery(&self, attachments: &[String]) -> Option<CreateContainerComponent<'static>> {
+        let urls = self.gallery_urls(attachments);
+        if urls.is_empty() {
+            return None;
+        }
+
+        let items: Vec<_> = urls
+            .into_iter()
+            .map(|url| CreateMediaGalleryItem::new(CreateUnfurledMediaItem::new(url)))
+            .collect();
+        Some(CreateContainerComponent::MediaGallery(
+            CreateMediaGallery::new(items),
+        ))
+    }
+
+    /// Images shown by [`Self::gallery`], without duplicates and within Discord's limit
+    fn gallery_urls(&self, attachments: &[String]) -> Vec<String> {
+        let mut urls = attachments.to_vec();
+        for url in self.image_urls() {
+            if !urls.contains(&url) {
+                urls.push(url);
             }
         }
+        urls.truncate(MAX_GALLERY_ITEMS);
+        urls
     }
 
     pub fn to_section_edit(&self, id_prefix: &str) -> CreateContainerComponent<'static> {
@@ -63,6 +197,7 @@ impl HobEntry {
                 id,
                 title,
                 comment: _,
+                value_type: _,
                 subentries,
             } => {
                 let edit_button = CreateSectionAccessory::Button(
@@ -70,7 +205,10 @@ impl HobEntry {
                         .emoji('📝')
                         .style(ButtonStyle::Primary),
                 );
-                let description = match subentries.first() {
+                let description = match subentries
+                    .iter()
+                    .max_by_key(|subentry| subentry.bingo.get_id())
+                {
                     Some(subentry) => Cow::Owned(format!(
                         "`{}` and {} others\nmost recently during {}",
                         subentry.player,
@@ -145,6 +283,8 @@ pub struct OngoingSubentry {
     pub entry_id: u64,
     pub player: String,
     pub value: String,
+    /// `value` normalized according to the entry's value type, `None` for text values
+    pub numeric_value: Option<f64>,
     pub bingo: Bingo,
 }
 
@@ -207,3 +347,74 @@ impl OneOffPlayers {
         self.format_list(str::to_string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tag_input() {
+        assert_eq!(
+            parse_tags("Speedrun, community ,,Team Effort, speedrun").unwrap(),
+            ["community", "speedrun", "team-effort"]
+        );
+        assert!(parse_tags("").unwrap().is_empty());
+        assert!(parse_tags("no_underscores").is_err());
+        // duplicates don't count towards the limit
+        assert_eq!(parse_tags(&"a,".repeat(MAX_TAGS + 1)).unwrap(), ["a"]);
+        let too_many: Vec<_> = (0..=MAX_TAGS).map(|i| i.to_string()).collect();
+        assert!(parse_tags(&too_many.join(",")).is_err());
+    }
+
+    #[test]
+    fn image_urls() {
+        assert!(is_image_url("https://example.com/screenshot.png"));
+        assert!(is_image_url("https://example.com/SCREENSHOT.JPEG"));
+        assert!(is_image_url(
+            "https://cdn.discordapp.com/attachments/1/2/image.webp?ex=abc&is=def"
+        ));
+        assert!(!is_image_url("http://example.com/screenshot.png"));
+        assert!(!is_image_url("https://example.com/screenshot.png.html"));
+        assert!(!is_image_url(
+            "https://example.com/page?file=screenshot.png"
+        ));
+        assert!(!is_image_url("screenshot.png"));
+    }
+
+    fn entry_with_comment(comment: &str) -> HobEntry {
+        HobEntry::OneOff {
+            id: 1,
+            title: "Title".to_string(),
+            comment: Some(comment.to_string()),
+            bingo: Bingo::default(),
+            players: OneOffPlayers {
+                players: vec!["Player".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    fn gallery_skips_duplicates() {
+        let entry = entry_with_comment(
+            "see https://example.com/b.png and https://example.com/a.png https://example.com/b.png",
+        );
+        let attachments = ["https://example.com/a.png".to_string()];
+
+        assert_eq!(
+            entry.gallery_urls(&attachments),
+            ["https://example.com/a.png", "https://example.com/b.png"]
+        );
+        assert!(entry_with_comment("no images").gallery(&[]).is_none());
+    }
+
+    #[test]
+    fn gallery_keeps_attachments_within_limit() {
+        let entry = entry_with_comment("https://example.com/comment.png");
+        let attachments: Vec<_> = (0..MAX_GALLERY_ITEMS)
+            .map(|i| format!("https://example.com/{i}.png"))
+            .collect();
+
+        // attachments come first, so the linked screenshot is the one left out
+        assert_eq!(entry.gallery_urls(&attachments), attachments);
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Result, anyhow, bail};
+
+use crate::error::UserError;
+
+/// How the subentry values of an ongoing entry are interpreted. Values are always displayed as
+/// entered, typed values are additionally normalized to a number for sorting and comparisons.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueType {
+    #[default]
+    Text = 0,
+    Number = 1,
+    /// normalized to seconds
+    Duration = 2,
+    Percentage = 3,
+}
+
+impl ValueType {
+    pub const ALL: &[ValueType; 4] = &[
+        ValueType::Text,
+        ValueType::Number,
+        ValueType::Duration,
+        ValueType::Percentage,
+    ];
+
+    pub fn from_u8(int: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| *kind as u8 == int)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::Text => "Text",
+            ValueType::Number => "Number",
+            ValueType::Duration => "Duration",
+            ValueType::Percentage => "Percentage",
+        }
+    }
+
+    /// Identifier used in JSON exports
+    pub fn key(self) -> &'static str {
+        match self {
+            ValueType::Text => "text",
+            ValueType::Number => "number",
+            ValueType::Duration => "duration",
+            ValueType::Percentage => "percentage",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.key() == key)
+    }
+
+    pub fn example(self) -> &'static str {
+        match self {
+            ValueType::Text => "any text",
+            ValueType::Number => "`1,250` or `2.5M`",
+            ValueType::Duration => "`1h 23m 45s` or `1:23:45`",
+            ValueType::Percentage => "`45%` or `99.5 %`",
+        }
+    }
+
+    /// Validates a subentry value, returning its normalized number (`None` for text values)
+    pub fn parse(self, value: &str) -> Result<Option<f64>> {
+        let parsed = match self {
+            ValueType::Text => return Ok(None),
+            ValueType::Number => parse_number(value),
+            ValueType::Duration => parse_duration(value),
+            ValueType::Percentage => parse_percentage(value),
+        };
+
+        match parsed {
+            Some(number) => Ok(Some(number)),
+            None => bail!(UserError(anyhow!(
+                "`{value}` isn't a valid {}, expected e.g. {}",
+                self.name().to_lowercase(),
+                self.example()
+            ))),
+        }
+    }
+}
+
+/// Parses the leading number of a value, e.g. `1,250 points` or `2.5M coins`. Units are stripped,
+/// apart from `k`/`m`/`b` directly following the number, which scale it.
+pub fn parse_number(value: &str) -> Option<f64> {
+    let value = value.replace([',', '_'], "");
+    let start = value.find(|c: char| c.is_ascii_digit())?;
+    let rest = &value[start..];
+
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let number: f64 = rest[..end].trim_end_matches('.').parse().ok()?;
+
+    let multiplier = match rest[end..].chars().next().map(|c| c.to_ascii_lowercase()) {
+        Some('k') => 1e3,
+        Some('m') => 1e6,
+        Some('b') => 1e9,
+        _ => 1.0,
+    };
+    Some(number * multiplier)
+}
+
+/// Parses durations like `1h 23m 45s`, `2 days 3 hours` or `1:23:45` into seconds
+pub fn parse_duration(value: &str) -> Option<f64> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+
+    if value.contains(':') {
+        // `h:m:s` or `m:s`, the last part may have fractional seconds
+        let parts = value
+            .split(':')
+            .map(|part| part.trim().parse::<f64>().ok().filter(|n| *n >= 0.0))
+            .collect::<Option<Vec<_>>>()?;
+        if !(2..=3).contains(&parts.len()) {
+            return None;
+        }
+        return Some(parts.iter().fold(0.0, |total, part| total * 60.0 + part));
+    }
+
+    let mut total = 0.0;
+    let mut rest = value.as_str();
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+
+        let unit_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_end] {
+            "ms" => 0.001,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            // a bare number is ambiguous
+            _ => return None,
+        };
+        total += number * seconds;
+        rest = rest[unit_end..].trim_start_matches([' ', ',']);
+    }
+    Some(total)
+}
+
+/// Parses percentages like `45%` or `99.5 %`, the percent sign is optional
+pub fn parse_percentage(value: &str) -> Option<f64> {
+    let number: f64 = value.trim().trim_end_matches('%').trim_end().parse().ok()?;
+    (0.0..=100.0).contains(&number).then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_number("1,250 points"), Some(1250.0));
+        assert_eq!(parse_number("2.5M coins"), Some(2_500_000.0));
+        assert_eq!(parse_number("~40k"), Some(40_000.0));
+        assert_eq!(parse_number("12. place"), Some(12.0));
+        assert_eq!(parse_number("none"), None);
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("1h 23m 45s"), Some(5025.0));
+        assert_eq!(parse_duration("1:23:45"), Some(5025.0));
+        assert_eq!(parse_duration("12:30.5"), Some(750.5));
+        assert_eq!(parse_duration("2 days, 3 hours"), Some(183_600.0));
+        assert_eq!(parse_duration("1.5h"), Some(5400.0));
+        assert_eq!(parse_duration("90"), None);
+        assert_eq!(parse_duration("1:2:3:4"), None);
+    }
+
+    #[test]
+    fn parse_percentages() {
+        assert_eq!(parse_percentage("45%"), Some(45.0));
+        assert_eq!(parse_percentage("99.5 %"), Some(99.5));
+        assert_eq!(parse_percentage("120%"), None);
+        assert_eq!(parse_percentage("most"), None);
+    }
+
+    #[test]
+    fn text_is_never_normalized() {
+        assert_eq!(ValueType::Text.parse("anything").unwrap(), None);
+        assert!(ValueType::Duration.parse("soon").is_err());
+    }
+}
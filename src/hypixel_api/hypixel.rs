@@ -1,23 +1,39 @@
-use std::str::FromStr as _;
+use std::{
+    collections::HashSet,
+    str::FromStr as _,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use anyhow::{Context as _, Result, bail};
-use chrono::{DateTime, Datelike as _};
 use serde_json::Value;
-use tracing::warn;
+use tracing::{error, warn};
 
 use crate::db::DbHandle;
-use crate::hypixel_api::ApiHandle;
+use crate::hypixel_api::{
+    ApiHandle,
+    retry::{self, Api},
+};
 use crate::role::{
-    db::cache::{CacheHypixelPlayerEndpoint, CachedHypixelPlayerEndpoint},
+    db::cache::{
+        CacheHypixelGuildMembers, CacheHypixelPlayerEndpoint, CachedHypixelGuildMembers,
+        CachedHypixelPlayerEndpoint,
+    },
     types::NetworkBingo,
 };
 use crate::shared::{
-    db::{AddBingoMapping, SetCurrentBingo},
+    calendar::bingo_id_from_timestamp,
+    db::{AddBingoMapping, RecordBingoPeriod, SetCurrentBingo},
     types::{Bingo, BingoKind},
 };
 
 pub mod network_bingo;
 
+/// Consecutive rejections of the API key after which it is reported as an error, as a single one
+/// may be a hiccup on Hypixel's side
+const AUTH_FAILURE_ALERT_THRESHOLD: u32 = 3;
+
+static AUTH_FAILURES: AtomicU32 = AtomicU32::new(0);
+
 pub async fn query_api(
     handle: &ApiHandle,
     endpoint: &str,
@@ -29,14 +45,34 @@ pub async fn query_api(
         .collect::<Vec<_>>()
         .join("");
 
-    let response = handle
-        .client
-        .get(format!(
-            "https://api.hypixel.net{endpoint}?key={}{}",
-            handle.api_key, params,
-        ))
-        .send()
-        .await?;
+    let url = format!(
+        "https://api.hypixel.net{endpoint}?key={}{}",
+        handle.api_key, params,
+    );
+    let url = url.as_str();
+    // rate limits are hit anyway if other applications share the key, in which case the next
+    // `acquire` waits until the quota resets
+    let response = retry::send(Api::Hypixel, Some(&handle.status), || async move {
+        handle.rate_limit.acquire().await;
+        let response = handle.client.get(url).send().await?;
+        handle.rate_limit.update(&response).await;
+        Ok(response)
+    })
+    .await?;
+
+    if response.status().as_u16() == 403 {
+        let failures = AUTH_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures == AUTH_FAILURE_ALERT_THRESHOLD {
+            error!(
+                "Hypixel's API rejected the API key {failures} times in a row, it may be invalid"
+            );
+        } else {
+            warn!("Hypixel's API rejected the API key on '{endpoint}'");
+        }
+    } else {
+        AUTH_FAILURES.store(0, Ordering::Relaxed);
+    }
+
     let text = response.text().await?;
     let json: Value = serde_json::from_str(&text)?;
 
@@ -94,6 +130,8 @@ pub async fn get_current_bingo_data(
             bingo_kind,
         })
         .await??;
+    db.request(RecordBingoPeriod { bingo, start, end })
+        .await??;
 
     Ok((bingo, start, end))
 }
@@ -110,6 +148,7 @@ pub async fn linked_discord(
     let json = match db
         .request(CachedHypixelPlayerEndpoint {
             uuid: uuid.to_string(),
+            allow_stale: handle.status.is_degraded(),
         })
         .await??
     {
@@ -133,6 +172,56 @@ pub async fn linked_discord(
     Ok(discord)
 }
 
+/// Looks up a guild by its name, returning its ID and correctly capitalized name
+pub async fn guild_by_name(handle: &ApiHandle, name: &str) -> Result<Option<(String, String)>> {
+    let (json, _) = query_api(handle, "/v2/guild", &[("name", name)]).await?;
+
+    // `guild` is null if no guild with that name exists
+    if json["guild"].is_null() {
+        return Ok(None);
+    }
+
+    let id = json["guild"]["_id"].as_str().context("Guild has no ID")?;
+    let name = json["guild"]["name"].as_str().unwrap_or(name);
+
+    Ok(Some((id.to_string(), name.to_string())))
+}
+
+pub async fn guild_members(
+    handle: &ApiHandle,
+    db: &DbHandle,
+    guild_id: &str,
+) -> Result<Vec<String>> {
+    // guild member lists are shared by every role request, so they are cached for a while
+    if let Some(members) = db
+        .request(CachedHypixelGuildMembers {
+            guild_id: guild_id.to_string(),
+            allow_stale: handle.status.is_degraded(),
+        })
+        .await??
+    {
+        return Ok(members);
+    }
+
+    let (json, _) = query_api(handle, "/v2/guild", &[("id", guild_id)]).await?;
+
+    let members: Vec<String> = json["guild"]["members"]
+        .as_array()
+        .context("Guild has no member list")?
+        .iter()
+        .filter_map(|member| member["uuid"].as_str().map(String::from))
+        .collect();
+
+    db.request(CacheHypixelGuildMembers {
+        guild_id: guild_id.to_string(),
+        timestamp: chrono::Utc::now().timestamp(),
+        members: members.clone(),
+    })
+    .await??;
+
+    Ok(members)
+}
+
 pub async fn bingo_completions(handle: &ApiHandle, uuid: &str) -> Result<Vec<u8>> {
     let params = [("uuid", uuid)];
     // NOTE: This errors if the user has never touched bingo
@@ -161,6 +250,82 @@ pub async fn bingo_completions(handle: &ApiHandle, uuid: &str) -> Result<Vec<u8>
     Ok(completed_goals)
 }
 
+/// Goals of the current bingo card, in card order, along with whether the player completed them
+#[derive(Debug)]
+pub struct BingoGoalProgress {
+    pub goals: Vec<(String, bool)>,
+}
+
+impl BingoGoalProgress {
+    pub fn completed(&self) -> usize {
+        self.goals
+            .iter()
+            .filter(|(_, completed)| *completed)
+            .count()
+    }
+}
+
+pub async fn bingo_goal_progress(handle: &ApiHandle, uuid: &str) -> Result<BingoGoalProgress> {
+    let (card_json, _) = query_api(handle, "/v2/resources/skyblock/bingo", &[]).await?;
+    let bingo_key = card_json["id"]
+        .as_u64()
+        .context("No bingo ID found for current bingo")?;
+    let bingo_key = u8::try_from(bingo_key).context("Current bingo ID is out of range")?;
+    let card = card_json["goals"]
+        .as_array()
+        .context("No goals found for current bingo")?;
+
+    let params = [("uuid", uuid)];
+    // NOTE: This errors if the user has never touched bingo
+    let completed_goals: HashSet<String> =
+        match query_api(handle, "/v2/skyblock/bingo", &params).await {
+            Ok((json, _)) => json["events"]
+                .as_array()
+                .and_then(|events| {
+                    events
+                        .iter()
+                        .find(|event| event["key"].as_u64() == Some(bingo_key.into()))
+                })
+                .and_then(|event| event["completed_goals"].as_array())
+                .map(|goals| {
+                    goals
+                        .iter()
+                        .filter_map(|goal| goal.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Err(err) => {
+                warn!("No Bingo data for '{uuid}': {err}");
+                HashSet::new()
+            }
+        };
+
+    let goals = card
+        .iter()
+        .filter_map(|goal| {
+            let id = goal["id"].as_str()?;
+            let name = strip_formatting(goal["name"].as_str().unwrap_or(id));
+            Some((name, completed_goals.contains(id)))
+        })
+        .collect();
+
+    Ok(BingoGoalProgress { goals })
+}
+
+/// Removes Minecraft's `§` formatting codes
+fn strip_formatting(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char == '§' {
+            chars.next();
+        } else {
+            output.push(char);
+        }
+    }
+    output
+}
+
 #[derive(Debug)]
 pub struct BingoProfileData {
     pub created_during: u8,
@@ -235,25 +400,10 @@ pub async fn bingo_profile_data(
     }))
 }
 
-// NOTE: Calculations are deliberately done in UTC, because this accounts for the weird 1h bingo
-// time shift that Hypixel sometimes has around DST by using the ~4-5h UTC-EST difference as a
-// buffer zone, while keeping timezone calculations simple as a side effect
-pub fn bingo_id_from_timestamp(timestamp: u32) -> Result<u8> {
-    const FIRST_BINGO_INDEX: u32 = 2021 * 12 + 12;
-
-    let time_utc = DateTime::from_timestamp(timestamp.into(), 0).context("Invalid timestamp")?;
-
-    let year = time_utc.year() as u32;
-    let month = time_utc.month();
-
-    let timestamp_index = year * 12 + month;
-
-    Ok(timestamp_index.saturating_sub(FIRST_BINGO_INDEX) as u8)
-}
-
 pub async fn network_bingo_completions(
     handle: &ApiHandle,
     db: &DbHandle,
+    registry_db: &DbHandle,
     uuid: &str,
 ) -> Result<Vec<NetworkBingo>> {
     let params = [("uuid", uuid)];
@@ -277,7 +427,12 @@ pub async fn network_bingo_completions(
         }
     };
 
-    let seasonal_events = network_bingo::network_bingo_completions(&json["player"]["seasonal"]);
+    let seasonal = &json["player"]["seasonal"];
+    for (season, year, rule) in network_bingo::unknown_events(seasonal) {
+        crate::role::network_bingo::register(registry_db, &season, &year, None, rule).await?;
+    }
+
+    let seasonal_events = network_bingo::network_bingo_completions(seasonal);
 
     Ok(seasonal_events)
 }
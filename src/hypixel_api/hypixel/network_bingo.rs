@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
+use chrono::{Datelike as _, Utc};
 use serde_json::Value;
 
-use crate::role::types::NetworkBingo;
+use crate::role::{
+    network_bingo::{self, DetectionRule},
+    types::NetworkBingo,
+};
 
 pub fn network_bingo_completions(seasonal: &Value) -> Vec<NetworkBingo> {
     let mut bingos = Vec::new();
@@ -11,35 +15,35 @@ pub fn network_bingo_completions(seasonal: &Value) -> Vec<NetworkBingo> {
         (
             ExpectedGoals::new(ANNIVERSARY_2023)
                 .is_fully_completed(&seasonal["anniversary"][" 2023"]["bingo"]), // HYPIXEL???
-            NetworkBingo::Anniversary2023,
+            NetworkBingo::ANNIVERSARY_2023,
         ),
         (
             has_all_difficulties(&seasonal["halloween"]["2023"]["bingo"]),
-            NetworkBingo::Halloween2023,
+            NetworkBingo::HALLOWEEN_2023,
         ),
         (
             has_all_difficulties(&seasonal["christmas"]["2023"]["bingo"]),
-            NetworkBingo::Christmas2023,
+            NetworkBingo::CHRISTMAS_2023,
         ),
         (
             has_all_difficulties(&seasonal["easter"]["2024"]["bingo"]),
-            NetworkBingo::Easter2024,
+            NetworkBingo::EASTER_2024,
         ),
         (
             has_all_difficulties(&seasonal["summer"]["2024"]["bingo"]),
-            NetworkBingo::Summer2024,
+            NetworkBingo::SUMMER_2024,
         ),
         (
             has_all_difficulties(&seasonal["halloween"]["2024"]["bingo"]),
-            NetworkBingo::Halloween2024,
+            NetworkBingo::HALLOWEEN_2024,
         ),
         (
             has_any_difficulty_pair(&seasonal["easter"]["2025"]["bingo"]),
-            NetworkBingo::Anniversary2025,
+            NetworkBingo::ANNIVERSARY_2025,
         ),
         (
             has_any_difficulty_pair(&seasonal["easter"]["2026"]["bingo"]),
-            NetworkBingo::Anniversary2026,
+            NetworkBingo::ANNIVERSARY_2026,
         ),
     ];
 
@@ -49,9 +53,74 @@ pub fn network_bingo_completions(seasonal: &Value) -> Vec<NetworkBingo> {
         }
     });
 
+    for event in network_bingo::registered() {
+        let bingo_json = &event_json(seasonal, &event.season, &event.year)["bingo"];
+        let completed = match event.rule {
+            DetectionRule::AllDifficulties => has_all_difficulties(bingo_json),
+            DetectionRule::DifficultyPair => has_any_difficulty_pair(bingo_json),
+        };
+        if completed {
+            bingos.push(event.bingo);
+        }
+    }
+
     bingos
 }
 
+/// The event stored under the raw key of the trimmed `year`, as the API pads some years with spaces
+fn event_json<'a>(seasonal: &'a Value, season: &str, year: &str) -> &'a Value {
+    seasonal[season]
+        .as_object()
+        .and_then(|years| {
+            years
+                .iter()
+                .find(|(key, _)| key.trim() == year)
+                .map(|(_, event)| event)
+        })
+        .unwrap_or(&Value::Null)
+}
+
+/// Seasonal events of this year or later with a bingo section that aren't known yet, along with a
+/// detection rule guessed from the layout of their cards. Older events are left to staff, as a
+/// newly registered event becomes [`NetworkBingo::latest`].
+pub fn unknown_events(seasonal: &Value) -> Vec<(String, String, DetectionRule)> {
+    let Some(seasons) = seasonal.as_object() else {
+        return Vec::new();
+    };
+    let current_year = Utc::now().year();
+
+    let mut events = Vec::new();
+    for (season, years) in seasons {
+        let Some(years) = years.as_object() else {
+            continue;
+        };
+        for (year, event) in years {
+            let Some(bingo_json) = event["bingo"].as_object() else {
+                continue;
+            };
+            if network_bingo::is_known(season, year) {
+                continue;
+            }
+            if !year
+                .trim()
+                .parse::<i32>()
+                .is_ok_and(|year| year >= current_year)
+            {
+                continue;
+            }
+
+            // cards of newer events are split by type, e.g. `casual_easy`
+            let rule = if bingo_json.keys().any(|key| key.contains('_')) {
+                DetectionRule::DifficultyPair
+            } else {
+                DetectionRule::AllDifficulties
+            };
+            events.push((season.clone(), year.clone(), rule));
+        }
+    }
+    events
+}
+
 fn has_all_difficulties(bingo_json: &Value) -> bool {
     has_blackouts_for(
         bingo_json,
@@ -262,3 +331,49 @@ const ANNIVERSARY_2023_HARD: &[(&str, i64)] = &[
     ("Megawallsfinal", 1),
     ("Pbnuke", 1),
 ];
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn unknown_events_skips_known_and_past_events() {
+        let year = Utc::now().year();
+        let seasonal = json!({
+            // built in
+            "easter": { "2025": { "bingo": { "easy": {} } } },
+            "halloween": {
+                // before this year
+                (year - 1).to_string(): { "bingo": { "easy": {} } },
+                // padded like some keys on the API
+                format!(" {year}"): { "bingo": { "easy": {}, "medium": {}, "hard": {} } },
+            },
+            // without a bingo section
+            "summer": { year.to_string(): { "other": {} } },
+        });
+
+        assert_eq!(
+            unknown_events(&seasonal),
+            vec![(
+                "halloween".to_string(),
+                format!(" {year}"),
+                DetectionRule::AllDifficulties
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_events_guesses_rule_from_card_layout() {
+        let year = (Utc::now().year() + 1).to_string();
+        let seasonal = json!({
+            "christmas": { year.clone(): { "bingo": { "casual_easy": {}, "pvp_hard": {} } } },
+        });
+
+        assert_eq!(
+            unknown_events(&seasonal),
+            vec![("christmas".to_string(), year, DetectionRule::DifficultyPair)]
+        );
+    }
+}
@@ -1,35 +1,100 @@
+use std::time::Duration;
+
 use anyhow::{Context as _, Result};
 use reqwest::Client;
+use tracing::warn;
 
 use crate::db::DbHandle;
-use crate::hypixel_api::hypixel::BingoProfileData;
-use crate::role::types::NetworkBingo;
-use crate::shared::types::Bingo;
+use crate::hypixel_api::{hypixel::BingoProfileData, rate_limit::RateLimiter, status::ApiStatus};
+use crate::role::{
+    db::cache::{CacheMojangProfile, CachedMojangUsername, CachedMojangUuid},
+    types::NetworkBingo,
+};
+use crate::shared::{
+    db::GetCurrentBingo,
+    types::{Bingo, MinecraftIdent},
+};
+pub use hypixel::BingoGoalProgress;
 
 mod hypixel;
 mod mojang;
+mod rate_limit;
+mod retry;
+mod status;
+
+/// Requests exceeding this are retried, see [`retry::send`]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct ApiHandle {
     client: Client,
     api_key: String,
+    /// only applies to Hypixel's API, Mojang's limits are per IP and rarely reached
+    rate_limit: RateLimiter,
+    /// only tracks Hypixel's API, see [`Self::is_degraded`]
+    status: ApiStatus,
 }
 
 impl ApiHandle {
     pub fn new(key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("HTTP client should build with a timeout"),
             api_key: key,
+            rate_limit: RateLimiter::new(),
+            status: ApiStatus::new(),
         }
     }
 
     const INVALID_RESPONSE: &str = "Invalid response from Hypixel's API";
 
-    pub async fn uuid(&self, username: &str) -> Result<String> {
-        mojang::uuid(&self.client, username).await
+    /// UUIDs are returned as is, without a lookup. Usernames are looked up in the cache first, as
+    /// Mojang's rate limits are strict.
+    pub async fn uuid(&self, db: &DbHandle, ident: &MinecraftIdent) -> Result<String> {
+        match ident {
+            MinecraftIdent::Username(username) => {
+                if let Some(uuid) = db
+                    .request(CachedMojangUuid {
+                        username: username.trim().to_string(),
+                    })
+                    .await??
+                {
+                    return Ok(uuid);
+                }
+
+                let (uuid, username) = mojang::uuid(&self.client, username).await?;
+                cache_mojang_profile(db, &uuid, username).await;
+                Ok(uuid)
+            }
+            MinecraftIdent::Uuid(uuid) => Ok(uuid.clone()),
+        }
+    }
+
+    /// Remaining Hypixel API requests until the quota resets, if known
+    pub async fn quota_remaining(&self) -> Option<u32> {
+        self.rate_limit.remaining().await
+    }
+
+    /// Whether Hypixel's API is currently failing most requests. Cached data is served regardless of
+    /// its age while degraded, which role requests should point out.
+    pub fn is_degraded(&self) -> bool {
+        self.status.is_degraded()
     }
 
-    pub async fn username(&self, uuid: &str) -> Result<String> {
-        mojang::username(&self.client, uuid).await
+    pub async fn username(&self, db: &DbHandle, uuid: &str) -> Result<String> {
+        if let Some(username) = db
+            .request(CachedMojangUsername {
+                uuid: uuid.to_string(),
+            })
+            .await??
+        {
+            return Ok(username);
+        }
+
+        let username = mojang::username(&self.client, uuid).await?;
+        cache_mojang_profile(db, uuid, username.clone()).await;
+        Ok(username)
     }
 
     pub async fn linked_discord(&self, db: &DbHandle, uuid: &str) -> Result<Option<String>> {
@@ -39,30 +104,75 @@ impl ApiHandle {
     }
 
     pub async fn update_current_bingo(&self, db: &DbHandle) -> Result<(Bingo, i64, i64)> {
+        // the stored bingo stays valid until it ends, with an occasional probe to notice recovery
+        if self.status.is_degraded()
+            && !self.status.take_probe()
+            && let Some(current) = db.request(GetCurrentBingo).await??
+            && chrono::Utc::now().timestamp() < current.2
+        {
+            return Ok(current);
+        }
+
         hypixel::get_current_bingo_data(self, db)
             .await
             .context(Self::INVALID_RESPONSE)
     }
 
+    pub async fn guild_by_name(&self, name: &str) -> Result<Option<(String, String)>> {
+        hypixel::guild_by_name(self, name)
+            .await
+            .context(Self::INVALID_RESPONSE)
+    }
+
+    pub async fn guild_members(&self, db: &DbHandle, guild_id: &str) -> Result<Vec<String>> {
+        hypixel::guild_members(self, db, guild_id)
+            .await
+            .context(Self::INVALID_RESPONSE)
+    }
+
     pub async fn bingo_completions(&self, uuid: &str) -> Result<Vec<u8>> {
         hypixel::bingo_completions(self, uuid)
             .await
             .context(Self::INVALID_RESPONSE)
     }
 
+    pub async fn bingo_goal_progress(&self, uuid: &str) -> Result<BingoGoalProgress> {
+        hypixel::bingo_goal_progress(self, uuid)
+            .await
+            .context(Self::INVALID_RESPONSE)
+    }
+
     pub async fn bingo_profile_data(&self, uuid: &str) -> Result<Option<BingoProfileData>> {
         hypixel::bingo_profile_data(self, uuid)
             .await
             .context(Self::INVALID_RESPONSE)
     }
 
+    /// Newly discovered events are always registered in `registry_db`, as the registry is shared by
+    /// all guilds
     pub async fn network_bingo_completions(
         &self,
         db: &DbHandle,
+        registry_db: &DbHandle,
         uuid: &str,
     ) -> Result<Vec<NetworkBingo>> {
-        hypixel::network_bingo_completions(self, db, uuid)
+        hypixel::network_bingo_completions(self, db, registry_db, uuid)
             .await
             .context(Self::INVALID_RESPONSE)
     }
 }
+
+/// Failing to cache the profile only costs another lookup later, so the fetched value is still used
+async fn cache_mojang_profile(db: &DbHandle, uuid: &str, username: String) {
+    let result = db
+        .request(CacheMojangProfile {
+            uuid: uuid.to_string(),
+            username,
+            timestamp: chrono::Utc::now().timestamp(),
+        })
+        .await
+        .and_then(|result| Ok(result?));
+    if let Err(err) = result {
+        warn!("Failed to cache Mojang profile of {uuid}: {err:#}");
+    }
+}
@@ -1,21 +1,19 @@
-use anyhow::{anyhow, bail, Context as _, Result};
+use anyhow::{Context as _, Result, anyhow, bail};
 use reqwest::Client;
 use serde_json::Value;
 
 use crate::error::UserError;
+use crate::hypixel_api::retry::{self, Api};
 
-pub async fn uuid(client: &Client, username: &str) -> Result<String> {
+/// Returns the UUID along with the correctly capitalized username
+pub async fn uuid(client: &Client, username: &str) -> Result<(String, String)> {
     let username = username.trim();
     if !validate_mc_username(username) {
         bail!(UserError(anyhow!("Invalid Minecraft username: {username}")));
     }
 
-    let response = client
-        .get(format!(
-            "https://api.minecraftservices.com/minecraft/profile/lookup/name/{username}"
-        ))
-        .send()
-        .await?;
+    let url = format!("https://api.minecraftservices.com/minecraft/profile/lookup/name/{username}");
+    let response = retry::send(Api::Mojang, None, || client.get(&url).send()).await?;
 
     let status = response.status();
     let text = response.text().await?;
@@ -32,17 +30,14 @@ pub async fn uuid(client: &Client, username: &str) -> Result<String> {
         .as_str()
         .context("No UUID in response")?
         .replace("-", "");
+    let username = json["name"].as_str().unwrap_or(username).to_string();
 
-    Ok(uuid)
+    Ok((uuid, username))
 }
 
 pub async fn username(client: &Client, uuid: &str) -> Result<String> {
-    let response = client
-        .get(format!(
-            "https://api.minecraftservices.com/minecraft/profile/lookup/{uuid}"
-        ))
-        .send()
-        .await?;
+    let url = format!("https://api.minecraftservices.com/minecraft/profile/lookup/{uuid}");
+    let response = retry::send(Api::Mojang, None, || client.get(&url).send()).await?;
 
     let status = response.status();
     let text = response.text().await?;
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use reqwest::{Response, header::HeaderMap};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::warn;
+
+/// Requests kept in reserve, so that bursts from concurrent callers don't exceed the limit
+const QUOTA_RESERVE: u32 = 5;
+/// Used if Hypixel doesn't specify when the quota resets
+const DEFAULT_RESET: Duration = Duration::from_secs(60);
+
+/// Tracks the API key's remaining quota from Hypixel's `RateLimit-*` response headers. Callers wait
+/// for the quota to reset instead of running into errors mid-way through bulk operations.
+pub struct RateLimiter {
+    state: Mutex<QuotaState>,
+}
+
+#[derive(Debug, Default)]
+struct QuotaState {
+    /// unknown until the first response
+    remaining: Option<u32>,
+    reset_at: Option<Instant>,
+    /// whether queueing until the reset was already logged
+    queue_logged: bool,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(QuotaState::default()),
+        }
+    }
+
+    /// Waits until a request may be sent without exceeding the quota, and reserves it
+    pub async fn acquire(&self) {
+        loop {
+            let mut state = self.state.lock().await;
+
+            if let (Some(remaining), Some(reset_at)) = (state.remaining, state.reset_at)
+                && remaining <= QUOTA_RESERVE
+            {
+                if reset_at > Instant::now() {
+                    if !state.queue_logged {
+                        state.queue_logged = true;
+                        warn!(
+                            "Hypixel API quota nearly exhausted, queueing requests for {:?}",
+                            reset_at - Instant::now()
+                        );
+                    }
+                    // the lock isn't held while sleeping, so that responses can still update the
+                    // quota, e.g. with a later reset after running into the limit
+                    drop(state);
+                    tokio::time::sleep_until(reset_at).await;
+                    continue;
+                }
+                *state = QuotaState::default();
+            }
+
+            if let Some(remaining) = &mut state.remaining {
+                *remaining = remaining.saturating_sub(1);
+            }
+            return;
+        }
+    }
+
+    /// Remaining requests of the current quota, unknown until the first response
+    pub async fn remaining(&self) -> Option<u32> {
+        self.state.lock().await.remaining
+    }
+
+    /// Updates the known quota from a response's headers
+    pub async fn update(&self, response: &Response) {
+        let headers = response.headers();
+        let mut state = self.state.lock().await;
+
+        if response.status().as_u16() == 429 {
+            state.remaining = Some(0);
+            state.reset_at = Some(
+                Instant::now()
+                    + header_secs(headers, "retry-after")
+                        .or_else(|| header_secs(headers, "ratelimit-reset"))
+                        .unwrap_or(DEFAULT_RESET),
+            );
+            return;
+        }
+
+        if let Some(remaining) = header_u64(headers, "ratelimit-remaining") {
+            state.remaining = Some(remaining as u32);
+        }
+        if let Some(reset) = header_secs(headers, "ratelimit-reset") {
+            state.reset_at = Some(Instant::now() + reset);
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    header_u64(headers, name).map(Duration::from_secs)
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher as _, Hasher as _},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use reqwest::{Response, StatusCode};
+use tracing::warn;
+
+use crate::error::UserError;
+use crate::hypixel_api::status::ApiStatus;
+use crate::metrics;
+
+/// Attempts per request, including the first one
+const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Api {
+    Hypixel,
+    Mojang,
+}
+
+impl Api {
+    fn name(self) -> &'static str {
+        match self {
+            Api::Hypixel => "Hypixel's API",
+            Api::Mojang => "Mojang's API",
+        }
+    }
+
+    /// Label in metrics
+    fn label(self) -> &'static str {
+        match self {
+            Api::Hypixel => "hypixel",
+            Api::Mojang => "mojang",
+        }
+    }
+
+    fn unavailable(self) -> UserError {
+        UserError(anyhow!(
+            "{} is currently unavailable, please try again in a few minutes",
+            self.name()
+        ))
+    }
+}
+
+/// Sends a request until it succeeds or fails permanently, retrying server errors, timeouts and
+/// rate limits with jittered exponential backoff. `send` is called once per attempt, so that it can
+/// build a fresh request. The outcome of every attempt is recorded in `status`, if given.
+///
+/// Responses with other error statuses (e.g. unknown players) are returned as is, while running out
+/// of attempts surfaces as a [`UserError`], as there is nothing the bot can do about an outage.
+pub async fn send<F, Fut>(api: Api, status: Option<&ApiStatus>, mut send: F) -> Result<Response>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = reqwest::Result<Response>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let last_attempt = attempt >= MAX_ATTEMPTS;
+
+        // the URL includes the API key, which mustn't end up in logs or error messages
+        let result = send().await.map_err(reqwest::Error::without_url);
+        let outcome = match &result {
+            Ok(response) if is_transient_status(response.status()) => "unavailable",
+            Ok(response) if response.status().is_client_error() => "client_error",
+            Ok(_) => "success",
+            Err(_) => "network_error",
+        };
+        metrics::record_api_request(api.label(), outcome);
+        if let Some(status) = status {
+            match &result {
+                Ok(response) => status.record(is_transient_status(response.status())),
+                Err(err) if is_transient_error(err) => status.record(true),
+                // e.g. invalid URLs, which say nothing about the API's health
+                Err(_) => {}
+            }
+        }
+
+        match result {
+            Ok(response) if is_transient_status(response.status()) => {
+                if last_attempt {
+                    warn!(
+                        "{} responded with {} after {attempt} attempts, giving up",
+                        api.name(),
+                        response.status()
+                    );
+                    return Err(api.unavailable().into());
+                }
+                warn!(
+                    "{} responded with {}, retrying (attempt {attempt})",
+                    api.name(),
+                    response.status()
+                );
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if is_transient_error(&err) => {
+                if last_attempt {
+                    return Err(err).context(api.unavailable());
+                }
+                warn!(
+                    "Request to {} failed, retrying (attempt {attempt}): {err:#}",
+                    api.name()
+                );
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        tokio::time::sleep(backoff(attempt, random_fraction())).await;
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request()
+}
+
+/// Delay before the next attempt, growing exponentially up to [`MAX_DELAY`]. Half of it is
+/// randomised by `jitter` (in `0.0..1.0`), so that concurrent callers don't retry in lockstep.
+fn backoff(attempt: u32, jitter: f64) -> Duration {
+    let delay = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_DELAY);
+    delay / 2 + (delay / 2).mul_f64(jitter)
+}
+
+/// Good enough randomness for jitter, without pulling in a dependency
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        assert_eq!(backoff(1, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff(2, 1.0), Duration::from_secs(1));
+        assert_eq!(backoff(3, 0.0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff(30, 1.0), MAX_DELAY);
+        assert_eq!(backoff(30, 0.0), MAX_DELAY / 2);
+    }
+
+    #[test]
+    fn random_fraction_is_in_range() {
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&random_fraction()));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+/// Recent attempts the failure rate is computed over
+const WINDOW: usize = 20;
+/// Attempts required before switching to degraded mode, so that a single failure after startup
+/// doesn't trigger it
+const MIN_SAMPLES: usize = 5;
+/// Failure rate of the window at which the API is considered unstable
+const DEGRADE_THRESHOLD: f64 = 0.5;
+/// Failure rate of the window below which the API is considered healthy again. Lower than
+/// [`DEGRADE_THRESHOLD`], so that the mode doesn't flap at the boundary.
+const RECOVER_THRESHOLD: f64 = 0.1;
+/// Minimum time between requests which are sent while degraded only to check for recovery
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tracks the outcome of recent Hypixel API attempts, as recorded by the retry layer. While the
+/// failure rate is high, role requests are served from the cache where possible and background
+/// jobs pause, until enough attempts succeed again.
+pub struct ApiStatus {
+    state: Mutex<StatusState>,
+}
+
+#[derive(Debug, Default)]
+struct StatusState {
+    /// `true` for failed attempts, newest last
+    outcomes: VecDeque<bool>,
+    degraded: bool,
+    last_probe: Option<Instant>,
+}
+
+impl StatusState {
+    fn record(&mut self, failed: bool) {
+        if self.outcomes.len() == WINDOW {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(failed);
+
+        let failures = self.outcomes.iter().filter(|failed| **failed).count();
+        let failure_rate = failures as f64 / self.outcomes.len() as f64;
+
+        if !self.degraded && self.outcomes.len() >= MIN_SAMPLES && failure_rate >= DEGRADE_THRESHOLD
+        {
+            self.degraded = true;
+            warn!(
+                "{failures} of the last {} Hypixel API requests failed, switching to degraded mode",
+                self.outcomes.len()
+            );
+        } else if self.degraded && failure_rate <= RECOVER_THRESHOLD {
+            self.degraded = false;
+            self.last_probe = None;
+            info!("Hypixel's API recovered, leaving degraded mode");
+        }
+    }
+}
+
+impl ApiStatus {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(StatusState::default()),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, StatusState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records a single attempt, where server errors, timeouts and rate limits count as failures
+    pub fn record(&self, failed: bool) {
+        self.state().record(failed);
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.state().degraded
+    }
+
+    /// Whether a request that would otherwise be skipped in degraded mode should be sent anyway,
+    /// so that recovery is noticed even if everything else is served from the cache
+    pub fn take_probe(&self) -> bool {
+        let mut state = self.state();
+        if state
+            .last_probe
+            .is_some_and(|last_probe| last_probe.elapsed() < PROBE_INTERVAL)
+        {
+            return false;
+        }
+        state.last_probe = Some(Instant::now());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_all(state: &mut StatusState, outcomes: &[bool]) {
+        for failed in outcomes {
+            state.record(*failed);
+        }
+    }
+
+    #[test]
+    fn degrades_only_after_enough_samples() {
+        let mut state = StatusState::default();
+        record_all(&mut state, &[true; MIN_SAMPLES - 1]);
+        assert!(!state.degraded);
+        state.record(true);
+        assert!(state.degraded);
+    }
+
+    #[test]
+    fn recovers_once_failures_leave_the_window() {
+        let mut state = StatusState::default();
+        record_all(&mut state, &[false, true, false, true, true, true]);
+        assert!(state.degraded);
+
+        // mostly successful, but still above the recovery threshold
+        record_all(&mut state, &[false; WINDOW - 3]);
+        assert!(state.degraded);
+
+        record_all(&mut state, &[false; 3]);
+        assert!(!state.degraded);
+    }
+}
@@ -1,27 +1,85 @@
+use std::env;
+
+use anyhow::{Result, bail};
 use tracing::Level;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{
-    Layer as _, filter, fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _,
+    Layer, Registry, filter,
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt as _,
+    util::SubscriberInitExt as _,
 };
 
-pub fn init_log() -> WorkerGuard {
+use crate::log::alert::{AlertLayer, AlertWebhook};
+
+pub mod alert;
+pub mod correlation;
+
+/// Output format of stdout and the log files, configured via `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// one JSON object per line, including the correlation ID of the current invocation, for log
+    /// aggregation tools
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Result<Self> {
+        match env::var("LOG_FORMAT").as_deref() {
+            Err(_) | Ok("text") => Ok(LogFormat::Text),
+            Ok("json") => Ok(LogFormat::Json),
+            Ok(other) => bail!("Invalid log format '{other}', expected `text` or `json`"),
+        }
+    }
+}
+
+pub fn init_log(format: LogFormat, alert_webhook: Option<AlertWebhook>) -> WorkerGuard {
     let file_appender = tracing_appender::rolling::daily("logs/", "bot.log");
     let (file_writer, guard) = tracing_appender::non_blocking(file_appender);
 
     let crate_filter = filter::Targets::new().with_targets([("bb_bot", Level::DEBUG)]);
 
-    let stdout_layer = fmt::layer()
-        .with_writer(std::io::stdout)
-        .with_filter(crate_filter.clone());
-    let file_layer = fmt::layer()
-        .with_writer(file_writer)
-        .with_ansi(false)
-        .with_filter(crate_filter);
+    let output_layers = vec![
+        output_layer(format, std::io::stdout, true, crate_filter.clone()),
+        output_layer(format, file_writer, false, crate_filter),
+    ];
+    // user errors are logged as warnings, so errors are critical by convention
+    let alert_layer = alert_webhook.map(|webhook| {
+        AlertLayer::new(webhook)
+            .with_filter(filter::Targets::new().with_targets([("bb_bot", Level::ERROR)]))
+    });
 
     tracing_subscriber::registry()
-        .with(stdout_layer)
-        .with(file_layer)
+        .with(output_layers)
+        .with(alert_layer)
         .init();
 
     guard
 }
+
+fn output_layer<W>(
+    format: LogFormat,
+    writer: W,
+    ansi: bool,
+    filter: filter::Targets,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_filter(filter)
+            .boxed(),
+        // only the innermost span, as invocations aren't nested
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(writer)
+            .with_filter(filter)
+            .boxed(),
+    }
+}
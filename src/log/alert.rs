@@ -0,0 +1,244 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    fmt::{Debug, Write as _},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, bail};
+use reqwest::{Client, header::CONTENT_TYPE};
+use serde_json::json;
+use tokio::{sync::mpsc, time::Instant};
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+    warn,
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+/// Identical alerts are only sent once within this window
+const DEDUP_WINDOW: Duration = Duration::from_secs(15 * 60);
+/// At most `MAX_ALERTS_PER_WINDOW` alerts are sent within `RATE_WINDOW`, further ones are counted
+/// and mentioned in the next alert
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const MAX_ALERTS_PER_WINDOW: usize = 5;
+/// Alerts waiting to be sent, further ones are dropped instead of blocking the logging thread
+const QUEUE_SIZE: usize = 64;
+/// Only the start of the message is used for deduplication, so that long error chains with
+/// varying details still count as the same error
+const DEDUP_KEY_LEN: usize = 120;
+
+/// How the alert is encoded in the webhook's request body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// `{"text": ...}`, understood by Slack and most compatible services
+    Slack,
+    /// `{"content": ...}`, for Discord webhooks
+    Discord,
+    /// the alert as a plain text body, e.g. for ntfy
+    Plain,
+}
+
+/// External webhook receiving critical errors, configured via `ALERT_WEBHOOK_URL` and optionally
+/// `ALERT_WEBHOOK_FORMAT` (`slack`, `discord` or `plain`)
+#[derive(Debug, Clone)]
+pub struct AlertWebhook {
+    url: String,
+    format: WebhookFormat,
+}
+
+impl AlertWebhook {
+    /// `None` if no webhook is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = env::var("ALERT_WEBHOOK_URL") else {
+            return Ok(None);
+        };
+        reqwest::Url::parse(&url).context("Invalid alert webhook URL")?;
+
+        let format = match env::var("ALERT_WEBHOOK_FORMAT").as_deref() {
+            Err(_) | Ok("slack") => WebhookFormat::Slack,
+            Ok("discord") => WebhookFormat::Discord,
+            Ok("plain") => WebhookFormat::Plain,
+            Ok(other) => bail!(
+                "Invalid alert webhook format '{other}', expected `slack`, `discord` or `plain`"
+            ),
+        };
+
+        Ok(Some(Self { url, format }))
+    }
+
+    async fn send(&self, client: &Client, text: &str) -> Result<()> {
+        let request = client.post(&self.url);
+        let request = match self.format {
+            WebhookFormat::Slack => request
+                .header(CONTENT_TYPE, "application/json")
+                .body(json!({ "text": text }).to_string()),
+            WebhookFormat::Discord => request
+                .header(CONTENT_TYPE, "application/json")
+                .body(json!({ "content": text, "allowed_mentions": { "parse": [] } }).to_string()),
+            WebhookFormat::Plain => request
+                .header(CONTENT_TYPE, "text/plain")
+                .body(text.to_string()),
+        };
+
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Alert {
+    target: String,
+    message: String,
+}
+
+impl Alert {
+    /// Digits are masked, so that errors only differing in IDs or durations are deduplicated
+    fn dedup_key(&self) -> String {
+        let message: String = self
+            .message
+            .chars()
+            .take(DEDUP_KEY_LEN)
+            .map(|c| if c.is_ascii_digit() { '#' } else { c })
+            .collect();
+        format!("{}: {message}", self.target)
+    }
+}
+
+/// Forwards error events to the webhook. Sending happens on a background task, so that logging
+/// never waits on the network.
+pub struct AlertLayer {
+    tx: mpsc::Sender<Alert>,
+}
+
+impl AlertLayer {
+    /// Must be called from within the Tokio runtime
+    pub fn new(webhook: AlertWebhook) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(send_alerts(webhook, rx));
+        Self { tx }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AlertLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        // a full queue means alerts are already being throttled
+        let _ = self.tx.try_send(Alert {
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+/// Collects the event's message, followed by any other fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            self.message = format!("{value:?}{fields}");
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
+
+async fn send_alerts(webhook: AlertWebhook, mut rx: mpsc::Receiver<Alert>) {
+    let client = Client::new();
+    let mut throttle = Throttle::default();
+
+    while let Some(alert) = rx.recv().await {
+        let Some(suppressed) = throttle.admit(alert.dedup_key(), Instant::now()) else {
+            continue;
+        };
+
+        let mut text = format!("[bb-bot] Error in {}: {}", alert.target, alert.message);
+        if suppressed > 0 {
+            let _ = write!(
+                text,
+                "\n({suppressed} duplicate or rate-limited alert(s) were suppressed since the last one)"
+            );
+        }
+
+        // logged as a warning, so that failing webhooks don't trigger further alerts
+        if let Err(err) = webhook.send(&client, &text).await {
+            warn!("Failed to send error alert to webhook: {err:#}");
+        }
+    }
+}
+
+/// Deduplication and rate limiting of alerts
+#[derive(Debug, Default)]
+struct Throttle {
+    /// when each recently sent alert was last sent
+    recent: HashMap<String, Instant>,
+    sent: VecDeque<Instant>,
+    suppressed: usize,
+}
+
+impl Throttle {
+    /// Returns the number of alerts suppressed since the last admitted one, or `None` if this one
+    /// should be suppressed as well
+    fn admit(&mut self, key: String, now: Instant) -> Option<usize> {
+        self.recent.retain(|_, at| now - *at < DEDUP_WINDOW);
+        while self.sent.front().is_some_and(|at| now - *at >= RATE_WINDOW) {
+            self.sent.pop_front();
+        }
+
+        if self.recent.contains_key(&key) || self.sent.len() >= MAX_ALERTS_PER_WINDOW {
+            self.suppressed += 1;
+            return None;
+        }
+
+        self.recent.insert(key, now);
+        self.sent.push_back(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_are_suppressed_within_window() {
+        let start = Instant::now();
+        let mut throttle = Throttle::default();
+
+        assert_eq!(throttle.admit("a".into(), start), Some(0));
+        assert_eq!(throttle.admit("a".into(), start + RATE_WINDOW), None);
+        assert_eq!(throttle.admit("a".into(), start + DEDUP_WINDOW), Some(1));
+    }
+
+    #[test]
+    fn alerts_are_rate_limited() {
+        let start = Instant::now();
+        let mut throttle = Throttle::default();
+
+        for i in 0..MAX_ALERTS_PER_WINDOW {
+            assert_eq!(throttle.admit(i.to_string(), start), Some(0));
+        }
+        assert_eq!(throttle.admit("new".into(), start), None);
+        assert_eq!(throttle.admit("newer".into(), start + RATE_WINDOW), Some(1));
+    }
+
+    #[test]
+    fn dedup_key_masks_digits() {
+        let alert = |message: &str| Alert {
+            target: "bb_bot".to_string(),
+            message: message.to_string(),
+        };
+
+        assert_eq!(
+            alert("Failed to handle event 123").dedup_key(),
+            alert("Failed to handle event 456").dedup_key()
+        );
+    }
+}
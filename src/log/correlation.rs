@@ -0,0 +1,55 @@
+//! Every command and interaction is handled within a span carrying the Discord ID of the invocation
+//! that started its flow as correlation ID, so that all logs of a multi-step flow (e.g. a role
+//! request) can be found together
+
+use poise::serenity_prelude::{
+    Client, Context as SerenityContext, FullEvent, Interaction, InteractionId,
+    MessageInteractionMetadata, async_trait,
+};
+use serenity::framework::Framework;
+use tracing::{Instrument as _, Span, info_span};
+
+/// Span for the event if it is an invocation, otherwise a disabled span
+pub fn span(event: &FullEvent) -> Span {
+    match event {
+        FullEvent::InteractionCreate { interaction, .. } => {
+            info_span!("invocation", correlation_id = %origin_id(interaction))
+        }
+        // prefix commands
+        FullEvent::Message { new_message } => {
+            info_span!("invocation", correlation_id = %new_message.id)
+        }
+        _ => Span::none(),
+    }
+}
+
+/// Components and modals are attributed to the interaction that sent their message, e.g. the command
+/// opening a menu, falling back to their own ID for messages not sent in response to an interaction
+fn origin_id(interaction: &Interaction) -> InteractionId {
+    let message = match interaction {
+        Interaction::Component(interaction) => Some(&*interaction.message),
+        Interaction::Modal(interaction) => interaction.message.as_deref(),
+        _ => None,
+    };
+
+    match message.and_then(|message| message.interaction_metadata.as_deref()) {
+        Some(MessageInteractionMetadata::Command(metadata)) => metadata.id,
+        Some(MessageInteractionMetadata::Component(metadata)) => metadata.id,
+        Some(MessageInteractionMetadata::ModalSubmit(metadata)) => metadata.id,
+        _ => interaction.id(),
+    }
+}
+
+/// Runs the wrapped framework's handling of every event within its [`span`]
+pub struct TracedFramework<F>(pub F);
+
+#[async_trait]
+impl<F: Framework> Framework for TracedFramework<F> {
+    async fn init(&mut self, client: &Client) {
+        self.0.init(client).await;
+    }
+
+    async fn dispatch(&self, ctx: &SerenityContext, event: &FullEvent) {
+        self.0.dispatch(ctx, event).instrument(span(event)).await;
+    }
+}
@@ -1,36 +1,56 @@
-use std::{borrow::Cow, collections::HashMap, env, str::FromStr as _, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr as _,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result, anyhow};
 use either::Either;
 use poise::{
     Framework, FrameworkOptions, PrefixFrameworkOptions,
     serenity_prelude::{
-        CacheHttp, ClientBuilder, Context as SerenityContext, CreateAllowedMentions, CreateMessage,
-        EventHandler, FullEvent, GatewayIntents, Interaction, Mentionable as _, Message,
-        Permissions, Token, async_trait,
+        CacheHttp, ClientBuilder, ComponentInteraction, ConnectionStage,
+        Context as SerenityContext, CreateAllowedMentions, CreateMessage, EventHandler, FullEvent,
+        GatewayIntents, GuildId, Interaction, Mentionable as _, Message, ModalInteraction, Token,
+        UserId, async_trait,
     },
 };
 use serenity::{all::CreateAttachment, futures::future::try_join_all};
 use tokio::sync::{Mutex, mpsc};
-use tracing::{info, warn};
+use tracing::{Instrument as _, info, warn};
 
 use db::DbHandle;
 use hypixel_api::ApiHandle;
-use shared::BotData;
+use shared::{
+    BotData, Sandbox,
+    db::GetGuildPrefix,
+    interaction::custom_id::{CustomId, Namespace},
+    shard::ShardConfig,
+};
 
 use crate::config::{
-    SECRET_BINGO_ANNOUNCEMENTS, SECRET_BINGO_DISCOVERIES, SECRET_BINGO_EXTERNAL, SPLASHES_CHANNEL,
+    DB_PATH, MANUAL_ROLE_CHANNEL, SANDBOX_DB_PATH, SECRET_BINGO_ANNOUNCEMENTS,
+    SECRET_BINGO_DISCOVERIES, SECRET_BINGO_EXTERNAL,
 };
+use crate::role::mapping_cache::RoleMappingCache;
 use crate::splash_reminder::SplashReminderHandle;
 
+mod bingo;
+mod changelog;
 mod commands;
 mod config;
 mod db;
 mod error;
+mod health;
 mod hob;
 mod hypixel_api;
 mod log;
+mod metrics;
 mod role;
+mod scheduler;
 mod shared;
 mod splash_reminder;
 mod splashes;
@@ -41,27 +61,70 @@ fn get_env_var(name: &str) -> Result<String> {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let _log_guard = log::init_log();
-
     _ = dotenvy::dotenv();
 
+    let _log_guard = log::init_log(
+        log::LogFormat::from_env()?,
+        log::alert::AlertWebhook::from_env()?,
+    );
+
     let token = Token::from_str(&get_env_var("DISCORD_TOKEN")?)?;
     let api_key = get_env_var("HYPIXEL_API_KEY").unwrap_or_else(|_| {
         warn!("No Hypixel API key provided, role request functionality will not work");
         String::new()
     });
 
-    let (db_tx, db_rx) = mpsc::channel(32);
-    match db::db_thread::start_db_thread(db_rx).await {
-        Ok(Ok(())) => {
-            info!("Database thread has completed initialisation");
-            Ok(())
+    if let Ok(millis) = env::var("DB_SLOW_QUERY_MS") {
+        let millis = millis.parse().context("Invalid slow query threshold")?;
+        db::slow_query::set_threshold(Duration::from_millis(millis));
+    }
+    // must be known before the first menu ID is generated
+    let shards = ShardConfig::from_env()?;
+    if let Some(shards) = shards {
+        shared::shard::init(shards);
+    }
+    if let Some(backups) = db::backup::BackupConfig::from_env()? {
+        db::backup::init(backups);
+    }
+
+    // owners of the bot itself rather than a guild, e.g. for direct database access
+    let owners = match env::var("OWNER_IDS") {
+        Ok(ids) => ids
+            .split(',')
+            .map(|id| id.trim().parse::<UserId>())
+            .collect::<Result<HashSet<_>, _>>()
+            .context("Invalid owner IDs")?,
+        Err(_) => {
+            warn!("No owners configured, owner-only commands will be unavailable");
+            HashSet::new()
         }
-        Ok(Err(err)) => Err(anyhow!("Failed to initialise database thread: {err:#}")),
-        Err(_) => Err(anyhow!("Database thread panicked during initialisation")),
-    }?;
+    };
+
+    let db_handle = start_db(DB_PATH).await?;
+    role::network_bingo::load(&db_handle).await?;
+
+    // writes from the sandbox guild go to a separate database, so that the production data isn't
+    // polluted while testing
+    let sandbox = match env::var("SANDBOX_GUILD_ID") {
+        Ok(id) => {
+            let guild_id = GuildId::new(id.parse().context("Invalid sandbox guild ID")?);
+            info!("Running with sandbox guild {guild_id}");
+            Some(Sandbox {
+                guild_id,
+                db_handle: start_db(SANDBOX_DB_PATH).await?,
+            })
+        }
+        Err(_) => None,
+    };
+
+    // the splash reminder is only run for a single guild, which is required once the bot is used in
+    // more than one guild
+    let home_guild = match env::var("HOME_GUILD_ID") {
+        Ok(id) => Some(GuildId::new(id.parse().context("Invalid home guild ID")?)),
+        Err(_) => None,
+    };
 
-    let mut commands = vec![
+    let commands = vec![
         commands::debug::debug(),
         commands::hob::hob(),
         commands::role::rolerequest(),
@@ -71,40 +134,62 @@ async fn main() -> Result<()> {
         commands::splashreminder::splashreminder(),
         commands::register::unregister(),
         commands::baninfo::baninfo(),
+        commands::countsplash::count_splash(),
+        commands::countsplash::uncount_splash(),
+        commands::countsplash::skip_reminder(),
+        commands::mystats::mystats(),
+        commands::stats::stats(),
+        commands::bingo::bingo(),
+        commands::splashes::splashes(),
+        commands::config::config(),
+        commands::feature::feature(),
     ];
-    // Set default permission to `MANAGE_GUILD`, as bots cannot access endpoint for role-based
-    // permission override (manual configuration intended)
-    for cmd in &mut commands {
-        cmd.default_member_permissions = Permissions::MANAGE_GUILD;
-    }
+    // permissions, cooldowns and disabled commands are configured in one place
+    let commands = commands::policy::apply(commands);
 
     // `GUILD_MESSAGES`: only for `register` prefix command
     // `MESSAGE_CONTENT`: same reason, as well as for splash message fetching
-    // `GUILD_MEMBERS`: needed to fetch all members with the splasher role
+    // `GUILD_MEMBERS`: needed to fetch all members with the splasher role, and to restore the roles
+    // of rejoining members
     // `GUILD_MESSAGE_REACTIONS`: necessary to detect reactions on splash messages
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::GUILD_MESSAGE_REACTIONS
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::GUILD_MEMBERS;
 
+    // the bot mention always works as a prefix, guilds can configure an additional text prefix
     let prefix_options = PrefixFrameworkOptions {
         dynamic_prefix: Some(|ctx| {
             Box::pin(async move {
-                Ok(Some(Cow::Owned(
-                    ctx.framework.bot_id().mention().to_string(),
-                )))
+                let prefix = match ctx.guild_id {
+                    Some(guild_id) => {
+                        ctx.serenity_context
+                            .data::<BotData>()
+                            .db_for(Some(guild_id))
+                            .request(GetGuildPrefix { guild_id })
+                            .await??
+                    }
+                    None => None,
+                };
+
+                Ok(Some(Cow::Owned(prefix.unwrap_or_else(|| {
+                    ctx.framework.bot_id().mention().to_string()
+                }))))
             })
         }),
+        mention_as_prefix: true,
         ..Default::default()
     };
 
     let framework = Framework::builder()
         .options(FrameworkOptions {
             commands,
+            owners,
             prefix_options,
             on_error: error::error_handler,
             pre_command: |ctx| {
                 Box::pin(async move {
+                    metrics::record_command(&ctx.command().qualified_name);
                     info!(
                         "[>] `{}` invoked by {}",
                         ctx.invocation_string(),
@@ -126,101 +211,240 @@ async fn main() -> Result<()> {
         .initialize_owners(false)
         .build();
 
+    let data = Arc::new(BotData {
+        db_handle,
+        sandbox,
+        home_guild,
+        api_handle: ApiHandle::new(api_key),
+        hob_sessions: Arc::new(Mutex::new(HashMap::new())),
+        role_sessions: Arc::new(Mutex::new(HashMap::new())),
+        role_mappings: RoleMappingCache::new(),
+        guild_configs: Mutex::new(HashMap::new()),
+        splash_reminder: Mutex::new(SplashReminderHandle::new()),
+    });
+
     let mut client = ClientBuilder::new(token, intents)
-        .framework(Box::new(framework))
+        .framework(Box::new(log::correlation::TracedFramework(framework)))
         .event_handler(Arc::new(Handler))
-        .data(Arc::new(BotData {
-            db_handle: DbHandle::new(db_tx),
-            api_handle: ApiHandle::new(api_key),
-            hob_sessions: Arc::new(Mutex::new(HashMap::new())),
-            role_sessions: Arc::new(Mutex::new(HashMap::new())),
-            splash_reminder: Mutex::new(SplashReminderHandle::new()),
-        }))
+        .data(Arc::clone(&data) as _)
         .await?;
 
-    client.start().await?;
+    // health and metrics endpoints for external monitoring, e.g. `HEALTH_ADDR=127.0.0.1:9100`
+    if let Ok(addr) = env::var("HEALTH_ADDR") {
+        let addr = addr.parse().context("Invalid health server address")?;
+        health::start_health_server(addr, Arc::clone(&client.http), Arc::clone(&data));
+    }
+
+    let http = Arc::clone(&client.http);
+    let shard_manager = Arc::clone(&client.shard_manager);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Received shutdown signal, shutting down gracefully");
+
+        shared::menu::persist::persist_sessions(&http, &data).await;
+
+        // make sure queued writes have reached the database before exiting
+        for db in std::iter::once(&data.db_handle).chain(data.sandbox.iter().map(|s| &s.db_handle))
+        {
+            if let Err(err) = db.flush().await {
+                warn!("Failed to flush database requests: {err:#}");
+            }
+        }
+
+        shard_manager.shutdown_all().await;
+    });
+
+    match shards {
+        Some(shards) => {
+            info!(
+                "Starting shards {}-{} of {}",
+                shards.first, shards.last, shards.total
+            );
+            client.start_shard_range(shards.ids(), shards.total).await?;
+        }
+        None => client.start().await?,
+    }
 
+    info!("Shut down successfully");
     Ok(())
 }
 
+/// Resolves on Ctrl+C, or on SIGTERM (e.g. when stopped by a service manager)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("SIGTERM handler should install");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => (),
+            _ = terminate.recv() => (),
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+async fn start_db(path: &'static str) -> Result<DbHandle> {
+    let (db_tx, db_rx) = mpsc::channel(32);
+    match db::db_thread::start_db_thread(db_rx, path).await {
+        Ok(Ok(())) => {
+            info!("Database thread for '{path}' has completed initialisation");
+            Ok(DbHandle::new(db_tx))
+        }
+        Ok(Err(err)) => Err(anyhow!("Failed to initialise database thread: {err:#}")),
+        Err(_) => Err(anyhow!("Database thread panicked during initialisation")),
+    }
+}
+
 struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn dispatch(&self, ctx: &SerenityContext, event: &FullEvent) {
-        if let Err(err) = match event {
-            FullEvent::InteractionCreate { interaction, .. } => match interaction {
-                Interaction::Component(interaction) => {
-                    let mut action = interaction.data.custom_id.split(':');
-
-                    match action.next().unwrap_or_default() {
-                        "hob" => {
-                            hob::interaction::handle_interaction(
-                                ctx,
-                                Either::Left(interaction),
-                                action,
-                            )
-                            .await
-                        }
-                        "role" => {
-                            role::interaction::handle_interaction(
-                                ctx,
-                                Either::Left(interaction),
-                                action,
-                            )
-                            .await
-                        }
-                        _ => Ok(()),
-                    }
-                }
-                Interaction::Modal(interaction) => {
-                    let mut action = interaction.data.custom_id.split(':');
-
-                    match action.next().unwrap_or_default() {
-                        "hob" => {
-                            hob::interaction::handle_interaction(
-                                ctx,
-                                Either::Right(interaction),
-                                action,
-                            )
-                            .await
-                        }
-                        "role" => {
-                            role::interaction::handle_interaction(
-                                ctx,
-                                Either::Right(interaction),
-                                action,
-                            )
-                            .await
-                        }
-                        _ => Ok(()),
-                    }
-                }
-                _ => Ok(()),
-            },
-            FullEvent::Message { new_message } => match new_message.channel_id {
-                SPLASHES_CHANNEL => {
-                    splash_reminder::event::splashes_message(ctx, new_message).await
-                }
-                SECRET_BINGO_EXTERNAL if new_message.message_reference.is_some() => {
-                    forward_secret_bingo_announcement(ctx, new_message).await
-                }
-                _ => Ok(()),
-            },
-            FullEvent::ReactionAdd { add_reaction, .. } => {
-                if add_reaction.channel_id == SPLASHES_CHANNEL {
-                    splash_reminder::event::splashes_reaction(ctx, add_reaction).await
-                } else {
-                    Ok(())
-                }
+        handle_event(ctx, event)
+            .instrument(log::correlation::span(event))
+            .await;
+    }
+}
+
+async fn handle_event(ctx: &SerenityContext, event: &FullEvent) {
+    if let Err(err) = match event {
+        FullEvent::InteractionCreate { interaction, .. } => match interaction {
+            Interaction::Component(interaction) => {
+                dispatch_interaction(ctx, Either::Left(interaction), &interaction.data.custom_id)
+                    .await
+            }
+            Interaction::Modal(interaction) => {
+                dispatch_interaction(ctx, Either::Right(interaction), &interaction.data.custom_id)
+                    .await
             }
             _ => Ok(()),
-        } {
-            error::event_handler_error(err, ctx, event).await;
+        },
+        FullEvent::Message { new_message } => match new_message.channel_id {
+            SECRET_BINGO_EXTERNAL if new_message.message_reference.is_some() => {
+                forward_secret_bingo_announcement(ctx, new_message).await
+            }
+            MANUAL_ROLE_CHANNEL => role::applications::application_posted(ctx, new_message).await,
+            // the splashes and thank-you channels are configured per guild (see `/config`)
+            _ => {
+                let thanks = splashes::ty_tracking::thanks_message(ctx, new_message).await;
+                let splash = splash_reminder::event::splashes_message(ctx, new_message).await;
+                thanks.and(splash)
+            }
+        },
+        // a resumed session replays missed events, but treating it like a new session only
+        // causes a short rescan
+        FullEvent::Resume { .. } => {
+            splashes::store::gateway_ready();
+            Ok(())
         }
+        FullEvent::ShardStageUpdate { event }
+            if event.old == ConnectionStage::Connected
+                && event.new != ConnectionStage::Connected =>
+        {
+            splashes::store::gateway_disconnected();
+            Ok(())
+        }
+        FullEvent::Ready { data_about_bot, .. } => {
+            splashes::store::gateway_ready();
+            shared::shard::register_context(ctx);
+            if shared::shard::current().is_primary() {
+                splashes::rollover::start_rollover_loop(ctx);
+                role::cache_pruning::start_cache_pruning(ctx);
+                role::link_verification::start_link_verification(ctx);
+                db::backup::start_nightly_backups(ctx);
+            }
+            // runs in every process, as the guild of a job may be on any shard
+            scheduler::start_scheduler(ctx);
+            if let Err(err) = shared::menu::persist::restore_sessions(ctx).await {
+                warn!("Failed to restore menu sessions: {err:#}");
+            }
+            let restored = splash_reminder::event::restore_reminder(ctx).await;
+            // after restoring the reminder, so that newer missed splashes take precedence
+            splashes::reconcile::start_reconciliation(
+                ctx,
+                data_about_bot.guilds.iter().map(|guild| guild.id).collect(),
+            );
+            restored
+        }
+        FullEvent::MessageUpdate { event, .. } => {
+            splashes::store::message_updated(ctx, &event.message).await
+        }
+        FullEvent::MessageDelete {
+            guild_id,
+            channel_id,
+            deleted_message_id,
+            ..
+        } => {
+            let stored =
+                splashes::store::message_deleted(ctx, *guild_id, *deleted_message_id).await;
+            let reminder = splash_reminder::event::splash_deleted(
+                ctx,
+                *guild_id,
+                *channel_id,
+                *deleted_message_id,
+            )
+            .await;
+            let application = role::applications::application_deleted(
+                ctx,
+                *guild_id,
+                *channel_id,
+                *deleted_message_id,
+            )
+            .await;
+            stored.and(reminder).and(application)
+        }
+        FullEvent::GuildMemberAddition { new_member, .. } => {
+            role::rejoin::member_joined(ctx, new_member).await
+        }
+        FullEvent::GuildMemberUpdate { event, .. } => {
+            splashes::onboarding::member_updated(ctx, event).await
+        }
+        FullEvent::ReactionAdd { add_reaction, .. } => {
+            let thanks = splashes::ty_tracking::thanks_reaction(ctx, add_reaction).await;
+            let splash = splash_reminder::event::splashes_reaction(ctx, add_reaction).await;
+            thanks.and(splash)
+        }
+        _ => Ok(()),
+    } {
+        error::event_handler_error(err, ctx, event).await;
     }
 }
 
+/// Routes components and modals of persistent menus and messages to their handler by their custom ID
+async fn dispatch_interaction(
+    ctx: &SerenityContext,
+    interaction: Either<&ComponentInteraction, &ModalInteraction>,
+    custom_id: &str,
+) -> Result<()> {
+    let Some(custom_id) = CustomId::decode(custom_id)? else {
+        return Ok(());
+    };
+
+    let started = Instant::now();
+    let result = match custom_id.namespace {
+        Namespace::Hob => {
+            hob::interaction::handle_interaction(ctx, interaction, custom_id.parts()).await
+        }
+        Namespace::Onboarding => {
+            splashes::onboarding::handle_interaction(ctx, interaction, custom_id.parts()).await
+        }
+        Namespace::Role => {
+            role::interaction::handle_interaction(ctx, interaction, custom_id.parts()).await
+        }
+        Namespace::SplashReminder => {
+            splash_reminder::interaction::handle_interaction(ctx, interaction, custom_id.parts())
+                .await
+        }
+    };
+    metrics::record_interaction(custom_id.namespace.key(), started.elapsed(), result.is_ok());
+
+    result
+}
+
 async fn forward_secret_bingo_announcement(ctx: &SerenityContext, message: &Message) -> Result<()> {
     let ping = if message.content.contains("@Bingo Discovery") {
         info!("Forwarding message to secret bingo announcements channel with discovery ping");
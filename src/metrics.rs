@@ -0,0 +1,251 @@
+//! Counters collected since startup, rendered in Prometheus' text exposition format for the health
+//! server's `/metrics` endpoint and `/debug metrics`
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::Utc;
+
+use crate::shared::{BotData, task};
+use crate::splashes::store;
+
+/// Upper bounds of the interaction latency buckets in seconds. Discord requires a response within
+/// 3 seconds, so the buckets are densest below that.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 10.0];
+
+/// Invocations per command, keyed by the qualified command name
+static COMMAND_COUNTS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+/// Role updates, keyed by their entry point and outcome
+static ROLE_UPDATE_COUNTS: Mutex<BTreeMap<(&str, &str), u64>> = Mutex::new(BTreeMap::new());
+/// Attempted requests to external APIs, keyed by the API and outcome
+static API_REQUEST_COUNTS: Mutex<BTreeMap<(&str, &str), u64>> = Mutex::new(BTreeMap::new());
+/// Handling latency of persistent components and modals, keyed by their custom ID namespace
+static INTERACTION_LATENCY: Mutex<BTreeMap<&str, Histogram>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// non-cumulative, the last entry counts everything above the largest bucket
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    errors: u64,
+    sum_secs: f64,
+}
+
+/// Called before every command invocation
+pub fn record_command(name: &str) {
+    let mut counts = COMMAND_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    *counts.entry(name.to_string()).or_default() += 1;
+}
+
+/// Called after every role update
+pub fn record_role_update(source: &'static str, outcome: &'static str) {
+    let mut counts = ROLE_UPDATE_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    *counts.entry((source, outcome)).or_default() += 1;
+}
+
+/// Called after every attempt of a request to Hypixel's or Mojang's API, including retries
+pub fn record_api_request(api: &'static str, outcome: &'static str) {
+    let mut counts = API_REQUEST_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    *counts.entry((api, outcome)).or_default() += 1;
+}
+
+/// Called after handling a component or modal routed by its custom ID
+pub fn record_interaction(namespace: &'static str, elapsed: Duration, success: bool) {
+    let mut latencies = INTERACTION_LATENCY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let histogram = latencies.entry(namespace).or_default();
+
+    let secs = elapsed.as_secs_f64();
+    let bucket = LATENCY_BUCKETS
+        .iter()
+        .position(|bound| secs <= *bound)
+        .unwrap_or(LATENCY_BUCKETS.len());
+    histogram.buckets[bucket] += 1;
+    histogram.count += 1;
+    histogram.sum_secs += secs;
+    if !success {
+        histogram.errors += 1;
+    }
+}
+
+pub async fn render(data: &BotData) -> String {
+    let mut out = String::new();
+
+    let live_since = store::live_since();
+    metric(
+        &mut out,
+        "bbbot_gateway_connected",
+        "gauge",
+        "Whether the gateway connection is ready",
+        live_since.is_some() as u64,
+    );
+    if let Some(live_since) = live_since {
+        metric(
+            &mut out,
+            "bbbot_gateway_connected_seconds",
+            "gauge",
+            "Seconds since the gateway connection became ready",
+            (Utc::now().timestamp() - live_since).max(0) as u64,
+        );
+    }
+
+    metric(
+        &mut out,
+        "bbbot_db_queue_depth",
+        "gauge",
+        "Database requests waiting to be executed",
+        data.db_handle.queue_depth() as u64,
+    );
+    if let Some(sandbox) = &data.sandbox {
+        metric(
+            &mut out,
+            "bbbot_sandbox_db_queue_depth",
+            "gauge",
+            "Sandbox database requests waiting to be executed",
+            sandbox.db_handle.queue_depth() as u64,
+        );
+    }
+    if let Some(remaining) = data.api_handle.quota_remaining().await {
+        metric(
+            &mut out,
+            "bbbot_hypixel_quota_remaining",
+            "gauge",
+            "Remaining Hypixel API requests until the quota resets",
+            remaining as u64,
+        );
+    }
+
+    metric(
+        &mut out,
+        "bbbot_hypixel_api_degraded",
+        "gauge",
+        "Whether Hypixel's API is failing most requests, serving role requests from the cache",
+        data.api_handle.is_degraded() as u64,
+    );
+
+    let tasks = task::stats();
+    metric(
+        &mut out,
+        "bbbot_background_tasks_running",
+        "gauge",
+        "Background tasks currently running",
+        tasks.running as u64,
+    );
+    metric(
+        &mut out,
+        "bbbot_background_tasks_panicked_total",
+        "counter",
+        "Background tasks that panicked since startup",
+        tasks.panicked as u64,
+    );
+
+    header(
+        &mut out,
+        "bbbot_commands_total",
+        "counter",
+        "Command invocations since startup",
+    );
+    let counts = COMMAND_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for (command, count) in counts.iter() {
+        let _ = writeln!(out, "bbbot_commands_total{{command=\"{command}\"}} {count}");
+    }
+    drop(counts);
+
+    header(
+        &mut out,
+        "bbbot_role_updates_total",
+        "counter",
+        "Role updates since startup",
+    );
+    let counts = ROLE_UPDATE_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for ((source, outcome), count) in counts.iter() {
+        let _ = writeln!(
+            out,
+            "bbbot_role_updates_total{{source=\"{source}\",outcome=\"{outcome}\"}} {count}"
+        );
+    }
+    drop(counts);
+
+    header(
+        &mut out,
+        "bbbot_api_requests_total",
+        "counter",
+        "Requests to external APIs since startup, counting every retry",
+    );
+    let counts = API_REQUEST_COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for ((api, outcome), count) in counts.iter() {
+        let _ = writeln!(
+            out,
+            "bbbot_api_requests_total{{api=\"{api}\",outcome=\"{outcome}\"}} {count}"
+        );
+    }
+    drop(counts);
+
+    let latencies = INTERACTION_LATENCY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    header(
+        &mut out,
+        "bbbot_interaction_duration_seconds",
+        "histogram",
+        "Time taken to handle persistent components and modals",
+    );
+    for (namespace, histogram) in latencies.iter() {
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "bbbot_interaction_duration_seconds_bucket{{namespace=\"{namespace}\",le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "bbbot_interaction_duration_seconds_bucket{{namespace=\"{namespace}\",le=\"+Inf\"}} {}\n\
+             bbbot_interaction_duration_seconds_sum{{namespace=\"{namespace}\"}} {}\n\
+             bbbot_interaction_duration_seconds_count{{namespace=\"{namespace}\"}} {}",
+            histogram.count, histogram.sum_secs, histogram.count
+        );
+    }
+    header(
+        &mut out,
+        "bbbot_interaction_errors_total",
+        "counter",
+        "Persistent components and modals whose handling failed",
+    );
+    for (namespace, histogram) in latencies.iter() {
+        let _ = writeln!(
+            out,
+            "bbbot_interaction_errors_total{{namespace=\"{namespace}\"}} {}",
+            histogram.errors
+        );
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+    header(out, name, kind, help);
+    let _ = writeln!(out, "{name} {value}");
+}
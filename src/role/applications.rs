@@ -0,0 +1,186 @@
+use anyhow::Result;
+use poise::serenity_prelude::{
+    ButtonStyle, Context as SerenityContext, CreateActionRow, CreateAllowedMentions, CreateButton,
+    CreateComponent, CreateContainer, CreateContainerComponent, CreateMessage, CreateTextDisplay,
+    GenericChannelId, GuildId, Member, Mentionable as _, Message, MessageFlags, MessageId,
+    Timestamp, UserId,
+    colours::{
+        css::{POSITIVE, WARNING},
+        roles::BLUE,
+    },
+};
+use tracing::{info, warn};
+
+use crate::config::MANUAL_ROLE_CHANNEL;
+use crate::role::{
+    db::application::{
+        DeleteUnresolvedManualApplication, GetManualApplication, HasUnresolvedManualApplication,
+        InsertManualApplication,
+    },
+    types::{ApplicationStatus, ManualApplication},
+};
+use crate::shared::{
+    BotData,
+    interaction::custom_id::{self, Namespace},
+    time::{TimestampStyle::Relative, discord_timestamp},
+};
+
+/// Replies to new applications in the manual role channel with a panel that lets staff claim them,
+/// so that the same application isn't handled twice
+pub async fn application_posted(ctx: &SerenityContext, message: &Message) -> Result<()> {
+    // replies are follow-ups to existing applications, e.g. staff asking for more screenshots
+    if message.author.bot() || message.message_reference.is_some() {
+        return Ok(());
+    }
+
+    let db = ctx.data::<BotData>().db_for(message.guild_id);
+
+    // messages missed during downtime are replayed from a point that may overlap with handled ones
+    if db
+        .request(GetManualApplication {
+            message_id: message.id,
+        })
+        .await??
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    // further messages of the applicant are treated as part of their open application
+    if db
+        .request(HasUnresolvedManualApplication {
+            author: message.author.id,
+        })
+        .await??
+    {
+        return Ok(());
+    }
+
+    let mut application = ManualApplication {
+        message_id: message.id,
+        author: message.author.id,
+        posted_at: message.timestamp.unix_timestamp(),
+        panel_message_id: message.id,
+        claimed: None,
+        resolved: None,
+    };
+
+    let panel_message = message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .reference_message(message)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(panel(&application)?),
+        )
+        .await?;
+    application.panel_message_id = panel_message.id;
+
+    db.request(InsertManualApplication { application })
+        .await??;
+
+    info!(
+        "Tracking manual role application {} by {}",
+        message.id, message.author.name
+    );
+
+    Ok(())
+}
+
+/// Stops tracking an unresolved application whose message was deleted, e.g. because the applicant
+/// withdrew it, so that their next message is treated as a new application again
+pub async fn application_deleted(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    channel_id: GenericChannelId,
+    message_id: MessageId,
+) -> Result<()> {
+    if channel_id != MANUAL_ROLE_CHANNEL {
+        return Ok(());
+    }
+
+    let db = ctx.data::<BotData>().db_for(guild_id);
+    let Some(application) = db
+        .request(DeleteUnresolvedManualApplication { message_id })
+        .await??
+    else {
+        return Ok(());
+    };
+    info!("Stopped tracking deleted manual role application {message_id}");
+
+    // the panel would only refer to the deleted message
+    if let Err(err) = ctx
+        .http
+        .delete_message(channel_id, application.panel_message_id, None)
+        .await
+    {
+        warn!("Failed to delete panel of manual role application {message_id}: {err}");
+    }
+
+    Ok(())
+}
+
+/// Status of the application along with the buttons to claim, release and resolve it
+pub fn panel(application: &ManualApplication) -> Result<Vec<CreateComponent<'static>>> {
+    let handled_by = |(user, at): (UserId, i64)| -> Result<String> {
+        Ok(format!(
+            "{} {}",
+            user.mention(),
+            discord_timestamp(Timestamp::from_unix_timestamp(at)?, Relative)
+        ))
+    };
+
+    let status = application.status();
+    let (text, colour) = match (status, application.claimed, application.resolved) {
+        (ApplicationStatus::Resolved, _, Some(resolved)) => (
+            format!("**Resolved** by {}", handled_by(resolved)?),
+            POSITIVE,
+        ),
+        (ApplicationStatus::Claimed, Some(claimed), _) => {
+            (format!("**Claimed** by {}", handled_by(claimed)?), WARNING)
+        }
+        _ => ("**Open**, waiting for staff".to_string(), BLUE),
+    };
+
+    let button = |action: &str| {
+        CreateButton::new(custom_id::encode(
+            Namespace::Role,
+            &[&"application", &action, &application.message_id],
+        ))
+    };
+    let claim_button = if status == ApplicationStatus::Claimed {
+        button("release")
+            .label("Release")
+            .style(ButtonStyle::Secondary)
+    } else {
+        button("claim")
+            .label("Claim")
+            .style(ButtonStyle::Primary)
+            .disabled(status == ApplicationStatus::Resolved)
+    };
+    let resolve_button = button("resolve")
+        .label("Resolve")
+        .style(ButtonStyle::Success)
+        .disabled(status == ApplicationStatus::Resolved);
+
+    Ok(vec![CreateComponent::Container(
+        CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+                "-# Staff only\nApplication status: {text}"
+            ))),
+            CreateContainerComponent::ActionRow(CreateActionRow::Buttons(
+                vec![claim_button, resolve_button].into(),
+            )),
+        ])
+        .accent_colour(colour),
+    )])
+}
+
+/// Matches the `MANAGE_GUILD` requirement of staff-only commands
+pub fn is_staff(member: Option<&Member>) -> bool {
+    member
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+}
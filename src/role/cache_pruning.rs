@@ -0,0 +1,76 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use poise::serenity_prelude::Context as SerenityContext;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+use crate::db::DbHandle;
+use crate::hypixel_api::ApiHandle;
+use crate::role::db::cache::{PruneCaches, PrunedCaches};
+use crate::shared::{BotData, task::spawn_background};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Periodically deletes expired cache entries, which are otherwise only cleaned up once the same
+/// player or guild is requested again
+pub fn start_cache_pruning(ctx: &SerenityContext) {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let data = ctx.data::<BotData>();
+
+    spawn_background("cache pruning", Arc::clone(&ctx.http), async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            // stale entries are still served while Hypixel's API is unstable, and pruning would
+            // query the API as well
+            if data.api_handle.is_degraded() {
+                info!("Hypixel's API is degraded, skipping cache pruning");
+                continue;
+            }
+
+            let dbs = std::iter::once(&data.db_handle)
+                .chain(data.sandbox.iter().map(|sandbox| &sandbox.db_handle));
+            for db in dbs {
+                if let Err(err) = prune(&data.api_handle, db).await {
+                    error!("Failed to prune caches: {err:#}");
+                }
+            }
+        }
+    });
+}
+
+/// Deletes expired cache entries now, also used by `/config cache-prune`
+pub async fn prune(api: &ApiHandle, db: &DbHandle) -> Result<PrunedCaches> {
+    // stale completions are detected by comparing against the current bingo
+    api.update_current_bingo(db).await?;
+
+    let pruned = db.request(PruneCaches).await??;
+    if pruned.total() > 0 {
+        info!(
+            "Pruned caches: {} player response(s), {} guild member list(s), {} completion(s), {} \
+             Discord username(s), {} Mojang profile(s)",
+            pruned.player_endpoint,
+            pruned.hypixel_guild,
+            pruned.completions,
+            pruned.discord_index,
+            pruned.mojang_profiles
+        );
+    }
+
+    Ok(pruned)
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use poise::serenity_prelude::{Role, RoleId};
+use serde_json::{Value, json};
+
+use crate::error::UserError;
+use crate::role::types::{
+    NetworkBingo, RoleMapping, RoleMappingKind, RoleMappingKindRaw, RolePatterns,
+};
+use crate::shared::types::{Bingo, BingoKind};
+
+/// Role bindings and auto-detection patterns read from an export
+pub struct RoleConfigImport {
+    pub mappings: Vec<ImportedMapping>,
+    /// `None` if the file doesn't contain patterns, in which case the current ones are kept
+    pub patterns: Option<RolePatterns>,
+}
+
+/// Binding whose role still has to be matched to one of the importing guild's roles
+pub struct ImportedMapping {
+    pub kind: RoleMappingKind,
+    pub role_id: RoleId,
+    pub role_name: Option<String>,
+}
+
+impl ImportedMapping {
+    /// Matches the exported role by ID, falling back to its name, as role IDs differ between
+    /// guilds
+    pub fn resolve(&self, guild_roles: &[Role]) -> Option<RoleMapping> {
+        guild_roles
+            .iter()
+            .find(|role| role.id == self.role_id)
+            .or_else(|| {
+                let name = self.role_name.as_deref()?;
+                guild_roles.iter().find(|role| role.name.as_str() == name)
+            })
+            .map(|role| RoleMapping::new(self.kind, role.id))
+    }
+}
+
+pub fn to_json(
+    mappings: &[RoleMapping],
+    patterns: &RolePatterns,
+    role_names: &HashMap<RoleId, String>,
+) -> String {
+    let mappings: Vec<_> = mappings
+        .iter()
+        .map(|mapping| {
+            let mut entry = json!({
+                "kind": mapping.kind.raw().key(),
+                // as a string, since JSON numbers can't represent every snowflake exactly
+                "role_id": mapping.role.to_string(),
+                "role_name": role_names.get(&mapping.role),
+            });
+            match mapping.kind {
+                RoleMappingKind::Completions { count } => entry["count"] = json!(count),
+                RoleMappingKind::SpecificCompletion { bingo } => {
+                    entry["bingo_kind"] = json!(bingo.kind as u8);
+                    entry["bingo_number"] = json!(bingo.kind_specific_id + 1);
+                }
+                RoleMappingKind::BingoRank { rank } => entry["rank"] = json!(rank),
+                RoleMappingKind::Immortal => (),
+                RoleMappingKind::NetworkBingo { bingo } => {
+                    entry["network_bingo"] = json!(bingo.id())
+                }
+            }
+            entry
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "patterns": {
+            "completions": patterns.completions,
+            "specific_completion": patterns.specific_completion,
+            "bingo_rank": patterns.bingo_rank,
+            "immortal": patterns.immortal,
+        },
+        "mappings": mappings,
+    }))
+    .unwrap_or_default()
+}
+
+/// Parses and validates a config exported by [`to_json`]
+pub fn parse(contents: &str) -> Result<RoleConfigImport> {
+    let value: Value = serde_json::from_str(contents)
+        .map_err(|err| UserError(anyhow!("The file isn't valid JSON: {err}")))?;
+
+    let mappings = value
+        .get("mappings")
+        .and_then(Value::as_array)
+        .ok_or_else(|| UserError(anyhow!("The file doesn't contain a `mappings` array")))?
+        .iter()
+        .enumerate()
+        .map(|(i, mapping)| {
+            parse_mapping(mapping).map_err(|err| err.context(format!("Binding #{}", i + 1)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // there is only a single immortal role, so importing several would silently drop all but one
+    if mappings
+        .iter()
+        .filter(|mapping| matches!(mapping.kind, RoleMappingKind::Immortal))
+        .count()
+        > 1
+    {
+        bail!(UserError(anyhow!(
+            "The file contains more than one immortal role"
+        )));
+    }
+
+    let patterns = value
+        .get("patterns")
+        .and_then(Value::as_object)
+        .map(|patterns| {
+            let pattern = |key: &str| {
+                patterns
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            RolePatterns::new(
+                pattern("completions"),
+                pattern("specific_completion"),
+                pattern("bingo_rank"),
+                pattern("immortal"),
+            )
+        });
+
+    Ok(RoleConfigImport { mappings, patterns })
+}
+
+fn parse_mapping(mapping: &Value) -> Result<ImportedMapping> {
+    let kind_key = mapping
+        .get("kind")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let Some(raw_kind) = RoleMappingKindRaw::from_key(kind_key) else {
+        bail!(UserError(anyhow!("Unknown category: `{kind_key}`")));
+    };
+
+    let number = |key: &str| -> Result<u64> {
+        mapping
+            .get(key)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| UserError(anyhow!("Missing or invalid `{key}`")).into())
+    };
+    let small_number = |key: &str| -> Result<u8> {
+        u8::try_from(number(key)?).map_err(|_| UserError(anyhow!("`{key}` is too large")).into())
+    };
+
+    let kind = match raw_kind {
+        RoleMappingKindRaw::Completions => RoleMappingKind::Completions {
+            count: number("count")? as usize,
+        },
+        RoleMappingKindRaw::SpecificCompletion => {
+            let Some(&bingo_kind) = BingoKind::ALL.get(small_number("bingo_kind")? as usize) else {
+                bail!(UserError(anyhow!("Unknown bingo kind")));
+            };
+            let Some(kind_specific_id) = small_number("bingo_number")?.checked_sub(1) else {
+                bail!(UserError(anyhow!("Bingo numbers start at 1")));
+            };
+            RoleMappingKind::SpecificCompletion {
+                bingo: Bingo::new(kind_specific_id, bingo_kind, None),
+            }
+        }
+        RoleMappingKindRaw::BingoRank => RoleMappingKind::BingoRank {
+            rank: small_number("rank")?,
+        },
+        RoleMappingKindRaw::Immortal => RoleMappingKind::Immortal,
+        RoleMappingKindRaw::NetworkBingo => {
+            let bingo = NetworkBingo::from_u8(small_number("network_bingo")?);
+            if !bingo.is_known() {
+                bail!(UserError(anyhow!("Unknown Network Bingo")));
+            }
+            RoleMappingKind::NetworkBingo { bingo }
+        }
+    };
+
+    let role_id = match mapping.get("role_id") {
+        Some(Value::String(id)) => id.parse::<u64>().ok(),
+        Some(Value::Number(id)) => id.as_u64(),
+        _ => None,
+    }
+    .filter(|id| *id != 0)
+    .map(RoleId::new)
+    .ok_or_else(|| UserError(anyhow!("Missing or invalid `role_id`")))?;
+
+    Ok(ImportedMapping {
+        kind,
+        role_id,
+        role_name: mapping
+            .get("role_name")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trips_export() {
+        let mappings = [
+            RoleMapping::new(RoleMappingKind::Completions { count: 5 }, RoleId::new(11)),
+            RoleMapping::new(
+                RoleMappingKind::SpecificCompletion {
+                    bingo: Bingo::new(3, BingoKind::Extreme, None),
+                },
+                RoleId::new(12),
+            ),
+            RoleMapping::new(RoleMappingKind::BingoRank { rank: 4 }, RoleId::new(13)),
+            RoleMapping::new(RoleMappingKind::Immortal, RoleId::new(14)),
+            RoleMapping::new(
+                RoleMappingKind::NetworkBingo {
+                    bingo: NetworkBingo::HALLOWEEN_2024,
+                },
+                // beyond what an f64 represents exactly
+                RoleId::new(1_234_567_890_123_456_789),
+            ),
+        ];
+        let patterns = RolePatterns::new(
+            "{count} Blackouts".to_string(),
+            String::new(),
+            "Rank {rank}".to_string(),
+            "Immortal".to_string(),
+        );
+        let role_names = HashMap::from([(RoleId::new(11), "5 Blackouts".to_string())]);
+
+        let import = parse(&to_json(&mappings, &patterns, &role_names)).unwrap();
+
+        assert_eq!(import.mappings.len(), mappings.len());
+        for (imported, exported) in import.mappings.iter().zip(&mappings) {
+            assert_eq!(
+                format!("{:?}", imported.kind),
+                format!("{:?}", exported.kind)
+            );
+            assert_eq!(imported.role_id, exported.role);
+            assert_eq!(imported.role_name.as_ref(), role_names.get(&exported.role));
+        }
+
+        let imported_patterns = import.patterns.unwrap();
+        assert_eq!(imported_patterns.completions, patterns.completions);
+        assert_eq!(imported_patterns.specific_completion, None);
+        assert_eq!(imported_patterns.bingo_rank, patterns.bingo_rank);
+        assert_eq!(imported_patterns.immortal, patterns.immortal);
+    }
+}
@@ -1,10 +1,13 @@
 use rusqlite::{Connection, Result};
 
+pub mod application;
+pub mod audit;
 pub mod cache;
 pub mod link;
 pub mod role_config;
 
-pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
+/// Applied by the baseline migration
+pub fn baseline_schema(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "
         -- Primarily stores patterns to attempt to auto-fetch roles if not defined explicitly,
@@ -56,6 +59,48 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             role INTEGER NOT NULL
         );
 
+        -- Hypixel guild whose members are granted a role (configurable, optional)
+        CREATE TABLE IF NOT EXISTS role_hypixel_guild_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            guild_id TEXT NOT NULL,
+            guild_name TEXT NOT NULL,
+            role INTEGER NOT NULL
+        );
+
+        -- Restoring roles of linked users who rejoin the server (optional, disabled if absent)
+        CREATE TABLE IF NOT EXISTS role_rejoin_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            delay_secs INTEGER NOT NULL,
+            -- where restored roles are logged for staff (optional)
+            log_channel INTEGER
+        );
+
+        -- Progress of an interrupted or cancelled `/rolerequest force update-all` run, for resuming
+        CREATE TABLE IF NOT EXISTS role_bulk_update_progress (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            -- users are processed in order of their discord ID
+            last_discord_id INTEGER NOT NULL,
+            updated INTEGER NOT NULL,
+            unchanged INTEGER NOT NULL,
+            skipped INTEGER NOT NULL,
+            failed INTEGER NOT NULL
+        );
+
+        -- Role changes applied by role updates, for `/rolerequest audit`
+        CREATE TABLE IF NOT EXISTS role_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            discord_id INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            -- 0 = role request, 1 = force update, 2 = bulk update, 3 = rejoin
+            trigger INTEGER NOT NULL,
+            -- staff member who triggered the update, if any
+            triggered_by INTEGER,
+            -- comma-separated role IDs
+            added TEXT NOT NULL,
+            removed TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS role_audit_log_user ON role_audit_log (discord_id, timestamp);
+
         -- Linked user accounts
         CREATE TABLE IF NOT EXISTS role_users_linked (
             discord_id INTEGER PRIMARY KEY,
@@ -92,14 +137,42 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             bingo_set BLOB
         );
 
-        -- Cached responses from hypixel's `/v2/player` endpoint
+        -- Cached member lists from hypixel's `/v2/guild` endpoint (newline-separated UUIDs)
+        CREATE TABLE IF NOT EXISTS role_hypixel_guild_cache (
+            guild_id TEXT PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            members TEXT NOT NULL
+        );
+
+        -- Cached responses from hypixel's `/v2/player` endpoint, cleared on startup
         CREATE TABLE IF NOT EXISTS role_player_endpoint_cache (
             uuid TEXT PRIMARY KEY,
             timestamp INTEGER NOT NULL,
             json TEXT
         );
-        -- Clear on startup
-        DELETE FROM role_player_endpoint_cache;
+
+        -- Discord usernames found in responses from hypixel's `/v2/player` endpoint, used to
+        -- suggest links for unlinked users
+        -- Note: not cleared on startup, suggestions are verified against the API before linking
+        CREATE TABLE IF NOT EXISTS role_player_discord_index (
+            uuid TEXT PRIMARY KEY,
+            discord TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS role_player_discord_index_discord
+        ON role_player_discord_index (discord);
+
+        -- How long Discord usernames are kept in the index above, the default applies if unset
+        CREATE TABLE IF NOT EXISTS role_cache_retention (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            discord_index_days INTEGER NOT NULL
+        );
         ",
     )
 }
+
+/// Runs on every startup, as the cached player responses may be outdated after downtime
+pub fn clear_startup_caches(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM role_player_endpoint_cache", [])?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use poise::serenity_prelude::{MessageId, UserId};
+use rusqlite::{Connection, OptionalExtension as _, Result, Row, params};
+
+use crate::db::DbRequest;
+use crate::role::types::{ApplicationStatus, ManualApplication};
+
+pub struct InsertManualApplication {
+    pub application: ManualApplication,
+}
+impl DbRequest for InsertManualApplication {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let application = self.application;
+        conn.execute(
+            "
+            INSERT OR IGNORE INTO role_manual_applications
+                (message_id, author, posted_at, panel_message_id)
+            VALUES (?1, ?2, ?3, ?4)
+            ",
+            params![
+                application.message_id.get(),
+                application.author.get(),
+                application.posted_at,
+                application.panel_message_id.get(),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct GetManualApplication {
+    pub message_id: MessageId,
+}
+impl DbRequest for GetManualApplication {
+    type ReturnValue = Result<Option<ManualApplication>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT * FROM role_manual_applications WHERE message_id=?1",
+            params![self.message_id.get()],
+            application_from_row,
+        )
+        .optional()
+    }
+}
+
+pub struct HasUnresolvedManualApplication {
+    pub author: UserId,
+}
+impl DbRequest for HasUnresolvedManualApplication {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT EXISTS(
+                SELECT 1 FROM role_manual_applications WHERE author=?1 AND resolved_by IS NULL
+            )
+            ",
+            params![self.author.get()],
+            |row| row.get(0),
+        )
+    }
+}
+
+/// Resolved applications are kept, as their history is still listed
+pub struct DeleteUnresolvedManualApplication {
+    pub message_id: MessageId,
+}
+impl DbRequest for DeleteUnresolvedManualApplication {
+    /// the deleted application, if it was tracked and unresolved
+    type ReturnValue = Result<Option<ManualApplication>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            DELETE FROM role_manual_applications WHERE message_id=?1 AND resolved_by IS NULL
+            RETURNING *
+            ",
+            params![self.message_id.get()],
+            application_from_row,
+        )
+        .optional()
+    }
+}
+
+pub struct GetManualApplications {
+    /// unresolved applications if `None`
+    pub status: Option<ApplicationStatus>,
+    pub limit: u32,
+}
+impl DbRequest for GetManualApplications {
+    /// oldest first, except for resolved applications which are listed newest first
+    type ReturnValue = Result<Vec<ManualApplication>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let query = match self.status {
+            None => "WHERE resolved_by IS NULL ORDER BY posted_at ASC",
+            Some(ApplicationStatus::Open) => {
+                "WHERE claimed_by IS NULL AND resolved_by IS NULL ORDER BY posted_at ASC"
+            }
+            Some(ApplicationStatus::Claimed) => {
+                "WHERE claimed_by IS NOT NULL AND resolved_by IS NULL ORDER BY posted_at ASC"
+            }
+            Some(ApplicationStatus::Resolved) => {
+                "WHERE resolved_by IS NOT NULL ORDER BY resolved_at DESC"
+            }
+        };
+
+        let mut statement = conn.prepare(&format!(
+            "SELECT * FROM role_manual_applications {query} LIMIT ?1"
+        ))?;
+        statement
+            .query_map(params![self.limit], application_from_row)?
+            .collect()
+    }
+}
+
+pub struct ClaimManualApplication {
+    pub message_id: MessageId,
+    pub by: UserId,
+    /// unix timestamp
+    pub at: i64,
+}
+impl DbRequest for ClaimManualApplication {
+    /// `false` if the application was already claimed or resolved
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let updated = conn.execute(
+            "
+            UPDATE role_manual_applications SET claimed_by=?2, claimed_at=?3
+            WHERE message_id=?1 AND claimed_by IS NULL AND resolved_by IS NULL
+            ",
+            params![self.message_id.get(), self.by.get(), self.at],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+pub struct ReleaseManualApplication {
+    pub message_id: MessageId,
+    /// only the staff member who claimed the application can release it
+    pub by: UserId,
+}
+impl DbRequest for ReleaseManualApplication {
+    /// `false` if the application wasn't claimed by `by` or was already resolved
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let updated = conn.execute(
+            "
+            UPDATE role_manual_applications SET claimed_by=NULL, claimed_at=NULL
+            WHERE message_id=?1 AND claimed_by=?2 AND resolved_by IS NULL
+            ",
+            params![self.message_id.get(), self.by.get()],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+pub struct ResolveManualApplication {
+    pub message_id: MessageId,
+    pub by: UserId,
+    /// unix timestamp
+    pub at: i64,
+}
+impl DbRequest for ResolveManualApplication {
+    /// `false` if the application was already resolved
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        // resolving an unclaimed application implicitly claims it
+        let updated = conn.execute(
+            "
+            UPDATE role_manual_applications SET
+                claimed_by=COALESCE(claimed_by, ?2),
+                claimed_at=COALESCE(claimed_at, ?3),
+                resolved_by=?2,
+                resolved_at=?3
+            WHERE message_id=?1 AND resolved_by IS NULL
+            ",
+            params![self.message_id.get(), self.by.get(), self.at],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+fn application_from_row(row: &Row) -> Result<ManualApplication> {
+    let handled_by = |user: &str, at: &str| -> Result<Option<(UserId, i64)>> {
+        Ok(row
+            .get::<_, Option<u64>>(user)?
+            .zip(row.get::<_, Option<i64>>(at)?)
+            .map(|(user, at)| (UserId::new(user), at)))
+    };
+
+    Ok(ManualApplication {
+        message_id: MessageId::new(row.get("message_id")?),
+        author: UserId::new(row.get("author")?),
+        posted_at: row.get("posted_at")?,
+        panel_message_id: MessageId::new(row.get("panel_message_id")?),
+        claimed: handled_by("claimed_by", "claimed_at")?,
+        resolved: handled_by("resolved_by", "resolved_at")?,
+    })
+}
@@ -0,0 +1,157 @@
+use poise::serenity_prelude::{RoleId, UserId};
+use rusqlite::{Connection, Result, params};
+
+use crate::db::DbRequest;
+use crate::role::types::{
+    RoleAuditEntry, RoleUpdateOutcome, RoleUpdateTrigger, RoleUpdateTriggerKind,
+};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+pub struct InsertRoleAuditEntry {
+    pub entry: RoleAuditEntry,
+}
+impl DbRequest for InsertRoleAuditEntry {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let (trigger, triggered_by) = self.entry.trigger.to_raw();
+
+        conn.execute(
+            "
+            INSERT INTO role_audit_log (discord_id, timestamp, trigger, triggered_by, added, removed)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ",
+            params![
+                self.entry.user.get(),
+                self.entry.timestamp,
+                trigger,
+                triggered_by.map(UserId::get),
+                join_roles(&self.entry.added),
+                join_roles(&self.entry.removed),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct GetRoleAuditEntries {
+    pub user: UserId,
+    pub limit: u32,
+}
+impl DbRequest for GetRoleAuditEntries {
+    /// newest first
+    type ReturnValue = Result<Vec<RoleAuditEntry>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT timestamp, trigger, triggered_by, added, removed
+            FROM role_audit_log
+            WHERE discord_id=?1
+            ORDER BY timestamp DESC, id DESC
+            LIMIT ?2
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.user.get(), self.limit], |row| {
+                Ok(RoleAuditEntry {
+                    user: self.user,
+                    timestamp: row.get("timestamp")?,
+                    trigger: RoleUpdateTrigger::from_raw(
+                        row.get("trigger")?,
+                        row.get::<_, Option<u64>>("triggered_by")?.map(UserId::new),
+                    ),
+                    added: split_roles(&row.get::<_, String>("added")?),
+                    removed: split_roles(&row.get::<_, String>("removed")?),
+                })
+            })?
+            .collect()
+    }
+}
+
+fn join_roles(roles: &[RoleId]) -> String {
+    roles
+        .iter()
+        .map(|role| role.get().to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_roles(roles: &str) -> Vec<RoleId> {
+    roles
+        .split(',')
+        .filter_map(|id| id.parse().ok())
+        .map(RoleId::new)
+        .collect()
+}
+
+pub struct RecordRoleUpdate {
+    pub trigger: RoleUpdateTrigger,
+    pub outcome: RoleUpdateOutcome,
+    /// unix timestamp
+    pub timestamp: i64,
+}
+impl DbRequest for RecordRoleUpdate {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO role_update_counts (day, trigger, outcome, count)
+            VALUES (?1, ?2, ?3, 1)
+            ON CONFLICT(day, trigger, outcome) DO UPDATE SET count = count + 1
+            ",
+            params![
+                self.timestamp.div_euclid(DAY_SECS),
+                self.trigger.kind().to_raw(),
+                self.outcome.to_raw(),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct GetRoleUpdateCounts {
+    /// only counts updates on or after the day of this unix timestamp, all of them if `None`
+    pub since: Option<i64>,
+}
+impl DbRequest for GetRoleUpdateCounts {
+    /// trigger kind, outcome and number of updates
+    type ReturnValue = Result<Vec<(RoleUpdateTriggerKind, RoleUpdateOutcome, u64)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let since_day = self
+            .since
+            .map_or(i64::MIN, |since| since.div_euclid(DAY_SECS));
+        let mut statement = conn.prepare(
+            "
+            SELECT trigger, outcome, SUM(count) AS count
+            FROM role_update_counts
+            WHERE day >= ?1
+            GROUP BY trigger, outcome
+            ",
+        )?;
+
+        let rows = statement.query_map(params![since_day], |row| {
+            Ok((
+                row.get::<_, u8>("trigger")?,
+                row.get::<_, u8>("outcome")?,
+                row.get::<_, u64>("count")?,
+            ))
+        })?;
+
+        let mut counts = Vec::new();
+        for row in rows {
+            let (trigger, outcome, count) = row?;
+            // trigger kinds and outcomes of newer versions are skipped
+            if let Some(trigger) = RoleUpdateTriggerKind::from_raw(trigger)
+                && let Some(outcome) = RoleUpdateOutcome::from_raw(outcome)
+            {
+                counts.push((trigger, outcome, count));
+            }
+        }
+        Ok(counts)
+    }
+}
@@ -1,7 +1,9 @@
+use std::collections::BTreeMap;
+
 use rusqlite::{Connection, OptionalExtension as _, Result, params};
 
 use crate::db::DbRequest;
-use crate::role::types::NetworkBingo;
+use crate::role::types::{NetworkBingo, PlayerCacheEntries};
 use crate::shared::{db::GetCurrentBingo, types::BitSet};
 
 // NOTE: Before reading any cached data, the current bingo should be updated. If cached data is
@@ -77,8 +79,7 @@ impl DbRequest for CachedNetworkBingos {
             )
             .optional()?;
 
-        let current_network_bingo =
-            *NetworkBingo::ALL.last().unwrap_or(&NetworkBingo::Unknown) as u8;
+        let current_network_bingo = NetworkBingo::latest().id();
 
         if let Some((updated_after, bingo_bytes)) = cached {
             if current_network_bingo > updated_after {
@@ -192,8 +193,13 @@ impl DbRequest for CachedImmortal {
     }
 }
 
+/// How long a response from hypixel's `/v2/player` endpoint is served from the cache, in seconds
+pub const PLAYER_ENDPOINT_CACHE_SECS: i64 = 60;
+
 pub struct CachedHypixelPlayerEndpoint {
     pub uuid: String,
+    /// returns expired entries instead of deleting them, while Hypixel's API is unstable
+    pub allow_stale: bool,
 }
 impl DbRequest for CachedHypixelPlayerEndpoint {
     type ReturnValue = Result<Option<(i64, String)>>;
@@ -217,7 +223,9 @@ impl DbRequest for CachedHypixelPlayerEndpoint {
             .optional()?;
 
         if let Some((timestamp, json)) = cached {
-            if chrono::Utc::now().timestamp() > timestamp + 60 {
+            if !self.allow_stale
+                && chrono::Utc::now().timestamp() > timestamp + PLAYER_ENDPOINT_CACHE_SECS
+            {
                 // invalid, delete cache entry
                 conn.execute(
                     "
@@ -235,3 +243,302 @@ impl DbRequest for CachedHypixelPlayerEndpoint {
         }
     }
 }
+
+/// Searches the Discord usernames of previously fetched players, returning the most recently
+/// fetched match
+pub struct FindPlayerByCachedDiscord {
+    pub discord: String,
+}
+impl DbRequest for FindPlayerByCachedDiscord {
+    type ReturnValue = Result<Option<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT uuid
+            FROM role_player_discord_index
+            WHERE discord=?1
+            ORDER BY timestamp DESC
+            LIMIT 1
+            ",
+            params![self.discord],
+            |row| row.get("uuid"),
+        )
+        .optional()
+    }
+}
+
+/// How long a guild's member list is served from the cache, in seconds
+pub const HYPIXEL_GUILD_CACHE_SECS: i64 = 600;
+
+pub struct CachedHypixelGuildMembers {
+    pub guild_id: String,
+    /// returns expired entries instead of deleting them, while Hypixel's API is unstable
+    pub allow_stale: bool,
+}
+impl DbRequest for CachedHypixelGuildMembers {
+    type ReturnValue = Result<Option<Vec<String>>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let cached: Option<(i64, String)> = conn
+            .query_one(
+                "
+                SELECT timestamp, members
+                FROM role_hypixel_guild_cache
+                WHERE guild_id=?1
+                ",
+                params![self.guild_id],
+                |row| Ok((row.get("timestamp")?, row.get("members")?)),
+            )
+            .optional()?;
+
+        match cached {
+            Some((timestamp, members))
+                if self.allow_stale
+                    || chrono::Utc::now().timestamp() <= timestamp + HYPIXEL_GUILD_CACHE_SECS =>
+            {
+                Ok(Some(members.lines().map(String::from).collect()))
+            }
+            Some(_) => {
+                // invalid, delete cache entry
+                conn.execute(
+                    "
+                    DELETE FROM role_hypixel_guild_cache
+                    WHERE guild_id=?1
+                    ",
+                    params![self.guild_id],
+                )?;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// How long Mojang's username of a UUID is served from the cache, in seconds. Usernames can only be
+/// changed every 30 days, so a renamed player is picked up within a day.
+pub const MOJANG_PROFILE_CACHE_SECS: i64 = 24 * 60 * 60;
+
+pub struct CachedMojangUuid {
+    pub username: String,
+}
+impl DbRequest for CachedMojangUuid {
+    type ReturnValue = Result<Option<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        // expired entries are removed by `PruneCaches`
+        conn.query_one(
+            "
+            SELECT uuid
+            FROM role_mojang_profile_cache
+            WHERE username=?1 COLLATE NOCASE AND timestamp >= ?2
+            ",
+            params![
+                self.username,
+                chrono::Utc::now().timestamp() - MOJANG_PROFILE_CACHE_SECS
+            ],
+            |row| row.get("uuid"),
+        )
+        .optional()
+    }
+}
+
+pub struct CachedMojangUsername {
+    pub uuid: String,
+}
+impl DbRequest for CachedMojangUsername {
+    type ReturnValue = Result<Option<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT username
+            FROM role_mojang_profile_cache
+            WHERE uuid=?1 AND timestamp >= ?2
+            ",
+            params![
+                self.uuid,
+                chrono::Utc::now().timestamp() - MOJANG_PROFILE_CACHE_SECS
+            ],
+            |row| row.get("username"),
+        )
+        .optional()
+    }
+}
+
+/// Reads all cache entries of a player for inspection, without invalidating outdated ones
+pub struct InspectPlayerCache {
+    pub uuid: String,
+}
+impl DbRequest for InspectPlayerCache {
+    type ReturnValue = Result<PlayerCacheEntries>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let bingo_set = |table: &str| {
+            conn.query_one(
+                &format!("SELECT updated_after_bingo, bingo_set FROM {table} WHERE uuid=?1"),
+                params![self.uuid],
+                |row| {
+                    Ok((
+                        row.get("updated_after_bingo")?,
+                        BitSet::from_bytes(
+                            row.get::<_, Option<_>>("bingo_set")?.unwrap_or_default(),
+                        ),
+                    ))
+                },
+            )
+            .optional()
+        };
+        let completions = bingo_set("role_completions_cache")?;
+        let network_bingos = bingo_set("role_network_bingo_cache")?;
+
+        let bingo_rank = conn
+            .query_one(
+                "SELECT updated_after_bingo, rank FROM role_bingo_rank_cache WHERE uuid=?1",
+                params![self.uuid],
+                |row| Ok((row.get("updated_after_bingo")?, row.get("rank")?)),
+            )
+            .optional()?;
+        let immortal = conn
+            .query_one(
+                "SELECT updated_after_bingo, has_achieved FROM role_immortal_cache WHERE uuid=?1",
+                params![self.uuid],
+                |row| Ok((row.get("updated_after_bingo")?, row.get("has_achieved")?)),
+            )
+            .optional()?;
+        let player_endpoint = conn
+            .query_one(
+                "SELECT timestamp FROM role_player_endpoint_cache WHERE uuid=?1",
+                params![self.uuid],
+                |row| row.get("timestamp"),
+            )
+            .optional()?;
+
+        Ok(PlayerCacheEntries {
+            completions,
+            network_bingos,
+            bingo_rank,
+            immortal,
+            player_endpoint,
+        })
+    }
+}
+
+/// Used until a retention is configured with `/config cache-retention`
+pub const DEFAULT_DISCORD_INDEX_RETENTION_DAYS: u32 = 90;
+
+/// How many days Discord usernames found in player responses are kept for link suggestions
+pub struct GetCacheRetention;
+impl DbRequest for GetCacheRetention {
+    type ReturnValue = Result<u32>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let days = conn
+            .query_one(
+                "
+                SELECT discord_index_days
+                FROM role_cache_retention
+                WHERE id = 1
+                ",
+                [],
+                |row| row.get("discord_index_days"),
+            )
+            .optional()?;
+        Ok(days.unwrap_or(DEFAULT_DISCORD_INDEX_RETENTION_DAYS))
+    }
+}
+
+/// Number of linked players per blackout count, based on their cached completions
+pub struct GetBlackoutDistribution;
+impl DbRequest for GetBlackoutDistribution {
+    type ReturnValue = Result<BTreeMap<u32, u32>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT c.bingo_set
+            FROM role_completions_cache c
+            JOIN role_users_linked l ON c.uuid = l.minecraft_uuid
+            ",
+        )?;
+
+        let mut distribution = BTreeMap::new();
+        for bingo_set in
+            statement.query_map([], |row| row.get::<_, Option<Vec<u8>>>("bingo_set"))?
+        {
+            let blackouts = bingo_set?
+                .unwrap_or_default()
+                .iter()
+                .map(|byte| byte.count_ones())
+                .sum();
+            *distribution.entry(blackouts).or_default() += 1;
+        }
+        Ok(distribution)
+    }
+}
+
+/// Number of linked players per bingo rank, based on their cached rank
+pub struct GetBingoRankDistribution;
+impl DbRequest for GetBingoRankDistribution {
+    type ReturnValue = Result<BTreeMap<u8, u32>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT r.rank, COUNT(*) AS players
+            FROM role_bingo_rank_cache r
+            JOIN role_users_linked l ON r.uuid = l.minecraft_uuid
+            GROUP BY r.rank
+            ",
+        )?;
+        statement
+            .query_map([], |row| Ok((row.get("rank")?, row.get("players")?)))?
+            .collect()
+    }
+}
+
+/// Number of linked players holding the immortal role, based on their cached status
+pub struct CountImmortalHolders;
+impl DbRequest for CountImmortalHolders {
+    type ReturnValue = Result<u32>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT COUNT(*)
+            FROM role_immortal_cache i
+            JOIN role_users_linked l ON i.uuid = l.minecraft_uuid
+            WHERE i.has_achieved
+            ",
+            [],
+            |row| row.get(0),
+        )
+    }
+}
+
+/// Number of linked players who completed each network bingo, keyed by its ID
+pub struct GetNetworkBingoCompletionCounts;
+impl DbRequest for GetNetworkBingoCompletionCounts {
+    type ReturnValue = Result<BTreeMap<u8, u32>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT n.bingo_set
+            FROM role_network_bingo_cache n
+            JOIN role_users_linked l ON n.uuid = l.minecraft_uuid
+            ",
+        )?;
+
+        let mut counts = BTreeMap::new();
+        for bingo_set in
+            statement.query_map([], |row| row.get::<_, Option<Vec<u8>>>("bingo_set"))?
+        {
+            let bingo_set = BitSet::from_bytes(bingo_set?.unwrap_or_default());
+            for id in bingo_set.get_all_set() {
+                *counts.entry(id as u8).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+}
@@ -1,6 +1,10 @@
 use rusqlite::{Connection, Result, params};
 
 use crate::db::DbRequest;
+use crate::role::db::cache::{
+    GetCacheRetention, HYPIXEL_GUILD_CACHE_SECS, MOJANG_PROFILE_CACHE_SECS,
+    PLAYER_ENDPOINT_CACHE_SECS,
+};
 use crate::role::types::NetworkBingo;
 use crate::shared::{db::GetCurrentBingo, types::BitSet};
 
@@ -36,8 +40,7 @@ impl DbRequest for CacheNetworkBingos {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let current_network_bingo =
-            *NetworkBingo::ALL.last().unwrap_or(&NetworkBingo::Unknown) as u8;
+        let current_network_bingo = NetworkBingo::latest().id();
 
         conn.execute(
             "
@@ -101,13 +104,253 @@ impl DbRequest for CacheHypixelPlayerEndpoint {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        conn.execute(
+        let discord = serde_json::from_str::<serde_json::Value>(&self.json)
+            .ok()
+            .and_then(|json| {
+                json["player"]["socialMedia"]["links"]["DISCORD"]
+                    .as_str()
+                    .map(String::from)
+            });
+
+        let transaction = conn.savepoint()?;
+
+        transaction.execute(
             "
             INSERT OR REPLACE INTO role_player_endpoint_cache (uuid, timestamp, json)
             VALUES (?1, ?2, ?3)
             ",
             params![self.uuid, self.timestamp, self.json],
         )?;
+
+        // keep link suggestion index in sync with the latest response
+        match discord {
+            Some(discord) => transaction.execute(
+                "
+                INSERT OR REPLACE INTO role_player_discord_index (uuid, discord, timestamp)
+                VALUES (?1, ?2, ?3)
+                ",
+                params![self.uuid, discord, self.timestamp],
+            )?,
+            None => transaction.execute(
+                "DELETE FROM role_player_discord_index WHERE uuid=?1",
+                params![self.uuid],
+            )?,
+        };
+
+        transaction.commit()
+    }
+}
+
+pub struct CacheHypixelGuildMembers {
+    pub guild_id: String,
+    pub timestamp: i64,
+    pub members: Vec<String>,
+}
+impl DbRequest for CacheHypixelGuildMembers {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR REPLACE INTO role_hypixel_guild_cache (guild_id, timestamp, members)
+            VALUES (?1, ?2, ?3)
+            ",
+            params![self.guild_id, self.timestamp, self.members.join("\n")],
+        )?;
         Ok(())
     }
 }
+
+pub struct CacheMojangProfile {
+    pub uuid: String,
+    pub username: String,
+    pub timestamp: i64,
+}
+impl DbRequest for CacheMojangProfile {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        // the username was released by a previous owner
+        transaction.execute(
+            "DELETE FROM role_mojang_profile_cache WHERE username=?1 COLLATE NOCASE AND uuid!=?2",
+            params![self.username, self.uuid],
+        )?;
+        transaction.execute(
+            "
+            INSERT OR REPLACE INTO role_mojang_profile_cache (uuid, username, timestamp)
+            VALUES (?1, ?2, ?3)
+            ",
+            params![self.uuid, self.username, self.timestamp],
+        )?;
+
+        transaction.commit()
+    }
+}
+
+/// `None` resets to the default retention
+pub struct SetCacheRetention {
+    pub discord_index_days: Option<u32>,
+}
+impl DbRequest for SetCacheRetention {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.discord_index_days {
+            Some(days) => conn.execute(
+                "
+                INSERT OR REPLACE INTO role_cache_retention (id, discord_index_days)
+                VALUES (1, ?1)
+                ",
+                params![days],
+            )?,
+            None => conn.execute("DELETE FROM role_cache_retention", [])?,
+        };
+        Ok(())
+    }
+}
+
+/// Rows deleted by `PruneCaches`
+#[derive(Debug, Default)]
+pub struct PrunedCaches {
+    pub player_endpoint: usize,
+    pub hypixel_guild: usize,
+    pub completions: usize,
+    pub discord_index: usize,
+    pub mojang_profiles: usize,
+}
+
+impl PrunedCaches {
+    pub fn total(&self) -> usize {
+        self.player_endpoint
+            + self.hypixel_guild
+            + self.completions
+            + self.discord_index
+            + self.mojang_profiles
+    }
+}
+
+/// Deletes cache entries which would be discarded on their next read anyway, as well as Discord
+/// usernames older than the configured retention. Cache reads only clean up the entries they touch,
+/// so entries of players who are never requested again would otherwise stay around forever.
+pub struct PruneCaches;
+impl DbRequest for PruneCaches {
+    type ReturnValue = Result<PrunedCaches>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let now = chrono::Utc::now().timestamp();
+        let (current_bingo, _, _) = GetCurrentBingo.execute(conn)?.unwrap_or_default();
+        let current_network_bingo = NetworkBingo::latest().id();
+        let retention_days = GetCacheRetention.execute(conn)?;
+
+        let tx = conn.savepoint()?;
+        let mut pruned = PrunedCaches {
+            player_endpoint: tx.execute(
+                "DELETE FROM role_player_endpoint_cache WHERE timestamp < ?1",
+                params![now - PLAYER_ENDPOINT_CACHE_SECS],
+            )?,
+            hypixel_guild: tx.execute(
+                "DELETE FROM role_hypixel_guild_cache WHERE timestamp < ?1",
+                params![now - HYPIXEL_GUILD_CACHE_SECS],
+            )?,
+            completions: 0,
+            discord_index: tx.execute(
+                "DELETE FROM role_player_discord_index WHERE timestamp < ?1",
+                params![now - i64::from(retention_days) * 24 * 60 * 60],
+            )?,
+            mojang_profiles: tx.execute(
+                "DELETE FROM role_mojang_profile_cache WHERE timestamp < ?1",
+                params![now - MOJANG_PROFILE_CACHE_SECS],
+            )?,
+        };
+
+        for table in ["role_completions_cache", "role_bingo_rank_cache"] {
+            pruned.completions += tx.execute(
+                &format!("DELETE FROM {table} WHERE updated_after_bingo < ?1"),
+                params![current_bingo.get_id()],
+            )?;
+        }
+        // the immortal role is never revoked, so achieved entries stay valid
+        pruned.completions += tx.execute(
+            "
+            DELETE FROM role_immortal_cache
+            WHERE updated_after_bingo < ?1 AND NOT has_achieved
+            ",
+            params![current_bingo.get_id()],
+        )?;
+        pruned.completions += tx.execute(
+            "DELETE FROM role_network_bingo_cache WHERE updated_after_bingo < ?1",
+            params![current_network_bingo],
+        )?;
+
+        tx.commit()?;
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_connection;
+    use crate::role::db::cache::{CachedMojangUsername, CachedMojangUuid};
+
+    fn cache_profile(conn: &mut Connection, uuid: &str, username: &str, timestamp: i64) {
+        CacheMojangProfile {
+            uuid: uuid.to_string(),
+            username: username.to_string(),
+            timestamp,
+        }
+        .execute(conn)
+        .unwrap();
+    }
+
+    fn cached_uuid(conn: &mut Connection, username: &str) -> Option<String> {
+        CachedMojangUuid {
+            username: username.to_string(),
+        }
+        .execute(conn)
+        .unwrap()
+    }
+
+    fn cached_username(conn: &mut Connection, uuid: &str) -> Option<String> {
+        CachedMojangUsername {
+            uuid: uuid.to_string(),
+        }
+        .execute(conn)
+        .unwrap()
+    }
+
+    #[test]
+    fn mojang_profile_is_cached_in_both_directions() {
+        let mut conn = test_connection();
+        cache_profile(&mut conn, "uuid", "Player", chrono::Utc::now().timestamp());
+
+        assert_eq!(cached_uuid(&mut conn, "player").as_deref(), Some("uuid"));
+        assert_eq!(
+            cached_username(&mut conn, "uuid").as_deref(),
+            Some("Player")
+        );
+    }
+
+    #[test]
+    fn mojang_profile_of_released_username_is_replaced() {
+        let mut conn = test_connection();
+        let now = chrono::Utc::now().timestamp();
+        cache_profile(&mut conn, "old", "Player", now);
+        cache_profile(&mut conn, "new", "player", now);
+
+        assert_eq!(cached_uuid(&mut conn, "Player").as_deref(), Some("new"));
+        assert_eq!(cached_username(&mut conn, "old"), None);
+    }
+
+    #[test]
+    fn expired_mojang_profile_is_ignored() {
+        let mut conn = test_connection();
+        let expired = chrono::Utc::now().timestamp() - MOJANG_PROFILE_CACHE_SECS - 1;
+        cache_profile(&mut conn, "uuid", "Player", expired);
+
+        assert_eq!(cached_uuid(&mut conn, "Player"), None);
+        assert_eq!(cached_username(&mut conn, "uuid"), None);
+    }
+}
@@ -1,8 +1,10 @@
+use anyhow::anyhow;
 use poise::serenity_prelude::UserId;
 use rusqlite::{Connection, OptionalExtension as _, Result, params};
 
 use crate::db::DbRequest;
-use crate::role::types::LinkedUser;
+use crate::error::UserError;
+use crate::role::types::{BulkUpdateProgress, LinkedUser};
 
 pub struct GetLinkedUserByDiscord {
     pub discord: UserId,
@@ -46,7 +48,7 @@ impl DbRequest for InsertLinkedUser {
     type ReturnValue = Result<(Option<UserId>, Option<String>)>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
 
         let duplicate_uuid: Option<String> = {
             let mut statement = transaction
@@ -95,7 +97,7 @@ impl DbRequest for UpdateLinkedUser {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
 
         transaction.execute(
             "
@@ -162,3 +164,375 @@ impl DbRequest for RemoveLinkedUserByMinecraft {
             .optional()
     }
 }
+
+pub struct TransferLinkedUser {
+    pub mc_uuid: String,
+    pub from: UserId,
+    pub to: UserId,
+}
+impl DbRequest for TransferLinkedUser {
+    /// `false` if the Minecraft account is no longer linked to `from`
+    type ReturnValue = anyhow::Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        let target_linked: bool = transaction.query_one(
+            "SELECT EXISTS (SELECT 1 FROM role_users_linked WHERE discord_id=?1)",
+            params![self.to.get()],
+            |row| row.get(0),
+        )?;
+        if target_linked {
+            return Err(UserError(anyhow!(
+                "This Discord account is already linked to another Minecraft account, unlink it \
+                first"
+            ))
+            .into());
+        }
+
+        let moved = transaction.execute(
+            "
+            UPDATE role_users_linked SET discord_id=?3
+            WHERE discord_id=?1 AND minecraft_uuid=?2
+            ",
+            params![self.from.get(), self.mc_uuid, self.to.get()],
+        )?;
+        if moved == 0 {
+            return Ok(false);
+        }
+
+        // cached stats are keyed by UUID and therefore follow the link without changes
+        transaction.execute(
+            "UPDATE role_audit_log SET discord_id=?2 WHERE discord_id=?1",
+            params![self.from.get(), self.to.get()],
+        )?;
+
+        transaction.commit()?;
+
+        Ok(true)
+    }
+}
+
+pub struct CountLinkedUsers;
+impl DbRequest for CountLinkedUsers {
+    type ReturnValue = Result<u32>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one("SELECT COUNT(*) FROM role_users_linked", [], |row| {
+            row.get(0)
+        })
+    }
+}
+
+pub struct GetLinkedUsersChunk {
+    /// only users with a greater discord ID are returned
+    pub after: Option<UserId>,
+    pub limit: u32,
+}
+impl DbRequest for GetLinkedUsersChunk {
+    /// ordered by discord ID
+    type ReturnValue = Result<Vec<LinkedUser>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT discord_id, minecraft_uuid FROM role_users_linked
+            WHERE discord_id > ?1
+            ORDER BY discord_id
+            LIMIT ?2
+            ",
+        )?;
+
+        statement
+            .query_map(
+                params![self.after.map(|id| id.get()).unwrap_or(0), self.limit],
+                |row| {
+                    Ok(LinkedUser::new(
+                        UserId::new(row.get("discord_id")?),
+                        row.get("minecraft_uuid")?,
+                    ))
+                },
+            )?
+            .collect()
+    }
+}
+
+pub struct GetBulkUpdateProgress;
+impl DbRequest for GetBulkUpdateProgress {
+    type ReturnValue = Result<Option<BulkUpdateProgress>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT last_discord_id, updated, unchanged, skipped, failed
+            FROM role_bulk_update_progress WHERE id=1
+            ",
+            [],
+            |row| {
+                Ok(BulkUpdateProgress {
+                    last_user: Some(UserId::new(row.get("last_discord_id")?)),
+                    updated: row.get("updated")?,
+                    unchanged: row.get("unchanged")?,
+                    skipped: row.get("skipped")?,
+                    failed: row.get("failed")?,
+                })
+            },
+        )
+        .optional()
+    }
+}
+
+pub struct SetBulkUpdateProgress {
+    /// clears the progress if `None`, e.g. once the update has completed
+    pub progress: Option<BulkUpdateProgress>,
+}
+impl DbRequest for SetBulkUpdateProgress {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.progress {
+            // nothing to resume from if nobody has been processed yet
+            Some(BulkUpdateProgress {
+                last_user: Some(last_user),
+                updated,
+                unchanged,
+                skipped,
+                failed,
+            }) => conn.execute(
+                "
+                INSERT INTO role_bulk_update_progress
+                    (id, last_discord_id, updated, unchanged, skipped, failed)
+                VALUES (1, ?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(id) DO UPDATE SET
+                    last_discord_id = excluded.last_discord_id,
+                    updated = excluded.updated,
+                    unchanged = excluded.unchanged,
+                    skipped = excluded.skipped,
+                    failed = excluded.failed
+                ",
+                params![last_user.get(), updated, unchanged, skipped, failed],
+            )?,
+            _ => conn.execute("DELETE FROM role_bulk_update_progress WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
+
+pub struct GetLinkedUsersByDiscord {
+    pub discord: Vec<UserId>,
+}
+impl DbRequest for GetLinkedUsersByDiscord {
+    /// users without a link are omitted
+    type ReturnValue = Result<Vec<LinkedUser>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement =
+            conn.prepare("SELECT minecraft_uuid FROM role_users_linked WHERE discord_id=?1")?;
+
+        let mut linked_users = Vec::new();
+        for discord in self.discord {
+            if let Some(uuid) = statement
+                .query_one(params![discord.get()], |row| row.get("minecraft_uuid"))
+                .optional()?
+            {
+                linked_users.push(LinkedUser::new(discord, uuid));
+            }
+        }
+
+        Ok(linked_users)
+    }
+}
+
+pub struct RemoveLinkedUsers {
+    pub discord: Vec<UserId>,
+}
+impl DbRequest for RemoveLinkedUsers {
+    /// removed links, users without a link are omitted
+    type ReturnValue = Result<Vec<LinkedUser>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        // all or nothing, so that a failure can't leave the cleanup half done
+        let transaction = conn.savepoint()?;
+
+        let mut removed = Vec::new();
+        {
+            let mut statement = transaction.prepare(
+                "
+                DELETE FROM role_users_linked
+                WHERE discord_id=?1
+                RETURNING minecraft_uuid
+                ",
+            )?;
+
+            for discord in self.discord {
+                if let Some(uuid) = statement
+                    .query_one(params![discord.get()], |row| row.get("minecraft_uuid"))
+                    .optional()?
+                {
+                    removed.push(LinkedUser::new(discord, uuid));
+                }
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(removed)
+    }
+}
+
+pub struct GetAllLinkedUsers;
+impl DbRequest for GetAllLinkedUsers {
+    /// ordered by discord ID
+    type ReturnValue = Result<Vec<LinkedUser>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "SELECT discord_id, minecraft_uuid FROM role_users_linked ORDER BY discord_id",
+        )?;
+
+        statement
+            .query_map([], |row| {
+                Ok(LinkedUser::new(
+                    UserId::new(row.get("discord_id")?),
+                    row.get("minecraft_uuid")?,
+                ))
+            })?
+            .collect()
+    }
+}
+
+pub struct ImportLinkedUsers {
+    pub users: Vec<LinkedUser>,
+}
+impl DbRequest for ImportLinkedUsers {
+    /// number of imported links, links conflicting with existing ones are skipped
+    type ReturnValue = Result<usize>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        let mut imported = 0;
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT OR IGNORE INTO role_users_linked (discord_id, minecraft_uuid)
+                VALUES (?1, ?2)
+                ",
+            )?;
+
+            for user in self.users {
+                imported += statement.execute(params![user.discord.get(), user.mc_uuid])?;
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(imported)
+    }
+}
+
+pub struct GetLinksDueForVerification {
+    /// links checked at or after this timestamp are skipped
+    pub checked_before: i64,
+    /// links whose failed check is retried after this timestamp are skipped
+    pub now: i64,
+    pub limit: u32,
+}
+impl DbRequest for GetLinksDueForVerification {
+    /// never checked links first, then the ones checked longest ago
+    type ReturnValue = Result<Vec<LinkedUser>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT discord_id, minecraft_uuid FROM role_users_linked
+            WHERE (last_checked IS NULL OR last_checked < ?1)
+                AND (retry_after IS NULL OR retry_after <= ?2)
+            ORDER BY last_checked NULLS FIRST, discord_id
+            LIMIT ?3
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.checked_before, self.now, self.limit], |row| {
+                Ok(LinkedUser::new(
+                    UserId::new(row.get("discord_id")?),
+                    row.get("minecraft_uuid")?,
+                ))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetLinkLastVerified {
+    pub discord: UserId,
+}
+impl DbRequest for GetLinkLastVerified {
+    /// `None` if the link was never verified, or doesn't exist
+    type ReturnValue = Result<Option<i64>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT last_verified FROM role_users_linked WHERE discord_id=?1",
+            params![self.discord.get()],
+            |row| row.get("last_verified"),
+        )
+        .optional()
+        .map(Option::flatten)
+    }
+}
+
+pub struct SetLinkChecked {
+    pub discord: UserId,
+    pub at: i64,
+    /// whether the link still matches Hypixel's Discord setting
+    pub verified: bool,
+}
+impl DbRequest for SetLinkChecked {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            UPDATE role_users_linked
+            SET last_checked = ?2,
+                last_verified = CASE WHEN ?3 THEN ?2 ELSE last_verified END,
+                check_failures = 0,
+                retry_after = NULL
+            WHERE discord_id=?1
+            ",
+            params![self.discord.get(), self.at, self.verified],
+        )?;
+        Ok(())
+    }
+}
+
+/// Postpones the check of a link that couldn't be fetched, without marking it as checked. The
+/// backoff doubles with every consecutive failure, up to `max_backoff_secs`.
+pub struct SetLinkCheckFailed {
+    pub discord: UserId,
+    pub at: i64,
+    pub backoff_secs: i64,
+    pub max_backoff_secs: i64,
+}
+impl DbRequest for SetLinkCheckFailed {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            UPDATE role_users_linked
+            SET retry_after = ?2 + MIN(?3 << MIN(check_failures, 16), ?4),
+                check_failures = check_failures + 1
+            WHERE discord_id=?1
+            ",
+            params![
+                self.discord.get(),
+                self.at,
+                self.backoff_secs,
+                self.max_backoff_secs
+            ],
+        )?;
+        Ok(())
+    }
+}
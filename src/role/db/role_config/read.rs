@@ -1,12 +1,15 @@
-use std::sync::Arc;
+use std::collections::HashMap;
 
-use poise::serenity_prelude::{RoleId, small_fixed_array::FixedArray};
-use rusqlite::{Connection, OptionalExtension as _, Result, params};
+use poise::serenity_prelude::{Colour, GenericChannelId, RoleId};
+use rusqlite::{Connection, OptionalExtension as _, Result};
 
 use crate::db::DbRequest;
-use crate::role::types::{
-    BingoRole, NetworkBingo, RoleDelta, RoleMapping, RoleMappingKind, RoleMappingKindRaw,
-    RolePatterns,
+use crate::role::{
+    mapping_cache::RoleMappingSnapshot,
+    types::{
+        HypixelGuildMapping, LinkVerificationConfig, NetworkBingo, RejoinRestoreConfig,
+        RoleAutoCreateConfig, RoleMapping, RoleMappingKind, RoleMappingKindRaw, RolePatterns,
+    },
 };
 use crate::shared::types::{Bingo, BingoKind};
 
@@ -97,268 +100,121 @@ impl DbRequest for GetRoleMappingsByKind {
     }
 }
 
-pub struct GetRoleMapping {
-    pub kind: RoleMappingKind,
-}
-impl DbRequest for GetRoleMapping {
-    type ReturnValue = Result<Option<RoleMapping>>;
+/// Number of configured role mappings of every kind
+pub struct GetRoleMappingCounts;
+impl DbRequest for GetRoleMappingCounts {
+    type ReturnValue = Result<HashMap<RoleMappingKindRaw, usize>>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        match self.kind {
-            RoleMappingKind::Completions { count } => conn
-                .query_one(
-                    "SELECT role FROM role_completions_config WHERE count=?1",
-                    params![count],
-                    |row| Ok(RoleId::new(row.get("role")?)),
-                )
-                .optional(),
-            RoleMappingKind::SpecificCompletion { bingo } => conn
-                .query_one(
-                    "SELECT role FROM role_specific_completion_config
-WHERE bingo_kind=?1 AND kind_specific_id=?2",
-                    params![bingo.kind as u8, bingo.kind_specific_id],
-                    |row| Ok(RoleId::new(row.get("role")?)),
-                )
-                .optional(),
-            RoleMappingKind::BingoRank { rank } => conn
-                .query_one(
-                    "SELECT role FROM role_bingo_rank_config WHERE rank=?1",
-                    params![rank],
-                    |row| Ok(RoleId::new(row.get("role")?)),
-                )
-                .optional(),
-            RoleMappingKind::NetworkBingo { bingo } => conn
-                .query_one(
-                    "SELECT role FROM role_network_bingo_config WHERE id=?1",
-                    [bingo as u8],
-                    |row| Ok(RoleId::new(row.get("role")?)),
-                )
-                .optional(),
-            RoleMappingKind::Immortal => conn
-                .query_one(
-                    "SELECT immortal_role FROM role_config_global WHERE id=1",
-                    [],
-                    |row| Ok(row.get("immortal_role").ok().map(RoleId::new)),
-                )
-                .optional()
-                .map(Option::flatten),
-        }
-        .map(|opt| opt.map(|role| RoleMapping::new(self.kind, role)))
+        RoleMappingKindRaw::ALL
+            .iter()
+            .map(|&kind| Ok((kind, GetRoleMappingsByKind { kind }.execute(conn)?.len())))
+            .collect()
     }
 }
 
-pub struct BuildRoleDeltaCompletions {
-    pub bingos: Vec<Bingo>,
-    pub user_roles: Arc<FixedArray<RoleId>>,
-}
-impl DbRequest for BuildRoleDeltaCompletions {
-    type ReturnValue = Result<RoleDelta>;
+/// Everything [`RoleMappingSnapshot`] holds, loaded at once for the mapping cache
+pub struct GetRoleMappingSnapshot;
+impl DbRequest for GetRoleMappingSnapshot {
+    type ReturnValue = Result<RoleMappingSnapshot>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let completion_count = self.bingos.len();
-
-        let completions_template = GetRolePatterns.execute(conn)?.completions;
-
-        let completions_role = GetRoleMapping {
-            kind: RoleMappingKind::Completions {
-                count: completion_count,
-            },
-        }
-        .execute(conn)?;
-
-        let completions_role = match completions_role {
-            Some(role_mapping) => Some(BingoRole::Id(role_mapping.role)),
-            None => {
-                if completion_count == 0 {
-                    None
-                } else {
-                    completions_template.map(|t| BingoRole::Name {
-                        name: complete_completion_template(&t, completion_count),
-                        kind: RoleMappingKind::Completions {
-                            count: completion_count,
-                        },
-                    })
-                }
-            }
-        };
-
-        let specific_template = GetRolePatterns.execute(conn)?.specific_completion;
-
-        let roles = self
-            .bingos
-            .into_iter()
-            .filter(|b| b.kind != BingoKind::Normal || b.kind_specific_id == 0)
-            .map(|b| {
-                let role = GetRoleMapping {
-                    kind: RoleMappingKind::SpecificCompletion { bingo: b },
-                }
-                .execute(conn)?;
-
-                let bingo_role = match role {
-                    Some(role_mapping) => Some(BingoRole::Id(role_mapping.role)),
-                    None => specific_template.as_ref().map(|t| BingoRole::Name {
-                        name: complete_bingo_template(t, &b),
-                        kind: RoleMappingKind::SpecificCompletion { bingo: b },
-                    }),
-                };
-
-                Ok(bingo_role)
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        // drop all roles, for which there is neither an ID nor a template
-        let mut roles: Vec<BingoRole> = roles.into_iter().flatten().collect();
-
-        if let Some(role) = completions_role {
-            roles.push(role);
+        let mut mappings = Vec::new();
+        for &kind in RoleMappingKindRaw::ALL {
+            mappings.extend(GetRoleMappingsByKind { kind }.execute(conn)?);
         }
 
-        let mut statement = conn.prepare(
-            "
-            SELECT role FROM role_completions_config
-            UNION ALL
-            SELECT role FROM role_specific_completion_config
-            ",
-        )?;
-
-        let known_roles = statement
-            .query_map([], |row| Ok(RoleId::new(row.get("role")?)))?
-            .collect::<Result<Vec<_>>>()?;
-
-        generate_role_delta(known_roles, self.user_roles, roles)
+        Ok(RoleMappingSnapshot {
+            mappings,
+            patterns: GetRolePatterns.execute(conn)?,
+            hypixel_guild: GetHypixelGuildMapping.execute(conn)?,
+        })
     }
 }
 
-pub struct BuildRoleDeltaBingoRank {
-    pub rank: u8,
-    pub user_roles: Arc<FixedArray<RoleId>>,
-}
-impl DbRequest for BuildRoleDeltaBingoRank {
-    type ReturnValue = Result<RoleDelta>;
+pub struct GetHypixelGuildMapping;
+impl DbRequest for GetHypixelGuildMapping {
+    type ReturnValue = Result<Option<HypixelGuildMapping>>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let role = GetRoleMapping {
-            kind: RoleMappingKind::BingoRank { rank: self.rank },
-        }
-        .execute(conn)?;
-
-        let roles = match role {
-            Some(role_mapping) => vec![BingoRole::Id(role_mapping.role)],
-            None => {
-                let template = GetRolePatterns.execute(conn)?.bingo_rank;
-
-                template.map_or_else(Vec::new, |t| {
-                    vec![BingoRole::Name {
-                        name: complete_bingo_rank_template(&t, self.rank),
-                        kind: RoleMappingKind::BingoRank { rank: self.rank },
-                    }]
+        conn.query_one(
+            "SELECT guild_id, guild_name, role FROM role_hypixel_guild_config WHERE id=1",
+            [],
+            |row| {
+                Ok(HypixelGuildMapping {
+                    guild_id: row.get("guild_id")?,
+                    guild_name: row.get("guild_name")?,
+                    role: RoleId::new(row.get("role")?),
                 })
-            }
-        };
-
-        let mut statement = conn.prepare("SELECT role FROM role_bingo_rank_config")?;
-
-        let known_roles = statement
-            .query_map([], |row| Ok(RoleId::new(row.get("role")?)))?
-            .collect::<Result<Vec<_>>>()?;
-
-        generate_role_delta(known_roles, self.user_roles, roles)
+            },
+        )
+        .optional()
     }
 }
 
-pub struct BuildRoleDeltaImmortal {
-    pub user_roles: Arc<FixedArray<RoleId>>,
-    pub has_achieved: bool,
-}
-impl DbRequest for BuildRoleDeltaImmortal {
-    type ReturnValue = Result<RoleDelta>;
+pub struct GetRejoinRestoreConfig;
+impl DbRequest for GetRejoinRestoreConfig {
+    /// `None` if roles aren't restored on rejoin
+    type ReturnValue = Result<Option<RejoinRestoreConfig>>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let role = GetRoleMapping {
-            kind: RoleMappingKind::Immortal,
-        }
-        .execute(conn)?;
-
-        if let Some(role_mapping) = role
-            && self.has_achieved
-            && !self.user_roles.contains(&role_mapping.role)
-        {
-            Ok(RoleDelta {
-                add: vec![BingoRole::Id(role_mapping.role)],
-                remove: Vec::new(),
-            })
-        } else {
-            Ok(RoleDelta {
-                add: Vec::new(),
-                remove: Vec::new(),
-            })
-        }
+        conn.query_one(
+            "SELECT delay_secs, log_channel FROM role_rejoin_config WHERE id=1",
+            [],
+            |row| {
+                Ok(RejoinRestoreConfig {
+                    delay_secs: row.get("delay_secs")?,
+                    log_channel: row
+                        .get::<_, Option<u64>>("log_channel")?
+                        .map(GenericChannelId::new),
+                })
+            },
+        )
+        .optional()
     }
 }
 
-pub struct BuildRoleDeltaNetworkBingos {
-    pub bingos: Vec<NetworkBingo>,
-    pub user_roles: Arc<FixedArray<RoleId>>,
-}
-impl DbRequest for BuildRoleDeltaNetworkBingos {
-    type ReturnValue = Result<RoleDelta>;
+pub struct GetLinkVerificationConfig;
+impl DbRequest for GetLinkVerificationConfig {
+    /// `None` if links aren't re-verified
+    type ReturnValue = Result<Option<LinkVerificationConfig>>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let roles: Vec<RoleMapping> = self
-            .bingos
-            .iter()
-            .filter_map(|n| {
-                GetRoleMapping {
-                    kind: RoleMappingKind::NetworkBingo { bingo: *n },
-                }
-                .execute(conn)
-                .transpose()
-            })
-            .collect::<Result<_>>()?;
-
-        let roles = roles.into_iter().map(|r| BingoRole::Id(r.role)).collect();
-
-        let mut statement = conn.prepare("SELECT role FROM role_network_bingo_config")?;
-
-        let known_roles = statement
-            .query_map([], |row| Ok(RoleId::new(row.get("role")?)))?
-            .collect::<Result<Vec<RoleId>>>()?;
-
-        generate_role_delta(known_roles, self.user_roles, roles)
+        conn.query_one(
+            "
+            SELECT channel, auto_unlink, interval_days
+            FROM role_link_verification_config WHERE id=1
+            ",
+            [],
+            |row| {
+                Ok(LinkVerificationConfig {
+                    channel: GenericChannelId::new(row.get("channel")?),
+                    auto_unlink: row.get("auto_unlink")?,
+                    interval_days: row.get("interval_days")?,
+                })
+            },
+        )
+        .optional()
     }
 }
 
-fn generate_role_delta(
-    known_roles: Vec<RoleId>,
-    user_has: Arc<FixedArray<RoleId>>,
-    add: Vec<BingoRole>,
-) -> Result<RoleDelta> {
-    let add_ids: Vec<&RoleId> = add
-        .iter()
-        .filter_map(|role| match role {
-            BingoRole::Id(id) => Some(id),
-            _ => None,
-        })
-        .collect();
-
-    let remove = known_roles
-        .into_iter()
-        .filter(|role| user_has.contains(role) && !add_ids.contains(&role))
-        .collect();
-
-    let add_filtered: Vec<BingoRole> = add
-        .into_iter()
-        .filter(|role| {
-            !matches!(role,
-                BingoRole::Id(id) if user_has.contains(id),
-            )
-        })
-        .collect();
+pub struct GetRoleAutoCreateConfig;
+impl DbRequest for GetRoleAutoCreateConfig {
+    /// `None` if roles aren't created automatically
+    type ReturnValue = Result<Option<RoleAutoCreateConfig>>;
 
-    Ok(RoleDelta {
-        add: add_filtered,
-        remove,
-    })
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT colour FROM role_auto_create_config WHERE id=1",
+            [],
+            |row| {
+                Ok(RoleAutoCreateConfig {
+                    colour: row.get::<_, Option<u32>>("colour")?.map(Colour::new),
+                })
+            },
+        )
+        .optional()
+    }
 }
 
 pub struct GetRolePatterns;
@@ -1,10 +1,16 @@
+use std::{collections::HashMap, sync::Arc};
+
 use poise::serenity_prelude::{Role, RoleId};
 use rusqlite::{Connection, Result, params};
 
 use crate::db::DbRequest;
 use crate::role::{
     db::role_config::read,
-    types::{RoleMapping, RoleMappingKind, RolePatterns},
+    mapping_cache,
+    types::{
+        HypixelGuildMapping, LinkVerificationConfig, RejoinRestoreConfig, RoleAutoCreateConfig,
+        RoleMapping, RoleMappingKind, RoleMappingKindRaw, RolePatterns,
+    },
 };
 use crate::shared::types::{Bingo, BingoKind};
 
@@ -15,7 +21,9 @@ impl DbRequest for InsertRoleMapping {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        insert_role_mapping(conn, &self.role_mapping)
+        insert_role_mapping(conn, &self.role_mapping)?;
+        mapping_cache::invalidate();
+        Ok(())
     }
 }
 
@@ -61,7 +69,7 @@ fn insert_role_mapping(conn: &Connection, role_mapping: &RoleMapping) -> Result<
             INSERT OR REPLACE INTO role_network_bingo_config (role, id)
             VALUES (?1, ?2)
             ",
-            params![role_mapping.role.get(), bingo as u8],
+            params![role_mapping.role.get(), bingo.id()],
         ),
     }?;
     Ok(())
@@ -74,7 +82,7 @@ impl DbRequest for DeleteRoleMappingByRole {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
 
         for table in [
             "role_completions_config",
@@ -97,83 +105,134 @@ impl DbRequest for DeleteRoleMappingByRole {
             params![self.role.get()],
         )?;
 
-        transaction.commit()
+        transaction.commit()?;
+        mapping_cache::invalidate();
+        Ok(())
     }
 }
 
-pub struct DetectRelevantRoles {
-    pub roles: Vec<Role>,
+/// Deletes the bindings of the specified roles within a single category, returning how many were
+/// deleted. Bindings of the same roles in other categories are kept.
+pub struct DeleteRoleMappingsOfKind {
+    pub kind: RoleMappingKindRaw,
+    pub roles: Vec<RoleId>,
 }
-impl DbRequest for DetectRelevantRoles {
-    type ReturnValue = Result<Vec<RoleMapping>>;
+impl DbRequest for DeleteRoleMappingsOfKind {
+    type ReturnValue = Result<usize>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let patterns = read::GetRolePatterns.execute(conn)?;
+        let transaction = conn.savepoint()?;
 
-        let mut detected_roles: Vec<RoleMapping> = Vec::new();
+        let mut deleted = 0;
+        for role in self.roles {
+            deleted += match kind_table(self.kind) {
+                Some(table) => transaction.execute(
+                    &format!("DELETE FROM {table} WHERE role=?1"),
+                    params![role.get()],
+                )?,
+                None => transaction.execute(
+                    "
+                    UPDATE role_config_global
+                    SET immortal_role=NULL
+                    WHERE id=1 AND immortal_role=?1
+                    ",
+                    params![role.get()],
+                )?,
+            };
+        }
 
-        if let Some(completion_template) = patterns.completions {
-            let mut statement = conn.prepare(
+        transaction.commit()?;
+        mapping_cache::invalidate();
+        Ok(deleted)
+    }
+}
+
+/// Deletes all bindings of a category, returning how many were deleted
+pub struct ClearRoleMappingKind {
+    pub kind: RoleMappingKindRaw,
+}
+impl DbRequest for ClearRoleMappingKind {
+    type ReturnValue = Result<usize>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let deleted = match kind_table(self.kind) {
+            Some(table) => conn.execute(&format!("DELETE FROM {table}"), [])?,
+            None => conn.execute(
                 "
-                SELECT 1 FROM role_completions_config
-                WHERE count=?1
+                UPDATE role_config_global
+                SET immortal_role=NULL
+                WHERE id=1 AND immortal_role IS NOT NULL
                 ",
-            )?;
+                [],
+            )?,
+        };
 
-            let mut not_found_contiguous = 0;
-            let mut count = 1;
-            while not_found_contiguous < 3 {
-                if statement.exists(params![count])? {
-                    count += 1;
-                    continue;
-                }
+        mapping_cache::invalidate();
+        Ok(deleted)
+    }
+}
 
-                let role = find_pattern_role(
-                    &read::complete_completion_template(&completion_template, count),
-                    &self.roles,
-                );
+/// The table holding a category's bindings, `None` for the immortal role stored in the global config
+fn kind_table(kind: RoleMappingKindRaw) -> Option<&'static str> {
+    match kind {
+        RoleMappingKindRaw::Completions => Some("role_completions_config"),
+        RoleMappingKindRaw::SpecificCompletion => Some("role_specific_completion_config"),
+        RoleMappingKindRaw::BingoRank => Some("role_bingo_rank_config"),
+        RoleMappingKindRaw::NetworkBingo => Some("role_network_bingo_config"),
+        RoleMappingKindRaw::Immortal => None,
+    }
+}
 
-                if let Some(id) = role {
-                    let role_mapping = RoleMapping {
-                        kind: RoleMappingKind::Completions { count },
-                        role: id,
-                    };
-                    insert_role_mapping(conn, &role_mapping)?;
-                    detected_roles.push(role_mapping);
-                    not_found_contiguous = 0;
-                } else {
-                    not_found_contiguous += 1;
-                }
-                count += 1;
-            }
+/// Detects roles of a single category, so that progress can be reported between categories in
+/// guilds with many roles
+pub struct DetectRelevantRoles {
+    pub roles: Arc<[Role]>,
+    pub category: RoleMappingKindRaw,
+}
+impl DbRequest for DetectRelevantRoles {
+    type ReturnValue = Result<Vec<RoleMapping>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let patterns = read::GetRolePatterns.execute(conn)?;
+
+        // the first role wins for duplicate names, like a linear search would
+        let mut roles_by_name: HashMap<&str, RoleId> = HashMap::new();
+        for role in self.roles.iter() {
+            roles_by_name.entry(role.name.as_str()).or_insert(role.id);
         }
 
-        if let Some(specific_completion_template) = patterns.specific_completion {
-            let mut statement = conn.prepare(
-                "
-                SELECT 1 FROM role_specific_completion_config
-                WHERE kind_specific_id=?1 AND bingo_kind=?2
-                ",
-            )?;
+        let mut detected_roles: Vec<RoleMapping> = Vec::new();
+
+        match self.category {
+            RoleMappingKindRaw::Completions => {
+                let Some(completion_template) = patterns.completions else {
+                    return Ok(detected_roles);
+                };
+                let mut statement = conn.prepare(
+                    "
+                    SELECT 1 FROM role_completions_config
+                    WHERE count=?1
+                    ",
+                )?;
 
-            for &kind in BingoKind::ALL {
                 let mut not_found_contiguous = 0;
-                let mut bingo_id = 0;
+                let mut count = 1;
                 while not_found_contiguous < 3 {
-                    if statement.exists(params![bingo_id, kind as u8])? {
-                        bingo_id += 1;
+                    if statement.exists(params![count])? {
+                        count += 1;
                         continue;
                     }
 
-                    let bingo = Bingo::new(bingo_id, kind, None);
-                    let role = find_pattern_role(
-                        &read::complete_bingo_template(&specific_completion_template, &bingo),
-                        &self.roles,
-                    );
+                    let role = roles_by_name
+                        .get(
+                            read::complete_completion_template(&completion_template, count)
+                                .as_str(),
+                        )
+                        .copied();
 
                     if let Some(id) = role {
                         let role_mapping = RoleMapping {
-                            kind: RoleMappingKind::SpecificCompletion { bingo },
+                            kind: RoleMappingKind::Completions { count },
                             role: id,
                         };
                         insert_role_mapping(conn, &role_mapping)?;
@@ -182,58 +241,108 @@ impl DbRequest for DetectRelevantRoles {
                     } else {
                         not_found_contiguous += 1;
                     }
-                    bingo_id += 1;
+                    count += 1;
                 }
             }
-        }
+            RoleMappingKindRaw::SpecificCompletion => {
+                let Some(specific_completion_template) = patterns.specific_completion else {
+                    return Ok(detected_roles);
+                };
+                let mut statement = conn.prepare(
+                    "
+                    SELECT 1 FROM role_specific_completion_config
+                    WHERE kind_specific_id=?1 AND bingo_kind=?2
+                    ",
+                )?;
 
-        if let Some(bingo_rank_template) = patterns.bingo_rank {
-            let mut statement = conn.prepare(
-                "
-                SELECT 1 FROM role_bingo_rank_config
-                WHERE rank=?1
-                ",
-            )?;
+                for &kind in BingoKind::ALL {
+                    let mut not_found_contiguous = 0;
+                    let mut bingo_id = 0;
+                    while not_found_contiguous < 3 {
+                        if statement.exists(params![bingo_id, kind as u8])? {
+                            bingo_id += 1;
+                            continue;
+                        }
 
-            let mut not_found_contiguous = 0;
-            let mut rank = 1;
-            while not_found_contiguous < 3 {
-                if statement.exists(params![rank])? {
-                    rank += 1;
-                    continue;
+                        let bingo = Bingo::new(bingo_id, kind, None);
+                        let role = roles_by_name
+                            .get(
+                                read::complete_bingo_template(
+                                    &specific_completion_template,
+                                    &bingo,
+                                )
+                                .as_str(),
+                            )
+                            .copied();
+
+                        if let Some(id) = role {
+                            let role_mapping = RoleMapping {
+                                kind: RoleMappingKind::SpecificCompletion { bingo },
+                                role: id,
+                            };
+                            insert_role_mapping(conn, &role_mapping)?;
+                            detected_roles.push(role_mapping);
+                            not_found_contiguous = 0;
+                        } else {
+                            not_found_contiguous += 1;
+                        }
+                        bingo_id += 1;
+                    }
                 }
+            }
+            RoleMappingKindRaw::BingoRank => {
+                let Some(bingo_rank_template) = patterns.bingo_rank else {
+                    return Ok(detected_roles);
+                };
+                let mut statement = conn.prepare(
+                    "
+                    SELECT 1 FROM role_bingo_rank_config
+                    WHERE rank=?1
+                    ",
+                )?;
+
+                let mut not_found_contiguous = 0;
+                let mut rank = 1;
+                while not_found_contiguous < 3 {
+                    if statement.exists(params![rank])? {
+                        rank += 1;
+                        continue;
+                    }
 
-                let role = find_pattern_role(
-                    &read::complete_bingo_rank_template(&bingo_rank_template, rank),
-                    &self.roles,
-                );
+                    let role = roles_by_name
+                        .get(
+                            read::complete_bingo_rank_template(&bingo_rank_template, rank).as_str(),
+                        )
+                        .copied();
 
-                if let Some(id) = role {
-                    let role_mapping = RoleMapping {
-                        kind: RoleMappingKind::BingoRank { rank },
-                        role: id,
-                    };
-                    insert_role_mapping(conn, &role_mapping)?;
-                    detected_roles.push(role_mapping);
-                    not_found_contiguous = 0;
-                } else {
-                    not_found_contiguous += 1;
+                    if let Some(id) = role {
+                        let role_mapping = RoleMapping {
+                            kind: RoleMappingKind::BingoRank { rank },
+                            role: id,
+                        };
+                        insert_role_mapping(conn, &role_mapping)?;
+                        detected_roles.push(role_mapping);
+                        not_found_contiguous = 0;
+                    } else {
+                        not_found_contiguous += 1;
+                    }
+                    rank += 1;
                 }
-                rank += 1;
             }
-        }
+            RoleMappingKindRaw::Immortal => {
+                let Some(immortal_template) = patterns.immortal else {
+                    return Ok(detected_roles);
+                };
+                let mut statement = conn.prepare(
+                    "
+                    SELECT 1 FROM role_config_global
+                    WHERE id=1 AND immortal_role NOT NULL
+                    ",
+                )?;
 
-        if let Some(immortal_template) = patterns.immortal {
-            let mut statement = conn.prepare(
-                "
-                SELECT 1 FROM role_config_global
-                WHERE id=1 AND immortal_role NOT NULL
-                ",
-            )?;
-
-            if !statement.exists([])? {
-                let role = find_pattern_role(&immortal_template, &self.roles);
-                if let Some(id) = role {
+                if !statement.exists([])?
+                    && let Some(&id) = roles_by_name.get(immortal_template.as_str())
+                {
                     let role_mapping = RoleMapping {
                         kind: RoleMappingKind::Immortal,
                         role: id,
@@ -242,18 +351,17 @@ impl DbRequest for DetectRelevantRoles {
                     detected_roles.push(role_mapping);
                 }
             }
+            // names of Network Bingo roles are too inconsistent for automatic detection
+            RoleMappingKindRaw::NetworkBingo => (),
         }
 
+        if !detected_roles.is_empty() {
+            mapping_cache::invalidate();
+        }
         Ok(detected_roles)
     }
 }
 
-fn find_pattern_role(pattern: &str, roles: &[Role]) -> Option<RoleId> {
-    roles
-        .iter()
-        .find_map(|r| (r.name == pattern).then_some(r.id))
-}
-
 pub struct SetRolePatterns {
     pub patterns: RolePatterns,
 }
@@ -261,25 +369,162 @@ impl DbRequest for SetRolePatterns {
     type ReturnValue = Result<()>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        conn.execute(
-            "
-            INSERT INTO role_config_global
-            (id, completion_pattern, special_completion_pattern,
-                bingo_rank_pattern, immortal_pattern)
-            VALUES (1, ?1, ?2, ?3, ?4)
-            ON CONFLICT(id) DO UPDATE SET
-                completion_pattern = excluded.completion_pattern,
-                special_completion_pattern = excluded.special_completion_pattern,
-                bingo_rank_pattern = excluded.bingo_rank_pattern,
-                immortal_pattern = excluded.immortal_pattern
-            ",
-            params![
-                self.patterns.completions,
-                self.patterns.specific_completion,
-                self.patterns.bingo_rank,
-                self.patterns.immortal,
-            ],
-        )?;
+        set_role_patterns(conn, &self.patterns)?;
+        mapping_cache::invalidate();
+        Ok(())
+    }
+}
+
+fn set_role_patterns(conn: &Connection, patterns: &RolePatterns) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO role_config_global
+        (id, completion_pattern, special_completion_pattern,
+            bingo_rank_pattern, immortal_pattern)
+        VALUES (1, ?1, ?2, ?3, ?4)
+        ON CONFLICT(id) DO UPDATE SET
+            completion_pattern = excluded.completion_pattern,
+            special_completion_pattern = excluded.special_completion_pattern,
+            bingo_rank_pattern = excluded.bingo_rank_pattern,
+            immortal_pattern = excluded.immortal_pattern
+        ",
+        params![
+            patterns.completions,
+            patterns.specific_completion,
+            patterns.bingo_rank,
+            patterns.immortal,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Inserts imported role bindings all at once, replacing the patterns if specified. Existing
+/// bindings of other roles are kept.
+pub struct ImportRoleConfig {
+    pub mappings: Vec<RoleMapping>,
+    pub patterns: Option<RolePatterns>,
+}
+impl DbRequest for ImportRoleConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        for mapping in &self.mappings {
+            insert_role_mapping(&transaction, mapping)?;
+        }
+        if let Some(patterns) = &self.patterns {
+            set_role_patterns(&transaction, patterns)?;
+        }
+
+        transaction.commit()?;
+        mapping_cache::invalidate();
+        Ok(())
+    }
+}
+
+pub struct SetHypixelGuildMapping {
+    /// Removes the mapping if `None`
+    pub mapping: Option<HypixelGuildMapping>,
+}
+impl DbRequest for SetHypixelGuildMapping {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.mapping {
+            Some(mapping) => conn.execute(
+                "
+                INSERT INTO role_hypixel_guild_config (id, guild_id, guild_name, role)
+                VALUES (1, ?1, ?2, ?3)
+                ON CONFLICT(id) DO UPDATE SET
+                    guild_id = excluded.guild_id,
+                    guild_name = excluded.guild_name,
+                    role = excluded.role
+                ",
+                params![mapping.guild_id, mapping.guild_name, mapping.role.get()],
+            )?,
+            None => conn.execute("DELETE FROM role_hypixel_guild_config WHERE id=1", [])?,
+        };
+        mapping_cache::invalidate();
+        Ok(())
+    }
+}
+
+pub struct SetRejoinRestoreConfig {
+    /// Disables restoring roles on rejoin if `None`
+    pub config: Option<RejoinRestoreConfig>,
+}
+impl DbRequest for SetRejoinRestoreConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.config {
+            Some(config) => conn.execute(
+                "
+                INSERT INTO role_rejoin_config (id, delay_secs, log_channel)
+                VALUES (1, ?1, ?2)
+                ON CONFLICT(id) DO UPDATE SET
+                    delay_secs = excluded.delay_secs,
+                    log_channel = excluded.log_channel
+                ",
+                params![config.delay_secs, config.log_channel.map(|c| c.get())],
+            )?,
+            None => conn.execute("DELETE FROM role_rejoin_config WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetLinkVerificationConfig {
+    /// Disables link re-verification if `None`
+    pub config: Option<LinkVerificationConfig>,
+}
+impl DbRequest for SetLinkVerificationConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.config {
+            Some(config) => conn.execute(
+                "
+                INSERT INTO role_link_verification_config
+                    (id, channel, auto_unlink, interval_days)
+                VALUES (1, ?1, ?2, ?3)
+                ON CONFLICT(id) DO UPDATE SET
+                    channel = excluded.channel,
+                    auto_unlink = excluded.auto_unlink,
+                    interval_days = excluded.interval_days
+                ",
+                params![
+                    config.channel.get(),
+                    config.auto_unlink,
+                    config.interval_days
+                ],
+            )?,
+            None => conn.execute("DELETE FROM role_link_verification_config WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetRoleAutoCreateConfig {
+    /// Disables automatic role creation if `None`
+    pub config: Option<RoleAutoCreateConfig>,
+}
+impl DbRequest for SetRoleAutoCreateConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.config {
+            Some(config) => conn.execute(
+                "
+                INSERT INTO role_auto_create_config (id, colour)
+                VALUES (1, ?1)
+                ON CONFLICT(id) DO UPDATE SET colour = excluded.colour
+                ",
+                params![config.colour.map(|c| c.0)],
+            )?,
+            None => conn.execute("DELETE FROM role_auto_create_config WHERE id=1", [])?,
+        };
         Ok(())
     }
 }
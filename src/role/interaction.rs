@@ -1,7 +1,10 @@
-use anyhow::{anyhow, Result};
+use anyhow::{Result, anyhow};
 use either::Either;
 use poise::serenity_prelude::{ComponentInteraction, Context as SerenityContext, ModalInteraction};
 
+use crate::shared::interaction::custom_id::Parts;
+
+mod application;
 mod config;
 mod modal;
 mod request;
@@ -9,11 +12,12 @@ mod request;
 pub async fn handle_interaction(
     ctx: &SerenityContext,
     interaction: Either<&ComponentInteraction, &ModalInteraction>,
-    mut action: impl Iterator<Item = &str>,
+    mut action: Parts<'_>,
 ) -> Result<()> {
     match action.next().unwrap_or_default() {
         "request" => request::handle_interaction(ctx, interaction, action).await,
         "config" => config::handle_interaction(ctx, interaction, action).await,
+        "application" => application::handle_interaction(ctx, interaction, action).await,
         _ => Err(anyhow!("Invalid interaction: Unknown subcategory")),
     }
 }
@@ -0,0 +1,112 @@
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::Utc;
+use either::Either;
+use poise::serenity_prelude::{
+    ComponentInteraction, Context as SerenityContext, CreateInteractionResponse,
+    CreateInteractionResponseMessage, Mentionable as _, MessageFlags, MessageId, ModalInteraction,
+};
+use tracing::info;
+
+use crate::error::UserError;
+use crate::role::{
+    applications,
+    db::application::{
+        ClaimManualApplication, GetManualApplication, ReleaseManualApplication,
+        ResolveManualApplication,
+    },
+};
+use crate::shared::{BotData, interaction::custom_id::Parts};
+
+pub async fn handle_interaction(
+    ctx: &SerenityContext,
+    interaction: Either<&ComponentInteraction, &ModalInteraction>,
+    mut action: Parts<'_>,
+) -> Result<()> {
+    let Either::Left(interaction) = interaction else {
+        return Err(anyhow!("Invalid interaction: Unexpected modal"));
+    };
+    info!(
+        "{} triggered component interaction: '{}'",
+        interaction.user.name, interaction.data.custom_id
+    );
+
+    if !applications::is_staff(interaction.member.as_deref()) {
+        bail!(UserError(anyhow!(
+            "Only staff can claim and resolve applications"
+        )));
+    }
+
+    let kind = action.next_required("action")?;
+    let message_id = MessageId::new(action.next_parsed("application")?);
+    let db = ctx.data::<BotData>().db_for(interaction.guild_id);
+    let user = interaction.user.id;
+    let now = Utc::now().timestamp();
+
+    let current = db
+        .request(GetManualApplication { message_id })
+        .await??
+        .context(UserError(anyhow!("This application is no longer tracked")))?;
+
+    // the requests only apply if the application is still in the expected state, so that two staff
+    // members clicking at the same time can't both claim it
+    let applied = match kind {
+        "claim" => {
+            db.request(ClaimManualApplication {
+                message_id,
+                by: user,
+                at: now,
+            })
+            .await??
+        }
+        "release" => {
+            db.request(ReleaseManualApplication {
+                message_id,
+                by: user,
+            })
+            .await??
+        }
+        "resolve" => {
+            db.request(ResolveManualApplication {
+                message_id,
+                by: user,
+                at: now,
+            })
+            .await??
+        }
+        _ => return Err(anyhow!("Invalid interaction: Unknown action")),
+    };
+
+    if !applied {
+        let reason = match (current.claimed, current.resolved) {
+            (_, Some((by, _))) => format!("was already resolved by {}", by.mention()),
+            (Some((by, _)), None) if by != user => {
+                format!("is already claimed by {}", by.mention())
+            }
+            _ => "has changed in the meantime".to_string(),
+        };
+        bail!(UserError(anyhow!("This application {reason}")));
+    }
+
+    info!(
+        "{} used '{kind}' on manual role application {message_id}",
+        interaction.user.name
+    );
+
+    let updated = db
+        .request(GetManualApplication { message_id })
+        .await??
+        .context("Application disappeared after updating it")?;
+
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .components(applications::panel(&updated)?),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -1,36 +1,56 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context as _, Result, anyhow, bail};
 use either::Either;
 use poise::serenity_prelude::{
-    CacheHttp as _, ComponentInteraction, ComponentInteractionDataKind, Context as SerenityContext,
-    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
-    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateTextDisplay,
-    GuildId, Mentionable as _, MessageFlags, ModalInteraction, Role, RoleId,
-    colours::css::{DANGER, POSITIVE},
+    ButtonStyle, CacheHttp as _, Colour, ComponentInteraction, ComponentInteractionDataKind,
+    Context as SerenityContext, CreateAttachment, CreateButton, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, CreateSection, CreateSectionAccessory,
+    CreateSectionComponent, CreateTextDisplay, GuildId, Mentionable as _, MessageFlags,
+    ModalInteraction, Role, RoleId,
+    colours::{
+        branding::YELLOW,
+        css::{DANGER, POSITIVE},
+    },
 };
 use tracing::{info, warn};
 
 use crate::role::{
+    config_export,
     db::role_config::{
-        DeleteRoleMappingByRole, GetRolePatterns, InsertRoleMapping, SetRolePatterns,
+        ClearRoleMappingKind, DeleteRoleMappingByRole, DeleteRoleMappingsOfKind,
+        GetRoleMappingCounts, GetRoleMappingSnapshot, GetRolePatterns, InsertRoleMapping,
+        SetRolePatterns,
     },
     interaction::modal,
-    menu::RoleConfigSession,
+    menu::{self, RoleConfigSession},
     types::{NetworkBingo, RoleMapping, RoleMappingKind, RoleMappingKindRaw, RolePatterns},
 };
 use crate::shared::{
     BotData,
-    interaction::{MessageEdit, modal as shared_modal},
-    menu::navigation::GenerateMenu as _,
+    interaction::{
+        MessageEdit,
+        custom_id::{self, Namespace, Parts},
+        modal as shared_modal,
+    },
+    menu::{navigation::GenerateMenu as _, timeout, transfer},
     types::{Bingo, BingoKind},
 };
 use crate::{error::UserError, role::db::role_config::DetectRelevantRoles};
 
+/// Minimum time between progress updates of the auto-detection followup
+const PROGRESS_EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
 pub async fn handle_interaction(
     ctx: &SerenityContext,
     interaction: Either<&ComponentInteraction, &ModalInteraction>,
-    mut action: impl Iterator<Item = &str>,
+    mut action: Parts<'_>,
 ) -> Result<()> {
     match interaction {
         Either::Left(component_interaction) => {
@@ -47,11 +67,7 @@ pub async fn handle_interaction(
         }
     }
 
-    let menu_id = action
-        .next()
-        .unwrap_or_default()
-        .parse::<u64>()
-        .context("Invalid interaction: Expected menu ID")?;
+    let menu_id: u64 = action.next_parsed("menu ID")?;
 
     // NOTE: lock dropped at the end of the expression
     let session_mutex = Arc::clone(
@@ -142,8 +158,9 @@ async fn component(
     mut action: impl Iterator<Item = &str>,
     session: &mut RoleConfigSession,
 ) -> Result<MessageEdit<'static>> {
-    let db = &ctx.data::<BotData>().db_handle;
-    let id_prefix = format!("role:config:{}", session.menu_id);
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
+    let id_prefix = custom_id::encode(Namespace::Role, &[&"config", &session.menu_id]);
 
     match action.next().unwrap_or_default() {
         "auto_detect" => {
@@ -153,11 +170,43 @@ async fn component(
                 "Interaction triggered outside of a guild"
             )))?;
 
-            let guild_roles: Vec<Role> = guild.roles(ctx.http()).await?.into_iter().collect();
+            let guild_roles: Arc<[Role]> = guild.roles(ctx.http()).await?.into_iter().collect();
 
-            let detected_roles = db
-                .request(DetectRelevantRoles { roles: guild_roles })
-                .await??;
+            let progress_message = interaction
+                .create_followup(
+                    ctx.http(),
+                    detection_followup(detection_progress(&[]), YELLOW),
+                )
+                .await?;
+
+            let mut detected_roles = Vec::new();
+            let mut finished = Vec::new();
+            let mut last_edit = Instant::now();
+            for &category in RoleMappingKindRaw::DETECTABLE {
+                let detected = db
+                    .request(DetectRelevantRoles {
+                        roles: Arc::clone(&guild_roles),
+                        category,
+                    })
+                    .await??;
+                finished.push((category, detected.len()));
+                detected_roles.extend(detected);
+
+                // fast detections would only run into rate limits with per-category edits
+                if last_edit.elapsed() >= PROGRESS_EDIT_INTERVAL
+                    && finished.len() < RoleMappingKindRaw::DETECTABLE.len()
+                {
+                    interaction
+                        .edit_followup(
+                            ctx.http(),
+                            progress_message.id,
+                            detection_followup(detection_progress(&finished), YELLOW),
+                        )
+                        .await?;
+                    last_edit = Instant::now();
+                }
+            }
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let role_list = if detected_roles.is_empty() {
                 Cow::Borrowed("*None*")
@@ -171,10 +220,13 @@ async fn component(
                 )
             };
 
-            let container = CreateComponent::Container(
-                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
-                    CreateTextDisplay::new(format!(
-                        "## Successfully ran detection
+            interaction
+                .edit_followup(
+                    ctx.http(),
+                    progress_message.id,
+                    detection_followup(
+                        format!(
+                            "## Successfully ran detection
 ### New detected roles
 {role_list}
 
@@ -184,24 +236,59 @@ Use the the configuration menu to manually assign them.
 -# - For roles with incrementally increasing numbers (e.g. Blackout counts), \
 detection stops after not finding a matching role for 3 consecutive numbers. \
 If such a gap is intentional, roles can still be configured manually."
-                    )),
-                )])
-                .accent_color(POSITIVE),
+                        ),
+                        POSITIVE,
+                    ),
+                )
+                .await?;
+
+            Ok(MessageEdit::Direct(
+                session.state.generate(db, session.menu_id).await?,
+            ))
+        }
+        "export_config" => {
+            interaction.defer_ephemeral(ctx.http()).await?;
+
+            let guild = interaction.guild_id.context(UserError(anyhow!(
+                "Interaction triggered outside of a guild"
+            )))?;
+
+            // role names let the import match roles of other guilds, whose IDs differ
+            let role_names: HashMap<_, _> = guild
+                .roles(ctx.http())
+                .await?
+                .into_iter()
+                .map(|role| (role.id, role.name.to_string()))
+                .collect();
+            let snapshot = db.request(GetRoleMappingSnapshot).await??;
+
+            let file = CreateAttachment::bytes(
+                config_export::to_json(&snapshot.mappings, &snapshot.patterns, &role_names)
+                    .into_bytes(),
+                format!("role_config_{}.json", chrono::Utc::now().format("%Y-%m-%d")),
             );
+            let text = CreateTextDisplay::new(format!(
+                "## Exported Role Config
+The attached file contains all {} role bindings and the auto-detection patterns.
+-# Import it with `/rolerequest config-import`.",
+                snapshot.mappings.len()
+            ));
 
             interaction
                 .create_followup(
                     ctx.http(),
                     CreateInteractionResponseFollowup::new()
                         .flags(MessageFlags::IS_COMPONENTS_V2)
-                        .components(vec![container])
+                        .components(vec![CreateComponent::Container(
+                            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                                .accent_color(POSITIVE),
+                        )])
+                        .add_file(file)
                         .ephemeral(true),
                 )
                 .await?;
 
-            Ok(MessageEdit::Direct(
-                session.state.generate(db, session.menu_id).await?,
-            ))
+            Ok(MessageEdit::NoEdit)
         }
         "edit_patterns" => {
             let patterns = db.request(GetRolePatterns).await??;
@@ -239,7 +326,7 @@ If such a gap is intentional, roles can still be configured manually."
                 _ => bail!("Invalid interaction: Unexpected category identifier"),
             };
 
-            session.state.kind = new_category;
+            session.state.switch_kind(new_category);
 
             Ok(MessageEdit::Interaction(
                 session.state.generate(db, session.menu_id).await?,
@@ -277,6 +364,116 @@ If such a gap is intentional, roles can still be configured manually."
 
             db.request(DeleteRoleMappingByRole { role: role_id })
                 .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
+
+            Ok(MessageEdit::Interaction(
+                session.state.generate(db, session.menu_id).await?,
+            ))
+        }
+        "delete_selected" => {
+            let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+            else {
+                bail!("Invalid interaction: Expected String SelectMenu")
+            };
+
+            let roles = values
+                .iter()
+                .map(|value| {
+                    value
+                        .parse()
+                        .map(RoleId::new)
+                        .context("Invalid interaction: Expected role ID")
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            db.request(DeleteRoleMappingsOfKind {
+                kind: session.state.kind,
+                roles,
+            })
+            .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
+
+            Ok(MessageEdit::Interaction(
+                session.state.generate(db, session.menu_id).await?,
+            ))
+        }
+        "clear_category" => {
+            let kind = parse_kind(action.next())?;
+            let count = db
+                .request(GetRoleMappingCounts)
+                .await??
+                .get(&kind)
+                .copied()
+                .unwrap_or_default();
+
+            let confirm_button =
+                CreateButton::new(format!("{id_prefix}:clear_category_confirm:{}", kind as u8))
+                    .label("Clear Category")
+                    .style(ButtonStyle::Danger);
+
+            let confirm_section = CreateContainerComponent::Section(CreateSection::new(
+                vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+                    format!(
+                        "## Confirm Deletion
+Are you sure you want to delete all {count} binding(s) of the *{}* category? This can't be undone.",
+                        kind.name()
+                    ),
+                ))],
+                CreateSectionAccessory::Button(confirm_button),
+            ));
+
+            let message = CreateInteractionResponseMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![confirm_section]).accent_color(DANGER),
+                )])
+                .ephemeral(true);
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Message(message))
+                .await?;
+            Ok(MessageEdit::NoEdit)
+        }
+        "clear_category_confirm" => {
+            let kind = parse_kind(action.next())?;
+
+            let deleted = db.request(ClearRoleMappingKind { kind }).await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
+
+            let message = CreateInteractionResponseMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                        CreateTextDisplay::new(format!(
+                            "## Cleared Category
+Deleted {deleted} binding(s) of the *{}* category.",
+                            kind.name()
+                        )),
+                    )])
+                    .accent_color(POSITIVE),
+                )])
+                .ephemeral(true);
+
+            interaction
+                .create_response(
+                    ctx.http(),
+                    CreateInteractionResponse::UpdateMessage(message),
+                )
+                .await?;
+
+            Ok(MessageEdit::Direct(
+                session.state.generate(db, session.menu_id).await?,
+            ))
+        }
+        "more_actions" => {
+            session.state.more_actions = true;
+
+            Ok(MessageEdit::Interaction(
+                session.state.generate(db, session.menu_id).await?,
+            ))
+        }
+        "back" => {
+            session.state.more_actions = false;
 
             Ok(MessageEdit::Interaction(
                 session.state.generate(db, session.menu_id).await?,
@@ -301,7 +498,15 @@ If such a gap is intentional, roles can still be configured manually."
                 .await?;
             Ok(MessageEdit::NoEdit)
         }
-        _ => Err(anyhow!("Invalid interaction: Unexpected action")),
+        // the timeout was already reset, re-rendering removes the expiry warning
+        timeout::EXTEND_ACTION => Ok(MessageEdit::Interaction(
+            session.state.generate(db, session.menu_id).await?,
+        )),
+        other => {
+            transfer::handle_component(ctx, interaction, other, &id_prefix, &mut session.owner)
+                .await?
+                .context("Invalid interaction: Unexpected action")
+        }
     }
 }
 
@@ -311,7 +516,8 @@ async fn modal(
     mut action: impl Iterator<Item = &str>,
     session: &mut RoleConfigSession,
 ) -> Result<MessageEdit<'static>> {
-    let db = &ctx.data::<BotData>().db_handle;
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(interaction.guild_id);
 
     match action.next().unwrap_or_default() {
         "jump_page_submit" => {
@@ -341,6 +547,7 @@ async fn modal(
                 ),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(
@@ -388,6 +595,7 @@ to run the detection process using the new patterns.",
                 role_mapping: RoleMapping::new(RoleMappingKind::BingoRank { rank }, role_id),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(format!(
@@ -435,6 +643,7 @@ Associated {} with Bingo Rank {rank}.",
                 role_mapping: RoleMapping::new(RoleMappingKind::Completions { count }, role_id),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(format!(
@@ -497,6 +706,7 @@ Associated {} with Blackout count {count}.",
                 ),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(format!(
@@ -540,6 +750,7 @@ Associated {} with {bingo}.",
                 role_mapping: RoleMapping::new(RoleMappingKind::Immortal, role_id),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(format!(
@@ -592,6 +803,7 @@ Associated {} with Immortal.",
                 role_mapping: RoleMapping::new(RoleMappingKind::NetworkBingo { bingo }, role_id),
             })
             .await??;
+            menu::refresh_other_sessions(ctx, session.menu_id, session.guild_id);
 
             let container = CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
                 CreateTextDisplay::new(format!(
@@ -639,3 +851,39 @@ async fn validate_role_string(
         .context("Failed to validate role ID: Invalid role in current guild")
         .map(|r| r.id)
 }
+
+fn parse_kind(value: Option<&str>) -> Result<RoleMappingKindRaw> {
+    value
+        .and_then(|value| value.parse().ok())
+        .and_then(RoleMappingKindRaw::from_u8)
+        .context("Invalid interaction: Expected category ID")
+}
+
+fn detection_progress(finished: &[(RoleMappingKindRaw, usize)]) -> String {
+    let categories: String = RoleMappingKindRaw::DETECTABLE
+        .iter()
+        .map(
+            |category| match finished.iter().find(|(finished, _)| finished == category) {
+                Some((_, count)) => format!("- ✅ {}: {count} new\n", category.name()),
+                None => format!("- ⏳ {}\n", category.name()),
+            },
+        )
+        .collect();
+
+    format!(
+        "## Running detection
+{categories}-# This may take a while in servers with many roles."
+    )
+}
+
+fn detection_followup(text: String, accent: Colour) -> CreateInteractionResponseFollowup<'static> {
+    CreateInteractionResponseFollowup::new()
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .components(vec![CreateComponent::Container(
+            CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                CreateTextDisplay::new(text),
+            )])
+            .accent_color(accent),
+        )])
+        .ephemeral(true)
+}
@@ -13,6 +13,7 @@ define_modal! {
                 style: InputTextStyle::Short,
                 label: "Username",
                 placeholder: "Enter Minecraft username",
+                min_length: 3,
                 max_length: 16,
                 required: true,
             }
@@ -78,6 +79,18 @@ define_modal! {
 }
 
 define_modal! {
+    // shared by every role mapping modal, validated via `validate_role_string`
+    group role_id_input(description) [
+        input role_id {
+            style: InputTextStyle::Short,
+            label: "Role",
+            description: $description,
+            placeholder: "Enter a role ID",
+            max_length: 20,
+            required: true,
+        }
+    ]
+
     RoleMappingBingoRank {
         custom_id: "role_mapping_bingo_rank_submit",
         title: "New Role Binding: Bingo Rank",
@@ -90,19 +103,10 @@ define_modal! {
                 max_length: 2,
                 required: true,
             },
-            input role_id {
-                style: InputTextStyle::Short,
-                label: "Role",
-                description: "The Role ID to associate with the given Bingo Rank",
-                placeholder: "Enter a role ID",
-                max_length: 20,
-                required: true,
-            }
+            include role_id_input("The Role ID to associate with the given Bingo Rank"),
         ]
     }
-}
 
-define_modal! {
     RoleMappingCompletions {
         custom_id: "role_mapping_completions_submit",
         title: "New Role Binding: Blackout Count",
@@ -115,19 +119,10 @@ define_modal! {
                 max_length: 3,
                 required: true,
             },
-            input role_id {
-                style: InputTextStyle::Short,
-                label: "Role",
-                description: "The Role ID to associate with the given Blackout count",
-                placeholder: "Enter a role ID",
-                max_length: 20,
-                required: true,
-            }
+            include role_id_input("The Role ID to associate with the given Blackout count"),
         ]
     }
-}
 
-define_modal! {
     RoleMappingSpecificCompletion {
         custom_id: "role_mapping_specific_completion_submit",
         title: "New Role Binding: Specific Blackout",
@@ -154,28 +149,19 @@ define_modal! {
                 max_length: 3,
                 required: true,
             },
-            input role_id {
-                style: InputTextStyle::Short,
-                label: "Role",
-                description: "The Role ID to associate with the given Bingo event",
-                placeholder: "Enter a role ID",
-                max_length: 20,
-                required: true,
-            }
+            include role_id_input("The Role ID to associate with the given Bingo event"),
         ]
     }
-}
 
-define_modal! {
     RoleMappingNetworkBingo {
         custom_id: "role_mapping_network_bingo_submit",
         title: "New Role Binding: Network Bingo",
         components: [
             select bingo {
                 kind: CreateSelectMenuKind::String {
-                    options: NetworkBingo::ALL
+                    options: NetworkBingo::all()
                         .iter()
-                        .map(|b| CreateSelectMenuOption::new(b.to_string(), (*b as u8).to_string()))
+                        .map(|b| CreateSelectMenuOption::new(b.to_string(), b.id().to_string()))
                         .collect(),
                 },
                 label: "Bingo",
@@ -183,31 +169,15 @@ define_modal! {
                 max_values: 1,
                 required: true,
             },
-            input role_id {
-                style: InputTextStyle::Short,
-                label: "Role",
-                description: "The Role ID to associate with the given Bingo event",
-                placeholder: "Enter a role ID",
-                max_length: 20,
-                required: true,
-            }
+            include role_id_input("The Role ID to associate with the given Bingo event"),
         ]
     }
-}
 
-define_modal! {
     RoleMappingImmortal {
         custom_id: "role_mapping_immortal_submit",
         title: "New Role Binding: Immortal",
         components: [
-            input role_id {
-                style: InputTextStyle::Short,
-                label: "Role",
-                description: "The Role ID to associate with the Immortal achievement",
-                placeholder: "Enter a role ID",
-                max_length: 20,
-                required: true,
-            }
+            include role_id_input("The Role ID to associate with the Immortal achievement"),
         ]
     }
 }
@@ -3,26 +3,40 @@ use std::borrow::Cow;
 use anyhow::{Context as _, Result, anyhow, bail};
 use either::Either;
 use poise::serenity_prelude::{
-    ButtonStyle, CacheHttp as _, ComponentInteraction, Context as SerenityContext, CreateButton,
-    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
-    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMediaGallery,
-    CreateMediaGalleryItem, CreateSection, CreateSectionAccessory, CreateSectionComponent,
-    CreateTextDisplay, CreateUnfurledMediaItem, Mentionable as _, MessageFlags, ModalInteraction,
-    colours::{css::POSITIVE, roles::BLUE},
+    ButtonStyle, CacheHttp as _, ComponentInteraction, Context as SerenityContext,
+    CreateAllowedMentions, CreateButton, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, CreateMediaGallery, CreateMediaGalleryItem, CreateSection,
+    CreateSectionAccessory, CreateSectionComponent, CreateTextDisplay, CreateUnfurledMediaItem,
+    EditInteractionResponse, Mentionable as _, MessageFlags, ModalInteraction, UserId,
+    colours::{
+        css::{POSITIVE, WARNING},
+        roles::BLUE,
+    },
 };
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::config::{BOT_MAINTAINER, MANUAL_ROLE_CHANNEL};
+use crate::error::UserError;
 use crate::role::{
-    db::link::{GetLinkedUserByDiscord, RemoveLinkedUserByDiscord, RemoveLinkedUserByMinecraft},
+    db::link::{
+        GetLinkedUserByDiscord, GetLinkedUserByMinecraft, InsertLinkedUser,
+        RemoveLinkedUserByDiscord, RemoveLinkedUserByMinecraft, TransferLinkedUser,
+    },
     interaction::modal,
+    request::full_username,
+    types::{LinkedUser, RoleUpdateTrigger},
+};
+use crate::shared::{
+    BotData,
+    interaction::custom_id::{self, Namespace, Parts},
+    types::MinecraftIdent,
 };
-use crate::shared::BotData;
 
 pub async fn handle_interaction(
     ctx: &SerenityContext,
     interaction: Either<&ComponentInteraction, &ModalInteraction>,
-    action: impl Iterator<Item = &str>,
+    action: Parts<'_>,
 ) -> Result<()> {
     match interaction {
         Either::Left(component_interaction) => {
@@ -47,10 +61,10 @@ const INSTRUCTIONS_GIF: &str = "https://media.discordapp.net/attachments/9975233
 async fn component(
     ctx: &SerenityContext,
     interaction: &ComponentInteraction,
-    mut action: impl Iterator<Item = &str>,
+    mut action: Parts<'_>,
 ) -> Result<()> {
     let data = ctx.data::<BotData>();
-    let db = &data.db_handle;
+    let db = data.db_for(interaction.guild_id);
 
     match action.next().unwrap_or_default() {
         "begin" => {
@@ -68,8 +82,13 @@ async fn component(
                     .as_ref()
                     .context("Interaction was triggered outside of a guild")?;
 
-                let role_status =
-                    crate::role::request::update_roles(ctx, &uuid, guild_member).await?;
+                let role_status = crate::role::request::update_roles(
+                    ctx,
+                    &uuid,
+                    guild_member,
+                    RoleUpdateTrigger::Request,
+                )
+                .await?;
 
                 let container = role_status.to_diff_message(None);
 
@@ -96,10 +115,13 @@ Your Discord account isn't currently linked to a Hypixel profile. Follow these s
 ### 3. Paste **`{full_username}`** into All Chat")
                 ));
 
-                let confirm_button = CreateButton::new("role:request:confirm_link")
-                    .emoji('🔗')
-                    .label("Link Account")
-                    .style(ButtonStyle::Success);
+                let confirm_button = CreateButton::new(custom_id::encode(
+                    Namespace::Role,
+                    &[&"request", &"confirm_link"],
+                ))
+                .emoji('🔗')
+                .label("Link Account")
+                .style(ButtonStyle::Success);
                 let button_section = CreateContainerComponent::Section(CreateSection::new(
                     vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
                         "### 4. Confirm username
@@ -133,7 +155,8 @@ click this button and enter your in-game username when prompted.",
             }
         }
         "confirm_link" => {
-            let modal = modal::RoleRequestLink::create("role:request");
+            let prefix = custom_id::encode(Namespace::Role, &[&"request"]);
+            let modal = modal::RoleRequestLink::create(&prefix);
 
             interaction
                 .create_response(ctx.http(), CreateInteractionResponse::Modal(modal))
@@ -182,6 +205,147 @@ You will be prompted to re-link the next time you request roles.",
 
             Ok(())
         }
+        "transfer" => {
+            let uuid = action.next_required("UUID")?.to_string();
+            let guild_member = interaction
+                .member
+                .as_ref()
+                .context("Interaction was triggered outside of a guild")?;
+
+            interaction.defer_ephemeral(ctx.http()).await?;
+
+            // the button may be clicked long after the check, so ownership is verified again
+            let linked_discord = data.api_handle.linked_discord(db, &uuid).await?;
+            if linked_discord.as_deref() != Some(&full_username(&interaction.user)) {
+                bail!(UserError(anyhow!(
+                    "Your Hypixel profile's Discord setting no longer matches this account, \
+                    please request roles again"
+                )));
+            }
+
+            let previous = db
+                .request(GetLinkedUserByMinecraft {
+                    mc_uuid: uuid.clone(),
+                })
+                .await??
+                .filter(|linked| linked.discord != interaction.user.id)
+                .context(UserError(anyhow!(
+                    "Your Minecraft account is no longer linked to another Discord account"
+                )))?;
+
+            let transferred = db
+                .request(TransferLinkedUser {
+                    mc_uuid: uuid.clone(),
+                    from: previous.discord,
+                    to: interaction.user.id,
+                })
+                .await??;
+            if !transferred {
+                bail!(UserError(anyhow!(
+                    "The link changed in the meantime, please request roles again"
+                )));
+            }
+
+            info!(
+                "Transferred link of {uuid} from {} to {}",
+                previous.discord, interaction.user.id
+            );
+
+            // the previous account would otherwise keep the roles of a profile it's no longer linked
+            // to. staff can still fix this with a force update, so a failure doesn't abort
+            let revoked = match guild_member
+                .guild_id
+                .member(ctx.http(), previous.discord)
+                .await
+            {
+                Ok(member) => crate::role::request::revoke_roles(
+                    ctx,
+                    &member,
+                    RoleUpdateTrigger::Transfer,
+                )
+                .await
+                .unwrap_or_else(|err| {
+                    warn!(
+                        "Failed to remove roles of {} after transferring their link: {err:#}",
+                        previous.discord
+                    );
+                    Vec::new()
+                }),
+                // the previous account already left the server
+                Err(_) => Vec::new(),
+            };
+
+            let role_status = crate::role::request::update_roles(
+                ctx,
+                &uuid,
+                guild_member,
+                RoleUpdateTrigger::Request,
+            )
+            .await?;
+
+            let revoked_note = if revoked.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n-# {} roles granted by role requests were removed from {}.",
+                    revoked.len(),
+                    previous.discord.mention()
+                )
+            };
+            let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+                "## Transferred Link
+Your Hypixel profile was moved from {} to this Discord account, along with its role history.\
+{revoked_note}",
+                previous.discord.mention()
+            )));
+            let container =
+                CreateComponent::Container(CreateContainer::new(vec![text]).accent_color(POSITIVE));
+
+            interaction
+                .create_followup(
+                    ctx.http(),
+                    CreateInteractionResponseFollowup::new()
+                        .flags(MessageFlags::IS_COMPONENTS_V2)
+                        .allowed_mentions(CreateAllowedMentions::new())
+                        .components(vec![container, role_status.to_diff_message(None)])
+                        .ephemeral(true),
+                )
+                .await?;
+
+            Ok(())
+        }
+        "preview" => {
+            let linked_user = db
+                .request(GetLinkedUserByDiscord {
+                    discord: interaction.user.id,
+                })
+                .await??
+                .context(UserError(anyhow!(
+                    "Your account isn't linked yet, use `Request Roles` to link it first"
+                )))?;
+            let guild_member = interaction
+                .member
+                .as_ref()
+                .context("Interaction was triggered outside of a guild")?;
+
+            interaction.defer_ephemeral(ctx.http()).await?;
+
+            let preview =
+                crate::role::request::preview_roles(ctx, &linked_user.mc_uuid, guild_member)
+                    .await?;
+
+            interaction
+                .create_followup(
+                    ctx.http(),
+                    CreateInteractionResponseFollowup::new()
+                        .flags(MessageFlags::IS_COMPONENTS_V2)
+                        .components(vec![preview.to_message(None)])
+                        .ephemeral(true),
+                )
+                .await?;
+
+            Ok(())
+        }
         "faq" => {
             let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
                 format!("## Frequently asked Questions
@@ -216,6 +380,85 @@ and you are now required to complete **any easy and any hard** card of your choi
             interaction.create_response(ctx.http(), message).await?;
             Ok(())
         }
+        "suggested" => {
+            let staff = UserId::new(action.next_parsed("staff member ID")?);
+            let discord = UserId::new(action.next_parsed("user ID")?);
+            let uuid = action.next_required("UUID")?.to_string();
+
+            if interaction.user.id != staff {
+                bail!(UserError(anyhow!(
+                    "Only the staff member who requested this suggestion can confirm it"
+                )));
+            }
+
+            interaction
+                .create_response(ctx.http(), CreateInteractionResponse::Acknowledge)
+                .await?;
+
+            // verify the suggestion, since the cached profile may be outdated
+            let user = discord.to_user(ctx).await?;
+            let linked_discord = data.api_handle.linked_discord(db, &uuid).await?;
+
+            let (text, colour) = if linked_discord.as_deref() != Some(&full_username(&user)) {
+                let text = format!(
+                    "## Suggestion Outdated
+The suggested Hypixel profile no longer lists {}'s Discord account.",
+                    discord.mention()
+                );
+                (text, WARNING)
+            } else {
+                // either account may have been linked since the suggestion was made
+                match db
+                    .request(InsertLinkedUser {
+                        user: LinkedUser::new(discord, uuid),
+                    })
+                    .await??
+                {
+                    (_, Some(other_uuid)) => {
+                        let text = format!(
+                            "## Account Already Linked
+{} is already linked to `{}`. Unlink their previous account first.",
+                            discord.mention(),
+                            data.api_handle.username(db, &other_uuid).await?
+                        );
+                        (text, WARNING)
+                    }
+                    (Some(other_discord), None) => {
+                        let text = format!(
+                            "## Account Already Linked
+The suggested Hypixel profile is already linked to {}. Unlink it first.",
+                            other_discord.mention()
+                        );
+                        (text, WARNING)
+                    }
+                    (None, None) => {
+                        let text = format!(
+                            "## Linked Successfully
+Linked {}'s accounts as suggested. Use '/rolerequest force update' to update their roles.",
+                            discord.mention()
+                        );
+                        (text, POSITIVE)
+                    }
+                }
+            };
+            let container = CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(text),
+                )])
+                .accent_color(colour),
+            );
+
+            interaction
+                .edit_response(
+                    ctx.http(),
+                    EditInteractionResponse::new()
+                        .allowed_mentions(CreateAllowedMentions::new())
+                        .components(vec![container]),
+                )
+                .await?;
+
+            Ok(())
+        }
         _ => bail!("Invalid interaction: Unexpected action"),
     }
 }
@@ -228,11 +471,17 @@ async fn modal(
     match action.next().unwrap_or_default() {
         "confirm_link_submit" => {
             let values = modal::RoleRequestLink::validate(&interaction.data.components)?;
+            let minecraft = MinecraftIdent::from_input(&values.username)?;
 
             interaction.defer_ephemeral(ctx.http()).await?;
 
-            let link_status =
-                crate::role::request::link_user(ctx, &interaction.user, &values.username).await?;
+            let link_status = crate::role::request::link_user(
+                ctx,
+                interaction.guild_id,
+                &interaction.user,
+                &minecraft,
+            )
+            .await?;
 
             let container = link_status.to_response();
 
@@ -0,0 +1,254 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use poise::serenity_prelude::{
+    Context as SerenityContext, CreateAllowedMentions, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, Http, Mentionable as _,
+    MessageFlags, UserId, colours::css::WARNING,
+};
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+use crate::db::DbHandle;
+use crate::hypixel_api::ApiHandle;
+use crate::role::{
+    db::{
+        link::{
+            GetLinksDueForVerification, RemoveLinkedUserByDiscord, SetLinkCheckFailed,
+            SetLinkChecked,
+        },
+        role_config::GetLinkVerificationConfig,
+    },
+    request::full_username,
+    types::LinkVerificationConfig,
+};
+use crate::shared::{BotData, task::spawn_background};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Wait before retrying a link whose check failed, so that it is retried on the next run. Doubles
+/// with every consecutive failure, up to [`MAX_RETRY_BACKOFF`].
+const RETRY_BACKOFF: Duration = Duration::from_secs(50 * 60);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+/// Links checked per database and run, spreading the player requests over the day
+const BATCH_SIZE: u32 = 60;
+/// Mismatches listed in a digest, so that it stays within Discord's message length limit
+const MAX_LISTED: usize = 50;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug)]
+struct Mismatch {
+    user: UserId,
+    uuid: String,
+    /// Discord account currently set on Hypixel, if any
+    hypixel_discord: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct VerificationRun {
+    verified: u32,
+    failed: u32,
+    mismatches: Vec<Mismatch>,
+}
+
+/// Periodically re-verifies links whose last check is older than the configured interval, as
+/// players may change the Discord account set on Hypixel after linking. Mismatches are reported to
+/// staff, and unlinked if configured.
+pub fn start_link_verification(ctx: &SerenityContext) {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let data = ctx.data::<BotData>();
+    let http = Arc::clone(&ctx.http);
+
+    spawn_background("link verification", Arc::clone(&ctx.http), async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            // stale player responses could report outdated Discord accounts
+            if data.api_handle.is_degraded() {
+                info!("Hypixel's API is degraded, skipping link verification");
+                continue;
+            }
+
+            let dbs = std::iter::once(&data.db_handle)
+                .chain(data.sandbox.iter().map(|sandbox| &sandbox.db_handle));
+            for db in dbs {
+                if let Err(err) = verify_due_links(&http, &data.api_handle, db).await {
+                    error!("Failed to verify links: {err:#}");
+                }
+            }
+        }
+    });
+}
+
+async fn verify_due_links(http: &Http, api: &ApiHandle, db: &DbHandle) -> Result<()> {
+    let Some(config) = db.request(GetLinkVerificationConfig).await?? else {
+        return Ok(());
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let due = db
+        .request(GetLinksDueForVerification {
+            checked_before: now - i64::from(config.interval_days) * 24 * 60 * 60,
+            now,
+            limit: BATCH_SIZE,
+        })
+        .await??;
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let mut run = VerificationRun::default();
+    for link in due {
+        let fetched = match (
+            api.linked_discord(db, &link.mc_uuid).await,
+            link.discord.to_user(http).await,
+        ) {
+            (Ok(hypixel_discord), Ok(user)) => Some((hypixel_discord, user)),
+            (Err(err), _) => {
+                warn!(
+                    "Failed to fetch Discord setting of {}: {err:#}",
+                    link.mc_uuid
+                );
+                None
+            }
+            (_, Err(err)) => {
+                warn!("Failed to fetch linked user {}: {err:#}", link.discord);
+                None
+            }
+        };
+
+        // failed checks aren't marked as checked, as nothing was verified, but retried after a
+        // backoff, so that they don't hold back the rest of the links
+        let Some((hypixel_discord, user)) = fetched else {
+            db.request(SetLinkCheckFailed {
+                discord: link.discord,
+                at: now,
+                backoff_secs: RETRY_BACKOFF.as_secs() as i64,
+                max_backoff_secs: MAX_RETRY_BACKOFF.as_secs() as i64,
+            })
+            .await??;
+            run.failed += 1;
+            continue;
+        };
+
+        let verified = hypixel_discord.as_deref() == Some(&full_username(&user));
+        // unlinked users no longer have a row, so this only affects verified and flagged ones
+        db.request(SetLinkChecked {
+            discord: link.discord,
+            at: now,
+            verified,
+        })
+        .await??;
+
+        if verified {
+            run.verified += 1;
+            continue;
+        }
+        if config.auto_unlink {
+            db.request(RemoveLinkedUserByDiscord {
+                discord: link.discord,
+            })
+            .await??;
+        }
+        run.mismatches.push(Mismatch {
+            user: link.discord,
+            uuid: link.mc_uuid,
+            hypixel_discord,
+        });
+    }
+
+    info!(
+        "Verified links: {} matching, {} mismatched, {} failed",
+        run.verified,
+        run.mismatches.len(),
+        run.failed
+    );
+
+    // a digest without mismatches would only be noise
+    if !run.mismatches.is_empty() {
+        send_digest(http, &config, &run).await?;
+    }
+
+    Ok(())
+}
+
+async fn send_digest(
+    http: &Http,
+    config: &LinkVerificationConfig,
+    run: &VerificationRun,
+) -> Result<()> {
+    let action = if config.auto_unlink {
+        "unlinked"
+    } else {
+        "flagged"
+    };
+
+    let mut list = run
+        .mismatches
+        .iter()
+        .take(MAX_LISTED)
+        .map(|mismatch| {
+            let setting = match &mismatch.hypixel_discord {
+                Some(discord) => format!("Hypixel lists `{discord}`"),
+                None => "no Discord account set on Hypixel".to_string(),
+            };
+            format!(
+                "- {} (`{}`): {setting}",
+                mismatch.user.mention(),
+                mismatch.uuid
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if run.mismatches.len() > MAX_LISTED {
+        list.push_str(&format!(
+            "\n-# ...and {} more",
+            run.mismatches.len() - MAX_LISTED
+        ));
+    }
+
+    let note = if config.auto_unlink {
+        "Their roles were kept, users can link again once their Discord setting matches."
+    } else {
+        "They stay linked. Use `/rolerequest force unlink` to remove a link, or enable automatic \
+         unlinking via `/rolerequest verification`."
+    };
+
+    let text = CreateTextDisplay::new(format!(
+        "## Link Verification
+**{}** of {} re-verified links no longer match the Discord account set on Hypixel and were \
+         {action}:
+{list}
+-# {note}",
+        run.mismatches.len(),
+        run.mismatches.len() as u32 + run.verified,
+    ));
+
+    config
+        .channel
+        .send_message(
+            http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                        .accent_color(WARNING),
+                )]),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use anyhow::{Result, anyhow, bail};
+use poise::serenity_prelude::UserId;
+use serde_json::{Value, json};
+
+use crate::error::UserError;
+use crate::role::types::LinkedUser;
+use crate::shared::types::MinecraftIdent;
+
+const CSV_HEADER: &str = "discord_id,minecraft_uuid";
+
+pub fn to_csv(linked_users: &[LinkedUser]) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for user in linked_users {
+        csv.push_str(&format!("{},{}\n", user.discord, user.mc_uuid));
+    }
+    csv
+}
+
+pub fn to_json(linked_users: &[LinkedUser]) -> String {
+    let links: Vec<_> = linked_users
+        .iter()
+        .map(|user| {
+            json!({
+                // as a string, since JSON numbers can't represent every snowflake exactly
+                "discord_id": user.discord.to_string(),
+                "minecraft_uuid": user.mc_uuid,
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "links": links,
+    }))
+    .unwrap_or_default()
+}
+
+/// Parses links exported by [`to_csv`] or [`to_json`]. CSV files of other bots work as well, as
+/// long as their first two columns are the Discord ID and UUID.
+pub fn parse(contents: &str) -> Result<Vec<LinkedUser>> {
+    let linked_users = if contents.trim_start().starts_with(['{', '[']) {
+        parse_json(contents)?
+    } else {
+        parse_csv(contents)?
+    };
+
+    // links are unique in both directions, so the file can't be imported as a whole otherwise
+    let mut discord_ids = HashSet::new();
+    let mut uuids = HashSet::new();
+    for user in &linked_users {
+        if !discord_ids.insert(user.discord) {
+            bail!(UserError(anyhow!(
+                "Discord ID {} is linked more than once",
+                user.discord
+            )));
+        }
+        if !uuids.insert(user.mc_uuid.as_str()) {
+            bail!(UserError(anyhow!(
+                "UUID `{}` is linked more than once",
+                user.mc_uuid
+            )));
+        }
+    }
+
+    Ok(linked_users)
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<LinkedUser>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        // skip the header, if there is one
+        .filter(|(i, line)| {
+            *i > 0
+                || line
+                    .split(',')
+                    .next()
+                    .is_some_and(|field| field.trim().parse::<u64>().is_ok())
+        })
+        .map(|(i, line)| {
+            let mut fields = line.split(',').map(|field| field.trim().trim_matches('"'));
+            let (Some(discord), Some(uuid)) = (fields.next(), fields.next()) else {
+                bail!(UserError(anyhow!(
+                    "Line {} doesn't contain a Discord ID and UUID",
+                    i + 1
+                )));
+            };
+            parse_link(discord, uuid).map_err(|err| err.context(format!("Line {}", i + 1)))
+        })
+        .collect()
+}
+
+fn parse_json(contents: &str) -> Result<Vec<LinkedUser>> {
+    let value: Value = serde_json::from_str(contents)
+        .map_err(|err| UserError(anyhow!("The file isn't valid JSON: {err}")))?;
+
+    // the export wraps the links in an object, but a bare array is accepted as well
+    let links = match &value {
+        Value::Array(links) => links,
+        Value::Object(object) => object
+            .get("links")
+            .and_then(Value::as_array)
+            .ok_or_else(|| UserError(anyhow!("The file doesn't contain a `links` array")))?,
+        _ => bail!(UserError(anyhow!("The file doesn't contain any links"))),
+    };
+
+    links
+        .iter()
+        .enumerate()
+        .map(|(i, link)| {
+            let discord = match link.get("discord_id") {
+                Some(Value::String(id)) => id.clone(),
+                Some(Value::Number(id)) => id.to_string(),
+                _ => String::new(),
+            };
+            let uuid = link
+                .get("minecraft_uuid")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            parse_link(&discord, uuid).map_err(|err| err.context(format!("Link #{}", i + 1)))
+        })
+        .collect()
+}
+
+fn parse_link(discord: &str, uuid: &str) -> Result<LinkedUser> {
+    let discord = discord
+        .parse::<u64>()
+        .ok()
+        .filter(|id| *id != 0)
+        .map(UserId::new)
+        .ok_or_else(|| UserError(anyhow!("Invalid Discord ID: `{discord}`")))?;
+
+    let MinecraftIdent::Uuid(uuid) = MinecraftIdent::from_input(uuid)? else {
+        bail!(UserError(anyhow!("Invalid Minecraft UUID: `{uuid}`")));
+    };
+
+    Ok(LinkedUser::new(discord, uuid))
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::Result;
+use poise::serenity_prelude::{GuildId, RoleId};
+
+use crate::db::DbHandle;
+use crate::role::{
+    db::role_config::{
+        GetRoleMappingSnapshot, complete_bingo_rank_template, complete_bingo_template,
+        complete_completion_template,
+    },
+    types::{
+        BingoRole, HypixelGuildMapping, NetworkBingo, RoleDelta, RoleMapping, RoleMappingKind,
+        RoleMappingKindRaw, RolePatterns,
+    },
+};
+use crate::shared::{db::GetDataVersion, types::Bingo};
+
+/// Bumped by every write request of this process touching role mappings, patterns or the Hypixel
+/// guild mapping, which makes all cached snapshots stale. Writes of other processes sharing the
+/// database are detected via its data version instead.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Called by write requests after successfully changing role configuration
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// Role configuration rarely changes, but is needed for every role request and kiosk, so it is
+/// kept in memory per guild until the next write
+pub struct RoleMappingCache {
+    /// snapshots along with the generation and data version they were loaded at
+    snapshots: Mutex<HashMap<Option<GuildId>, (u64, i64, Arc<RoleMappingSnapshot>)>>,
+}
+
+impl RoleMappingCache {
+    pub fn new() -> Self {
+        Self {
+            snapshots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(
+        &self,
+        db: &DbHandle,
+        guild_id: Option<GuildId>,
+    ) -> Result<Arc<RoleMappingSnapshot>> {
+        // loaded before querying, so that a write during the query leaves the snapshot stale
+        let generation = GENERATION.load(Ordering::Acquire);
+        let data_version = db.request(GetDataVersion).await??;
+
+        if let Some((cached_generation, cached_data_version, snapshot)) = self
+            .snapshots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&guild_id)
+            && *cached_generation == generation
+            && *cached_data_version == data_version
+        {
+            return Ok(Arc::clone(snapshot));
+        }
+
+        let snapshot = Arc::new(db.request(GetRoleMappingSnapshot).await??);
+        self.snapshots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(guild_id, (generation, data_version, Arc::clone(&snapshot)));
+
+        Ok(snapshot)
+    }
+}
+
+/// All role configuration needed to build role deltas, as loaded at one point in time
+#[derive(Default)]
+pub struct RoleMappingSnapshot {
+    pub mappings: Vec<RoleMapping>,
+    pub patterns: RolePatterns,
+    pub hypixel_guild: Option<HypixelGuildMapping>,
+}
+
+impl RoleMappingSnapshot {
+    pub fn role(&self, kind: RoleMappingKind) -> Option<RoleId> {
+        self.mappings
+            .iter()
+            .find(|mapping| same_kind(mapping.kind, kind))
+            .map(|mapping| mapping.role)
+    }
+
+    fn roles_of(&self, kinds: &[RoleMappingKindRaw]) -> Vec<RoleId> {
+        self.mappings
+            .iter()
+            .filter(|mapping| kinds.contains(&mapping.kind.raw()))
+            .map(|mapping| mapping.role)
+            .collect()
+    }
+
+    /// Roles granted through role requests, except for the immortal role, which may have been
+    /// granted manually
+    pub fn revocable_roles(&self) -> Vec<RoleId> {
+        let mut roles = self.roles_of(&[
+            RoleMappingKindRaw::Completions,
+            RoleMappingKindRaw::SpecificCompletion,
+            RoleMappingKindRaw::BingoRank,
+            RoleMappingKindRaw::NetworkBingo,
+        ]);
+        roles.extend(self.hypixel_guild.as_ref().map(|mapping| mapping.role));
+        roles
+    }
+
+    pub fn completions_delta(&self, bingos: &[Bingo], user_roles: &[RoleId]) -> RoleDelta {
+        let completion_count = bingos.len();
+
+        let completions_role = match self.role(RoleMappingKind::Completions {
+            count: completion_count,
+        }) {
+            Some(role) => Some(BingoRole::Id(role)),
+            None if completion_count == 0 => None,
+            None => self.patterns.completions.as_ref().map(|t| BingoRole::Name {
+                name: complete_completion_template(t, completion_count),
+                kind: RoleMappingKind::Completions {
+                    count: completion_count,
+                },
+            }),
+        };
+
+        // drop all roles, for which there is neither an ID nor a template
+        let mut roles: Vec<BingoRole> = bingos
+            .iter()
+            .filter(|b| b.kind.properties().specific_completion_roles || b.kind_specific_id == 0)
+            .filter_map(|&bingo| {
+                let kind = RoleMappingKind::SpecificCompletion { bingo };
+                match self.role(kind) {
+                    Some(role) => Some(BingoRole::Id(role)),
+                    None => self
+                        .patterns
+                        .specific_completion
+                        .as_ref()
+                        .map(|t| BingoRole::Name {
+                            name: complete_bingo_template(t, &bingo),
+                            kind,
+                        }),
+                }
+            })
+            .collect();
+
+        if let Some(role) = completions_role {
+            roles.push(role);
+        }
+
+        let known_roles = self.roles_of(&[
+            RoleMappingKindRaw::Completions,
+            RoleMappingKindRaw::SpecificCompletion,
+        ]);
+
+        generate_role_delta(known_roles, user_roles, roles)
+    }
+
+    pub fn bingo_rank_delta(&self, rank: u8, user_roles: &[RoleId]) -> RoleDelta {
+        let kind = RoleMappingKind::BingoRank { rank };
+        let roles = match self.role(kind) {
+            Some(role) => vec![BingoRole::Id(role)],
+            None => self
+                .patterns
+                .bingo_rank
+                .as_ref()
+                .map(|t| BingoRole::Name {
+                    name: complete_bingo_rank_template(t, rank),
+                    kind,
+                })
+                .into_iter()
+                .collect(),
+        };
+
+        let known_roles = self.roles_of(&[RoleMappingKindRaw::BingoRank]);
+
+        generate_role_delta(known_roles, user_roles, roles)
+    }
+
+    pub fn immortal_delta(&self, has_achieved: bool, user_roles: &[RoleId]) -> RoleDelta {
+        let add = match self.role(RoleMappingKind::Immortal) {
+            Some(role) if has_achieved && !user_roles.contains(&role) => {
+                vec![BingoRole::Id(role)]
+            }
+            _ => Vec::new(),
+        };
+
+        RoleDelta {
+            add,
+            remove: Vec::new(),
+        }
+    }
+
+    pub fn network_bingos_delta(
+        &self,
+        bingos: &[NetworkBingo],
+        user_roles: &[RoleId],
+    ) -> RoleDelta {
+        let roles = bingos
+            .iter()
+            .filter_map(|&bingo| self.role(RoleMappingKind::NetworkBingo { bingo }))
+            .map(BingoRole::Id)
+            .collect();
+
+        let known_roles = self.roles_of(&[RoleMappingKindRaw::NetworkBingo]);
+
+        generate_role_delta(known_roles, user_roles, roles)
+    }
+
+    pub fn hypixel_guild_delta(&self, is_member: bool, user_roles: &[RoleId]) -> RoleDelta {
+        let Some(mapping) = &self.hypixel_guild else {
+            return RoleDelta {
+                add: Vec::new(),
+                remove: Vec::new(),
+            };
+        };
+
+        let roles = if is_member {
+            vec![BingoRole::Id(mapping.role)]
+        } else {
+            Vec::new()
+        };
+
+        generate_role_delta(vec![mapping.role], user_roles, roles)
+    }
+}
+
+/// Bingos are compared without their unique ID, which isn't stored with role mappings
+fn same_kind(a: RoleMappingKind, b: RoleMappingKind) -> bool {
+    match (a, b) {
+        (RoleMappingKind::Completions { count: a }, RoleMappingKind::Completions { count: b }) => {
+            a == b
+        }
+        (
+            RoleMappingKind::SpecificCompletion { bingo: a },
+            RoleMappingKind::SpecificCompletion { bingo: b },
+        ) => a.kind == b.kind && a.kind_specific_id == b.kind_specific_id,
+        (RoleMappingKind::BingoRank { rank: a }, RoleMappingKind::BingoRank { rank: b }) => a == b,
+        (RoleMappingKind::Immortal, RoleMappingKind::Immortal) => true,
+        (
+            RoleMappingKind::NetworkBingo { bingo: a },
+            RoleMappingKind::NetworkBingo { bingo: b },
+        ) => a == b,
+        _ => false,
+    }
+}
+
+fn generate_role_delta(
+    known_roles: Vec<RoleId>,
+    user_has: &[RoleId],
+    add: Vec<BingoRole>,
+) -> RoleDelta {
+    let add_ids: Vec<&RoleId> = add
+        .iter()
+        .filter_map(|role| match role {
+            BingoRole::Id(id) => Some(id),
+            _ => None,
+        })
+        .collect();
+
+    let remove = known_roles
+        .into_iter()
+        .filter(|role| user_has.contains(role) && !add_ids.contains(&role))
+        .collect();
+
+    let add_filtered: Vec<BingoRole> = add
+        .into_iter()
+        .filter(|role| {
+            !matches!(role,
+                BingoRole::Id(id) if user_has.contains(id),
+            )
+        })
+        .collect();
+
+    RoleDelta {
+        add: add_filtered,
+        remove,
+    }
+}
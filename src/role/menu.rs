@@ -1,20 +1,27 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
 use poise::serenity_prelude::{
-    Component, CreateComponent, CreateContainer, CreateContainerComponent, CreateTextDisplay,
-    GenericChannelId, Http, MessageId, UserId, async_trait,
+    CacheHttp as _, Component, Context as SerenityContext, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateTextDisplay, GenericChannelId, GuildId, Http, MessageId,
+    UserId, async_trait,
 };
+use serde_json::{Value, json};
 use tokio::sync::Notify;
+use tracing::{info, warn};
 
 use crate::db::DbHandle;
-use crate::role::db::role_config::GetRoleMappingsByKind;
+use crate::error;
+use crate::role::db::role_config::{GetRoleMappingCounts, GetRoleMappingsByKind};
 use crate::role::types::RoleMappingKindRaw;
+use crate::shared::BotData;
+use crate::shared::interaction::custom_id::{self, Namespace};
 use crate::shared::menu::navigation::GenerateMenu;
 use crate::shared::menu::{
     MenuMessage,
     timeout::{Expirable, IntoCreate as _},
 };
+use crate::shared::task::spawn_background;
 
 mod configure_roles;
 
@@ -23,6 +30,8 @@ pub struct RoleConfigSession {
     pub menu_id: u64,
     pub state: RoleConfigState,
     pub owner: (UserId, String),
+    /// determines which database the menu operates on
+    pub guild_id: Option<GuildId>,
     pub channel_id: GenericChannelId,
     pub message_id: MessageId,
     pub timeout_reset: Arc<Notify>,
@@ -30,7 +39,7 @@ pub struct RoleConfigSession {
 
 #[async_trait]
 impl Expirable for RoleConfigSession {
-    async fn invalidate<'a>(&'a self, http: Arc<Http>) -> Result<&'a str> {
+    async fn invalidate<'a>(&'a self, http: Arc<Http>, notice: &str) -> Result<&'a str> {
         let (&channel_id, &message_id) = self.message_ids();
 
         let mut components = http.get_message(channel_id, message_id).await?.components;
@@ -48,7 +57,7 @@ impl Expirable for RoleConfigSession {
         }) {
             *container = std::mem::replace(container, CreateContainer::new(Vec::new()))
                 .add_component(CreateContainerComponent::TextDisplay(
-                    CreateTextDisplay::new("-# This menu has expired."),
+                    CreateTextDisplay::new(notice.to_string()),
                 ));
         }
 
@@ -63,16 +72,66 @@ impl Expirable for RoleConfigSession {
     fn message_ids(&self) -> (&GenericChannelId, &MessageId) {
         (&self.channel_id, &self.message_id)
     }
+
+    fn id_prefix(&self) -> String {
+        custom_id::encode(Namespace::Role, &[&"config", &self.menu_id])
+    }
 }
 
 #[derive(Debug)]
 pub struct RoleConfigState {
     pub kind: RoleMappingKindRaw,
     pub page: usize,
+    /// last viewed page of the other categories, restored when switching back to them
+    pub category_pages: HashMap<RoleMappingKindRaw, usize>,
+    /// whether the view with bulk actions, export and transfer is shown instead of the bindings
+    pub more_actions: bool,
 }
 impl RoleConfigState {
     pub fn new(kind: RoleMappingKindRaw, page: usize) -> Self {
-        Self { kind, page }
+        Self {
+            kind,
+            page,
+            category_pages: HashMap::new(),
+            more_actions: false,
+        }
+    }
+
+    /// Switches to another category, remembering the page of the current one
+    pub fn switch_kind(&mut self, kind: RoleMappingKindRaw) {
+        if kind == self.kind {
+            return;
+        }
+        self.category_pages.insert(self.kind, self.page);
+        self.page = self.category_pages.remove(&kind).unwrap_or(0);
+        self.kind = kind;
+    }
+
+    /// Serialises the state to be restored after a restart
+    pub fn to_persisted(&self) -> Value {
+        let category_pages: serde_json::Map<String, Value> = self
+            .category_pages
+            .iter()
+            .map(|(kind, page)| ((*kind as u8).to_string(), json!(page)))
+            .collect();
+        json!({ "kind": self.kind as u8, "page": self.page, "category_pages": category_pages })
+    }
+
+    pub fn from_persisted(value: &Value) -> Option<Self> {
+        let kind = RoleMappingKindRaw::from_u8(u8::try_from(value["kind"].as_u64()?).ok()?)?;
+        let mut state = Self::new(kind, value["page"].as_u64().unwrap_or(0) as usize);
+
+        // menus persisted before page memory existed simply start without it
+        if let Some(pages) = value["category_pages"].as_object() {
+            state.category_pages = pages
+                .iter()
+                .filter_map(|(kind, page)| {
+                    let kind = RoleMappingKindRaw::from_u8(kind.parse().ok()?)?;
+                    Some((kind, page.as_u64()? as usize))
+                })
+                .collect();
+        }
+        Some(state)
     }
 }
 
@@ -82,6 +141,76 @@ impl GenerateMenu for RoleConfigState {
         let role_mappings = db
             .request(GetRoleMappingsByKind { kind: self.kind })
             .await??;
-        Ok(configure_roles::generate(menu_id, &role_mappings, self))
+        let counts = db.request(GetRoleMappingCounts).await??;
+        Ok(configure_roles::generate(
+            menu_id,
+            &role_mappings,
+            &counts,
+            self,
+        ))
+    }
+}
+
+/// Re-renders all open config menus of the same guild except the one with the specified ID, so that
+/// configuration changes are reflected without their owners having to interact with them first.
+/// Runs in the background, so that the triggering interaction can be acknowledged in time.
+pub fn refresh_other_sessions(ctx: &SerenityContext, menu_id: u64, guild_id: Option<GuildId>) {
+    let ctx = ctx.clone();
+    spawn_background("role config refresh", Arc::clone(&ctx.http), async move {
+        refresh_sessions(&ctx, menu_id, guild_id).await;
+    });
+}
+
+async fn refresh_sessions(ctx: &SerenityContext, menu_id: u64, guild_id: Option<GuildId>) {
+    let data = ctx.data::<BotData>();
+
+    // NOTE: lock dropped at the end of the expression
+    let sessions: Vec<_> = data
+        .role_sessions
+        .lock()
+        .await
+        .iter()
+        .filter(|(id, _)| **id != menu_id)
+        .map(|(_, session)| Arc::clone(session))
+        .collect();
+
+    for session_mutex in sessions {
+        // a locked session is currently handling an interaction and will re-render by itself;
+        // waiting for it could also deadlock if it is broadcasting a refresh at the same time
+        let Ok(mut session) = session_mutex.try_lock() else {
+            continue;
+        };
+        if session.guild_id != guild_id {
+            continue;
+        }
+
+        let menu_id = session.menu_id;
+        let result: Result<()> = async {
+            let menu = session
+                .state
+                .generate(data.db_for(guild_id), menu_id)
+                .await?;
+            ctx.http()
+                .edit_message(
+                    session.channel_id,
+                    session.message_id,
+                    &menu.into_edit(),
+                    vec![],
+                )
+                .await?;
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => (),
+            // the menu can't be interacted with anymore, so there is nobody left to notify
+            Err(err) if error::is_unknown_message(&err) => {
+                drop(session);
+                data.role_sessions.lock().await.remove(&menu_id);
+                info!("Dropped role config menu {menu_id}, as its message was deleted");
+            }
+            Err(err) => warn!("Failed to refresh role config menu {menu_id}: {err:#}"),
+        }
     }
 }
@@ -1,27 +1,47 @@
+use std::collections::{HashMap, HashSet};
+
 use poise::serenity_prelude::{
     ButtonStyle, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
     CreateContainerComponent, CreateSection, CreateSectionAccessory, CreateSectionComponent,
-    CreateSelectMenu, CreateSelectMenuOption, CreateSeparator, CreateTextDisplay,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, CreateSeparator,
+    CreateTextDisplay,
 };
 
 use crate::role::types::RoleMappingKindRaw;
 use crate::role::{menu::RoleConfigState, types::RoleMapping};
-use crate::shared::menu::{
-    MenuMessage,
-    navigation::{self, PaginatedChunk},
+use crate::shared::{
+    interaction::custom_id::{self, Namespace},
+    menu::{
+        MenuMessage,
+        navigation::{self, PaginatedChunk},
+        transfer,
+    },
 };
 
-const PAGE_SIZE: usize = 6;
+// NOTE: Discord limits messages to 40 components (nested ones included), the main page uses 39
+const PAGE_SIZE: usize = 5;
+/// Discord's limit of options in a select menu
+const MAX_SELECT_OPTIONS: usize = 25;
 
 pub fn generate(
     menu_id: u64,
     role_mappings: &[RoleMapping],
+    counts: &HashMap<RoleMappingKindRaw, usize>,
     session_state: &mut RoleConfigState,
 ) -> MenuMessage<'static> {
-    let id_prefix = format!("role:config:{menu_id}");
+    let id_prefix = custom_id::encode(Namespace::Role, &[&"config", &menu_id]);
 
     let chunk = PaginatedChunk::new(role_mappings.len(), session_state.page, PAGE_SIZE);
     session_state.page = chunk.page;
+
+    if session_state.more_actions {
+        return generate_more_actions(
+            &id_prefix,
+            &role_mappings[chunk.range.start..],
+            session_state.kind,
+        );
+    }
+
     let role_mappings_paginated = &role_mappings[chunk.range.clone()];
 
     let divider = CreateContainerComponent::Separator(CreateSeparator::new(true));
@@ -56,18 +76,51 @@ pub fn generate(
         ),
     ));
 
-    let category_options = vec![
-        CreateSelectMenuOption::new("Bingo Rank Roles", "bingo_rank")
-            .default_selection(session_state.kind == RoleMappingKindRaw::BingoRank),
-        CreateSelectMenuOption::new("Blackout Roles", "completions")
-            .default_selection(session_state.kind == RoleMappingKindRaw::Completions),
-        CreateSelectMenuOption::new("Specific Blackout Roles", "specific_completion")
-            .default_selection(session_state.kind == RoleMappingKindRaw::SpecificCompletion),
-        CreateSelectMenuOption::new("Network Bingo Roles", "network_bingo")
-            .default_selection(session_state.kind == RoleMappingKindRaw::NetworkBingo),
-        CreateSelectMenuOption::new("Immortal Role", "immortal")
-            .default_selection(session_state.kind == RoleMappingKindRaw::Immortal),
-    ];
+    let more_actions_section = CreateContainerComponent::Section(CreateSection::new(
+        vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+            "-# Delete several bindings at once, export the config or hand this menu over.",
+        ))],
+        CreateSectionAccessory::Button(
+            CreateButton::new(format!("{id_prefix}:more_actions"))
+                .label("More Actions")
+                .style(ButtonStyle::Secondary),
+        ),
+    ));
+
+    let category_options = [
+        (
+            "Bingo Rank Roles",
+            "bingo_rank",
+            RoleMappingKindRaw::BingoRank,
+        ),
+        (
+            "Blackout Roles",
+            "completions",
+            RoleMappingKindRaw::Completions,
+        ),
+        (
+            "Specific Blackout Roles",
+            "specific_completion",
+            RoleMappingKindRaw::SpecificCompletion,
+        ),
+        (
+            "Network Bingo Roles",
+            "network_bingo",
+            RoleMappingKindRaw::NetworkBingo,
+        ),
+        ("Immortal Role", "immortal", RoleMappingKindRaw::Immortal),
+    ]
+    .into_iter()
+    .map(|(label, value, kind)| {
+        let count = counts.get(&kind).copied().unwrap_or_default();
+        CreateSelectMenuOption::new(label, value)
+            .description(format!(
+                "{count} {}",
+                if count == 1 { "binding" } else { "bindings" }
+            ))
+            .default_selection(session_state.kind == kind)
+    })
+    .collect::<Vec<_>>();
 
     let category_select = CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
         CreateSelectMenu::new(
@@ -88,7 +141,9 @@ pub fn generate(
     let category_section = CreateContainerComponent::Section(CreateSection::new(
         vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
             format!(
-                "Showing {}–{} of {} entries.",
+                "**Page {}/{}** · Showing {}–{} of {} entries.",
+                chunk.page + 1,
+                chunk.total_pages.max(1),
                 chunk.range.start + 1,
                 chunk.range.end,
                 role_mappings.len()
@@ -102,6 +157,7 @@ pub fn generate(
     let components: Vec<_> = [
         title_section,
         description_section,
+        more_actions_section,
         divider.clone(),
         category_select,
         category_section,
@@ -116,3 +172,97 @@ pub fn generate(
 
     MenuMessage::new(vec![container])
 }
+
+/// Actions that don't fit on the main page, kept in a separate view
+fn generate_more_actions(
+    id_prefix: &str,
+    role_mappings: &[RoleMapping],
+    kind: RoleMappingKindRaw,
+) -> MenuMessage<'static> {
+    let divider = CreateContainerComponent::Separator(CreateSeparator::new(true));
+
+    let title = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+        "# More Actions
+Bulk actions apply to the *{}* category.",
+        kind.name()
+    )));
+
+    let bulk_delete: Vec<_> = if role_mappings.is_empty() {
+        Vec::new()
+    } else {
+        vec![
+            bulk_delete_select(id_prefix, role_mappings),
+            CreateContainerComponent::Section(CreateSection::new(
+                vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+                    format!(
+                        "-# Delete all bindings of the *{}* category at once.",
+                        kind.name()
+                    ),
+                ))],
+                CreateSectionAccessory::Button(
+                    CreateButton::new(format!("{id_prefix}:clear_category:{}", kind as u8))
+                        .label("Clear Category")
+                        .style(ButtonStyle::Danger),
+                ),
+            )),
+            divider.clone(),
+        ]
+    };
+
+    let export_section = CreateContainerComponent::Section(CreateSection::new(
+        vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+            "-# Copy all bindings and patterns to another server using `/rolerequest config-import`.",
+        ))],
+        CreateSectionAccessory::Button(
+            CreateButton::new(format!("{id_prefix}:export_config"))
+                .label("Export Config")
+                .style(ButtonStyle::Secondary),
+        ),
+    ));
+
+    let components: Vec<_> = [title, divider.clone()]
+        .into_iter()
+        .chain(bulk_delete)
+        .chain([
+            export_section,
+            transfer::section(id_prefix),
+            divider,
+            navigation::nagivation_back(id_prefix),
+        ])
+        .collect();
+
+    let container = CreateComponent::Container(CreateContainer::new(components));
+
+    MenuMessage::new(vec![container])
+}
+
+/// Multi-select for deleting several bindings of the category, starting at the current page
+fn bulk_delete_select(
+    id_prefix: &str,
+    role_mappings: &[RoleMapping],
+) -> CreateContainerComponent<'static> {
+    // a role can't be bound twice within a category, but the option values must be unique either way
+    let mut seen = HashSet::new();
+    let options: Vec<_> = role_mappings
+        .iter()
+        .filter(|mapping| seen.insert(mapping.role))
+        .take(MAX_SELECT_OPTIONS)
+        .map(|mapping| {
+            CreateSelectMenuOption::new(mapping.kind.to_string(), mapping.role.to_string())
+                .description(format!("Role ID {}", mapping.role))
+        })
+        .collect();
+    let max_values = options.len() as u8;
+
+    CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{id_prefix}:delete_selected"),
+            CreateSelectMenuKind::String {
+                options: options.into(),
+            },
+        )
+        .placeholder("Select bindings to delete.")
+        .min_values(1)
+        .max_values(max_values),
+    ))
+}
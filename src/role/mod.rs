@@ -1,5 +1,13 @@
+pub mod applications;
+pub mod cache_pruning;
+pub mod config_export;
 pub mod db;
 pub mod interaction;
+pub mod link_verification;
+pub mod links;
+pub mod mapping_cache;
 pub mod menu;
+pub mod network_bingo;
+pub mod rejoin;
 pub mod request;
 pub mod types;
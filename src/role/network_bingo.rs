@@ -0,0 +1,183 @@
+//! Network bingo events added after [`NetworkBingo::BUILTIN`], so that new seasonal bingos don't
+//! require a code change. Events are discovered in the `seasonal` section of the player endpoint,
+//! or registered by staff ahead of time via `/rolerequest networkbingo-register`.
+
+use std::{
+    sync::{PoisonError, RwLock},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::Utc;
+use tracing::info;
+
+use crate::db::DbHandle;
+use crate::error::UserError;
+use crate::role::types::NetworkBingo;
+use crate::shared::db::{GetRegisteredNetworkBingos, RegisterNetworkBingo};
+
+/// Loaded on startup and reloaded by [`register`] and [`reload`], as it is needed by synchronous code
+/// such as cache requests and `Display`
+static REGISTRY: RwLock<Vec<RegisteredNetworkBingo>> = RwLock::new(Vec::new());
+
+/// Wait between reloads of the registry, see [`reload`]
+const RELOAD_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Keys of the built-in events in the `seasonal` section, which are detected separately
+const BUILTIN_KEYS: [(&str, &str); 8] = [
+    ("anniversary", "2023"),
+    ("halloween", "2023"),
+    ("christmas", "2023"),
+    ("easter", "2024"),
+    ("summer", "2024"),
+    ("halloween", "2024"),
+    ("easter", "2025"),
+    ("easter", "2026"),
+];
+
+/// How completing a registered event is detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum DetectionRule {
+    #[name = "Blackout on every difficulty"]
+    AllDifficulties,
+    #[name = "Blackout on easy and hard of any card type"]
+    DifficultyPair,
+}
+
+impl DetectionRule {
+    pub fn key(self) -> &'static str {
+        match self {
+            DetectionRule::AllDifficulties => "all_difficulties",
+            DetectionRule::DifficultyPair => "difficulty_pair",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "all_difficulties" => Some(DetectionRule::AllDifficulties),
+            "difficulty_pair" => Some(DetectionRule::DifficultyPair),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisteredNetworkBingo {
+    pub bingo: NetworkBingo,
+    /// keys of the event in the `seasonal` section of the player endpoint, e.g. `halloween` and
+    /// `2025`. The year is stored trimmed, as the API pads some of them with spaces.
+    pub season: String,
+    pub year: String,
+    pub name: String,
+    pub rule: DetectionRule,
+}
+
+pub fn registered() -> Vec<RegisteredNetworkBingo> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+pub fn registered_ids() -> Vec<NetworkBingo> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|event| event.bingo)
+        .collect()
+}
+
+pub fn registered_name(bingo: NetworkBingo) -> Option<String> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|event| event.bingo == bingo)
+        .map(|event| event.name.clone())
+}
+
+/// Whether the event is built in or already registered. The API pads some years with spaces.
+pub fn is_known(season: &str, year: &str) -> bool {
+    let year = year.trim();
+    BUILTIN_KEYS.contains(&(season, year))
+        || REGISTRY
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|event| event.season == season && event.year == year)
+}
+
+pub async fn load(db: &DbHandle) -> Result<()> {
+    let events = db.request(GetRegisteredNetworkBingos).await??;
+    if !events.is_empty() {
+        info!("Loaded {} registered Network Bingo(s)", events.len());
+    }
+    *REGISTRY.write().unwrap_or_else(PoisonError::into_inner) = events;
+    Ok(())
+}
+
+/// Picks up events registered by other processes sharing the database, returning the wait until the
+/// next reload. Run by the scheduler in every process, as each keeps its own copy of the registry.
+pub async fn reload(db: &DbHandle) -> Result<Duration> {
+    let events = db.request(GetRegisteredNetworkBingos).await??;
+    *REGISTRY.write().unwrap_or_else(PoisonError::into_inner) = events;
+    Ok(RELOAD_INTERVAL)
+}
+
+/// Registers a new event with the next free ID, or updates the name of an already registered one,
+/// whose detection rule is kept. Always pass the main database, as the registry is shared by all
+/// guilds.
+pub async fn register(
+    db: &DbHandle,
+    season: &str,
+    year: &str,
+    name: Option<String>,
+    rule: DetectionRule,
+) -> Result<RegisteredNetworkBingo> {
+    let season = season.trim().to_lowercase();
+    let year = year.trim().to_string();
+    if BUILTIN_KEYS.contains(&(season.as_str(), year.as_str())) {
+        bail!(UserError(anyhow!(
+            "This Network Bingo is built in and can't be changed"
+        )));
+    }
+
+    let bingo = db
+        .request(RegisterNetworkBingo {
+            default_name: default_name(&season, &year),
+            season,
+            year,
+            name,
+            rule,
+            registered_at: Utc::now().timestamp(),
+        })
+        .await??;
+
+    // reloading also picks up events registered by other processes in the meantime
+    let events = db.request(GetRegisteredNetworkBingos).await??;
+    let event = events
+        .iter()
+        .find(|event| event.bingo == bingo)
+        .cloned()
+        .context("Registered Network Bingo is missing")?;
+    *REGISTRY.write().unwrap_or_else(PoisonError::into_inner) = events;
+
+    info!(
+        "Registered Network Bingo '{}' with ID {}",
+        event.name,
+        event.bingo.id()
+    );
+
+    Ok(event)
+}
+
+/// e.g. `Halloween Bingo 2025`
+fn default_name(season: &str, year: &str) -> String {
+    let mut chars = season.chars();
+    let season: String = chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default();
+    format!("{season} Bingo {year}")
+}
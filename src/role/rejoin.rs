@@ -0,0 +1,134 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use poise::serenity_prelude::{
+    Context as SerenityContext, CreateAllowedMentions, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, GenericChannelId, GuildId, Member,
+    Mentionable as _, MessageFlags, RoleId, UserId, colours::css::POSITIVE,
+};
+use tracing::{error, info};
+
+use crate::role::{
+    db::{link::GetLinkedUserByDiscord, role_config::GetRejoinRestoreConfig},
+    request::{self, RoleRequestStatus},
+    types::RoleUpdateTrigger,
+};
+use crate::shared::{BotData, task::spawn_background};
+
+/// Members lose their roles when leaving, so linked users get their earned roles back (primarily
+/// from cached stats) once they rejoin
+pub async fn member_joined(ctx: &SerenityContext, member: &Member) -> Result<()> {
+    if member.user.bot() {
+        return Ok(());
+    }
+
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(Some(member.guild_id));
+
+    let Some(config) = db.request(GetRejoinRestoreConfig).await?? else {
+        return Ok(());
+    };
+    let Some(linked_user) = db
+        .request(GetLinkedUserByDiscord {
+            discord: member.user.id,
+        })
+        .await??
+    else {
+        return Ok(());
+    };
+
+    let ctx = ctx.clone();
+    let guild_id = member.guild_id;
+    let user_id = member.user.id;
+
+    spawn_background("rejoin role restore", Arc::clone(&ctx.http), async move {
+        tokio::time::sleep(Duration::from_secs(config.delay_secs)).await;
+
+        if let Err(err) = restore_roles(
+            &ctx,
+            guild_id,
+            user_id,
+            &linked_user.mc_uuid,
+            config.log_channel,
+        )
+        .await
+        {
+            error!("Failed to restore roles of rejoined member {user_id}: {err:#}");
+        }
+    });
+
+    Ok(())
+}
+
+async fn restore_roles(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    user_id: UserId,
+    uuid: &str,
+    log_channel: Option<GenericChannelId>,
+) -> Result<()> {
+    // refetch, as the member might have left again or been given roles in the meantime
+    let Ok(member) = guild_id.member(&ctx.http, user_id).await else {
+        info!("Member {user_id} left before their roles could be restored");
+        return Ok(());
+    };
+
+    let status = request::update_roles(ctx, uuid, &member, RoleUpdateTrigger::Rejoin).await?;
+
+    let RoleRequestStatus::Updated {
+        added,
+        removed,
+        roles,
+    } = status
+    else {
+        return Ok(());
+    };
+
+    info!(
+        "Restored {} role(s) of rejoined member {user_id} ({})",
+        added.len(),
+        roles.username
+    );
+
+    if let Some(channel) = log_channel {
+        let text = CreateTextDisplay::new(format!(
+            "## Restored Roles
+{} rejoined and had their roles restored from their linked account `{}`.
+### Restored
+{}
+### Removed
+{}",
+            user_id.mention(),
+            roles.username,
+            role_list(&added),
+            role_list(&removed),
+        ));
+
+        channel
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![CreateComponent::Container(
+                        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                            .accent_color(POSITIVE),
+                    )]),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn role_list(roles: &[RoleId]) -> String {
+    if roles.is_empty() {
+        return "*None*".to_string();
+    }
+
+    roles
+        .iter()
+        .map(|role| format!("- {}", role.mention()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
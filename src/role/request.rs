@@ -1,53 +1,58 @@
-use std::{borrow::Cow, sync::Arc};
+use std::borrow::Cow;
 
 use anyhow::{Context as _, Result};
 use chrono::Utc;
 use poise::serenity_prelude::{
     ButtonStyle, CacheHttp as _, Context as SerenityContext, CreateButton, CreateComponent,
     CreateContainer, CreateContainerComponent, CreateSection, CreateSectionAccessory,
-    CreateSectionComponent, CreateTextDisplay, Member, Mentionable as _, RoleId, User, UserId,
+    CreateSectionComponent, CreateTextDisplay, GuildId, Member, Mentionable as _, RoleId, User,
+    UserId,
     colours::{branding::YELLOW, css::POSITIVE},
 };
 use tracing::warn;
 
 use crate::config::BOT_MAINTAINER;
+use crate::db::DbHandle;
+use crate::metrics;
 use crate::role::{
     db::{
+        audit::RecordRoleUpdate,
         cache::{
             CacheBingoRank, CacheCompletions, CacheImmortal, CacheNetworkBingos, CachedBingoRank,
-            CachedCompletions, CachedImmortal, CachedNetworkBingos,
+            CachedCompletions, CachedImmortal, CachedNetworkBingos, FindPlayerByCachedDiscord,
         },
         link::InsertLinkedUser,
-        role_config::{
-            BuildRoleDeltaBingoRank, BuildRoleDeltaCompletions, BuildRoleDeltaImmortal,
-            BuildRoleDeltaNetworkBingos,
-        },
     },
-    types::{LinkStatus, LinkedUser, NetworkBingo},
+    types::{
+        LinkStatus, LinkedUser, NetworkBingo, RoleDeltaResolved, RoleUpdateOutcome,
+        RoleUpdateTrigger,
+    },
 };
 use crate::shared::{
     BotData,
     db::{GetBingoData, GetIsNetworkBingo},
-    types::{Bingo, BitSet},
+    interaction::custom_id::{self, Namespace},
+    types::{Bingo, BitSet, MinecraftIdent},
 };
 
-pub async fn link_user(ctx: &SerenityContext, user: &User, mc_name: &str) -> Result<LinkStatus> {
+pub async fn link_user(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    user: &User,
+    minecraft: &MinecraftIdent,
+) -> Result<LinkStatus> {
     let data = ctx.data::<BotData>();
-    let db = &data.db_handle;
+    let db = data.db_for(guild_id);
     let api = &data.api_handle;
 
-    let uuid = api.uuid(mc_name).await?;
+    let uuid = api.uuid(db, minecraft).await?;
 
     let discord = api.linked_discord(db, &uuid).await?;
 
     match discord {
         None => Ok(LinkStatus::NoDiscord),
         Some(linked) => {
-            let discriminator = user
-                .discriminator
-                .map_or(Cow::Borrowed(""), |d| Cow::Owned(format!("#{}", d.get())));
-
-            if linked != format!("{}{}", user.name, discriminator) {
+            if linked != full_username(user) {
                 return Ok(LinkStatus::DifferentDiscord {
                     other_discord: linked,
                 });
@@ -61,7 +66,7 @@ pub async fn link_user(ctx: &SerenityContext, user: &User, mc_name: &str) -> Res
 
             if let Some(uuid) = duplicate_uuid {
                 return Ok(LinkStatus::DuplicateMinecraft {
-                    other_username: api.username(&uuid).await?,
+                    other_username: api.username(db, &uuid).await?,
                 });
             }
             if let Some(discord) = duplicate_discord {
@@ -76,6 +81,40 @@ pub async fn link_user(ctx: &SerenityContext, user: &User, mc_name: &str) -> Res
     }
 }
 
+/// Username as it would be entered in Hypixel's Discord setting, including legacy discriminators
+pub fn full_username(user: &User) -> String {
+    let discriminator = user
+        .discriminator
+        .map_or(Cow::Borrowed(""), |d| Cow::Owned(format!("#{}", d.get())));
+
+    format!("{}{}", user.name, discriminator)
+}
+
+/// Looks for a previously fetched Hypixel profile whose Discord setting matches the user, returning
+/// its UUID and username
+pub async fn suggest_link(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    user: &User,
+) -> Result<Option<(String, String)>> {
+    let data = ctx.data::<BotData>();
+
+    let db = data.db_for(guild_id);
+
+    let Some(uuid) = db
+        .request(FindPlayerByCachedDiscord {
+            discord: full_username(user),
+        })
+        .await??
+    else {
+        return Ok(None);
+    };
+
+    let username = data.api_handle.username(db, &uuid).await?;
+
+    Ok(Some((uuid, username)))
+}
+
 pub enum RoleRequestStatus {
     Updated {
         added: Vec<RoleId>,
@@ -101,42 +140,22 @@ impl RoleRequestStatus {
             None => Cow::Borrowed("Your"),
         };
 
-        let unlink_button = CreateButton::new("role:request:unlink")
-            .label("Unlink Account")
-            .style(ButtonStyle::Danger);
+        let unlink_button =
+            CreateButton::new(custom_id::encode(Namespace::Role, &[&"request", &"unlink"]))
+                .label("Unlink Account")
+                .style(ButtonStyle::Danger);
 
         let diff_text = match self {
             RoleRequestStatus::Updated { added, removed, .. } => {
-                let added_mentions = if added.is_empty() {
-                    Cow::Borrowed("*None*")
-                } else {
-                    Cow::Owned(
-                        added
-                            .iter()
-                            .map(|role| role.mention().to_string())
-                            .collect::<Vec<_>>()
-                            .join("\n"),
-                    )
-                };
-                let removed_mentions = if removed.is_empty() {
-                    Cow::Borrowed("*None*")
-                } else {
-                    Cow::Owned(
-                        removed
-                            .iter()
-                            .map(|role| role.mention().to_string())
-                            .collect::<Vec<_>>()
-                            .join("\n"),
-                    )
-                };
-
                 CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
                     "## Roles Updated Successfully
 {user_mention} roles were updated successfully.
 ### Added
-{added_mentions}
+{}
 ### Removed
-{removed_mentions}"
+{}",
+                    mention_list(added),
+                    mention_list(removed)
                 )))
             }
             RoleRequestStatus::NoChanges { .. } => {
@@ -170,12 +189,91 @@ impl RoleRequestStatus {
     }
 }
 
+/// Roles an update would add and remove, computed without applying them
+pub struct RolePreview {
+    pub added: Vec<RoleId>,
+    pub removed: Vec<RoleId>,
+    /// names of missing roles that automatic role creation would create and add
+    pub would_create: Vec<String>,
+    pub roles: PlayerRoles,
+}
+
+impl RolePreview {
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty() || !self.would_create.is_empty()
+    }
+
+    pub fn to_message(&self, other_user: Option<&UserId>) -> CreateComponent<'static> {
+        let user_mention = match other_user {
+            Some(id) => Cow::Owned(format!("{}'s", id.mention())),
+            None => Cow::Borrowed("your"),
+        };
+
+        let diff_text = if !self.has_changes() {
+            format!(
+                "## Role Preview
+Updating {user_mention} roles wouldn't change anything."
+            )
+        } else {
+            let created = if self.would_create.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n### Would Create and Add\n{}",
+                    self.would_create
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            format!(
+                "## Role Preview
+Updating {user_mention} roles would make the following changes.
+### Would Add
+{}{created}
+### Would Remove
+{}",
+                mention_list(&self.added),
+                mention_list(&self.removed)
+            )
+        };
+
+        CreateComponent::Container(
+            CreateContainer::new(vec![
+                CreateContainerComponent::TextDisplay(CreateTextDisplay::new(diff_text)),
+                CreateContainerComponent::TextDisplay(self.roles.to_text_display()),
+                CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+                    "-# This is a preview, no roles were changed.",
+                )),
+            ])
+            .accent_color(YELLOW),
+        )
+    }
+}
+
+fn mention_list(roles: &[RoleId]) -> Cow<'static, str> {
+    if roles.is_empty() {
+        Cow::Borrowed("*None*")
+    } else {
+        Cow::Owned(
+            roles
+                .iter()
+                .map(|role| role.mention().to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
 pub struct PlayerRoles {
     pub username: String,
     pub blackouts: Vec<Bingo>,
     pub bingo_rank: u8,
     pub immortal: bool,
     pub network_bingos: Vec<NetworkBingo>,
+    /// the stats were fetched while Hypixel's API was unstable, so cached data may be outdated
+    pub stale: bool,
 }
 
 impl PlayerRoles {
@@ -216,8 +314,13 @@ impl PlayerRoles {
 {blackout_list}
 ### Network Bingos
 {network_bingo_list}
-\n-# Please report any issues to {}.",
+{}\n-# Please report any issues to {}.",
             self.username,
+            if self.stale {
+                "\n-# :warning: Hypixel's API is currently unstable, so these results may be stale."
+            } else {
+                ""
+            },
             BOT_MAINTAINER.mention()
         ))
     }
@@ -227,52 +330,43 @@ pub async fn update_roles(
     ctx: &SerenityContext,
     uuid: &str,
     discord_user: &Member,
+    trigger: RoleUpdateTrigger,
 ) -> Result<RoleRequestStatus> {
-    let db = &ctx.data::<BotData>().db_handle;
-
-    let discord_roles = Arc::new(discord_user.roles.clone());
-
-    let player_roles = player_roles(ctx, uuid).await?;
-
-    let mut role_delta = db
-        .request(BuildRoleDeltaCompletions {
-            bingos: player_roles.blackouts.clone(),
-            user_roles: Arc::clone(&discord_roles),
-        })
-        .await??;
-
-    role_delta.merge(
-        db.request(BuildRoleDeltaNetworkBingos {
-            bingos: player_roles.network_bingos.clone(),
-            user_roles: Arc::clone(&discord_roles),
-        })
-        .await??,
-    );
+    let db = ctx.data::<BotData>().db_for(Some(discord_user.guild_id));
 
-    role_delta.merge(
-        db.request(BuildRoleDeltaBingoRank {
-            rank: player_roles.bingo_rank,
-            user_roles: Arc::clone(&discord_roles),
-        })
-        .await??,
-    );
+    let status = apply_roles(ctx, db, uuid, discord_user, trigger).await;
 
-    role_delta.merge(
-        db.request(BuildRoleDeltaImmortal {
-            has_achieved: player_roles.immortal,
-            user_roles: Arc::clone(&discord_roles),
+    // counted per entry point, so that `/rolerequest query stats` shows which flows carry the load
+    let outcome = match &status {
+        Ok(RoleRequestStatus::Updated { .. }) => RoleUpdateOutcome::Updated,
+        Ok(RoleRequestStatus::NoChanges { .. }) => RoleUpdateOutcome::Unchanged,
+        Err(_) => RoleUpdateOutcome::Failed,
+    };
+    metrics::record_role_update(trigger.kind().label(), outcome.name());
+    let recorded = db
+        .request(RecordRoleUpdate {
+            trigger,
+            outcome,
+            timestamp: Utc::now().timestamp(),
         })
-        .await??,
-    );
+        .await;
+    match recorded {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => warn!("Failed to record role update: {err:#}"),
+        Err(err) => warn!("Failed to record role update: {err:#}"),
+    }
 
-    let guild_roles = discord_user
-        .guild_id
-        .roles(ctx.http())
-        .await?
-        .into_iter()
-        .collect();
+    status
+}
 
-    let role_delta = role_delta.resolve(db, guild_roles).await?;
+async fn apply_roles(
+    ctx: &SerenityContext,
+    db: &DbHandle,
+    uuid: &str,
+    discord_user: &Member,
+    trigger: RoleUpdateTrigger,
+) -> Result<RoleRequestStatus> {
+    let (player_roles, role_delta) = role_delta(ctx, uuid, discord_user, false).await?;
 
     if role_delta.is_empty() {
         return Ok(RoleRequestStatus::NoChanges {
@@ -281,7 +375,7 @@ pub async fn update_roles(
     }
 
     role_delta
-        .apply(ctx.http(), discord_user)
+        .apply(ctx.http(), db, discord_user, trigger)
         .await
         .context("Failed to update user's roles")?;
 
@@ -292,14 +386,124 @@ pub async fn update_roles(
     })
 }
 
-pub async fn player_roles(ctx: &SerenityContext, uuid: &str) -> Result<PlayerRoles> {
+/// Removes the roles granted through role requests, e.g. from a member whose link moved to another
+/// Discord account. Returns the removed roles.
+pub async fn revoke_roles(
+    ctx: &SerenityContext,
+    discord_user: &Member,
+    trigger: RoleUpdateTrigger,
+) -> Result<Vec<RoleId>> {
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(Some(discord_user.guild_id));
+
+    let mappings = data
+        .role_mappings
+        .get(db, Some(discord_user.guild_id))
+        .await?;
+
+    let mut remove: Vec<RoleId> = mappings
+        .revocable_roles()
+        .into_iter()
+        .filter(|role| discord_user.roles.contains(role))
+        .collect();
+    remove.sort_unstable();
+    remove.dedup();
+    if remove.is_empty() {
+        return Ok(remove);
+    }
+
+    let role_delta = RoleDeltaResolved {
+        add: Vec::new(),
+        remove,
+        would_create: Vec::new(),
+    };
+    role_delta
+        .apply(ctx.http(), db, discord_user, trigger)
+        .await
+        .context("Failed to remove user's roles")?;
+
+    Ok(role_delta.remove)
+}
+
+/// Computes the same changes as [`update_roles`] without applying them, e.g. to debug the role
+/// mapping configuration
+pub async fn preview_roles(
+    ctx: &SerenityContext,
+    uuid: &str,
+    discord_user: &Member,
+) -> Result<RolePreview> {
+    let (roles, role_delta) = role_delta(ctx, uuid, discord_user, true).await?;
+
+    Ok(RolePreview {
+        added: role_delta.add,
+        removed: role_delta.remove,
+        would_create: role_delta.would_create,
+        roles,
+    })
+}
+
+async fn role_delta(
+    ctx: &SerenityContext,
+    uuid: &str,
+    discord_user: &Member,
+    preview: bool,
+) -> Result<(PlayerRoles, RoleDeltaResolved)> {
     let data = ctx.data::<BotData>();
-    let db = &data.db_handle;
+    let db = data.db_for(Some(discord_user.guild_id));
+
+    let discord_roles: &[RoleId] = &discord_user.roles;
+
+    let player_roles = player_roles(ctx, Some(discord_user.guild_id), uuid).await?;
+
+    let mappings = data
+        .role_mappings
+        .get(db, Some(discord_user.guild_id))
+        .await?;
+
+    let mut role_delta = mappings.completions_delta(&player_roles.blackouts, discord_roles);
+    role_delta.merge(mappings.network_bingos_delta(&player_roles.network_bingos, discord_roles));
+    role_delta.merge(mappings.bingo_rank_delta(player_roles.bingo_rank, discord_roles));
+    role_delta.merge(mappings.immortal_delta(player_roles.immortal, discord_roles));
+
+    if let Some(mapping) = &mappings.hypixel_guild {
+        let members = data.api_handle.guild_members(db, &mapping.guild_id).await?;
+        let is_member = members.iter().any(|member| {
+            member
+                .replace('-', "")
+                .eq_ignore_ascii_case(&uuid.replace('-', ""))
+        });
+
+        role_delta.merge(mappings.hypixel_guild_delta(is_member, discord_roles));
+    }
+
+    let guild_roles = discord_user
+        .guild_id
+        .roles(ctx.http())
+        .await?
+        .into_iter()
+        .collect();
+
+    let role_delta = role_delta
+        .resolve(ctx.http(), discord_user.guild_id, db, guild_roles, preview)
+        .await?;
+
+    Ok((player_roles, role_delta))
+}
+
+pub async fn player_roles(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    uuid: &str,
+) -> Result<PlayerRoles> {
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(guild_id);
     let api = &data.api_handle;
+    // decided upfront, as the cached data is served as soon as the API is degraded
+    let stale = api.is_degraded();
 
     let (current_bingo, _, bingo_end) = api.update_current_bingo(db).await?;
     let bingo_ended = Utc::now().timestamp() > bingo_end;
-    let current_network_bingo = NetworkBingo::ALL.last().map(|b| *b as u8).unwrap_or(0);
+    let current_network_bingo = NetworkBingo::latest();
     let network_bingo_active = db.request(GetIsNetworkBingo).await??.unwrap_or(false);
 
     let bingo_completions = db
@@ -350,14 +554,14 @@ pub async fn player_roles(ctx: &SerenityContext, uuid: &str) -> Result<PlayerRol
             .collect(),
         // cache miss
         None => {
-            let completions = api.network_bingo_completions(db, uuid).await?;
-            if completions.contains(&NetworkBingo::from_u8(current_network_bingo))
-                || !network_bingo_active
-            {
+            let completions = api
+                .network_bingo_completions(db, &data.db_handle, uuid)
+                .await?;
+            if completions.contains(&current_network_bingo) || !network_bingo_active {
                 db.request(CacheNetworkBingos {
                     uuid: uuid.to_string(),
                     completions: BitSet::from_indexes(
-                        &completions.iter().map(|b| *b as u8).collect::<Vec<_>>(),
+                        &completions.iter().map(|b| b.id()).collect::<Vec<_>>(),
                     ),
                 })
                 .await??;
@@ -424,7 +628,7 @@ pub async fn player_roles(ctx: &SerenityContext, uuid: &str) -> Result<PlayerRol
         }
     };
 
-    let username = api.username(uuid).await?;
+    let username = api.username(db, uuid).await?;
 
     Ok(PlayerRoles {
         username,
@@ -432,5 +636,6 @@ pub async fn player_roles(ctx: &SerenityContext, uuid: &str) -> Result<PlayerRol
         bingo_rank,
         immortal,
         network_bingos: network_bingo_completions,
+        stale,
     })
 }
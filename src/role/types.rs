@@ -1,16 +1,32 @@
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
 
 use anyhow::Result;
 use poise::serenity_prelude::{
-    ButtonStyle, CacheHttp as _, CreateButton, CreateComponent, CreateContainer,
-    CreateContainerComponent, CreateSection, CreateSectionAccessory, CreateSectionComponent,
-    CreateTextDisplay, EditMember, Http, Member, Mentionable as _, Permissions, Role, RoleId,
-    UserId,
+    ButtonStyle, CacheHttp as _, Colour, CreateActionRow, CreateButton, CreateComponent,
+    CreateContainer, CreateContainerComponent, CreateSection, CreateSectionAccessory,
+    CreateSectionComponent, CreateTextDisplay, EditMember, EditRole, GenericChannelId, GuildId,
+    Http, Member, Mentionable as _, MessageId, Permissions, Role, RoleId, Timestamp, UserId,
     colours::css::{POSITIVE, WARNING},
 };
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{info, warn};
 
-use crate::shared::types::Bingo;
-use crate::{db::DbHandle, role::db::role_config::InsertRoleMapping};
+use crate::role::network_bingo;
+use crate::shared::{
+    interaction::custom_id::{self, Namespace},
+    types::{Bingo, BitSet},
+};
+use crate::{
+    db::DbHandle,
+    role::db::{
+        audit::InsertRoleAuditEntry,
+        role_config::{GetRoleAutoCreateConfig, InsertRoleMapping},
+    },
+};
 
 #[derive(Debug, Clone)]
 pub struct LinkedUser {
@@ -27,63 +43,296 @@ impl LinkedUser {
     }
 }
 
-// NOTE: careful with updating network bingo enum (other than appending), always update the stored
-// bit sets in the database accordingly
-#[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum NetworkBingo {
-    Unknown = 0,
-    Anniversary2023 = 1,
-    Halloween2023 = 2,
-    Christmas2023 = 3,
-    Easter2024 = 4,
-    Summer2024 = 5,
-    Halloween2024 = 6,
-    Anniversary2025 = 7,
-    Anniversary2026 = 8,
+/// Counts of a bulk role update, persisted after every chunk so that it can be resumed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BulkUpdateProgress {
+    /// last processed user, `None` if nobody has been processed yet
+    pub last_user: Option<UserId>,
+    pub updated: u32,
+    pub unchanged: u32,
+    /// linked users who aren't members of the guild
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+impl BulkUpdateProgress {
+    pub fn processed(&self) -> u32 {
+        self.updated + self.unchanged + self.skipped + self.failed
+    }
+}
+
+/// What caused a role update, recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleUpdateTrigger {
+    /// the user requested their roles through the role request message
+    Request,
+    /// `/rolerequest force update`
+    ForceUpdate { by: UserId },
+    /// `/rolerequest force update-all`
+    BulkUpdate { by: UserId },
+    /// roles were restored after the user rejoined the server
+    Rejoin,
+    /// roles were removed, as the user's link moved to another Discord account
+    Transfer,
+}
+
+impl RoleUpdateTrigger {
+    pub fn kind(self) -> RoleUpdateTriggerKind {
+        match self {
+            Self::Request => RoleUpdateTriggerKind::Request,
+            Self::ForceUpdate { .. } => RoleUpdateTriggerKind::ForceUpdate,
+            Self::BulkUpdate { .. } => RoleUpdateTriggerKind::BulkUpdate,
+            Self::Rejoin => RoleUpdateTriggerKind::Rejoin,
+            Self::Transfer => RoleUpdateTriggerKind::Transfer,
+        }
+    }
+
+    /// stored representation, along with the staff member who triggered the update
+    pub fn to_raw(self) -> (u8, Option<UserId>) {
+        let by = match self {
+            Self::ForceUpdate { by } | Self::BulkUpdate { by } => Some(by),
+            Self::Request | Self::Rejoin | Self::Transfer => None,
+        };
+        (self.kind().to_raw(), by)
+    }
+
+    /// Unknown values fall back to [`Self::Request`]
+    pub fn from_raw(kind: u8, by: Option<UserId>) -> Self {
+        match (RoleUpdateTriggerKind::from_raw(kind), by) {
+            (Some(RoleUpdateTriggerKind::ForceUpdate), Some(by)) => Self::ForceUpdate { by },
+            (Some(RoleUpdateTriggerKind::BulkUpdate), Some(by)) => Self::BulkUpdate { by },
+            (Some(RoleUpdateTriggerKind::Rejoin), _) => Self::Rejoin,
+            (Some(RoleUpdateTriggerKind::Transfer), _) => Self::Transfer,
+            _ => Self::Request,
+        }
+    }
+}
+
+impl Display for RoleUpdateTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request => write!(f, "Role request"),
+            Self::ForceUpdate { by } => {
+                write!(f, "`/rolerequest force update` by {}", by.mention())
+            }
+            Self::BulkUpdate { by } => {
+                write!(f, "`/rolerequest force update-all` by {}", by.mention())
+            }
+            Self::Rejoin => write!(f, "Restored after rejoining"),
+            Self::Transfer => write!(f, "Link transferred to another account"),
+        }
+    }
+}
+
+/// [`RoleUpdateTrigger`] without the staff member, which is what role updates are counted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleUpdateTriggerKind {
+    Request,
+    ForceUpdate,
+    BulkUpdate,
+    Rejoin,
+    Transfer,
+}
+
+impl RoleUpdateTriggerKind {
+    pub const ALL: [Self; 5] = [
+        Self::Request,
+        Self::ForceUpdate,
+        Self::BulkUpdate,
+        Self::Rejoin,
+        Self::Transfer,
+    ];
+
+    pub fn to_raw(self) -> u8 {
+        match self {
+            Self::Request => 0,
+            Self::ForceUpdate => 1,
+            Self::BulkUpdate => 2,
+            Self::Rejoin => 3,
+            Self::Transfer => 4,
+        }
+    }
+
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.to_raw() == raw)
+    }
+
+    /// Used as metric label
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::ForceUpdate => "force_update",
+            Self::BulkUpdate => "bulk_update",
+            Self::Rejoin => "rejoin",
+            Self::Transfer => "transfer",
+        }
+    }
+}
+
+/// Entry points of role updates, along with their displayed name
+pub fn role_update_sources() -> [(RoleUpdateTriggerKind, &'static str); 4] {
+    [
+        (RoleUpdateTriggerKind::Request, "Role request button"),
+        (RoleUpdateTriggerKind::ForceUpdate, "Force update"),
+        (RoleUpdateTriggerKind::BulkUpdate, "Bulk update"),
+        (RoleUpdateTriggerKind::Rejoin, "Rejoin restore"),
+    ]
+}
+
+/// Result of a single role update, counted per entry point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleUpdateOutcome {
+    Updated,
+    Unchanged,
+    Failed,
+}
+
+impl RoleUpdateOutcome {
+    pub const ALL: [Self; 3] = [Self::Updated, Self::Unchanged, Self::Failed];
+
+    pub fn to_raw(self) -> u8 {
+        match self {
+            Self::Updated => 0,
+            Self::Unchanged => 1,
+            Self::Failed => 2,
+        }
+    }
+
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|outcome| outcome.to_raw() == raw)
+    }
+
+    /// Used as metric label
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Updated => "updated",
+            Self::Unchanged => "unchanged",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Roles added and removed from a user by a single role update
+#[derive(Debug, Clone)]
+pub struct RoleAuditEntry {
+    pub user: UserId,
+    /// unix timestamp
+    pub timestamp: i64,
+    pub trigger: RoleUpdateTrigger,
+    pub added: Vec<RoleId>,
+    pub removed: Vec<RoleId>,
+}
+
+/// A manual role application posted in [`MANUAL_ROLE_CHANNEL`](crate::config::MANUAL_ROLE_CHANNEL)
+#[derive(Debug, Clone)]
+pub struct ManualApplication {
+    pub message_id: MessageId,
+    pub author: UserId,
+    /// unix timestamp
+    pub posted_at: i64,
+    /// the bot's reply carrying the claim buttons
+    pub panel_message_id: MessageId,
+    /// staff member handling the application, and when they claimed it
+    pub claimed: Option<(UserId, i64)>,
+    /// staff member who resolved the application, and when
+    pub resolved: Option<(UserId, i64)>,
+}
+
+impl ManualApplication {
+    pub fn status(&self) -> ApplicationStatus {
+        match (self.claimed, self.resolved) {
+            (_, Some(_)) => ApplicationStatus::Resolved,
+            (Some(_), None) => ApplicationStatus::Claimed,
+            (None, None) => ApplicationStatus::Open,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum ApplicationStatus {
+    Open,
+    Claimed,
+    Resolved,
 }
 
+/// A network-wide bingo event, identified by the index of its bit in the cached completion sets.
+/// Events up to [`NetworkBingo::BUILTIN`] have hardcoded detection rules, later ones are registered
+/// at runtime (see [`crate::role::network_bingo`]).
+// NOTE: never reuse or reorder IDs, always update the stored bit sets in the database accordingly
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkBingo(u8);
+
 impl Display for NetworkBingo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let human_name = match self {
-            NetworkBingo::Anniversary2023 => "Anniversary Bingo 2023",
-            NetworkBingo::Halloween2023 => "Halloween Bingo 2023",
-            NetworkBingo::Christmas2023 => "Holiday Bingo 2023",
-            NetworkBingo::Easter2024 => "Easter Bingo 2024",
-            NetworkBingo::Summer2024 => "Summer Bingo 2024",
-            NetworkBingo::Halloween2024 => "Halloween Bingo 2024",
-            NetworkBingo::Anniversary2025 => "Anniversary Bingo 2025",
-            NetworkBingo::Anniversary2026 => "Anniversary Bingo 2026",
-            _ => "Unknown Network Bingo",
+        let builtin_name = match *self {
+            NetworkBingo::ANNIVERSARY_2023 => Some("Anniversary Bingo 2023"),
+            NetworkBingo::HALLOWEEN_2023 => Some("Halloween Bingo 2023"),
+            NetworkBingo::CHRISTMAS_2023 => Some("Holiday Bingo 2023"),
+            NetworkBingo::EASTER_2024 => Some("Easter Bingo 2024"),
+            NetworkBingo::SUMMER_2024 => Some("Summer Bingo 2024"),
+            NetworkBingo::HALLOWEEN_2024 => Some("Halloween Bingo 2024"),
+            NetworkBingo::ANNIVERSARY_2025 => Some("Anniversary Bingo 2025"),
+            NetworkBingo::ANNIVERSARY_2026 => Some("Anniversary Bingo 2026"),
+            _ => None,
         };
-        write!(f, "{}", human_name)
+        match builtin_name {
+            Some(name) => write!(f, "{name}"),
+            None => match network_bingo::registered_name(*self) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "Unknown Network Bingo"),
+            },
+        }
     }
 }
 
 impl NetworkBingo {
-    pub const ALL: [NetworkBingo; 8] = [
-        NetworkBingo::Anniversary2023,
-        NetworkBingo::Halloween2023,
-        NetworkBingo::Christmas2023,
-        NetworkBingo::Easter2024,
-        NetworkBingo::Summer2024,
-        NetworkBingo::Halloween2024,
-        NetworkBingo::Anniversary2025,
-        NetworkBingo::Anniversary2026,
+    pub const UNKNOWN: Self = Self(0);
+    pub const ANNIVERSARY_2023: Self = Self(1);
+    pub const HALLOWEEN_2023: Self = Self(2);
+    pub const CHRISTMAS_2023: Self = Self(3);
+    pub const EASTER_2024: Self = Self(4);
+    pub const SUMMER_2024: Self = Self(5);
+    pub const HALLOWEEN_2024: Self = Self(6);
+    pub const ANNIVERSARY_2025: Self = Self(7);
+    pub const ANNIVERSARY_2026: Self = Self(8);
+
+    /// Events predating the registry
+    pub const BUILTIN: [NetworkBingo; 8] = [
+        NetworkBingo::ANNIVERSARY_2023,
+        NetworkBingo::HALLOWEEN_2023,
+        NetworkBingo::CHRISTMAS_2023,
+        NetworkBingo::EASTER_2024,
+        NetworkBingo::SUMMER_2024,
+        NetworkBingo::HALLOWEEN_2024,
+        NetworkBingo::ANNIVERSARY_2025,
+        NetworkBingo::ANNIVERSARY_2026,
     ];
 
+    /// Unknown IDs are kept as they are, as they may be registered later on
     pub fn from_u8(id: u8) -> Self {
-        match id {
-            1 => Self::Anniversary2023,
-            2 => Self::Halloween2023,
-            3 => Self::Christmas2023,
-            4 => Self::Easter2024,
-            5 => Self::Summer2024,
-            6 => Self::Halloween2024,
-            7 => Self::Anniversary2025,
-            8 => Self::Anniversary2026,
-            _ => Self::Unknown,
-        }
+        Self(id)
+    }
+
+    pub fn id(self) -> u8 {
+        self.0
+    }
+
+    /// Every known event in chronological order, including registered ones
+    pub fn all() -> Vec<NetworkBingo> {
+        let mut all = Self::BUILTIN.to_vec();
+        all.extend(network_bingo::registered_ids());
+        all
+    }
+
+    /// The most recent known event, whose completions invalidate cached ones
+    pub fn latest() -> NetworkBingo {
+        Self::all().last().copied().unwrap_or(Self::UNKNOWN)
+    }
+
+    pub fn is_known(self) -> bool {
+        Self::all().contains(&self)
     }
 }
 
@@ -144,7 +393,7 @@ impl RoleMapping {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum RoleMappingKindRaw {
     Completions = 0,
     SpecificCompletion = 1,
@@ -153,6 +402,53 @@ pub enum RoleMappingKindRaw {
     NetworkBingo = 4,
 }
 
+impl RoleMappingKindRaw {
+    pub const ALL: &[RoleMappingKindRaw; 5] = &[
+        RoleMappingKindRaw::Completions,
+        RoleMappingKindRaw::SpecificCompletion,
+        RoleMappingKindRaw::BingoRank,
+        RoleMappingKindRaw::Immortal,
+        RoleMappingKindRaw::NetworkBingo,
+    ];
+
+    /// Categories supported by automatic role detection, in the order they are detected
+    pub const DETECTABLE: &[RoleMappingKindRaw; 4] = &[
+        RoleMappingKindRaw::Completions,
+        RoleMappingKindRaw::SpecificCompletion,
+        RoleMappingKindRaw::BingoRank,
+        RoleMappingKindRaw::Immortal,
+    ];
+
+    pub fn from_u8(int: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| *kind as u8 == int)
+    }
+
+    /// Identifier used in config exports
+    pub fn key(self) -> &'static str {
+        match self {
+            RoleMappingKindRaw::Completions => "completions",
+            RoleMappingKindRaw::SpecificCompletion => "specific_completion",
+            RoleMappingKindRaw::BingoRank => "bingo_rank",
+            RoleMappingKindRaw::Immortal => "immortal",
+            RoleMappingKindRaw::NetworkBingo => "network_bingo",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.key() == key)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RoleMappingKindRaw::Completions => "Blackout counts",
+            RoleMappingKindRaw::SpecificCompletion => "Specific completions",
+            RoleMappingKindRaw::BingoRank => "Bingo ranks",
+            RoleMappingKindRaw::Immortal => "Immortal role",
+            RoleMappingKindRaw::NetworkBingo => "Network Bingo completions",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum RoleMappingKind {
     Completions { count: usize },
@@ -162,6 +458,18 @@ pub enum RoleMappingKind {
     NetworkBingo { bingo: NetworkBingo },
 }
 
+impl RoleMappingKind {
+    pub fn raw(self) -> RoleMappingKindRaw {
+        match self {
+            RoleMappingKind::Completions { .. } => RoleMappingKindRaw::Completions,
+            RoleMappingKind::SpecificCompletion { .. } => RoleMappingKindRaw::SpecificCompletion,
+            RoleMappingKind::BingoRank { .. } => RoleMappingKindRaw::BingoRank,
+            RoleMappingKind::Immortal => RoleMappingKindRaw::Immortal,
+            RoleMappingKind::NetworkBingo { .. } => RoleMappingKindRaw::NetworkBingo,
+        }
+    }
+}
+
 impl Display for RoleMappingKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -176,6 +484,53 @@ impl Display for RoleMappingKind {
     }
 }
 
+/// Grants a role to all members of a Hypixel guild
+#[derive(Debug, Clone)]
+pub struct HypixelGuildMapping {
+    /// ID used by Hypixel's API
+    pub guild_id: String,
+    pub guild_name: String,
+    pub role: RoleId,
+}
+
+/// Restores the roles of linked users when they rejoin the server
+#[derive(Debug, Clone, Copy)]
+pub struct RejoinRestoreConfig {
+    /// how long to wait after the member joins, e.g. to let membership screening complete first
+    pub delay_secs: u64,
+    pub log_channel: Option<GenericChannelId>,
+}
+
+/// Periodically compares links against the Discord account set on Hypixel
+#[derive(Debug, Clone, Copy)]
+pub struct LinkVerificationConfig {
+    /// where the digest of mismatched links is posted
+    pub channel: GenericChannelId,
+    /// removes mismatched links instead of only reporting them
+    pub auto_unlink: bool,
+    /// how long a check stays valid before the link is checked again
+    pub interval_days: u32,
+}
+
+/// Creates roles for patterns that don't match any existing role
+#[derive(Debug, Clone, Copy)]
+pub struct RoleAutoCreateConfig {
+    /// Discord's default colour if `None`
+    pub colour: Option<Colour>,
+}
+
+/// Raw cache entries of a player, regardless of whether they are still valid. Each entry includes
+/// the ID of the (network) bingo it was last updated after.
+#[derive(Default)]
+pub struct PlayerCacheEntries {
+    pub completions: Option<(u8, BitSet)>,
+    pub network_bingos: Option<(u8, BitSet)>,
+    pub bingo_rank: Option<(u8, u8)>,
+    pub immortal: Option<(u8, bool)>,
+    /// timestamp of the cached `/v2/player` response
+    pub player_endpoint: Option<i64>,
+}
+
 #[derive(Debug)]
 pub enum BingoRole {
     Id(RoleId),
@@ -194,24 +549,70 @@ impl RoleDelta {
         self.remove.append(&mut other.remove);
     }
 
-    pub async fn resolve(self, db: &DbHandle, roles: Vec<Role>) -> Result<RoleDeltaResolved> {
+    /// Patterns without a matching role are dropped, unless automatic role creation is enabled.
+    /// Previews only report the roles that would be created, without creating or binding any.
+    pub async fn resolve(
+        self,
+        http: &Http,
+        guild_id: GuildId,
+        db: &DbHandle,
+        mut roles: Vec<Role>,
+        preview: bool,
+    ) -> Result<RoleDeltaResolved> {
+        // only looked up once a pattern doesn't match any role
+        let mut auto_create: Option<Option<RoleAutoCreateConfig>> = None;
+
         let mut role_ids: Vec<RoleId> = Vec::new();
+        let mut would_create: Vec<String> = Vec::new();
         for add_role in self.add {
             match add_role {
                 BingoRole::Id(id) => role_ids.push(id),
                 BingoRole::Name { name, kind } => {
-                    if let Some(id) = roles.iter().find_map(|role| {
-                        // enforces empty permissions on automatic role detection, to fully prevent
-                        // accidentally granting permissions to users
-                        (role.name == name && role.permissions == Permissions::empty())
-                            .then_some(role.id)
-                    }) {
+                    let id = match matching_role(&roles, &name).map(|role| role.id) {
+                        Some(id) => id,
+                        None => {
+                            let config = match auto_create {
+                                Some(config) => config,
+                                None => {
+                                    let config = auto_create_config(http, guild_id, db).await?;
+                                    auto_create = Some(config);
+                                    config
+                                }
+                            };
+                            let Some(config) = config else {
+                                continue;
+                            };
+
+                            if preview {
+                                if !would_create.contains(&name) {
+                                    would_create.push(name);
+                                }
+                                continue;
+                            }
+
+                            let role = match create_role(http, guild_id, &name, config).await {
+                                Ok(role) => role,
+                                Err(err) => {
+                                    warn!("Failed to create role '{name}' for {kind}: {err:#}");
+                                    // the other patterns would most likely fail the same way
+                                    auto_create = Some(None);
+                                    continue;
+                                }
+                            };
+                            let id = role.id;
+                            // another pattern of this update may resolve to the same name
+                            roles.push(role);
+                            id
+                        }
+                    };
+
+                    if !preview {
                         db.request(InsertRoleMapping {
                             role_mapping: RoleMapping::new(kind, id),
                         })
                         .await??;
-                        role_ids.push(id);
                     }
+                    role_ids.push(id);
                 }
             }
         }
@@ -219,22 +620,104 @@ impl RoleDelta {
         Ok(RoleDeltaResolved {
             add: role_ids,
             remove: self.remove,
+            would_create,
         })
     }
 }
 
+/// Enforces empty permissions on automatic role detection, to fully prevent accidentally granting
+/// permissions to users
+fn matching_role<'a>(roles: &'a [Role], name: &str) -> Option<&'a Role> {
+    roles
+        .iter()
+        .find(|role| role.name == name && role.permissions == Permissions::empty())
+}
+
+/// `None` if automatic role creation is disabled or the bot isn't allowed to create roles
+async fn auto_create_config(
+    http: &Http,
+    guild_id: GuildId,
+    db: &DbHandle,
+) -> Result<Option<RoleAutoCreateConfig>> {
+    let Some(config) = db.request(GetRoleAutoCreateConfig).await?? else {
+        return Ok(None);
+    };
+
+    let bot = guild_id
+        .member(http, http.get_current_user().await?.id)
+        .await?;
+    let permissions = guild_id
+        .to_partial_guild(http)
+        .await?
+        .member_permissions(&bot);
+    if !permissions.manage_roles() {
+        warn!("Automatic role creation is enabled, but the bot lacks the Manage Roles permission");
+        return Ok(None);
+    }
+
+    Ok(Some(config))
+}
+
+/// Serializes role creation per guild, so that concurrent role requests missing the same role
+/// don't both create it
+static ROLE_CREATION_LOCKS: LazyLock<Mutex<HashMap<GuildId, Arc<AsyncMutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// Creates the role, unless a concurrent role request has created it in the meantime
+async fn create_role(
+    http: &Http,
+    guild_id: GuildId,
+    name: &str,
+    config: RoleAutoCreateConfig,
+) -> Result<Role> {
+    let lock = Arc::clone(
+        ROLE_CREATION_LOCKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(guild_id)
+            .or_default(),
+    );
+    let _guard = lock.lock().await;
+
+    let roles: Vec<Role> = guild_id.roles(http).await?.into_iter().collect();
+    if let Some(role) = matching_role(&roles, name) {
+        return Ok(role.clone());
+    }
+
+    let mut role = EditRole::new()
+        .name(name.to_string())
+        .permissions(Permissions::empty())
+        .audit_log_reason("Automatic role creation for role requests");
+    if let Some(colour) = config.colour {
+        role = role.colour(colour);
+    }
+    let role = guild_id.create_role(http, role).await?;
+    info!("Created role '{name}'");
+
+    Ok(role)
+}
+
 #[derive(Debug)]
 pub struct RoleDeltaResolved {
     pub add: Vec<RoleId>,
     pub remove: Vec<RoleId>,
+    /// names of the roles a preview would create and add, always empty otherwise
+    pub would_create: Vec<String>,
 }
 
 impl RoleDeltaResolved {
     pub fn is_empty(&self) -> bool {
-        self.add.is_empty() && self.remove.is_empty()
+        self.add.is_empty() && self.remove.is_empty() && self.would_create.is_empty()
     }
 
-    pub async fn apply(&self, http: &Http, member: &Member) -> Result<()> {
+    /// Edits the member's roles and records the change in the audit log
+    pub async fn apply(
+        &self,
+        http: &Http,
+        db: &DbHandle,
+        member: &Member,
+        trigger: RoleUpdateTrigger,
+    ) -> Result<()> {
         let mut user_roles: HashSet<RoleId> = member.roles.iter().copied().collect();
 
         user_roles.extend(&self.add);
@@ -250,6 +733,18 @@ impl RoleDeltaResolved {
                 EditMember::new().roles(user_roles.into_iter().collect::<Vec<_>>()),
             )
             .await?;
+
+        db.request(InsertRoleAuditEntry {
+            entry: RoleAuditEntry {
+                user: member.user.id,
+                timestamp: Timestamp::now().unix_timestamp(),
+                trigger,
+                added: self.add.clone(),
+                removed: self.remove.clone(),
+            },
+        })
+        .await??;
+
         Ok(())
     }
 }
@@ -292,9 +787,10 @@ impl LinkStatus {
 \nUse the button to unlink your previous account, then try again.",
                 )));
 
-                let unlink_button = CreateButton::new("role:request:unlink")
-                    .label("Unlink Account")
-                    .style(ButtonStyle::Danger);
+                let unlink_button =
+                    CreateButton::new(custom_id::encode(Namespace::Role, &[&"request", &"unlink"]))
+                        .label("Unlink Account")
+                        .style(ButtonStyle::Danger);
 
                 let section = CreateContainerComponent::Section(CreateSection::new(
                     vec![text],
@@ -307,30 +803,41 @@ impl LinkStatus {
             }
             // NOTE: Only triggers when the correct discord account is found, but the database
             // contains an existing linking entry.
-            // This makes is safe to provide an unlink button since the user has proven
+            // This makes is safe to provide unlink and transfer buttons since the user has proven
             // ownership of the account
             LinkStatus::DuplicateDiscord {
                 uuid,
                 other_discord,
             } => {
-                let text = CreateSectionComponent::TextDisplay(CreateTextDisplay::new(format!(
+                let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
                     "## Account already linked
 **Your Minecraft account is currently linked to {}!**
-\nUse the button to unlink your previous account, then try again.",
+\nIf you switched Discord accounts, transfer the link to this account. \
+Your role history is transferred along with it.
+Otherwise, unlink your previous account, then try again.",
                     other_discord.mention()
                 )));
 
-                let unlink_button = CreateButton::new(format!("role:request:unlink:{}", uuid))
-                    .label("Unlink Account")
-                    .style(ButtonStyle::Danger);
-
-                let section = CreateContainerComponent::Section(CreateSection::new(
-                    vec![text],
-                    CreateSectionAccessory::Button(unlink_button),
+                let transfer_button = CreateButton::new(custom_id::encode(
+                    Namespace::Role,
+                    &[&"request", &"transfer", &uuid],
+                ))
+                .emoji('🔁')
+                .label("Transfer Link")
+                .style(ButtonStyle::Success);
+                let unlink_button = CreateButton::new(custom_id::encode(
+                    Namespace::Role,
+                    &[&"request", &"unlink", &uuid],
+                ))
+                .label("Unlink Account")
+                .style(ButtonStyle::Danger);
+
+                let buttons = CreateContainerComponent::ActionRow(CreateActionRow::Buttons(
+                    vec![transfer_button, unlink_button].into(),
                 ));
 
                 CreateComponent::Container(
-                    CreateContainer::new(vec![section]).accent_color(WARNING),
+                    CreateContainer::new(vec![text, buttons]).accent_color(WARNING),
                 )
             }
             LinkStatus::Success => {
@@ -340,9 +847,10 @@ Your Hypixel profile was successfully linked to this Discord account.
 \nPress the button to continue the role requesting process.",
                 ));
 
-                let continue_button = CreateButton::new("role:request:begin")
-                    .label("Request Roles")
-                    .style(ButtonStyle::Primary);
+                let continue_button =
+                    CreateButton::new(custom_id::encode(Namespace::Role, &[&"request", &"begin"]))
+                        .label("Request Roles")
+                        .style(ButtonStyle::Primary);
 
                 let section = CreateContainerComponent::Section(CreateSection::new(
                     vec![text],
@@ -0,0 +1,28 @@
+use rusqlite::{Connection, Result};
+
+mod read;
+mod write;
+
+pub use read::*;
+pub use write::*;
+
+/// Applied by the baseline migration
+pub fn baseline_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        -- Channel the splash list is automatically posted to at the end of each bingo, disabled if absent
+        CREATE TABLE IF NOT EXISTS schedule_splashlist_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            channel INTEGER NOT NULL
+        );
+
+        -- Bingos whose splash list has already been posted automatically
+        CREATE TABLE IF NOT EXISTS schedule_splashlist_posts (
+            bingo INTEGER NOT NULL,
+            bingo_kind INTEGER NOT NULL,
+            message_id INTEGER NOT NULL,
+            PRIMARY KEY(bingo, bingo_kind)
+        );
+        ",
+    )
+}
@@ -0,0 +1,121 @@
+use poise::serenity_prelude::{GenericChannelId, MessageId};
+use rusqlite::{Connection, OptionalExtension as _, Result, params};
+
+use crate::db::DbRequest;
+use crate::shared::types::Bingo;
+
+pub struct GetSplashListSchedule;
+impl DbRequest for GetSplashListSchedule {
+    /// channel the splash list is posted to, `None` if scheduled posting is disabled
+    type ReturnValue = Result<Option<GenericChannelId>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT channel FROM schedule_splashlist_config WHERE id=1",
+            [],
+            |row| Ok(GenericChannelId::new(row.get("channel")?)),
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashListPosted {
+    pub bingo: Bingo,
+}
+impl DbRequest for GetSplashListPosted {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT 1 FROM schedule_splashlist_posts WHERE bingo=?1 AND bingo_kind=?2",
+            params![self.bingo.kind_specific_id, self.bingo.kind as u8],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|opt| opt.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WeeklyChartConfig {
+    pub enabled: bool,
+    /// the splashes channel is used if `None`
+    pub channel: Option<GenericChannelId>,
+}
+
+impl Default for WeeklyChartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            channel: None,
+        }
+    }
+}
+
+pub struct GetWeeklyChartConfig;
+impl DbRequest for GetWeeklyChartConfig {
+    type ReturnValue = Result<WeeklyChartConfig>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let config = conn
+            .query_one(
+                "SELECT enabled, channel FROM schedule_weekly_chart_config WHERE id=1",
+                [],
+                |row| {
+                    Ok(WeeklyChartConfig {
+                        enabled: row.get("enabled")?,
+                        channel: row
+                            .get::<_, Option<u64>>("channel")?
+                            .map(GenericChannelId::new),
+                    })
+                },
+            )
+            .optional()?;
+        Ok(config.unwrap_or_default())
+    }
+}
+
+pub struct GetWeeklyChartPosted {
+    /// start of the week's Monday, see `schedule_weekly_chart_posts`
+    pub week: i64,
+}
+impl DbRequest for GetWeeklyChartPosted {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT 1 FROM schedule_weekly_chart_posts WHERE week=?1",
+            params![self.week],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|opt| opt.is_some())
+    }
+}
+
+/// Message that is kept up to date with the current bingo's splash list
+#[derive(Debug, Clone, Copy)]
+pub struct LiveSplashList {
+    pub channel: GenericChannelId,
+    pub message_id: MessageId,
+}
+
+pub struct GetLiveSplashList;
+impl DbRequest for GetLiveSplashList {
+    /// `None` if the live splash list is disabled
+    type ReturnValue = Result<Option<LiveSplashList>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT channel, message_id FROM schedule_live_splashlist WHERE id=1",
+            [],
+            |row| {
+                Ok(LiveSplashList {
+                    channel: GenericChannelId::new(row.get("channel")?),
+                    message_id: MessageId::new(row.get("message_id")?),
+                })
+            },
+        )
+        .optional()
+    }
+}
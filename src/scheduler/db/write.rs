@@ -0,0 +1,121 @@
+use poise::serenity_prelude::{GenericChannelId, MessageId};
+use rusqlite::{Connection, Result, params};
+
+use crate::db::DbRequest;
+use crate::scheduler::db::{LiveSplashList, WeeklyChartConfig};
+use crate::shared::types::Bingo;
+
+pub struct SetSplashListSchedule {
+    /// disables scheduled posting if `None`
+    pub channel: Option<GenericChannelId>,
+}
+impl DbRequest for SetSplashListSchedule {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.channel {
+            Some(channel) => conn.execute(
+                "
+                INSERT INTO schedule_splashlist_config (id, channel)
+                VALUES (1, ?1)
+                ON CONFLICT(id) DO UPDATE SET
+                    channel = excluded.channel
+                ",
+                params![channel.get()],
+            )?,
+            None => conn.execute("DELETE FROM schedule_splashlist_config WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetSplashListPosted {
+    pub bingo: Bingo,
+    pub message_id: MessageId,
+}
+impl DbRequest for SetSplashListPosted {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR IGNORE INTO schedule_splashlist_posts (bingo, bingo_kind, message_id)
+            VALUES (?1, ?2, ?3)
+            ",
+            params![
+                self.bingo.kind_specific_id,
+                self.bingo.kind as u8,
+                self.message_id.get()
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetWeeklyChartConfig {
+    pub config: WeeklyChartConfig,
+}
+impl DbRequest for SetWeeklyChartConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO schedule_weekly_chart_config (id, enabled, channel)
+            VALUES (1, ?1, ?2)
+            ON CONFLICT(id) DO UPDATE SET
+                enabled = excluded.enabled,
+                channel = excluded.channel
+            ",
+            params![
+                self.config.enabled,
+                self.config.channel.map(|channel| channel.get())
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetWeeklyChartPosted {
+    pub week: i64,
+    pub message_id: MessageId,
+}
+impl DbRequest for SetWeeklyChartPosted {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR IGNORE INTO schedule_weekly_chart_posts (week, message_id)
+            VALUES (?1, ?2)
+            ",
+            params![self.week, self.message_id.get()],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetLiveSplashList {
+    /// disables the live splash list if `None`
+    pub live: Option<LiveSplashList>,
+}
+impl DbRequest for SetLiveSplashList {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.live {
+            Some(live) => conn.execute(
+                "
+                INSERT INTO schedule_live_splashlist (id, channel, message_id)
+                VALUES (1, ?1, ?2)
+                ON CONFLICT(id) DO UPDATE SET
+                    channel = excluded.channel,
+                    message_id = excluded.message_id
+                ",
+                params![live.channel.get(), live.message_id.get()],
+            )?,
+            None => conn.execute("DELETE FROM schedule_live_splashlist WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
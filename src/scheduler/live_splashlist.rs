@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use poise::serenity_prelude::Http;
+use tracing::info;
+
+use crate::db::DbHandle;
+use crate::error;
+use crate::scheduler::{
+    MAX_WAIT,
+    db::{GetLiveSplashList, SetLiveSplashList},
+    next_wait,
+};
+use crate::shared::BotData;
+use crate::splashes::splashlist::{self, BingoSelector, ChartOptions, SplashPeriod};
+
+/// How often the live splash list is regenerated. Charts are cached, so this stays cheap while no
+/// new splashes come in.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Updates the live splash lists of the main and sandbox databases with the current bingo's
+/// splashes, returning how long to wait until the next update
+pub async fn update(http: &Http, data: &BotData) -> Result<Duration> {
+    let dbs = std::iter::once(&data.db_handle)
+        .chain(data.sandbox.iter().map(|sandbox| &sandbox.db_handle));
+
+    let mut wait = MAX_WAIT;
+    for db in dbs {
+        let result = update_list(http, data, db).await;
+        wait = wait.min(next_wait("live splash list update", result));
+    }
+    Ok(wait)
+}
+
+async fn update_list(http: &Http, data: &BotData, db: &DbHandle) -> Result<Duration> {
+    let Some(live) = db.request(GetLiveSplashList).await?? else {
+        return Ok(MAX_WAIT);
+    };
+
+    let period = SplashPeriod::resolve(db, &data.api_handle, BingoSelector::Current).await?;
+    let message =
+        splashlist::generate_message(http, db, None, period, ChartOptions::default(), |_| {})
+            .await?;
+
+    let edited = live
+        .channel
+        .edit_message(http, live.message_id, message.into_edit())
+        .await
+        .map_err(anyhow::Error::from);
+    match edited {
+        Ok(_) => Ok(UPDATE_INTERVAL),
+        // staff removed the live splash list, so there is nothing left to update
+        Err(err) if error::is_unknown_message(&err) => {
+            info!("Disabling the live splash list, as its message was deleted");
+            db.request(SetLiveSplashList { live: None }).await??;
+            Ok(MAX_WAIT)
+        }
+        Err(err) => Err(err),
+    }
+}
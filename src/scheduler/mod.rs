@@ -0,0 +1,101 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use poise::serenity_prelude::Context as SerenityContext;
+use tracing::error;
+
+use crate::role::network_bingo;
+use crate::shared::{
+    BotData,
+    feature::{self, Feature},
+    shard,
+    task::spawn_background,
+};
+use crate::splashes::inactivity;
+
+pub mod db;
+mod live_splashlist;
+mod splashlist;
+mod weekly_chart;
+
+/// Upper bound between checks, so that configuration changes are picked up without a restart
+pub const MAX_WAIT: Duration = Duration::from_secs(60 * 60);
+/// Wait after a failed run before trying again
+const RETRY_WAIT: Duration = Duration::from_secs(10 * 60);
+
+/// `Ready` is dispatched again after reconnects, but only one scheduler should ever run
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Starts the background loop running scheduled jobs. All state is persisted in the database, so
+/// jobs due during downtime are caught up on after a restart, and completed ones aren't repeated.
+/// Runs in every process, as some jobs need the shard of a guild, while the others only run in the
+/// primary process.
+pub fn start_scheduler(ctx: &SerenityContext) {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let http = Arc::clone(&ctx.http);
+    let data = ctx.data::<BotData>();
+
+    spawn_background("scheduler", Arc::clone(&http), async move {
+        loop {
+            // keep running if the flag can't be read, as the scheduler predates feature flags
+            if !feature::enabled_globally(&data.db_handle, Feature::Scheduler)
+                .await
+                .unwrap_or(true)
+            {
+                tokio::time::sleep(MAX_WAIT).await;
+                continue;
+            }
+
+            let mut waits = Vec::new();
+            if shard::current().is_primary() {
+                waits.push(next_wait(
+                    "scheduled splash list post",
+                    splashlist::post_if_due(&http, &data).await,
+                ));
+                waits.push(next_wait(
+                    "weekly splash chart",
+                    weekly_chart::post_if_due(&http, &data).await,
+                ));
+                waits.push(next_wait(
+                    "live splash list update",
+                    live_splashlist::update(&http, &data).await,
+                ));
+            }
+            // the registry is shared by all guilds, but every process keeps its own copy
+            waits.push(next_wait(
+                "Network Bingo registry reload",
+                network_bingo::reload(&data.db_handle).await,
+            ));
+            let dbs = std::iter::once(&data.db_handle)
+                .chain(data.sandbox.iter().map(|sandbox| &sandbox.db_handle));
+            for db in dbs {
+                waits.push(next_wait(
+                    "splasher inactivity digest",
+                    inactivity::post_if_due(db).await,
+                ));
+            }
+            let wait = waits.into_iter().min().unwrap_or(MAX_WAIT);
+
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+fn next_wait(job: &str, result: Result<Duration>) -> Duration {
+    match result {
+        Ok(wait) => wait.min(MAX_WAIT),
+        Err(err) => {
+            error!("Failed to run {job}: {err:#}");
+            RETRY_WAIT
+        }
+    }
+}
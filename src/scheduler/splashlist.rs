@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use poise::serenity_prelude::{GenericChannelId, Http};
+use tracing::info;
+
+use crate::scheduler::{
+    MAX_WAIT,
+    db::{GetSplashListPosted, GetSplashListSchedule, SetSplashListPosted},
+};
+use crate::shared::{BotData, db::GetCurrentBingo, types::Bingo};
+use crate::splashes::splashlist::{self, BingoSelector, ChartOptions, SplashPeriod};
+
+/// Grace period after the bingo ends, so that last-second splashes are included
+const POST_DELAY_SECS: i64 = 60;
+
+/// Posts the splash list to the configured channel once the current bingo has ended, returning
+/// how long to wait until the next check
+pub async fn post_if_due(http: &Http, data: &BotData) -> Result<Duration> {
+    let db = &data.db_handle;
+
+    let Some(channel) = db.request(GetSplashListSchedule).await?? else {
+        return Ok(MAX_WAIT);
+    };
+
+    let now = Utc::now().timestamp();
+    let stored = db.request(GetCurrentBingo).await??;
+    let (bingo, _, end) = match stored {
+        Some(current) if now < current.2 => current,
+        // make sure current bingo data is up-to-date if possibly outdated
+        _ => data.api_handle.update_current_bingo(db).await?,
+    };
+
+    // after downtime across a bingo boundary, the stored bingo ended without its list being posted
+    if let Some((previous, _, _)) = stored
+        && previous != bingo
+    {
+        post(http, data, channel, previous).await?;
+    }
+
+    let due = end + POST_DELAY_SECS;
+    if now < due {
+        return Ok(Duration::from_secs((due - now) as u64));
+    }
+
+    post(http, data, channel, bingo).await?;
+    Ok(MAX_WAIT)
+}
+
+/// Posts the splash list of an ended bingo, unless it was already posted
+async fn post(http: &Http, data: &BotData, channel: GenericChannelId, bingo: Bingo) -> Result<()> {
+    let db = &data.db_handle;
+    if db.request(GetSplashListPosted { bingo }).await?? {
+        return Ok(());
+    }
+
+    info!("Posting scheduled splash list for {bingo}");
+
+    // a newer bingo may have started already after extended downtime
+    let period =
+        SplashPeriod::resolve(db, &data.api_handle, BingoSelector::Specific(bingo)).await?;
+    let message =
+        splashlist::generate_message(http, db, None, period, ChartOptions::default(), |_| {})
+            .await?;
+    let message = channel.send_message(http, message.into_message()).await?;
+
+    db.request(SetSplashListPosted {
+        bingo,
+        message_id: message.id,
+    })
+    .await??;
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::Result;
+use chrono::Utc;
+use poise::serenity_prelude::{
+    CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent,
+    CreateMediaGallery, CreateMediaGalleryItem, CreateMessage, CreateTextDisplay,
+    CreateUnfurledMediaItem, Http, MessageFlags, colours::branding::YELLOW,
+};
+use tracing::info;
+
+use crate::scheduler::{
+    MAX_WAIT,
+    db::{GetWeeklyChartConfig, GetWeeklyChartPosted, SetWeeklyChartPosted},
+};
+use crate::shared::{BotData, calendar::week_start_est, db::GetCurrentBingo};
+use crate::splashes::splashlist::{self, BingoSelector, SplashPeriod};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+/// Days covered by each chart
+const WEEK_DAYS: usize = 7;
+
+/// Posts a chart of the past week's daily splashes every Monday (in EST) during a bingo, returning
+/// how long to wait until the next check
+pub async fn post_if_due(http: &Http, data: &BotData) -> Result<Duration> {
+    let db = &data.db_handle;
+
+    let config = db.request(GetWeeklyChartConfig).await??;
+    if !config.enabled {
+        return Ok(MAX_WAIT);
+    }
+
+    let now = Utc::now().timestamp();
+    let week = week_start_est(now);
+    // only posted on Mondays, a week missed due to downtime is skipped rather than posted late
+    if now - week >= DAY_SECS || db.request(GetWeeklyChartPosted { week }).await?? {
+        return Ok(MAX_WAIT);
+    }
+
+    let (bingo, start, end) = match db.request(GetCurrentBingo).await?? {
+        Some(current) if now < current.2 => current,
+        // make sure current bingo data is up-to-date if possibly outdated
+        _ => data.api_handle.update_current_bingo(db).await?,
+    };
+    if now < start || now >= end {
+        return Ok(MAX_WAIT);
+    }
+
+    let period =
+        SplashPeriod::resolve(db, &data.api_handle, BingoSelector::Specific(bingo)).await?;
+    let days = reported_days(now, start, period.days());
+    // the bingo started today, so there is nothing to report yet
+    if days.is_empty() {
+        return Ok(MAX_WAIT);
+    }
+
+    info!("Posting weekly splash chart for {bingo}");
+
+    let splashes = splashlist::load_bingo(http, db, None, period, |_| {})
+        .await?
+        .splashes;
+    let per_day = splashes.split_days();
+    let counts: Vec<(usize, u32)> = days.map(|day| (day + 1, per_day[day])).collect();
+
+    let total: u32 = counts.iter().map(|(_, count)| count).sum();
+    let text = CreateTextDisplay::new(format!(
+        "## Weekly Splashes
+**{total}** splashes over the last {} days of {bingo}, **{:.1}** per day on average.",
+        counts.len(),
+        total as f64 / counts.len() as f64
+    ));
+    let chart = splashlist::weekly_chart(counts).await?;
+
+    let channel = match config.channel {
+        Some(channel) => channel,
+        None => data.guild_config(None).await?.splashes_channel,
+    };
+    let message = channel
+        .send_message(
+            http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![
+                        CreateContainerComponent::TextDisplay(text),
+                        CreateContainerComponent::MediaGallery(CreateMediaGallery::new(vec![
+                            CreateMediaGalleryItem::new(CreateUnfurledMediaItem::new(
+                                "attachment://weekly.png",
+                            )),
+                        ])),
+                        CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+                            "-# Use `/splashlist send` for the full splash list.",
+                        )),
+                    ])
+                    .accent_color(YELLOW),
+                )])
+                .add_file(CreateAttachment::bytes(chart, "weekly.png")),
+        )
+        .await?;
+
+    db.request(SetWeeklyChartPosted {
+        week,
+        message_id: message.id,
+    })
+    .await??;
+
+    Ok(MAX_WAIT)
+}
+
+/// Zero-based days of the bingo which have fully passed, limited to the last week
+fn reported_days(now: i64, start: i64, bingo_days: usize) -> Range<usize> {
+    let completed = usize::try_from((now - start).div_euclid(DAY_SECS))
+        .unwrap_or(0)
+        .min(bingo_days);
+    completed.saturating_sub(WEEK_DAYS)..completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_at_most_a_week() {
+        let start = 1_704_690_000;
+        assert_eq!(reported_days(start + 3600, start, 7), 0..0);
+        assert_eq!(reported_days(start + 3 * DAY_SECS + 1, start, 7), 0..3);
+        assert_eq!(reported_days(start + 10 * DAY_SECS, start, 31), 3..10);
+        assert_eq!(reported_days(start + 40 * DAY_SECS, start, 31), 24..31);
+    }
+}
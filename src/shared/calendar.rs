@@ -0,0 +1,255 @@
+//! Pure date calculations that bingo IDs and daily splash statistics are derived from. Nothing in
+//! here reads the system time directly; callers pass a [`Clock`] (usually [`SystemClock`]) instead,
+//! so that the calculations can be checked at arbitrary points in time.
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Datelike as _, FixedOffset, TimeZone as _, Timelike as _};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+/// Bingo #1 took place in December 2021
+const FIRST_BINGO_INDEX: u32 = 2021 * 12 + 12;
+
+/// Source of the current time
+pub trait Clock {
+    /// Current unix timestamp in seconds
+    fn now(&self) -> i64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+}
+
+/// Clock that is stuck at a fixed point in time
+#[cfg(test)]
+pub struct FixedClock(pub i64);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> i64 {
+        self.0
+    }
+}
+
+/// Hypixel's bingo schedule follows EST, without observing DST
+pub fn est() -> FixedOffset {
+    FixedOffset::west_opt(5 * 3600).unwrap()
+}
+
+// NOTE: Calculations are deliberately done in UTC, because this accounts for the weird 1h bingo
+// time shift that Hypixel sometimes has around DST by using the ~4-5h UTC-EST difference as a
+// buffer zone, while keeping timezone calculations simple as a side effect
+pub fn bingo_id_from_timestamp(timestamp: u32) -> Result<u8> {
+    let time_utc = DateTime::from_timestamp(timestamp.into(), 0).context("Invalid timestamp")?;
+
+    let year = time_utc.year() as u32;
+    let month = time_utc.month();
+
+    let timestamp_index = year * 12 + month;
+
+    Ok(timestamp_index.saturating_sub(FIRST_BINGO_INDEX) as u8)
+}
+
+/// Zero-based day of a bingo starting at `start` that the timestamp falls into, negative before
+/// the start. Days are fixed 24h buckets, so they stay aligned across DST changes.
+pub fn bingo_day(start: i64, timestamp: i64) -> i64 {
+    (timestamp - start).div_euclid(DAY_SECS)
+}
+
+/// Local date and time of the timestamp in EST
+pub fn datetime_est(timestamp: i64) -> DateTime<FixedOffset> {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&est())
+}
+
+/// Hour of the day in EST
+pub fn hour_est(timestamp: i64) -> u32 {
+    datetime_est(timestamp).hour()
+}
+
+/// Year and month the timestamp falls into in EST
+pub fn month_est(timestamp: i64) -> (i32, u32) {
+    let date = datetime_est(timestamp);
+    (date.year(), date.month())
+}
+
+/// Unix timestamp of midnight EST on the given day. Days past the end of the month roll over into
+/// the following month, so that the end of a bingo can be expressed as the day after its last day.
+pub fn start_of_day_est(year: i32, month: u32, day_of_month: u32) -> i64 {
+    let first = est().with_ymd_and_hms(year, month, 1, 0, 0, 0).unwrap();
+    first.timestamp() + i64::from(day_of_month.saturating_sub(1)) * DAY_SECS
+}
+
+/// Unix timestamp of midnight EST on the first day of the month `offset_months` away from the
+/// current one
+pub fn start_of_month_est(clock: &impl Clock, offset_months: i32) -> i64 {
+    let (year, month) = month_est(clock.now());
+    let (year, month) = add_months(year, month, offset_months);
+    start_of_day_est(year, month, 1)
+}
+
+/// Unix timestamp of midnight EST on the Monday of the week the timestamp falls into
+pub fn week_start_est(timestamp: i64) -> i64 {
+    let offset = i64::from(est().local_minus_utc());
+
+    let day = (timestamp + offset).div_euclid(DAY_SECS);
+    // the unix epoch was a Thursday
+    let days_since_monday = (day + 3).rem_euclid(7);
+    (day - days_since_monday) * DAY_SECS - offset
+}
+
+/// Shifts a month by a (possibly negative) number of months
+pub fn add_months(year: i32, month: u32, months: i32) -> (i32, u32) {
+    let index = year * 12 + month as i32 - 1 + months;
+    (index.div_euclid(12), index.rem_euclid(12) as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp()
+    }
+
+    fn bingo_id(timestamp: i64) -> u8 {
+        bingo_id_from_timestamp(timestamp as u32).unwrap()
+    }
+
+    #[test]
+    fn bingo_ids_count_months_since_the_first_bingo() {
+        assert_eq!(bingo_id(utc(2021, 11, 30, 12, 0)), 0);
+        assert_eq!(bingo_id(utc(2021, 12, 1, 5, 0)), 0);
+        assert_eq!(bingo_id(utc(2022, 1, 1, 5, 0)), 1);
+        assert_eq!(bingo_id(utc(2022, 12, 31, 23, 59)), 12);
+        assert_eq!(bingo_id(utc(2023, 1, 1, 0, 0)), 13);
+    }
+
+    #[test]
+    fn bingo_ids_tolerate_hypixel_time_shifts() {
+        // bingos start at midnight EST, but Hypixel has started them an hour early around DST
+        // changes, e.g. at 04:00 UTC on 2024-11-01
+        let regular_start = utc(2024, 11, 1, 5, 0);
+        let shifted_start = utc(2024, 11, 1, 4, 0);
+        assert_eq!(bingo_id(regular_start), bingo_id(shifted_start));
+        assert_eq!(
+            bingo_id(shifted_start),
+            bingo_id(utc(2024, 10, 31, 23, 59)) + 1
+        );
+
+        // the previous month's bingo only lasts a week, so the end of the month is unambiguous
+        let march_start = utc(2024, 3, 1, 5, 0);
+        assert_eq!(bingo_id(utc(2024, 3, 10, 7, 0)), bingo_id(march_start));
+    }
+
+    #[test]
+    fn bingo_days_ignore_dst() {
+        // US DST started on 2024-03-10, bingo days are still exactly 24 hours long
+        let start = start_of_day_est(2024, 3, 1);
+        assert_eq!(bingo_day(start, utc(2024, 3, 10, 4, 59)), 8);
+        assert_eq!(bingo_day(start, utc(2024, 3, 10, 5, 0)), 9);
+        assert_eq!(bingo_day(start, start - 1), -1);
+    }
+
+    #[test]
+    fn start_of_day_rolls_over_into_the_next_month() {
+        assert_eq!(start_of_day_est(2023, 12, 32), start_of_day_est(2024, 1, 1));
+        assert_eq!(start_of_day_est(2024, 2, 30), start_of_day_est(2024, 3, 1));
+        assert_eq!(start_of_day_est(2024, 1, 1), utc(2024, 1, 1, 5, 0));
+    }
+
+    #[test]
+    fn fixed_clock_determines_the_current_month() {
+        let clock = FixedClock(utc(2025, 1, 1, 4, 59));
+        assert_eq!(month_est(clock.now()), (2024, 12));
+        assert_eq!(start_of_month_est(&clock, 0), utc(2024, 12, 1, 5, 0));
+        assert_eq!(start_of_month_est(&clock, 1), utc(2025, 1, 1, 5, 0));
+        assert_eq!(start_of_month_est(&clock, -12), utc(2023, 12, 1, 5, 0));
+    }
+
+    #[test]
+    fn week_starts_on_monday_midnight_est() {
+        let monday = utc(2024, 1, 8, 5, 0);
+        assert_eq!(week_start_est(monday), monday);
+        assert_eq!(week_start_est(monday + 6 * DAY_SECS + 3600), monday);
+        // Sunday 23:59 EST still belongs to the previous week
+        assert_eq!(week_start_est(monday - 60), monday - 7 * DAY_SECS);
+    }
+
+    // 2015 to 2040, before IDs would exceed the range of a `u8`
+    const RANGE: std::ops::Range<i64> = 1_420_070_400..2_208_988_800;
+
+    proptest! {
+        #[test]
+        fn bingo_ids_never_decrease(a in RANGE, b in RANGE) {
+            let (earlier, later) = (a.min(b), a.max(b));
+            prop_assert!(bingo_id(earlier) <= bingo_id(later));
+        }
+
+        #[test]
+        fn bingo_ids_are_constant_within_a_utc_month(timestamp in RANGE) {
+            let date = DateTime::from_timestamp(timestamp, 0).unwrap();
+            let month_start = utc(date.year(), date.month(), 1, 0, 0);
+            prop_assert_eq!(bingo_id(timestamp), bingo_id(month_start));
+        }
+
+        #[test]
+        fn bingo_ids_increase_by_one_per_month(year in 2022..2030i32, month in 1..=12u32) {
+            let (next_year, next_month) = add_months(year, month, 1);
+            prop_assert_eq!(
+                bingo_id(utc(next_year, next_month, 1, 0, 0)),
+                bingo_id(utc(year, month, 1, 0, 0)) + 1
+            );
+        }
+
+        #[test]
+        fn bingo_start_is_in_its_own_month(year in 2022..2040i32, month in 1..=12u32) {
+            // bingos start at midnight EST, with Hypixel's occasional shift of an hour
+            for start in [start_of_day_est(year, month, 1), start_of_day_est(year, month, 1) - 3600] {
+                prop_assert_eq!(
+                    bingo_id(start),
+                    bingo_id(utc(year, month, 15, 12, 0))
+                );
+            }
+        }
+
+        #[test]
+        fn bingo_days_are_24h_buckets(start in RANGE, offset in 0..31 * DAY_SECS) {
+            let day = bingo_day(start, start + offset);
+            prop_assert_eq!(day, offset / DAY_SECS);
+            prop_assert_eq!(bingo_day(start, start + day * DAY_SECS), day);
+        }
+
+        #[test]
+        fn hours_are_offset_from_utc(timestamp in RANGE) {
+            let hour = hour_est(timestamp);
+            prop_assert!(hour < 24);
+            prop_assert_eq!(i64::from(hour), (timestamp - 5 * 3600).rem_euclid(DAY_SECS) / 3600);
+        }
+
+        #[test]
+        fn months_round_trip(year in 2000..2100i32, month in 1..=12u32, day in 1..=28u32) {
+            prop_assert_eq!(month_est(start_of_day_est(year, month, day)), (year, month));
+            prop_assert_eq!(month_est(start_of_day_est(year, month, 1) - 1), add_months(year, month, -1));
+        }
+
+        #[test]
+        fn adding_months_composes(year in 2000..2100i32, month in 1..=12u32, a in -60..60i32, b in -60..60i32) {
+            let (y, m) = add_months(year, month, a);
+            prop_assert_eq!(add_months(y, m, b), add_months(year, month, a + b));
+            prop_assert!((1..=12).contains(&add_months(year, month, a).1));
+        }
+    }
+}
@@ -6,7 +6,8 @@ mod write;
 pub use read::*;
 pub use write::*;
 
-pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
+/// Tables as of the first migration, see `db::migrations` for later schema changes
+pub fn baseline_schema(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "
         -- Stores data about the current bingo
@@ -26,6 +27,15 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             UNIQUE(bingo_kind, kind_specific_id)
         );
 
+        -- Start and end of each bingo seen as the current bingo, which splash lists are keyed off
+        CREATE TABLE IF NOT EXISTS bingo_periods (
+            bingo INTEGER NOT NULL,
+            bingo_kind INTEGER NOT NULL,
+            start INTEGER NOT NULL,
+            end INTEGER NOT NULL,
+            PRIMARY KEY(bingo, bingo_kind)
+        );
+
         -- Stores persistent configurable values
         CREATE TABLE IF NOT EXISTS config_global (
             id INTEGER PRIMARY KEY CHECK (id = 1),
@@ -33,6 +43,180 @@ pub fn initialise_tables(conn: &mut Connection) -> Result<()> {
             splash_reminder_emoji_id INTEGER,
             splash_reminder_emoji_count INTEGER
         );
+
+        -- Stores the staff-configured splash target for a bingo
+        CREATE TABLE IF NOT EXISTS splash_goals (
+            bingo INTEGER NOT NULL,
+            bingo_kind INTEGER NOT NULL,
+            target INTEGER NOT NULL,
+            PRIMARY KEY(bingo, bingo_kind)
+        );
+
+        -- Periods during which splashers are unavailable, at most one per splasher
+        CREATE TABLE IF NOT EXISTS splasher_away (
+            user INTEGER PRIMARY KEY,
+            start INTEGER NOT NULL,
+            end INTEGER NOT NULL,
+            reason TEXT
+        );
+
+        -- Menu sessions that were open when the bot last shut down, restored on startup
+        CREATE TABLE IF NOT EXISTS menu_sessions (
+            menu_id INTEGER PRIMARY KEY,
+            kind TEXT NOT NULL,
+            owner INTEGER NOT NULL,
+            owner_name TEXT NOT NULL,
+            guild_id INTEGER,
+            channel INTEGER NOT NULL,
+            message INTEGER NOT NULL,
+            -- JSON, see `to_persisted` of the menu's state
+            state TEXT NOT NULL,
+            persisted_at INTEGER NOT NULL
+        );
+
+        -- Staged features, enabled either for everyone or only for the users and roles in
+        -- `feature_flag_access`; features without a row are enabled for everyone
+        CREATE TABLE IF NOT EXISTS feature_flags (
+            name TEXT PRIMARY KEY,
+            enabled INTEGER NOT NULL CHECK (enabled in (0, 1))
+        );
+
+        -- Users and roles a disabled feature is available to regardless (kind 0: user, 1: role)
+        CREATE TABLE IF NOT EXISTS feature_flag_access (
+            name TEXT NOT NULL REFERENCES feature_flags(name) ON DELETE CASCADE,
+            kind INTEGER NOT NULL CHECK (kind in (0, 1)),
+            id INTEGER NOT NULL,
+            PRIMARY KEY(name, kind, id)
+        );
+
+        -- Stores the configured splash reminder delay, channel and message template, falling back to
+        -- the bot's defaults if unset
+        CREATE TABLE IF NOT EXISTS splash_reminder_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            delay_secs INTEGER NOT NULL,
+            channel INTEGER NOT NULL,
+            template TEXT NOT NULL
+        );
+
+        -- Stores the latest splash and when its reminder is due, so the timer survives restarts
+        CREATE TABLE IF NOT EXISTS splash_reminder_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            latest_message_id INTEGER NOT NULL,
+            deadline INTEGER NOT NULL
+        );
+
+        -- Stores rules that splash messages must follow to be counted
+        CREATE TABLE IF NOT EXISTS splash_rules_global (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            require_image INTEGER NOT NULL DEFAULT 0 CHECK (require_image in (0, 1))
+        );
+
+        -- Splash messages manually counted or uncounted by staff, regardless of their content
+        CREATE TABLE IF NOT EXISTS splash_overrides (
+            message_id INTEGER PRIMARY KEY,
+            counted INTEGER NOT NULL CHECK (counted in (0, 1))
+        );
+
+        -- Snapshot of every splasher's total for a month (formatted as `YYYY-MM`), taken at the month rollover
+        CREATE TABLE IF NOT EXISTS splash_monthly_totals (
+            month TEXT NOT NULL,
+            splasher INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY(month, splasher)
+        );
+
+        -- Months whose rollover announcement has already been posted
+        CREATE TABLE IF NOT EXISTS splash_rollover_announcements (
+            month TEXT PRIMARY KEY,
+            message_id INTEGER NOT NULL
+        );
+
+        -- Channel release notes are posted to, and the version they were last posted for
+        CREATE TABLE IF NOT EXISTS release_announcements (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            channel INTEGER NOT NULL,
+            version TEXT NOT NULL
+        );
+
+        -- Per-guild channel and role IDs, NULL values fall back to the compile-time defaults
+        CREATE TABLE IF NOT EXISTS guild_config (
+            guild_id INTEGER PRIMARY KEY,
+            splashes_channel INTEGER,
+            splash_ping_role INTEGER,
+            splasher_role INTEGER,
+            ty_channel INTEGER
+        );
+
+        -- Additional text prefix for prefix commands per guild, the bot mention always works
+        CREATE TABLE IF NOT EXISTS guild_prefixes (
+            guild_id INTEGER PRIMARY KEY,
+            prefix TEXT NOT NULL
+        );
+
+        -- How splashers are determined from splash messages, defaults to the author if absent
+        CREATE TABLE IF NOT EXISTS splash_ingestion_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            -- 0 = author, 1 = message content
+            mode INTEGER NOT NULL CHECK (mode in (0, 1)),
+            -- guild whose members are searched for splashers named in the message content
+            guild_id INTEGER
+        );
+
+        -- Splash messages of the splashes channels, stored as they arrive or when backfilling
+        CREATE TABLE IF NOT EXISTS splashes (
+            message_id INTEGER PRIMARY KEY,
+            channel INTEGER NOT NULL,
+            guild_id INTEGER,
+            timestamp INTEGER NOT NULL,
+            author INTEGER NOT NULL,
+            -- first non-bot user mentioned, used if splashers are taken from the message content
+            mentioned INTEGER,
+            -- lowercased name from a 'by <name>' pattern, used if nobody is mentioned
+            named TEXT,
+            hub TEXT,
+            has_image INTEGER NOT NULL CHECK (has_image in (0, 1))
+        );
+        CREATE INDEX IF NOT EXISTS splashes_channel_timestamp ON splashes (channel, timestamp);
+
+        -- Time range (unix timestamps) of each splashes channel whose splashes are fully stored
+        CREATE TABLE IF NOT EXISTS splashes_coverage (
+            channel INTEGER PRIMARY KEY,
+            oldest INTEGER NOT NULL,
+            newest INTEGER NOT NULL
+        );
+
+        -- Bingos whose splashes have been fully fetched from a splashes channel
+        CREATE TABLE IF NOT EXISTS splash_history_cache (
+            channel INTEGER NOT NULL,
+            -- bingo key like `extreme-3` (formerly months formatted as `YYYY-MM`, which are unused now)
+            month TEXT NOT NULL,
+            PRIMARY KEY(channel, month)
+        );
+
+        -- Splashes of cached bingos, so that past splash lists don't require rescanning the channel
+        CREATE TABLE IF NOT EXISTS splash_history_cache_items (
+            channel INTEGER NOT NULL,
+            month TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            splasher INTEGER NOT NULL,
+            FOREIGN KEY(channel, month) REFERENCES splash_history_cache(channel, month) ON DELETE CASCADE
+        );
+
+        -- Staff channel receiving digests of splashers who haven't splashed in a while, disabled if absent
+        CREATE TABLE IF NOT EXISTS splash_inactivity_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            -- guild whose splasher role is checked
+            guild_id INTEGER NOT NULL,
+            channel INTEGER NOT NULL,
+            threshold_days INTEGER NOT NULL,
+            -- unix timestamp of the latest digest
+            last_alert INTEGER
+        );
+
+        -- Splashers excluded from inactivity alerts, e.g. occasional splashers
+        CREATE TABLE IF NOT EXISTS splash_inactivity_opt_outs (
+            user INTEGER PRIMARY KEY
+        );
         ",
     )
 }
@@ -1,12 +1,25 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use anyhow::{Context as _, anyhow, bail};
-use poise::serenity_prelude::EmojiId;
+use poise::serenity_prelude::{EmojiId, GenericChannelId, GuildId, MessageId, RoleId, UserId};
 use rusqlite::{Connection, OptionalExtension as _, Result, params, types::Value};
 
 use crate::db::DbRequest;
 use crate::error::UserError;
-use crate::shared::types::{Bingo, BingoKind, SqlResponse};
+use crate::role::{
+    network_bingo::{DetectionRule, RegisteredNetworkBingo},
+    types::NetworkBingo,
+};
+use crate::shared::feature::{Feature, FeatureFlag};
+use crate::shared::menu::persist::{PersistedMenu, PersistedMenuKind};
+use crate::shared::shard::ShardConfig;
+use crate::shared::types::{
+    Bingo, BingoKind, DbStats, GuildConfig, SplashInactivityConfig, SplashIngestion,
+    SplashReminderConfig, SplasherAway, SplasherOnboarding, SqlResponse, StoredSplash,
+};
 
 pub struct GetBingoData {
     pub bingo_ids: Vec<u8>,
@@ -15,7 +28,7 @@ impl DbRequest for GetBingoData {
     type ReturnValue = Result<Vec<Bingo>>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let transaction = conn.transaction()?;
+        let transaction = conn.savepoint()?;
 
         let mut statement = transaction.prepare(
             "
@@ -91,6 +104,79 @@ impl DbRequest for GetCurrentBingo {
     }
 }
 
+pub struct GetBingoUniqueId {
+    pub bingo: Bingo,
+}
+impl DbRequest for GetBingoUniqueId {
+    /// `None` if the bingo hasn't been seen as the current bingo yet
+    type ReturnValue = Result<Option<u8>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        if let Some(unique_id) = self.bingo.unique_id {
+            return Ok(Some(unique_id));
+        }
+        if self.bingo.kind.properties().shares_unique_ids {
+            return Ok(Some(self.bingo.kind_specific_id));
+        }
+
+        conn.query_one(
+            "SELECT bingo FROM bingo_kind_id_map WHERE bingo_kind=?1 AND kind_specific_id=?2",
+            params![self.bingo.kind as u8, self.bingo.kind_specific_id],
+            |row| row.get("bingo"),
+        )
+        .optional()
+    }
+}
+
+pub struct GetBingoPeriod {
+    pub bingo: Bingo,
+}
+impl DbRequest for GetBingoPeriod {
+    /// Unix timestamps of the start and end, `None` for bingos from before periods were recorded
+    type ReturnValue = Result<Option<(i64, i64)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT start, end FROM bingo_periods WHERE bingo=?1 AND bingo_kind=?2",
+            params![self.bingo.kind_specific_id, self.bingo.kind as u8],
+            |row| Ok((row.get("start")?, row.get("end")?)),
+        )
+        .optional()
+    }
+}
+
+/// The bingo whose recorded period started within the range, preferring regular bingos if several
+/// did
+pub struct GetBingoStartedIn {
+    /// unix timestamps, the end is exclusive
+    pub start: i64,
+    pub end: i64,
+}
+impl DbRequest for GetBingoStartedIn {
+    /// `None` if no period starting within the range was recorded
+    type ReturnValue = Result<Option<Bingo>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT bingo, bingo_kind FROM bingo_periods
+            WHERE start >= ?1 AND start < ?2
+            ORDER BY bingo_kind ASC, start ASC
+            LIMIT 1
+            ",
+            params![self.start, self.end],
+            |row| {
+                Ok(Bingo::new(
+                    row.get("bingo")?,
+                    BingoKind::from_u8(row.get("bingo_kind")?),
+                    None,
+                ))
+            },
+        )
+        .optional()
+    }
+}
+
 pub struct GetIsNetworkBingo;
 impl DbRequest for GetIsNetworkBingo {
     type ReturnValue = Result<Option<bool>>;
@@ -109,6 +195,30 @@ impl DbRequest for GetIsNetworkBingo {
     }
 }
 
+/// Network bingos added after the built-in ones, ordered by ID
+pub struct GetRegisteredNetworkBingos;
+impl DbRequest for GetRegisteredNetworkBingos {
+    type ReturnValue = Result<Vec<RegisteredNetworkBingo>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "SELECT id, season, year, name, rule FROM network_bingo_registry ORDER BY id ASC",
+        )?;
+        statement
+            .query_map([], |row| {
+                Ok(RegisteredNetworkBingo {
+                    bingo: NetworkBingo::from_u8(row.get("id")?),
+                    season: row.get("season")?,
+                    year: row.get("year")?,
+                    name: row.get("name")?,
+                    rule: DetectionRule::from_key(&row.get::<_, String>("rule")?)
+                        .unwrap_or(DetectionRule::AllDifficulties),
+                })
+            })?
+            .collect()
+    }
+}
+
 pub struct RawQueryReadonly {
     pub sql: String,
 }
@@ -166,6 +276,313 @@ impl DbRequest for RawQueryReadonly {
     }
 }
 
+pub struct GetDbStats;
+impl DbRequest for GetDbStats {
+    type ReturnValue = Result<DbStats>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let table_names: Vec<String> = conn
+            .prepare(
+                "
+                SELECT name
+                FROM sqlite_schema
+                WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                ORDER BY name
+                ",
+            )?
+            .query_map([], |row| row.get("name"))?
+            .collect::<Result<_>>()?;
+
+        let tables = table_names
+            .into_iter()
+            .map(|table| {
+                // table names come from the schema itself, so they are safe to interpolate
+                let count =
+                    conn.query_one(&format!("SELECT COUNT(*) FROM \"{table}\""), [], |row| {
+                        row.get(0)
+                    })?;
+                Ok((table, count))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(DbStats {
+            tables,
+            page_size: conn.query_one("PRAGMA page_size", [], |row| row.get(0))?,
+            page_count: conn.query_one("PRAGMA page_count", [], |row| row.get(0))?,
+            free_pages: conn.query_one("PRAGMA freelist_count", [], |row| row.get(0))?,
+        })
+    }
+}
+
+/// Changes whenever another connection, e.g. of another process sharing the database, commits a
+/// write. Writes of this process's connection leave it unchanged.
+pub struct GetDataVersion;
+impl DbRequest for GetDataVersion {
+    type ReturnValue = Result<i64>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+    }
+}
+
+/// Away periods that haven't ended yet, including upcoming ones, ordered by their start
+pub struct GetSplasherAways {
+    pub now: i64,
+}
+impl DbRequest for GetSplasherAways {
+    type ReturnValue = Result<Vec<SplasherAway>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "SELECT user, start, end, reason FROM splasher_away WHERE end >= ?1 ORDER BY start",
+        )?;
+        statement
+            .query_map([self.now], |row| {
+                Ok(SplasherAway {
+                    user: UserId::new(row.get("user")?),
+                    start: row.get("start")?,
+                    end: row.get("end")?,
+                    reason: row.get("reason")?,
+                })
+            })?
+            .collect()
+    }
+}
+
+pub struct GetSplasherOnboarding {
+    pub user: UserId,
+}
+impl DbRequest for GetSplasherOnboarding {
+    /// `None` if the member was never sent the checklist
+    type ReturnValue = Result<Option<SplasherOnboarding>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let Some(started_at) = conn
+            .query_one(
+                "SELECT started_at FROM splasher_onboarding WHERE user=?1",
+                [self.user.get()],
+                |row| row.get(0),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let mut statement =
+            conn.prepare("SELECT item FROM splasher_onboarding_acks WHERE user=?1")?;
+        let acknowledged = statement
+            .query_map([self.user.get()], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+
+        Ok(Some(SplasherOnboarding {
+            user: self.user,
+            started_at,
+            acknowledged,
+        }))
+    }
+}
+
+/// Every member who was sent the checklist, oldest first
+pub struct GetSplasherOnboardings;
+impl DbRequest for GetSplasherOnboardings {
+    type ReturnValue = Result<Vec<SplasherOnboarding>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut acknowledged: HashMap<u64, HashSet<String>> = HashMap::new();
+        let mut statement = conn.prepare("SELECT user, item FROM splasher_onboarding_acks")?;
+        for ack in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (user, item) = ack?;
+            acknowledged.entry(user).or_default().insert(item);
+        }
+
+        let mut statement = conn
+            .prepare("SELECT user, started_at FROM splasher_onboarding ORDER BY started_at ASC")?;
+        statement
+            .query_map([], |row| {
+                let user: u64 = row.get("user")?;
+                Ok(SplasherOnboarding {
+                    user: UserId::new(user),
+                    started_at: row.get("started_at")?,
+                    acknowledged: acknowledged.remove(&user).unwrap_or_default(),
+                })
+            })?
+            .collect()
+    }
+}
+
+/// `None` if inactivity alerts are disabled
+pub struct GetSplashInactivityConfig;
+impl DbRequest for GetSplashInactivityConfig {
+    type ReturnValue = Result<Option<SplashInactivityConfig>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT guild_id, channel, threshold_days, last_alert FROM splash_inactivity_config \
+             WHERE id=1",
+            [],
+            |row| {
+                Ok(SplashInactivityConfig {
+                    guild_id: GuildId::new(row.get("guild_id")?),
+                    channel: GenericChannelId::new(row.get("channel")?),
+                    threshold_days: row.get("threshold_days")?,
+                    last_alert: row.get("last_alert")?,
+                })
+            },
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashInactivityOptOuts;
+impl DbRequest for GetSplashInactivityOptOuts {
+    type ReturnValue = Result<Vec<UserId>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare("SELECT user FROM splash_inactivity_opt_outs")?;
+        statement
+            .query_map([], |row| Ok(UserId::new(row.get("user")?)))?
+            .collect()
+    }
+}
+
+/// Returns and removes all persisted menu sessions, so that each is restored at most once
+pub struct TakePersistedMenuSessions {
+    /// only menus of guilds on these shards are taken, the others are left to their own process
+    pub shards: ShardConfig,
+}
+impl DbRequest for TakePersistedMenuSessions {
+    type ReturnValue = Result<Vec<PersistedMenu>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        // see `ShardConfig::owns`, menus outside of guilds belong to shard 0
+        const OWNED_GUILD_CONDITION: &str = "COALESCE((guild_id >> 22) % ?1, 0) BETWEEN ?2 AND ?3";
+        let shard_params = params![self.shards.total, self.shards.first, self.shards.last];
+
+        let transaction = conn.savepoint()?;
+        let menus = {
+            let mut statement = transaction.prepare(&format!(
+                "
+                SELECT menu_id, kind, owner, owner_name, guild_id, channel, message, state, persisted_at
+                FROM menu_sessions
+                WHERE {OWNED_GUILD_CONDITION}
+                ",
+            ))?;
+            statement
+                .query_map(shard_params, |row| {
+                    Ok((
+                        row.get::<_, u64>("menu_id")?,
+                        row.get::<_, String>("kind")?,
+                        (
+                            UserId::new(row.get("owner")?),
+                            row.get::<_, String>("owner_name")?,
+                        ),
+                        row.get::<_, Option<u64>>("guild_id")?.map(GuildId::new),
+                        GenericChannelId::new(row.get("channel")?),
+                        MessageId::new(row.get("message")?),
+                        row.get::<_, String>("state")?,
+                        row.get::<_, i64>("persisted_at")?,
+                    ))
+                })?
+                .filter_map(|row| {
+                    let (
+                        menu_id,
+                        kind,
+                        owner,
+                        guild_id,
+                        channel_id,
+                        message_id,
+                        state,
+                        persisted_at,
+                    ) = match row {
+                        Ok(row) => row,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    // skip menus whose kind or state can't be read anymore
+                    Some(Ok(PersistedMenu {
+                        menu_id,
+                        kind: PersistedMenuKind::from_key(&kind)?,
+                        owner,
+                        guild_id,
+                        channel_id,
+                        message_id,
+                        state: serde_json::from_str(&state).ok()?,
+                        persisted_at,
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        transaction.execute(
+            &format!("DELETE FROM menu_sessions WHERE {OWNED_GUILD_CONDITION}"),
+            shard_params,
+        )?;
+        transaction.commit()?;
+        Ok(menus)
+    }
+}
+
+pub struct GetFeatureFlag {
+    pub feature: Feature,
+}
+impl DbRequest for GetFeatureFlag {
+    type ReturnValue = Result<FeatureFlag>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let Some(enabled) = conn
+            .query_one(
+                "SELECT enabled FROM feature_flags WHERE name=?1",
+                [self.feature.key()],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()?
+        else {
+            return Ok(FeatureFlag::unset(self.feature));
+        };
+
+        let mut flag = FeatureFlag {
+            feature: self.feature,
+            enabled,
+            users: Vec::new(),
+            roles: Vec::new(),
+        };
+
+        let mut statement =
+            conn.prepare("SELECT kind, id FROM feature_flag_access WHERE name=?1")?;
+        let rows = statement.query_map([self.feature.key()], |row| {
+            Ok((row.get::<_, u8>(0)?, row.get::<_, u64>(1)?))
+        })?;
+        for row in rows {
+            match row? {
+                (0, id) => flag.users.push(UserId::new(id)),
+                (_, id) => flag.roles.push(RoleId::new(id)),
+            }
+        }
+
+        Ok(flag)
+    }
+}
+
+pub struct GetSplashReminderConfig;
+impl DbRequest for GetSplashReminderConfig {
+    type ReturnValue = Result<SplashReminderConfig>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        Ok(conn
+            .query_one(
+                "SELECT delay_secs, channel, template FROM splash_reminder_config WHERE id=1",
+                [],
+                |row| {
+                    Ok(SplashReminderConfig {
+                        delay_secs: row.get("delay_secs")?,
+                        channel: GenericChannelId::new(row.get("channel")?),
+                        template: row.get("template")?,
+                    })
+                },
+            )
+            .optional()?
+            .unwrap_or_default())
+    }
+}
+
 pub struct GetSplashReminder;
 impl DbRequest for GetSplashReminder {
     type ReturnValue = Result<(bool, Option<EmojiId>, Option<u32>)>;
@@ -192,3 +609,415 @@ impl DbRequest for GetSplashReminder {
         })
     }
 }
+
+pub struct GetSplashGoal {
+    pub bingo: Bingo,
+}
+impl DbRequest for GetSplashGoal {
+    type ReturnValue = Result<Option<u32>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT target FROM splash_goals WHERE bingo=?1 AND bingo_kind=?2",
+            params![self.bingo.kind_specific_id, self.bingo.kind as u8],
+            |row| row.get("target"),
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashImageRequired;
+impl DbRequest for GetSplashImageRequired {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT require_image FROM splash_rules_global WHERE id=1",
+            [],
+            |row| row.get("require_image"),
+        )
+        .optional()
+        .map(|opt| opt.unwrap_or(false))
+    }
+}
+
+pub struct GetSplashOverrides;
+impl DbRequest for GetSplashOverrides {
+    /// whether each overridden message is counted as a splash
+    type ReturnValue = Result<HashMap<MessageId, bool>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare("SELECT message_id, counted FROM splash_overrides")?;
+
+        statement
+            .query_map([], |row| {
+                Ok((MessageId::new(row.get("message_id")?), row.get("counted")?))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetGuildConfig {
+    /// defaults are returned outside of guilds
+    pub guild_id: Option<GuildId>,
+}
+impl DbRequest for GetGuildConfig {
+    type ReturnValue = Result<GuildConfig>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let Some(guild_id) = self.guild_id else {
+            return Ok(GuildConfig::default());
+        };
+
+        conn.query_one(
+            "
+            SELECT splashes_channel, splash_ping_role, splasher_role, ty_channel, splash_reminder_role
+            FROM guild_config WHERE guild_id=?1
+            ",
+            params![guild_id.get()],
+            |row| {
+                let default = GuildConfig::default();
+                let channel = |column: &str| {
+                    row.get::<_, Option<u64>>(column)
+                        .map(|id| id.map(GenericChannelId::new))
+                };
+                let role = |column: &str| {
+                    row.get::<_, Option<u64>>(column)
+                        .map(|id| id.map(RoleId::new))
+                };
+
+                Ok(GuildConfig {
+                    splashes_channel: channel("splashes_channel")?
+                        .unwrap_or(default.splashes_channel),
+                    splash_ping_role: role("splash_ping_role")?.unwrap_or(default.splash_ping_role),
+                    splasher_role: role("splasher_role")?.unwrap_or(default.splasher_role),
+                    ty_channel: channel("ty_channel")?.unwrap_or(default.ty_channel),
+                    splash_reminder_role: role("splash_reminder_role")?
+                        .unwrap_or(default.splash_reminder_role),
+                })
+            },
+        )
+        .optional()
+        .map(Option::unwrap_or_default)
+    }
+}
+
+pub struct GetGuildPrefix {
+    pub guild_id: GuildId,
+}
+impl DbRequest for GetGuildPrefix {
+    type ReturnValue = Result<Option<String>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT prefix FROM guild_prefixes WHERE guild_id=?1",
+            params![self.guild_id.get()],
+            |row| row.get("prefix"),
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashIngestion;
+impl DbRequest for GetSplashIngestion {
+    type ReturnValue = Result<SplashIngestion>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT mode, guild_id FROM splash_ingestion_config WHERE id=1",
+            [],
+            |row| {
+                let mode: u8 = row.get("mode")?;
+                let guild_id: Option<u64> = row.get("guild_id")?;
+                Ok(match (mode, guild_id) {
+                    (1, Some(guild_id)) => SplashIngestion::Content {
+                        guild_id: GuildId::new(guild_id),
+                    },
+                    _ => SplashIngestion::Author,
+                })
+            },
+        )
+        .optional()
+        .map(Option::unwrap_or_default)
+    }
+}
+
+pub struct GetMonthlySplashTotals {
+    /// formatted as `YYYY-MM`
+    pub month: String,
+}
+impl DbRequest for GetMonthlySplashTotals {
+    /// splashers sorted by descending count
+    type ReturnValue = Result<Vec<(UserId, u32)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT splasher, count
+            FROM splash_monthly_totals
+            WHERE month=?1
+            ORDER BY count DESC
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.month], |row| {
+                Ok((UserId::new(row.get("splasher")?), row.get("count")?))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetCachedSplashPeriod {
+    pub channel: GenericChannelId,
+    /// identifies the bingo, see `SplashPeriod::key`
+    pub period: String,
+}
+impl DbRequest for GetCachedSplashPeriod {
+    /// unix timestamps and splashers of the bingo's splashes, `None` if the bingo isn't cached
+    type ReturnValue = Result<Option<Vec<(i64, UserId)>>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let cached = conn
+            .query_one(
+                "SELECT 1 FROM splash_history_cache WHERE channel=?1 AND month=?2",
+                params![self.channel.get(), self.period],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if cached.is_none() {
+            return Ok(None);
+        }
+
+        let mut statement = conn.prepare(
+            "
+            SELECT timestamp, splasher
+            FROM splash_history_cache_items
+            WHERE channel=?1 AND month=?2
+            ORDER BY timestamp
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.channel.get(), self.period], |row| {
+                Ok((row.get("timestamp")?, UserId::new(row.get("splasher")?)))
+            })?
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+}
+
+pub struct GetStoredSplashes {
+    pub channel: GenericChannelId,
+    /// unix timestamps, inclusive
+    pub start: i64,
+    pub end: i64,
+}
+impl DbRequest for GetStoredSplashes {
+    /// newest first
+    type ReturnValue = Result<Vec<StoredSplash>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT message_id, channel, guild_id, timestamp, author, mentioned, named, hub, has_image
+            FROM splashes
+            WHERE channel=?1 AND timestamp BETWEEN ?2 AND ?3
+            ORDER BY timestamp DESC, message_id DESC
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.channel.get(), self.start, self.end], |row| {
+                Ok(StoredSplash {
+                    message_id: MessageId::new(row.get("message_id")?),
+                    channel: GenericChannelId::new(row.get("channel")?),
+                    guild_id: row.get::<_, Option<u64>>("guild_id")?.map(GuildId::new),
+                    timestamp: row.get("timestamp")?,
+                    author: UserId::new(row.get("author")?),
+                    mentioned: row.get::<_, Option<u64>>("mentioned")?.map(UserId::new),
+                    named: row.get("named")?,
+                    hub: row.get("hub")?,
+                    has_image: row.get("has_image")?,
+                })
+            })?
+            .collect()
+    }
+}
+
+pub struct GetSplashStoreCoverage {
+    pub channel: GenericChannelId,
+}
+impl DbRequest for GetSplashStoreCoverage {
+    /// oldest and newest unix timestamp up to which the channel's splashes are fully stored
+    type ReturnValue = Result<Option<(i64, i64)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT oldest, newest FROM splashes_coverage WHERE channel=?1",
+            params![self.channel.get()],
+            |row| Ok((row.get("oldest")?, row.get("newest")?)),
+        )
+        .optional()
+    }
+}
+
+pub struct GetRolloverAnnounced {
+    /// formatted as `YYYY-MM`
+    pub month: String,
+}
+impl DbRequest for GetRolloverAnnounced {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT EXISTS(SELECT 1 FROM splash_rollover_announcements WHERE month=?1)",
+            params![self.month],
+            |row| row.get(0),
+        )
+    }
+}
+
+pub struct GetReleaseAnnouncement;
+impl DbRequest for GetReleaseAnnouncement {
+    /// announcement channel and last announced version
+    type ReturnValue = Result<Option<(GenericChannelId, String)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT channel, version FROM release_announcements WHERE id=1",
+            [],
+            |row| {
+                Ok((
+                    GenericChannelId::new(row.get("channel")?),
+                    row.get("version")?,
+                ))
+            },
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashThanksOf {
+    /// message in the thank-you channel
+    pub source_message_id: MessageId,
+}
+impl DbRequest for GetSplashThanksOf {
+    /// splash the message thanked and its splasher, `None` if the message wasn't a thank-you
+    type ReturnValue = Result<Option<(MessageId, Option<UserId>)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "
+            SELECT splash_message_id, splasher FROM splash_thanks
+            WHERE source_message_id=?1
+            ORDER BY timestamp ASC
+            LIMIT 1
+            ",
+            params![self.source_message_id.get()],
+            |row| {
+                Ok((
+                    MessageId::new(row.get("splash_message_id")?),
+                    row.get::<_, Option<u64>>("splasher")?.map(UserId::new),
+                ))
+            },
+        )
+        .optional()
+    }
+}
+
+pub struct GetSplashThanksCounts {
+    /// unix timestamps, inclusive
+    pub start: i64,
+    pub end: i64,
+}
+impl DbRequest for GetSplashThanksCounts {
+    /// thanks per splasher, most thanked first
+    type ReturnValue = Result<Vec<(UserId, u32)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "
+            SELECT splasher, COUNT(*) AS count FROM splash_thanks
+            WHERE splasher IS NOT NULL AND timestamp BETWEEN ?1 AND ?2
+            GROUP BY splasher
+            ORDER BY count DESC, splasher ASC
+            ",
+        )?;
+
+        statement
+            .query_map(params![self.start, self.end], |row| {
+                Ok((UserId::new(row.get("splasher")?), row.get("count")?))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetThankedSplashes {
+    /// unix timestamps of the thanks, inclusive
+    pub start: i64,
+    pub end: i64,
+}
+impl DbRequest for GetThankedSplashes {
+    type ReturnValue = Result<HashSet<MessageId>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement = conn.prepare(
+            "SELECT DISTINCT splash_message_id FROM splash_thanks WHERE timestamp BETWEEN ?1 AND ?2",
+        )?;
+
+        statement
+            .query_map(params![self.start, self.end], |row| {
+                Ok(MessageId::new(row.get(0)?))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetSplashReminderSkipped {
+    pub message_id: MessageId,
+}
+impl DbRequest for GetSplashReminderSkipped {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT EXISTS(SELECT 1 FROM splash_reminder_skips WHERE message_id=?1)",
+            params![self.message_id.get()],
+            |row| row.get(0),
+        )
+    }
+}
+
+pub struct GetSplashReminderTimers;
+impl DbRequest for GetSplashReminderTimers {
+    /// splash messages with a pending reminder, along with the unix timestamp of its deadline
+    type ReturnValue = Result<Vec<(MessageId, i64)>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let mut statement =
+            conn.prepare("SELECT message_id, deadline FROM splash_reminder_timers")?;
+        statement
+            .query_map([], |row| {
+                Ok((MessageId::new(row.get("message_id")?), row.get("deadline")?))
+            })?
+            .collect()
+    }
+}
+
+pub struct GetSplashReminderTimer {
+    pub message_id: MessageId,
+}
+impl DbRequest for GetSplashReminderTimer {
+    /// unix timestamp of the reminder deadline, `None` if the reminder isn't pending
+    type ReturnValue = Result<Option<i64>>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.query_one(
+            "SELECT deadline FROM splash_reminder_timers WHERE message_id=?1",
+            params![self.message_id.get()],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+}
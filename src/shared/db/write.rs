@@ -1,8 +1,15 @@
-use poise::serenity_prelude::EmojiId;
+use anyhow::Context as _;
+use poise::serenity_prelude::{EmojiId, GenericChannelId, GuildId, MessageId, UserId};
 use rusqlite::{Connection, Result, params};
 
 use crate::db::DbRequest;
-use crate::shared::types::{Bingo, BingoKind};
+use crate::role::{network_bingo::DetectionRule, types::NetworkBingo};
+use crate::shared::feature::{Feature, FeatureAccess};
+use crate::shared::menu::persist::PersistedMenu;
+use crate::shared::types::{
+    Bingo, BingoKind, GuildConfigKey, SplashInactivityConfig, SplashIngestion,
+    SplashReminderConfig, SplashThanks, SplasherAway, StoredSplash,
+};
 
 pub struct AddBingoMapping {
     pub bingo_id: u8,
@@ -12,20 +19,18 @@ impl DbRequest for AddBingoMapping {
     type ReturnValue = Result<Bingo>;
 
     fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
-        let kind_specific_id = match self.bingo_kind {
-            BingoKind::Normal => self.bingo_id,
-            _ => {
-                let mut statement = conn.prepare(
-                    "SELECT kind_specific_id FROM bingo_kind_id_map WHERE bingo_kind=?1",
-                )?;
-                let kind_bingos: Vec<u8> = statement
-                    .query_map(params![self.bingo_kind as u8], |row| {
-                        row.get("kind_specific_id")
-                    })?
-                    .collect::<Result<_>>()?;
+        let kind_specific_id = if self.bingo_kind.properties().shares_unique_ids {
+            self.bingo_id
+        } else {
+            let mut statement =
+                conn.prepare("SELECT kind_specific_id FROM bingo_kind_id_map WHERE bingo_kind=?1")?;
+            let kind_bingos: Vec<u8> = statement
+                .query_map(params![self.bingo_kind as u8], |row| {
+                    row.get("kind_specific_id")
+                })?
+                .collect::<Result<_>>()?;
 
-                kind_bingos.iter().max().map(|id| id + 1).unwrap_or(0)
-            }
+            kind_bingos.iter().max().map(|id| id + 1).unwrap_or(0)
         };
 
         conn.execute(
@@ -69,6 +74,34 @@ impl DbRequest for SetCurrentBingo {
     }
 }
 
+pub struct RecordBingoPeriod {
+    pub bingo: Bingo,
+    pub start: i64,
+    pub end: i64,
+}
+impl DbRequest for RecordBingoPeriod {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO bingo_periods (bingo, bingo_kind, start, end)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(bingo, bingo_kind) DO UPDATE SET
+                start = excluded.start,
+                end = excluded.end
+            ",
+            params![
+                self.bingo.kind_specific_id,
+                self.bingo.kind as u8,
+                self.start,
+                self.end
+            ],
+        )?;
+        Ok(())
+    }
+}
+
 pub struct SetIsNetworkBingo {
     pub is_active: bool,
 }
@@ -89,6 +122,57 @@ impl DbRequest for SetIsNetworkBingo {
     }
 }
 
+/// Keeps the ID and detection rule of an already registered event, only updating its name if given,
+/// so that rediscovering an event doesn't override the rule chosen by staff. New events are assigned the next free ID within the statement, so that registrations of
+/// separate processes sharing the database can't collide.
+pub struct RegisterNetworkBingo {
+    pub season: String,
+    pub year: String,
+    pub name: Option<String>,
+    /// used for new events without a name
+    pub default_name: String,
+    /// only used for new events
+    pub rule: DetectionRule,
+    pub registered_at: i64,
+}
+impl DbRequest for RegisterNetworkBingo {
+    type ReturnValue = anyhow::Result<NetworkBingo>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let latest_builtin = NetworkBingo::BUILTIN
+            .last()
+            .map_or(NetworkBingo::UNKNOWN.id(), |bingo| bingo.id());
+
+        let transaction = conn.savepoint()?;
+        // `WHERE true` avoids the parsing ambiguity between a join and the upsert clause
+        let id: i64 = transaction.query_row(
+            "
+            INSERT INTO network_bingo_registry (id, season, year, name, rule, registered_at)
+            SELECT MAX(COALESCE(MAX(id), ?1), ?1) + 1, ?2, ?3, COALESCE(?4, ?5), ?6, ?7
+            FROM network_bingo_registry WHERE true
+            ON CONFLICT(season, year) DO UPDATE SET
+                name = COALESCE(?4, name)
+            RETURNING id
+            ",
+            params![
+                latest_builtin,
+                self.season,
+                self.year,
+                self.name,
+                self.default_name,
+                self.rule.key(),
+                self.registered_at
+            ],
+            |row| row.get(0),
+        )?;
+        // the savepoint is rolled back when dropped
+        let id = u8::try_from(id).context("No Network Bingo IDs left")?;
+        transaction.commit()?;
+
+        Ok(NetworkBingo::from_u8(id))
+    }
+}
+
 #[allow(dead_code)] // used in sql script command (disabled)
 pub struct RawBatch {
     pub sql: String,
@@ -126,3 +210,761 @@ impl DbRequest for SetSplashReminder {
         Ok(())
     }
 }
+
+pub struct SetSplasherAway {
+    pub away: SplasherAway,
+}
+impl DbRequest for SetSplasherAway {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "INSERT OR REPLACE INTO splasher_away (user, start, end, reason) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                self.away.user.get(),
+                self.away.start,
+                self.away.end,
+                self.away.reason
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns whether the splasher was marked as away
+pub struct ClearSplasherAway {
+    pub user: UserId,
+}
+impl DbRequest for ClearSplasherAway {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        Ok(conn.execute("DELETE FROM splasher_away WHERE user=?1", [self.user.get()])? > 0)
+    }
+}
+
+/// Returns whether the checklist hadn't been sent to the member before
+pub struct StartSplasherOnboarding {
+    pub user: UserId,
+    pub started_at: i64,
+}
+impl DbRequest for StartSplasherOnboarding {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        Ok(conn.execute(
+            "INSERT OR IGNORE INTO splasher_onboarding (user, started_at) VALUES (?1, ?2)",
+            params![self.user.get(), self.started_at],
+        )? > 0)
+    }
+}
+
+pub struct AcknowledgeOnboardingItem {
+    pub user: UserId,
+    pub item: &'static str,
+    pub acknowledged_at: i64,
+}
+impl DbRequest for AcknowledgeOnboardingItem {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR IGNORE INTO splasher_onboarding_acks (user, item, acknowledged_at)
+            VALUES (?1, ?2, ?3)
+            ",
+            params![self.user.get(), self.item, self.acknowledged_at],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetSplashInactivityConfig {
+    /// disables inactivity alerts if `None`, `last_alert` is kept when reconfiguring
+    pub config: Option<SplashInactivityConfig>,
+}
+impl DbRequest for SetSplashInactivityConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.config {
+            Some(config) => conn.execute(
+                "
+                INSERT INTO splash_inactivity_config (id, guild_id, channel, threshold_days)
+                VALUES (1, ?1, ?2, ?3)
+                ON CONFLICT(id) DO UPDATE SET
+                    guild_id = excluded.guild_id,
+                    channel = excluded.channel,
+                    threshold_days = excluded.threshold_days
+                ",
+                params![
+                    config.guild_id.get(),
+                    config.channel.get(),
+                    config.threshold_days
+                ],
+            )?,
+            None => conn.execute("DELETE FROM splash_inactivity_config WHERE id=1", [])?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetSplashInactivityAlerted {
+    /// unix timestamp of the digest
+    pub at: i64,
+}
+impl DbRequest for SetSplashInactivityAlerted {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "UPDATE splash_inactivity_config SET last_alert=?1 WHERE id=1",
+            [self.at],
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns whether the splasher's opt-out changed
+pub struct SetSplashInactivityOptOut {
+    pub user: UserId,
+    pub opted_out: bool,
+}
+impl DbRequest for SetSplashInactivityOptOut {
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let changed = if self.opted_out {
+            conn.execute(
+                "INSERT OR IGNORE INTO splash_inactivity_opt_outs (user) VALUES (?1)",
+                [self.user.get()],
+            )?
+        } else {
+            conn.execute(
+                "DELETE FROM splash_inactivity_opt_outs WHERE user=?1",
+                [self.user.get()],
+            )?
+        };
+        Ok(changed > 0)
+    }
+}
+
+pub struct PersistMenuSessions {
+    pub menus: Vec<PersistedMenu>,
+}
+impl DbRequest for PersistMenuSessions {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT OR REPLACE INTO menu_sessions
+                    (menu_id, kind, owner, owner_name, guild_id, channel, message, state, persisted_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ",
+            )?;
+            for menu in &self.menus {
+                statement.execute(params![
+                    menu.menu_id,
+                    menu.kind.key(),
+                    menu.owner.0.get(),
+                    menu.owner.1,
+                    menu.guild_id.map(GuildId::get),
+                    menu.channel_id.get(),
+                    menu.message_id.get(),
+                    menu.state.to_string(),
+                    menu.persisted_at,
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+}
+
+pub struct SetFeatureEnabled {
+    pub feature: Feature,
+    pub enabled: bool,
+}
+impl DbRequest for SetFeatureEnabled {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO feature_flags (name, enabled) VALUES (?1, ?2)
+            ON CONFLICT(name) DO UPDATE SET enabled = excluded.enabled
+            ",
+            params![self.feature.key(), self.enabled],
+        )?;
+        Ok(())
+    }
+}
+
+/// Adds or removes a user or role from a feature's allow list. Adding stages the feature as disabled
+/// for everyone else if it wasn't staged yet.
+pub struct SetFeatureAccess {
+    pub feature: Feature,
+    pub access: FeatureAccess,
+    pub allowed: bool,
+}
+impl DbRequest for SetFeatureAccess {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let (kind, id) = match self.access {
+            FeatureAccess::User(user) => (0, user.get()),
+            FeatureAccess::Role(role) => (1, role.get()),
+        };
+
+        let transaction = conn.savepoint()?;
+        if self.allowed {
+            transaction.execute(
+                "INSERT OR IGNORE INTO feature_flags (name, enabled) VALUES (?1, 0)",
+                [self.feature.key()],
+            )?;
+            transaction.execute(
+                "INSERT OR IGNORE INTO feature_flag_access (name, kind, id) VALUES (?1, ?2, ?3)",
+                params![self.feature.key(), kind, id],
+            )?;
+        } else {
+            transaction.execute(
+                "DELETE FROM feature_flag_access WHERE name=?1 AND kind=?2 AND id=?3",
+                params![self.feature.key(), kind, id],
+            )?;
+        }
+        transaction.commit()
+    }
+}
+
+pub struct SetSplashReminderConfig {
+    pub config: SplashReminderConfig,
+}
+impl DbRequest for SetSplashReminderConfig {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO splash_reminder_config (id, delay_secs, channel, template)
+            VALUES (1, ?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET
+                delay_secs = excluded.delay_secs,
+                channel = excluded.channel,
+                template = excluded.template
+            ",
+            params![
+                self.config.delay_secs,
+                self.config.channel.get(),
+                self.config.template
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetSplashGoal {
+    pub bingo: Bingo,
+    /// Removes the goal if `None`
+    pub target: Option<u32>,
+}
+impl DbRequest for SetSplashGoal {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.target {
+            Some(target) => conn.execute(
+                "
+                INSERT INTO splash_goals (bingo, bingo_kind, target)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(bingo, bingo_kind) DO UPDATE SET
+                    target = excluded.target
+                ",
+                params![self.bingo.kind_specific_id, self.bingo.kind as u8, target],
+            )?,
+            None => conn.execute(
+                "DELETE FROM splash_goals WHERE bingo=?1 AND bingo_kind=?2",
+                params![self.bingo.kind_specific_id, self.bingo.kind as u8],
+            )?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetSplashImageRequired {
+    pub required: bool,
+}
+impl DbRequest for SetSplashImageRequired {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO splash_rules_global (id, require_image)
+            VALUES (1, ?1)
+            ON CONFLICT(id) DO UPDATE SET
+                require_image = excluded.require_image
+            ",
+            params![self.required],
+        )?;
+        clear_splash_history_cache(conn)
+    }
+}
+
+pub struct SetSplashReminderTimer {
+    pub message_id: MessageId,
+    /// unix timestamp of the reminder deadline, removes the timer if `None`
+    pub deadline: Option<i64>,
+}
+impl DbRequest for SetSplashReminderTimer {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.deadline {
+            Some(deadline) => conn.execute(
+                "
+                INSERT INTO splash_reminder_timers (message_id, deadline)
+                VALUES (?1, ?2)
+                ON CONFLICT(message_id) DO UPDATE SET
+                    deadline = excluded.deadline
+                ",
+                params![self.message_id.get(), deadline],
+            )?,
+            None => conn.execute(
+                "DELETE FROM splash_reminder_timers WHERE message_id=?1",
+                params![self.message_id.get()],
+            )?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetSplashReminderSkipped {
+    pub message_id: MessageId,
+    /// whether the splash should never trigger a reminder
+    pub skipped: bool,
+}
+impl DbRequest for SetSplashReminderSkipped {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        if self.skipped {
+            conn.execute(
+                "INSERT OR IGNORE INTO splash_reminder_skips (message_id) VALUES (?1)",
+                params![self.message_id.get()],
+            )?;
+        } else {
+            conn.execute(
+                "DELETE FROM splash_reminder_skips WHERE message_id=?1",
+                params![self.message_id.get()],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct RecordSplashThanks {
+    pub thanks: SplashThanks,
+}
+impl DbRequest for RecordSplashThanks {
+    /// `false` if the thank-you was already recorded
+    type ReturnValue = Result<bool>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let thanks = self.thanks;
+        let inserted = conn.execute(
+            "
+            INSERT OR IGNORE INTO splash_thanks
+                (source_message_id, thanked_by, splash_message_id, splasher, timestamp)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ",
+            params![
+                thanks.source_message_id.get(),
+                thanks.thanked_by.get(),
+                thanks.splash_message_id.get(),
+                thanks.splasher.map(UserId::get),
+                thanks.timestamp,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+}
+
+pub struct SetSplashOverride {
+    pub message_id: MessageId,
+    /// whether the message is counted as a splash, regardless of its content
+    pub counted: bool,
+}
+impl DbRequest for SetSplashOverride {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO splash_overrides (message_id, counted)
+            VALUES (?1, ?2)
+            ON CONFLICT(message_id) DO UPDATE SET
+                counted = excluded.counted
+            ",
+            params![self.message_id.get(), self.counted],
+        )?;
+        clear_splash_history_cache(conn)
+    }
+}
+
+pub struct SetGuildConfigValue {
+    pub guild_id: GuildId,
+    pub key: GuildConfigKey,
+    /// channel or role ID, resets to the default if `None`
+    pub value: Option<u64>,
+}
+impl DbRequest for SetGuildConfigValue {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        // column names come from a fixed set, so interpolating them is safe
+        let column = self.key.column();
+        conn.execute(
+            &format!(
+                "
+                INSERT INTO guild_config (guild_id, {column})
+                VALUES (?1, ?2)
+                ON CONFLICT(guild_id) DO UPDATE SET
+                    {column} = excluded.{column}
+                "
+            ),
+            params![self.guild_id.get(), self.value],
+        )?;
+
+        // the ping role determines which messages are splashes, so the stored splashes need to be
+        // fetched again
+        if self.key == GuildConfigKey::SplashPingRole {
+            conn.execute("DELETE FROM splashes", [])?;
+            conn.execute("DELETE FROM splashes_coverage", [])?;
+            clear_splash_history_cache(conn)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct SetGuildPrefix {
+    pub guild_id: GuildId,
+    /// removes the text prefix if `None`
+    pub prefix: Option<String>,
+}
+impl DbRequest for SetGuildPrefix {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        match self.prefix {
+            Some(prefix) => conn.execute(
+                "
+                INSERT INTO guild_prefixes (guild_id, prefix)
+                VALUES (?1, ?2)
+                ON CONFLICT(guild_id) DO UPDATE SET
+                    prefix = excluded.prefix
+                ",
+                params![self.guild_id.get(), prefix],
+            )?,
+            None => conn.execute(
+                "DELETE FROM guild_prefixes WHERE guild_id=?1",
+                params![self.guild_id.get()],
+            )?,
+        };
+        Ok(())
+    }
+}
+
+pub struct SetSplashIngestion {
+    pub ingestion: SplashIngestion,
+}
+impl DbRequest for SetSplashIngestion {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let (mode, guild_id) = match self.ingestion {
+            SplashIngestion::Author => (0, None),
+            SplashIngestion::Content { guild_id } => (1, Some(guild_id.get())),
+        };
+
+        conn.execute(
+            "
+            INSERT INTO splash_ingestion_config (id, mode, guild_id)
+            VALUES (1, ?1, ?2)
+            ON CONFLICT(id) DO UPDATE SET
+                mode = excluded.mode,
+                guild_id = excluded.guild_id
+            ",
+            params![mode, guild_id],
+        )?;
+        clear_splash_history_cache(conn)
+    }
+}
+
+pub struct SaveMonthlySplashTotals {
+    /// formatted as `YYYY-MM`
+    pub month: String,
+    pub totals: Vec<(UserId, u32)>,
+}
+impl DbRequest for SaveMonthlySplashTotals {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        // replace any previous snapshot, e.g. if the rollover was interrupted
+        transaction.execute(
+            "DELETE FROM splash_monthly_totals WHERE month=?1",
+            params![self.month],
+        )?;
+
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT INTO splash_monthly_totals (month, splasher, count)
+                VALUES (?1, ?2, ?3)
+                ",
+            )?;
+            for (splasher, count) in &self.totals {
+                statement.execute(params![self.month, splasher.get(), count])?;
+            }
+        }
+
+        transaction.commit()
+    }
+}
+
+pub struct CacheSplashPeriod {
+    pub channel: GenericChannelId,
+    /// identifies the bingo, see `SplashPeriod::key`
+    pub period: String,
+    /// unix timestamps and splashers
+    pub splashes: Vec<(i64, UserId)>,
+}
+impl DbRequest for CacheSplashPeriod {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        // replaces the cached splashes through the cascade
+        transaction.execute(
+            "DELETE FROM splash_history_cache WHERE channel=?1 AND month=?2",
+            params![self.channel.get(), self.period],
+        )?;
+        transaction.execute(
+            "INSERT INTO splash_history_cache (channel, month) VALUES (?1, ?2)",
+            params![self.channel.get(), self.period],
+        )?;
+
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT INTO splash_history_cache_items (channel, month, timestamp, splasher)
+                VALUES (?1, ?2, ?3, ?4)
+                ",
+            )?;
+            for (timestamp, splasher) in &self.splashes {
+                statement.execute(params![
+                    self.channel.get(),
+                    self.period,
+                    timestamp,
+                    splasher.get()
+                ])?;
+            }
+        }
+
+        transaction.commit()
+    }
+}
+
+/// Cached splashes of past months become outdated when the rules for counting splashes change
+fn clear_splash_history_cache(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM splash_history_cache", [])?;
+    Ok(())
+}
+
+pub struct StoreSplashes {
+    pub splashes: Vec<StoredSplash>,
+}
+impl DbRequest for StoreSplashes {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        let transaction = conn.savepoint()?;
+
+        {
+            let mut statement = transaction.prepare(
+                "
+                INSERT OR REPLACE INTO splashes
+                    (message_id, channel, guild_id, timestamp, author, mentioned, named, hub, has_image)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ",
+            )?;
+            for splash in &self.splashes {
+                statement.execute(params![
+                    splash.message_id.get(),
+                    splash.channel.get(),
+                    splash.guild_id.map(GuildId::get),
+                    splash.timestamp,
+                    splash.author.get(),
+                    splash.mentioned.map(UserId::get),
+                    splash.named,
+                    splash.hub,
+                    splash.has_image,
+                ])?;
+            }
+        }
+
+        transaction.commit()
+    }
+}
+
+pub struct DeleteStoredSplash {
+    pub message_id: MessageId,
+}
+impl DbRequest for DeleteStoredSplash {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "DELETE FROM splashes WHERE message_id=?1",
+            params![self.message_id.get()],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetSplashStoreCoverage {
+    pub channel: GenericChannelId,
+    /// unix timestamps
+    pub oldest: i64,
+    pub newest: i64,
+}
+impl DbRequest for SetSplashStoreCoverage {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT INTO splashes_coverage (channel, oldest, newest)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(channel) DO UPDATE SET
+                oldest = excluded.oldest,
+                newest = excluded.newest
+            ",
+            params![self.channel.get(), self.oldest, self.newest],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct SetReleaseAnnouncement {
+    pub channel: GenericChannelId,
+    pub version: String,
+}
+impl DbRequest for SetReleaseAnnouncement {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR REPLACE INTO release_announcements (id, channel, version)
+            VALUES (1, ?1, ?2)
+            ",
+            params![self.channel.get(), self.version],
+        )?;
+        Ok(())
+    }
+}
+
+/// Rebuilds the database file, returning the space of deleted rows to the file system
+pub struct Vacuum;
+impl DbRequest for Vacuum {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute_batch("VACUUM")
+    }
+}
+
+pub struct SetRolloverAnnounced {
+    /// formatted as `YYYY-MM`
+    pub month: String,
+    pub message_id: MessageId,
+}
+impl DbRequest for SetRolloverAnnounced {
+    type ReturnValue = Result<()>;
+
+    fn execute(self, conn: &mut Connection) -> Self::ReturnValue {
+        conn.execute(
+            "
+            INSERT OR REPLACE INTO splash_rollover_announcements (month, message_id)
+            VALUES (?1, ?2)
+            ",
+            params![self.month, self.message_id.get()],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_connection;
+    use crate::shared::db::GetRegisteredNetworkBingos;
+
+    fn register(
+        conn: &mut Connection,
+        season: &str,
+        name: Option<&str>,
+        rule: DetectionRule,
+    ) -> NetworkBingo {
+        RegisterNetworkBingo {
+            season: season.to_string(),
+            year: "2030".to_string(),
+            name: name.map(str::to_string),
+            default_name: format!("{season} default"),
+            rule,
+            registered_at: 0,
+        }
+        .execute(conn)
+        .unwrap()
+    }
+
+    #[test]
+    fn register_network_bingo_allocates_consecutive_ids() {
+        let mut conn = test_connection();
+        let latest_builtin = NetworkBingo::BUILTIN.last().unwrap().id();
+
+        let summer = register(&mut conn, "summer", None, DetectionRule::AllDifficulties);
+        let winter = register(&mut conn, "winter", None, DetectionRule::AllDifficulties);
+
+        assert_eq!(summer.id(), latest_builtin + 1);
+        assert_eq!(winter.id(), latest_builtin + 2);
+    }
+
+    #[test]
+    fn register_network_bingo_keeps_id_and_rule_of_known_event() {
+        let mut conn = test_connection();
+
+        let bingo = register(&mut conn, "summer", None, DetectionRule::AllDifficulties);
+        let renamed = register(
+            &mut conn,
+            "summer",
+            Some("Summer Bingo"),
+            DetectionRule::DifficultyPair,
+        );
+        // rediscovered without a name
+        let rediscovered = register(&mut conn, "summer", None, DetectionRule::DifficultyPair);
+
+        assert_eq!(renamed, bingo);
+        assert_eq!(rediscovered, bingo);
+
+        let events = GetRegisteredNetworkBingos.execute(&mut conn).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Summer Bingo");
+        assert_eq!(events[0].rule, DetectionRule::AllDifficulties);
+    }
+}
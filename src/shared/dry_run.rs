@@ -0,0 +1,30 @@
+//! Dry runs of destructive staff commands. These run their regular code path against a database
+//! handle which rolls back every write (see [`DbHandle::with_dry_run`]), skip changes made through
+//! Discord, and report what would have changed instead.
+
+use poise::serenity_prelude::GuildId;
+
+use crate::db::DbHandle;
+use crate::shared::BotData;
+
+pub fn db(data: &BotData, guild_id: Option<GuildId>, dry_run: bool) -> DbHandle {
+    data.db_for(guild_id).with_dry_run(dry_run)
+}
+
+/// Prefixes a result heading, e.g. `Dry Run: Unlinked Accounts`
+pub fn title(title: &str, dry_run: bool) -> String {
+    if dry_run {
+        format!("Dry Run: {title}")
+    } else {
+        title.to_string()
+    }
+}
+
+/// Appended to a command's result; empty for regular runs
+pub fn notice(dry_run: bool) -> &'static str {
+    if dry_run {
+        "\n-# **Dry run:** nothing was changed, this only shows what would have happened."
+    } else {
+        ""
+    }
+}
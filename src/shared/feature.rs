@@ -0,0 +1,118 @@
+use anyhow::{Result, anyhow, bail};
+use poise::serenity_prelude::{RoleId, UserId};
+
+use crate::db::DbHandle;
+use crate::error::UserError;
+use crate::shared::{Context, db::GetFeatureFlag};
+
+/// Subsystems that can be rolled out gradually, first to specific staff members or roles and then
+/// to everyone, without redeploying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Feature {
+    #[name = "Scheduler"]
+    Scheduler,
+    #[name = "Splash list history"]
+    SplashlistHistory,
+    #[name = "HoB browsing"]
+    HobBrowse,
+    #[name = "Live splash list"]
+    LiveSplashlist,
+    /// staged ahead of the nominations subsystem, which nothing checks yet
+    #[name = "Nominations"]
+    Nominations,
+}
+
+impl Feature {
+    pub const ALL: &[Feature; 5] = &[
+        Feature::Scheduler,
+        Feature::SplashlistHistory,
+        Feature::HobBrowse,
+        Feature::LiveSplashlist,
+        Feature::Nominations,
+    ];
+
+    /// Name stored in the `feature_flags` table
+    pub fn key(self) -> &'static str {
+        match self {
+            Feature::Scheduler => "scheduler",
+            Feature::SplashlistHistory => "splashlist_history",
+            Feature::HobBrowse => "hob_browse",
+            Feature::LiveSplashlist => "live_splashlist",
+            Feature::Nominations => "nominations",
+        }
+    }
+
+    /// Whether the feature is available to everyone while no flag is stored. Features that predate
+    /// the flags stay enabled, while new subsystems start out disabled until they are rolled out.
+    pub fn default_enabled(self) -> bool {
+        match self {
+            Feature::Scheduler | Feature::SplashlistHistory | Feature::HobBrowse => true,
+            Feature::LiveSplashlist | Feature::Nominations => false,
+        }
+    }
+}
+
+/// A user or role on a feature's allow list
+#[derive(Debug, Clone, Copy)]
+pub enum FeatureAccess {
+    User(UserId),
+    Role(RoleId),
+}
+
+/// Who a staged feature is available to. Features without a stored flag fall back to
+/// [`Feature::default_enabled`].
+#[derive(Debug, Clone)]
+pub struct FeatureFlag {
+    pub feature: Feature,
+    /// enabled for everyone, regardless of the allow lists
+    pub enabled: bool,
+    pub users: Vec<UserId>,
+    pub roles: Vec<RoleId>,
+}
+
+impl FeatureFlag {
+    pub fn unset(feature: Feature) -> Self {
+        Self {
+            feature,
+            enabled: feature.default_enabled(),
+            users: Vec::new(),
+            roles: Vec::new(),
+        }
+    }
+
+    pub fn allows(&self, user: UserId, roles: &[RoleId]) -> bool {
+        self.enabled || self.users.contains(&user) || roles.iter().any(|r| self.roles.contains(r))
+    }
+}
+
+/// Whether the feature is enabled for everyone, for background jobs not triggered by a user
+pub async fn enabled_globally(db: &DbHandle, feature: Feature) -> Result<bool> {
+    Ok(db.request(GetFeatureFlag { feature }).await??.enabled)
+}
+
+/// Whether the feature is available to the invoking user
+pub async fn enabled_for(ctx: Context<'_>, feature: Feature) -> Result<bool> {
+    let flag = ctx
+        .data()
+        .db_for(ctx.guild_id())
+        .request(GetFeatureFlag { feature })
+        .await??;
+
+    let roles = ctx
+        .author_member()
+        .await
+        .map(|member| member.roles.to_vec())
+        .unwrap_or_default();
+
+    Ok(flag.allows(ctx.author().id, &roles))
+}
+
+/// Fails with a user-facing error if the feature isn't available to the invoking user yet
+pub async fn require(ctx: Context<'_>, feature: Feature) -> Result<()> {
+    if !enabled_for(ctx, feature).await? {
+        bail!(UserError(anyhow!(
+            "This feature isn't available yet, check back later!"
+        )));
+    }
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use crate::shared::menu::MenuMessage;
 
+pub mod custom_id;
 pub mod modal;
+pub mod select;
 
 pub struct MenuChange<'a, T> {
     pub new_state: Option<T>,
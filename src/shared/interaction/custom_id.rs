@@ -0,0 +1,200 @@
+//! Custom IDs of components handled by the global event handler, formatted as
+//! `v{version}:{namespace}:{parts...}`.
+//!
+//! Components outlive the bot version that created them (e.g. role kiosks are never resent), so
+//! the version prefix allows changing the layout of an ID while still understanding older ones.
+//! IDs without a version prefix were created before versioning was introduced and are treated as
+//! version 0.
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+
+use crate::error::UserError;
+
+/// Version of the IDs created by this build. Bump it when changing the layout of existing IDs, and
+/// translate the previous layout in `migrate`.
+pub const CURRENT_VERSION: u8 = 1;
+
+const SEPARATOR: char = ':';
+
+/// Top-level handler an ID is routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Hob,
+    Onboarding,
+    Role,
+    SplashReminder,
+}
+
+impl Namespace {
+    pub fn key(self) -> &'static str {
+        match self {
+            Namespace::Hob => "hob",
+            Namespace::Onboarding => "onboarding",
+            Namespace::Role => "role",
+            Namespace::SplashReminder => "reminder",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "hob" => Some(Namespace::Hob),
+            "onboarding" => Some(Namespace::Onboarding),
+            "role" => Some(Namespace::Role),
+            "reminder" => Some(Namespace::SplashReminder),
+            _ => None,
+        }
+    }
+}
+
+/// Builds an ID of the current version. Further parts may be appended with `:`, e.g. by modals.
+pub fn encode(namespace: Namespace, parts: &[&dyn Display]) -> String {
+    let mut custom_id = format!("v{CURRENT_VERSION}{SEPARATOR}{}", namespace.key());
+    for part in parts {
+        custom_id.push(SEPARATOR);
+        custom_id.push_str(&part.to_string());
+    }
+    custom_id
+}
+
+/// A decoded ID, already translated to the current layout
+#[derive(Debug)]
+pub struct CustomId<'a> {
+    pub namespace: Namespace,
+    parts: Vec<&'a str>,
+}
+
+impl<'a> CustomId<'a> {
+    /// Returns `None` for IDs outside of the known namespaces, which belong to the collectors of
+    /// individual commands
+    pub fn decode(custom_id: &'a str) -> Result<Option<Self>> {
+        let mut segments = custom_id.split(SEPARATOR).peekable();
+
+        let version = match segments.peek().and_then(|first| parse_version(first)) {
+            Some(version) => {
+                segments.next();
+                version
+            }
+            None => 0,
+        };
+
+        let Some(namespace) = segments.next().and_then(Namespace::from_key) else {
+            return Ok(None);
+        };
+
+        if version > CURRENT_VERSION {
+            // only possible while processes running different versions share the bot
+            bail!(UserError(anyhow!(
+                "This component was created by a newer version of the bot, please try again in a few minutes"
+            )));
+        }
+
+        Ok(Some(Self {
+            namespace,
+            parts: migrate(version, segments.collect()),
+        }))
+    }
+
+    pub fn parts(&self) -> Parts<'a> {
+        Parts {
+            inner: self.parts.clone().into_iter(),
+        }
+    }
+}
+
+fn parse_version(segment: &str) -> Option<u8> {
+    segment.strip_prefix('v')?.parse().ok()
+}
+
+/// Translates the parts of an older ID to the current layout
+fn migrate<'a>(version: u8, parts: Vec<&'a str>) -> Vec<&'a str> {
+    match version {
+        // the layout was unchanged when introducing the version prefix
+        0 | CURRENT_VERSION => parts,
+        _ => unreachable!("newer versions are rejected while decoding"),
+    }
+}
+
+/// The remaining parts of an ID, with typed accessors for required parts
+#[derive(Debug, Clone)]
+pub struct Parts<'a> {
+    inner: std::vec::IntoIter<&'a str>,
+}
+
+impl<'a> Iterator for Parts<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> Parts<'a> {
+    /// Components with missing parts were most likely created by an older version with a different
+    /// layout, so the user is asked to reopen the menu instead of seeing an internal error
+    pub fn next_required(&mut self, name: &str) -> Result<&'a str> {
+        self.next().with_context(|| {
+            UserError(anyhow!(
+                "This component is outdated (missing {name}), please reopen the menu"
+            ))
+        })
+    }
+
+    pub fn next_parsed<T: FromStr>(&mut self, name: &str) -> Result<T> {
+        self.next_required(name)?.parse().ok().with_context(|| {
+            UserError(anyhow!(
+                "This component is outdated (invalid {name}), please reopen the menu"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_parts(custom_id: &str) -> Option<(Namespace, Vec<&str>)> {
+        CustomId::decode(custom_id)
+            .unwrap()
+            .map(|id| (id.namespace, id.parts().collect()))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let custom_id = format!("{}:edit", encode(Namespace::Hob, &[&42u64]));
+        assert_eq!(custom_id, "v1:hob:42:edit");
+        assert_eq!(
+            decode_parts(&custom_id),
+            Some((Namespace::Hob, vec!["42", "edit"]))
+        );
+    }
+
+    #[test]
+    fn legacy_ids_are_understood() {
+        assert_eq!(
+            decode_parts("role:request:begin"),
+            Some((Namespace::Role, vec!["request", "begin"]))
+        );
+    }
+
+    #[test]
+    fn foreign_ids_are_ignored() {
+        assert_eq!(decode_parts("hobbrowse:123:next"), None);
+        assert_eq!(decode_parts("v1:splashes:history:1"), None);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        assert!(CustomId::decode("v200:hob:1:edit").is_err());
+    }
+
+    #[test]
+    fn typed_parts() {
+        let id = CustomId::decode("v1:hob:12:x").unwrap().unwrap();
+        let mut parts = id.parts();
+        assert_eq!(parts.next_parsed::<u64>("menu ID").unwrap(), 12);
+        assert!(parts.next_parsed::<u64>("page").is_err());
+        assert!(parts.next_required("action").is_err());
+    }
+}
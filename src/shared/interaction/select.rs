@@ -0,0 +1,76 @@
+//! Parsing of select menu values submitted in modals. Serenity doesn't parse these anymore, so
+//! User, Role, Channel and Mentionable selects only provide the raw snowflakes as strings.
+
+use std::num::NonZeroU64;
+
+use anyhow::{Result, anyhow};
+
+use crate::error::UserError;
+
+/// Parses the snowflakes of a select menu, failing with a user-facing error naming the field on the
+/// first malformed value
+pub fn parse_select_ids<T: From<u64>>(
+    field: &str,
+    kind: &str,
+    values: &[String],
+) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .trim()
+                // zero isn't a valid snowflake, and would panic when constructing IDs
+                .parse::<NonZeroU64>()
+                .map(|id| T::from(id.get()))
+                .map_err(|_| {
+                    UserError(anyhow!(
+                        "Invalid {kind} ID in field '{field}': `{value}` is not a valid ID"
+                    ))
+                    .into()
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::{RoleId, UserId};
+
+    use super::*;
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_valid_ids() {
+        let users: Vec<UserId> =
+            parse_select_ids("user", "User", &values(&["821735954128830504", " 42 "])).unwrap();
+        assert_eq!(
+            users,
+            vec![UserId::new(821735954128830504), UserId::new(42)]
+        );
+
+        let roles: Vec<RoleId> = parse_select_ids("role", "Role", &values(&[])).unwrap();
+        assert!(roles.is_empty());
+    }
+
+    #[test]
+    fn parse_malformed_ids() {
+        for malformed in ["", "abc", "-5", "0", "1.5", "18446744073709551616"] {
+            let err = parse_select_ids::<RoleId>("role_id", "Role", &values(&["1", malformed]))
+                .unwrap_err();
+
+            assert!(
+                err.is::<UserError>(),
+                "'{malformed}' should be a user error"
+            );
+            let message = format!("{err:#}");
+            assert!(
+                message.contains("role_id"),
+                "'{message}' should name the field"
+            );
+            assert!(message.contains("Role"), "'{message}' should name the kind");
+        }
+    }
+}
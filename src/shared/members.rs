@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use poise::serenity_prelude::{
+    ChunkGuildFilter, Context as SerenityContext, Event, GuildId, RoleId, UserId, collector,
+    futures::StreamExt as _,
+};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::error::UserError;
+use crate::shared::menu::generate_id;
+
+/// Upper bound for receiving all chunks, including restarts after reconnects
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(90);
+/// Chunk requests sent at most, as repeated reconnects indicate a larger gateway issue
+const MAX_CHUNK_REQUESTS: u32 = 3;
+
+enum ChunkEvent {
+    Chunk {
+        request: u32,
+        is_final: bool,
+        members: Vec<UserId>,
+    },
+    Reconnected,
+}
+
+/// Requests the guild's members to be chunked over the shard connection and returns those who
+/// have the role, as there is no endpoint for listing a role's members
+pub async fn role_members(
+    ctx: &SerenityContext,
+    guild: GuildId,
+    role: RoleId,
+) -> Result<Vec<UserId>> {
+    // each chunk request gets its own nonce, so that chunks of an abandoned request are ignored
+    let nonce_prefix = generate_id().to_string();
+
+    let prefix = nonce_prefix.clone();
+    let mut stream = collector::collect(ctx, move |event| match event {
+        Event::GuildMembersChunk(event) => {
+            let request = event
+                .nonce
+                .as_ref()?
+                .as_str()
+                .strip_prefix(prefix.as_str())?
+                .strip_prefix(':')?
+                .parse()
+                .ok()?;
+            let is_final = event.chunk_index == event.chunk_count - 1;
+
+            let members: Vec<_> = event
+                .members
+                .iter()
+                // filter inside collector to avoid cloning values
+                .filter_map(|m| m.roles.contains(&role).then_some(m.user.id))
+                .collect();
+
+            Some(ChunkEvent::Chunk {
+                request,
+                is_final,
+                members,
+            })
+        }
+        // NOTE: chunks that were pending when the shard disconnected are never sent, and shard
+        // stage updates aren't gateway events, so reconnects are detected by how they finish
+        Event::Resumed(_) | Event::Ready(_) => Some(ChunkEvent::Reconnected),
+        _ => None,
+    });
+
+    let deadline = Instant::now() + CHUNK_TIMEOUT;
+    let mut request = 0;
+    request_chunks(ctx, guild, &nonce_prefix, request);
+
+    let mut role_members = Vec::new();
+    loop {
+        let event = match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(event)) => event,
+            Ok(None) => bail!("Member chunk collector ended unexpectedly"),
+            Err(_) => bail!(UserError(anyhow!(
+                "Timed out while receiving the server's members from Discord, please try again later"
+            ))),
+        };
+
+        match event {
+            ChunkEvent::Chunk {
+                request: chunk_request,
+                is_final,
+                members,
+            } if chunk_request == request => {
+                role_members.extend(members);
+                if is_final {
+                    return Ok(role_members);
+                }
+            }
+            // belongs to an abandoned request
+            ChunkEvent::Chunk { .. } => {}
+            ChunkEvent::Reconnected => {
+                request += 1;
+                if request >= MAX_CHUNK_REQUESTS {
+                    bail!(UserError(anyhow!(
+                        "Lost the connection to Discord too often while receiving the server's \
+                        members, please try again later"
+                    )));
+                }
+
+                warn!("Shard reconnected while chunking members of guild {guild}, restarting");
+                role_members.clear();
+                request_chunks(ctx, guild, &nonce_prefix, request);
+            }
+        }
+    }
+}
+
+fn request_chunks(ctx: &SerenityContext, guild: GuildId, nonce_prefix: &str, request: u32) {
+    ctx.chunk_guild(
+        guild,
+        None,
+        false,
+        ChunkGuildFilter::None,
+        Some(format!("{nonce_prefix}:{request}")),
+    );
+}
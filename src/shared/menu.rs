@@ -1,3 +1,8 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use poise::{
     CreateReply,
     serenity_prelude::{
@@ -6,8 +11,13 @@ use poise::{
     },
 };
 
+use crate::shared::shard;
+
+pub mod confirm;
 pub mod navigation;
+pub mod persist;
 pub mod timeout;
+pub mod transfer;
 
 pub const ACCENT_COLOR: Color = Color::BLUE;
 
@@ -43,7 +53,40 @@ impl<'a> MenuMessage<'a> {
     }
 }
 
-/// Generate an ID that can be stored as i64 in SQLite
+/// Custom epoch for generated IDs (2025-01-01T00:00:00Z), in milliseconds
+const ID_EPOCH_MILLIS: u64 = 1_735_689_600_000;
+const ID_SEQUENCE_BITS: u32 = 12;
+const ID_WORKER_BITS: u32 = 10;
+
+/// Last used millisecond and sequence number within that millisecond
+static ID_STATE: Mutex<(u64, u64)> = Mutex::new((0, 0));
+
+/// Generate a unique, monotonically increasing snowflake-style ID that can be stored as i64 in
+/// SQLite (41 bits of milliseconds since `ID_EPOCH_MILLIS`, 10 bits worker, 12 bits sequence)
 pub fn generate_id() -> u64 {
-    rand::random_range(0..=i64::MAX as u64)
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+        .saturating_sub(ID_EPOCH_MILLIS);
+
+    let mut state = ID_STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    let (last_millis, last_sequence) = *state;
+
+    // never go back in time, even if the system clock does
+    let (millis, sequence) = if now > last_millis {
+        (now, 0)
+    } else if last_sequence + 1 < 1 << ID_SEQUENCE_BITS {
+        (last_millis, last_sequence + 1)
+    } else {
+        // sequence exhausted: borrow the next millisecond instead of waiting for it
+        (last_millis + 1, 0)
+    };
+    *state = (millis, sequence);
+
+    // distinguishes IDs generated by separate processes sharing a database, whose shard ranges
+    // never overlap
+    let worker = u64::from(shard::current().first) & ((1 << ID_WORKER_BITS) - 1);
+
+    (millis << (ID_WORKER_BITS + ID_SEQUENCE_BITS)) | (worker << ID_SEQUENCE_BITS) | sequence
 }
@@ -0,0 +1,90 @@
+//! Previews of changes that are only applied once confirmed, such as imports and bulk removals
+
+use std::time::Duration;
+
+use anyhow::Result;
+use poise::{
+    CreateReply, ReplyHandle,
+    serenity_prelude::{
+        Colour, CreateComponent, CreateContainer, CreateContainerComponent,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateTextDisplay, Event,
+        Interaction, MessageFlags, collector, colours::css::WARNING, futures::StreamExt as _,
+    },
+};
+
+use crate::config::MENU_TIMEOUT_SECS;
+use crate::shared::Context;
+
+/// Message replacing a preview once it was confirmed, cancelled or expired
+pub fn outcome(text: &str, accent: Colour) -> Vec<CreateComponent<'static>> {
+    vec![CreateComponent::Container(
+        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+            CreateTextDisplay::new(text.to_string()),
+        )])
+        .accent_color(accent),
+    )]
+}
+
+/// Waits for the `{id_prefix}:confirm` or `{id_prefix}:cancel` button of an ephemeral preview.
+/// Confirming replaces the preview with the outcome returned by `on_confirm`, while cancelling or
+/// letting the menu time out replaces it with "{action} Cancelled/Expired" and `unchanged`.
+pub async fn await_confirmation<F>(
+    ctx: Context<'_>,
+    handle: ReplyHandle<'_>,
+    id_prefix: &str,
+    action: &str,
+    unchanged: &str,
+    on_confirm: impl FnOnce() -> F,
+) -> Result<()>
+where
+    F: Future<Output = Result<Vec<CreateComponent<'static>>>>,
+{
+    // only the invoking user can see the ephemeral response, so no need to check who clicked
+    let prefix = id_prefix.to_string();
+    let mut stream = collector::collect(ctx.serenity_context(), move |event| match event {
+        Event::InteractionCreate(event) => match &event.interaction {
+            Interaction::Component(interaction)
+                if interaction.data.custom_id.starts_with(&prefix) =>
+            {
+                Some(interaction.clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    });
+
+    let timeout = Duration::from_secs(MENU_TIMEOUT_SECS);
+    let Ok(Some(interaction)) = tokio::time::timeout(timeout, stream.next()).await else {
+        handle
+            .edit(
+                ctx,
+                CreateReply::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .components(outcome(
+                        &format!("## {action} Expired\n{unchanged}"),
+                        WARNING,
+                    )),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let result = if interaction.data.custom_id.ends_with(":confirm") {
+        on_confirm().await?
+    } else {
+        outcome(&format!("## {action} Cancelled\n{unchanged}"), WARNING)
+    };
+
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .components(result),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
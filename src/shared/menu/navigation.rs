@@ -51,6 +51,15 @@ pub fn page_navigation_jump(
     CreateContainerComponent::ActionRow(CreateActionRow::Buttons(buttons.into()))
 }
 
+pub fn page_navigation(
+    id_prefix: &str,
+    page_chunk: &PaginatedChunk,
+) -> CreateContainerComponent<'static> {
+    let buttons = navigation_buttons_basic(id_prefix, page_chunk);
+
+    CreateContainerComponent::ActionRow(CreateActionRow::Buttons(buttons.into()))
+}
+
 pub fn page_navigation_subentry(
     id_prefix: &str,
     page_chunk: &PaginatedChunk,
@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use chrono::Utc;
+use poise::serenity_prelude::{
+    CacheHttp as _, Context as SerenityContext, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, GenericChannelId, GuildId, Http,
+    MessageFlags, MessageId, UserId, colours::css::WARNING,
+};
+use serde_json::Value;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, info, warn};
+
+use crate::config::MENU_TIMEOUT_SECS;
+use crate::error;
+use crate::hob::menu::{HobEditSession, HobEditState};
+use crate::role::menu::{RoleConfigSession, RoleConfigState};
+use crate::shared::{
+    BotData,
+    db::{PersistMenuSessions, TakePersistedMenuSessions},
+    menu::{
+        navigation::GenerateMenu as _,
+        timeout::{self, Expirable},
+    },
+    shard,
+};
+
+/// Appended to menus while the bot restarts
+const RESTART_NOTICE: &str = "-# The bot is restarting, this menu will be available again shortly.";
+/// Menus persisted longer ago than this aren't restored, as their owners have likely moved on
+const RESTORE_WINDOW_SECS: i64 = 30 * 60;
+
+/// `Ready` is dispatched again after reconnects, but sessions should only be restored on startup
+static RESTORED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistedMenuKind {
+    Hob,
+    Role,
+}
+
+impl PersistedMenuKind {
+    pub fn key(self) -> &'static str {
+        match self {
+            PersistedMenuKind::Hob => "hob",
+            PersistedMenuKind::Role => "role",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "hob" => Some(PersistedMenuKind::Hob),
+            "role" => Some(PersistedMenuKind::Role),
+            _ => None,
+        }
+    }
+
+    /// Command that opens a new menu of this kind
+    pub fn command(self) -> &'static str {
+        match self {
+            PersistedMenuKind::Hob => "/hob manage",
+            PersistedMenuKind::Role => "/rolerequest config",
+        }
+    }
+}
+
+/// A menu session that was open when the bot shut down
+#[derive(Debug, Clone)]
+pub struct PersistedMenu {
+    pub menu_id: u64,
+    pub kind: PersistedMenuKind,
+    pub owner: (UserId, String),
+    pub guild_id: Option<GuildId>,
+    pub channel_id: GenericChannelId,
+    pub message_id: MessageId,
+    pub state: Value,
+    /// unix timestamp
+    pub persisted_at: i64,
+}
+
+/// Stores all open menu sessions and disables their messages until they are restored, so that
+/// clicks during the restart don't fail with "This menu has expired"
+pub async fn persist_sessions(http: &Arc<Http>, data: &BotData) {
+    let now = Utc::now().timestamp();
+    let mut persisted = Vec::new();
+
+    // NOTE: the sessions are removed, so their timeouts won't invalidate them in the meantime
+    let hob_sessions: Vec<_> = data.hob_sessions.lock().await.drain().collect();
+    for (_, session_mutex) in hob_sessions {
+        let session = session_mutex.lock().await;
+        persisted.push(PersistedMenu {
+            menu_id: session.menu_id,
+            kind: PersistedMenuKind::Hob,
+            owner: session.owner.clone(),
+            guild_id: session.guild_id,
+            channel_id: session.channel_id,
+            message_id: session.message_id,
+            state: session.state.to_persisted(),
+            persisted_at: now,
+        });
+        disable(http, &*session).await;
+    }
+
+    let role_sessions: Vec<_> = data.role_sessions.lock().await.drain().collect();
+    for (_, session_mutex) in role_sessions {
+        let session = session_mutex.lock().await;
+        persisted.push(PersistedMenu {
+            menu_id: session.menu_id,
+            kind: PersistedMenuKind::Role,
+            owner: session.owner.clone(),
+            guild_id: session.guild_id,
+            channel_id: session.channel_id,
+            message_id: session.message_id,
+            state: session.state.to_persisted(),
+            persisted_at: now,
+        });
+        disable(http, &*session).await;
+    }
+
+    if persisted.is_empty() {
+        return;
+    }
+
+    let count = persisted.len();
+    match data
+        .db_handle
+        .request(PersistMenuSessions { menus: persisted })
+        .await
+    {
+        Ok(Ok(())) => info!("Persisted {count} open menu session(s)"),
+        Ok(Err(err)) => error!("Failed to persist menu sessions: {err:#}"),
+        Err(err) => error!("Failed to persist menu sessions: {err:#}"),
+    }
+}
+
+async fn disable(http: &Arc<Http>, session: &impl Expirable) {
+    if let Err(err) = session.invalidate(Arc::clone(http), RESTART_NOTICE).await {
+        warn!("Unable to disable menu message before shutdown: {err:#}");
+    }
+}
+
+/// Re-activates the menu sessions persisted during the last shutdown
+pub async fn restore_sessions(ctx: &SerenityContext) -> Result<()> {
+    if RESTORED.swap(true, Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let data = ctx.data::<BotData>();
+    let menus = data
+        .db_handle
+        .request(TakePersistedMenuSessions {
+            shards: shard::current(),
+        })
+        .await??;
+    let cutoff = Utc::now().timestamp() - RESTORE_WINDOW_SECS;
+
+    for menu in menus.into_iter().filter(|m| m.persisted_at >= cutoff) {
+        let (menu_id, kind, owner) = (menu.menu_id, menu.kind, menu.owner.0);
+        match restore(ctx, &data, menu).await {
+            Ok(()) => (),
+            // the session was already taken from the database, so it is simply dropped
+            Err(err) if error::is_unknown_message(&err) => {
+                info!("Dropped menu session {menu_id}, as its message was deleted");
+                if let Err(err) = notify_deleted(ctx, owner, kind).await {
+                    warn!("Unable to notify owner of deleted menu session {menu_id}: {err:#}");
+                }
+            }
+            Err(err) => warn!("Failed to restore menu session {menu_id}: {err:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn restore(ctx: &SerenityContext, data: &BotData, menu: PersistedMenu) -> Result<()> {
+    let db = data.db_for(menu.guild_id);
+    let timeout_reset = Arc::new(Notify::new());
+
+    match menu.kind {
+        PersistedMenuKind::Hob => {
+            let mut state = HobEditState::from_persisted(&menu.state)
+                .context("Invalid persisted HoB menu state")?;
+            let message = state.generate(db, menu.menu_id).await?;
+            ctx.http()
+                .edit_message(
+                    menu.channel_id,
+                    menu.message_id,
+                    &message.into_edit(),
+                    vec![],
+                )
+                .await?;
+
+            let session = HobEditSession {
+                menu_id: menu.menu_id,
+                state,
+                owner: menu.owner,
+                guild_id: menu.guild_id,
+                channel_id: menu.channel_id,
+                message_id: menu.message_id,
+                timeout_reset: Arc::clone(&timeout_reset),
+            };
+            register(
+                ctx,
+                &data.hob_sessions,
+                menu.menu_id,
+                session,
+                timeout_reset,
+            )
+            .await;
+        }
+        PersistedMenuKind::Role => {
+            let mut state = RoleConfigState::from_persisted(&menu.state)
+                .context("Invalid persisted role config menu state")?;
+            let message = state.generate(db, menu.menu_id).await?;
+            ctx.http()
+                .edit_message(
+                    menu.channel_id,
+                    menu.message_id,
+                    &message.into_edit(),
+                    vec![],
+                )
+                .await?;
+
+            let session = RoleConfigSession {
+                menu_id: menu.menu_id,
+                state,
+                owner: menu.owner,
+                guild_id: menu.guild_id,
+                channel_id: menu.channel_id,
+                message_id: menu.message_id,
+                timeout_reset: Arc::clone(&timeout_reset),
+            };
+            register(
+                ctx,
+                &data.role_sessions,
+                menu.menu_id,
+                session,
+                timeout_reset,
+            )
+            .await;
+        }
+    }
+
+    info!("Restored menu session {}", menu.menu_id);
+    Ok(())
+}
+
+/// Lets the owner of a menu whose message was deleted during the restart know how to reopen it
+async fn notify_deleted(
+    ctx: &SerenityContext,
+    owner: UserId,
+    kind: PersistedMenuKind,
+) -> Result<()> {
+    let text = CreateTextDisplay::new(format!(
+        "## Menu Not Restored
+Your menu couldn't be restored after the bot restarted, as its message was deleted in the meantime. Use `{}` to open a new one.",
+        kind.command()
+    ));
+
+    owner
+        .direct_message(
+            ctx.http(),
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                        .accent_color(WARNING),
+                )]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn register<T: Expirable>(
+    ctx: &SerenityContext,
+    sessions: &Arc<Mutex<HashMap<u64, Arc<Mutex<T>>>>>,
+    menu_id: u64,
+    session: T,
+    timeout_reset: Arc<Notify>,
+) {
+    timeout::spawn_timeout(
+        Arc::clone(&ctx.http),
+        Arc::clone(sessions),
+        menu_id,
+        Duration::from_secs(MENU_TIMEOUT_SECS),
+        timeout_reset,
+    )
+    .await;
+
+    sessions
+        .lock()
+        .await
+        .insert(menu_id, Arc::new(Mutex::new(session)));
+}
@@ -1,9 +1,10 @@
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use anyhow::Result;
+use chrono::Utc;
 use poise::serenity_prelude::{
-    ActionRow, ActionRowComponent, Button, ButtonKind, Component, ComponentType, Container,
-    ContainerComponent, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
+    ActionRow, ActionRowComponent, Button, ButtonKind, ButtonStyle, Component, ComponentType,
+    Container, ContainerComponent, CreateActionRow, CreateButton, CreateComponent, CreateContainer,
     CreateContainerComponent, CreateFile, CreateInputText, CreateMediaGallery,
     CreateMediaGalleryItem, CreateSection, CreateSectionAccessory, CreateSectionComponent,
     CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, CreateSeparator,
@@ -16,13 +17,64 @@ use tokio::{
     select,
     sync::{Mutex, Notify},
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+use crate::error;
+use crate::shared::{menu::MenuMessage, task::spawn_background};
+
+/// Appended to menus that ran out of time
+pub const EXPIRED_NOTICE: &str = "-# This menu has expired.";
+/// Action of the button shown with the expiry warning, which only needs to reset the timeout
+pub const EXTEND_ACTION: &str = "extend";
+/// How long before expiring a menu its owner is warned
+const EXPIRY_WARNING: Duration = Duration::from_secs(60);
 
 #[async_trait]
 pub trait Expirable: Send + Sync + 'static {
     fn message_ids(&self) -> (&GenericChannelId, &MessageId);
 
-    async fn invalidate<'a>(&'a self, http: Arc<Http>) -> Result<&'a str>;
+    /// Prefix of the menu's custom IDs, used for the extend button
+    fn id_prefix(&self) -> String;
+
+    /// Disables the menu's components and appends `notice` to it
+    async fn invalidate<'a>(&'a self, http: Arc<Http>, notice: &str) -> Result<&'a str>;
+
+    /// Appends a notice with a button to extend the menu, which is removed again once the menu is
+    /// re-rendered. The notice takes up 3 components, which menus have to leave room for.
+    async fn warn_expiry(&self, http: Arc<Http>, expires_at: i64) -> Result<()> {
+        let (&channel_id, &message_id) = self.message_ids();
+
+        let components = http.get_message(channel_id, message_id).await?.components;
+        let mut component_builders: Vec<_> =
+            components.into_iter().map(Component::into_create).collect();
+
+        let warning = CreateContainerComponent::Section(CreateSection::new(
+            vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+                format!("-# This menu expires <t:{expires_at}:R>, click Extend to keep working."),
+            ))],
+            CreateSectionAccessory::Button(
+                CreateButton::new(format!("{}:{EXTEND_ACTION}", self.id_prefix()))
+                    .label("Extend")
+                    .style(ButtonStyle::Primary),
+            ),
+        ));
+        if let Some(container) = component_builders.iter_mut().rev().find_map(|c| {
+            if let CreateComponent::Container(container) = c {
+                Some(container)
+            } else {
+                None
+            }
+        }) {
+            *container = std::mem::replace(container, CreateContainer::new(Vec::new()))
+                .add_component(warning);
+        }
+
+        let menu = MenuMessage::new(component_builders);
+        http.edit_message(channel_id, message_id, &menu.into_edit(), Vec::new())
+            .await?;
+
+        Ok(())
+    }
 
     fn disable_components(components: &mut FixedArray<Component>) {
         for c in components {
@@ -71,10 +123,36 @@ pub async fn spawn_timeout<T>(
 ) where
     T: Expirable,
 {
-    tokio::spawn(async move {
+    let task_http = Arc::clone(&http);
+    spawn_background("menu timeout", task_http, async move {
         loop {
+            // menus with short timeouts expire without a warning, as it would be shown right away
+            if timeout <= EXPIRY_WARNING {
+                select! {
+                    _ = tokio::time::sleep(timeout) => break,
+                    _ = reset_rx.notified() => continue,
+                };
+            }
+
             select! {
-                _ = tokio::time::sleep(timeout) => break,
+                _ = tokio::time::sleep(timeout - EXPIRY_WARNING) => (),
+                _ = reset_rx.notified() => continue,
+            };
+
+            // NOTE: lock dropped at the end of the expression
+            let menu_mutex = sessions.lock().await.get(&session_id).cloned();
+            if let Some(menu_mutex) = menu_mutex {
+                let expires_at = Utc::now().timestamp() + EXPIRY_WARNING.as_secs() as i64;
+                let menu = menu_mutex.lock().await;
+                match menu.warn_expiry(Arc::clone(&http), expires_at).await {
+                    Ok(()) => (),
+                    Err(err) if error::is_unknown_message(&err) => (),
+                    Err(err) => warn!("Unable to warn about menu expiry: {err:#}"),
+                }
+            }
+
+            select! {
+                _ = tokio::time::sleep(EXPIRY_WARNING) => break,
                 _ = reset_rx.notified() => continue,
             };
         }
@@ -82,8 +160,11 @@ pub async fn spawn_timeout<T>(
         if let Some(menu_mutex) = sessions.lock().await.remove(&session_id) {
             let menu = menu_mutex.lock().await;
 
-            match menu.invalidate(http).await {
+            match menu.invalidate(http, EXPIRED_NOTICE).await {
                 Ok(name) => info!("Successfully invalidated {}'s menu message", name),
+                Err(err) if error::is_unknown_message(&err) => {
+                    info!("Menu message of session {session_id} was already deleted")
+                }
                 Err(err) => {
                     error!("Unable to invalidate menu message: {err:#}")
                 }
@@ -0,0 +1,155 @@
+//! Handing a menu session over to another staff member, who can continue where its owner left off
+//! instead of opening a new menu
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use poise::serenity_prelude::{
+    ButtonStyle, CacheHttp as _, ComponentInteraction, ComponentInteractionDataKind,
+    Context as SerenityContext, CreateActionRow, CreateAllowedMentions, CreateButton,
+    CreateComponent, CreateContainer, CreateContainerComponent, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateSection,
+    CreateSectionAccessory, CreateSectionComponent, CreateSelectMenu, CreateSelectMenuKind,
+    CreateTextDisplay, Mentionable as _, MessageFlags, UserId, colours::css::POSITIVE,
+};
+use tracing::info;
+
+use crate::error::UserError;
+use crate::shared::{interaction::MessageEdit, menu::ACCENT_COLOR};
+
+const PROMPT_ACTION: &str = "transfer";
+const SELECT_ACTION: &str = "transfer_to";
+
+/// Section with the button starting a transfer, placed at the bottom of a menu
+pub fn section(id_prefix: &str) -> CreateContainerComponent<'static> {
+    CreateContainerComponent::Section(CreateSection::new(
+        vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+            "-# Hand this menu over to another staff member.",
+        ))],
+        CreateSectionAccessory::Button(
+            CreateButton::new(format!("{id_prefix}:{PROMPT_ACTION}"))
+                .label("Transfer")
+                .style(ButtonStyle::Secondary),
+        ),
+    ))
+}
+
+/// Handles the transfer button and the subsequent user select, returning `None` for any other
+/// action. Must only be called for interactions by the current owner.
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    interaction: &ComponentInteraction,
+    action: &str,
+    id_prefix: &str,
+    owner: &mut (UserId, String),
+) -> Result<Option<MessageEdit<'static>>> {
+    match action {
+        PROMPT_ACTION => {
+            let container = CreateComponent::Container(
+                CreateContainer::new(vec![
+                    CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+                        "## Transfer Menu
+Select the staff member who should take over this menu. You won't be able to interact with it \
+                         afterwards.",
+                    )),
+                    CreateContainerComponent::ActionRow(CreateActionRow::SelectMenu(
+                        CreateSelectMenu::new(
+                            format!("{id_prefix}:{SELECT_ACTION}"),
+                            CreateSelectMenuKind::User {
+                                default_users: None,
+                            },
+                        )
+                        .placeholder("Select the new owner.")
+                        .min_values(1)
+                        .max_values(1),
+                    )),
+                ])
+                .accent_color(ACCENT_COLOR),
+            );
+
+            interaction
+                .create_response(
+                    ctx.http(),
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::default()
+                            .flags(MessageFlags::IS_COMPONENTS_V2)
+                            .components(vec![container])
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+        }
+        SELECT_ACTION => {
+            let ComponentInteractionDataKind::UserSelect { values } = &interaction.data.kind else {
+                bail!("Invalid interaction: Expected User SelectMenu");
+            };
+            let new_owner = *values.first().context("No user selected")?;
+            if new_owner == owner.0 {
+                bail!(UserError(anyhow!("You already own this menu")));
+            }
+
+            let guild_id = interaction.guild_id.context(UserError(anyhow!(
+                "Menus can only be transferred within a server"
+            )))?;
+            let member = guild_id
+                .member(ctx.http(), new_owner)
+                .await
+                .context(UserError(anyhow!("The selected user isn't in this server")))?;
+            if member.user.bot() {
+                bail!(UserError(anyhow!("Menus can't be transferred to bots")));
+            }
+            // the same permission that staff commands require by default
+            let permissions = guild_id
+                .to_partial_guild(ctx.http())
+                .await?
+                .member_permissions(&member);
+            if !permissions.manage_guild() {
+                bail!(UserError(anyhow!(
+                    "Menus can only be transferred to staff members"
+                )));
+            }
+
+            let previous = std::mem::replace(owner, (new_owner, member.user.name.to_string()));
+            info!("{} transferred their menu to {}", previous.1, owner.1);
+
+            let container = CreateComponent::Container(
+                CreateContainer::new(vec![CreateContainerComponent::TextDisplay(
+                    CreateTextDisplay::new(format!(
+                        "## Menu Transferred
+{} handed their menu over to {}, who can continue where they left off.",
+                        previous.0.mention(),
+                        new_owner.mention()
+                    )),
+                )])
+                .accent_color(POSITIVE),
+            );
+
+            interaction
+                .create_response(
+                    ctx.http(),
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::default()
+                            .flags(MessageFlags::IS_COMPONENTS_V2)
+                            .components(vec![CreateComponent::TextDisplay(
+                                CreateTextDisplay::new(format!(
+                                    "Transferred the menu to {}.",
+                                    new_owner.mention()
+                                )),
+                            )]),
+                    ),
+                )
+                .await?;
+            // public, so that the new owner is notified
+            interaction
+                .create_followup(
+                    ctx.http(),
+                    CreateInteractionResponseFollowup::new()
+                        .flags(MessageFlags::IS_COMPONENTS_V2)
+                        .allowed_mentions(CreateAllowedMentions::new().users(vec![new_owner]))
+                        .components(vec![container]),
+                )
+                .await?;
+        }
+        _ => return Ok(None),
+    }
+
+    Ok(Some(MessageEdit::NoEdit))
+}
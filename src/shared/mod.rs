@@ -1,21 +1,43 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::Error;
+use anyhow::{Error, Result};
+use poise::serenity_prelude::GuildId;
 use tokio::sync::Mutex;
 
 use crate::db::DbHandle;
 use crate::hob::menu::HobEditSession;
 use crate::hypixel_api::ApiHandle;
-use crate::role::menu::RoleConfigSession;
+use crate::role::{mapping_cache::RoleMappingCache, menu::RoleConfigSession};
+use crate::shared::{db::GetGuildConfig, types::GuildConfig};
 use crate::splash_reminder::SplashReminderHandle;
 
+pub mod calendar;
 pub mod db;
+pub mod dry_run;
+pub mod feature;
 pub mod interaction;
+pub mod members;
 pub mod menu;
+pub mod shard;
+pub mod task;
+pub mod time;
 pub mod types;
 
+/// How long guild configs are kept in memory, bounding how long changes made by other processes
+/// take to apply
+const GUILD_CONFIG_TTL: Duration = Duration::from_secs(60);
+
 pub struct BotData {
+    /// Production database; use [`Self::db_for`] for anything originating from a guild
     pub db_handle: DbHandle,
+    pub sandbox: Option<Sandbox>,
+    /// Guild the splash reminder runs for, as its handle and configuration are shared by all
+    /// guilds. Every guild is treated as the home guild if unset, which only suits a single guild.
+    pub home_guild: Option<GuildId>,
     pub api_handle: ApiHandle,
     // NOTE: nested Arc-Mutexes so that commands and interactions can clone the inner Arc and drop
     // the outer lock, allowing for concurrent mutable access to separate entries
@@ -23,7 +45,56 @@ pub struct BotData {
     // struct in another reference-counting pointer
     pub hob_sessions: Arc<Mutex<HashMap<u64, Arc<Mutex<HobEditSession>>>>>,
     pub role_sessions: Arc<Mutex<HashMap<u64, Arc<Mutex<RoleConfigSession>>>>>,
+    pub role_mappings: RoleMappingCache,
+    /// Needed for every message and reaction, see [`Self::guild_config`]
+    pub guild_configs: Mutex<HashMap<Option<GuildId>, (Instant, GuildConfig)>>,
     pub splash_reminder: Mutex<SplashReminderHandle>,
 }
 
+/// Guild used for testing, whose data is kept in a separate database
+pub struct Sandbox {
+    pub guild_id: GuildId,
+    pub db_handle: DbHandle,
+}
+
+impl BotData {
+    /// Database that requests originating from the specified guild should be sent to
+    pub fn db_for(&self, guild_id: Option<GuildId>) -> &DbHandle {
+        match &self.sandbox {
+            Some(sandbox) if guild_id == Some(sandbox.guild_id) => &sandbox.db_handle,
+            _ => &self.db_handle,
+        }
+    }
+
+    /// Whether the splash reminder runs for the guild, see [`Self::home_guild`]
+    pub fn is_home_guild(&self, guild_id: Option<GuildId>) -> bool {
+        self.home_guild.is_none_or(|home| guild_id == Some(home))
+    }
+
+    /// Channels and roles configured for the guild, falling back to the defaults in
+    /// [`crate::config`] for anything that isn't configured
+    pub async fn guild_config(&self, guild_id: Option<GuildId>) -> Result<GuildConfig> {
+        if let Some((loaded_at, config)) = self.guild_configs.lock().await.get(&guild_id)
+            && loaded_at.elapsed() < GUILD_CONFIG_TTL
+        {
+            return Ok(*config);
+        }
+
+        let config = self
+            .db_for(guild_id)
+            .request(GetGuildConfig { guild_id })
+            .await??;
+        self.guild_configs
+            .lock()
+            .await
+            .insert(guild_id, (Instant::now(), config));
+        Ok(config)
+    }
+
+    /// Called after changing the guild's config, so that this process applies it immediately
+    pub async fn invalidate_guild_config(&self, guild_id: GuildId) {
+        self.guild_configs.lock().await.remove(&Some(guild_id));
+    }
+}
+
 pub type Context<'a> = poise::Context<'a, BotData, Error>;
@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    env,
+    ops::Range,
+    sync::{LazyLock, Mutex, OnceLock, PoisonError},
+};
+
+use anyhow::{Context as _, Result, bail};
+use poise::serenity_prelude::{Context as SerenityContext, GuildId};
+
+static CURRENT: OnceLock<ShardConfig> = OnceLock::new();
+/// Context of each shard run by this process, as member chunks can only be requested over the
+/// connection of the guild's shard
+static CONTEXTS: LazyLock<Mutex<HashMap<u16, SerenityContext>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The gateway shards run by this process. Large deployments can split the shards across multiple
+/// processes sharing the same database, by setting `SHARD_COUNT` and a distinct `SHARD_IDS` range
+/// (e.g. `0-3` or `4`) for each process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardConfig {
+    pub total: u16,
+    pub first: u16,
+    /// inclusive
+    pub last: u16,
+}
+
+impl ShardConfig {
+    /// A single process running a single shard, used if sharding isn't configured
+    pub const SINGLE: Self = Self {
+        total: 1,
+        first: 0,
+        last: 0,
+    };
+
+    /// `None` if sharding isn't configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(total) = env::var("SHARD_COUNT") else {
+            return Ok(None);
+        };
+        Self::parse(&total, env::var("SHARD_IDS").ok().as_deref()).map(Some)
+    }
+
+    /// Runs all shards if `ids` is omitted
+    fn parse(total: &str, ids: Option<&str>) -> Result<Self> {
+        let total: u16 = total.trim().parse().context("Invalid shard count")?;
+        if total == 0 {
+            bail!("The shard count must be at least 1");
+        }
+
+        let (first, last) = match ids.map(str::trim) {
+            None | Some("") => (0, total - 1),
+            Some(ids) => match ids.split_once('-') {
+                Some((first, last)) => (
+                    first.trim().parse().context("Invalid first shard ID")?,
+                    last.trim().parse().context("Invalid last shard ID")?,
+                ),
+                None => {
+                    let id = ids.parse().context("Invalid shard ID")?;
+                    (id, id)
+                }
+            },
+        };
+
+        if first > last || last >= total {
+            bail!(
+                "Shard IDs {first}-{last} must be an ascending range below the shard count {total}"
+            );
+        }
+
+        Ok(Self { total, first, last })
+    }
+
+    pub fn ids(self) -> Range<u16> {
+        self.first..self.last + 1
+    }
+
+    /// Discord's assignment of guilds to shards
+    pub fn shard_of(self, guild_id: GuildId) -> u16 {
+        ((guild_id.get() >> 22) % u64::from(self.total)) as u16
+    }
+
+    /// Whether events of the guild are received by this process. Direct messages are always
+    /// received by shard 0.
+    pub fn owns(self, guild_id: Option<GuildId>) -> bool {
+        let shard = guild_id.map_or(0, |guild_id| self.shard_of(guild_id));
+        self.ids().contains(&shard)
+    }
+
+    /// Tasks that aren't tied to a guild (e.g. the month rollover) only run in the process running
+    /// shard 0, so that they aren't duplicated across processes
+    pub fn is_primary(self) -> bool {
+        self.first == 0
+    }
+}
+
+/// Must be called at most once, before the client is started
+pub fn init(config: ShardConfig) {
+    CURRENT
+        .set(config)
+        .expect("shard config should only be initialised once");
+}
+
+pub fn current() -> ShardConfig {
+    CURRENT.get().copied().unwrap_or(ShardConfig::SINGLE)
+}
+
+/// Called on every `Ready`, so that background tasks can reach the shard of a specific guild
+pub fn register_context(ctx: &SerenityContext) {
+    CONTEXTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(ctx.shard_id.0, ctx.clone());
+}
+
+/// Context of the shard receiving the guild's events, `None` if it isn't run by this process or
+/// hasn't connected yet
+pub fn context_for(guild_id: GuildId) -> Option<SerenityContext> {
+    let shard = current().shard_of(guild_id);
+    CONTEXTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&shard)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shard_ranges() {
+        assert_eq!(
+            ShardConfig::parse("4", None).unwrap(),
+            ShardConfig {
+                total: 4,
+                first: 0,
+                last: 3
+            }
+        );
+        assert_eq!(ShardConfig::parse("4", Some("1-2")).unwrap().ids(), 1..3);
+        assert_eq!(ShardConfig::parse("4", Some("3")).unwrap().ids(), 3..4);
+        assert!(ShardConfig::parse("4", Some("2-4")).is_err());
+        assert!(ShardConfig::parse("4", Some("3-1")).is_err());
+        assert!(ShardConfig::parse("0", None).is_err());
+    }
+
+    #[test]
+    fn assigns_guilds_to_shards() {
+        let config = ShardConfig::parse("4", Some("1")).unwrap();
+        let guild = GuildId::new(5 << 22);
+
+        assert_eq!(config.shard_of(guild), 1);
+        assert!(config.owns(Some(guild)));
+        assert!(!config.owns(None));
+        assert!(!config.is_primary());
+    }
+}
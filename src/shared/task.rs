@@ -0,0 +1,99 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use poise::serenity_prelude::{
+    CreateComponent, CreateContainer, CreateContainerComponent, CreateMessage, CreateTextDisplay,
+    Http, MessageFlags, colours::css::DANGER, futures::FutureExt as _,
+};
+use tracing::{Instrument as _, error};
+
+use crate::config::BOT_MAINTAINER;
+
+static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+static FINISHED: AtomicUsize = AtomicUsize::new(0);
+static PANICKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Counters for background tasks since startup, shown by `/debug tasks`
+#[derive(Debug, Clone, Copy)]
+pub struct TaskStats {
+    pub spawned: usize,
+    pub running: usize,
+    pub panicked: usize,
+}
+
+pub fn stats() -> TaskStats {
+    let spawned = SPAWNED.load(Ordering::Relaxed);
+    let finished = FINISHED.load(Ordering::Relaxed);
+    TaskStats {
+        spawned,
+        running: spawned.saturating_sub(finished),
+        panicked: PANICKED.load(Ordering::Relaxed),
+    }
+}
+
+/// Spawns a background task whose panics are logged and reported to the bot maintainer, instead of
+/// silently killing the task. The task stays in the current span, so that its logs keep the
+/// correlation ID of the invocation spawning it.
+pub fn spawn_background<F>(name: &'static str, http: Arc<Http>, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    SPAWNED.fetch_add(1, Ordering::Relaxed);
+
+    tokio::spawn(
+        async move {
+            let result = AssertUnwindSafe(future).catch_unwind().await;
+            FINISHED.fetch_add(1, Ordering::Relaxed);
+
+            if let Err(payload) = result {
+                PANICKED.fetch_add(1, Ordering::Relaxed);
+                let message = panic_message(&*payload).to_string();
+                error!("Background task '{name}' panicked: {message}");
+
+                if let Err(err) = report_panic(&http, name, &message).await {
+                    error!("Failed to report panic in background task '{name}': {err:#}");
+                }
+            }
+        }
+        .in_current_span(),
+    );
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string panic payload>")
+}
+
+async fn report_panic(http: &Http, name: &str, message: &str) -> anyhow::Result<()> {
+    let text = CreateTextDisplay::new(format!(
+        "## Background Task Panicked
+Task `{name}` panicked and was stopped:
+```
+{message}
+```"
+    ));
+
+    BOT_MAINTAINER
+        .direct_message(
+            http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                        .accent_color(DANGER),
+                )]),
+        )
+        .await?;
+
+    Ok(())
+}
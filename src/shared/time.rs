@@ -0,0 +1,58 @@
+use std::fmt::Display;
+
+use poise::serenity_prelude::Timestamp;
+
+/// Display styles supported by Discord timestamp markers, rendered in the viewer's timezone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)] // not all styles are in use
+pub enum TimestampStyle {
+    /// e.g. `16:20`
+    ShortTime,
+    /// e.g. `16:20:30`
+    LongTime,
+    /// e.g. `20/04/2021`
+    ShortDate,
+    /// e.g. `20 April 2021`
+    LongDate,
+    /// e.g. `20 April 2021 16:20`
+    ShortDateTime,
+    /// e.g. `Tuesday, 20 April 2021 16:20`
+    LongDateTime,
+    /// e.g. `2 months ago`
+    Relative,
+}
+
+impl TimestampStyle {
+    fn as_flag(&self) -> char {
+        match self {
+            TimestampStyle::ShortTime => 't',
+            TimestampStyle::LongTime => 'T',
+            TimestampStyle::ShortDate => 'd',
+            TimestampStyle::LongDate => 'D',
+            TimestampStyle::ShortDateTime => 'f',
+            TimestampStyle::LongDateTime => 'F',
+            TimestampStyle::Relative => 'R',
+        }
+    }
+}
+
+/// Wrapper formatting a timestamp as a Discord timestamp marker (`<t:...:D>`)
+pub struct DiscordTimestamp {
+    timestamp: Timestamp,
+    style: TimestampStyle,
+}
+
+impl Display for DiscordTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:{}>",
+            self.timestamp.unix_timestamp(),
+            self.style.as_flag()
+        )
+    }
+}
+
+pub fn discord_timestamp(timestamp: Timestamp, style: TimestampStyle) -> DiscordTimestamp {
+    DiscordTimestamp { timestamp, style }
+}
@@ -1,7 +1,13 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display};
 
 use anyhow::{Context as _, Result, anyhow, bail};
 
+use poise::serenity_prelude::{GenericChannelId, GuildId, MessageId, RoleId, UserId};
+
+use crate::config::{
+    SPLASH_PING_ROLE, SPLASH_REMINDER_CHANNEL, SPLASH_REMINDER_DELAY_SECS, SPLASH_REMINDER_ROLE,
+    SPLASHER_ROLE, SPLASHES_CHANNEL, TY_CHANNEL,
+};
 use crate::error::UserError;
 
 pub struct BitSet {
@@ -140,6 +146,10 @@ impl BingoKind {
         }
     }
 
+    pub fn properties(&self) -> &'static BingoKindProperties {
+        &BINGO_KIND_PROPERTIES[*self as usize]
+    }
+
     pub fn as_prefix(&self) -> &str {
         match self {
             BingoKind::Normal => "",
@@ -149,6 +159,38 @@ impl BingoKind {
     }
 }
 
+/// Behaviour that differs between bingo kinds, indexed by the `BingoKind` discriminant
+#[derive(Debug)]
+pub struct BingoKindProperties {
+    /// Number of days the event lasts, counted from the first day of the month
+    pub duration_days: usize,
+    /// Whether every bingo of this kind gets its own completion role (otherwise only the first)
+    pub specific_completion_roles: bool,
+    /// Whether kind-specific IDs are identical to the unique IDs used by the API
+    pub shares_unique_ids: bool,
+}
+
+const BINGO_KIND_PROPERTIES: [BingoKindProperties; 3] = [
+    // Normal
+    BingoKindProperties {
+        duration_days: 7,
+        specific_completion_roles: false,
+        shares_unique_ids: true,
+    },
+    // Extreme
+    BingoKindProperties {
+        duration_days: 14,
+        specific_completion_roles: true,
+        shares_unique_ids: false,
+    },
+    // Secret
+    BingoKindProperties {
+        duration_days: 14,
+        specific_completion_roles: true,
+        shares_unique_ids: false,
+    },
+];
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Bingo {
     pub kind_specific_id: u8,
@@ -229,6 +271,240 @@ impl Bingo {
     }
 }
 
+/// A Minecraft account given as user input, either by username or UUID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MinecraftIdent {
+    /// name lookups are case-insensitive, so the original casing is kept for display
+    Username(String),
+    /// lowercase and without dashes, as stored in the database
+    Uuid(String),
+}
+
+impl Display for MinecraftIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Username(username) => write!(f, "{username}"),
+            Self::Uuid(uuid) => write!(f, "{uuid}"),
+        }
+    }
+}
+
+impl MinecraftIdent {
+    pub fn from_input(input: &str) -> Result<Self> {
+        let input = input.trim();
+
+        // UUIDs are accepted with or without dashes, but dashes have to be in the right places
+        let undashed = input.replace('-', "");
+        if undashed.len() == 32 && undashed.chars().all(|c| c.is_ascii_hexdigit()) {
+            let dashes: Vec<_> = input.match_indices('-').map(|(i, _)| i).collect();
+            if !dashes.is_empty() && dashes != [8, 13, 18, 23] {
+                bail!(UserError(anyhow!("Invalid Minecraft UUID: `{input}`")));
+            }
+            return Ok(Self::Uuid(undashed.to_lowercase()));
+        }
+
+        if !(3..=16).contains(&input.len())
+            || !input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!(UserError(anyhow!(
+                "Invalid Minecraft username: `{input}` (expected 3-16 letters, digits or underscores, or a UUID)"
+            )));
+        }
+
+        Ok(Self::Username(input.to_string()))
+    }
+}
+
+/// Per-guild channels and roles, defaulting to the constants in [`crate::config`]
+#[derive(Debug, Clone, Copy)]
+pub struct GuildConfig {
+    pub splashes_channel: GenericChannelId,
+    pub splash_ping_role: RoleId,
+    pub splasher_role: RoleId,
+    pub ty_channel: GenericChannelId,
+    /// pinged by splash reminders
+    pub splash_reminder_role: RoleId,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            splashes_channel: SPLASHES_CHANNEL,
+            splash_ping_role: SPLASH_PING_ROLE,
+            splasher_role: SPLASHER_ROLE,
+            ty_channel: TY_CHANNEL,
+            splash_reminder_role: SPLASH_REMINDER_ROLE,
+        }
+    }
+}
+
+/// A single configurable value of [`GuildConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuildConfigKey {
+    SplashesChannel,
+    SplashPingRole,
+    SplasherRole,
+    TyChannel,
+    SplashReminderRole,
+}
+
+impl GuildConfigKey {
+    pub const ALL: &[GuildConfigKey; 5] = &[
+        GuildConfigKey::SplashesChannel,
+        GuildConfigKey::SplashPingRole,
+        GuildConfigKey::SplasherRole,
+        GuildConfigKey::TyChannel,
+        GuildConfigKey::SplashReminderRole,
+    ];
+
+    /// Column in the `guild_config` table
+    pub fn column(self) -> &'static str {
+        match self {
+            GuildConfigKey::SplashesChannel => "splashes_channel",
+            GuildConfigKey::SplashPingRole => "splash_ping_role",
+            GuildConfigKey::SplasherRole => "splasher_role",
+            GuildConfigKey::TyChannel => "ty_channel",
+            GuildConfigKey::SplashReminderRole => "splash_reminder_role",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GuildConfigKey::SplashesChannel => "Splashes Channel",
+            GuildConfigKey::SplashPingRole => "Splash Ping Role",
+            GuildConfigKey::SplasherRole => "Splasher Role",
+            GuildConfigKey::TyChannel => "Thank You Channel",
+            GuildConfigKey::SplashReminderRole => "Splash Reminder Role",
+        }
+    }
+}
+
+/// A period during which a splasher marked themselves as unavailable via `/splashes away`
+#[derive(Debug, Clone)]
+pub struct SplasherAway {
+    pub user: UserId,
+    /// unix timestamp of the start of the first day
+    pub start: i64,
+    /// unix timestamp of the end of the last day
+    pub end: i64,
+    pub reason: Option<String>,
+}
+
+impl SplasherAway {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.start <= now && now <= self.end
+    }
+}
+
+/// Progress of a splasher through the onboarding checklist sent after gaining the splasher role
+#[derive(Debug, Clone)]
+pub struct SplasherOnboarding {
+    pub user: UserId,
+    pub started_at: i64,
+    /// identifiers of the acknowledged checklist items
+    pub acknowledged: HashSet<String>,
+}
+
+/// Where and when splashers without recent splashes are reported to staff
+#[derive(Debug, Clone)]
+pub struct SplashInactivityConfig {
+    /// guild whose splasher role is checked
+    pub guild_id: GuildId,
+    pub channel: GenericChannelId,
+    pub threshold_days: u32,
+    /// unix timestamp of the latest digest
+    pub last_alert: Option<i64>,
+}
+
+/// Placeholders available in splash reminder message templates
+pub const REMINDER_PLACEHOLDERS: &[&str] = &["{reason}", "{role}"];
+
+/// When and how splash reminders are sent, defaulting to the constants in [`crate::config`]
+#[derive(Debug, Clone)]
+pub struct SplashReminderConfig {
+    pub delay_secs: u64,
+    pub channel: GenericChannelId,
+    /// Supports the placeholders in [`REMINDER_PLACEHOLDERS`]
+    pub template: String,
+}
+
+impl SplashReminderConfig {
+    pub const DEFAULT_TEMPLATE: &str = "## Splash Needed
+{reason}
+{role}";
+
+    /// Fills in the template's placeholders
+    pub fn render(&self, reason: &str, role: &str) -> String {
+        self.template
+            .replace("{reason}", reason)
+            .replace("{role}", role)
+    }
+
+    /// The delay in words, e.g. `1 hour` or `45 minutes`
+    pub fn delay_text(&self) -> String {
+        let minutes = self.delay_secs / 60;
+        match (minutes / 60, minutes % 60) {
+            (1, 0) => "1 hour".to_string(),
+            (hours, 0) => format!("{hours} hours"),
+            (0, 1) => "1 minute".to_string(),
+            (0, minutes) => format!("{minutes} minutes"),
+            (hours, minutes) => format!("{hours}h {minutes}min"),
+        }
+    }
+}
+
+impl Default for SplashReminderConfig {
+    fn default() -> Self {
+        Self {
+            delay_secs: SPLASH_REMINDER_DELAY_SECS,
+            channel: SPLASH_REMINDER_CHANNEL,
+            template: Self::DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// How the splasher of a splash message is determined
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SplashIngestion {
+    /// The message's author performed the splash
+    #[default]
+    Author,
+    /// Splashes are relayed by a webhook or bridge bot, the splasher is mentioned in the content or
+    /// named in a "by <name>" pattern, and looked up among the members of `guild_id`
+    Content { guild_id: GuildId },
+}
+
+/// A splash message persisted in the `splashes` table. Only what is needed to apply the splash
+/// rules and resolve the splasher is stored, so the rules can change after the message arrived.
+#[derive(Debug, Clone)]
+pub struct StoredSplash {
+    pub message_id: MessageId,
+    pub channel: GenericChannelId,
+    pub guild_id: Option<GuildId>,
+    /// unix timestamp
+    pub timestamp: i64,
+    pub author: UserId,
+    /// first non-bot user mentioned in the message
+    pub mentioned: Option<UserId>,
+    /// splasher named in a "by <name>" pattern, lowercased
+    pub named: Option<String>,
+    pub hub: Option<String>,
+    pub has_image: bool,
+}
+
+/// A thank-you in the thank-you channel, attributed to the most recent splash
+#[derive(Debug, Clone)]
+pub struct SplashThanks {
+    /// message in the thank-you channel, either sent or reacted to by `thanked_by`
+    pub source_message_id: MessageId,
+    pub thanked_by: UserId,
+    pub splash_message_id: MessageId,
+    /// `None` if the splasher couldn't be determined
+    pub splasher: Option<UserId>,
+    /// unix timestamp
+    pub timestamp: i64,
+}
+
 #[derive(Debug)]
 pub enum SqlResponse {
     AffectedRows(usize),
@@ -243,3 +519,25 @@ impl SqlResponse {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct DbStats {
+    /// row count per table, ordered by name
+    pub tables: Vec<(String, u64)>,
+    pub page_size: u64,
+    pub page_count: u64,
+    /// unused pages, which are only returned to the file system by `VACUUM`
+    pub free_pages: u64,
+}
+
+impl DbStats {
+    /// in bytes, excluding the WAL
+    pub fn file_size(&self) -> u64 {
+        self.page_size * self.page_count
+    }
+
+    /// in bytes
+    pub fn free_size(&self) -> u64 {
+        self.page_size * self.free_pages
+    }
+}
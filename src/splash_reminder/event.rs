@@ -1,77 +1,295 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::db::DbHandle;
+use crate::error;
 use crate::shared::{
     BotData,
-    db::{GetCurrentBingo, GetSplashReminder},
+    db::{
+        GetCurrentBingo, GetSplashImageRequired, GetSplashIngestion, GetSplashReminder,
+        GetSplashReminderConfig, GetSplashReminderSkipped, GetSplashReminderTimer,
+        GetSplashReminderTimers, SetSplashReminderTimer,
+    },
+    shard,
 };
-use crate::splash_reminder::reminder::{self, ReminderVariant, TIMER_WAIT_SECS};
-use crate::splashes::fetch::FetchSplashes;
+use crate::splash_reminder::reminder::{self, ReminderVariant};
+use crate::splashes::{self, fetch::FetchSplashes, ingestion::SplasherResolver};
 
 use anyhow::Result;
 use chrono::Utc;
-use poise::serenity_prelude::{Context as SerenityContext, Message, Reaction, ReactionType};
+use poise::serenity_prelude::{
+    Context as SerenityContext, CreateAllowedMentions, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, GenericChannelId, GuildId,
+    Mentionable as _, Message, MessageFlags, MessageId, Reaction, ReactionType, UserId,
+    colours::css::WARNING,
+};
+use tracing::{error, info};
 
 pub async fn splashes_message(ctx: &SerenityContext, message: &Message) -> Result<()> {
-    if !FetchSplashes::is_splash(message) {
+    let data = ctx.data::<BotData>();
+    let guild_config = data.guild_config(message.guild_id).await?;
+
+    if message.channel_id != guild_config.splashes_channel
+        || !FetchSplashes::is_splash(message, guild_config.splash_ping_role)
+    {
         return Ok(());
     }
 
-    let (enabled, _, _) = ctx
-        .data::<BotData>()
-        .db_handle
-        .request(GetSplashReminder)
-        .await??;
+    splashes::store::store_message(data.db_for(message.guild_id), message).await?;
 
-    if !enabled {
+    // the reminder and its settings are shared by all guilds, so it only runs for one of them
+    if !data.is_home_guild(message.guild_id) {
         return Ok(());
     }
 
-    // abort if no active bingo in one hour
-    if !is_active_bingo_with_offset(ctx, Duration::from_hours(1)).await? {
-        return Ok(());
+    // nudge the splasher independently of the reminder, which still treats this as a splash
+    let db = data.db_for(message.guild_id);
+    if db.request(GetSplashImageRequired).await?? && !FetchSplashes::has_image(message) {
+        let ingestion = db.request(GetSplashIngestion).await??;
+        // the nudge is optional, so failing to resolve the splasher mustn't skip the reminder
+        match SplasherResolver::new(ingestion)
+            .resolve(&ctx.http, message)
+            .await
+        {
+            Ok(Some(splasher)) => {
+                if let Err(err) = nudge_missing_image(ctx, message, splasher).await {
+                    error!("Failed to send missing splash image notice: {err:#}");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to resolve splasher of {}: {err:#}", message.id),
+        }
     }
 
-    // update latest splash and initiate timer
+    register_splash(ctx, message.guild_id, message.id).await
+}
+
+/// Starts the reminder timer of the splash, unless it is already running or the reminder is already
+/// due
+pub async fn register_splash(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    message_id: MessageId,
+) -> Result<()> {
     let data = ctx.data::<BotData>();
+    let db = data.db_for(guild_id);
+    let (enabled, _, _) = db.request(GetSplashReminder).await??;
+
+    if !enabled
+        || db
+            .request(GetSplashReminderSkipped { message_id })
+            .await??
+    {
+        return Ok(());
+    }
+
+    let delay_secs = db.request(GetSplashReminderConfig).await??.delay_secs;
+
+    // abort if no active bingo once the reminder would be due
+    if !is_active_bingo_with_offset(&data, db, Duration::from_secs(delay_secs)).await? {
+        return Ok(());
+    }
+
+    let deadline = message_id.created_at().unix_timestamp() + delay_secs as i64;
+    let remaining = deadline - Utc::now().timestamp();
+    if remaining <= 0 {
+        return Ok(());
+    }
+
+    // e.g. counted manually after being detected automatically
     {
         let mut handle = data.splash_reminder.lock().await;
-        handle.new_splash(Arc::clone(&ctx.http), message.id).await;
+        if handle.is_pending(message_id) {
+            return Ok(());
+        }
+        handle
+            .new_splash(
+                Arc::clone(&ctx.http),
+                Arc::clone(&data),
+                guild_id,
+                message_id,
+                Duration::from_secs(remaining as u64),
+            )
+            .await;
     }
 
+    // persist timer in case of a restart
+    db.request(SetSplashReminderTimer {
+        message_id,
+        deadline: Some(deadline),
+    })
+    .await??;
+
     Ok(())
 }
 
-pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Result<()> {
+/// Stops the reminder timer started by the specified message, if any
+pub async fn unregister_splash(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    message_id: MessageId,
+) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    data.splash_reminder.lock().await.cancel(message_id);
+
+    // also covers timers of other processes, which check the persisted timer before reminding
+    data.db_for(guild_id)
+        .request(SetSplashReminderTimer {
+            message_id,
+            deadline: None,
+        })
+        .await??;
+
+    Ok(())
+}
+
+/// Stops the reminder timer of a splash that was deleted, as it can no longer be followed up on
+pub async fn splash_deleted(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    channel_id: GenericChannelId,
+    message_id: MessageId,
+) -> Result<()> {
     let data = ctx.data::<BotData>();
+    if !data.is_home_guild(guild_id)
+        || channel_id != data.guild_config(guild_id).await?.splashes_channel
     {
-        let mut handle = data.splash_reminder.lock().await;
-        let Some(latest_id) = handle.latest() else {
-            return Ok(());
-        };
+        return Ok(());
+    }
 
-        // verify message id
-        if reaction.message_id != latest_id {
-            return Ok(());
-        }
+    if data
+        .db_for(guild_id)
+        .request(GetSplashReminderTimer { message_id })
+        .await??
+        .is_some()
+    {
+        info!("Cancelling splash reminder timer for deleted splash {message_id}");
+    }
+    unregister_splash(ctx, guild_id, message_id).await
+}
 
-        // ensure timer wait time hasn't passed yet
-        let now = Utc::now().timestamp();
-        let created = latest_id.created_at().timestamp();
+/// Re-arms the reminder timers of splashes that were posted before the last restart, if their
+/// deadline hasn't passed yet
+pub async fn restore_reminder(ctx: &SerenityContext) -> Result<()> {
+    // the persisted timers are shared by all processes, so only one may re-arm them
+    if !shard::current().is_primary() {
+        return Ok(());
+    }
 
-        if now - created >= TIMER_WAIT_SECS as i64 {
-            // clear so that check isn't performed on the next reaction
-            handle.clear_latest();
-            return Ok(());
+    let data = ctx.data::<BotData>();
+    // timers of the main database belong to the home guild, unless that is the sandbox guild
+    let main_guild = data.home_guild.filter(|&home| {
+        !data
+            .sandbox
+            .as_ref()
+            .is_some_and(|sandbox| sandbox.guild_id == home)
+    });
+    let guilds = std::iter::once(main_guild)
+        .chain(data.sandbox.iter().map(|sandbox| Some(sandbox.guild_id)));
+
+    for guild_id in guilds {
+        restore_timers(ctx, &data, guild_id).await?;
+    }
+
+    Ok(())
+}
+
+async fn restore_timers(
+    ctx: &SerenityContext,
+    data: &Arc<BotData>,
+    guild_id: Option<GuildId>,
+) -> Result<()> {
+    let db = data.db_for(guild_id);
+    let now = Utc::now().timestamp();
+    let (enabled, _, _) = db.request(GetSplashReminder).await??;
+
+    for (message_id, deadline) in db.request(GetSplashReminderTimers).await?? {
+        let remaining = deadline - now;
+        // the same checks as when registering the splash, as the reminder may have been disabled
+        // or the bingo may end during downtime
+        let obsolete = remaining <= 0
+            || !enabled
+            || db
+                .request(GetSplashReminderSkipped { message_id })
+                .await??
+            || !is_active_bingo_with_offset(data, db, Duration::from_secs(remaining as u64))
+                .await?;
+        if obsolete {
+            db.request(SetSplashReminderTimer {
+                message_id,
+                deadline: None,
+            })
+            .await??;
+            continue;
         }
+
+        info!("Restoring splash reminder timer for {message_id} with {remaining}s remaining");
+        data.splash_reminder
+            .lock()
+            .await
+            .new_splash(
+                Arc::clone(&ctx.http),
+                Arc::clone(data),
+                guild_id,
+                message_id,
+                Duration::from_secs(remaining as u64),
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Replies to a splash that doesn't satisfy the image requirement, so it isn't silently ignored
+async fn nudge_missing_image(
+    ctx: &SerenityContext,
+    message: &Message,
+    splasher: UserId,
+) -> Result<()> {
+    let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
+        "## Missing Screenshot
+{} Splashes need to include a screenshot to be counted on the splash list. Please attach one to your future splash messages!",
+        splasher.mention()
+    )));
+
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .reference_message(message)
+                .allowed_mentions(CreateAllowedMentions::new().users(&[splasher]))
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![text]).accent_color(WARNING),
+                )]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    if !data.is_home_guild(reaction.guild_id)
+        || reaction.channel_id != data.guild_config(reaction.guild_id).await?.splashes_channel
+    {
+        return Ok(());
+    }
+
+    // only splashes whose timer hasn't run out yet can trigger a reminder through reactions
+    if !data
+        .splash_reminder
+        .lock()
+        .await
+        .is_pending(reaction.message_id)
+    {
+        return Ok(());
     }
 
     // fetch and verify configuration
-    let (enabled, emoji_id, emoji_count) = ctx
-        .data::<BotData>()
-        .db_handle
-        .request(GetSplashReminder)
-        .await??;
+    let db = data.db_for(reaction.guild_id);
+    let (enabled, emoji_id, emoji_count) = db.request(GetSplashReminder).await??;
 
     if !enabled || emoji_id.is_none() {
         return Ok(());
@@ -89,11 +307,21 @@ pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Re
     }
 
     // abort if no active bingo
-    if !is_active_bingo(ctx).await? {
+    if !is_active_bingo(&data, db).await? {
         return Ok(());
     }
 
-    let message = reaction.message(ctx).await?;
+    let message = match reaction.message(ctx).await {
+        Ok(message) => message,
+        Err(err) => {
+            let err = anyhow::Error::from(err);
+            // deleted before the reaction was handled
+            if error::is_unknown_message(&err) {
+                return unregister_splash(ctx, reaction.guild_id, reaction.message_id).await;
+            }
+            return Err(err);
+        }
+    };
     // find and compare reaction count
     if let Some(r) = message
         .reactions
@@ -101,11 +329,20 @@ pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Re
         .find(|r| matches!(&r.reaction_type, ReactionType::Custom { id, .. } if *id == emoji_id))
         && r.count >= emoji_count as u64
     {
-        // cancel 1 hour reminder
+        // cancel the splash's timed reminder, unless a concurrent reaction already did
+        if !data
+            .splash_reminder
+            .lock()
+            .await
+            .cancel(reaction.message_id)
         {
-            let mut handle = data.splash_reminder.lock().await;
-            handle.clear_latest();
+            return Ok(());
         }
+        db.request(SetSplashReminderTimer {
+            message_id: reaction.message_id,
+            deadline: None,
+        })
+        .await??;
         // trigger reminder
         if let ReactionType::Custom { animated, id, name } = &r.reaction_type
             && *id == emoji_id
@@ -118,6 +355,9 @@ pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Re
             );
             reminder::send_reminder(
                 Arc::clone(&ctx.http),
+                &data,
+                reaction.guild_id,
+                reaction.message_id,
                 ReminderVariant::Reactions {
                     emoji_mention,
                     emoji_count,
@@ -130,16 +370,16 @@ pub async fn splashes_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Re
     Ok(())
 }
 
-async fn is_active_bingo(ctx: &SerenityContext) -> Result<bool> {
-    is_active_bingo_with_offset(ctx, Duration::ZERO).await
+async fn is_active_bingo(data: &BotData, db: &DbHandle) -> Result<bool> {
+    is_active_bingo_with_offset(data, db, Duration::ZERO).await
 }
 
 async fn is_active_bingo_with_offset(
-    ctx: &SerenityContext,
+    data: &BotData,
+    db: &DbHandle,
     future_offset: Duration,
 ) -> Result<bool> {
-    let data = ctx.data::<BotData>();
-    let current_bingo = data.db_handle.request(GetCurrentBingo).await??;
+    let current_bingo = db.request(GetCurrentBingo).await??;
 
     let now = Utc::now().timestamp();
     let now_offset = now + future_offset.as_secs() as i64;
@@ -147,11 +387,7 @@ async fn is_active_bingo_with_offset(
     let (_, start, end) = match current_bingo {
         Some((b, start, end)) if now < end => (b, start, end),
         // make sure current bingo data is up-to-date if possibly outdated
-        _ => {
-            data.api_handle
-                .update_current_bingo(&data.db_handle)
-                .await?
-        }
+        _ => data.api_handle.update_current_bingo(db).await?,
     };
 
     // Only consider bingo active if it will still be active after `future_offset`
@@ -0,0 +1,132 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use either::Either;
+use poise::serenity_prelude::{
+    ComponentInteraction, Context as SerenityContext, CreateAllowedMentions, CreateComponent,
+    CreateContainer, CreateContainerComponent, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateTextDisplay, Mentionable as _, MessageFlags, MessageId,
+    ModalInteraction, Timestamp, colours::css::POSITIVE,
+};
+use tracing::info;
+
+use crate::error::UserError;
+use crate::shared::{
+    BotData,
+    db::{GetSplashReminderSkipped, SetSplashReminderTimer},
+    interaction::custom_id::Parts,
+    time::{TimestampStyle::ShortTime, discord_timestamp},
+};
+
+/// How long a snoozed reminder waits before being sent again
+pub const SNOOZE: Duration = Duration::from_secs(30 * 60);
+
+pub async fn handle_interaction(
+    ctx: &SerenityContext,
+    interaction: Either<&ComponentInteraction, &ModalInteraction>,
+    mut action: Parts<'_>,
+) -> Result<()> {
+    let Either::Left(interaction) = interaction else {
+        return Err(anyhow!("Invalid interaction: Unexpected modal"));
+    };
+    info!(
+        "{} triggered component interaction: '{}'",
+        interaction.user.name, interaction.data.custom_id
+    );
+
+    match action.next().unwrap_or_default() {
+        "snooze" => {
+            snooze(
+                ctx,
+                interaction,
+                MessageId::new(action.next_parsed("splash")?),
+            )
+            .await
+        }
+        _ => Err(anyhow!("Invalid interaction: Unknown action")),
+    }
+}
+
+async fn snooze(
+    ctx: &SerenityContext,
+    interaction: &ComponentInteraction,
+    splash: MessageId,
+) -> Result<()> {
+    let data = ctx.data::<BotData>();
+
+    // those pinged by the reminder and splashers themselves may snooze it
+    let config = data.guild_config(interaction.guild_id).await?;
+    let allowed = interaction.member.as_ref().is_some_and(|member| {
+        member.roles.contains(&config.splash_reminder_role)
+            || member.roles.contains(&config.splasher_role)
+    });
+    if !allowed {
+        bail!(UserError(anyhow!(
+            "Only members with the {} or {} role can snooze splash reminders",
+            config.splash_reminder_role.mention(),
+            config.splasher_role.mention()
+        )));
+    }
+
+    let db = data.db_for(interaction.guild_id);
+    if db
+        .request(GetSplashReminderSkipped { message_id: splash })
+        .await??
+    {
+        bail!(UserError(anyhow!(
+            "This splash was marked as not needing a reminder"
+        )));
+    }
+
+    let snoozes = {
+        let mut handle = data.splash_reminder.lock().await;
+        handle
+            .snooze(
+                Arc::clone(&ctx.http),
+                Arc::clone(&data),
+                interaction.guild_id,
+                splash,
+                SNOOZE,
+            )
+            .await?
+    };
+
+    // persist timer in case of a restart
+    let deadline = Utc::now().timestamp() + SNOOZE.as_secs() as i64;
+    db.request(SetSplashReminderTimer {
+        message_id: splash,
+        deadline: Some(deadline),
+    })
+    .await??;
+
+    info!(
+        "{} snoozed the splash reminder for {splash}",
+        interaction.user.name
+    );
+
+    let text = CreateTextDisplay::new(format!(
+        "## Reminder Snoozed
+{} snoozed the splash reminder, it will be sent again at {} unless someone splashes first.
+-# Snoozed {snoozes} time(s) so far.",
+        interaction.user.mention(),
+        discord_timestamp(Timestamp::from_unix_timestamp(deadline)?, ShortTime)
+    ));
+
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(vec![CreateComponent::Container(
+                        CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                            .accent_color(POSITIVE),
+                    )]),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
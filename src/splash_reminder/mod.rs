@@ -1,52 +1,149 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use poise::serenity_prelude::{Http, MessageId};
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use poise::serenity_prelude::{GuildId, Http, MessageId};
 use tokio::sync::oneshot;
 
+use crate::error::UserError;
+use crate::shared::BotData;
+use crate::splash_reminder::reminder::ReminderVariant;
+
 pub mod event;
+pub mod interaction;
+pub mod modal;
 mod reminder;
 
+/// How often the reminder for a single splash may be snoozed
+const MAX_SNOOZES: u32 = 3;
+/// How long a splash's timer is tracked after it was posted, so that its snoozes are still counted
+/// after the reminder was sent. Exceeds the maximum delay and all snoozes combined.
+const TRACKED_SECS: i64 = 12 * 60 * 60;
+
+/// Reminder timers of all splashes that haven't been followed up on yet, each with its own
+/// lifecycle, so that a new splash doesn't cancel the reminder of an earlier one
 pub struct SplashReminderHandle {
-    latest: Option<MessageId>,
+    timers: HashMap<MessageId, ReminderTimer>,
+}
+
+struct ReminderTimer {
+    /// `None` once the timer was cancelled
     cancel_tx: Option<oneshot::Sender<()>>,
+    /// times the reminder was snoozed
+    snoozes: u32,
+}
+
+impl ReminderTimer {
+    fn cancel(&mut self) {
+        if let Some(tx) = self.cancel_tx.take() {
+            // this `Result` is irrelevant, as failure simply means the timer has already run out
+            // and dropped the receiver
+            let _ = tx.send(());
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        self.cancel_tx.as_ref().is_some_and(|tx| !tx.is_closed())
+    }
 }
 
 impl SplashReminderHandle {
     pub fn new() -> Self {
         Self {
-            latest: None,
-            cancel_tx: None,
+            timers: HashMap::new(),
         }
     }
 
-    pub fn latest(&self) -> Option<MessageId> {
-        self.latest
+    /// Whether the splash has a timer that hasn't run out or been cancelled yet
+    pub fn is_pending(&self, message: MessageId) -> bool {
+        self.timers
+            .get(&message)
+            .is_some_and(ReminderTimer::is_pending)
     }
 
-    fn cancel_timer(&mut self) {
-        if let Some(tx) = self.cancel_tx.take() {
-            // this `Result` is irrelevant, as failure simply means the timer has already run out
-            // and dropped the receiver
-            let _ = tx.send(());
-        }
+    /// Stops the splash's timer, returning whether it was still pending
+    pub fn cancel(&mut self, message: MessageId) -> bool {
+        let Some(timer) = self.timers.get_mut(&message) else {
+            return false;
+        };
+        let pending = timer.is_pending();
+        timer.cancel();
+        pending
     }
 
-    pub fn clear_latest(&mut self) {
-        self.cancel_timer();
-        self.latest = None;
+    /// Starts the reminder timer of a new splash, which runs out after `wait`
+    pub async fn new_splash(
+        &mut self,
+        http: Arc<Http>,
+        data: Arc<BotData>,
+        guild_id: Option<GuildId>,
+        message: MessageId,
+        wait: Duration,
+    ) {
+        self.prune();
+        self.start_timer(http, data, guild_id, message, wait, ReminderVariant::Time)
+            .await;
     }
 
-    pub async fn new_splash(&mut self, http: Arc<Http>, message: MessageId) {
-        self.latest = Some(message);
+    /// Re-arms the reminder for a splash whose reminder was already sent, unless it was snoozed too
+    /// often. Returns how often it has been snoozed.
+    pub async fn snooze(
+        &mut self,
+        http: Arc<Http>,
+        data: Arc<BotData>,
+        guild_id: Option<GuildId>,
+        message: MessageId,
+        wait: Duration,
+    ) -> Result<u32> {
+        let snoozes = self.timers.get(&message).map_or(0, |timer| timer.snoozes);
+        if snoozes >= MAX_SNOOZES {
+            bail!(UserError(anyhow!(
+                "This reminder was already snoozed {MAX_SNOOZES} times"
+            )));
+        }
+
+        self.start_timer(
+            http,
+            data,
+            guild_id,
+            message,
+            wait,
+            ReminderVariant::Snoozed,
+        )
+        .await;
+        if let Some(timer) = self.timers.get_mut(&message) {
+            timer.snoozes = snoozes + 1;
+        }
 
-        // cancel previous timer if present
-        self.cancel_timer();
+        Ok(snoozes + 1)
+    }
 
-        // initiate new timer
+    async fn start_timer(
+        &mut self,
+        http: Arc<Http>,
+        data: Arc<BotData>,
+        guild_id: Option<GuildId>,
+        message: MessageId,
+        wait: Duration,
+        variant: ReminderVariant,
+    ) {
+        // cancel the splash's previous timer if present
         let (cancel_tx, cancel_rx) = oneshot::channel();
+        let timer = self.timers.entry(message).or_insert(ReminderTimer {
+            cancel_tx: None,
+            snoozes: 0,
+        });
+        timer.cancel();
+        timer.cancel_tx = Some(cancel_tx);
 
-        self.cancel_tx = Some(cancel_tx);
+        reminder::spawn_timer(http, data, guild_id, message, cancel_rx, wait, variant).await;
+    }
 
-        reminder::spawn_timer(http, cancel_rx).await;
+    /// Forgets splashes that were posted too long ago to still be reminded of
+    fn prune(&mut self) {
+        let now = Utc::now().timestamp();
+        self.timers.retain(|message, timer| {
+            timer.is_pending() || now - message.created_at().unix_timestamp() < TRACKED_SECS
+        });
     }
 }
@@ -0,0 +1,36 @@
+use poise::serenity_prelude::InputTextStyle;
+
+use bb_bot_macros::define_modal;
+
+define_modal! {
+    ReminderConfig {
+        custom_id: "reminder_config_submit",
+        title: "Configure Splash Reminders",
+        components: [
+            input delay {
+                style: InputTextStyle::Short,
+                label: "Delay (minutes)",
+                description: "How long after the latest splash the reminder is sent",
+                placeholder: "Enter a number",
+                max_length: 3,
+                required: true,
+            },
+            input channel_id {
+                style: InputTextStyle::Short,
+                label: "Channel",
+                description: "Where reminders are sent",
+                placeholder: "Enter a channel ID",
+                max_length: 20,
+                required: true,
+            },
+            input template {
+                style: InputTextStyle::Paragraph,
+                label: "Message",
+                description: "Available placeholders: {reason}, {role}",
+                placeholder: "Enter the reminder message",
+                max_length: 1000,
+                required: true,
+            },
+        ]
+    }
+}
@@ -1,50 +1,87 @@
+use std::sync::Arc;
 use std::time::Duration;
-use std::{borrow::Cow, sync::Arc};
 
 use anyhow::Result;
 use poise::serenity_prelude::{
-    CreateAllowedMentions, CreateComponent, CreateContainer, CreateContainerComponent,
-    CreateMessage, CreateTextDisplay, Http, Mentionable, MessageFlags, colours::css::DANGER,
+    ButtonStyle, CreateActionRow, CreateAllowedMentions, CreateButton, CreateComponent,
+    CreateContainer, CreateContainerComponent, CreateMessage, CreateTextDisplay, GuildId, Http,
+    Mentionable, MessageFlags, MessageId, colours::css::DANGER,
 };
 use tokio::{select, sync::oneshot};
 use tracing::error;
 
-use crate::config::{SPLASH_REMINDER_CHANNEL, SPLASH_REMINDER_ROLE};
+use crate::shared::{
+    BotData,
+    db::{GetSplashReminderConfig, GetSplashReminderTimer, SetSplashReminderTimer},
+    interaction::custom_id::{self, Namespace},
+    task::spawn_background,
+};
+use crate::splash_reminder::interaction::SNOOZE;
 
 pub enum ReminderVariant {
     Time,
+    /// a previous reminder was snoozed without a new splash being posted
+    Snoozed,
     Reactions {
         emoji_mention: String,
         emoji_count: u32,
     },
 }
 
-pub async fn send_reminder(http: Arc<Http>, variant: ReminderVariant) -> Result<()> {
+/// Sends the reminder for `splash`, the latest splash message, which can be snoozed from the
+/// reminder itself
+pub async fn send_reminder(
+    http: Arc<Http>,
+    data: &BotData,
+    guild_id: Option<GuildId>,
+    splash: MessageId,
+    variant: ReminderVariant,
+) -> Result<()> {
+    // read when sending, so that configuration changes apply to already running timers
+    let config = data
+        .db_for(guild_id)
+        .request(GetSplashReminderConfig)
+        .await??;
+    let role = data.guild_config(guild_id).await?.splash_reminder_role;
+
     let variant_text = match variant {
-        ReminderVariant::Time => Cow::Borrowed("It has been 1 hour since the last splash!"),
+        ReminderVariant::Time => {
+            format!("It has been {} since the last splash!", config.delay_text())
+        }
+        ReminderVariant::Snoozed => {
+            "The reminder was snoozed, but there still hasn't been a new splash!".to_string()
+        }
         ReminderVariant::Reactions {
             emoji_mention,
             emoji_count,
-        } => Cow::Owned(format!(
-            "The latest splash message has {emoji_count}+ {emoji_mention} reactions!"
-        )),
+        } => format!("The latest splash message has {emoji_count}+ {emoji_mention} reactions!"),
     };
 
-    let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(format!(
-        "## Splash Needed
-{variant_text}
-{}",
-        SPLASH_REMINDER_ROLE.mention()
-    )));
+    let text = CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+        config.render(&variant_text, &role.mention().to_string()),
+    ));
+    let snooze_button = CreateButton::new(custom_id::encode(
+        Namespace::SplashReminder,
+        &[&"snooze", &splash],
+    ))
+    .label(format!("Snooze for {} minutes", SNOOZE.as_secs() / 60))
+    .style(ButtonStyle::Secondary);
 
-    SPLASH_REMINDER_CHANNEL
+    config
+        .channel
         .send_message(
             &http,
             CreateMessage::new()
                 .flags(MessageFlags::IS_COMPONENTS_V2)
-                .allowed_mentions(CreateAllowedMentions::new().roles(&[SPLASH_REMINDER_ROLE]))
+                .allowed_mentions(CreateAllowedMentions::new().roles(&[role]))
                 .components(vec![CreateComponent::Container(
-                    CreateContainer::new(vec![text]).accent_color(DANGER),
+                    CreateContainer::new(vec![
+                        text,
+                        CreateContainerComponent::ActionRow(CreateActionRow::Buttons(
+                            vec![snooze_button].into(),
+                        )),
+                    ])
+                    .accent_color(DANGER),
                 )]),
         )
         .await?;
@@ -52,18 +89,51 @@ pub async fn send_reminder(http: Arc<Http>, variant: ReminderVariant) -> Result<
     Ok(())
 }
 
-pub const TIMER_WAIT_SECS: u64 = 3600;
-
-pub async fn spawn_timer(http: Arc<Http>, cancel_rx: oneshot::Receiver<()>) {
+pub async fn spawn_timer(
+    http: Arc<Http>,
+    data: Arc<BotData>,
+    guild_id: Option<GuildId>,
+    message: MessageId,
+    cancel_rx: oneshot::Receiver<()>,
+    wait: Duration,
+    variant: ReminderVariant,
+) {
     println!("spawned timer");
-    tokio::spawn(async move {
+    let task_http = Arc::clone(&http);
+    spawn_background("splash reminder timer", task_http, async move {
         select! {
-            _ = tokio::time::sleep(Duration::from_secs(TIMER_WAIT_SECS)) => (),
+            _ = tokio::time::sleep(wait) => (),
             _ = cancel_rx => return,
         };
 
-        if let Err(err) = send_reminder(http, ReminderVariant::Time).await {
+        // with multiple processes, the timer may have been cancelled by another one
+        let db = data.db_for(guild_id);
+        match db
+            .request(GetSplashReminderTimer {
+                message_id: message,
+            })
+            .await
+        {
+            Ok(Ok(None)) => return,
+            Ok(Ok(Some(_))) => (),
+            Ok(Err(err)) => error!("Failed to check splash reminder state: {err:#}"),
+            Err(err) => error!("Failed to check splash reminder state: {err:#}"),
+        }
+
+        if let Err(err) = send_reminder(http, &data, guild_id, message, variant).await {
             error!("Failed to send splash reminder: {err:#}");
         };
+
+        match db
+            .request(SetSplashReminderTimer {
+                message_id: message,
+                deadline: None,
+            })
+            .await
+        {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => error!("Failed to clear splash reminder timer: {err:#}"),
+            Err(err) => error!("Failed to clear splash reminder timer: {err:#}"),
+        }
     });
 }
@@ -1,127 +1,463 @@
-use std::sync::LazyLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
 
 use anyhow::Result;
 use poise::serenity_prelude::{
-    GetMessages, Http, Mentionable as _, Message, MessageId, Timestamp, UserId,
+    Error as SerenityError, GetMessages, GuildId, Http, HttpError, Mentionable as _, Message,
+    MessageId, RoleId, Route, Timestamp, UserId,
 };
 use regex::Regex;
+use tracing::warn;
 
-use crate::config::{SPLASH_PING_ROLE, SPLASHES_CHANNEL};
+use crate::db::DbHandle;
+use crate::shared::db::{
+    GetGuildConfig, GetSplashImageRequired, GetSplashIngestion, GetSplashOverrides,
+    GetSplashStoreCoverage, GetStoredSplashes, SetSplashStoreCoverage, StoreSplashes,
+};
+use crate::shared::types::{GuildConfig, SplashIngestion, StoredSplash};
+use crate::splashes::{ingestion::SplasherResolver, store};
 
 // only compile regex once per program execution
 static HUB_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?i)(?:dungeon|d|dung)?[\s*_~`|]*hub[^\d\n]*\d+").unwrap());
+    LazyLock::new(|| Regex::new(r"(?i)(dungeon|d|dung)?[\s*_~`|]*hub[^\d\n]*(\d+)").unwrap());
+
+/// How often a rate-limited batch is retried before giving up
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+/// Wait time used if Discord doesn't tell us how long to wait, doubled on each retry
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Discord's epoch in unix milliseconds, which snowflakes count from
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// Snapshot of a running scan, passed to the progress callback after every batch
+#[derive(Debug, Clone, Copy)]
+pub struct FetchProgress {
+    pub scanned_messages: usize,
+    pub splashes: usize,
+    /// creation time of the oldest message scanned so far
+    pub reached: Option<Timestamp>,
+}
 
-/// Helper to fetch splashes. Stores previously fetched splash messages internally on the instance
-/// for future use. (for example when requesting the latest splash for a set of splashers)
+/// A counted splash along with who performed the splash
+#[derive(Debug, Clone)]
+pub struct Splash {
+    pub message_id: MessageId,
+    pub timestamp: Timestamp,
+    /// usually the message's author, unless splashes are relayed (see [`SplashIngestion`])
+    pub splasher: UserId,
+    pub hub: Option<String>,
+    /// jump link to the splash message
+    pub link: String,
+}
+
+/// Helper to fetch splashes. Splashes are read from the database, where new splashes are stored as
+/// they arrive (see [`crate::splashes::store`]). Parts of the splashes channel that haven't been
+/// stored yet (e.g. due to downtime) are scanned and backfilled on demand. Loaded splashes are kept
+/// on the instance for future use. (for example when requesting the latest splash for a set of
+/// splashers)
 pub struct FetchSplashes {
-    splash_messages: Vec<Message>,
-    last_id: Option<MessageId>,
-    /// whether the beginning of the channel has been reached
-    done: bool,
+    /// newest first, from `loaded_since` up to the time they were loaded
+    splashes: Vec<Splash>,
+    loaded_since: Option<Timestamp>,
+    /// splashes channel and ping role of the guild
+    guild_config: GuildConfig,
+    /// whether splashes without an image are skipped
+    require_image: bool,
+    /// messages manually counted or uncounted by staff, taking priority over all other rules
+    overrides: HashMap<MessageId, bool>,
+    resolver: SplasherResolver,
+    scanned_messages: usize,
+    stored_splashes: usize,
+    progress: Option<Box<dyn Fn(FetchProgress) + Send + Sync>>,
 }
 
 impl FetchSplashes {
     pub fn new() -> Self {
         Self {
-            splash_messages: Vec::new(),
-            last_id: None,
-            done: false,
+            splashes: Vec::new(),
+            loaded_since: None,
+            guild_config: GuildConfig::default(),
+            require_image: false,
+            overrides: HashMap::new(),
+            resolver: SplasherResolver::new(SplashIngestion::Author),
+            scanned_messages: 0,
+            stored_splashes: 0,
+            progress: None,
         }
     }
 
+    /// Applies the guild's splashes channel and the configured splash rules (image requirement,
+    /// overrides and ingestion mode)
+    pub async fn from_config(db: &DbHandle, guild_id: Option<GuildId>) -> Result<Self> {
+        let guild_config = db.request(GetGuildConfig { guild_id }).await??;
+        let require_image = db.request(GetSplashImageRequired).await??;
+        let overrides = db.request(GetSplashOverrides).await??;
+        let ingestion = db.request(GetSplashIngestion).await??;
+
+        Ok(Self::new()
+            .guild_config(guild_config)
+            .require_image(require_image)
+            .overrides(overrides)
+            .ingestion(ingestion))
+    }
+
+    /// Register a callback which is invoked after every scanned batch, e.g. to update a deferred
+    /// response during long backfills
+    pub fn on_progress(mut self, callback: impl Fn(FetchProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Fetch from the guild's splashes channel and detect its ping role
+    pub fn guild_config(mut self, guild_config: GuildConfig) -> Self {
+        self.guild_config = guild_config;
+        self
+    }
+
+    /// Only count splashes that include an image (see [`Self::has_image`])
+    pub fn require_image(mut self, require_image: bool) -> Self {
+        self.require_image = require_image;
+        self
+    }
+
+    /// Count or ignore specific messages regardless of their content (see
+    /// [`GetSplashOverrides`](crate::shared::db::GetSplashOverrides))
+    pub fn overrides(mut self, overrides: HashMap<MessageId, bool>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Determine splashers from the message content instead of the author (see [`SplashIngestion`])
+    pub fn ingestion(mut self, ingestion: SplashIngestion) -> Self {
+        self.resolver = SplasherResolver::new(ingestion);
+        self
+    }
+
     /// Fetches all splashes within the specified time window, returns slice of internal vector
     pub async fn splashes_during(
         &mut self,
         http: &Http,
+        db: &DbHandle,
         start: Timestamp,
         end: Timestamp,
-    ) -> Result<&[Message]> {
+    ) -> Result<&[Splash]> {
         if start > end {
             return Ok(&[]);
         }
 
-        while !self.done {
-            if let Some(id) = self.last_id
-                && id.created_at() < start
-            {
-                break;
-            }
-
-            self.fetch_more(http).await?;
+        if self.loaded_since.is_none_or(|loaded| loaded > start) {
+            self.load(http, db, start).await?;
         }
 
         // first index whose value is before the end of the timeframe
-        let end_index = self.splash_messages.partition_point(|m| m.timestamp > end);
+        let end_index = self.splashes.partition_point(|s| s.timestamp > end);
         // first index whose value is before the start of the timeframe
-        let start_index = self
-            .splash_messages
-            .partition_point(|m| m.timestamp >= start);
+        let start_index = self.splashes.partition_point(|s| s.timestamp >= start);
 
-        Ok(&self.splash_messages[end_index..start_index])
+        Ok(&self.splashes[end_index..start_index])
     }
 
-    /// Fetches splashes until one by specified user is found, then returns its timestamp (returns
-    /// None if no splash is found in the last 6 months)
-    pub async fn latest_splash(&mut self, http: &Http, user: UserId) -> Result<Option<Timestamp>> {
+    /// Returns the timestamp of the latest splash by the specified user (returns None if no splash
+    /// is found in the last 6 months)
+    pub async fn latest_splash(
+        &mut self,
+        http: &Http,
+        db: &DbHandle,
+        user: UserId,
+    ) -> Result<Option<Timestamp>> {
         let search_limit = Timestamp::from_unix_timestamp(
             (chrono::Utc::now() - chrono::Months::new(6)).timestamp(),
         )
         .unwrap();
 
-        if let Some(message) = self.splash_messages.iter().find(|m| m.author.id == user) {
-            return Ok(Some(message.timestamp));
+        Ok(self
+            .splashes_during(http, db, search_limit, Timestamp::now())
+            .await?
+            .iter()
+            .find(|s| s.splasher == user)
+            .map(|s| s.timestamp))
+    }
+
+    /// Stores all splashes sent since the channel was last covered (e.g. during downtime), without
+    /// loading anything. Returns the newest stored splash, if any arrived in the meantime.
+    pub async fn catch_up(&mut self, http: &Http, db: &DbHandle) -> Result<Option<StoredSplash>> {
+        let channel = self.guild_config.splashes_channel;
+
+        // channels that were never scanned are backfilled on demand once they are needed
+        let Some((_, newest)) = db.request(GetSplashStoreCoverage { channel }).await?? else {
+            return Ok(None);
+        };
+
+        self.backfill(http, db, newest).await?;
+
+        Ok(db
+            .request(GetStoredSplashes {
+                channel,
+                start: newest,
+                end: i64::MAX,
+            })
+            .await??
+            .into_iter()
+            .next())
+    }
+
+    /// Loads all splashes since `start` from the database, after backfilling missing parts
+    async fn load(&mut self, http: &Http, db: &DbHandle, start: Timestamp) -> Result<()> {
+        let channel = self.guild_config.splashes_channel;
+
+        self.backfill(http, db, start.unix_timestamp()).await?;
+        self.store_counted_overrides(http, db, start).await?;
+
+        let stored = db
+            .request(GetStoredSplashes {
+                channel,
+                start: start.unix_timestamp(),
+                end: i64::MAX,
+            })
+            .await??;
+
+        self.splashes.clear();
+        for splash in stored {
+            let counted = self
+                .overrides
+                .get(&splash.message_id)
+                .copied()
+                .unwrap_or(!self.require_image || splash.has_image);
+            if !counted {
+                continue;
+            }
+
+            // relayed splashes whose splasher can't be determined can't be attributed to anyone
+            if let Some(splasher) = self.resolver.resolve_stored(http, &splash).await? {
+                self.splashes.push(Splash {
+                    message_id: splash.message_id,
+                    timestamp: Timestamp::from_unix_timestamp(splash.timestamp)?,
+                    splasher,
+                    link: message_link(&splash),
+                    hub: splash.hub,
+                });
+            }
         }
+        self.loaded_since = Some(start);
 
-        while !self.done {
-            if let Some(id) = self.last_id
-                && id.created_at() < search_limit
-            {
-                break;
+        Ok(())
+    }
+
+    /// Makes sure that every splash since `start` is stored, scanning the parts of the channel not
+    /// covered by the database
+    async fn backfill(&mut self, http: &Http, db: &DbHandle, start: i64) -> Result<()> {
+        let channel = self.guild_config.splashes_channel;
+        let scan_start = Timestamp::now().unix_timestamp();
+
+        let coverage = db.request(GetSplashStoreCoverage { channel }).await??;
+        let oldest = match coverage {
+            // everything newer than the covered range has been stored as it arrived
+            Some((oldest, newest)) if store::live_since().is_some_and(|live| newest >= live) => {
+                oldest
+            }
+            // fill the gap between the covered range and now
+            Some((oldest, newest)) => {
+                self.scan(http, db, None, newest).await?;
+                oldest
             }
+            None => self.scan(http, db, None, start).await?,
+        };
+
+        let oldest = if start < oldest {
+            self.scan(http, db, Some(snowflake_at(oldest)), start)
+                .await?
+        } else {
+            oldest
+        };
+
+        db.request(SetSplashStoreCoverage {
+            channel,
+            oldest,
+            newest: scan_start,
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Scans the channel backwards from `before` (or the latest message) until reaching messages
+    /// older than `until`, storing all splashes. Returns the time up to which the channel has been
+    /// scanned.
+    async fn scan(
+        &mut self,
+        http: &Http,
+        db: &DbHandle,
+        mut before: Option<MessageId>,
+        until: i64,
+    ) -> Result<i64> {
+        loop {
+            let batch = self.fetch_batch(http, before).await?;
+            self.scanned_messages += batch.len();
+
+            let Some(last) = batch.last() else {
+                // reached the beginning of the channel
+                return Ok(0);
+            };
+            let reached = last.timestamp;
+            before = Some(last.id);
 
-            let batch = self.fetch_more(http).await?;
+            let splashes: Vec<_> = batch
+                .iter()
+                .filter(|message| {
+                    self.overrides.get(&message.id).copied().unwrap_or_else(|| {
+                        Self::is_splash(message, self.guild_config.splash_ping_role)
+                    })
+                })
+                .map(store::stored_splash)
+                .collect();
+            self.stored_splashes += splashes.len();
+            db.request(StoreSplashes { splashes }).await??;
 
-            if let Some(message) = batch.iter().find(|m| m.author.id == user) {
-                return Ok(Some(message.timestamp));
+            if let Some(callback) = &self.progress {
+                callback(FetchProgress {
+                    scanned_messages: self.scanned_messages,
+                    splashes: self.stored_splashes,
+                    reached: Some(reached),
+                });
             }
-        }
 
-        Ok(None)
+            if reached.unix_timestamp() < until {
+                return Ok(reached.unix_timestamp());
+            }
+        }
     }
 
-    /// Fetch an additional batch of up to 100 messages and append it to the internal vector.
-    /// Returns a reference to the new batch.
-    async fn fetch_more(&mut self, http: &Http) -> Result<&[Message]> {
-        let mut builder = GetMessages::new().limit(100);
-        if let Some(id) = self.last_id {
-            builder = builder.before(id);
+    /// Messages counted by staff are stored regardless of their content, but may have been
+    /// overridden after they were sent (and therefore missed when they arrived)
+    async fn store_counted_overrides(
+        &self,
+        http: &Http,
+        db: &DbHandle,
+        start: Timestamp,
+    ) -> Result<()> {
+        let channel = self.guild_config.splashes_channel;
+
+        let stored: HashSet<_> = db
+            .request(GetStoredSplashes {
+                channel,
+                start: start.unix_timestamp(),
+                end: i64::MAX,
+            })
+            .await??
+            .iter()
+            .map(|s| s.message_id)
+            .collect();
+
+        let mut splashes = Vec::new();
+        for (&message_id, _) in self
+            .overrides
+            .iter()
+            .filter(|(id, counted)| **counted && id.created_at() >= start && !stored.contains(id))
+        {
+            match channel.message(http, message_id).await {
+                Ok(message) => splashes.push(store::stored_splash(&message)),
+                // most likely in another channel or deleted
+                Err(err) => warn!("Failed to fetch manually counted splash {message_id}: {err}"),
+            }
         }
 
-        let batch = SPLASHES_CHANNEL.messages(http, builder).await?;
+        if !splashes.is_empty() {
+            db.request(StoreSplashes { splashes }).await??;
+        }
 
-        let old_len = self.splash_messages.len();
+        Ok(())
+    }
 
-        if let Some(message) = batch.last() {
-            self.last_id = Some(message.id);
-        } else {
-            self.done = true;
-        };
+    /// Fetches the next batch of up to 100 messages, waiting out rate limits instead of failing the
+    /// entire scan
+    async fn fetch_batch(&self, http: &Http, before: Option<MessageId>) -> Result<Vec<Message>> {
+        let mut retries = 0;
 
-        self.splash_messages
-            .extend(batch.into_iter().filter(Self::is_splash));
+        loop {
+            let mut builder = GetMessages::new().limit(100);
+            if let Some(id) = before {
+                builder = builder.before(id);
+            }
 
-        if old_len < self.splash_messages.len() {
-            Ok(&self.splash_messages[old_len..])
-        } else {
-            Ok(&[])
+            match self
+                .guild_config
+                .splashes_channel
+                .messages(http, builder)
+                .await
+            {
+                Ok(batch) => return Ok(batch),
+                Err(SerenityError::Http(HttpError::UnsuccessfulRequest(response)))
+                    if response.status_code.as_u16() == 429 && retries < MAX_RATE_LIMIT_RETRIES =>
+                {
+                    let wait = match self.retry_after(http).await {
+                        Some(wait) => wait,
+                        None => DEFAULT_RETRY_AFTER * 2u32.pow(retries),
+                    };
+                    warn!("Rate limited while fetching splashes, retrying in {wait:?}");
+                    tokio::time::sleep(wait).await;
+                    retries += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
     }
 
-    pub fn is_splash(message: &Message) -> bool {
+    /// How long Discord asked to wait before fetching messages of the splashes channel again.
+    /// serenity doesn't expose the headers of failed requests, but its ratelimiter tracks the
+    /// `Retry-After` of the channel's bucket.
+    async fn retry_after(&self, http: &Http) -> Option<Duration> {
+        let bucket = Route::ChannelMessages {
+            channel_id: self.guild_config.splashes_channel,
+        }
+        .ratelimiting_bucket();
+
+        let routes = http.ratelimiter.as_ref()?.routes();
+        let ratelimit = Arc::clone(routes.read().await.get(&bucket)?);
+        let reset_after = ratelimit.lock().await.reset_after();
+        reset_after.filter(|wait| !wait.is_zero())
+    }
+
+    pub fn is_splash(message: &Message, ping_role: RoleId) -> bool {
         HUB_REGEX.is_match(&message.content)
-            && message
-                .content
-                .contains(&SPLASH_PING_ROLE.mention().to_string())
+            && message.content.contains(&ping_role.mention().to_string())
     }
+
+    /// Hub the splash took place in, e.g. `Dungeon Hub 3`
+    pub fn hub(message: &Message) -> Option<String> {
+        let captures = HUB_REGEX.captures(&message.content)?;
+        let prefix = if captures.get(1).is_some() {
+            "Dungeon "
+        } else {
+            ""
+        };
+        Some(format!("{prefix}Hub {}", &captures[2]))
+    }
+
+    /// Whether the message includes a screenshot, either as an attachment or as an embedded image
+    pub fn has_image(message: &Message) -> bool {
+        !message.attachments.is_empty()
+            || message
+                .embeds
+                .iter()
+                .any(|e| e.image.is_some() || e.thumbnail.is_some())
+    }
+}
+
+/// Smallest message ID that could have been created at the unix timestamp, e.g. for pagination
+pub fn snowflake_at(timestamp: i64) -> MessageId {
+    let millis = (timestamp * 1000 - DISCORD_EPOCH_MS).max(1);
+    MessageId::new((millis as u64) << 22)
+}
+
+fn message_link(splash: &StoredSplash) -> String {
+    let guild = splash
+        .guild_id
+        .map_or_else(|| "@me".to_string(), |guild_id| guild_id.to_string());
+    format!(
+        "https://discord.com/channels/{guild}/{}/{}",
+        splash.channel, splash.message_id
+    )
 }
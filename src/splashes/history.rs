@@ -0,0 +1,38 @@
+use anyhow::Result;
+use poise::serenity_prelude::{GuildId, Http, Timestamp, UserId};
+
+use crate::db::DbHandle;
+use crate::splashes::fetch::FetchSplashes;
+
+#[derive(Debug, Clone)]
+pub struct SplashHistoryEntry {
+    pub timestamp: Timestamp,
+    pub hub: Option<String>,
+    pub link: String,
+}
+
+/// Every counted splash by the user since `start`, newest first
+pub async fn splash_history(
+    http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
+    user: UserId,
+    start: Timestamp,
+) -> Result<Vec<SplashHistoryEntry>> {
+    // apply the same rules as the splash list, so that the history matches the counts
+    let mut fetcher = FetchSplashes::from_config(db, guild_id).await?;
+
+    let entries = fetcher
+        .splashes_during(http, db, start, Timestamp::now())
+        .await?
+        .iter()
+        .filter(|s| s.splasher == user)
+        .map(|s| SplashHistoryEntry {
+            timestamp: s.timestamp,
+            hub: s.hub.clone(),
+            link: s.link.clone(),
+        })
+        .collect();
+
+    Ok(entries)
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashSet, time::Duration};
+
+use anyhow::Result;
+use poise::serenity_prelude::{
+    Context as SerenityContext, CreateAllowedMentions, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, Mentionable as _, MessageFlags,
+    Timestamp, UserId, colours::css::WARNING,
+};
+use tracing::info;
+
+use crate::db::DbHandle;
+use crate::scheduler::MAX_WAIT;
+use crate::shared::{
+    db::{
+        GetGuildConfig, GetSplashInactivityConfig, GetSplashInactivityOptOuts, GetSplasherAways,
+        SetSplashInactivityAlerted,
+    },
+    members, shard,
+    time::{TimestampStyle::LongDate, discord_timestamp},
+    types::SplashInactivityConfig,
+};
+use crate::splashes::lastsplashed;
+
+/// Minimum time between two digests
+pub const ALERT_INTERVAL_SECS: i64 = 7 * 24 * 60 * 60;
+/// Splashers listed in a digest, so that it stays within Discord's message length limit
+const MAX_LISTED: usize = 50;
+
+#[derive(Debug)]
+struct InactiveSplasher {
+    user: UserId,
+    /// `None` if the splasher hasn't splashed within the searched six months
+    last_splash: Option<Timestamp>,
+}
+
+/// Posts a digest of splashers who haven't splashed within the configured number of days to the
+/// staff channel, at most once a week, returning how long to wait until the next check
+pub async fn post_if_due(db: &DbHandle) -> Result<Duration> {
+    let Some(config) = db.request(GetSplashInactivityConfig).await?? else {
+        return Ok(MAX_WAIT);
+    };
+    // the splasher role's members can only be requested over the guild's shard
+    let Some(ctx) = shard::context_for(config.guild_id) else {
+        return Ok(MAX_WAIT);
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    if let Some(last_alert) = config.last_alert
+        && now - last_alert < ALERT_INTERVAL_SECS
+    {
+        return Ok(Duration::from_secs(
+            (last_alert + ALERT_INTERVAL_SECS - now).unsigned_abs(),
+        ));
+    }
+
+    let inactive = inactive_splashers(&ctx, db, &config).await?;
+
+    // an empty digest is skipped, but still counts as a check to avoid rescanning every hour
+    if !inactive.is_empty() {
+        send_digest(&ctx, &config, &inactive).await?;
+        info!(
+            "Reported {} inactive splasher(s) in channel {}",
+            inactive.len(),
+            config.channel
+        );
+    }
+
+    db.request(SetSplashInactivityAlerted { at: now }).await??;
+
+    Ok(Duration::from_secs(ALERT_INTERVAL_SECS.unsigned_abs()))
+}
+
+/// Splashers without a splash within the threshold, excluding opted-out and currently away ones.
+/// Ordered by their latest splash, longest inactive first.
+async fn inactive_splashers(
+    ctx: &SerenityContext,
+    db: &DbHandle,
+    config: &SplashInactivityConfig,
+) -> Result<Vec<InactiveSplasher>> {
+    let guild_id = Some(config.guild_id);
+    let splasher_role = db
+        .request(GetGuildConfig { guild_id })
+        .await??
+        .splasher_role;
+    let splashers = members::role_members(ctx, config.guild_id, splasher_role).await?;
+
+    let now = chrono::Utc::now().timestamp();
+    let opt_outs: HashSet<UserId> = db
+        .request(GetSplashInactivityOptOuts)
+        .await??
+        .into_iter()
+        .collect();
+    let away: HashSet<UserId> = db
+        .request(GetSplasherAways { now })
+        .await??
+        .into_iter()
+        .filter(|away| away.is_active(now))
+        .map(|away| away.user)
+        .collect();
+
+    let candidates: Vec<UserId> = splashers
+        .into_iter()
+        .filter(|user| !opt_outs.contains(user) && !away.contains(user))
+        .collect();
+
+    let latest = lastsplashed::latest_splash_batch(&ctx.http, db, guild_id, &candidates).await?;
+
+    let cutoff = now - i64::from(config.threshold_days) * 24 * 60 * 60;
+    let mut inactive: Vec<InactiveSplasher> = candidates
+        .into_iter()
+        .map(|user| InactiveSplasher {
+            user,
+            last_splash: latest.get(&user).copied(),
+        })
+        .filter(|splasher| {
+            splasher
+                .last_splash
+                .is_none_or(|timestamp| timestamp.unix_timestamp() < cutoff)
+        })
+        .collect();
+    // `None` sorts first
+    inactive.sort_by_key(|splasher| splasher.last_splash);
+
+    Ok(inactive)
+}
+
+async fn send_digest(
+    ctx: &SerenityContext,
+    config: &SplashInactivityConfig,
+    inactive: &[InactiveSplasher],
+) -> Result<()> {
+    let mut list = inactive
+        .iter()
+        .take(MAX_LISTED)
+        .map(|splasher| match splasher.last_splash {
+            Some(timestamp) => format!(
+                "- {}: last splashed on {}",
+                splasher.user.mention(),
+                discord_timestamp(timestamp, LongDate)
+            ),
+            None => format!(
+                "- {}: no splash in the last six months",
+                splasher.user.mention()
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if inactive.len() > MAX_LISTED {
+        list.push_str(&format!("\n-# ...and {} more", inactive.len() - MAX_LISTED));
+    }
+
+    let text = CreateTextDisplay::new(format!(
+        "## Inactive Splashers
+**{}** splashers haven't splashed in the last {} days:
+{list}
+-# Splashers who are marked as away or opted out via `/splashes inactivity opt-out` aren't listed.",
+        inactive.len(),
+        config.threshold_days,
+    ));
+
+    config
+        .channel
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                        .accent_color(WARNING),
+                )]),
+        )
+        .await?;
+
+    Ok(())
+}
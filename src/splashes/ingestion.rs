@@ -0,0 +1,111 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use anyhow::Result;
+use poise::serenity_prelude::{Http, Message, UserId};
+use regex::Regex;
+use tracing::warn;
+
+use crate::shared::types::{SplashIngestion, StoredSplash};
+
+// e.g. "Hub 5 splash by Steve", matching Discord's username rules
+static BY_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bby\s+@?([a-z0-9_.]{2,32})").unwrap());
+
+/// Determines who performed a splash, caching name lookups across messages
+pub struct SplasherResolver {
+    ingestion: SplashIngestion,
+    names: HashMap<String, Option<UserId>>,
+}
+
+impl SplasherResolver {
+    pub fn new(ingestion: SplashIngestion) -> Self {
+        Self {
+            ingestion,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Returns `None` if the splasher can't be determined from the message content
+    pub async fn resolve(&mut self, http: &Http, message: &Message) -> Result<Option<UserId>> {
+        self.resolve_parts(
+            http,
+            message.author.id,
+            mentioned_user(message),
+            named_user(message).as_deref(),
+        )
+        .await
+    }
+
+    /// Same as [`Self::resolve`], for splashes that are only available in their stored form
+    pub async fn resolve_stored(
+        &mut self,
+        http: &Http,
+        splash: &StoredSplash,
+    ) -> Result<Option<UserId>> {
+        self.resolve_parts(
+            http,
+            splash.author,
+            splash.mentioned,
+            splash.named.as_deref(),
+        )
+        .await
+    }
+
+    async fn resolve_parts(
+        &mut self,
+        http: &Http,
+        author: UserId,
+        mentioned: Option<UserId>,
+        named: Option<&str>,
+    ) -> Result<Option<UserId>> {
+        let SplashIngestion::Content { guild_id } = self.ingestion else {
+            return Ok(Some(author));
+        };
+
+        if let Some(user) = mentioned {
+            return Ok(Some(user));
+        }
+
+        let Some(name) = named else {
+            return Ok(None);
+        };
+
+        if let Some(cached) = self.names.get(name) {
+            return Ok(*cached);
+        }
+
+        let members = guild_id.search_members(http, name, None).await?;
+        let user = members
+            .iter()
+            .find(|m| {
+                m.user.name.eq_ignore_ascii_case(name)
+                    || m.nick
+                        .as_ref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name))
+                    || m.user
+                        .global_name
+                        .as_ref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+            .map(|m| m.user.id);
+
+        if user.is_none() {
+            warn!("Unable to resolve splasher '{name}' from relayed splash message");
+        }
+        self.names.insert(name.to_string(), user);
+
+        Ok(user)
+    }
+}
+
+/// First non-bot user mentioned in the message, taking priority over a named splasher
+pub fn mentioned_user(message: &Message) -> Option<UserId> {
+    message.mentions.iter().find(|u| !u.bot()).map(|u| u.id)
+}
+
+/// Splasher named in a "by <name>" pattern, lowercased
+pub fn named_user(message: &Message) -> Option<String> {
+    BY_NAME_REGEX
+        .captures(&message.content)
+        .map(|c| c[1].to_lowercase())
+}
@@ -1,26 +1,47 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use poise::serenity_prelude::{Http, Timestamp, UserId};
+use poise::serenity_prelude::{GuildId, Http, Timestamp, UserId};
 
+use crate::db::DbHandle;
+use crate::shared::db::{GetGuildConfig, GetSplashIngestion, GetSplashOverrides};
 use crate::splashes::fetch::FetchSplashes;
 
-pub async fn latest_splash(http: &Http, user: UserId) -> Result<Option<Timestamp>> {
-    let mut splashes = FetchSplashes::new();
+/// Unlike the splash list, any splash counts as activity, regardless of the image requirement
+async fn fetcher(db: &DbHandle, guild_id: Option<GuildId>) -> Result<FetchSplashes> {
+    let guild_config = db.request(GetGuildConfig { guild_id }).await??;
+    let overrides = db.request(GetSplashOverrides).await??;
+    let ingestion = db.request(GetSplashIngestion).await??;
 
-    splashes.latest_splash(http, user).await
+    Ok(FetchSplashes::new()
+        .guild_config(guild_config)
+        .overrides(overrides)
+        .ingestion(ingestion))
+}
+
+pub async fn latest_splash(
+    http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
+    user: UserId,
+) -> Result<Option<Timestamp>> {
+    let mut splashes = fetcher(db, guild_id).await?;
+
+    splashes.latest_splash(http, db, user).await
 }
 
 pub async fn latest_splash_batch(
     http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
     users: &[UserId],
 ) -> Result<HashMap<UserId, Timestamp>> {
-    let mut splashes = FetchSplashes::new();
+    let mut splashes = fetcher(db, guild_id).await?;
 
     let mut users_map = HashMap::new();
 
     for user in users {
-        if let Some(timestamp) = splashes.latest_splash(http, *user).await? {
+        if let Some(timestamp) = splashes.latest_splash(http, db, *user).await? {
             users_map.insert(*user, timestamp);
         }
     }
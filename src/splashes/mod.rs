@@ -1,3 +1,12 @@
 pub mod fetch;
+pub mod history;
+pub mod inactivity;
+pub mod ingestion;
 pub mod lastsplashed;
+pub mod onboarding;
+pub mod palette;
+pub mod reconcile;
+pub mod rollover;
 pub mod splashlist;
+pub mod store;
+pub mod ty_tracking;
@@ -0,0 +1,318 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use either::Either;
+use poise::serenity_prelude::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateAllowedMentions,
+    CreateButton, CreateComponent, CreateContainer, CreateContainerComponent,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateSection,
+    CreateSectionAccessory, CreateSectionComponent, CreateTextDisplay, GuildId,
+    GuildMemberUpdateEvent, Mentionable as _, MessageFlags, ModalInteraction, UserId,
+    colours::css::{POSITIVE, WARNING},
+};
+use tracing::{info, warn};
+
+use crate::error::UserError;
+use crate::shared::{
+    BotData,
+    db::{
+        AcknowledgeOnboardingItem, GetSplashReminderConfig, GetSplasherOnboarding,
+        StartSplasherOnboarding,
+    },
+    interaction::custom_id::{self, Namespace, Parts},
+    types::{GuildConfig, SplashReminderConfig, SplasherOnboarding},
+};
+use crate::splash_reminder::interaction::SNOOZE;
+
+/// Items of the checklist, identified in the database by their key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingItem {
+    Format,
+    Hubs,
+    Reminder,
+}
+
+impl OnboardingItem {
+    pub const ALL: [OnboardingItem; 3] = [
+        OnboardingItem::Format,
+        OnboardingItem::Hubs,
+        OnboardingItem::Reminder,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            OnboardingItem::Format => "format",
+            OnboardingItem::Hubs => "hubs",
+            OnboardingItem::Reminder => "reminder",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.key() == key)
+    }
+
+    fn text(self, config: &GuildConfig, reminder: &SplashReminderConfig) -> String {
+        match self {
+            OnboardingItem::Format => format!(
+                "### Formatting Splash Messages
+Post splashes in {} and ping {} in the message, otherwise the splash isn't counted. Attach a screenshot of the hub if asked to.",
+                config.splashes_channel.mention(),
+                config.splash_ping_role.mention()
+            ),
+            OnboardingItem::Hubs => "### Hub Conventions
+Always include the hub number, e.g. `Hub 14` or `DH 3` for dungeon hubs, and stay in the hub you announced until the splash is over, so that nobody ends up in the wrong lobby."
+                .to_string(),
+            OnboardingItem::Reminder => format!(
+                "### Splash Reminders
+If nobody splashes for {} (or the latest splash gets a lot of reactions), the bot reminds splashers. You can snooze the reminder for {} minutes from the reminder itself.",
+                reminder.delay_text(),
+                SNOOZE.as_secs() / 60
+            ),
+        }
+    }
+}
+
+impl SplasherOnboarding {
+    pub fn acknowledged_count(&self) -> usize {
+        OnboardingItem::ALL
+            .iter()
+            .filter(|item| self.acknowledged.contains(item.key()))
+            .count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.acknowledged_count() == OnboardingItem::ALL.len()
+    }
+}
+
+/// Sends the checklist to members with the splasher role, once per member. The member cache isn't
+/// filled, so whether the role is new is decided by whether the member was onboarded before.
+pub async fn member_updated(ctx: &SerenityContext, event: &GuildMemberUpdateEvent) -> Result<()> {
+    if event.user.bot() {
+        return Ok(());
+    }
+
+    let data = ctx.data::<BotData>();
+    let config = data.guild_config(Some(event.guild_id)).await?;
+    if !event.roles.contains(&config.splasher_role) {
+        return Ok(());
+    }
+
+    let db = data.db_for(Some(event.guild_id));
+    if db
+        .request(GetSplasherOnboarding {
+            user: event.user.id,
+        })
+        .await??
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    // the insert is ignored if a concurrent member update already started the onboarding
+    let started_at = Utc::now().timestamp();
+    if !db
+        .request(StartSplasherOnboarding {
+            user: event.user.id,
+            started_at,
+        })
+        .await??
+    {
+        return Ok(());
+    }
+
+    info!("Sending the onboarding checklist to {}", event.user.name);
+
+    let onboarding = SplasherOnboarding {
+        user: event.user.id,
+        started_at,
+        acknowledged: Default::default(),
+    };
+    let reminder = db.request(GetSplashReminderConfig).await??;
+    let components = checklist(&onboarding, Some(event.guild_id), &config, &reminder);
+
+    let dm = event
+        .user
+        .id
+        .direct_message(
+            ctx.http(),
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .components(components.clone()),
+        )
+        .await;
+
+    // members may have DMs from server members disabled
+    if let Err(err) = dm {
+        warn!(
+            "Failed to DM the onboarding checklist to {}, posting it in the splashes channel: {err}",
+            event.user.name
+        );
+        let mention = CreateComponent::TextDisplay(CreateTextDisplay::new(
+            event.user.id.mention().to_string(),
+        ));
+        config
+            .splashes_channel
+            .send_message(
+                ctx.http(),
+                CreateMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new().users(vec![event.user.id]))
+                    .components([vec![mention], components].concat()),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// The guild is part of the button IDs, as the checklist may be sent via DM
+fn checklist(
+    onboarding: &SplasherOnboarding,
+    guild_id: Option<GuildId>,
+    config: &GuildConfig,
+    reminder: &SplashReminderConfig,
+) -> Vec<CreateComponent<'static>> {
+    let guild = guild_id.map_or(0, GuildId::get);
+
+    let mut components = vec![CreateContainerComponent::TextDisplay(CreateTextDisplay::new(
+        "# Welcome to the Splashers!
+Please read through the following and confirm each point, so that splashes run smoothly for everyone.",
+    ))];
+
+    for item in OnboardingItem::ALL {
+        let acknowledged = onboarding.acknowledged.contains(item.key());
+        let button = CreateButton::new(custom_id::encode(
+            Namespace::Onboarding,
+            &[&"ack", &item.key(), &onboarding.user, &guild],
+        ))
+        .label(if acknowledged { "Done" } else { "Got it" })
+        .emoji(if acknowledged { '✅' } else { '👍' })
+        .style(if acknowledged {
+            ButtonStyle::Secondary
+        } else {
+            ButtonStyle::Success
+        })
+        .disabled(acknowledged);
+
+        components.push(CreateContainerComponent::Section(CreateSection::new(
+            vec![CreateSectionComponent::TextDisplay(CreateTextDisplay::new(
+                item.text(config, reminder),
+            ))],
+            CreateSectionAccessory::Button(button),
+        )));
+    }
+
+    let footer = if onboarding.is_complete() {
+        "-# All done, happy splashing!".to_string()
+    } else {
+        format!(
+            "-# {}/{} confirmed",
+            onboarding.acknowledged_count(),
+            OnboardingItem::ALL.len()
+        )
+    };
+    components.push(CreateContainerComponent::TextDisplay(
+        CreateTextDisplay::new(footer),
+    ));
+
+    let accent = if onboarding.is_complete() {
+        POSITIVE
+    } else {
+        WARNING
+    };
+    vec![CreateComponent::Container(
+        CreateContainer::new(components).accent_color(accent),
+    )]
+}
+
+pub async fn handle_interaction(
+    ctx: &SerenityContext,
+    interaction: Either<&ComponentInteraction, &ModalInteraction>,
+    mut action: Parts<'_>,
+) -> Result<()> {
+    let Either::Left(interaction) = interaction else {
+        return Err(anyhow!("Invalid interaction: Unexpected modal"));
+    };
+    info!(
+        "{} triggered component interaction: '{}'",
+        interaction.user.name, interaction.data.custom_id
+    );
+
+    match action.next().unwrap_or_default() {
+        "ack" => {
+            let item = OnboardingItem::from_key(action.next_required("item")?)
+                .ok_or_else(|| anyhow!("Invalid interaction: Unknown onboarding item"))?;
+            let user = UserId::new(action.next_parsed("user")?);
+            let guild = action.next_parsed::<u64>("guild")?;
+            acknowledge(
+                ctx,
+                interaction,
+                item,
+                user,
+                (guild != 0).then(|| GuildId::new(guild)),
+            )
+            .await
+        }
+        _ => Err(anyhow!("Invalid interaction: Unknown action")),
+    }
+}
+
+async fn acknowledge(
+    ctx: &SerenityContext,
+    interaction: &ComponentInteraction,
+    item: OnboardingItem,
+    user: UserId,
+    guild_id: Option<GuildId>,
+) -> Result<()> {
+    // the fallback in the splashes channel is visible to everyone
+    if interaction.user.id != user {
+        bail!(UserError(anyhow!(
+            "This checklist belongs to {}",
+            user.mention()
+        )));
+    }
+
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(guild_id);
+    db.request(AcknowledgeOnboardingItem {
+        user,
+        item: item.key(),
+        acknowledged_at: Utc::now().timestamp(),
+    })
+    .await??;
+
+    let Some(onboarding) = db.request(GetSplasherOnboarding { user }).await?? else {
+        bail!(UserError(anyhow!(
+            "Your onboarding was reset, please ask staff for help"
+        )));
+    };
+    let config = data.guild_config(guild_id).await?;
+    let reminder = db.request(GetSplashReminderConfig).await??;
+
+    let components = if interaction.guild_id.is_some() {
+        // keep the mention of the fallback message in the splashes channel
+        let mention =
+            CreateComponent::TextDisplay(CreateTextDisplay::new(user.mention().to_string()));
+        [
+            vec![mention],
+            checklist(&onboarding, guild_id, &config, &reminder),
+        ]
+        .concat()
+    } else {
+        checklist(&onboarding, guild_id, &config, &reminder)
+    };
+
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .flags(MessageFlags::IS_COMPONENTS_V2)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .components(components),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Colors and marker emojis that highlight the top splashers, shared by the charts and every list
+//! referring to them, so that both always match
+
+/// Appearance of a single highlighted rank
+#[derive(Debug, Clone, Copy)]
+pub struct RankMarker {
+    pub rgb: (u8, u8, u8),
+    /// emoji of the same color, shown next to the splasher in lists
+    pub emoji: &'static str,
+    /// label of the rank in chart legends
+    pub label: &'static str,
+}
+
+/// Colors match Discord's rendering of the emojis
+const MARKERS: [RankMarker; 5] = [
+    RankMarker {
+        rgb: (221, 46, 68),
+        emoji: "🔴",
+        label: "#1",
+    },
+    RankMarker {
+        rgb: (120, 177, 89),
+        emoji: "🟢",
+        label: "#2",
+    },
+    RankMarker {
+        rgb: (85, 172, 238),
+        emoji: "🔵",
+        label: "#3",
+    },
+    RankMarker {
+        rgb: (253, 203, 88),
+        emoji: "🟡",
+        label: "#4",
+    },
+    RankMarker {
+        rgb: (170, 142, 214),
+        emoji: "🟣",
+        label: "#5",
+    },
+];
+
+/// How many of the top splashers are highlighted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TopSplashers {
+    #[default]
+    Three,
+    Five,
+}
+
+impl TopSplashers {
+    pub fn count(self) -> usize {
+        match self {
+            TopSplashers::Three => 3,
+            TopSplashers::Five => 5,
+        }
+    }
+
+    /// Markers of the highlighted ranks, best first
+    pub fn markers(self) -> &'static [RankMarker] {
+        &MARKERS[..self.count()]
+    }
+
+    /// Marker of the splasher at the (zero-based) rank, if it is highlighted
+    pub fn marker(self, rank: usize) -> Option<&'static RankMarker> {
+        self.markers().get(rank)
+    }
+}
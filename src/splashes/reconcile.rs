@@ -0,0 +1,170 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+use anyhow::Result;
+use poise::serenity_prelude::{
+    Context as SerenityContext, GenericChannelId, GetMessages, GuildId, Http, Message, Timestamp,
+};
+use tracing::{error, info};
+
+use crate::config::MANUAL_ROLE_CHANNEL;
+use crate::role::applications;
+use crate::shared::{BotData, db::GetSplashStoreCoverage, task::spawn_background};
+use crate::splash_reminder::event::register_splash;
+use crate::splashes::{
+    fetch::{FetchSplashes, snowflake_at},
+    store, ty_tracking,
+};
+
+/// Upper bound of messages replayed per channel, as only a short downtime is worth catching up on
+const MAX_REPLAYED_MESSAGES: usize = 500;
+
+/// Only the first `Ready` of each shard follows downtime, later ones are reconnects that
+/// `backfill` covers lazily
+static RECONCILED_GUILDS: LazyLock<Mutex<HashSet<GuildId>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Messages posted while the bot was offline never reached the event handler. Replays them once
+/// after startup, so that the stored splashes, the reminder timer, thank-yous and manual role
+/// applications reflect them without waiting for someone to request the splash list.
+pub fn start_reconciliation(ctx: &SerenityContext, guilds: Vec<GuildId>) {
+    let guilds: Vec<GuildId> = {
+        let mut reconciled = RECONCILED_GUILDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        guilds
+            .into_iter()
+            .filter(|guild_id| reconciled.insert(*guild_id))
+            .collect()
+    };
+    if guilds.is_empty() {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    spawn_background("splash reconciliation", Arc::clone(&ctx.http), async move {
+        // unconfigured guilds share the default splashes channel, which only needs one scan
+        let mut reconciled_channels = HashSet::new();
+
+        for guild_id in guilds {
+            if let Err(err) = reconcile_guild(&ctx, guild_id, &mut reconciled_channels).await {
+                error!("Failed to reconcile missed splashes of guild {guild_id}: {err:#}");
+            }
+        }
+    });
+}
+
+async fn reconcile_guild(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    reconciled_channels: &mut HashSet<u64>,
+) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    let db = data.db_for(Some(guild_id));
+    let config = data.guild_config(Some(guild_id)).await?;
+
+    let channel = config.splashes_channel;
+    if !reconciled_channels.insert(channel.get()) {
+        return Ok(());
+    }
+
+    // channels that were never scanned are backfilled on demand once they are needed
+    let Some((_, covered_until)) = db.request(GetSplashStoreCoverage { channel }).await?? else {
+        return Ok(());
+    };
+
+    let mut fetcher = FetchSplashes::from_config(db, Some(guild_id)).await?;
+    let latest = fetcher.catch_up(&ctx.http, db).await?;
+
+    // the other channels are replayed from the same point, which may overlap with messages that
+    // were handled before the downtime, so their handlers skip already handled ones
+    let until = store::live_since().unwrap_or_else(|| Timestamp::now().unix_timestamp());
+    let mut replayed = 0;
+    if reconciled_channels.insert(config.ty_channel.get()) {
+        let messages =
+            missed_messages(&ctx.http, guild_id, config.ty_channel, covered_until, until).await?;
+        replayed += messages.len();
+        for message in messages {
+            if let Err(err) = ty_tracking::thanks_message(ctx, &message).await {
+                error!("Failed to replay thank-you {}: {err:#}", message.id);
+            }
+        }
+    }
+    if data.is_home_guild(Some(guild_id)) && reconciled_channels.insert(MANUAL_ROLE_CHANNEL.get()) {
+        let messages = missed_messages(
+            &ctx.http,
+            guild_id,
+            MANUAL_ROLE_CHANNEL,
+            covered_until,
+            until,
+        )
+        .await?;
+        replayed += messages.len();
+        for message in messages {
+            if let Err(err) = applications::application_posted(ctx, &message).await {
+                error!(
+                    "Failed to replay manual role application {}: {err:#}",
+                    message.id
+                );
+            }
+        }
+    }
+
+    if let Some(latest) = &latest {
+        info!(
+            "Replayed splashes missed during downtime in channel {channel}, latest: {}",
+            latest.message_id
+        );
+    }
+    if replayed > 0 {
+        info!("Replayed {replayed} other message(s) missed during downtime in guild {guild_id}");
+    }
+
+    // only re-arms the reminder if the missed splash is newer than the restored one and not due yet
+    match latest {
+        Some(latest) => register_splash(ctx, Some(guild_id), latest.message_id).await,
+        None => Ok(()),
+    }
+}
+
+/// Messages sent in the channel between the unix timestamps, oldest first. Messages fetched over
+/// HTTP lack their guild, which the handlers need to pick the guild's config and database.
+async fn missed_messages(
+    http: &Http,
+    guild_id: GuildId,
+    channel: GenericChannelId,
+    since: i64,
+    until: i64,
+) -> Result<Vec<Message>> {
+    let mut messages = Vec::new();
+    let mut after = snowflake_at(since);
+
+    while messages.len() < MAX_REPLAYED_MESSAGES {
+        let mut batch = channel
+            .messages(http, GetMessages::new().after(after).limit(100))
+            .await?;
+        batch.sort_by_key(|message| message.id);
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = last.id;
+        let exhausted = batch.len() < 100 || after.created_at().unix_timestamp() >= until;
+
+        messages.extend(
+            batch
+                .into_iter()
+                .filter(|message| message.timestamp.unix_timestamp() < until)
+                .map(|mut message| {
+                    message.guild_id = Some(guild_id);
+                    message
+                }),
+        );
+        if exhausted {
+            break;
+        }
+    }
+
+    Ok(messages)
+}
@@ -0,0 +1,155 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Result;
+use chrono::Datelike as _;
+use poise::serenity_prelude::{
+    Context as SerenityContext, CreateAllowedMentions, CreateComponent, CreateContainer,
+    CreateContainerComponent, CreateMessage, CreateTextDisplay, Http, Mentionable as _,
+    MessageFlags, Timestamp, colours::branding::YELLOW,
+};
+use tracing::{error, info};
+
+use crate::shared::{
+    BotData,
+    calendar::{self, Clock as _, SystemClock},
+    db::{
+        GetMonthlySplashTotals, GetRolloverAnnounced, SaveMonthlySplashTotals, SetRolloverAnnounced,
+    },
+    task::spawn_background,
+};
+use crate::splashes::{fetch::FetchSplashes, palette::TopSplashers, splashlist::SplashList};
+
+/// `Ready` is dispatched again after reconnects, but only one rollover loop should ever run
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Starts the background loop posting last month's splash recap on the first day of every month
+pub fn start_rollover_loop(ctx: &SerenityContext) {
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let http = Arc::clone(&ctx.http);
+    let data = ctx.data::<BotData>();
+
+    spawn_background("month rollover", Arc::clone(&http), async move {
+        loop {
+            if let Err(err) = announce_previous_month(&http, &data).await {
+                error!("Failed to post month rollover announcement: {err:#}");
+            }
+
+            // wake up shortly after the next month starts
+            let next_month = calendar::start_of_month_est(&SystemClock, 1);
+            let wait = std::time::Duration::from_secs(
+                u64::try_from(next_month - SystemClock.now()).unwrap_or(0) + 60,
+            );
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+/// Snapshots last month's totals and announces them, if it is the first day of the month and this
+/// month's announcement hasn't been posted yet
+async fn announce_previous_month(http: &Http, data: &BotData) -> Result<()> {
+    // splashes are only recapped for one guild, the same one the splash reminder runs for
+    let guild_id = data.home_guild;
+    let db = data.db_for(guild_id);
+
+    let month_start = calendar::start_of_month_est(&SystemClock, 0);
+    if calendar::datetime_est(SystemClock.now()).day() != 1 {
+        return Ok(());
+    }
+
+    let previous_start = calendar::start_of_month_est(&SystemClock, -1);
+    let previous_month = calendar::datetime_est(previous_start);
+    let month = previous_month.format("%Y-%m").to_string();
+
+    if db
+        .request(GetRolloverAnnounced {
+            month: month.clone(),
+        })
+        .await??
+    {
+        return Ok(());
+    }
+
+    info!("Composing month rollover announcement for {month}");
+
+    let start = Timestamp::from_unix_timestamp(previous_start)?;
+    let mut fetcher = FetchSplashes::from_config(db, guild_id).await?;
+    let splashes: Vec<_> = fetcher
+        .splashes_during(
+            http,
+            db,
+            start,
+            Timestamp::from_unix_timestamp(month_start - 1)?,
+        )
+        .await?
+        .iter()
+        .map(|s| (s.timestamp, s.splasher))
+        .collect();
+    let totals = SplashList::new(splashes, start, 0).per_splasher_sorted();
+
+    db.request(SaveMonthlySplashTotals {
+        month: month.clone(),
+        totals,
+    })
+    .await??;
+
+    // compose from the persisted snapshot, so the announcement matches the stored history
+    let totals = db
+        .request(GetMonthlySplashTotals {
+            month: month.clone(),
+        })
+        .await??;
+
+    let total_splashes: u32 = totals.iter().map(|(_, count)| count).sum();
+    let top_splashers: String = totals
+        .iter()
+        .zip(TopSplashers::default().markers())
+        .map(|((splasher, count), marker)| {
+            format!("{} {}: **{count}**\n", marker.emoji, splasher.mention())
+        })
+        .collect();
+
+    let text = CreateTextDisplay::new(format!(
+        "## {} Splash Recap
+Last month, **{total_splashes}** splashes were done by **{}** splashers!
+### Top Splashers
+{}\
+### Thank you to everyone who splashed :heart:!",
+        previous_month.format("%B %Y"),
+        totals.len(),
+        if top_splashers.is_empty() {
+            "*Nobody splashed last month.*\n"
+        } else {
+            top_splashers.as_str()
+        },
+    ));
+
+    let message = data
+        .guild_config(guild_id)
+        .await?
+        .ty_channel
+        .send_message(
+            http,
+            CreateMessage::new()
+                .flags(MessageFlags::IS_COMPONENTS_V2)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![CreateComponent::Container(
+                    CreateContainer::new(vec![CreateContainerComponent::TextDisplay(text)])
+                        .accent_color(YELLOW),
+                )]),
+        )
+        .await?;
+
+    db.request(SetRolloverAnnounced {
+        month,
+        message_id: message.id,
+    })
+    .await??;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    sync::{LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::splashes::splashlist::{ChartOptions, SplashList};
+
+/// Splash lists are usually regenerated a few times while the bingo is running, repeated requests
+/// within this duration mostly hit unchanged data
+const CHART_TTL: Duration = Duration::from_secs(3600);
+/// Charts are up to a few MB at high resolution, so only a handful are kept around
+const MAX_ENTRIES: usize = 16;
+
+static CACHE: LazyLock<Mutex<HashMap<u64, CachedChart>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct CachedChart {
+    bytes: Vec<u8>,
+    created: Instant,
+}
+
+/// Identifies a chart by everything it is generated from, so identical inputs reuse the output
+pub fn chart_key(splashes: &SplashList, chart: ChartOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    splashes.start.unix_timestamp().hash(&mut hasher);
+    splashes.bingo_days.hash(&mut hasher);
+    for (timestamp, splasher) in &splashes.items {
+        timestamp.unix_timestamp().hash(&mut hasher);
+        splasher.get().hash(&mut hasher);
+    }
+    chart.kind.hash(&mut hasher);
+    chart.size.width.hash(&mut hasher);
+    chart.size.height.hash(&mut hasher);
+    chart.size.scale.to_bits().hash(&mut hasher);
+    chart.svg.hash(&mut hasher);
+    chart.top.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+pub fn get(key: u64) -> Option<Vec<u8>> {
+    let cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    cache
+        .get(&key)
+        .filter(|chart| chart.created.elapsed() < CHART_TTL)
+        .map(|chart| chart.bytes.clone())
+}
+
+pub fn insert(key: u64, bytes: Vec<u8>) {
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+
+    cache.retain(|_, chart| chart.created.elapsed() < CHART_TTL);
+    if cache.len() >= MAX_ENTRIES
+        && let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, chart)| chart.created)
+            .map(|(key, _)| *key)
+    {
+        cache.remove(&oldest);
+    }
+
+    cache.insert(
+        key,
+        CachedChart {
+            bytes,
+            created: Instant::now(),
+        },
+    );
+}
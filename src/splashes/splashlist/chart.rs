@@ -1,39 +1,161 @@
-use anyhow::{Context as _, Result};
-use image::{ImageBuffer, ImageFormat, Rgba};
+use anyhow::Result;
 use plotters::{
     chart::{ChartBuilder, ChartContext},
     coord::types::RangedCoordusize,
+    element::{Circle, EmptyElement, Pie, Text},
     prelude::{
-        Cartesian2d, DrawingAreaErrorKind, DrawingBackend, IntoDrawingArea, Polygon, SVGBackend,
+        Cartesian2d, DrawingAreaErrorKind, DrawingBackend, IntoDrawingArea, IntoSegmentedCoord,
+        Polygon, Rectangle, SVGBackend, SegmentValue,
+    },
+    series::AreaSeries,
+    style::{
+        RGBAColor, RGBColor, ShapeStyle, TRANSPARENT, TextStyle,
+        full_palette::GREY_200,
+        text_anchor::{HPos, Pos, VPos},
+    },
+};
+
+use crate::splashes::{
+    palette::TopSplashers,
+    splashlist::{
+        SplashList,
+        render::{FONT_NAME, svg_to_png},
     },
-    style::{RGBAColor, ShapeStyle, TRANSPARENT, TextStyle, full_palette::GREY_200},
 };
-use resvg::{tiny_skia, usvg};
 
-use crate::splashes::splashlist::SplashList;
+/// Dimensions of a generated chart. Text and margins are sized relative to the height, while
+/// `scale` only multiplies the pixel density of rendered PNGs.
+#[derive(Debug, Clone, Copy)]
+pub struct ChartSize {
+    pub width: u32,
+    pub height: u32,
+    pub scale: f32,
+}
+
+impl ChartSize {
+    /// Narrower layout that stays legible on mobile
+    pub const COMPACT: Self = Self {
+        width: 1000,
+        height: 700,
+        scale: 1.0,
+    };
+    pub const STANDARD: Self = Self {
+        width: 1600,
+        height: 800,
+        scale: 1.0,
+    };
+    /// Standard layout rendered at twice the resolution, for high-DPI displays
+    pub const HIGH_DPI: Self = Self {
+        width: 1600,
+        height: 800,
+        scale: 2.0,
+    };
+}
+
+impl Default for ChartSize {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Which aspect of the splashes a chart visualizes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChartKind {
+    /// Daily splashes, stacked by the top splashers
+    #[default]
+    Distribution,
+    /// Share of each splasher in the total splashes
+    Splashers,
+    /// Running total of splashes over the days of the bingo
+    Cumulative,
+    /// Splashes per hour of each day
+    Heatmap,
+}
+
+impl ChartKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            ChartKind::Distribution => "Distribution Graph",
+            ChartKind::Splashers => "Splasher Shares",
+            ChartKind::Cumulative => "Cumulative Splashes",
+            ChartKind::Heatmap => "Hourly Heatmap",
+        }
+    }
+}
+
+// height that the text and margin sizes below were designed for
+const REFERENCE_HEIGHT: f64 = 800.0;
+const LAYER_OPACITY: f64 = 0.8;
+/// Layer of everyone outside of the top splashers
+const REST_LAYER_COLOR: RGBAColor = RGBAColor(255, 255, 255, LAYER_OPACITY);
 
-pub fn distribution_png_bytes(splashes: &SplashList) -> Result<Vec<u8>> {
-    let svg = distribution_chart_svg(splashes)?;
+pub fn png_bytes(
+    splashes: &SplashList,
+    kind: ChartKind,
+    size: ChartSize,
+    top: TopSplashers,
+) -> Result<Vec<u8>> {
+    let svg = chart_svg(splashes, kind, size, top)?;
     // NOTE: Charts are initially created using the `plotters` SVG backend, before being rendered
     // using `resvg` and encoded as a PNG to be sent on Discord. The reason for the SVG 'detour' is
     // that the Bitmap backend doesn't support transparency. (This won't be used often enough to
     // consider switching libraries at the moment)
-    let png_buffer = render_svg(&svg)?;
+    let png_buffer = svg_to_png(&svg, size.scale)?;
 
     Ok(png_buffer)
 }
 
-fn distribution_chart_svg(splashes: &SplashList) -> Result<String> {
-    const FONT_NAME: &str = "Noto Sans";
-    const CHART_SIZE: (u32, u32) = (1600, 800);
-    const LAYER_COLORS: [RGBAColor; 4] = [
-        RGBAColor(221, 46, 68, 0.8),   // top 1
-        RGBAColor(120, 177, 89, 0.8),  // top 2
-        RGBAColor(85, 172, 238, 0.8),  // top 3
-        RGBAColor(255, 255, 255, 0.8), // rest
-    ];
+pub fn svg_bytes(
+    splashes: &SplashList,
+    kind: ChartKind,
+    size: ChartSize,
+    top: TopSplashers,
+) -> Result<Vec<u8>> {
+    Ok(chart_svg(splashes, kind, size, top)?.into_bytes())
+}
+
+fn chart_svg(
+    splashes: &SplashList,
+    kind: ChartKind,
+    size: ChartSize,
+    top: TopSplashers,
+) -> Result<String> {
+    match kind {
+        ChartKind::Distribution => {
+            stacked_chart_svg(splashes.split_days_top(top.count()), splashes, size, top)
+        }
+        ChartKind::Splashers => splasher_pie_svg(splashes, size, top),
+        ChartKind::Cumulative => {
+            // each layer is summed up separately, so the top splashers stay distinguishable
+            let mut totals = vec![0u32; top.count() + 1];
+            let per_day = splashes
+                .split_days_top(top.count())
+                .into_iter()
+                .map(|day| {
+                    for (total, count) in totals.iter_mut().zip(day) {
+                        *total += count;
+                    }
+                    totals.clone()
+                })
+                .collect();
+            stacked_chart_svg(per_day, splashes, size, top)
+        }
+        ChartKind::Heatmap => heatmap_svg(splashes, size),
+    }
+}
+
+fn relative(value: f64, size: ChartSize) -> f64 {
+    (value * size.height as f64 / REFERENCE_HEIGHT).round()
+}
+
+fn stacked_chart_svg(
+    per_day: Vec<Vec<u32>>,
+    splashes: &SplashList,
+    size: ChartSize,
+    top: TopSplashers,
+) -> Result<String> {
+    let relative = |value: f64| relative(value, size);
 
-    let per_day = splashes.split_days_top_3();
     let max_daily = per_day.iter().map(|d| d.iter().sum()).max().unwrap_or(0);
     // round up to nearest 10
     let chart_max = max_daily.div_ceil(10) * 10;
@@ -41,21 +163,21 @@ fn distribution_chart_svg(splashes: &SplashList) -> Result<String> {
     let mut svg = String::new();
 
     {
-        let root = SVGBackend::with_string(&mut svg, CHART_SIZE).into_drawing_area();
+        let root = SVGBackend::with_string(&mut svg, (size.width, size.height)).into_drawing_area();
 
         let mut chart = ChartBuilder::on(&root)
-            .margin_top(20)
-            .margin_right(20)
-            .x_label_area_size(100)
-            .y_label_area_size(120)
+            .margin_top(relative(20.0) as i32)
+            .margin_right(relative(20.0) as i32)
+            .x_label_area_size(relative(100.0) as i32)
+            .y_label_area_size(relative(120.0) as i32)
             .build_cartesian_2d(1..splashes.bingo_days(), 0..chart_max as usize)?;
 
         chart
             .configure_mesh()
             .x_desc("Day")
             .y_desc("Splashes")
-            .axis_desc_style(TextStyle::from((FONT_NAME, 60)).color(&GREY_200))
-            .label_style(TextStyle::from((FONT_NAME, 40)).color(&GREY_200))
+            .axis_desc_style(TextStyle::from((FONT_NAME, relative(60.0))).color(&GREY_200))
+            .label_style(TextStyle::from((FONT_NAME, relative(40.0))).color(&GREY_200))
             .axis_style(GREY_200)
             .bold_line_style(GREY_200)
             .light_line_style(TRANSPARENT)
@@ -63,15 +185,21 @@ fn distribution_chart_svg(splashes: &SplashList) -> Result<String> {
 
         let mut stacked_chart = StackedAreaChartContent::new();
 
-        let chart_data = splashes.split_days_top_3();
-
-        for layer_index in 0..4 {
-            let points: Vec<_> = chart_data
+        let layer_colors = top
+            .markers()
+            .iter()
+            .map(|marker| {
+                let (r, g, b) = marker.rgb;
+                RGBAColor(r, g, b, LAYER_OPACITY)
+            })
+            .chain([REST_LAYER_COLOR]);
+        for (layer_index, color) in layer_colors.enumerate() {
+            let points: Vec<_> = per_day
                 .iter()
                 .enumerate()
                 .map(|(i, day)| (i + 1, day[layer_index] as usize))
                 .collect();
-            stacked_chart.add_layer_relative(points, &LAYER_COLORS[layer_index]);
+            stacked_chart.add_layer_relative(points, &color);
         }
 
         stacked_chart.draw(&mut chart)?;
@@ -82,6 +210,259 @@ fn distribution_chart_svg(splashes: &SplashList) -> Result<String> {
     Ok(svg)
 }
 
+/// Splashers beyond this rank are merged into a single slice
+const PIE_SPLASHERS: usize = 8;
+
+fn splasher_pie_svg(splashes: &SplashList, size: ChartSize, top: TopSplashers) -> Result<String> {
+    // the rest fade from light to dark grey, only as many as needed with the fewest top splashers
+    const GREYS: [RGBColor; 6] = [
+        RGBColor(238, 238, 238),
+        RGBColor(214, 214, 214),
+        RGBColor(189, 189, 189),
+        RGBColor(158, 158, 158),
+        RGBColor(117, 117, 117),
+        RGBColor(97, 97, 97),
+    ];
+    // top splashers match the distribution graph
+    let slice_colors: Vec<RGBColor> = top
+        .markers()
+        .iter()
+        .map(|marker| {
+            let (r, g, b) = marker.rgb;
+            RGBColor(r, g, b)
+        })
+        .chain(GREYS)
+        .take(PIE_SPLASHERS + 1)
+        .collect();
+
+    let splashers = splashes.per_splasher_sorted();
+
+    // labels refer to the ranks in the individual splasher list
+    let mut sizes: Vec<f64> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    for (rank, (_, count)) in splashers.iter().take(PIE_SPLASHERS).enumerate() {
+        sizes.push(*count as f64);
+        labels.push(top.marker(rank).map_or_else(
+            || format!("#{}", rank + 1),
+            |marker| marker.label.to_string(),
+        ));
+    }
+    let others: u32 = splashers
+        .iter()
+        .skip(PIE_SPLASHERS)
+        .map(|(_, count)| count)
+        .sum();
+    if others > 0 {
+        sizes.push(others as f64);
+        labels.push("Others".to_string());
+    }
+
+    let mut svg = String::new();
+
+    {
+        let root = SVGBackend::with_string(&mut svg, (size.width, size.height)).into_drawing_area();
+
+        if !sizes.is_empty() {
+            let center = (size.width as i32 / 2, size.height as i32 / 2);
+            let radius = size.height as f64 * 0.35;
+            let colors = &slice_colors[..sizes.len()];
+
+            let mut pie = Pie::new(&center, &radius, &sizes, colors, &labels);
+            pie.start_angle(-90.0);
+            pie.label_style(TextStyle::from((FONT_NAME, relative(40.0, size))).color(&GREY_200));
+            pie.label_offset(relative(30.0, size));
+            root.draw(&pie)?;
+        }
+
+        root.present()?;
+    }
+
+    Ok(svg)
+}
+
+fn heatmap_svg(splashes: &SplashList, size: ChartSize) -> Result<String> {
+    let relative = |value: f64| relative(value, size);
+
+    let per_hour = splashes.split_days_hourly();
+    let max_hourly = per_hour.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    let mut svg = String::new();
+
+    {
+        let root = SVGBackend::with_string(&mut svg, (size.width, size.height)).into_drawing_area();
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin_top(relative(20.0) as i32)
+            .margin_right(relative(20.0) as i32)
+            .x_label_area_size(relative(100.0) as i32)
+            .y_label_area_size(relative(120.0) as i32)
+            .build_cartesian_2d(0..24usize, 1..splashes.bingo_days() + 1)?;
+
+        chart
+            .configure_mesh()
+            .x_desc("Hour (EST)")
+            .y_desc("Day")
+            .axis_desc_style(TextStyle::from((FONT_NAME, relative(60.0))).color(&GREY_200))
+            .label_style(TextStyle::from((FONT_NAME, relative(40.0))).color(&GREY_200))
+            .axis_style(GREY_200)
+            .bold_line_style(TRANSPARENT)
+            .light_line_style(TRANSPARENT)
+            .draw()?;
+
+        chart.draw_series(per_hour.iter().enumerate().flat_map(|(day, hours)| {
+            hours.iter().enumerate().map(move |(hour, &count)| {
+                // empty hours stay faintly visible, so that the grid remains readable
+                let opacity = 0.05 + 0.9 * count as f64 / max_hourly as f64;
+                Rectangle::new(
+                    [(hour, day + 1), (hour + 1, day + 2)],
+                    ShapeStyle::from(&RGBAColor(221, 46, 68, opacity)).filled(),
+                )
+            })
+        }))?;
+
+        root.present()?;
+    }
+
+    Ok(svg)
+}
+
+/// Size of the weekly chart, which is meant to be glanced at rather than studied
+const WEEKLY_SIZE: ChartSize = ChartSize {
+    width: 1000,
+    height: 300,
+    scale: 1.0,
+};
+
+/// Sparkline-style chart of the total splashes of each given day of the bingo, labelled with the
+/// day and its count instead of axes
+pub fn weekly_png(days: &[(usize, u32)]) -> Result<Vec<u8>> {
+    let size = WEEKLY_SIZE;
+    let relative = |value: f64| relative(value, size);
+
+    let first = days.first().map_or(1, |(day, _)| *day) as f64;
+    let last = days.last().map_or(1, |(day, _)| *day) as f64;
+    let max_daily = days
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+
+    let line_color = RGBColor(221, 46, 68);
+    let label_style = TextStyle::from((FONT_NAME, relative(36.0))).color(&GREY_200);
+    let points: Vec<(f64, f64)> = days
+        .iter()
+        .map(|(day, count)| (*day as f64, *count as f64))
+        .collect();
+
+    let mut svg = String::new();
+
+    {
+        let root = SVGBackend::with_string(&mut svg, (size.width, size.height)).into_drawing_area();
+
+        // the extra space around the data leaves room for the labels
+        let mut chart = ChartBuilder::on(&root)
+            .margin_left(relative(40.0) as i32)
+            .margin_right(relative(40.0) as i32)
+            .build_cartesian_2d(first - 0.5..last + 0.5, -0.35 * max_daily..1.35 * max_daily)?;
+
+        chart.draw_series(
+            AreaSeries::new(points.clone(), 0.0, RGBAColor(221, 46, 68, 0.3))
+                .border_style(ShapeStyle::from(&line_color).stroke_width(relative(4.0) as u32)),
+        )?;
+
+        chart.draw_series(points.iter().map(|&(day, count)| {
+            EmptyElement::at((day, count))
+                + Circle::new(
+                    (0, 0),
+                    relative(6.0) as i32,
+                    ShapeStyle::from(&line_color).filled(),
+                )
+                + Text::new(
+                    format!("{count}"),
+                    (0, -relative(14.0) as i32),
+                    label_style
+                        .clone()
+                        .pos(Pos::new(HPos::Center, VPos::Bottom)),
+                )
+        }))?;
+        chart.draw_series(points.iter().map(|&(day, _)| {
+            EmptyElement::at((day, 0.0))
+                + Text::new(
+                    format!("Day {day}"),
+                    (0, relative(14.0) as i32),
+                    label_style.clone().pos(Pos::new(HPos::Center, VPos::Top)),
+                )
+        }))?;
+
+        root.present()?;
+    }
+
+    svg_to_png(&svg, size.scale)
+}
+
+/// Bar chart of labelled counts, e.g. how many players have each blackout count
+pub fn bar_png(bars: &[(String, u32)], x_desc: &str, y_desc: &str) -> Result<Vec<u8>> {
+    let size = ChartSize::STANDARD;
+    let relative = |value: f64| relative(value, size);
+
+    let max_count = bars.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    // round up to nearest 10
+    let chart_max = max_count.div_ceil(10).max(1) * 10;
+
+    let bar_color = RGBColor(221, 46, 68);
+
+    let mut svg = String::new();
+
+    {
+        let root = SVGBackend::with_string(&mut svg, (size.width, size.height)).into_drawing_area();
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin_top(relative(20.0) as i32)
+            .margin_right(relative(20.0) as i32)
+            .x_label_area_size(relative(100.0) as i32)
+            .y_label_area_size(relative(120.0) as i32)
+            .build_cartesian_2d((0..bars.len()).into_segmented(), 0..chart_max as usize)?;
+
+        chart
+            .configure_mesh()
+            .x_desc(x_desc)
+            .y_desc(y_desc)
+            .x_labels(bars.len())
+            .x_label_formatter(&|value| match value {
+                SegmentValue::CenterOf(i) => bars
+                    .get(*i)
+                    .map(|(label, _)| label.clone())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .axis_desc_style(TextStyle::from((FONT_NAME, relative(60.0))).color(&GREY_200))
+            .label_style(TextStyle::from((FONT_NAME, relative(40.0))).color(&GREY_200))
+            .axis_style(GREY_200)
+            .bold_line_style(GREY_200)
+            .light_line_style(TRANSPARENT)
+            .disable_x_mesh()
+            .draw()?;
+
+        chart.draw_series(bars.iter().enumerate().map(|(i, (_, count))| {
+            let mut bar = Rectangle::new(
+                [
+                    (SegmentValue::Exact(i), 0),
+                    (SegmentValue::Exact(i + 1), *count as usize),
+                ],
+                ShapeStyle::from(&bar_color).filled(),
+            );
+            // gaps between neighbouring bars
+            bar.set_margin(0, 0, relative(6.0) as u32, relative(6.0) as u32);
+            bar
+        }))?;
+
+        root.present()?;
+    }
+
+    svg_to_png(&svg, size.scale)
+}
+
 // NOTE: custom implementation of a stacked area chart, as `plotters` only supports a constant
 // baseline for any AreaSeries, meaning transparent colors would mix when drawing over other layers
 struct StackedAreaChartContent<'a> {
@@ -138,25 +519,3 @@ impl<'a> StackedAreaChartContent<'a> {
         Ok(())
     }
 }
-
-/// Renders SVG string, returns encoded PNG data
-fn render_svg(svg: &str) -> Result<Vec<u8>> {
-    let mut opt = usvg::Options::default();
-    opt.fontdb_mut().load_system_fonts();
-
-    let tree = usvg::Tree::from_str(svg, &opt)?;
-    let size = tree.size().to_int_size();
-
-    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
-        .context("Failed to create pixmap with requested size")?;
-    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
-    let mut png_bytes: Vec<u8> = Vec::new();
-
-    let img: ImageBuffer<Rgba<u8>, _> =
-        ImageBuffer::from_raw(size.width(), size.height(), pixmap.data())
-            .context("Failed to build image from rgba buffer")?;
-
-    img.write_to(&mut std::io::Cursor::new(&mut png_bytes), ImageFormat::Png)?;
-
-    Ok(png_bytes)
-}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use poise::serenity_prelude::{Timestamp, UserId};
+use serde_json::json;
+
+use crate::shared::calendar;
+use crate::splashes::splashlist::SplashList;
+
+/// Raw splash records, e.g. for analysis in spreadsheets. `start` is the start of the bingo, which
+/// the day of bingo is counted from.
+pub fn to_csv(
+    splashes: &SplashList,
+    start: Timestamp,
+    usernames: &HashMap<UserId, String>,
+) -> String {
+    let mut output = String::from("timestamp,user_id,username,bingo_day\n");
+    for (timestamp, user) in chronological(splashes) {
+        writeln!(
+            output,
+            "{},{user},{},{}",
+            timestamp_rfc3339(timestamp),
+            csv_field(usernames.get(&user).map_or("", String::as_str)),
+            bingo_day(timestamp, start)
+        )
+        .unwrap();
+    }
+    output
+}
+
+pub fn to_json(
+    splashes: &SplashList,
+    start: Timestamp,
+    usernames: &HashMap<UserId, String>,
+) -> String {
+    let records: Vec<_> = chronological(splashes)
+        .map(|(timestamp, user)| {
+            json!({
+                "timestamp": timestamp_rfc3339(timestamp),
+                "user_id": user.to_string(),
+                "username": usernames.get(&user),
+                "bingo_day": bingo_day(timestamp, start),
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&records).unwrap_or_default()
+}
+
+fn chronological(splashes: &SplashList) -> impl Iterator<Item = (Timestamp, UserId)> {
+    let mut items = splashes.items().to_vec();
+    items.sort_by_key(|(timestamp, _)| *timestamp);
+    items.into_iter()
+}
+
+fn timestamp_rfc3339(timestamp: Timestamp) -> String {
+    chrono::DateTime::from_timestamp(timestamp.unix_timestamp(), 0)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+/// 1-based day of the bingo the splash happened on
+fn bingo_day(timestamp: Timestamp, start: Timestamp) -> i64 {
+    calendar::bingo_day(start.unix_timestamp(), timestamp.unix_timestamp()) + 1
+}
+
+/// Quotes fields that would otherwise break the CSV structure
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
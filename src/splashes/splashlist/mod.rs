@@ -1,37 +1,64 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
-use chrono::{Datelike, TimeZone};
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::TimeZone as _;
 use poise::{
     CreateReply,
     serenity_prelude::{
-        CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent,
-        CreateMediaGallery, CreateMediaGalleryItem, CreateTextDisplay, CreateUnfurledMediaItem,
-        Mentionable as _, MessageFlags, Timestamp, UserId,
+        CreateAttachment, CreateComponent, CreateContainer, CreateContainerComponent, CreateFile,
+        CreateMediaGallery, CreateMediaGalleryItem, CreateMessage, CreateTextDisplay,
+        CreateUnfurledMediaItem, EditAttachments, EditMessage, GuildId, Http, Mentionable as _,
+        MessageFlags, Timestamp, UserId,
     },
 };
 
-use crate::config::TY_CHANNEL;
-use crate::shared::{Context, types::BingoKind};
-use crate::splashes::fetch;
+use crate::db::DbHandle;
+use crate::error::UserError;
+use crate::hypixel_api::ApiHandle;
+use crate::shared::{
+    calendar::{self, Clock, SystemClock},
+    db::{
+        CacheSplashPeriod, GetBingoData, GetBingoPeriod, GetBingoStartedIn, GetBingoUniqueId,
+        GetCachedSplashPeriod, GetGuildConfig, GetSplashGoal,
+    },
+    time::{TimestampStyle, discord_timestamp},
+    types::{Bingo, BingoKind},
+};
+use crate::splashes::{fetch, palette::TopSplashers};
 
+mod cache;
 mod chart;
+pub mod export;
+pub mod render;
+
+pub use chart::{ChartKind, ChartSize};
 
 #[derive(Debug, Clone)]
 pub struct SplashList {
     items: Vec<(Timestamp, UserId)>,
+    /// days are counted from here rather than the start of the month, as bingos may cross months
+    start: Timestamp,
     bingo_days: usize,
 }
 
 impl SplashList {
-    pub fn new(items: Vec<(Timestamp, UserId)>, bingo_days: usize) -> SplashList {
-        SplashList { items, bingo_days }
+    pub fn new(items: Vec<(Timestamp, UserId)>, start: Timestamp, bingo_days: usize) -> SplashList {
+        SplashList {
+            items,
+            start,
+            bingo_days,
+        }
     }
 
     pub fn len(&self) -> usize {
         self.items.len()
     }
 
+    /// Individual splashes along with who performed them
+    pub fn items(&self) -> &[(Timestamp, UserId)] {
+        &self.items
+    }
+
     pub fn bingo_days(&self) -> usize {
         self.bingo_days
     }
@@ -50,108 +77,431 @@ impl SplashList {
         splashers
     }
 
-    pub fn split_days_top_3(&self) -> Vec<[u32; 4]> {
+    /// Splashes of each day by the `top` splashers in order of their rank, followed by the
+    /// splashes of everyone else
+    pub fn split_days_top(&self, top: usize) -> Vec<Vec<u32>> {
         let mut day_maps = vec![HashMap::new(); self.bingo_days];
 
         for (timestamp, user_id) in &self.items {
-            let day_of_month = day_of_month_est(*timestamp) as usize;
-
-            if day_of_month > self.bingo_days {
+            let Some(day) = self.day_of_bingo(*timestamp) else {
                 continue;
-            }
+            };
 
-            *day_maps[day_of_month - 1].entry(user_id).or_insert(0) += 1;
+            *day_maps[day].entry(user_id).or_insert(0) += 1;
         }
 
-        let top_3: Vec<UserId> = self
+        let top_splashers: Vec<UserId> = self
             .per_splasher_sorted()
             .iter()
-            .take(3)
+            .take(top)
             .map(|(user_id, _)| *user_id)
             .collect();
 
         day_maps
             .iter()
             .map(|day| {
-                let mut counts = [0u32; 4];
+                let mut counts = vec![0u32; top + 1];
                 let mut rest = 0u32;
 
                 for (&user, &count) in day {
-                    if let Some(pos) = top_3.iter().position(|u| u == user) {
+                    if let Some(pos) = top_splashers.iter().position(|u| u == user) {
                         counts[pos] = count;
                     } else {
                         rest += count;
                     }
                 }
 
-                counts[3] = rest;
+                counts[top] = rest;
                 counts
             })
             .collect()
     }
+
+    /// Total splashes of each day
+    pub fn split_days(&self) -> Vec<u32> {
+        let mut days = vec![0u32; self.bingo_days];
+
+        for (timestamp, _) in &self.items {
+            if let Some(day) = self.day_of_bingo(*timestamp) {
+                days[day] += 1;
+            }
+        }
+
+        days
+    }
+
+    /// Splashes per hour (in EST) of each day
+    pub fn split_days_hourly(&self) -> Vec<[u32; 24]> {
+        let mut days = vec![[0u32; 24]; self.bingo_days];
+
+        for (timestamp, _) in &self.items {
+            let Some(day) = self.day_of_bingo(*timestamp) else {
+                continue;
+            };
+
+            days[day][calendar::hour_est(timestamp.unix_timestamp()) as usize] += 1;
+        }
+
+        days
+    }
+
+    /// Zero-based day since the start of the bingo, `None` if outside of it
+    fn day_of_bingo(&self, timestamp: Timestamp) -> Option<usize> {
+        let day = calendar::bingo_day(self.start.unix_timestamp(), timestamp.unix_timestamp());
+        let day = usize::try_from(day).ok()?;
+        (day < self.bingo_days).then_some(day)
+    }
 }
 
-fn timestamp_start_of_day_est(day_of_month: u32) -> Timestamp {
-    let est = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
-    let now = chrono::Utc::now().with_timezone(&est);
-    let start_of_month = est
-        .with_ymd_and_hms(now.year(), now.month(), day_of_month, 0, 0, 0)
-        .unwrap();
-    Timestamp::from_unix_timestamp(start_of_month.timestamp()).unwrap()
+/// A calendar month in EST, which past bingos can be selected by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplashMonth {
+    pub year: i32,
+    pub month: u32,
 }
 
-fn day_of_month_est(timestamp: Timestamp) -> u32 {
-    let est = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
-    let timestamp = chrono::DateTime::from_timestamp(timestamp.unix_timestamp(), 0)
+impl SplashMonth {
+    pub fn current() -> Self {
+        Self::current_at(&SystemClock)
+    }
+
+    pub fn current_at(clock: &impl Clock) -> Self {
+        let (year, month) = calendar::month_est(clock.now());
+        Self { year, month }
+    }
+
+    /// The month a timestamp falls into
+    pub fn of(timestamp: Timestamp) -> Self {
+        let (year, month) = calendar::month_est(timestamp.unix_timestamp());
+        Self { year, month }
+    }
+
+    pub fn months_before(self, months: u32) -> Self {
+        let (year, month) = calendar::add_months(self.year, self.month, -(months as i32));
+        Self { year, month }
+    }
+
+    pub fn next(self) -> Self {
+        let (year, month) = calendar::add_months(self.year, self.month, 1);
+        Self { year, month }
+    }
+
+    /// Parses a month formatted as `YYYY-MM`
+    pub fn parse(input: &str) -> Option<Self> {
+        let (year, month) = input.trim().split_once('-')?;
+        let year = year.parse().ok()?;
+        let month = month.parse().ok()?;
+        ((1000..=9999).contains(&year) && (1..=12).contains(&month)).then_some(Self { year, month })
+    }
+
+    /// How many months before the current month this is, negative for future months
+    pub fn months_ago(self) -> i32 {
+        let current = Self::current();
+        (current.year - self.year) * 12 + current.month as i32 - self.month as i32
+    }
+
+    pub fn name(self) -> String {
+        calendar::est()
+            .with_ymd_and_hms(self.year, self.month, 1, 0, 0, 0)
+            .unwrap()
+            .format("%B %Y")
+            .to_string()
+    }
+
+    pub fn start_of_day(self, day_of_month: u32) -> Timestamp {
+        Timestamp::from_unix_timestamp(calendar::start_of_day_est(
+            self.year,
+            self.month,
+            day_of_month,
+        ))
         .unwrap()
-        .with_timezone(&est);
-    timestamp.day()
+    }
+}
+
+pub fn timestamp_start_of_day_est(day_of_month: u32) -> Timestamp {
+    SplashMonth::current().start_of_day(day_of_month)
+}
+
+/// Which bingo a splash list is created for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BingoSelector {
+    Current,
+    Previous,
+    Specific(Bingo),
+    /// the bingo that started in a calendar month
+    Month(SplashMonth),
 }
 
-fn current_month_name_est() -> String {
-    let est = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
-    let now = chrono::Utc::now().with_timezone(&est);
-    now.format("%B %Y").to_string()
+impl BingoSelector {
+    /// Parses `current`, `previous` or a bingo identifier like `#42` or `extreme 3`
+    pub fn parse(input: &str) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "current" => Ok(Self::Current),
+            "previous" | "last" => Ok(Self::Previous),
+            _ => Bingo::from_input(input).map(Self::Specific),
+        }
+    }
 }
 
-pub async fn generate_message(ctx: &Context<'_>) -> Result<CreateReply<'static>> {
-    let data = ctx.data();
-    let db = &data.db_handle;
-    let api = &data.api_handle;
+/// The bingo a splash list covers, along with the period it lasted
+#[derive(Debug, Clone, Copy)]
+pub struct SplashPeriod {
+    pub bingo: Bingo,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// whether this is the latest bingo, rather than a past one
+    pub is_current: bool,
+}
 
-    let (current_bingo, _, _) = api.update_current_bingo(db).await?;
+impl SplashPeriod {
+    /// Looks up the stored start and end of the selected bingo. Extreme and Secret bingos may
+    /// cross month boundaries, so the calendar month is only assumed for bingos from before
+    /// periods were recorded.
+    pub async fn resolve(db: &DbHandle, api: &ApiHandle, selector: BingoSelector) -> Result<Self> {
+        let (current, start, end) = api.update_current_bingo(db).await?;
+        let current_period = Self {
+            bingo: current,
+            start: Timestamp::from_unix_timestamp(start)?,
+            end: Timestamp::from_unix_timestamp(end)?,
+            is_current: true,
+        };
+
+        let unique_id = match selector {
+            BingoSelector::Current => return Ok(current_period),
+            BingoSelector::Previous => current
+                .get_id()
+                .checked_sub(1)
+                .context(UserError(anyhow!("No bingo took place before {current}")))?,
+            BingoSelector::Month(month) => {
+                let months_ago = month.months_ago();
+                if months_ago < 0 {
+                    bail!(UserError(anyhow!(
+                        "Can't generate the splash list for a future month"
+                    )));
+                }
 
-    let bingo_days = if current_bingo.kind == BingoKind::Normal {
-        7
-    } else {
-        14
+                let started = db
+                    .request(GetBingoStartedIn {
+                        start: month.start_of_day(1).unix_timestamp(),
+                        end: month.next().start_of_day(1).unix_timestamp(),
+                    })
+                    .await??;
+                match started {
+                    Some(bingo) => db
+                        .request(GetBingoUniqueId { bingo })
+                        .await??
+                        .context(UserError(anyhow!("No data about {bingo} is available")))?,
+                    // bingos from before periods were recorded started on the first of each month,
+                    // so they can be derived from the current one
+                    None => u8::try_from(months_ago)
+                        .ok()
+                        .and_then(|offset| current.get_id().checked_sub(offset))
+                        .context(UserError(anyhow!(
+                            "No bingo took place in {}",
+                            month.name()
+                        )))?,
+                }
+            }
+            BingoSelector::Specific(bingo) => db
+                .request(GetBingoUniqueId { bingo })
+                .await??
+                .context(UserError(anyhow!(
+                    "No data about {bingo} is available, it may not have started yet"
+                )))?,
+        };
+
+        if unique_id == current.get_id() {
+            return Ok(current_period);
+        }
+        if unique_id > current.get_id() {
+            bail!(UserError(anyhow!("That bingo hasn't started yet")));
+        }
+
+        let bingo = db
+            .request(GetBingoData {
+                bingo_ids: vec![unique_id],
+            })
+            .await??
+            .remove(0);
+
+        if let Some((start, end)) = db.request(GetBingoPeriod { bingo }).await?? {
+            return Ok(Self {
+                bingo,
+                start: Timestamp::from_unix_timestamp(start)?,
+                end: Timestamp::from_unix_timestamp(end)?,
+                is_current: false,
+            });
+        }
+
+        // bingos used to start on the first of each month
+        let month = SplashMonth::current().months_before(u32::from(current.get_id() - unique_id));
+        let bingo_days = bingo.kind.properties().duration_days as u32;
+        Ok(Self {
+            bingo,
+            start: month.start_of_day(1),
+            end: month.start_of_day(bingo_days + 1),
+            is_current: false,
+        })
+    }
+
+    /// Started days of the bingo, which the splash list's charts span
+    pub fn days(self) -> usize {
+        let duration = (self.end.unix_timestamp() - self.start.unix_timestamp()).max(0);
+        (duration as usize).div_ceil(24 * 3600)
+    }
+
+    pub fn is_over(self) -> bool {
+        self.end.unix_timestamp() <= Timestamp::now().unix_timestamp()
+    }
+
+    /// Identifies the bingo, e.g. `extreme-3`
+    pub fn key(self) -> String {
+        let kind = match self.bingo.kind {
+            BingoKind::Normal => "normal",
+            BingoKind::Extreme => "extreme",
+            BingoKind::Secret => "secret",
+        };
+        format!("{kind}-{}", self.bingo.kind_specific_id + 1)
+    }
+}
+
+/// Compares the splash count against a target, assuming an even distribution over the bingo
+fn goal_progress_text(total: usize, target: u32, start: Timestamp, end: Timestamp) -> String {
+    let duration = (end.unix_timestamp() - start.unix_timestamp()).max(1) as f64;
+    let elapsed = (Timestamp::now().unix_timestamp() - start.unix_timestamp()) as f64;
+    let elapsed_fraction = (elapsed / duration).clamp(0.0, 1.0);
+    let bingo_days = duration / (24 * 3600) as f64;
+
+    let expected = (target as f64 * elapsed_fraction).round() as i64;
+    let difference = total as i64 - expected;
+    let percentage = total as f64 / target.max(1) as f64 * 100.0;
+
+    let pace = match difference {
+        0 => "Exactly on pace".to_string(),
+        d if d > 0 => format!("**{d}** splashes ahead of pace 🟢"),
+        d => format!("**{}** splashes behind pace 🔴", -d),
     };
 
-    let start_timestamp = timestamp_start_of_day_est(1);
-    let end_timestamp = timestamp_start_of_day_est(bingo_days as u32 + 1);
+    format!(
+        "### Goal
+Progress: **{total}**/**{target}** ({percentage:.0}%)
+Daily Target: **{:.1}** splashes/day
+{pace}",
+        target as f64 / bingo_days
+    )
+}
 
-    let mut fetcher = fetch::FetchSplashes::new();
-    let splash_messages: Vec<_> = fetcher
-        .splashes_during(ctx.http(), start_timestamp, end_timestamp)
-        .await?
-        .iter()
-        .map(|m| (m.timestamp, m.author.id))
-        .collect();
-    let splashes = SplashList::new(splash_messages, bingo_days);
+/// Renders the compact chart of the weekly summary, see [`chart::weekly_png`]
+pub async fn weekly_chart(days: Vec<(usize, u32)>) -> Result<Vec<u8>> {
+    render::render(move || chart::weekly_png(&days)).await
+}
+
+/// Renders a bar chart of labelled counts, see [`chart::bar_png`]
+pub async fn bar_chart(bars: Vec<(String, u32)>, x_desc: &str, y_desc: &str) -> Result<Vec<u8>> {
+    let (x_desc, y_desc) = (x_desc.to_string(), y_desc.to_string());
+    render::render(move || chart::bar_png(&bars, &x_desc, &y_desc)).await
+}
+
+/// Which chart is attached and how it is rendered
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChartOptions {
+    pub kind: ChartKind,
+    pub size: ChartSize,
+    pub svg: bool,
+    /// highlighted in the chart and the splasher list alike
+    pub top: TopSplashers,
+}
+
+/// A generated splash list, which can be sent as a command response or a regular message
+pub struct SplashListMessage {
+    components: Vec<CreateComponent<'static>>,
+    chart: CreateAttachment<'static>,
+}
+
+impl SplashListMessage {
+    pub fn into_reply(self) -> CreateReply<'static> {
+        CreateReply::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(self.components)
+            .attachment(self.chart)
+    }
+
+    pub fn into_message(self) -> CreateMessage<'static> {
+        CreateMessage::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(self.components)
+            .add_file(self.chart)
+    }
+
+    /// Replaces a previously sent splash list, including its chart
+    pub fn into_edit(self) -> EditMessage<'static> {
+        EditMessage::new()
+            .flags(MessageFlags::IS_COMPONENTS_V2)
+            .components(self.components)
+            .attachments(EditAttachments::new().add(self.chart))
+    }
+}
+
+/// Splashes of a bingo, along with the bingo and the period it lasted
+pub struct BingoSplashes {
+    pub bingo: Bingo,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub splashes: SplashList,
+}
+
+pub async fn load_bingo(
+    http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
+    period: SplashPeriod,
+    on_progress: impl Fn(fetch::FetchProgress) + Send + Sync + 'static,
+) -> Result<BingoSplashes> {
+    let splash_messages = period_splashes(http, db, guild_id, period, on_progress).await?;
+    Ok(BingoSplashes {
+        bingo: period.bingo,
+        start: period.start,
+        end: period.end,
+        splashes: SplashList::new(splash_messages, period.start, period.days()),
+    })
+}
+
+pub async fn generate_message(
+    http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
+    period: SplashPeriod,
+    chart_options: ChartOptions,
+    on_progress: impl Fn(fetch::FetchProgress) + Send + Sync + 'static,
+) -> Result<SplashListMessage> {
+    let BingoSplashes {
+        bingo,
+        start: start_timestamp,
+        end: end_timestamp,
+        splashes,
+    } = load_bingo(http, db, guild_id, period, on_progress).await?;
+    let bingo_days = splashes.bingo_days();
 
     let total_splashes = splashes.len();
 
+    let goal_text = db
+        .request(GetSplashGoal { bingo })
+        .await??
+        .map(|target| {
+            goal_progress_text(total_splashes, target, start_timestamp, end_timestamp) + "\n"
+        })
+        .unwrap_or_default();
+
     let splasher_list: String = splashes
         .per_splasher_sorted()
         .iter()
         .enumerate()
         .map(|(i, (splasher_id, count))| {
-            let suffix = match i {
-                0 => " (🔴)",
-                1 => " (🟢)",
-                2 => " (🔵)",
-                _ => "",
-            };
+            let suffix = chart_options
+                .top
+                .marker(i)
+                .map(|marker| format!(" ({})", marker.emoji))
+                .unwrap_or_default();
             format!("{}: **{}**{suffix}\n", splasher_id.mention(), count)
         })
         .collect();
@@ -162,39 +512,136 @@ pub async fn generate_message(ctx: &Context<'_>) -> Result<CreateReply<'static>>
         "
 ## {} Splash List
 ### Overview
+Period: {} – {}
 Total Splashes: **{total_splashes}**
 Hourly Average: **{hourly_average:.2}** splashes/h
-### Distribution Graph
+{goal_text}\
+### {}
         ",
-        current_month_name_est(),
+        bingo,
+        discord_timestamp(start_timestamp, TimestampStyle::ShortDateTime),
+        discord_timestamp(end_timestamp, TimestampStyle::ShortDateTime),
+        chart_options.kind.title(),
     ));
 
+    // thanking splashers of past bingos would only revive old threads
+    let thank_text = if period.is_current {
+        format!(
+            "### Go thank them in {} :heart:!",
+            db.request(GetGuildConfig { guild_id })
+                .await??
+                .ty_channel
+                .mention()
+        )
+    } else {
+        String::new()
+    };
     let individual_list = CreateTextDisplay::new(format!(
         "
 ### Individual Splashers:
 {splasher_list}\
-### Go thank them in {} :heart:!
-        ",
-        TY_CHANNEL.mention()
+{thank_text}
+        "
     ));
 
-    let chart_bytes =
-        tokio::task::spawn_blocking(move || chart::distribution_png_bytes(&splashes)).await??;
-
-    let chart_attachment = CreateAttachment::bytes(chart_bytes, "chart.png");
-
-    Ok(CreateReply::new()
-        .flags(MessageFlags::IS_COMPONENTS_V2)
-        .components(vec![CreateComponent::Container(CreateContainer::new(
-            vec![
-                CreateContainerComponent::TextDisplay(text_overview),
-                CreateContainerComponent::MediaGallery(CreateMediaGallery::new(vec![
-                    CreateMediaGalleryItem::new(CreateUnfurledMediaItem::new(
-                        "attachment://chart.png",
-                    )),
-                ])),
-                CreateContainerComponent::TextDisplay(individual_list),
-            ],
-        ))])
-        .attachment(chart_attachment))
+    // the live-updating splash list is regenerated often, while its data rarely changes
+    let cache_key = cache::chart_key(&splashes, chart_options);
+    let chart_bytes = match cache::get(cache_key) {
+        Some(bytes) => bytes,
+        None => {
+            let ChartOptions {
+                kind,
+                size,
+                svg,
+                top,
+            } = chart_options;
+            let bytes = if svg {
+                chart::svg_bytes(&splashes, kind, size, top)?
+            } else {
+                render::render(move || chart::png_bytes(&splashes, kind, size, top)).await?
+            };
+            cache::insert(cache_key, bytes.clone());
+            bytes
+        }
+    };
+
+    // SVGs can't be previewed by Discord, so they are attached as a downloadable file instead
+    let (chart_attachment, chart_component) = if chart_options.svg {
+        (
+            CreateAttachment::bytes(chart_bytes, "chart.svg"),
+            CreateContainerComponent::File(CreateFile::new(CreateUnfurledMediaItem::new(
+                "attachment://chart.svg",
+            ))),
+        )
+    } else {
+        (
+            CreateAttachment::bytes(chart_bytes, "chart.png"),
+            CreateContainerComponent::MediaGallery(CreateMediaGallery::new(vec![
+                CreateMediaGalleryItem::new(CreateUnfurledMediaItem::new("attachment://chart.png")),
+            ])),
+        )
+    };
+
+    Ok(SplashListMessage {
+        components: vec![CreateComponent::Container(CreateContainer::new(vec![
+            CreateContainerComponent::TextDisplay(text_overview),
+            chart_component,
+            CreateContainerComponent::TextDisplay(individual_list),
+        ]))],
+        chart: chart_attachment,
+    })
+}
+
+/// Fetches the splashes of a bingo, using the cache for bingos that are already over, since their
+/// splashes can't change anymore (apart from changed splash rules, which clear the cache)
+async fn period_splashes(
+    http: &Http,
+    db: &DbHandle,
+    guild_id: Option<GuildId>,
+    period: SplashPeriod,
+    on_progress: impl Fn(fetch::FetchProgress) + Send + Sync + 'static,
+) -> Result<Vec<(Timestamp, UserId)>> {
+    let past = period.is_over();
+    let channel = db
+        .request(GetGuildConfig { guild_id })
+        .await??
+        .splashes_channel;
+
+    if past
+        && let Some(cached) = db
+            .request(GetCachedSplashPeriod {
+                channel,
+                period: period.key(),
+            })
+            .await??
+    {
+        return cached
+            .into_iter()
+            .map(|(timestamp, splasher)| Ok((Timestamp::from_unix_timestamp(timestamp)?, splasher)))
+            .collect();
+    }
+
+    let mut fetcher = fetch::FetchSplashes::from_config(db, guild_id)
+        .await?
+        .on_progress(on_progress);
+    let splashes: Vec<_> = fetcher
+        .splashes_during(http, db, period.start, period.end)
+        .await?
+        .iter()
+        .map(|s| (s.timestamp, s.splasher))
+        .collect();
+
+    if past {
+        db.request(CacheSplashPeriod {
+            channel,
+            period: period.key(),
+            splashes: splashes
+                .iter()
+                .map(|(timestamp, splasher)| (timestamp.unix_timestamp(), *splasher))
+                .collect(),
+        })
+        .await??;
+    }
+
+    Ok(splashes)
 }
@@ -0,0 +1,92 @@
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use image::{ImageBuffer, ImageFormat, Rgba};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use resvg::{tiny_skia, usvg};
+use tokio::sync::{Semaphore, oneshot};
+
+use crate::error::UserError;
+
+/// Font of all text in rendered images
+pub const FONT_NAME: &str = "Noto Sans";
+
+/// Rendering is CPU-bound, so more threads than this would only slow down concurrent jobs
+const RENDER_THREADS: usize = 2;
+/// Jobs either rendering or waiting for a thread, further requests are rejected
+const MAX_QUEUED_JOBS: usize = 6;
+const RENDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+// dedicated pool, so that long renders can't exhaust tokio's blocking threads
+static POOL: LazyLock<ThreadPool> = LazyLock::new(|| {
+    ThreadPoolBuilder::new()
+        .num_threads(RENDER_THREADS)
+        .thread_name(|i| format!("chart-render-{i}"))
+        .build()
+        .expect("chart render pool should be able to spawn its threads")
+});
+static QUEUE: Semaphore = Semaphore::const_new(MAX_QUEUED_JOBS);
+
+/// Runs a chart rendering job on the render pool
+pub async fn render<T>(job: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T>
+where
+    T: Send + 'static,
+{
+    let Ok(permit) = QUEUE.try_acquire() else {
+        bail!(UserError(anyhow!(
+            "Too many charts are being generated right now, please try again in a moment"
+        )));
+    };
+
+    let (tx, rx) = oneshot::channel();
+    POOL.spawn(move || {
+        // keep the slot occupied until the job actually finishes, even if the caller timed out
+        let _permit = permit;
+        // rayon aborts the process on panics in spawned jobs
+        let result = catch_unwind(AssertUnwindSafe(job))
+            .unwrap_or_else(|_| Err(anyhow!("Chart rendering job panicked")));
+        // receiver being dropped only means the caller timed out
+        let _ = tx.send(result);
+    });
+
+    match tokio::time::timeout(RENDER_TIMEOUT, rx).await {
+        Ok(result) => result?,
+        Err(_) => bail!(UserError(anyhow!(
+            "Generating the chart took too long, please try a smaller size"
+        ))),
+    }
+}
+
+/// Renders SVG string at the given pixel density, returns encoded PNG data
+pub fn svg_to_png(svg: &str, scale: f32) -> Result<Vec<u8>> {
+    let mut opt = usvg::Options::default();
+    opt.fontdb_mut().load_system_fonts();
+
+    let tree = usvg::Tree::from_str(svg, &opt)?;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .context("Invalid image scale")?;
+
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .context("Failed to create pixmap with requested size")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    let mut png_bytes: Vec<u8> = Vec::new();
+
+    let img: ImageBuffer<Rgba<u8>, _> =
+        ImageBuffer::from_raw(size.width(), size.height(), pixmap.data())
+            .context("Failed to build image from rgba buffer")?;
+
+    img.write_to(&mut std::io::Cursor::new(&mut png_bytes), ImageFormat::Png)?;
+
+    Ok(png_bytes)
+}
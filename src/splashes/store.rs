@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use anyhow::Result;
+use poise::serenity_prelude::{Context as SerenityContext, GuildId, Message, MessageId, Timestamp};
+
+use crate::db::DbHandle;
+use crate::shared::{
+    BotData,
+    db::{DeleteStoredSplash, StoreSplashes},
+    types::StoredSplash,
+};
+use crate::splashes::{
+    fetch::FetchSplashes,
+    ingestion::{mentioned_user, named_user},
+};
+
+/// Unix timestamp since which the bot has been receiving gateway events without interruption, i.e.
+/// since which all new splashes have been stored as they arrived
+static LIVE_SINCE: AtomicI64 = AtomicI64::new(i64::MAX);
+
+/// Events may have been missed before `Ready` (e.g. during downtime or after a reconnect), which
+/// backfilling takes care of
+pub fn gateway_ready() {
+    LIVE_SINCE.store(Timestamp::now().unix_timestamp(), Ordering::Relaxed);
+}
+
+/// Events aren't received until the shard is ready again, see [`gateway_ready`]
+pub fn gateway_disconnected() {
+    LIVE_SINCE.store(i64::MAX, Ordering::Relaxed);
+}
+
+/// `None` if the bot isn't connected
+pub fn live_since() -> Option<i64> {
+    let live_since = LIVE_SINCE.load(Ordering::Relaxed);
+    (live_since != i64::MAX).then_some(live_since)
+}
+
+pub fn stored_splash(message: &Message) -> StoredSplash {
+    StoredSplash {
+        message_id: message.id,
+        channel: message.channel_id,
+        guild_id: message.guild_id,
+        timestamp: message.timestamp.unix_timestamp(),
+        author: message.author.id,
+        mentioned: mentioned_user(message),
+        named: named_user(message),
+        hub: FetchSplashes::hub(message),
+        has_image: FetchSplashes::has_image(message),
+    }
+}
+
+/// Persists a newly sent splash message
+pub async fn store_message(db: &DbHandle, message: &Message) -> Result<()> {
+    db.request(StoreSplashes {
+        splashes: vec![stored_splash(message)],
+    })
+    .await??;
+    Ok(())
+}
+
+/// Edits may add or remove the ping, image or mentioned splasher, so the stored splash is replaced
+/// with the edited message, or removed if it isn't a splash anymore
+pub async fn message_updated(ctx: &SerenityContext, message: &Message) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    let guild_config = data.guild_config(message.guild_id).await?;
+    if message.channel_id != guild_config.splashes_channel {
+        return Ok(());
+    }
+
+    let db = data.db_for(message.guild_id);
+    if FetchSplashes::is_splash(message, guild_config.splash_ping_role) {
+        store_message(db, message).await
+    } else {
+        db.request(DeleteStoredSplash {
+            message_id: message.id,
+        })
+        .await??;
+        Ok(())
+    }
+}
+
+/// Deleted splashes aren't counted anymore
+pub async fn message_deleted(
+    ctx: &SerenityContext,
+    guild_id: Option<GuildId>,
+    message_id: MessageId,
+) -> Result<()> {
+    ctx.data::<BotData>()
+        .db_for(guild_id)
+        .request(DeleteStoredSplash { message_id })
+        .await??;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use chrono::Utc;
+use poise::serenity_prelude::{Context as SerenityContext, Message, Reaction};
+
+use crate::shared::{
+    BotData,
+    db::{GetSplashIngestion, GetSplashThanksOf, GetStoredSplashes, RecordSplashThanks},
+    types::SplashThanks,
+};
+use crate::splashes::ingestion::SplasherResolver;
+
+/// Thank-yous sent longer than this after the latest splash aren't attributed to it
+pub const THANKS_WINDOW_SECS: i64 = 2 * 60 * 60;
+
+/// Attributes messages in the thank-you channel to the most recent splash
+pub async fn thanks_message(ctx: &SerenityContext, message: &Message) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    let guild_config = data.guild_config(message.guild_id).await?;
+    if message.channel_id != guild_config.ty_channel || message.author.bot() {
+        return Ok(());
+    }
+
+    let db = data.db_for(message.guild_id);
+    let now = message.timestamp.unix_timestamp();
+
+    // newest first
+    let Some(splash) = db
+        .request(GetStoredSplashes {
+            channel: guild_config.splashes_channel,
+            start: now - THANKS_WINDOW_SECS,
+            end: now,
+        })
+        .await??
+        .into_iter()
+        .next()
+    else {
+        return Ok(());
+    };
+
+    let ingestion = db.request(GetSplashIngestion).await??;
+    let splasher = SplasherResolver::new(ingestion)
+        .resolve_stored(&ctx.http, &splash)
+        .await?;
+    // splashers often reply to thanks in the same channel
+    if splasher == Some(message.author.id) {
+        return Ok(());
+    }
+
+    db.request(RecordSplashThanks {
+        thanks: SplashThanks {
+            source_message_id: message.id,
+            thanked_by: message.author.id,
+            splash_message_id: splash.message_id,
+            splasher,
+            timestamp: now,
+        },
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Reacting to a thank-you counts as thanking the same splash
+pub async fn thanks_reaction(ctx: &SerenityContext, reaction: &Reaction) -> Result<()> {
+    let data = ctx.data::<BotData>();
+    if reaction.channel_id != data.guild_config(reaction.guild_id).await?.ty_channel {
+        return Ok(());
+    }
+    let Some(user) = reaction.user_id else {
+        return Ok(());
+    };
+    if reaction
+        .member
+        .as_ref()
+        .is_some_and(|member| member.user.bot())
+    {
+        return Ok(());
+    }
+
+    let db = data.db_for(reaction.guild_id);
+    let Some((splash_message_id, splasher)) = db
+        .request(GetSplashThanksOf {
+            source_message_id: reaction.message_id,
+        })
+        .await??
+    else {
+        return Ok(());
+    };
+    if splasher == Some(user) {
+        return Ok(());
+    }
+
+    // ignored if the user already thanked with the message itself or another reaction
+    db.request(RecordSplashThanks {
+        thanks: SplashThanks {
+            source_message_id: reaction.message_id,
+            thanked_by: user,
+            splash_message_id,
+            splasher,
+            timestamp: Utc::now().timestamp(),
+        },
+    })
+    .await??;
+
+    Ok(())
+}